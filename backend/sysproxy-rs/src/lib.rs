@@ -15,6 +15,13 @@ pub struct Sysproxy {
     pub bypass: String,
 }
 
+/// the proxy auto-config (PAC) url
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Autoproxy {
+    pub enable: bool,
+    pub url: String,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("failed to parse string `{0}`")]
@@ -42,3 +49,29 @@ impl Sysproxy {
         ))
     }
 }
+
+impl Autoproxy {
+    pub fn is_support() -> bool {
+        cfg!(any(
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "windows",
+        ))
+    }
+}
+
+/// the identity of the active network, changes when switching from Wi-Fi to Ethernet
+/// macos: the network service name, others: the local address of the default route
+pub fn current_network() -> Result<String> {
+    #[cfg(target_os = "macos")]
+    {
+        macos::current_network_service()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect("1.1.1.1:80")?;
+        Ok(socket.local_addr()?.ip().to_string())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Error, Result, Sysproxy};
+use crate::{Autoproxy, Error, Result, Sysproxy};
 use std::{process::Command, str::from_utf8};
 
 const CMD_KEY: &str = "org.gnome.system.proxy";
@@ -125,6 +125,38 @@ impl Sysproxy {
     }
 }
 
+impl Autoproxy {
+    pub fn get_auto_proxy() -> Result<Autoproxy> {
+        let mode = gsettings().args(["get", CMD_KEY, "mode"]).output()?;
+        let mode = from_utf8(&mode.stdout)
+            .or(Err(Error::ParseStr("mode".into())))?
+            .trim();
+
+        let url = gsettings()
+            .args(["get", CMD_KEY, "autoconfig-url"])
+            .output()?;
+        let url = from_utf8(&url.stdout)
+            .or(Err(Error::ParseStr("url".into())))?
+            .trim();
+
+        Ok(Autoproxy {
+            enable: mode == "'auto'",
+            url: strip_str(url).into(),
+        })
+    }
+
+    pub fn set_auto_proxy(&self) -> Result<()> {
+        let mode = if self.enable { "'auto'" } else { "'none'" };
+        let url = format!("'{}'", self.url);
+
+        gsettings()
+            .args(["set", CMD_KEY, "autoconfig-url", url.as_str()])
+            .status()?;
+        gsettings().args(["set", CMD_KEY, "mode", mode]).status()?;
+        Ok(())
+    }
+}
+
 fn gsettings() -> Command {
     Command::new("gsettings")
 }
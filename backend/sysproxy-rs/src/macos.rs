@@ -1,10 +1,10 @@
-use crate::{Error, Result, Sysproxy};
+use crate::{Autoproxy, Error, Result, Sysproxy};
 use std::net::{SocketAddr, UdpSocket};
 use std::{process::Command, str::from_utf8};
 
 impl Sysproxy {
     pub fn get_system_proxy() -> Result<Sysproxy> {
-        let service = default_network_service().or_else(|_| default_network_service_by_ns())?;
+        let service = current_network_service()?;
         let service = service.as_str();
 
         let mut socks = Sysproxy::get_socks(service)?;
@@ -31,9 +31,13 @@ impl Sysproxy {
     }
 
     pub fn set_system_proxy(&self) -> Result<()> {
-        let service = default_network_service().or_else(|_| default_network_service_by_ns())?;
-        let service = service.as_str();
+        let service = current_network_service()?;
+        self.set_system_proxy_on(service.as_str())
+    }
 
+    /// only apply to the given network service
+    /// e.g. remove the proxy from the previous service after switching networks
+    pub fn set_system_proxy_on(&self, service: &str) -> Result<()> {
         self.set_socks(service)?;
         self.set_https(service)?;
         self.set_http(service)?;
@@ -89,6 +93,43 @@ impl Sysproxy {
     }
 }
 
+impl Autoproxy {
+    pub fn get_auto_proxy() -> Result<Autoproxy> {
+        let service = current_network_service()?;
+        let service = service.as_str();
+
+        let output = networksetup()
+            .args(["-getautoproxyurl", service])
+            .output()?;
+        let stdout = from_utf8(&output.stdout).or(Err(Error::ParseStr("output".into())))?;
+
+        let url = parse(stdout, "URL:");
+        let url = if url == "(null)" { "" } else { url };
+
+        Ok(Autoproxy {
+            enable: parse(stdout, "Enabled:") == "Yes",
+            url: url.into(),
+        })
+    }
+
+    pub fn set_auto_proxy(&self) -> Result<()> {
+        let service = current_network_service()?;
+        let service = service.as_str();
+        let enable = if self.enable { "on" } else { "off" };
+
+        if !self.url.is_empty() {
+            networksetup()
+                .args(["-setautoproxyurl", service, self.url.as_str()])
+                .status()?;
+        }
+        networksetup()
+            .args(["-setautoproxystate", service, enable])
+            .status()?;
+
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 enum ProxyType {
     HTTP,
@@ -171,6 +212,10 @@ fn parse<'a>(target: &'a str, key: &'a str) -> &'a str {
     }
 }
 
+pub(crate) fn current_network_service() -> Result<String> {
+    default_network_service().or_else(|_| default_network_service_by_ns())
+}
+
 fn default_network_service() -> Result<String> {
     let socket = UdpSocket::bind("0.0.0.0:0")?;
     socket.connect("1.1.1.1:80")?;
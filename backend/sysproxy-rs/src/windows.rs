@@ -1,4 +1,4 @@
-use crate::{Error, Result, Sysproxy};
+use crate::{Autoproxy, Error, Result, Sysproxy};
 use iptools::iprange::IpRange;
 use iptools::ipv4::validate_cidr;
 use std::ffi::c_void;
@@ -55,7 +55,6 @@ fn unset_proxy() -> Result<()> {
     res
 }
 
-#[allow(dead_code)]
 /// set auto detect proxy, aka PAC
 fn set_auto_proxy(url: &str) -> Result<()> {
     let mut p_opts = ManuallyDrop::new(Vec::<INTERNET_PER_CONN_OPTIONW>::with_capacity(2));
@@ -338,3 +337,23 @@ impl Sysproxy {
         }
     }
 }
+
+impl Autoproxy {
+    pub fn get_auto_proxy() -> Result<Autoproxy> {
+        let hkcu = RegKey::predef(enums::HKEY_CURRENT_USER);
+        let cur_var = hkcu.open_subkey_with_flags(SUB_KEY, enums::KEY_READ)?;
+        let url = cur_var.get_value::<String, _>("AutoConfigURL");
+
+        Ok(Autoproxy {
+            enable: url.is_ok(),
+            url: url.unwrap_or_default(),
+        })
+    }
+
+    pub fn set_auto_proxy(&self) -> Result<()> {
+        match self.enable {
+            true => set_auto_proxy(&self.url),
+            false => unset_proxy(),
+        }
+    }
+}
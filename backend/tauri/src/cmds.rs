@@ -1,19 +1,20 @@
 use crate::{
     config::*,
     core::{tasks::jobs::ProfilesJobGuard, updater::ManifestVersionLatest, *},
-    feat,
+    enhance, feat,
     utils::{
-        candy, dirs, help,
+        boot, candy, dirs, help, logging,
         resolve::{self, save_window_state},
+        window_state,
     },
 };
-use crate::{ret_err, wrap_err};
+use crate::{log_err, ret_err, wrap_err};
 use anyhow::{Context, Result};
 use chrono::Local;
-use log::debug;
 use serde_yaml::Mapping;
 use std::collections::{HashMap, VecDeque};
 use sysproxy::Sysproxy;
+use tracing::debug;
 
 use tauri::api::dialog::FileDialogBuilder;
 
@@ -31,23 +32,73 @@ pub async fn enhance_profiles() -> CmdResult {
     Ok(())
 }
 
+/// 按当前设置重新生成配置，返回和正在使用的配置的差异，不会重载内核
 #[tauri::command]
-pub async fn import_profile(url: String, option: Option<PrfOption>) -> CmdResult {
-    let item = wrap_err!(PrfItem::from_url(&url, None, None, option).await)?;
-    wrap_err!(Config::profiles().data().append_item(item))
+pub async fn preview_config_changes() -> CmdResult<preview::ConfigPreview> {
+    wrap_err!(tokio::task::spawn_blocking(preview::preview).await)
+}
+
+/// a duplicate url is not imported without the strategy, see [`ImportOutcome`]
+#[tauri::command]
+pub async fn import_profile(
+    url: String,
+    option: Option<PrfOption>,
+    strategy: Option<ImportStrategy>,
+) -> CmdResult<ImportOutcome> {
+    wrap_err!(feat::import_profile(url, option, strategy).await)
 }
 
 #[tauri::command]
 pub async fn create_profile(item: PrfItem, file_data: Option<String>) -> CmdResult {
     let item = wrap_err!(PrfItem::from(item, file_data).await)?;
-    wrap_err!(Config::profiles().data().append_item(item))
+    let uid = RemoteScript::is_remote(&item)
+        .then(|| item.uid.clone())
+        .flatten();
+    wrap_err!(Config::profiles().data().append_item(item))?;
+
+    // 远程脚本的首个版本也要确认
+    if let Some(uid) = uid {
+        wrap_err!(feat::update_profile(uid, None).await)?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn reorder_profile(active_id: String, over_id: String) -> CmdResult {
+    wrap_err!(trash::snapshot(trash::ProfileOperation::Reorder, &[]))?;
     wrap_err!(Config::profiles().data().reorder(active_id, over_id))
 }
 
+/// the downloaded remote script waiting for review
+#[tauri::command]
+pub fn get_pending_script(uid: String) -> CmdResult<Option<PendingScript>> {
+    let item = { wrap_err!(Config::profiles().latest().get_item(&uid))?.clone() };
+    wrap_err!(RemoteScript::pending(&item))
+}
+
+/// `hash` is the one of the reviewed script
+#[tauri::command]
+pub async fn approve_remote_script(uid: String, hash: String) -> CmdResult {
+    wrap_err!(feat::approve_remote_script(uid, hash).await)
+}
+
+#[tauri::command]
+pub fn reject_remote_script(uid: String) -> CmdResult {
+    wrap_err!(RemoteScript::reject(&uid))
+}
+
+/// restore the profiles before the last delete, reorder or import
+#[tauri::command]
+pub async fn undo_last_profile_operation() -> CmdResult<trash::TrashEntry> {
+    wrap_err!(trash::undo_last().await)
+}
+
+/// the successful updates of the remote profile, the latest first
+#[tauri::command]
+pub fn get_profile_history(index: String) -> CmdResult<Vec<ProfileUpdateRecord>> {
+    wrap_err!(IProfiles::update_history(&index))
+}
+
 #[tauri::command]
 pub async fn update_profile(index: String, option: Option<PrfOption>) -> CmdResult {
     wrap_err!(feat::update_profile(index, option).await)
@@ -55,6 +106,10 @@ pub async fn update_profile(index: String, option: Option<PrfOption>) -> CmdResu
 
 #[tauri::command]
 pub async fn delete_profile(index: String) -> CmdResult {
+    wrap_err!(trash::snapshot(
+        trash::ProfileOperation::Delete,
+        &[index.clone()]
+    ))?;
     let should_update = wrap_err!({ Config::profiles().data().delete_item(index) })?;
     if should_update {
         wrap_err!(CoreManager::global().update_config().await)?;
@@ -67,26 +122,26 @@ pub async fn delete_profile(index: String) -> CmdResult {
 /// 修改profiles的
 #[tauri::command]
 pub async fn patch_profiles_config(profiles: IProfiles) -> CmdResult {
-    wrap_err!({ Config::profiles().draft().patch_config(profiles) })?;
-
-    match CoreManager::global().update_config().await {
-        Ok(_) => {
-            handle::Handle::refresh_clash();
-            Config::profiles().apply();
-            wrap_err!(Config::profiles().data().save_file())?;
-            Ok(())
-        }
-        Err(err) => {
-            Config::profiles().discard();
-            log::error!(target: "app", "{err}");
-            Err(format!("{err}"))
-        }
-    }
+    wrap_err!(feat::patch_profiles(profiles).await)
 }
 
 /// 修改某个profile item的
 #[tauri::command]
 pub fn patch_profile(index: String, profile: PrfItem) -> CmdResult {
+    if let Some(tls) = profile
+        .option
+        .as_ref()
+        .and_then(|option| option.tls.as_ref())
+    {
+        wrap_err!(tls.validate())?;
+    }
+    if let Some(hooks) = profile
+        .option
+        .as_ref()
+        .and_then(|option| option.hooks.as_ref())
+    {
+        wrap_err!(ProfileHook::validate(hooks))?;
+    }
     wrap_err!(Config::profiles().data().patch_item(index, profile))?;
     ProfilesJobGuard::global().lock().refresh();
     Ok(())
@@ -110,91 +165,1019 @@ pub fn view_profile(index: String) -> CmdResult {
 }
 
 #[tauri::command]
-pub fn read_profile_file(index: String) -> CmdResult<String> {
-    let profiles = Config::profiles();
-    let profiles = profiles.latest();
-    let item = wrap_err!(profiles.get_item(&index))?;
-    let data = wrap_err!(item.read_file())?;
-    Ok(data)
+pub fn read_profile_file(index: String) -> CmdResult<String> {
+    let profiles = Config::profiles();
+    let profiles = profiles.latest();
+    let item = wrap_err!(profiles.get_item(&index))?;
+    let data = wrap_err!(item.read_file())?;
+    Ok(data)
+}
+
+#[tauri::command]
+pub fn save_profile_file(index: String, file_data: Option<String>) -> CmdResult {
+    if file_data.is_none() {
+        return Ok(());
+    }
+
+    let profiles = Config::profiles();
+    let profiles = profiles.latest();
+    let item = wrap_err!(profiles.get_item(&index))?;
+    let file_data = file_data.unwrap();
+    wrap_err!(item.save_file(file_data.clone()))?;
+
+    // 在编辑器里保存的远程脚本视为已确认
+    if RemoteScript::is_remote(item) {
+        wrap_err!(RemoteScript::trust(&index, &file_data))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn format_yaml(content: String) -> CmdResult<editor::FormatResult> {
+    wrap_err!(editor::format(&content))
+}
+
+#[tauri::command]
+pub fn lint_profile_file(index: String, content: String) -> CmdResult<Vec<editor::Diagnostic>> {
+    let item = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        wrap_err!(profiles.get_item(&index))?.clone()
+    };
+    Ok(editor::lint(&item, &content))
+}
+
+/// 保存前先检查，错误会带上行列号返回
+#[tauri::command]
+pub async fn apply_profile_file(index: String, content: String) -> CmdResult<editor::ApplyResult> {
+    wrap_err!(editor::apply(index, content).await)
+}
+
+/// 用样例配置试运行 merge/script，返回结果、日志和耗时
+#[tauri::command]
+pub async fn test_script_chain(
+    chain_id: String,
+    sample_profile: String,
+) -> CmdResult<enhance::ChainTestResult> {
+    let result =
+        tokio::task::spawn_blocking(move || enhance::test_chain(chain_id, sample_profile)).await;
+    wrap_err!(wrap_err!(result)?)
+}
+
+#[tauri::command]
+pub fn query_api_requests(
+    query: clash_api::RequestQuery,
+) -> CmdResult<Vec<clash_api::RequestRecord>> {
+    Ok(clash_api::Inspector::global().query(&query))
+}
+
+#[tauri::command]
+pub fn clear_api_requests() -> CmdResult {
+    clash_api::Inspector::global().clear();
+    Ok(())
+}
+
+/// the frontend requests the controller directly, report them here
+#[tauri::command]
+pub fn record_api_request(mut record: clash_api::RequestRecord) -> CmdResult {
+    record.source = clash_api::RequestSource::Webview;
+    clash_api::Inspector::global().record(record);
+    Ok(())
+}
+
+/// everything the ui needs to render at startup, instead of a dozen calls
+#[tauri::command]
+pub async fn get_app_snapshot() -> CmdResult<snapshot::AppSnapshot> {
+    Ok(snapshot::get().await)
+}
+
+#[tauri::command]
+pub fn get_clash_info() -> CmdResult<ClashInfo> {
+    Ok(Config::clash().latest().get_client_info())
+}
+
+#[tauri::command]
+pub fn get_runtime_config() -> CmdResult<Option<Mapping>> {
+    Ok(Config::runtime().latest().config.clone())
+}
+
+#[tauri::command]
+pub fn get_runtime_yaml() -> CmdResult<String> {
+    let runtime = Config::runtime();
+    let runtime = runtime.latest();
+    let config = runtime.config.as_ref();
+    wrap_err!(config
+        .ok_or(anyhow::anyhow!("failed to parse config to yaml file"))
+        .and_then(
+            |config| serde_yaml::to_string(config).context("failed to convert config to yaml")
+        ))
+}
+
+#[tauri::command]
+pub fn get_runtime_exists() -> CmdResult<Vec<String>> {
+    Ok(Config::runtime().latest().exists_keys.clone())
+}
+
+#[tauri::command]
+pub fn get_runtime_logs() -> CmdResult<HashMap<String, Vec<(String, String)>>> {
+    Ok(Config::runtime().latest().chain_logs.clone())
+}
+
+#[tauri::command]
+pub async fn patch_clash_config(payload: Mapping) -> CmdResult {
+    wrap_err!(feat::patch_clash(payload).await)
+}
+
+#[tauri::command]
+pub fn get_verge_config() -> CmdResult<IVerge> {
+    Ok(Config::verge().data().clone())
+}
+
+#[tauri::command]
+pub async fn patch_verge_config(payload: IVerge) -> CmdResult {
+    wrap_err!(feat::patch_verge(payload).await)
+}
+
+#[tauri::command]
+pub async fn change_clash_core(clash_core: Option<ClashCore>) -> CmdResult {
+    wrap_err!(CoreManager::global().change_core(clash_core).await)
+}
+
+/// restart the sidecar
+#[tauri::command]
+pub async fn restart_sidecar() -> CmdResult {
+    wrap_err!(CoreManager::global().run_core().await)
+}
+
+#[tauri::command]
+pub fn grant_permission(_core: String) -> CmdResult {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    return wrap_err!(manager::grant_permission(_core));
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    return Err("Unsupported target".into());
+}
+
+#[tauri::command]
+pub async fn get_tun_status() -> CmdResult<tun::TunStatus> {
+    Ok(tun::TunManager::global().check().await)
+}
+
+/// install the service on windows, the privileged helper on macos, setcap on linux
+#[tauri::command]
+pub async fn grant_tun_privileges() -> CmdResult {
+    wrap_err!(tun::grant_privileges().await)
+}
+
+/// the local ips and the qr code payload for the lan clients
+#[tauri::command]
+pub fn get_lan_access_info() -> CmdResult<lan::LanAccessInfo> {
+    wrap_err!(lan::get_lan_access_info())
+}
+
+/// the changes will be pushed by the `network_changed` event of `nyanpasu://event`
+#[tauri::command]
+pub fn get_network_state() -> CmdResult<network::NetworkState> {
+    Ok(network::NetworkMonitor::global().state())
+}
+
+/// the timing and failures of each startup stage
+#[tauri::command]
+pub fn get_boot_report() -> CmdResult<boot::BootReport> {
+    Ok(boot::Boot::global().report())
+}
+
+/// the proxy environment variables in the shell syntax
+#[tauri::command]
+pub fn get_proxy_env(shell: proxy_env::ShellType) -> CmdResult<String> {
+    Ok(proxy_env::ProxyEnv::current().render(shell))
+}
+
+#[tauri::command]
+pub fn copy_proxy_env(shell: proxy_env::ShellType) -> CmdResult {
+    feat::copy_clash_env(shell);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_notifications() -> CmdResult<Vec<notification::NotificationItem>> {
+    Ok(notification::Notifications::global().list())
+}
+
+#[tauri::command]
+pub fn clear_notifications() -> CmdResult {
+    notification::Notifications::global().clear();
+    Ok(())
+}
+
+/// run the click action of a notification
+#[tauri::command]
+pub async fn run_notification_action(id: u32) -> CmdResult {
+    wrap_err!(notification::Notifications::global().run(id).await)
+}
+
+/// the page requested by a notification before the window created
+#[tauri::command]
+pub fn take_notification_page() -> CmdResult<Option<String>> {
+    Ok(notification::Notifications::global().take_pending_page())
+}
+
+/// save the endpoint to the config and the password to the keyring
+#[tauri::command]
+pub async fn set_webdav_config(
+    url: String,
+    username: String,
+    password: Option<String>,
+) -> CmdResult {
+    if let Some(password) = password {
+        wrap_err!(webdav::set_password(&username, &password))?;
+    }
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            webdav_url: Some(url),
+            webdav_username: Some(username),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+/// save the event hook to the config and the mqtt password to the keyring
+#[tauri::command]
+pub async fn save_event_hook(hook: EventHook, password: Option<String>) -> CmdResult {
+    if let (
+        HookTarget::Mqtt {
+            username: Some(username),
+            ..
+        },
+        Some(password),
+    ) = (&hook.target, password)
+    {
+        wrap_err!(event_hook::set_password(username, &password))?;
+    }
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            event_hook: Some(hook),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+/// save the tray menu items, the commands in them are allowed to run from now on
+#[tauri::command]
+pub async fn save_tray_menu(entries: Vec<TrayMenuEntry>) -> CmdResult {
+    for entry in entries.iter() {
+        wrap_err!(entry.validate())?;
+    }
+    wrap_err!(tray::TrayMenu::allow(&entries))?;
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            tray_menu: Some(entries),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+#[tauri::command]
+pub async fn test_event_hook() -> CmdResult {
+    wrap_err!(event_hook::test().await)
+}
+
+#[tauri::command]
+pub fn get_remote_management_info() -> CmdResult<remote::RemoteManagementInfo> {
+    wrap_err!(remote::get_info())
+}
+
+/// an empty password removes it
+#[tauri::command]
+pub async fn set_remote_management_password(password: String) -> CmdResult {
+    wrap_err!(remote::set_password(&password))?;
+    wrap_err!(remote::RemoteManagement::global().restart().await)
+}
+
+#[tauri::command]
+pub async fn webdav_backup() -> CmdResult<String> {
+    wrap_err!(webdav::backup().await)
+}
+
+#[tauri::command]
+pub async fn webdav_list_backups() -> CmdResult<Vec<webdav::RemoteBackup>> {
+    wrap_err!(webdav::list_backups().await)
+}
+
+#[tauri::command]
+pub async fn webdav_preview_restore(name: String) -> CmdResult<webdav::RestorePreview> {
+    wrap_err!(webdav::preview_restore(&name).await)
+}
+
+/// the app restarts after restored
+#[tauri::command]
+pub async fn webdav_restore(name: String) -> CmdResult {
+    wrap_err!(webdav::restore(&name).await)
+}
+
+/// save the s3 storage to the config and the secret key to the keyring
+#[tauri::command]
+pub async fn set_sync_s3_config(config: SyncS3Config, secret: Option<String>) -> CmdResult {
+    if let Some(secret) = secret {
+        wrap_err!(sync::set_s3_secret(&config.access_key_id, &secret))?;
+    }
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            sync_s3: Some(config),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+#[tauri::command]
+pub async fn sync_now() -> CmdResult<sync::SyncReport> {
+    wrap_err!(sync::SyncManager::global().sync().await)
+}
+
+#[tauri::command]
+pub fn get_sync_conflicts() -> CmdResult<Vec<sync::SyncConflict>> {
+    Ok(sync::SyncManager::global().conflicts())
+}
+
+#[tauri::command]
+pub async fn resolve_sync_conflict(
+    path: String,
+    pick: sync::SyncPick,
+) -> CmdResult<sync::SyncReport> {
+    wrap_err!(sync::SyncManager::global().resolve(path, pick).await)
+}
+
+/// change the log level and the per-module filters without restart
+#[tauri::command]
+pub async fn set_log_level(level: String, filter: Option<String>) -> CmdResult {
+    let verge = IVerge {
+        app_log_level: Some(level),
+        app_log_filter: filter,
+        ..IVerge::default()
+    };
+    wrap_err!(logging::build_filter(
+        verge.get_log_level(),
+        verge.app_log_filter.as_deref()
+    ))?;
+    wrap_err!(feat::patch_verge(verge).await)
+}
+
+/// the bundle for the bug reports, the secrets are redacted
+#[tauri::command]
+pub async fn generate_diagnostic_bundle() -> CmdResult<String> {
+    let path = wrap_err!(diagnostic::generate_bundle().await)?;
+    if let Some(dir) = path.parent() {
+        let _ = open::that(dir);
+    }
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// export the active connections to the download dir, return the file path
+#[tauri::command]
+pub async fn export_connections(
+    format: export::ExportFormat,
+    filter: Option<export::ConnectionFilter>,
+) -> CmdResult<String> {
+    let path = wrap_err!(export::export_connections(format, filter.unwrap_or_default()).await)?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// export the traffic by minute in the range, the history keeps 7 days
+#[tauri::command]
+pub fn export_traffic_history(
+    range: export::TimeRange,
+    format: export::ExportFormat,
+) -> CmdResult<String> {
+    let path = wrap_err!(export::export_traffic_history(range, format))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// check the core, the ports, the system proxy, the dns and the proxy in order
+#[tauri::command]
+pub async fn run_connectivity_doctor() -> CmdResult<Vec<doctor::DoctorCheck>> {
+    Ok(doctor::run().await)
+}
+
+/// recreate the data dirs, restore the resources and the missing core, fix the permissions,
+/// clear the stale pid file and register the url schemes again
+#[tauri::command]
+pub async fn repair_installation() -> CmdResult<Vec<repair::RepairStep>> {
+    wrap_err!(tokio::task::spawn_blocking(repair::repair_installation).await)
+}
+
+/// the crash reports of the app and the core, the newest first
+#[tauri::command]
+pub fn get_crash_reports() -> CmdResult<Vec<crash::CrashReport>> {
+    Ok(crash::list())
+}
+
+#[tauri::command]
+pub async fn upload_crash_report(id: String) -> CmdResult {
+    wrap_err!(crash::upload(&id).await)
+}
+
+#[tauri::command]
+pub fn clear_crash_reports() -> CmdResult {
+    wrap_err!(crash::clear())
+}
+
+/// check the app update of the selected channel
+#[tauri::command]
+pub async fn check_app_update() -> CmdResult<Option<app_updater::AppUpdateInfo>> {
+    wrap_err!(app_updater::AppUpdater::global().check().await)
+}
+
+/// install the update and restart the app
+#[tauri::command]
+pub async fn install_app_update() -> CmdResult {
+    wrap_err!(app_updater::AppUpdater::global().install_now().await)
+}
+
+/// download the update, installed when the app exits
+#[tauri::command]
+pub async fn stage_app_update() -> CmdResult<String> {
+    wrap_err!(app_updater::AppUpdater::global().stage().await)
+}
+
+#[tauri::command]
+pub async fn skip_app_update(version: String) -> CmdResult {
+    wrap_err!(app_updater::AppUpdater::global().skip(version).await)
+}
+
+/// the port and the token of the binary stream channel
+/// `ws://127.0.0.1:{port}/streams/{connections|traffic|logs}?token={token}`
+#[tauri::command]
+pub fn get_stream_server() -> CmdResult<(u16, String)> {
+    Ok((
+        IVerge::get_singleton_port(),
+        control::ipc_token().to_string(),
+    ))
+}
+
+/// a page of the proxies, filtered and sorted by the backend
+#[tauri::command]
+pub async fn list_proxies(
+    page: query::PageQuery,
+    filter: Option<query::ProxyFilter>,
+    sort: Option<query::ProxySort>,
+) -> CmdResult<query::Page<query::ProxyRow>> {
+    wrap_err!(query::list_proxies(page, filter, sort).await)
+}
+
+/// a page of the connections, filtered and sorted by the backend
+#[tauri::command]
+pub async fn list_connections(
+    page: query::PageQuery,
+    filter: Option<query::ConnectionFilter>,
+    sort: Option<query::ConnectionSort>,
+) -> CmdResult<query::ConnectionsPage> {
+    wrap_err!(query::list_connections(page, filter, sort).await)
+}
+
+/// the dns override of the profile, or the global one when `uid` is empty
+#[tauri::command]
+pub fn get_dns_override(uid: Option<String>) -> CmdResult<Option<DnsOverride>> {
+    match uid {
+        Some(uid) => {
+            let profiles = Config::profiles();
+            let profiles = profiles.latest();
+            let item = wrap_err!(profiles.get_item(&uid))?;
+            Ok(item
+                .option
+                .as_ref()
+                .and_then(|option| option.dns_override.clone()))
+        }
+        None => Ok(Config::verge().latest().dns_override.clone()),
+    }
+}
+
+/// bind the core to the profile, `None` unbinds it
+#[tauri::command]
+pub async fn set_profile_core(uid: String, core: Option<ClashCore>) -> CmdResult {
+    wrap_err!(feat::patch_profile_core(uid, core).await)
+}
+
+/// validate and save the dns override, `None` removes it
+#[tauri::command]
+pub async fn set_dns_override(uid: Option<String>, dns: Option<DnsOverride>) -> CmdResult {
+    wrap_err!(feat::patch_dns_override(uid, dns).await)
+}
+
+/// the entries of the system hosts file, to be imported by the hosts manager
+#[tauri::command]
+pub fn read_system_hosts() -> CmdResult<Vec<HostsEntry>> {
+    wrap_err!(crate::config::read_system_hosts())
+}
+
+#[tauri::command]
+pub fn get_user_rules() -> CmdResult<Vec<UserRule>> {
+    Ok(Config::verge()
+        .latest()
+        .user_rules
+        .clone()
+        .unwrap_or_default())
+}
+
+async fn save_user_rules(rules: Vec<UserRule>) -> CmdResult {
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            user_rules: Some(rules),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+/// validate and add the rule, return the generated id
+#[tauri::command]
+pub async fn add_user_rule(rule: UserRule) -> CmdResult<String> {
+    let id = help::get_uid("u");
+    let mut rules = get_user_rules()?;
+    rules.push(UserRule {
+        id: id.clone(),
+        ..rule
+    });
+    save_user_rules(rules).await?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_user_rule(id: String, rule: UserRule) -> CmdResult {
+    let mut rules = get_user_rules()?;
+    let Some(each) = rules.iter_mut().find(|each| each.id == id) else {
+        ret_err!(format!("the user rule `{id}` not found"));
+    };
+    *each = UserRule { id, ..rule };
+    save_user_rules(rules).await
+}
+
+#[tauri::command]
+pub async fn delete_user_rule(id: String) -> CmdResult {
+    let mut rules = get_user_rules()?;
+    rules.retain(|each| each.id != id);
+    save_user_rules(rules).await
+}
+
+/// the rules with the same position are inserted in this order
+#[tauri::command]
+pub async fn reorder_user_rules(ids: Vec<String>) -> CmdResult {
+    let mut rules = get_user_rules()?;
+    if ids.len() != rules.len() || !rules.iter().all(|rule| ids.contains(&rule.id)) {
+        ret_err!("the ids do not match the user rules");
+    }
+    rules.sort_by_key(|rule| ids.iter().position(|id| *id == rule.id));
+    save_user_rules(rules).await
+}
+
+/// a page of the entries of the rule provider or the geosite category
+/// `filter.target` tests which entries match the domain or the ip
+#[tauri::command]
+pub async fn browse_rule_set(
+    source: rule_set::RuleSetSource,
+    filter: Option<rule_set::RuleSetFilter>,
+    page: query::PageQuery,
+) -> CmdResult<rule_set::RuleSetPage> {
+    wrap_err!(rule_set::browse(source, filter, page).await)
+}
+
+/// the counts of the rules, the providers and the geosite categories
+/// computed in the background, poll again while `pending`
+#[tauri::command]
+pub fn get_rule_stats() -> CmdResult<Option<rule_set::RuleStats>> {
+    Ok(rule_set::stats())
+}
+
+/// the proxies and the groups that can be the hops of a chain
+#[tauri::command]
+pub fn get_chain_candidates() -> CmdResult<Vec<ChainCandidate>> {
+    Ok(ChainNodes::current().candidates())
+}
+
+/// check the chain against the running config and the current core
+#[tauri::command]
+pub fn validate_proxy_chain(chain: ProxyChain) -> CmdResult {
+    let core = { Config::verge().latest().clash_core.clone() };
+    wrap_err!(chain.validate(&ChainNodes::current(), &core.unwrap_or_default()))
+}
+
+#[tauri::command]
+pub async fn save_proxy_chains(chains: Vec<ProxyChain>) -> CmdResult {
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            proxy_chains: Some(chains),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+#[tauri::command]
+pub fn get_tunnels() -> CmdResult<Vec<Tunnel>> {
+    Ok(Config::verge().latest().tunnels.clone().unwrap_or_default())
+}
+
+async fn save_tunnels(tunnels: Vec<Tunnel>) -> CmdResult {
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            tunnels: Some(tunnels),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+/// return the id of the new tunnel
+#[tauri::command]
+pub async fn add_tunnel(tunnel: Tunnel) -> CmdResult<String> {
+    let id = help::get_uid("t");
+    let mut tunnels = get_tunnels()?;
+    tunnels.push(Tunnel {
+        id: id.clone(),
+        ..tunnel
+    });
+    save_tunnels(tunnels).await?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_tunnel(id: String, tunnel: Tunnel) -> CmdResult {
+    let mut tunnels = get_tunnels()?;
+    let Some(each) = tunnels.iter_mut().find(|each| each.id == id) else {
+        ret_err!(format!("the tunnel `{id}` not found"));
+    };
+    *each = Tunnel { id, ..tunnel };
+    save_tunnels(tunnels).await
+}
+
+#[tauri::command]
+pub async fn delete_tunnel(id: String) -> CmdResult {
+    let mut tunnels = get_tunnels()?;
+    tunnels.retain(|each| each.id != id);
+    save_tunnels(tunnels).await
+}
+
+#[tauri::command]
+pub fn get_app_rules() -> CmdResult<Vec<AppRule>> {
+    Ok(Config::verge()
+        .latest()
+        .app_rules
+        .clone()
+        .unwrap_or_default())
+}
+
+async fn save_app_rules(rules: Vec<AppRule>) -> CmdResult {
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            app_rules: Some(rules),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+/// return the id of the new app rule
+#[tauri::command]
+pub async fn add_app_rule(rule: AppRule) -> CmdResult<String> {
+    let id = help::get_uid("a");
+    let mut rules = get_app_rules()?;
+    rules.push(AppRule {
+        id: id.clone(),
+        ..rule
+    });
+    save_app_rules(rules).await?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_app_rule(id: String, rule: AppRule) -> CmdResult {
+    let mut rules = get_app_rules()?;
+    let Some(each) = rules.iter_mut().find(|each| each.id == id) else {
+        ret_err!(format!("the app rule `{id}` not found"));
+    };
+    *each = AppRule { id, ..rule };
+    save_app_rules(rules).await
+}
+
+#[tauri::command]
+pub async fn delete_app_rule(id: String) -> CmdResult {
+    let mut rules = get_app_rules()?;
+    rules.retain(|each| each.id != id);
+    save_app_rules(rules).await
+}
+
+#[tauri::command]
+pub fn get_throttle_rules() -> CmdResult<Vec<ThrottleRule>> {
+    Ok(Config::verge()
+        .latest()
+        .throttle_rules
+        .clone()
+        .unwrap_or_default())
+}
+
+async fn save_throttle_rules(rules: Vec<ThrottleRule>) -> CmdResult {
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            throttle_rules: Some(rules),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+/// return the id of the new throttle rule
+#[tauri::command]
+pub async fn add_throttle_rule(rule: ThrottleRule) -> CmdResult<String> {
+    let id = help::get_uid("t");
+    let mut rules = get_throttle_rules()?;
+    rules.push(ThrottleRule {
+        id: id.clone(),
+        ..rule
+    });
+    save_throttle_rules(rules).await?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_throttle_rule(id: String, rule: ThrottleRule) -> CmdResult {
+    let mut rules = get_throttle_rules()?;
+    let Some(each) = rules.iter_mut().find(|each| each.id == id) else {
+        ret_err!(format!("the throttle rule `{id}` not found"));
+    };
+    *each = ThrottleRule { id, ..rule };
+    save_throttle_rules(rules).await
+}
+
+#[tauri::command]
+pub async fn delete_throttle_rule(id: String) -> CmdResult {
+    let mut rules = get_throttle_rules()?;
+    rules.retain(|each| each.id != id);
+    save_throttle_rules(rules).await
+}
+
+/// the running processes for the app rules picker
+#[tauri::command]
+pub async fn list_processes() -> CmdResult<Vec<process::ProcessInfo>> {
+    wrap_err!(tokio::task::spawn_blocking(process::list_processes).await)
+}
+
+/// probe the local ports of the tunnels
+#[tauri::command]
+pub async fn check_tunnels() -> CmdResult<Vec<TunnelStatus>> {
+    let tunnels = get_tunnels()?;
+    let core = { Config::verge().latest().clash_core.clone() }.unwrap_or_default();
+    let unsupported = !core.supports(CoreFeature::Tunnels);
+    let running = CoreManager::global().is_running();
+
+    let mut result = vec![];
+    for tunnel in tunnels {
+        let message = if !tunnel.enable {
+            None
+        } else if unsupported {
+            Some(format!("`{core}` does not support the tunnels"))
+        } else if !running {
+            Some("the core is not running".to_string())
+        } else {
+            result.push(tunnel.probe().await);
+            continue;
+        };
+        result.push(TunnelStatus {
+            id: tunnel.id,
+            state: TunnelState::Inactive,
+            message,
+        });
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn get_listeners() -> CmdResult<Vec<InboundListener>> {
+    Ok(Config::verge()
+        .latest()
+        .listeners
+        .clone()
+        .unwrap_or_default())
+}
+
+async fn save_listeners(listeners: Vec<InboundListener>) -> CmdResult {
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            listeners: Some(listeners),
+            ..IVerge::default()
+        })
+        .await
+    )
+}
+
+/// return the id of the new listener
+#[tauri::command]
+pub async fn add_listener(listener: InboundListener) -> CmdResult<String> {
+    let id = help::get_uid("l");
+    let mut listeners = get_listeners()?;
+    listeners.push(InboundListener {
+        id: id.clone(),
+        ..listener
+    });
+    save_listeners(listeners).await?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_listener(id: String, listener: InboundListener) -> CmdResult {
+    let mut listeners = get_listeners()?;
+    let Some(each) = listeners.iter_mut().find(|each| each.id == id) else {
+        ret_err!(format!("the listener `{id}` not found"));
+    };
+    *each = InboundListener { id, ..listener };
+    save_listeners(listeners).await
+}
+
+#[tauri::command]
+pub async fn toggle_listener(id: String, enable: bool) -> CmdResult {
+    let mut listeners = get_listeners()?;
+    let Some(each) = listeners.iter_mut().find(|each| each.id == id) else {
+        ret_err!(format!("the listener `{id}` not found"));
+    };
+    each.enable = enable;
+    save_listeners(listeners).await
+}
+
+#[tauri::command]
+pub async fn delete_listener(id: String) -> CmdResult {
+    let mut listeners = get_listeners()?;
+    listeners.retain(|each| each.id != id);
+    save_listeners(listeners).await
+}
+
+/// save the usernames to the config and the passwords to the keyring
+/// the passwords of the removed users are deleted
+#[tauri::command]
+pub async fn set_inbound_auth(auth: InboundAuth, passwords: HashMap<String, String>) -> CmdResult {
+    // 全部检查完再动 keyring
+    wrap_err!(auth.validate())?;
+    if let Some(username) = passwords
+        .keys()
+        .find(|username| !auth.users.contains(username))
+    {
+        ret_err!(format!("the user `{username}` does not exist"));
+    }
+    for username in &auth.users {
+        if !passwords.contains_key(username) && get_inbound_password(username).is_err() {
+            ret_err!(format!("the password of `{username}` is not set"));
+        }
+    }
+
+    let removed = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        verge
+            .inbound_auth
+            .as_ref()
+            .map(|old| {
+                old.users
+                    .iter()
+                    .filter(|user| !auth.users.contains(user))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+
+    // the generated config reads the passwords from the keyring, they are saved before the patch
+    // and the old ones are put back if the patch fails
+    let previous = passwords
+        .keys()
+        .map(|username| (username.clone(), get_inbound_password(username).ok()))
+        .collect::<Vec<_>>();
+    let result = async {
+        for (username, password) in &passwords {
+            set_inbound_password(username, password)?;
+        }
+        feat::patch_verge(IVerge {
+            inbound_auth: Some(auth),
+            ..IVerge::default()
+        })
+        .await
+    }
+    .await;
+    if let Err(err) = result {
+        for (username, password) in previous {
+            match password {
+                Some(password) => log_err!(set_inbound_password(&username, &password)),
+                None => log_err!(delete_inbound_password(&username)),
+            }
+        }
+        return wrap_err!(Err(err));
+    }
+
+    for username in removed {
+        log_err!(delete_inbound_password(&username));
+    }
+    Ok(())
+}
+
+/// the features and the keys supported by the core, the current one by default
+#[tauri::command]
+pub fn get_core_capabilities(core: Option<ClashCore>) -> CmdResult<CoreCapabilities> {
+    let core = core.unwrap_or_else(|| {
+        Config::verge()
+            .latest()
+            .clash_core
+            .clone()
+            .unwrap_or_default()
+    });
+    Ok(CoreCapabilities::of(core))
 }
 
+/// the overrides and the profile keys which break after switching to the core
 #[tauri::command]
-pub fn save_profile_file(index: String, file_data: Option<String>) -> CmdResult {
-    if file_data.is_none() {
-        return Ok(());
+pub fn check_core_compatibility(core: ClashCore) -> CmdResult<Vec<CapabilityWarning>> {
+    let mut warnings = check_overrides(&Config::verge().latest(), &core);
+    if let Some(config) = Config::runtime().latest().config.as_ref() {
+        warnings.extend(check_config_keys(config, &core));
     }
-
-    let profiles = Config::profiles();
-    let profiles = profiles.latest();
-    let item = wrap_err!(profiles.get_item(&index))?;
-    wrap_err!(item.save_file(file_data.unwrap()))
+    Ok(warnings)
 }
 
+/// whether the window should ask for the profile, only true once after launch
 #[tauri::command]
-pub fn get_clash_info() -> CmdResult<ClashInfo> {
-    Ok(Config::clash().latest().get_client_info())
+pub fn take_startup_profile_prompt() -> CmdResult<bool> {
+    Ok(resolve::take_startup_profile_prompt())
 }
 
 #[tauri::command]
-pub fn get_runtime_config() -> CmdResult<Option<Mapping>> {
-    Ok(Config::runtime().latest().config.clone())
+pub async fn save_pause_windows(windows: Vec<PauseWindow>) -> CmdResult {
+    wrap_err!(
+        feat::patch_verge(IVerge {
+            pause_windows: Some(windows),
+            ..IVerge::default()
+        })
+        .await
+    )?;
+    // 立即检查，不用等下一次任务
+    pause::ProxyPause::global().on_tick().await;
+    Ok(())
 }
 
+/// the current pause, none if the proxy is not paused
 #[tauri::command]
-pub fn get_runtime_yaml() -> CmdResult<String> {
-    let runtime = Config::runtime();
-    let runtime = runtime.latest();
-    let config = runtime.config.as_ref();
-    wrap_err!(config
-        .ok_or(anyhow::anyhow!("failed to parse config to yaml file"))
-        .and_then(
-            |config| serde_yaml::to_string(config).context("failed to convert config to yaml")
-        ))
+pub fn get_proxy_pause() -> CmdResult<Option<pause::PauseState>> {
+    Ok(pause::ProxyPause::global().state())
 }
 
+/// pause the system proxy, the tun and go direct, or resume the previous state
 #[tauri::command]
-pub fn get_runtime_exists() -> CmdResult<Vec<String>> {
-    Ok(Config::runtime().latest().exists_keys.clone())
+pub async fn toggle_pause() -> CmdResult<bool> {
+    let paused = wrap_err!(pause::ProxyPause::global().toggle().await)?;
+    handle::Handle::refresh_verge();
+    Ok(paused)
 }
 
+/// find the invalid and conflicting hotkeys before saving
 #[tauri::command]
-pub fn get_runtime_logs() -> CmdResult<HashMap<String, Vec<(String, String)>>> {
-    Ok(Config::runtime().latest().chain_logs.clone())
+pub fn check_hotkeys(hotkeys: Vec<String>) -> CmdResult<Vec<hotkey::HotkeyIssue>> {
+    Ok(hotkey::Hotkey::check(&hotkeys))
 }
 
+/// the session type, the tray and the shortcuts on the linux desktops, `None` on the others
 #[tauri::command]
-pub async fn patch_clash_config(payload: Mapping) -> CmdResult {
-    wrap_err!(feat::patch_clash(payload).await)
-}
+pub fn get_desktop_session() -> CmdResult<Option<serde_json::Value>> {
+    #[cfg(target_os = "linux")]
+    return Ok(serde_json::to_value(crate::core::linux_status::desktop_session()).ok());
 
-#[tauri::command]
-pub fn get_verge_config() -> CmdResult<IVerge> {
-    Ok(Config::verge().data().clone())
+    #[cfg(not(target_os = "linux"))]
+    return Ok(None);
 }
 
+/// generate a new token and restart the control api
 #[tauri::command]
-pub async fn patch_verge_config(payload: IVerge) -> CmdResult {
-    wrap_err!(feat::patch_verge(payload).await)
+pub async fn reset_control_api_token() -> CmdResult<String> {
+    let token = wrap_err!(control_api::reset_token())?;
+    wrap_err!(control_api::ControlApi::global().restart().await)?;
+    Ok(token)
 }
 
+/// generate a new token and restart the controller proxy
 #[tauri::command]
-pub async fn change_clash_core(clash_core: Option<ClashCore>) -> CmdResult {
-    wrap_err!(CoreManager::global().change_core(clash_core).await)
+pub async fn reset_controller_proxy_token() -> CmdResult<String> {
+    let token = wrap_err!(controller_proxy::reset_token())?;
+    wrap_err!(controller_proxy::ControllerProxy::global().restart().await)?;
+    Ok(token)
 }
 
-/// restart the sidecar
+/// the mutations of the third-party dashboards, the latest first
 #[tauri::command]
-pub async fn restart_sidecar() -> CmdResult {
-    wrap_err!(CoreManager::global().run_core().await)
+pub fn get_external_mutations() -> CmdResult<Vec<controller_proxy::ExternalMutation>> {
+    Ok(controller_proxy::ControllerProxy::global().mutations())
 }
 
 #[tauri::command]
-pub fn grant_permission(_core: String) -> CmdResult {
-    #[cfg(any(target_os = "macos", target_os = "linux"))]
-    return wrap_err!(manager::grant_permission(_core));
-
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    return Err("Unsupported target".into());
+pub fn clear_external_mutations() -> CmdResult {
+    controller_proxy::ControllerProxy::global().clear_mutations();
+    Ok(())
 }
 
 /// get the system proxy
@@ -213,11 +1196,60 @@ pub fn get_sys_proxy() -> CmdResult<Mapping> {
     Ok(map)
 }
 
+/// validate the bypass text and convert it to the structured list
+#[tauri::command]
+pub fn parse_bypass_list(text: String) -> CmdResult<Vec<BypassRule>> {
+    wrap_err!(parse_bypass(&text))
+}
+
+#[tauri::command]
+pub fn get_default_bypass_list() -> CmdResult<Vec<BypassRule>> {
+    Ok(default_bypass())
+}
+
 #[tauri::command]
 pub fn get_clash_logs() -> CmdResult<VecDeque<String>> {
     Ok(logger::Logger::global().get_log())
 }
 
+#[tauri::command]
+pub fn query_clash_logs(
+    filter: Option<log_stream::LogFilter>,
+) -> CmdResult<Vec<log_stream::LogItem>> {
+    Ok(log_stream::LogStream::global().query(&filter.unwrap_or_default()))
+}
+
+/// the matched logs will be pushed by the `verge://clash-logs` event
+#[tauri::command]
+pub fn subscribe_clash_logs(filter: log_stream::LogFilter) -> CmdResult {
+    log_stream::LogStream::global().subscribe(filter);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unsubscribe_clash_logs() -> CmdResult {
+    log_stream::LogStream::global().unsubscribe();
+    Ok(())
+}
+
+/// the new samples will be pushed by the `verge://clash-memory` event
+#[tauri::command]
+pub fn get_clash_memory_history() -> CmdResult<Vec<memory_stream::MemorySample>> {
+    Ok(memory_stream::MemoryStream::global().history())
+}
+
+/// the changes will be pushed by the `verge://stream-state` event
+#[tauri::command]
+pub fn get_stream_states() -> CmdResult<HashMap<String, stream::StreamState>> {
+    Ok(stream::StreamManager::global().states())
+}
+
+#[tauri::command]
+pub fn reconnect_streams() -> CmdResult {
+    stream::StreamManager::global().reconnect_all();
+    Ok(())
+}
+
 #[tauri::command]
 pub fn open_app_dir() -> CmdResult<()> {
     let app_dir = wrap_err!(dirs::app_home_dir())?;
@@ -248,6 +1280,13 @@ pub fn save_window_size_state() -> CmdResult<()> {
     wrap_err!(save_window_state(&handle, true))
 }
 
+/// 窗口跑到屏幕外时用
+#[tauri::command]
+pub fn reset_window_state() -> CmdResult<()> {
+    let handle = handle::Handle::global().app_handle.lock().clone().unwrap();
+    wrap_err!(window_state::reset(&handle))
+}
+
 #[tauri::command]
 pub async fn fetch_latest_core_versions() -> CmdResult<ManifestVersionLatest> {
     let mut updater = updater::Updater::global().write().await; // It is intended to block here
@@ -280,7 +1319,7 @@ pub async fn collect_logs() -> CmdResult {
                 match candy::collect_logs(&path) {
                     Ok(_) => (),
                     Err(err) => {
-                        log::error!(target: "app", "{err}");
+                        tracing::error!("{err}");
                     }
                 }
             }
@@ -299,6 +1338,27 @@ pub async fn update_core(core_type: ClashCore) -> CmdResult {
     )
 }
 
+/// install the core from a local binary or a release archive, return the version
+#[tauri::command]
+pub async fn install_core_from_file(path: String, core_type: ClashCore) -> CmdResult<String> {
+    wrap_err!(
+        updater::Updater::install_core_from_file(std::path::Path::new(&path), &core_type).await
+    )
+}
+
+/// pick the file of the core, `None` if canceled
+#[tauri::command]
+pub async fn select_core_file() -> CmdResult<Option<String>> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    FileDialogBuilder::new()
+        .set_title("Select the core")
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let path = wrap_err!(rx.await)?;
+    Ok(path.map(|path| path.to_string_lossy().into_owned()))
+}
+
 #[tauri::command]
 pub async fn clash_api_get_proxy_delay(
     name: String,
@@ -310,6 +1370,219 @@ pub async fn clash_api_get_proxy_delay(
     }
 }
 
+/// test the urls across the nodes, the timeout is in milliseconds
+#[tauri::command]
+pub async fn test_url_matrix(
+    urls: Vec<String>,
+    nodes: Vec<String>,
+    timeout: Option<u64>,
+) -> CmdResult<benchmark::UrlTestMatrix> {
+    wrap_err!(benchmark::test_url_matrix(urls, nodes, timeout.unwrap_or(5000)).await)
+}
+
+/// 切换分组节点，并记录到当前 profile 中
+#[tauri::command]
+pub async fn select_proxy(group: String, name: String) -> CmdResult {
+    wrap_err!(selection::select(group, name).await)
+}
+
+/// select the fastest node in every selector group
+#[tauri::command]
+pub async fn select_fastest_all(
+    test_url: Option<String>,
+) -> CmdResult<Vec<selection::BulkSelected>> {
+    wrap_err!(selection::select_fastest(test_url).await)
+}
+
+/// select a node of the region, e.g. `HK`, in every selector group
+#[tauri::command]
+pub async fn pin_groups_to_region(region: String) -> CmdResult<Vec<selection::BulkSelected>> {
+    wrap_err!(selection::pin_region(region).await)
+}
+
+/// select the first node in every selector group
+#[tauri::command]
+pub async fn reset_group_selections() -> CmdResult<Vec<selection::BulkSelected>> {
+    wrap_err!(selection::reset_default().await)
+}
+
+/// the cached country and asn of the nodes
+#[tauri::command]
+pub fn get_node_info() -> CmdResult<Vec<node_info::NodeInfo>> {
+    Ok(node_info::cached())
+}
+
+/// look up the nodes, all the nodes if `names` is none, and probe the exit ip if `exit`
+#[tauri::command]
+pub async fn enrich_nodes(
+    names: Option<Vec<String>>,
+    exit: Option<bool>,
+) -> CmdResult<Vec<node_info::NodeInfo>> {
+    wrap_err!(node_info::enrich(names, exit.unwrap_or(false)).await)
+}
+
+/// the size of the cache.db and the store flags in effect
+#[tauri::command]
+pub fn get_core_cache() -> CmdResult<cache_db::CacheInfo> {
+    wrap_err!(cache_db::info())
+}
+
+#[tauri::command]
+pub async fn flush_fakeip_cache() -> CmdResult {
+    wrap_err!(cache_db::flush_fakeip().await)
+}
+
+/// remove the cache.db, the core is restarted
+#[tauri::command]
+pub async fn clear_core_cache() -> CmdResult {
+    wrap_err!(cache_db::clear().await)
+}
+
+/// the report of the last maintenance run
+#[tauri::command]
+pub fn get_maintenance_report() -> CmdResult<Option<maintenance::MaintenanceReport>> {
+    Ok(maintenance::last_report())
+}
+
+/// prune the expired logs, the stale snapshots and the orphaned temp dirs now
+#[tauri::command]
+pub async fn run_maintenance() -> CmdResult<maintenance::MaintenanceReport> {
+    wrap_err!(wrap_err!(
+        tokio::task::spawn_blocking(maintenance::run).await
+    )?)
+}
+
+#[tauri::command]
+pub fn get_artifact_cache() -> CmdResult<artifact_cache::ArtifactCacheInfo> {
+    wrap_err!(artifact_cache::info())
+}
+
+/// remove the downloaded core artifacts, return the reclaimed bytes
+#[tauri::command]
+pub fn purge_artifact_cache() -> CmdResult<u64> {
+    wrap_err!(artifact_cache::purge())
+}
+
+#[tauri::command]
+pub fn get_web_uis() -> CmdResult<Vec<web_ui::WebUiInfo>> {
+    wrap_err!(web_ui::list())
+}
+
+/// download the latest build of the web ui
+#[tauri::command]
+pub async fn install_web_ui(id: String) -> CmdResult {
+    wrap_err!(web_ui::install(&id).await)
+}
+
+#[tauri::command]
+pub fn remove_web_ui(id: String) -> CmdResult {
+    wrap_err!(web_ui::remove(&id))
+}
+
+/// open the downloaded web ui with the controller filled in, the default one if `id` is none
+#[tauri::command]
+pub async fn open_web_ui(id: Option<String>) -> CmdResult {
+    wrap_err!(web_ui::WebUiServer::global().open(id).await)
+}
+
+/// the failures counted by the failover and its last run
+#[tauri::command]
+pub fn get_failover_status() -> CmdResult<failover::FailoverStatus> {
+    Ok(failover::Failover::global().status())
+}
+
+/// the traffic by the destination country of the latest `days` days
+#[tauri::command]
+pub fn get_country_stats(days: u32) -> CmdResult<Vec<country_stats::CountryStat>> {
+    Ok(country_stats::CountryStats::global().stats(days))
+}
+
+#[tauri::command]
+pub fn clear_country_stats() -> CmdResult {
+    wrap_err!(country_stats::CountryStats::global().clear())
+}
+
+#[tauri::command]
+pub fn get_onboarding_state() -> CmdResult<onboarding::OnboardingState> {
+    Ok(onboarding::Onboarding::global().state())
+}
+
+/// the locale and the region, for proposing the language and the mirror
+#[tauri::command]
+pub fn onboarding_detect_env() -> CmdResult<onboarding::OnboardingEnv> {
+    Ok(onboarding::detect_env())
+}
+
+#[tauri::command]
+pub async fn onboarding_probe_mirrors() -> CmdResult<Vec<onboarding::MirrorProbe>> {
+    wrap_err!(onboarding::probe_mirrors().await)
+}
+
+/// the other clients whose config can be imported
+#[tauri::command]
+pub fn onboarding_detect_clients() -> CmdResult<Vec<onboarding::DetectedClient>> {
+    Ok(onboarding::detect_clients())
+}
+
+#[tauri::command]
+pub fn onboarding_set_language(language: String) -> CmdResult {
+    wrap_err!(onboarding::Onboarding::global().set_language(language))
+}
+
+#[tauri::command]
+pub fn onboarding_set_mirror(mirror: String) -> CmdResult {
+    wrap_err!(onboarding::Onboarding::global().set_mirror(mirror))
+}
+
+/// return the version of the core
+#[tauri::command]
+pub async fn onboarding_set_core(core: ClashCore) -> CmdResult<String> {
+    wrap_err!(onboarding::Onboarding::global().set_core(core).await)
+}
+
+/// import the client by the importer, its profile in use is switched to when applying
+#[tauri::command]
+pub async fn onboarding_import_client(
+    client: onboarding::KnownClient,
+) -> CmdResult<importer::ImportReport> {
+    wrap_err!(onboarding::Onboarding::global().import_client(client).await)
+}
+
+#[tauri::command]
+pub async fn onboarding_apply() -> CmdResult {
+    wrap_err!(onboarding::Onboarding::global().apply().await)
+}
+
+/// skip the wizard
+#[tauri::command]
+pub fn onboarding_finish() -> CmdResult {
+    wrap_err!(onboarding::Onboarding::global().finish())
+}
+
+/// what will be imported from the data dir of the client
+#[tauri::command]
+pub async fn preview_client_import(
+    client: onboarding::KnownClient,
+) -> CmdResult<importer::ImportReport> {
+    wrap_err!(importer::preview(client).await)
+}
+
+/// import the profiles and the settings of the client
+#[tauri::command]
+pub async fn import_client_data(
+    client: onboarding::KnownClient,
+) -> CmdResult<importer::ImportReport> {
+    wrap_err!(importer::import(client).await)
+}
+
+/// the running config with the providers resolved, for running the raw mihomo on a device
+#[tauri::command]
+pub async fn export_flattened_config(
+    target: flatten::FlattenTarget,
+) -> CmdResult<flatten::FlattenResult> {
+    wrap_err!(flatten::export_flattened_config(target).await)
+}
+
 #[cfg(windows)]
 pub mod uwp {
     use super::*;
@@ -319,6 +1592,17 @@ pub mod uwp {
     pub async fn invoke_uwp_tool() -> CmdResult {
         wrap_err!(win_uwp::invoke_uwptools().await)
     }
+
+    #[tauri::command]
+    pub async fn get_uwp_apps() -> CmdResult<Vec<win_uwp::UwpApp>> {
+        wrap_err!(win_uwp::list_apps())
+    }
+
+    /// 会弹出 uac
+    #[tauri::command]
+    pub async fn set_uwp_loopback(sids: Vec<String>, exempt: bool) -> CmdResult {
+        wrap_err!(win_uwp::set_loopback(sids, exempt).await)
+    }
 }
 
 #[cfg(windows)]
@@ -342,7 +1626,39 @@ pub mod service {
     }
 }
 
-#[cfg(not(windows))]
+/// the privileged helper, the same codes as the windows service
+#[cfg(target_os = "macos")]
+pub mod service {
+    use super::*;
+    use crate::core::mac_helper;
+
+    /// `0` the core is running by the helper, `400` installed
+    #[tauri::command]
+    pub async fn check_service() -> CmdResult<serde_json::Value> {
+        let status = wrap_err!(mac_helper::check_helper().await)?;
+        let code = match status.core_pid {
+            Some(_) => 0,
+            None => 400,
+        };
+        let msg = match mac_helper::is_outdated() {
+            true => "outdated",
+            false => "ok",
+        };
+        Ok(serde_json::json!({ "code": code, "msg": msg, "data": status }))
+    }
+
+    #[tauri::command]
+    pub async fn install_service() -> CmdResult {
+        wrap_err!(mac_helper::install_helper().await)
+    }
+
+    #[tauri::command]
+    pub async fn uninstall_service() -> CmdResult {
+        wrap_err!(mac_helper::uninstall_helper().await)
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
 pub mod service {
     use super::*;
 
@@ -360,6 +1676,65 @@ pub mod service {
     }
 }
 
+#[cfg(target_os = "linux")]
+pub mod systemd {
+    use super::*;
+    use crate::core::systemd::{self, UnitOptions, UnitStatus};
+
+    /// preview the unit file
+    #[tauri::command]
+    pub fn generate_systemd_unit(options: UnitOptions) -> CmdResult<String> {
+        wrap_err!(systemd::generate(&options))
+    }
+
+    #[tauri::command]
+    pub async fn install_systemd_unit(options: UnitOptions) -> CmdResult {
+        wrap_err!(systemd::install(&options).await)
+    }
+
+    #[tauri::command]
+    pub async fn uninstall_systemd_unit(options: UnitOptions) -> CmdResult {
+        wrap_err!(systemd::uninstall(&options).await)
+    }
+
+    /// enable and start, or disable and stop
+    #[tauri::command]
+    pub async fn set_systemd_unit_enabled(options: UnitOptions, enable: bool) -> CmdResult {
+        wrap_err!(systemd::set_enabled(&options, enable).await)
+    }
+
+    #[tauri::command]
+    pub async fn get_systemd_unit_status(options: UnitOptions) -> CmdResult<UnitStatus> {
+        wrap_err!(systemd::status(&options).await)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub mod systemd {
+    use super::*;
+
+    #[tauri::command]
+    pub fn generate_systemd_unit(_options: serde_json::Value) -> CmdResult<String> {
+        Err("Unsupported target".into())
+    }
+    #[tauri::command]
+    pub async fn install_systemd_unit(_options: serde_json::Value) -> CmdResult {
+        Err("Unsupported target".into())
+    }
+    #[tauri::command]
+    pub async fn uninstall_systemd_unit(_options: serde_json::Value) -> CmdResult {
+        Err("Unsupported target".into())
+    }
+    #[tauri::command]
+    pub async fn set_systemd_unit_enabled(_options: serde_json::Value, _enable: bool) -> CmdResult {
+        Err("Unsupported target".into())
+    }
+    #[tauri::command]
+    pub async fn get_systemd_unit_status(_options: serde_json::Value) -> CmdResult {
+        Err("Unsupported target".into())
+    }
+}
+
 #[cfg(not(windows))]
 pub mod uwp {
     use super::*;
@@ -368,4 +1743,14 @@ pub mod uwp {
     pub async fn invoke_uwp_tool() -> CmdResult {
         Ok(())
     }
+
+    #[tauri::command]
+    pub async fn get_uwp_apps() -> CmdResult<Vec<serde_json::Value>> {
+        Ok(vec![])
+    }
+
+    #[tauri::command]
+    pub async fn set_uwp_loopback(_sids: Vec<String>, _exempt: bool) -> CmdResult {
+        Err("Unsupported target".into())
+    }
 }
@@ -0,0 +1,99 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// route the connections of an app, written as the `PROCESS-NAME` rule
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AppRule {
+    #[serde(default)]
+    pub id: String,
+    /// the file name of the executable, e.g. `chrome.exe`
+    pub process: String,
+    /// the proxy, the group, `DIRECT` or `REJECT`
+    pub target: String,
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+impl AppRule {
+    /// the targets are from `rule_targets` of the config if given
+    pub fn validate(&self, targets: Option<&HashSet<String>>) -> Result<()> {
+        let process = self.process.trim();
+        if process.is_empty() {
+            bail!("the process name is empty");
+        }
+        // 规则以逗号分隔，路径应该用 PROCESS-PATH
+        if process.contains([',', '/', '\\']) {
+            bail!("invalid process name `{process}`");
+        }
+        if self.target.trim().is_empty() {
+            bail!("the target of `{process}` is empty");
+        }
+        if targets.is_some_and(|targets| !targets.contains(&self.target)) {
+            bail!("the target `{}` of `{process}` is not found", self.target);
+        }
+        Ok(())
+    }
+
+    pub fn to_rule(&self) -> String {
+        format!("PROCESS-NAME,{},{}", self.process.trim(), self.target)
+    }
+}
+
+/// the same process should not be routed twice
+/// only the rules not in `saved` are validated, the targets of the saved ones may be gone
+/// after switching the profile
+pub fn check_app_rules(
+    rules: &[AppRule],
+    saved: &[AppRule],
+    targets: Option<&HashSet<String>>,
+) -> Result<()> {
+    let mut processes = HashSet::new();
+    for rule in rules.iter().filter(|rule| rule.enable) {
+        if !saved.contains(rule) {
+            rule.validate(targets)?;
+        }
+        // windows 的进程名不区分大小写
+        if !processes.insert(rule.process.trim().to_lowercase()) {
+            bail!("the process `{}` is routed by another rule", rule.process);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_app_rules() {
+    let rule = |process: &str, target: &str| AppRule {
+        id: String::new(),
+        process: process.into(),
+        target: target.into(),
+        enable: true,
+    };
+    let targets = HashSet::from(["Proxy".to_string(), "DIRECT".to_string()]);
+
+    assert_eq!(
+        rule("chrome.exe", "Proxy").to_rule(),
+        "PROCESS-NAME,chrome.exe,Proxy"
+    );
+    assert!(rule("chrome.exe", "DIRECT")
+        .validate(Some(&targets))
+        .is_ok());
+    assert!(rule("chrome.exe", "Missing")
+        .validate(Some(&targets))
+        .is_err());
+    assert!(rule("C:\\chrome.exe", "Proxy").validate(None).is_err());
+    assert!(check_app_rules(
+        &[rule("Chrome.exe", "Proxy"), rule("chrome.exe", "DIRECT")],
+        &[],
+        None
+    )
+    .is_err());
+    // 已保存的规则目标不存在也不挡住保存
+    let saved = [rule("chrome.exe", "Missing")];
+    assert!(check_app_rules(&saved, &saved, Some(&targets)).is_ok());
+    assert!(check_app_rules(&saved, &[], Some(&targets)).is_err());
+}
@@ -0,0 +1,158 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+#[cfg(target_os = "windows")]
+static DEFAULT_BYPASS: &str = "localhost;127.*;192.168.*;10.*;172.16.*;<local>";
+#[cfg(target_os = "linux")]
+static DEFAULT_BYPASS: &str = "localhost,127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,::1";
+#[cfg(target_os = "macos")]
+static DEFAULT_BYPASS: &str =
+    "127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,localhost,*.local,*.crashlytics.com,<local>";
+
+/// an entry of the system proxy bypass list
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum BypassRule {
+    /// e.g. `localhost` `example.com`
+    Domain(String),
+    /// e.g. `*.local` `192.168.*`
+    Wildcard(String),
+    /// e.g. `127.0.0.1` `::1`
+    Ip(IpAddr),
+    /// e.g. `10.0.0.0/8`
+    Cidr(String),
+    /// the plain host names, aka `<local>`
+    Local,
+}
+
+impl BypassRule {
+    pub fn parse(item: &str) -> Result<Self> {
+        let item = item.trim();
+
+        if item.is_empty() {
+            bail!("empty bypass rule");
+        }
+        if item == "<local>" {
+            return Ok(BypassRule::Local);
+        }
+        if let Ok(ip) = item.parse::<IpAddr>() {
+            return Ok(BypassRule::Ip(ip));
+        }
+
+        if let Some((ip, prefix)) = item.split_once('/') {
+            let max = match ip.parse::<IpAddr>() {
+                Ok(IpAddr::V4(_)) => 32,
+                Ok(IpAddr::V6(_)) => 128,
+                Err(_) => bail!("invalid cidr `{item}`"),
+            };
+            match prefix.parse::<u8>() {
+                Ok(prefix) if prefix <= max => return Ok(BypassRule::Cidr(item.into())),
+                _ => bail!("invalid cidr prefix `{item}`"),
+            }
+        }
+
+        let valid = item
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '_' | '*' | ':'));
+        if !valid {
+            bail!("invalid bypass rule `{item}`");
+        }
+
+        match item.contains('*') {
+            true => Ok(BypassRule::Wildcard(item.into())),
+            false => Ok(BypassRule::Domain(item.into())),
+        }
+    }
+
+    /// the `no_proxy` env format
+    /// wildcard `*.example.com` is written as `.example.com`
+    fn to_no_proxy(&self) -> Option<String> {
+        match self {
+            BypassRule::Domain(s) | BypassRule::Cidr(s) => Some(s.clone()),
+            BypassRule::Ip(ip) => Some(ip.to_string()),
+            BypassRule::Wildcard(s) => s
+                .strip_prefix('*')
+                .filter(|s| !s.contains('*'))
+                .map(|s| s.to_string()),
+            BypassRule::Local => None,
+        }
+    }
+
+    fn to_platform(&self) -> Option<String> {
+        match self {
+            BypassRule::Domain(s) | BypassRule::Wildcard(s) | BypassRule::Cidr(s) => {
+                Some(s.clone())
+            }
+            BypassRule::Ip(ip) => Some(ip.to_string()),
+            // gsettings does not support `<local>`
+            BypassRule::Local if cfg!(target_os = "linux") => None,
+            BypassRule::Local => Some("<local>".into()),
+        }
+    }
+}
+
+/// parse the text separated by `,` `;` or new lines
+pub fn parse_bypass(text: &str) -> Result<Vec<BypassRule>> {
+    let mut list = Vec::new();
+
+    for item in text.split([',', ';', '\n']) {
+        if item.trim().is_empty() {
+            continue;
+        }
+        let rule = BypassRule::parse(item)?;
+        if !list.contains(&rule) {
+            list.push(rule);
+        }
+    }
+
+    Ok(list)
+}
+
+pub fn default_bypass() -> Vec<BypassRule> {
+    parse_bypass(DEFAULT_BYPASS).unwrap_or_default()
+}
+
+/// windows uses the `;` list, macos and linux use the `,` list
+/// (sysproxy will split it into the networksetup arguments)
+pub fn bypass_to_platform(list: &[BypassRule]) -> String {
+    let sep = if cfg!(target_os = "windows") {
+        ";"
+    } else {
+        ","
+    };
+
+    list.iter()
+        .filter_map(|rule| rule.to_platform())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+pub fn bypass_to_no_proxy(list: &[BypassRule]) -> String {
+    list.iter()
+        .filter_map(|rule| rule.to_no_proxy())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[test]
+fn test_parse_bypass() {
+    let list = parse_bypass("localhost;127.*\n10.0.0.0/8, ::1,<local>,*.local").unwrap();
+
+    assert_eq!(
+        list,
+        vec![
+            BypassRule::Domain("localhost".into()),
+            BypassRule::Wildcard("127.*".into()),
+            BypassRule::Cidr("10.0.0.0/8".into()),
+            BypassRule::Ip("::1".parse().unwrap()),
+            BypassRule::Local,
+            BypassRule::Wildcard("*.local".into()),
+        ]
+    );
+    assert_eq!(bypass_to_no_proxy(&list), "localhost,10.0.0.0/8,::1,.local");
+
+    assert!(parse_bypass("10.0.0.0/33").is_err());
+    assert!(parse_bypass("exa mple.com").is_err());
+    assert!(!default_bypass().is_empty());
+}
@@ -0,0 +1,222 @@
+use super::{ChainMode, ClashCore, IVerge, SnifferOverride};
+use serde::Serialize;
+use serde_yaml::Mapping;
+
+/// the features of the overrides which depend on the core
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoreFeature {
+    Sniffer,
+    Tunnels,
+    Listeners,
+    DialerProxy,
+    SkipAuthPrefixes,
+    RuleProviders,
+    ProcessRules,
+}
+
+impl CoreFeature {
+    const ALL: [CoreFeature; 7] = [
+        CoreFeature::Sniffer,
+        CoreFeature::Tunnels,
+        CoreFeature::Listeners,
+        CoreFeature::DialerProxy,
+        CoreFeature::SkipAuthPrefixes,
+        CoreFeature::RuleProviders,
+        CoreFeature::ProcessRules,
+    ];
+
+    /// the top-level key of the config written by the feature
+    fn key(&self) -> Option<&'static str> {
+        match self {
+            CoreFeature::Sniffer => Some("sniffer"),
+            CoreFeature::Tunnels => Some("tunnels"),
+            CoreFeature::Listeners => Some("listeners"),
+            CoreFeature::SkipAuthPrefixes => Some("skip-auth-prefixes"),
+            CoreFeature::RuleProviders => Some("rule-providers"),
+            _ => None,
+        }
+    }
+}
+
+/// the top-level keys only known by mihomo
+const META_KEYS: [&str; 13] = [
+    "tls",
+    "sniffer",
+    "geox-url",
+    "listeners",
+    "sub-rules",
+    "geodata-mode",
+    "unified-delay",
+    "tcp-concurrent",
+    "enable-process",
+    "find-process-mode",
+    "skip-auth-prefixes",
+    "external-controller-tls",
+    "global-client-fingerprint",
+];
+
+/// the top-level keys of clash premium
+const PREMIUM_KEYS: [&str; 3] = ["script", "ebpf", "auto-redir"];
+
+/// the keys clash-rs does not support besides the meta ones
+const CLASH_RS_MISSING_KEYS: [&str; 5] = [
+    "tunnels",
+    "redir-port",
+    "tproxy-port",
+    "iptables",
+    "routing-mark",
+];
+
+impl ClashCore {
+    /// the capability matrix, keep it in sync with the cores
+    pub fn supports(&self, feature: CoreFeature) -> bool {
+        match (self, feature) {
+            (ClashCore::Mihomo | ClashCore::MihomoAlpha, _) => true,
+            (ClashCore::ClashPremium, CoreFeature::Tunnels | CoreFeature::RuleProviders) => true,
+            (ClashCore::ClashPremium, CoreFeature::ProcessRules) => true,
+            (ClashCore::ClashRs, CoreFeature::RuleProviders) => true,
+            _ => false,
+        }
+    }
+
+    pub fn supports_key(&self, key: &str) -> bool {
+        match self {
+            ClashCore::Mihomo | ClashCore::MihomoAlpha => !PREMIUM_KEYS.contains(&key),
+            ClashCore::ClashPremium => !META_KEYS.contains(&key),
+            ClashCore::ClashRs => {
+                !META_KEYS.contains(&key)
+                    && !PREMIUM_KEYS.contains(&key)
+                    && !CLASH_RS_MISSING_KEYS.contains(&key)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreCapabilities {
+    pub core: ClashCore,
+    pub features: Vec<CoreFeature>,
+    /// the known keys which are ignored or rejected by the core
+    pub unsupported_keys: Vec<&'static str>,
+}
+
+impl CoreCapabilities {
+    pub fn of(core: ClashCore) -> Self {
+        let features = CoreFeature::ALL
+            .into_iter()
+            .filter(|feature| core.supports(*feature))
+            .collect();
+        let unsupported_keys = META_KEYS
+            .into_iter()
+            .chain(PREMIUM_KEYS)
+            .chain(CLASH_RS_MISSING_KEYS)
+            .filter(|key| !core.supports_key(key))
+            .collect();
+        Self {
+            core,
+            features,
+            unsupported_keys,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapabilityWarning {
+    /// the feature of the overrides, or none for the keys of the profile
+    pub feature: Option<CoreFeature>,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+/// the overrides of the user which do not work with the core
+pub fn check_overrides(verge: &IVerge, core: &ClashCore) -> Vec<CapabilityWarning> {
+    let mut used = vec![];
+    if verge
+        .sniffer_override
+        .as_ref()
+        .is_some_and(|sniffer| sniffer != &SnifferOverride::default())
+    {
+        used.push(CoreFeature::Sniffer);
+    }
+    if verge.tunnels.iter().flatten().any(|tunnel| tunnel.enable) {
+        used.push(CoreFeature::Tunnels);
+    }
+    if verge
+        .listeners
+        .iter()
+        .flatten()
+        .any(|listener| listener.enable)
+    {
+        used.push(CoreFeature::Listeners);
+    }
+    if verge
+        .proxy_chains
+        .iter()
+        .flatten()
+        .any(|chain| chain.enable && chain.mode == ChainMode::DialerProxy)
+    {
+        used.push(CoreFeature::DialerProxy);
+    }
+    if verge
+        .inbound_auth
+        .as_ref()
+        .is_some_and(|auth| auth.enable && !auth.skip_auth_prefixes.is_empty())
+    {
+        used.push(CoreFeature::SkipAuthPrefixes);
+    }
+    if verge
+        .user_rules
+        .iter()
+        .flatten()
+        .any(|rule| rule.enable && rule.rule_type.starts_with("PROCESS-"))
+        || verge.app_rules.iter().flatten().any(|rule| rule.enable)
+    {
+        used.push(CoreFeature::ProcessRules);
+    }
+
+    used.into_iter()
+        .filter(|feature| !core.supports(*feature))
+        .map(|feature| CapabilityWarning {
+            feature: Some(feature),
+            key: feature.key().map(|key| key.to_string()),
+            message: format!("{feature:?} is not supported by `{core}`"),
+        })
+        .collect()
+}
+
+/// the keys of the generated config which the core does not know
+pub fn check_config_keys(config: &Mapping, core: &ClashCore) -> Vec<CapabilityWarning> {
+    config
+        .keys()
+        .filter_map(|key| key.as_str())
+        .filter(|key| !core.supports_key(key))
+        .map(|key| CapabilityWarning {
+            feature: None,
+            key: Some(key.to_string()),
+            message: format!("the key `{key}` is not supported by `{core}`"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_core_capabilities() {
+    assert!(ClashCore::Mihomo.supports(CoreFeature::DialerProxy));
+    assert!(!ClashCore::ClashRs.supports(CoreFeature::Sniffer));
+    assert!(ClashCore::ClashPremium.supports(CoreFeature::Tunnels));
+
+    let config = serde_yaml::from_str::<Mapping>("sniffer: {}\nscript: {}\nproxies: []\n").unwrap();
+    let keys = |core| {
+        check_config_keys(&config, &core)
+            .into_iter()
+            .filter_map(|w| w.key)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(keys(ClashCore::Mihomo), vec!["script"]);
+    assert_eq!(keys(ClashCore::ClashPremium), vec!["sniffer"]);
+    assert_eq!(keys(ClashCore::ClashRs), vec!["sniffer", "script"]);
+
+    let caps = CoreCapabilities::of(ClashCore::ClashRs);
+    assert_eq!(caps.features, vec![CoreFeature::RuleProviders]);
+    assert!(caps.unsupported_keys.contains(&"tunnels"));
+}
@@ -1,4 +1,4 @@
-use crate::utils::{dirs, help};
+use crate::utils::{dirs, help, secrets};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_yaml::{Mapping, Value};
@@ -7,6 +7,8 @@ use std::{
     str::FromStr,
 };
 
+const CLASH_SECRET_KEY: &str = "clash/secret";
+
 #[derive(Default, Debug, Clone)]
 pub struct IClashTemp(pub Mapping);
 
@@ -15,7 +17,7 @@ impl IClashTemp {
         match dirs::clash_path().and_then(|path| help::read_merge_mapping(&path)) {
             Ok(map) => Self(Self::guard(map)),
             Err(err) => {
-                log::error!(target: "app", "{err}");
+                tracing::error!("{err}");
                 Self::template()
             }
         }
@@ -45,6 +47,10 @@ impl IClashTemp {
 
         config.insert("mixed-port".into(), port.into());
         config.insert("external-controller".into(), ctrl.into());
+        if let Some(Value::String(secret)) = config.get("secret") {
+            let secret = secrets::resolve(secret);
+            config.insert("secret".into(), secret.into());
+        }
         config
     }
 
@@ -54,10 +60,17 @@ impl IClashTemp {
         }
     }
 
+    /// the controller secret is saved to the keyring
     pub fn save_config(&self) -> Result<()> {
+        let mut config = self.0.clone();
+        if let Some(Value::String(secret)) = config.get("secret") {
+            let secret = secrets::protect(CLASH_SECRET_KEY, secret);
+            config.insert("secret".into(), secret.into());
+        }
+
         help::save_yaml(
             &dirs::clash_path()?,
-            &self.0,
+            &config,
             Some("# Generated by Clash Nyanpasu"),
         )
     }
@@ -66,6 +79,13 @@ impl IClashTemp {
         Self::guard_mixed_port(&self.0)
     }
 
+    pub fn get_mode(&self) -> Option<String> {
+        self.0
+            .get("mode")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    }
+
     pub fn get_client_info(&self) -> ClashInfo {
         let config = &self.0;
 
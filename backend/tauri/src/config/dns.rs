@@ -0,0 +1,204 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, SocketAddr},
+};
+
+/// the schemes of the nameservers supported by the cores
+const SCHEMES: [&str; 7] = ["udp", "tcp", "tls", "https", "quic", "dhcp", "system"];
+
+/// the `dns` settings applied on top of the profile when generating the runtime config
+/// the `None` fields keep the values of the profile, the lists replace the whole list
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct DnsOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6: Option<bool>,
+
+    /// `fake-ip` or `redir-host`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enhanced_mode: Option<String>,
+
+    /// e.g. `223.5.5.5` `tls://dns.google:853` `https://1.1.1.1/dns-query`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nameserver: Option<Vec<String>>,
+
+    /// resolve the domains of the nameservers, only the ips
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_nameserver: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<Vec<String>>,
+
+    /// e.g. `*.lan` `+.stun.*.*` `geosite:cn`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fake_ip_filter: Option<Vec<String>>,
+
+    /// domain -> ip, merged into the `hosts` of the profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hosts: Option<BTreeMap<String, String>>,
+}
+
+impl DnsOverride {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(mode) = &self.enhanced_mode {
+            if !matches!(mode.as_str(), "fake-ip" | "redir-host" | "normal") {
+                bail!("invalid enhanced mode `{mode}`");
+            }
+        }
+        for server in self.nameserver.iter().chain(self.fallback.iter()).flatten() {
+            check_nameserver(server, false)?;
+        }
+        for server in self.default_nameserver.iter().flatten() {
+            check_nameserver(server, true)?;
+        }
+        for pattern in self.fake_ip_filter.iter().flatten() {
+            check_pattern(pattern)?;
+        }
+        for (domain, ip) in self.hosts.iter().flatten() {
+            check_pattern(domain)?;
+            if ip.trim().parse::<IpAddr>().is_err() {
+                bail!("invalid ip `{ip}` of the host `{domain}`");
+            }
+        }
+        Ok(())
+    }
+
+    /// the fields of `other` take precedence
+    pub fn merge(one: Option<Self>, other: Option<Self>) -> Option<Self> {
+        match (one, other) {
+            (Some(mut a), Some(b)) => {
+                a.enable = b.enable.or(a.enable);
+                a.ipv6 = b.ipv6.or(a.ipv6);
+                a.enhanced_mode = b.enhanced_mode.or(a.enhanced_mode);
+                a.nameserver = b.nameserver.or(a.nameserver);
+                a.default_nameserver = b.default_nameserver.or(a.default_nameserver);
+                a.fallback = b.fallback.or(a.fallback);
+                a.fake_ip_filter = b.fake_ip_filter.or(a.fake_ip_filter);
+                a.hosts = match (a.hosts, b.hosts) {
+                    (Some(mut a), Some(b)) => {
+                        a.extend(b);
+                        Some(a)
+                    }
+                    (a, b) => b.or(a),
+                };
+                Some(a)
+            }
+            t @ _ => t.0.or(t.1),
+        }
+    }
+}
+
+/// `ip_only` for the default nameservers, they can not be resolved by others
+fn check_nameserver(server: &str, ip_only: bool) -> Result<()> {
+    let server = server.trim();
+    // meta 支持 `#proxy` 指定出站
+    let addr = server.split('#').next().unwrap_or_default();
+    if addr.is_empty() {
+        bail!("empty nameserver");
+    }
+    if addr == "system" && !ip_only {
+        return Ok(());
+    }
+
+    let host = match addr.split_once("://") {
+        Some((scheme, rest)) => {
+            if !SCHEMES.contains(&scheme) {
+                bail!("unsupported scheme `{scheme}` of the nameserver `{server}`");
+            }
+            let host = rest.split(['/', '?']).next().unwrap_or_default();
+            // dhcp://en0 和 system:// 不是地址
+            if matches!(scheme, "dhcp" | "system") {
+                if ip_only {
+                    bail!("the default nameserver `{server}` must be an ip");
+                }
+                return Ok(());
+            }
+            host
+        }
+        None => addr,
+    };
+
+    if host.parse::<IpAddr>().is_ok() || host.parse::<SocketAddr>().is_ok() {
+        return Ok(());
+    }
+    if ip_only {
+        bail!("the default nameserver `{server}` must be an ip");
+    }
+
+    let domain = match host.rsplit_once(':') {
+        Some((domain, port)) if port.parse::<u16>().is_ok() => domain,
+        Some(_) => bail!("invalid port of the nameserver `{server}`"),
+        None => host,
+    };
+    let valid = !domain.is_empty()
+        && domain
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '_'));
+    if !valid {
+        bail!("invalid nameserver `{server}`");
+    }
+    Ok(())
+}
+
+pub(super) fn check_pattern(pattern: &str) -> Result<()> {
+    let pattern = pattern.trim();
+    let valid = !pattern.is_empty()
+        && pattern
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '_' | '*' | '+' | ':'));
+    if !valid {
+        bail!("invalid domain pattern `{pattern}`");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_validate_dns_override() {
+    let servers = |list: &[&str]| Some(list.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+
+    let dns = DnsOverride {
+        enhanced_mode: Some("fake-ip".into()),
+        nameserver: servers(&[
+            "223.5.5.5",
+            "[2400:3200::1]:53",
+            "tls://dns.google:853",
+            "https://1.1.1.1/dns-query#DIRECT",
+            "dhcp://en0",
+            "system",
+        ]),
+        default_nameserver: servers(&["114.114.114.114", "tcp://1.1.1.1"]),
+        fake_ip_filter: servers(&["*.lan", "+.stun.*.*", "geosite:cn"]),
+        hosts: Some([("router.lan".to_string(), "192.168.1.1".to_string())].into()),
+        ..DnsOverride::default()
+    };
+    assert!(dns.validate().is_ok());
+
+    let invalid = [
+        DnsOverride {
+            nameserver: servers(&["ftp://1.1.1.1"]),
+            ..DnsOverride::default()
+        },
+        DnsOverride {
+            default_nameserver: servers(&["dns.google"]),
+            ..DnsOverride::default()
+        },
+        DnsOverride {
+            fallback: servers(&["dns.google:99999"]),
+            ..DnsOverride::default()
+        },
+        DnsOverride {
+            hosts: Some([("a.com".to_string(), "not-ip".to_string())].into()),
+            ..DnsOverride::default()
+        },
+        DnsOverride {
+            enhanced_mode: Some("mapping".into()),
+            ..DnsOverride::default()
+        },
+    ];
+    assert!(invalid.iter().all(|dns| dns.validate().is_err()));
+}
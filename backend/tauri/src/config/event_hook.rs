@@ -0,0 +1,99 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    CoreCrashed,
+    ProfileUpdated,
+    QuotaExceeded,
+    NodeSwitched,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::CoreCrashed => "core_crashed",
+            HookEvent::ProfileUpdated => "profile_updated",
+            HookEvent::QuotaExceeded => "quota_exceeded",
+            HookEvent::NodeSwitched => "node_switched",
+        }
+    }
+}
+
+/// where the events are published
+/// the mqtt password is kept in the keyring like the webdav one
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookTarget {
+    /// the events are posted as json
+    Webhook { url: String },
+    /// `mqtt://host:1883` or `mqtts://host:8883`
+    Mqtt {
+        broker: String,
+        topic: String,
+        #[serde(default)]
+        username: Option<String>,
+    },
+}
+
+/// publish the selected events to the home automation, e.g. home assistant
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventHook {
+    #[serde(default)]
+    pub enable: bool,
+    pub target: HookTarget,
+    #[serde(default)]
+    pub events: Vec<HookEvent>,
+}
+
+impl EventHook {
+    pub fn validate(&self) -> Result<()> {
+        match &self.target {
+            HookTarget::Webhook { url } => match Url::parse(url) {
+                Ok(url) if ["http", "https"].contains(&url.scheme()) => {}
+                _ => bail!("invalid webhook url `{url}`"),
+            },
+            HookTarget::Mqtt { broker, topic, .. } => {
+                match Url::parse(broker) {
+                    Ok(url) if ["mqtt", "mqtts"].contains(&url.scheme()) && url.has_host() => {}
+                    _ => bail!("invalid mqtt broker `{broker}`"),
+                }
+                // 发布时不能使用通配符
+                if topic.is_empty() || topic.contains(['+', '#']) {
+                    bail!("invalid mqtt topic `{topic}`");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn subscribed(&self, event: HookEvent) -> bool {
+        self.enable && self.events.contains(&event)
+    }
+}
+
+#[test]
+fn test_event_hook_validate() {
+    let mqtt = |broker: &str, topic: &str| EventHook {
+        enable: true,
+        target: HookTarget::Mqtt {
+            broker: broker.into(),
+            topic: topic.into(),
+            username: None,
+        },
+        events: vec![HookEvent::CoreCrashed],
+    };
+    assert!(mqtt("mqtt://192.168.1.2:1883", "nyanpasu/events")
+        .validate()
+        .is_ok());
+    assert!(!mqtt("http://192.168.1.2", "nyanpasu/events")
+        .validate()
+        .is_ok());
+    assert!(!mqtt("mqtt://192.168.1.2", "nyanpasu/#").validate().is_ok());
+
+    let hook = mqtt("mqtt://localhost", "a");
+    assert!(hook.subscribed(HookEvent::CoreCrashed));
+    assert!(!hook.subscribed(HookEvent::NodeSwitched));
+}
@@ -0,0 +1,70 @@
+use super::ClashCore;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// what to do when the core keeps failing, run in order
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FailoverAction {
+    /// switch to the backup profile
+    SwitchProfile {
+        uid: String,
+    },
+    /// switch to another core, e.g. the stable mihomo from the alpha
+    SwitchCore {
+        core: ClashCore,
+    },
+    /// turn off the system proxy and the tun mode to restore the raw connectivity
+    DisableSystemProxy,
+    Notify,
+}
+
+/// run the actions once the core fails `threshold` times within `window` seconds
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CoreFailover {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default = "default_threshold")]
+    pub threshold: usize,
+    #[serde(default = "default_window")]
+    pub window: u64,
+    #[serde(default)]
+    pub actions: Vec<FailoverAction>,
+}
+
+fn default_threshold() -> usize {
+    3
+}
+
+fn default_window() -> u64 {
+    5 * 60
+}
+
+impl CoreFailover {
+    pub fn validate(&self) -> Result<()> {
+        if self.threshold == 0 {
+            bail!("the failover threshold must be at least 1");
+        }
+        if self.window == 0 {
+            bail!("the failover window must be at least 1 second");
+        }
+        if self.enable && self.actions.is_empty() {
+            bail!("the failover has no actions");
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_core_failover_validate() {
+    let failover: CoreFailover =
+        serde_json::from_str(r#"{ "enable": true, "actions": [{ "type": "notify" }] }"#).unwrap();
+    assert_eq!((failover.threshold, failover.window), (3, 300));
+    assert!(failover.validate().is_ok());
+
+    let failover = CoreFailover {
+        actions: vec![],
+        ..failover
+    };
+    assert!(failover.validate().is_err());
+}
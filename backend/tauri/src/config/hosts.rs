@@ -0,0 +1,102 @@
+use super::dns::check_pattern;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, net::IpAddr, path::PathBuf};
+
+/// the names of the system hosts file that are useless for clash
+const SKIPPED_HOSTS: [&str; 5] = [
+    "localhost",
+    "localhost.localdomain",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+];
+
+/// a managed `hosts` entry, merged into the generated config
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HostsEntry {
+    /// e.g. `router.lan` `*.example.com`
+    pub domain: String,
+    pub ip: String,
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+impl HostsEntry {
+    pub fn validate(&self) -> Result<()> {
+        check_pattern(&self.domain)?;
+        if self.ip.trim().parse::<IpAddr>().is_err() {
+            bail!("invalid ip `{}` of the host `{}`", self.ip, self.domain);
+        }
+        Ok(())
+    }
+}
+
+/// parse the `ip name [aliases...]` lines, the comments and the loopback names are skipped
+pub fn parse_hosts_file(text: &str) -> Vec<HostsEntry> {
+    let mut entries: Vec<HostsEntry> = vec![];
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut parts = line.split_whitespace();
+        let Some(ip) = parts.next().and_then(|ip| ip.parse::<IpAddr>().ok()) else {
+            continue;
+        };
+
+        for domain in parts {
+            let domain = domain.to_lowercase();
+            if SKIPPED_HOSTS.contains(&domain.as_str()) || check_pattern(&domain).is_err() {
+                continue;
+            }
+            let entry = HostsEntry {
+                domain,
+                ip: ip.to_string(),
+                enable: true,
+            };
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+    }
+    entries
+}
+
+fn system_hosts_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let root = std::env::var("SystemRoot").unwrap_or("C:\\Windows".into());
+        PathBuf::from(root).join("System32\\drivers\\etc\\hosts")
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        PathBuf::from("/etc/hosts")
+    }
+}
+
+/// read the entries of the system hosts file
+pub fn read_system_hosts() -> Result<Vec<HostsEntry>> {
+    let path = system_hosts_path();
+    let text = match fs::read(&path) {
+        Ok(data) => String::from_utf8_lossy(&data).to_string(),
+        Err(err) => bail!("failed to read `{}`: {err}", path.display()),
+    };
+    Ok(parse_hosts_file(&text))
+}
+
+#[test]
+fn test_parse_hosts_file() {
+    let text = "# comment\n127.0.0.1 localhost\n::1 localhost ip6-localhost\n\n192.168.1.1\trouter.lan nas.lan # home\n192.168.1.1 router.lan\nnot-ip a.com\n10.0.0.1 bad/name\n";
+    let entries = parse_hosts_file(text);
+
+    let domains = entries
+        .iter()
+        .map(|e| e.domain.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(domains, vec!["router.lan", "nas.lan"]);
+    assert!(entries.iter().all(|e| e.ip == "192.168.1.1" && e.enable));
+    assert!(entries.iter().all(|e| e.validate().is_ok()));
+}
@@ -0,0 +1,147 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// what the app's own request is for, each has its own policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpPurpose {
+    /// the core updates, the version manifests and the web ui downloads
+    Updater,
+    /// the remote profiles and scripts, the options of each profile take precedence
+    Profiles,
+    /// the rule providers and the other data files of the core
+    Geodata,
+    /// the webdav backups and the sync storage
+    Sync,
+    /// the posts to the user's endpoints, the event hooks and the crash reports
+    Webhooks,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpProxy {
+    Direct,
+    /// the system proxy if it is enabled
+    System,
+    /// the mixed port of the core if it is running
+    Core,
+    /// e.g. `socks5://127.0.0.1:1080`
+    Custom(String),
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HttpPolicy {
+    /// seconds of the whole request, 0 means no limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// retried on the connection errors, the timeouts and the 5xx responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<HttpProxy>,
+}
+
+/// the unset fields fall back to the defaults of each purpose
+#[derive(Default, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HttpPolicies {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updater: Option<HttpPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<HttpPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geodata: Option<HttpPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync: Option<HttpPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhooks: Option<HttpPolicy>,
+}
+
+const MAX_RETRIES: u32 = 5;
+
+impl HttpPurpose {
+    /// the defaults when the policy is not set, no proxy means the proxy env vars
+    fn default_policy(&self) -> (u64, u32, Option<HttpProxy>) {
+        match self {
+            // 内核的压缩包走镜像可能很慢
+            HttpPurpose::Updater => (300, 2, None),
+            HttpPurpose::Profiles => (60, 1, None),
+            // 规则集的地址经常被墙，内核运行时走代理
+            HttpPurpose::Geodata => (30, 1, Some(HttpProxy::Core)),
+            HttpPurpose::Sync => (60, 1, None),
+            // 重试可能重复投递
+            HttpPurpose::Webhooks => (30, 0, None),
+        }
+    }
+}
+
+impl HttpPolicies {
+    fn get(&self, purpose: HttpPurpose) -> Option<&HttpPolicy> {
+        match purpose {
+            HttpPurpose::Updater => self.updater.as_ref(),
+            HttpPurpose::Profiles => self.profiles.as_ref(),
+            HttpPurpose::Geodata => self.geodata.as_ref(),
+            HttpPurpose::Sync => self.sync.as_ref(),
+            HttpPurpose::Webhooks => self.webhooks.as_ref(),
+        }
+    }
+
+    /// the policy with the defaults filled
+    pub fn resolve(&self, purpose: HttpPurpose) -> (u64, u32, Option<HttpProxy>) {
+        let (timeout, retries, proxy) = purpose.default_policy();
+        let policy = self.get(purpose).cloned().unwrap_or_default();
+        (
+            policy.timeout.unwrap_or(timeout),
+            policy.retries.unwrap_or(retries),
+            policy.proxy.or(proxy),
+        )
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let policies = [
+            self.updater.as_ref(),
+            self.profiles.as_ref(),
+            self.geodata.as_ref(),
+            self.sync.as_ref(),
+            self.webhooks.as_ref(),
+        ];
+        for policy in policies.into_iter().flatten() {
+            if policy.retries.unwrap_or_default() > MAX_RETRIES {
+                bail!("the retries should be at most {MAX_RETRIES}");
+            }
+            if let Some(HttpProxy::Custom(url)) = &policy.proxy {
+                reqwest::Proxy::all(url.trim())
+                    .map_err(|err| anyhow!("invalid proxy `{url}`: {err}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_http_policies() {
+    let policies = serde_yaml::from_str::<HttpPolicies>(
+        "geodata: {retries: 3, proxy: direct}\nprofiles: {proxy: {custom: 'socks5://127.0.0.1:1080'}}",
+    )
+    .unwrap();
+    assert_eq!(
+        policies.resolve(HttpPurpose::Geodata),
+        (30, 3, Some(HttpProxy::Direct))
+    );
+    assert_eq!(policies.resolve(HttpPurpose::Updater), (300, 2, None));
+    assert!(policies.validate().is_ok());
+
+    let invalid = HttpPolicies {
+        updater: Some(HttpPolicy {
+            retries: Some(10),
+            ..HttpPolicy::default()
+        }),
+        ..HttpPolicies::default()
+    };
+    assert!(invalid.validate().is_err());
+}
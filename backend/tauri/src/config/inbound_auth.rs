@@ -0,0 +1,101 @@
+use crate::utils::secrets;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, net::IpAddr};
+
+/// the auth of the mixed / http / socks ports, the `authentication` of clash
+/// only the usernames are saved here, the passwords are kept in the keyring
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InboundAuth {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default)]
+    pub users: Vec<String>,
+    /// the clients in these cidrs skip the auth, only meta
+    #[serde(default)]
+    pub skip_auth_prefixes: Vec<String>,
+}
+
+fn secret_key(username: &str) -> String {
+    format!("inbound::{username}")
+}
+
+pub fn set_inbound_password(username: &str, password: &str) -> Result<()> {
+    secrets::set(&secret_key(username), password)
+}
+
+pub fn get_inbound_password(username: &str) -> Result<String> {
+    secrets::get(&secret_key(username))?.ok_or(anyhow!("the password of `{username}` is not set"))
+}
+
+pub fn delete_inbound_password(username: &str) -> Result<()> {
+    secrets::delete(&secret_key(username))
+}
+
+impl InboundAuth {
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for user in &self.users {
+            if user.trim().is_empty() {
+                bail!("the username is empty");
+            }
+            // 生成的配置是 `user:pass` 的格式
+            if user.contains(':') {
+                bail!("the username `{user}` can not contain `:`");
+            }
+            if !names.insert(user) {
+                bail!("the username `{user}` is duplicated");
+            }
+        }
+        if self.enable && self.users.is_empty() {
+            bail!("add a user before enabling the auth");
+        }
+
+        for prefix in &self.skip_auth_prefixes {
+            let (ip, len) = prefix.split_once('/').unwrap_or((prefix, ""));
+            let max = match ip.parse::<IpAddr>() {
+                Ok(IpAddr::V4(_)) => 32,
+                Ok(IpAddr::V6(_)) => 128,
+                Err(_) => bail!("invalid cidr `{prefix}`"),
+            };
+            if !len.is_empty() && !len.parse::<u8>().is_ok_and(|len| len <= max) {
+                bail!("invalid cidr `{prefix}`");
+            }
+        }
+        Ok(())
+    }
+
+    /// the `user:pass` list, the users without password are skipped
+    pub fn credentials(&self) -> Vec<String> {
+        if !self.enable {
+            return vec![];
+        }
+        self.users
+            .iter()
+            .filter_map(|user| match get_inbound_password(user) {
+                Ok(password) => Some(format!("{user}:{password}")),
+                Err(err) => {
+                    tracing::warn!("skip the inbound user: {err}");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_validate_inbound_auth() {
+    let auth = |users: &[&str], prefixes: &[&str]| InboundAuth {
+        enable: true,
+        users: users.iter().map(|s| s.to_string()).collect(),
+        skip_auth_prefixes: prefixes.iter().map(|s| s.to_string()).collect(),
+    };
+
+    assert!(auth(&["alice", "bob"], &["127.0.0.1/32", "fd00::/8"])
+        .validate()
+        .is_ok());
+    assert!(auth(&[], &[]).validate().is_err());
+    assert!(auth(&["a:b"], &[]).validate().is_err());
+    assert!(auth(&["alice", "alice"], &[]).validate().is_err());
+    assert!(auth(&["alice"], &["10.0.0.0/33"]).validate().is_err());
+}
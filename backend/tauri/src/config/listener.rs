@@ -0,0 +1,285 @@
+use crate::utils::secrets;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+};
+
+/// the name of the hidden group and the listener to query the exit ip of the nodes
+pub const EXIT_PROBE: &str = "nyanpasu-exit-probe";
+pub const EXIT_PROBE_PORT: u16 = 33336;
+
+/// the ports of the built-in inbounds of the clash config
+const PORT_KEYS: [&str; 5] = [
+    "port",
+    "socks-port",
+    "mixed-port",
+    "redir-port",
+    "tproxy-port",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenerType {
+    Socks,
+    Http,
+    Mixed,
+    Redir,
+    Tproxy,
+}
+
+impl ListenerType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ListenerType::Socks => "socks",
+            ListenerType::Http => "http",
+            ListenerType::Mixed => "mixed",
+            ListenerType::Redir => "redir",
+            ListenerType::Tproxy => "tproxy",
+        }
+    }
+
+    /// the transparent proxies have no auth
+    fn support_auth(&self) -> bool {
+        !matches!(self, ListenerType::Redir | ListenerType::Tproxy)
+    }
+
+    fn support_udp(&self) -> bool {
+        !matches!(self, ListenerType::Http | ListenerType::Redir)
+    }
+}
+
+/// the password is saved to the keyring, `verge.yaml` keeps the placeholder
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ListenerUser {
+    pub username: String,
+    pub password: String,
+}
+
+/// an additional inbound, the `listeners` of mihomo
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InboundListener {
+    /// generated when the listener is added
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub listener_type: ListenerType,
+    pub port: u16,
+    /// the bind address, all the addresses when empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+    #[serde(default)]
+    pub udp: bool,
+    /// the auth of the listener, no auth when empty
+    #[serde(default)]
+    pub users: Vec<ListenerUser>,
+    /// send all the traffic to this proxy instead of the rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+impl InboundListener {
+    fn password_key(&self, username: &str) -> String {
+        format!("listener::{}::{username}", self.id)
+    }
+
+    /// replace the passwords with the placeholders before saved
+    pub fn protect_passwords(&mut self) {
+        let keys = self
+            .users
+            .iter()
+            .map(|user| self.password_key(&user.username))
+            .collect::<Vec<_>>();
+        for (user, key) in self.users.iter_mut().zip(keys) {
+            user.password = secrets::protect(&key, &user.password);
+        }
+    }
+
+    pub fn resolve_passwords(&mut self) {
+        for user in self.users.iter_mut() {
+            user.password = secrets::resolve(&user.password);
+        }
+    }
+
+    /// check the fields, and the proxy when the proxies are known
+    pub fn validate(&self, proxies: Option<&HashSet<String>>) -> Result<()> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            bail!("the name of the listener is empty");
+        }
+        if self.port == 0 {
+            bail!("the port of the listener `{name}` is invalid");
+        }
+        if let Some(listen) = &self.listen {
+            if listen.parse::<IpAddr>().is_err() {
+                bail!("invalid bind address `{listen}`");
+            }
+        }
+
+        let listener_type = self.listener_type;
+        match listener_type {
+            ListenerType::Redir if !cfg!(any(target_os = "linux", target_os = "macos")) => {
+                bail!("the redir listener is only supported on linux and macos")
+            }
+            ListenerType::Tproxy if !cfg!(target_os = "linux") => {
+                bail!("the tproxy listener is only supported on linux")
+            }
+            _ => {}
+        }
+        if !self.users.is_empty() && !listener_type.support_auth() {
+            bail!(
+                "the {} listener does not support auth",
+                listener_type.as_str()
+            );
+        }
+        if self.udp && !listener_type.support_udp() {
+            bail!(
+                "the {} listener does not support udp",
+                listener_type.as_str()
+            );
+        }
+        if self
+            .users
+            .iter()
+            .any(|user| user.username.trim().is_empty())
+        {
+            bail!("the username of the listener `{name}` is empty");
+        }
+
+        if let (Some(proxy), Some(proxies)) = (self.proxy.as_ref(), proxies) {
+            if !proxies.contains(proxy) {
+                bail!("the proxy `{proxy}` does not exist");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut map = Mapping::new();
+        map.insert("name".into(), self.name.trim().into());
+        map.insert("type".into(), self.listener_type.as_str().into());
+        map.insert("port".into(), self.port.into());
+        if let Some(listen) = &self.listen {
+            map.insert("listen".into(), listen.as_str().into());
+        }
+        if self.listener_type.support_udp() {
+            map.insert("udp".into(), self.udp.into());
+        }
+        if !self.users.is_empty() {
+            let users = self
+                .users
+                .iter()
+                .map(|user| {
+                    let mut map = Mapping::new();
+                    map.insert("username".into(), user.username.as_str().into());
+                    map.insert("password".into(), user.password.as_str().into());
+                    Value::from(map)
+                })
+                .collect::<Vec<_>>();
+            map.insert("users".into(), users.into());
+        }
+        if let Some(proxy) = &self.proxy {
+            map.insert("proxy".into(), proxy.as_str().into());
+        }
+        map.into()
+    }
+}
+
+/// the ports used by the built-in inbounds and the controller of the config
+pub fn reserved_ports(config: &Mapping) -> HashMap<u16, String> {
+    let mut ports = PORT_KEYS
+        .iter()
+        .filter_map(|key| {
+            let port = config.get(*key)?.as_u64()?;
+            Some((port as u16, key.to_string()))
+        })
+        .filter(|(port, _)| *port != 0)
+        .collect::<HashMap<_, _>>();
+
+    let controller = config
+        .get("external-controller")
+        .and_then(Value::as_str)
+        .and_then(|addr| addr.rsplit_once(':'))
+        .and_then(|(_, port)| port.parse::<u16>().ok());
+    if let Some(port) = controller {
+        ports.insert(port, "external-controller".into());
+    }
+    ports
+}
+
+/// the enabled listeners can not share the name and the port, or use the reserved ports
+pub fn check_listener_ports(
+    listeners: &[InboundListener],
+    reserved: &HashMap<u16, String>,
+) -> Result<()> {
+    let mut used = HashMap::new();
+    let mut names = HashSet::new();
+    for listener in listeners.iter().filter(|listener| listener.enable) {
+        if !names.insert(listener.name.trim()) {
+            bail!("the name `{}` is used by another listener", listener.name);
+        }
+        let port = listener.port;
+        if let Some(owner) = reserved.get(&port) {
+            bail!(
+                "the port {port} of `{}` is used by `{owner}`",
+                listener.name
+            );
+        }
+        if let Some(other) = used.insert(port, &listener.name) {
+            bail!(
+                "the listeners `{other}` and `{}` use the same port {port}",
+                listener.name
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_listeners() {
+    let listener = |name: &str, listener_type, port, udp| InboundListener {
+        id: String::new(),
+        name: name.into(),
+        listener_type,
+        port,
+        listen: Some("127.0.0.1".into()),
+        udp,
+        users: vec![],
+        proxy: None,
+        enable: true,
+    };
+    let config =
+        serde_yaml::from_str::<Mapping>("mixed-port: 7890\nexternal-controller: 127.0.0.1:9090\n")
+            .unwrap();
+    let reserved = reserved_ports(&config);
+
+    assert!(listener("a", ListenerType::Socks, 1080, true)
+        .validate(None)
+        .is_ok());
+    assert!(listener("a", ListenerType::Http, 1080, true)
+        .validate(None)
+        .is_err());
+
+    let list = [
+        listener("a", ListenerType::Socks, 1080, true),
+        listener("b", ListenerType::Http, 8080, false),
+    ];
+    assert!(check_listener_ports(&list, &reserved).is_ok());
+
+    let list = [
+        listener("a", ListenerType::Socks, 1080, true),
+        listener("b", ListenerType::Http, 1080, false),
+    ];
+    assert!(check_listener_ports(&list, &reserved).is_err());
+    let list = [listener("a", ListenerType::Mixed, 9090, true)];
+    assert!(check_listener_ports(&list, &reserved).is_err());
+}
@@ -1,15 +1,59 @@
+mod app_rule;
+mod bypass;
+mod capability;
 mod clash;
 mod config;
+mod dns;
 mod draft;
+mod event_hook;
+mod failover;
+mod hosts;
+mod http_policy;
+mod inbound_auth;
+mod listener;
+mod pause;
+mod prf_tls;
 mod prfitem;
+mod profile_hook;
 mod profiles;
+mod profiles_store;
+mod proxy_chain;
+mod remote_script;
+mod rules;
 mod runtime;
+mod sniffer;
+mod store;
+mod throttle;
+mod tray_menu;
+mod tunnel;
 mod verge;
 
+pub use self::app_rule::*;
+pub use self::bypass::*;
+pub use self::capability::*;
 pub use self::clash::*;
 pub use self::config::*;
+pub use self::dns::*;
 pub use self::draft::*;
+pub use self::event_hook::*;
+pub use self::failover::*;
+pub use self::hosts::*;
+pub use self::http_policy::*;
+pub use self::inbound_auth::*;
+pub use self::listener::*;
+pub use self::pause::*;
+pub use self::prf_tls::*;
 pub use self::prfitem::*;
+pub use self::profile_hook::*;
 pub use self::profiles::*;
+pub use self::profiles_store::{ProfileUpdateRecord, ProfilesStore, ScriptReview};
+pub use self::proxy_chain::*;
+pub use self::remote_script::*;
+pub use self::rules::*;
 pub use self::runtime::*;
+pub use self::sniffer::*;
+pub use self::store::*;
+pub use self::throttle::*;
+pub use self::tray_menu::*;
+pub use self::tunnel::*;
 pub use self::verge::*;
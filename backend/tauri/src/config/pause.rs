@@ -0,0 +1,110 @@
+use anyhow::{bail, Result};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+/// pause the proxy in the time window, e.g. the office hours
+/// the paused settings are restored after the window ends
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PauseWindow {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enable: bool,
+    /// `HH:MM`, the window crosses midnight when `end` is earlier than `start`
+    pub start: String,
+    pub end: String,
+    /// 1 is monday, 7 is sunday, none means every day
+    /// the weekday of a window crossing midnight is the day it starts
+    #[serde(default)]
+    pub weekdays: Option<Vec<u8>>,
+    /// disable the system proxy in the window
+    #[serde(default = "default_true")]
+    pub system_proxy: bool,
+    /// disable the tun mode in the window
+    #[serde(default)]
+    pub tun: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()
+}
+
+impl PauseWindow {
+    pub fn validate(&self) -> Result<()> {
+        let (Some(start), Some(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            bail!("invalid time of the pause window `{}`", self.name);
+        };
+        if start == end {
+            bail!("the pause window `{}` is empty", self.name);
+        }
+        if let Some(day) = self
+            .weekdays
+            .iter()
+            .flatten()
+            .find(|day| !(1..=7).contains(*day))
+        {
+            bail!(
+                "invalid weekday `{day}` of the pause window `{}`",
+                self.name
+            );
+        }
+        if !self.system_proxy && !self.tun {
+            bail!("the pause window `{}` pauses nothing", self.name);
+        }
+        Ok(())
+    }
+
+    /// whether the time of the weekday is in the window
+    pub fn contains(&self, time: NaiveTime, weekday: u8) -> bool {
+        let (Some(start), Some(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        let on = |day: u8| {
+            self.weekdays
+                .as_ref()
+                .map_or(true, |days| days.contains(&day))
+        };
+
+        if start < end {
+            on(weekday) && start <= time && time < end
+        } else {
+            // 跨过零点，零点之后属于前一天的窗口
+            let yesterday = if weekday == 1 { 7 } else { weekday - 1 };
+            (on(weekday) && start <= time) || (on(yesterday) && time < end)
+        }
+    }
+}
+
+#[test]
+fn test_pause_window() {
+    let window = |start: &str, end: &str| PauseWindow {
+        id: "p".into(),
+        name: "office".into(),
+        enable: true,
+        start: start.into(),
+        end: end.into(),
+        weekdays: Some(vec![1, 2, 3, 4, 5]),
+        system_proxy: true,
+        tun: false,
+    };
+    let time = |time: &str| NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+
+    let office = window("09:00", "18:00");
+    assert!(office.validate().is_ok());
+    assert!(office.contains(time("09:00"), 1));
+    assert!(!office.contains(time("18:00"), 1));
+    assert!(!office.contains(time("10:00"), 6));
+
+    // friday night to saturday morning
+    let night = window("22:00", "06:00");
+    assert!(night.contains(time("23:00"), 5));
+    assert!(night.contains(time("05:00"), 6));
+    assert!(!night.contains(time("05:00"), 1));
+
+    assert!(window("09:00", "09:00").validate().is_err());
+    assert!(window("9am", "18:00").validate().is_err());
+}
@@ -0,0 +1,237 @@
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::ClientBuilder;
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+    Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{sync::Arc, time::SystemTime};
+
+const PIN_PREFIX: &str = "sha256/";
+
+/// the tls options of fetching the `remote` profile
+/// for the private servers with the self-signed certificates
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PrfTls {
+    /// the extra trusted ca certificates in pem, can be a bundle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca: Option<String>,
+
+    /// the sha256 of the server's public key (spki) in base64, e.g. `sha256/AAAA...=`
+    /// checked in addition to the certificate verification
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pins: Option<Vec<String>>,
+
+    /// skip the certificate verification, only the pins are checked if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insecure: Option<bool>,
+}
+
+impl PrfTls {
+    pub fn validate(&self) -> Result<()> {
+        root_store(self.ca())?;
+        for pin in self.pins.iter().flatten() {
+            decode_pin(pin)?;
+        }
+        Ok(())
+    }
+
+    /// the pins are checked in the handshake, nothing is sent to a server not matching them
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder> {
+        let insecure = self.insecure.unwrap_or(false);
+        let pins = self
+            .pins
+            .iter()
+            .flatten()
+            .map(|pin| decode_pin(pin))
+            .collect::<Result<Vec<_>>>()?;
+        if self.ca().is_none() && !insecure && pins.is_empty() {
+            return Ok(builder);
+        }
+
+        if insecure {
+            tracing::warn!("the certificate verification of the profile is skipped");
+        }
+        let verifier = PinnedVerifier {
+            webpki: match insecure {
+                true => None,
+                false => Some(WebPkiVerifier::new(root_store(self.ca())?, None)),
+            },
+            pins,
+        };
+        let mut config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        // reqwest 不会给预先配置的 tls 设置 alpn
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(builder.use_preconfigured_tls(config))
+    }
+
+    fn ca(&self) -> Option<&str> {
+        self.ca.as_deref().filter(|ca| !ca.trim().is_empty())
+    }
+}
+
+/// verify the certificate as usual unless insecure, then match the public key with the pins
+struct PinnedVerifier {
+    webpki: Option<WebPkiVerifier>,
+    pins: Vec<Vec<u8>>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        if let Some(webpki) = &self.webpki {
+            webpki.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                scts,
+                ocsp_response,
+                now,
+            )?;
+        }
+        check_pins(&self.pins, &end_entity.0)
+            .map_err(|err| rustls::Error::General(err.to_string()))?;
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+fn check_pins(pins: &[Vec<u8>], cert: &[u8]) -> Result<()> {
+    if pins.is_empty() {
+        return Ok(());
+    }
+    let spki = spki_of(cert).ok_or(anyhow!("failed to parse the server certificate"))?;
+    let hash = Sha256::digest(spki);
+    if pins.iter().any(|pin| pin.as_slice() == hash.as_slice()) {
+        return Ok(());
+    }
+    bail!(
+        "the server public key {PIN_PREFIX}{} matches none of the pins",
+        STANDARD.encode(hash)
+    )
+}
+
+/// the bundled roots of reqwest, plus the extra ca
+fn root_store(ca: Option<&str>) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    if let Some(ca) = ca {
+        for cert in parse_certificates(ca)? {
+            roots
+                .add(&cert)
+                .map_err(|err| anyhow!("invalid ca certificate: {err}"))?;
+        }
+    }
+    Ok(roots)
+}
+
+fn parse_certificates(pem: &str) -> Result<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut pem.as_bytes())
+        .map_err(|err| anyhow!("invalid ca certificate: {err}"))?;
+    if certs.is_empty() {
+        bail!("no certificate found in the ca");
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn decode_pin(pin: &str) -> Result<Vec<u8>> {
+    let pin = pin.trim();
+    let hash = STANDARD
+        .decode(pin.strip_prefix(PIN_PREFIX).unwrap_or(pin))
+        .map_err(|_| anyhow!("invalid pin `{pin}`"))?;
+    if hash.len() != 32 {
+        bail!("the pin `{pin}` is not a sha256 hash");
+    }
+    Ok(hash)
+}
+
+/// (tag, header length, content length)
+fn der_header(data: &[u8]) -> Option<(u8, usize, usize)> {
+    let tag = *data.first()?;
+    let first = *data.get(1)? as usize;
+    if first < 0x80 {
+        return Some((tag, 2, first));
+    }
+    let n = first & 0x7f;
+    if n == 0 || n > 4 {
+        return None;
+    }
+    let len = data
+        .get(2..2 + n)?
+        .iter()
+        .fold(0usize, |len, byte| (len << 8) | *byte as usize);
+    Some((tag, 2 + n, len))
+}
+
+/// the whole der of the `subjectPublicKeyInfo` in the certificate
+fn spki_of(cert: &[u8]) -> Option<&[u8]> {
+    let (_, header, _) = der_header(cert)?;
+    let tbs = cert.get(header..)?;
+    let (_, header, len) = der_header(tbs)?;
+    let mut rest = tbs.get(header..header + len)?;
+
+    // serialNumber, signature, issuer, validity, subject 之后就是公钥
+    let mut index = 0;
+    loop {
+        let (tag, header, len) = der_header(rest)?;
+        let end = header + len;
+        // version 是可选的 [0]
+        if tag == 0xa0 && index == 0 {
+            rest = rest.get(end..)?;
+            continue;
+        }
+        if index == 5 {
+            return rest.get(..end);
+        }
+        rest = rest.get(end..)?;
+        index += 1;
+    }
+}
+
+#[test]
+fn test_spki_pin() {
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut data = vec![tag, content.len() as u8];
+        data.extend_from_slice(content);
+        data
+    }
+
+    let spki = tlv(0x30, &tlv(0x03, &[0x00, 0x04, 0x01, 0x02]));
+    let tbs = [
+        tlv(0xa0, &tlv(0x02, &[0x02])),
+        tlv(0x02, &[0x01]),
+        tlv(0x30, &[]),
+        tlv(0x30, &[]),
+        tlv(0x30, &[]),
+        tlv(0x30, &[]),
+        spki.clone(),
+    ]
+    .concat();
+    let cert = tlv(0x30, &[tlv(0x30, &tbs), tlv(0x30, &[])].concat());
+    assert_eq!(spki_of(&cert), Some(spki.as_slice()));
+
+    let pin = format!("{PIN_PREFIX}{}", STANDARD.encode(Sha256::digest(&spki)));
+    assert_eq!(decode_pin(&pin).unwrap(), Sha256::digest(&spki).to_vec());
+    assert!(check_pins(&[decode_pin(&pin).unwrap()], &cert).is_ok());
+    assert!(check_pins(&[vec![0; 32]], &cert).is_err());
+    assert!(check_pins(&[], &cert).is_ok());
+    assert!(decode_pin("sha256/AAAA").is_err());
+    assert!(parse_certificates("not a certificate").is_err());
+}
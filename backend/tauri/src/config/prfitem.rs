@@ -4,9 +4,9 @@ use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Mapping;
 use std::fs;
-use sysproxy::Sysproxy;
 
-use super::Config;
+use super::{ClashCore, DnsOverride, HttpProxy, HttpPurpose, PrfTls, ProfileHook};
+use crate::core::http;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PrfItem {
@@ -84,6 +84,24 @@ pub struct PrfOption {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub update_interval: Option<u64>,
+
+    /// the dns settings applied when this profile is current
+    /// take precedence over the global one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_override: Option<DnsOverride>,
+
+    /// the core used when this profile is current, e.g. a config only for clash-rs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub core: Option<ClashCore>,
+
+    /// for `remote` profile
+    /// custom ca, pins or skipping the verification
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<PrfTls>,
+
+    /// run after the timer updates the profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Vec<ProfileHook>>,
 }
 
 impl PrfOption {
@@ -94,6 +112,10 @@ impl PrfOption {
                 a.with_proxy = b.with_proxy.or(a.with_proxy);
                 a.self_proxy = b.self_proxy.or(a.self_proxy);
                 a.update_interval = b.update_interval.or(a.update_interval);
+                a.dns_override = b.dns_override.or(a.dns_override);
+                a.core = b.core.or(a.core);
+                a.tls = b.tls.or(a.tls);
+                a.hooks = b.hooks.or(a.hooks);
                 Some(a)
             }
             t @ _ => t.0.or(t.1),
@@ -120,6 +142,11 @@ impl Default for PrfItem {
 }
 
 impl PrfItem {
+    /// 只有订阅和本地配置能作为当前配置，merge 和 script 不行
+    pub fn is_profile(&self) -> bool {
+        matches!(self.itype.as_deref(), Some("remote" | "local"))
+    }
+
     /// From partial item
     /// must contain `itype`
     pub async fn from(item: PrfItem, file_data: Option<String>) -> Result<PrfItem> {
@@ -150,7 +177,13 @@ impl PrfItem {
             "script" => {
                 let name = item.name.unwrap_or("Script".into());
                 let desc = item.desc.unwrap_or("".into());
-                PrfItem::from_script(name, desc)
+                let mut script = PrfItem::from_script(name, desc)?;
+                // 远程脚本先用空模板，拉取的内容确认后才会运行
+                script.url = item.url.filter(|url| !url.trim().is_empty());
+                if script.url.is_some() {
+                    script.option = item.option;
+                }
+                Ok(script)
             }
             typ @ _ => bail!("invalid profile item type \"{typ}\""),
         }
@@ -185,57 +218,7 @@ impl PrfItem {
         desc: Option<String>,
         option: Option<PrfOption>,
     ) -> Result<PrfItem> {
-        let opt_ref = option.as_ref();
-        let with_proxy = opt_ref.map_or(false, |o| o.with_proxy.unwrap_or(false));
-        let self_proxy = opt_ref.map_or(false, |o| o.self_proxy.unwrap_or(false));
-        let user_agent = opt_ref.map_or(None, |o| o.user_agent.clone());
-
-        let mut builder = reqwest::ClientBuilder::new().use_rustls_tls().no_proxy();
-
-        // 使用软件自己的代理
-        if self_proxy {
-            let port = Config::verge()
-                .latest()
-                .verge_mixed_port
-                .unwrap_or(Config::clash().data().get_mixed_port());
-
-            let proxy_scheme = format!("http://127.0.0.1:{port}");
-
-            if let Ok(proxy) = reqwest::Proxy::http(&proxy_scheme) {
-                builder = builder.proxy(proxy);
-            }
-            if let Ok(proxy) = reqwest::Proxy::https(&proxy_scheme) {
-                builder = builder.proxy(proxy);
-            }
-            if let Ok(proxy) = reqwest::Proxy::all(&proxy_scheme) {
-                builder = builder.proxy(proxy);
-            }
-        }
-        // 使用系统代理
-        else if with_proxy {
-            match Sysproxy::get_system_proxy() {
-                Ok(p @ Sysproxy { enable: true, .. }) => {
-                    let proxy_scheme = format!("http://{}:{}", p.host, p.port);
-
-                    if let Ok(proxy) = reqwest::Proxy::http(&proxy_scheme) {
-                        builder = builder.proxy(proxy);
-                    }
-                    if let Ok(proxy) = reqwest::Proxy::https(&proxy_scheme) {
-                        builder = builder.proxy(proxy);
-                    }
-                    if let Ok(proxy) = reqwest::Proxy::all(&proxy_scheme) {
-                        builder = builder.proxy(proxy);
-                    }
-                }
-                _ => {}
-            };
-        }
-
-        let version = unsafe { dirs::APP_VERSION };
-        let version = format!("clash-verge/v{version}");
-        builder = builder.user_agent(user_agent.unwrap_or(version));
-
-        let resp = builder.build()?.get(url).send().await?;
+        let resp = PrfItem::fetch(url, option.as_ref()).await?;
 
         let status_code = resp.status();
         if !StatusCode::is_success(&status_code) {
@@ -311,6 +294,33 @@ impl PrfItem {
         })
     }
 
+    /// request the url with the proxy, user agent and tls options of the item
+    pub async fn fetch(url: &str, option: Option<&PrfOption>) -> Result<reqwest::Response> {
+        let with_proxy = option.map_or(false, |o| o.with_proxy.unwrap_or(false));
+        let self_proxy = option.map_or(false, |o| o.self_proxy.unwrap_or(false));
+        let user_agent = option.map_or(None, |o| o.user_agent.clone());
+        let tls = option.and_then(|o| o.tls.clone()).unwrap_or_default();
+
+        let mut builder = http::builder(HttpPurpose::Profiles)?;
+        // 使用软件自己的代理或系统代理，优先于全局的策略，都没开时按全局的策略
+        let proxy = match (self_proxy, with_proxy) {
+            (true, _) => Some(HttpProxy::Core),
+            (false, true) => Some(HttpProxy::System),
+            _ => None,
+        };
+        if let Some(proxy) = proxy {
+            builder = http::apply_proxy(builder, &proxy)?;
+        }
+
+        let version = unsafe { dirs::APP_VERSION };
+        let version = format!("clash-verge/v{version}");
+        builder = builder.user_agent(user_agent.unwrap_or(version));
+        builder = tls.apply(builder)?;
+
+        let client = builder.build()?;
+        http::send_with_retry(http::retries(HttpPurpose::Profiles), || client.get(url)).await
+    }
+
     /// ## Merge type (enhance)
     /// create the enhanced item by using `merge` rule
     pub fn from_merge(name: String, desc: String) -> Result<PrfItem> {
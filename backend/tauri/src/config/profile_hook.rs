@@ -0,0 +1,38 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// run after the profile is updated by the timer, in order
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProfileHook {
+    Notify,
+    /// run the merge and the script items against the updated profile, in order
+    /// the output is not applied, a script can throw to mark the hook failed, e.g. no nodes left,
+    /// the update is already applied by then
+    RunChain {
+        chain: Vec<String>,
+    },
+    RestartCore,
+    /// the checks of the connectivity doctor, after the new config is applied
+    ConnectivityCheck,
+}
+
+impl ProfileHook {
+    pub fn validate(hooks: &[ProfileHook]) -> Result<()> {
+        for hook in hooks {
+            if let ProfileHook::RunChain { chain } = hook {
+                if chain.is_empty() {
+                    bail!("the script chain of the hook is empty");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the result of a hook, recorded in the update history
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfileHookResult {
+    pub hook: ProfileHook,
+    pub error: Option<String>,
+}
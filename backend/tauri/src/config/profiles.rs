@@ -1,9 +1,79 @@
-use super::prfitem::PrfItem;
-use crate::utils::{dirs, help};
+use super::{
+    prfitem::{PrfItem, PrfOption, PrfSelected},
+    profiles_store::{ProfileUpdateRecord, ProfilesStore},
+    ClashCore, DnsOverride, ProfileHookResult, RemoteScript,
+};
+use crate::utils::{dirs, help, secrets};
 use anyhow::{bail, Context, Result};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Mapping;
-use std::{fs, io::Write};
+use std::{fs, io::Write, path::PathBuf};
+use url::Url;
+
+/// how to import a subscription url that already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStrategy {
+    /// keep the existing profile, merge the new options and update it
+    Merge,
+    /// overwrite the name, the options and the content of the existing profile
+    Replace,
+    /// import it as another profile anyway
+    Copy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Imported {
+        uid: String,
+    },
+    /// nothing is changed, import again with a strategy
+    Duplicate {
+        uid: String,
+        name: Option<String>,
+    },
+    Merged {
+        uid: String,
+    },
+    Replaced {
+        uid: String,
+    },
+}
+
+impl ImportOutcome {
+    pub fn uid(&self) -> &str {
+        match self {
+            ImportOutcome::Imported { uid }
+            | ImportOutcome::Duplicate { uid, .. }
+            | ImportOutcome::Merged { uid }
+            | ImportOutcome::Replaced { uid } => uid,
+        }
+    }
+}
+
+/// the url compared by the duplicate detection
+/// the fragment, the trailing slash and the order of the query are ignored
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    parsed.set_fragment(None);
+
+    let mut pairs = parsed.query_pairs().into_owned().collect::<Vec<_>>();
+    pairs.sort();
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    let path = parsed.path().trim_end_matches('/').to_string();
+    parsed.set_path(&path);
+    parsed.into()
+}
 
 /// Define the `profiles.yaml` schema
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
@@ -31,26 +101,52 @@ macro_rules! patch {
 
 impl IProfiles {
     pub fn new() -> Self {
-        match dirs::profiles_path().and_then(|path| help::read_yaml::<Self>(&path)) {
-            Ok(mut profiles) => {
-                if profiles.items.is_none() {
-                    profiles.items = Some(vec![]);
+        let stored = ProfilesStore::load().unwrap_or_else(|err| {
+            tracing::error!("failed to load the profiles from the storage: {err}");
+            None
+        });
+
+        match stored {
+            Some(profiles) => profiles.normalize(),
+            // 第一次启动时从 profiles.yaml 迁移
+            None => match Self::from_file() {
+                Ok(profiles) => {
+                    tracing::info!("move the profiles into the storage");
+                    crate::log_err!(profiles.save_file());
+                    profiles
                 }
-                // compatible with the old old old version
-                profiles.items.as_mut().map(|items| {
-                    for item in items.iter_mut() {
-                        if item.uid.is_none() {
-                            item.uid = Some(help::get_uid("d"));
-                        }
-                    }
-                });
-                profiles
-            }
-            Err(err) => {
-                log::error!(target: "app", "{err}");
-                Self::template()
-            }
+                Err(err) => {
+                    tracing::error!("{err}");
+                    Self::template()
+                }
+            },
+        }
+    }
+
+    /// read the exported `profiles.yaml`, e.g. restored from a backup or pulled by the sync
+    pub fn from_file() -> Result<Self> {
+        Self::from_path(&dirs::profiles_path()?)
+    }
+
+    pub fn from_path(path: &PathBuf) -> Result<Self> {
+        let profiles = help::read_yaml::<Self>(path)?;
+        Ok(profiles.normalize())
+    }
+
+    fn normalize(mut self) -> Self {
+        if self.items.is_none() {
+            self.items = Some(vec![]);
         }
+        // compatible with the old old old version
+        self.items.as_mut().map(|items| {
+            for item in items.iter_mut() {
+                if item.uid.is_none() {
+                    item.uid = Some(help::get_uid("d"));
+                }
+                item.url = item.url.as_deref().map(secrets::resolve);
+            }
+        });
+        self
     }
 
     pub fn template() -> Self {
@@ -65,14 +161,44 @@ impl IProfiles {
         }
     }
 
+    /// the subscription urls are saved to the keyring, they may contain the tokens
+    /// the storage is the source of truth, `profiles.yaml` is exported for the backups
     pub fn save_file(&self) -> Result<()> {
+        // 避免并发保存时导出的文件交错
+        static SAVE_LOCK: Mutex<()> = Mutex::new(());
+        let _guard = SAVE_LOCK.lock();
+
+        let mut profiles = self.clone();
+        if let Some(items) = profiles.items.as_mut() {
+            for item in items.iter_mut() {
+                if let (Some(uid), Some(url)) = (&item.uid, &item.url) {
+                    item.url = Some(secrets::protect(&Self::url_secret_key(uid), url));
+                }
+            }
+        }
+
+        ProfilesStore::save(&profiles)?;
         help::save_yaml(
             &dirs::profiles_path()?,
-            self,
+            &profiles,
             Some("# Profiles Config for Clash Nyanpasu"),
         )
     }
 
+    /// the update history of the remote item, the latest first
+    pub fn update_history(uid: &str) -> Result<Vec<ProfileUpdateRecord>> {
+        ProfilesStore::history(uid)
+    }
+
+    /// attach the results of the hooks to the latest update record
+    pub fn record_hooks(uid: &str, hooks: Vec<ProfileHookResult>) -> Result<()> {
+        ProfilesStore::record_hooks(uid, hooks)
+    }
+
+    pub fn url_secret_key(uid: &str) -> String {
+        format!("profiles/{uid}/url")
+    }
+
     /// 只修改current，valid和chain
     pub fn patch_config(&mut self, patch: IProfiles) -> Result<()> {
         if self.items.is_none() {
@@ -123,6 +249,15 @@ impl IProfiles {
         bail!("failed to get the profile item \"uid:{uid}\"");
     }
 
+    /// the remote item with the same normalized url
+    pub fn find_by_url(&self, url: &str) -> Option<&PrfItem> {
+        let url = normalize_url(url);
+        self.items.iter().flatten().find(|item| {
+            item.itype.as_deref() == Some("remote")
+                && item.url.as_deref().map(normalize_url).as_ref() == Some(&url)
+        })
+    }
+
     /// append new item
     /// if the file_data is some
     /// then should save the data to file
@@ -204,6 +339,94 @@ impl IProfiles {
         bail!("failed to find the profile item \"uid:{uid}\"")
     }
 
+    /// record the selected proxy of a group
+    pub fn patch_selected(&mut self, uid: &str, group: &str, now: &str) -> Result<()> {
+        let item = self
+            .items
+            .as_mut()
+            .and_then(|items| items.iter_mut().find(|e| e.uid.as_deref() == Some(uid)));
+
+        let item = match item {
+            Some(item) => item,
+            None => bail!("failed to find the profile item \"uid:{uid}\""),
+        };
+
+        let selected = item.selected.get_or_insert_with(Vec::new);
+        match selected
+            .iter_mut()
+            .find(|e| e.name.as_deref() == Some(group))
+        {
+            Some(each) => each.now = Some(now.into()),
+            None => selected.push(PrfSelected {
+                name: Some(group.into()),
+                now: Some(now.into()),
+            }),
+        }
+
+        self.save_file()
+    }
+
+    /// forget the selected proxies of the groups
+    pub fn clear_selected(&mut self, uid: &str) -> Result<()> {
+        let item = self
+            .items
+            .as_mut()
+            .and_then(|items| items.iter_mut().find(|e| e.uid.as_deref() == Some(uid)));
+
+        match item {
+            Some(item) => item.selected = None,
+            None => bail!("failed to find the profile item \"uid:{uid}\""),
+        }
+        self.save_file()
+    }
+
+    /// set or clear the dns override of the item, saved by the caller
+    pub fn set_dns_override(&mut self, uid: &str, dns: Option<DnsOverride>) -> Result<()> {
+        let item = self
+            .items
+            .as_mut()
+            .and_then(|items| items.iter_mut().find(|e| e.uid.as_deref() == Some(uid)));
+
+        let item = match item {
+            Some(item) => item,
+            None => bail!("failed to find the profile item \"uid:{uid}\""),
+        };
+
+        let option = item.option.get_or_insert_with(PrfOption::default);
+        option.dns_override = dns;
+        Ok(())
+    }
+
+    pub fn set_profile_core(&mut self, uid: &str, core: Option<ClashCore>) -> Result<()> {
+        let item = self
+            .items
+            .as_mut()
+            .and_then(|items| items.iter_mut().find(|e| e.uid.as_deref() == Some(uid)));
+
+        let item = match item {
+            Some(item) => item,
+            None => bail!("failed to find the profile item \"uid:{uid}\""),
+        };
+
+        let option = item.option.get_or_insert_with(PrfOption::default);
+        option.core = core;
+        Ok(())
+    }
+
+    /// the core bound to the current profile
+    pub fn current_core(&self) -> Option<ClashCore> {
+        let current = self.current.as_ref()?;
+        let item = self.get_item(current).ok()?;
+        item.option.as_ref()?.core.clone()
+    }
+
+    /// the dns override of the current profile
+    pub fn current_dns_override(&self) -> Option<DnsOverride> {
+        let current = self.current.as_ref()?;
+        let item = self.get_item(current).ok()?;
+        item.option.as_ref()?.dns_override.clone()
+    }
+
     /// be used to update the remote item
     /// only patch `updated` `extra` `file_data`
     pub fn update_item(&mut self, uid: String, mut item: PrfItem) -> Result<()> {
@@ -213,6 +436,12 @@ impl IProfiles {
 
         // find the item
         let _ = self.get_item(&uid)?;
+        let record = ProfileUpdateRecord {
+            time: chrono::Local::now().timestamp(),
+            size: item.file_data.as_ref().map_or(0, |data| data.len()),
+            extra: item.extra,
+            hooks: vec![],
+        };
 
         if let Some(items) = self.items.as_mut() {
             let some_uid = Some(uid.clone());
@@ -245,7 +474,9 @@ impl IProfiles {
             }
         }
 
-        self.save_file()
+        self.save_file()?;
+        crate::log_err!(ProfilesStore::record_update(&uid, &record));
+        Ok(())
     }
 
     /// delete item
@@ -266,6 +497,8 @@ impl IProfiles {
         }
 
         if let Some(index) = index {
+            // the url secret is kept for undoing, removed with the snapshot
+            RemoteScript::remove_pending(&uid);
             items.remove(index).file.map(|file| {
                 let _ = dirs::app_profiles_dir().map(|path| {
                     let path = path.join(file);
@@ -289,6 +522,15 @@ impl IProfiles {
         Ok(current == uid)
     }
 
+    /// the content of the item
+    pub fn item_mapping(&self, uid: &String) -> Result<Mapping> {
+        let file = match self.get_item(uid)?.file.as_ref() {
+            Some(file) => dirs::app_profiles_dir()?.join(file),
+            None => bail!("failed to get the file field"),
+        };
+        Ok(help::read_merge_mapping(&file)?)
+    }
+
     /// 获取current指向的配置内容
     pub fn current_mapping(&self) -> Result<Mapping> {
         match (self.current.as_ref(), self.items.as_ref()) {
@@ -306,3 +548,16 @@ impl IProfiles {
         }
     }
 }
+
+#[test]
+fn test_normalize_url() {
+    assert_eq!(
+        normalize_url(" HTTPS://Example.com:443/sub/?token=1&flag=clash#x "),
+        normalize_url("https://example.com/sub?flag=clash&token=1")
+    );
+    assert_ne!(
+        normalize_url("https://example.com/sub?token=1"),
+        normalize_url("https://example.com/sub?token=2")
+    );
+    assert_eq!(normalize_url("not a url"), "not a url");
+}
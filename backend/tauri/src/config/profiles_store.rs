@@ -0,0 +1,296 @@
+//! the profiles are kept in the embedded sqlite database `profiles.db`
+//! the items, the update history and the script reviews are rows written in the transactions,
+//! so that the concurrent updates can't leave a half-written file
+//! `profiles.yaml` is still exported after saving, the backups and the sync use it
+use super::{IProfiles, PrfExtra, PrfItem, ProfileHookResult};
+use crate::utils::dirs;
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// keep the latest records of each item
+const HISTORY_LEN: usize = 50;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    current TEXT,
+    chain TEXT,
+    valid TEXT
+);
+CREATE TABLE IF NOT EXISTS items (
+    uid TEXT PRIMARY KEY,
+    position INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS items_position ON items (position);
+CREATE TABLE IF NOT EXISTS history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    uid TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS history_uid_time ON history (uid, time);
+CREATE TABLE IF NOT EXISTS scripts (
+    uid TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+";
+
+/// a successful update of the remote profile
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfileUpdateRecord {
+    /// unix timestamp
+    pub time: i64,
+    /// the size of the downloaded file in bytes
+    pub size: usize,
+    pub extra: Option<PrfExtra>,
+    /// the results of the hooks run after the update
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<ProfileHookResult>,
+}
+
+/// the review state of the remote script
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ScriptReview {
+    /// the sha256 of the script allowed to run
+    pub approved: Option<String>,
+    /// the sha256 of the downloaded script waiting for review
+    pub pending: Option<String>,
+    /// the sha256 of the last rejected script, not quarantined again
+    pub rejected: Option<String>,
+    /// unix timestamp of the last download
+    pub fetched: Option<i64>,
+}
+
+fn open(conn: Connection) -> Result<Connection> {
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+/// one connection for the app, the writes are serialized by the lock
+fn connection() -> Result<&'static Mutex<Connection>> {
+    static CONNECTION: OnceCell<Mutex<Connection>> = OnceCell::new();
+    CONNECTION.get_or_try_init(|| {
+        let conn = Connection::open(dirs::profiles_db_path()?)?;
+        Ok(Mutex::new(open(conn)?))
+    })
+}
+
+fn to_json<T: Serialize>(value: &Option<T>) -> Result<Option<String>> {
+    Ok(value.as_ref().map(serde_json::to_string).transpose()?)
+}
+
+fn from_json<T: DeserializeOwned>(value: Option<String>) -> Result<Option<T>> {
+    Ok(value.as_deref().map(serde_json::from_str).transpose()?)
+}
+
+pub struct ProfilesStore;
+
+impl ProfilesStore {
+    /// `None` if not migrated from `profiles.yaml` yet
+    pub fn load() -> Result<Option<IProfiles>> {
+        load(&connection()?.lock())
+    }
+
+    /// save all in a transaction, the removed items are deleted with their history
+    pub fn save(profiles: &IProfiles) -> Result<()> {
+        save(&mut connection()?.lock(), profiles)
+    }
+
+    pub fn record_update(uid: &str, record: &ProfileUpdateRecord) -> Result<()> {
+        record_update(&mut connection()?.lock(), uid, record)
+    }
+
+    /// attach the results of the hooks to the latest record
+    pub fn record_hooks(uid: &str, hooks: Vec<ProfileHookResult>) -> Result<()> {
+        let conn = connection()?.lock();
+        let latest = conn
+            .query_row(
+                "SELECT id, data FROM history WHERE uid = ?1 ORDER BY time DESC, id DESC LIMIT 1",
+                [uid],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        if let Some((id, data)) = latest {
+            let mut record = serde_json::from_str::<ProfileUpdateRecord>(&data)?;
+            record.hooks = hooks;
+            conn.execute(
+                "UPDATE history SET data = ?1 WHERE id = ?2",
+                params![serde_json::to_string(&record)?, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn script_review(uid: &str) -> Result<ScriptReview> {
+        let conn = connection()?.lock();
+        let data = conn
+            .query_row("SELECT data FROM scripts WHERE uid = ?1", [uid], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()?;
+        Ok(from_json(data)?.unwrap_or_default())
+    }
+
+    pub fn set_script_review(uid: &str, review: &ScriptReview) -> Result<()> {
+        let conn = connection()?.lock();
+        conn.execute(
+            "INSERT OR REPLACE INTO scripts (uid, data) VALUES (?1, ?2)",
+            params![uid, serde_json::to_string(review)?],
+        )?;
+        Ok(())
+    }
+
+    /// the latest first
+    pub fn history(uid: &str) -> Result<Vec<ProfileUpdateRecord>> {
+        history(&connection()?.lock(), uid)
+    }
+}
+
+fn load(conn: &Connection) -> Result<Option<IProfiles>> {
+    let meta = conn
+        .query_row(
+            "SELECT current, chain, valid FROM meta WHERE id = 0",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((current, chain, valid)) = meta else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare("SELECT data FROM items ORDER BY position")?;
+    let items = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|data| Ok(serde_json::from_str::<PrfItem>(&data?)?))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(IProfiles {
+        current,
+        chain: from_json(chain)?,
+        valid: from_json(valid)?,
+        items: Some(items),
+    }))
+}
+
+fn save(conn: &mut Connection, profiles: &IProfiles) -> Result<()> {
+    let items = profiles.items.as_deref().unwrap_or_default();
+    let uids = items
+        .iter()
+        .filter_map(|item| item.uid.clone())
+        .collect::<Vec<_>>();
+
+    let tx = conn.transaction()?;
+    let stale = {
+        let mut stmt = tx.prepare("SELECT uid FROM items")?;
+        let saved = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        saved
+            .into_iter()
+            .filter(|uid| !uids.contains(uid))
+            .collect::<Vec<_>>()
+    };
+    for uid in stale.iter() {
+        for table in ["items", "history", "scripts"] {
+            tx.execute(&format!("DELETE FROM {table} WHERE uid = ?1"), [uid])?;
+        }
+    }
+    for (position, item) in items.iter().enumerate() {
+        if let Some(uid) = item.uid.as_ref() {
+            tx.execute(
+                "INSERT OR REPLACE INTO items (uid, position, data) VALUES (?1, ?2, ?3)",
+                params![uid, position as i64, serde_json::to_string(item)?],
+            )?;
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (id, current, chain, valid) VALUES (0, ?1, ?2, ?3)",
+        params![
+            profiles.current,
+            to_json(&profiles.chain)?,
+            to_json(&profiles.valid)?
+        ],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn record_update(conn: &mut Connection, uid: &str, record: &ProfileUpdateRecord) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO history (uid, time, data) VALUES (?1, ?2, ?3)",
+        params![uid, record.time, serde_json::to_string(record)?],
+    )?;
+    tx.execute(
+        "DELETE FROM history WHERE uid = ?1 AND id NOT IN (
+            SELECT id FROM history WHERE uid = ?1 ORDER BY time DESC, id DESC LIMIT ?2
+        )",
+        params![uid, HISTORY_LEN as i64],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn history(conn: &Connection, uid: &str) -> Result<Vec<ProfileUpdateRecord>> {
+    let mut stmt =
+        conn.prepare("SELECT data FROM history WHERE uid = ?1 ORDER BY time DESC, id DESC")?;
+    let records = stmt
+        .query_map([uid], |row| row.get::<_, String>(0))?
+        .map(|data| Ok(serde_json::from_str(&data?)?))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(records)
+}
+
+#[test]
+fn test_profiles_store() {
+    let mut conn = open(Connection::open_in_memory().unwrap()).unwrap();
+    assert!(load(&conn).unwrap().is_none());
+
+    let item = |uid: &str| PrfItem {
+        uid: Some(uid.into()),
+        ..PrfItem::default()
+    };
+    let mut profiles = IProfiles {
+        current: Some("b".into()),
+        items: Some(vec![item("b"), item("a")]),
+        ..IProfiles::default()
+    };
+    save(&mut conn, &profiles).unwrap();
+    for time in 0..(HISTORY_LEN as i64 + 2) {
+        let record = ProfileUpdateRecord {
+            time,
+            size: 0,
+            extra: None,
+            hooks: vec![],
+        };
+        record_update(&mut conn, "a", &record).unwrap();
+    }
+    let records = history(&conn, "a").unwrap();
+    assert_eq!(records.len(), HISTORY_LEN);
+    assert_eq!(records[0].time, HISTORY_LEN as i64 + 1);
+
+    let loaded = load(&conn).unwrap().unwrap();
+    assert_eq!(loaded.current.as_deref(), Some("b"));
+    let uids = loaded
+        .items
+        .unwrap()
+        .into_iter()
+        .filter_map(|item| item.uid)
+        .collect::<Vec<_>>();
+    assert_eq!(uids, ["b", "a"]);
+
+    // 删掉的订阅连同历史一起删除
+    profiles.items = Some(vec![item("b")]);
+    save(&mut conn, &profiles).unwrap();
+    assert!(history(&conn, "a").unwrap().is_empty());
+}
@@ -0,0 +1,233 @@
+use super::{ClashCore, Config, CoreFeature};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainMode {
+    /// a `relay` group, supported by all the cores
+    #[default]
+    Relay,
+    /// the copies of the nodes with `dialer-proxy`, only meta
+    DialerProxy,
+}
+
+/// the traffic goes through the proxies in order, the last one is the exit
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProxyChain {
+    /// the name of the generated group or node
+    pub name: String,
+    pub proxies: Vec<String>,
+    #[serde(default)]
+    pub mode: ChainMode,
+    /// also put the chain into this selector group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attach_to: Option<String>,
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+/// a node that can be a hop of the chains
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainCandidate {
+    pub name: String,
+    /// e.g. `ss` `vmess` `select`
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub is_group: bool,
+    pub udp: bool,
+}
+
+/// the proxies and the groups of the config
+#[derive(Debug, Default)]
+pub struct ChainNodes {
+    pub proxies: Vec<ChainCandidate>,
+    pub groups: Vec<ChainCandidate>,
+}
+
+impl ChainNodes {
+    /// the names in `exclude` are skipped, e.g. the outputs of the chains themselves
+    pub fn from_config(config: &Mapping, exclude: &HashSet<String>) -> Self {
+        let list = |key: &str, is_group: bool| {
+            config
+                .get(key)
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .filter_map(|item| {
+                    let name = item.get("name")?.as_str()?.to_string();
+                    if exclude.contains(&name) {
+                        return None;
+                    }
+                    let node_type = item.get("type").and_then(Value::as_str).unwrap_or_default();
+                    Some(ChainCandidate {
+                        name,
+                        node_type: node_type.to_lowercase(),
+                        is_group,
+                        udp: item.get("udp").and_then(Value::as_bool).unwrap_or(false),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        Self {
+            proxies: list("proxies", false),
+            groups: list("proxy-groups", true),
+        }
+    }
+
+    /// the nodes of the running config, without the outputs of the saved chains
+    pub fn current() -> Self {
+        let exclude = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            verge
+                .proxy_chains
+                .iter()
+                .flatten()
+                .flat_map(|chain| chain.outputs())
+                .collect::<HashSet<_>>()
+        };
+        let runtime = Config::runtime();
+        let runtime = runtime.latest();
+        match runtime.config.as_ref() {
+            Some(config) => Self::from_config(config, &exclude),
+            None => Self::default(),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&ChainCandidate> {
+        self.proxies
+            .iter()
+            .chain(self.groups.iter())
+            .find(|node| node.name == name)
+    }
+
+    pub fn candidates(self) -> Vec<ChainCandidate> {
+        self.proxies.into_iter().chain(self.groups).collect()
+    }
+}
+
+impl ProxyChain {
+    /// the names of the groups or the nodes generated by the chain
+    pub fn outputs(&self) -> Vec<String> {
+        match self.mode {
+            ChainMode::Relay => vec![self.name.clone()],
+            ChainMode::DialerProxy => {
+                let last = self.proxies.len().saturating_sub(1);
+                self.proxies
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .map(|(index, hop)| self.hop_name(index == last, hop))
+                    .collect()
+            }
+        }
+    }
+
+    /// the exit is named after the chain
+    pub(crate) fn hop_name(&self, is_exit: bool, hop: &str) -> String {
+        match is_exit {
+            true => self.name.clone(),
+            false => format!("{} ({hop})", self.name),
+        }
+    }
+
+    pub fn validate(&self, nodes: &ChainNodes, core: &ClashCore) -> Result<()> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            bail!("the name of the chain is empty");
+        }
+        if self.proxies.len() < 2 {
+            bail!("the chain `{name}` needs two proxies at least");
+        }
+        let unique = self.proxies.iter().collect::<HashSet<_>>();
+        if unique.len() != self.proxies.len() {
+            bail!("the chain `{name}` has duplicated proxies");
+        }
+
+        if self.mode == ChainMode::DialerProxy && !core.supports(CoreFeature::DialerProxy) {
+            bail!("dialer-proxy is only supported by mihomo, use the relay mode");
+        }
+
+        for output in self.outputs() {
+            if nodes.get(&output).is_some() {
+                bail!("the name `{output}` is used by another proxy or group");
+            }
+        }
+
+        for (index, hop) in self.proxies.iter().enumerate() {
+            let Some(node) = nodes.get(hop) else {
+                bail!("the proxy `{hop}` does not exist");
+            };
+            if matches!(node.node_type.as_str(), "direct" | "reject" | "reject-drop") {
+                bail!("the `{hop}` can not be a hop of the chain");
+            }
+            // 每一跳都会被复制并设置 dialer-proxy，只有第一跳可以是组
+            if self.mode == ChainMode::DialerProxy && index > 0 && node.is_group {
+                bail!("only the first hop of the dialer-proxy chain can be a group, `{hop}` is a group");
+            }
+        }
+
+        if let Some(group) = &self.attach_to {
+            match nodes.groups.iter().find(|g| &g.name == group) {
+                Some(g) if g.node_type == "select" => {}
+                Some(_) => bail!("the group `{group}` is not a selector"),
+                None => bail!("the group `{group}` does not exist"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_validate_proxy_chain() {
+    let config = serde_yaml::from_str::<Mapping>(
+        r#"
+proxies:
+  - { name: a, type: ss }
+  - { name: b, type: vmess }
+proxy-groups:
+  - { name: Auto, type: url-test }
+  - { name: Proxy, type: select }
+"#,
+    )
+    .unwrap();
+    let nodes = ChainNodes::from_config(&config, &HashSet::new());
+    let chain = |proxies: &[&str], mode| ProxyChain {
+        name: "Chain".into(),
+        proxies: proxies.iter().map(|s| s.to_string()).collect(),
+        mode,
+        attach_to: Some("Proxy".into()),
+        enable: true,
+    };
+
+    assert!(chain(&["Auto", "a", "b"], ChainMode::DialerProxy)
+        .validate(&nodes, &ClashCore::Mihomo)
+        .is_ok());
+    assert!(chain(&["a", "Auto"], ChainMode::Relay)
+        .validate(&nodes, &ClashCore::ClashPremium)
+        .is_ok());
+
+    // 不支持 dialer-proxy 的内核、组不在第一跳、只有一跳
+    assert!(chain(&["a", "b"], ChainMode::DialerProxy)
+        .validate(&nodes, &ClashCore::ClashPremium)
+        .is_err());
+    assert!(chain(&["a", "Auto"], ChainMode::DialerProxy)
+        .validate(&nodes, &ClashCore::Mihomo)
+        .is_err());
+    assert!(chain(&["a"], ChainMode::Relay)
+        .validate(&nodes, &ClashCore::Mihomo)
+        .is_err());
+
+    assert_eq!(
+        chain(&["Auto", "a", "b"], ChainMode::DialerProxy).outputs(),
+        vec!["Chain (a)".to_string(), "Chain".to_string()]
+    );
+}
@@ -0,0 +1,173 @@
+//! the scripts downloaded from the urls are quarantined until approved
+//! only the approved content, by sha256, is allowed to run against the config
+use super::{
+    profiles_store::{ProfilesStore, ScriptReview},
+    PrfItem,
+};
+use crate::utils::dirs;
+use anyhow::{bail, Context, Result};
+use reqwest::StatusCode;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{fs, path::PathBuf};
+
+/// the downloaded script waiting for review
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingScript {
+    pub hash: String,
+    pub data: String,
+    /// the running script, to compare with
+    pub current: String,
+    pub fetched: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Fetched {
+    /// the same as the approved one
+    Unchanged,
+    /// rejected before, keep it away
+    Rejected,
+    Changed,
+}
+
+fn classify(review: &ScriptReview, hash: &str) -> Fetched {
+    if review.approved.as_deref() == Some(hash) {
+        Fetched::Unchanged
+    } else if review.rejected.as_deref() == Some(hash) {
+        Fetched::Rejected
+    } else {
+        Fetched::Changed
+    }
+}
+
+fn hash(data: &str) -> String {
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+fn pending_path(uid: &str) -> Result<PathBuf> {
+    Ok(dirs::app_profiles_dir()?.join(format!("{uid}.pending.js")))
+}
+
+pub struct RemoteScript;
+
+impl RemoteScript {
+    pub fn is_remote(item: &PrfItem) -> bool {
+        item.itype.as_deref() == Some("script") && item.url.is_some()
+    }
+
+    /// download the script, `true` if a changed one is waiting for review
+    pub async fn fetch(uid: &str, item: &PrfItem) -> Result<bool> {
+        let url = item.url.as_deref().context("the script has no url")?;
+        let resp = PrfItem::fetch(url, item.option.as_ref()).await?;
+        let status = resp.status();
+        if !StatusCode::is_success(&status) {
+            bail!("failed to fetch the remote script with status {status}");
+        }
+        let data = resp.text_with_charset("utf-8").await?;
+        let data = data.trim_start_matches('\u{feff}');
+        let hash = hash(data);
+
+        let mut review = ProfilesStore::script_review(uid)?;
+        review.fetched = Some(chrono::Local::now().timestamp());
+        let fetched = classify(&review, &hash);
+        match fetched {
+            // 服务器又换回了已确认的版本
+            Fetched::Unchanged => {
+                if review.pending.take().is_some() {
+                    let _ = fs::remove_file(pending_path(uid)?);
+                }
+            }
+            Fetched::Rejected => {}
+            Fetched::Changed => {
+                fs::write(pending_path(uid)?, data)?;
+                if review.pending.as_ref() != Some(&hash) {
+                    tracing::info!("the remote script `{uid}` changed, waiting for review");
+                }
+                review.pending = Some(hash);
+            }
+        }
+        ProfilesStore::set_script_review(uid, &review)?;
+        Ok(review.pending.is_some())
+    }
+
+    pub fn pending(item: &PrfItem) -> Result<Option<PendingScript>> {
+        let uid = item.uid.as_deref().context("the uid should not be null")?;
+        let review = ProfilesStore::script_review(uid)?;
+        let Some(hash) = review.pending else {
+            return Ok(None);
+        };
+        Ok(Some(PendingScript {
+            hash,
+            data: fs::read_to_string(pending_path(uid)?)
+                .context("failed to read the pending script")?,
+            current: item.read_file().unwrap_or_default(),
+            fetched: review.fetched,
+        }))
+    }
+
+    /// run the reviewed script from now on
+    /// the hash must be the one reviewed, a newer download needs another review
+    pub fn approve(item: &PrfItem, reviewed: &str) -> Result<()> {
+        let uid = item.uid.as_deref().context("the uid should not be null")?;
+        let mut review = ProfilesStore::script_review(uid)?;
+        if review.pending.as_deref() != Some(reviewed) {
+            bail!("the script changed during the review, please check it again");
+        }
+        let data = fs::read_to_string(pending_path(uid)?)?;
+        if hash(&data) != reviewed {
+            bail!("the pending script was modified, please update it again");
+        }
+
+        item.save_file(data)?;
+        review.approved = review.pending.take();
+        review.rejected = None;
+        ProfilesStore::set_script_review(uid, &review)?;
+        let _ = fs::remove_file(pending_path(uid)?);
+        Ok(())
+    }
+
+    pub fn reject(uid: &str) -> Result<()> {
+        let mut review = ProfilesStore::script_review(uid)?;
+        review.rejected = review.pending.take();
+        ProfilesStore::set_script_review(uid, &review)?;
+        let _ = fs::remove_file(pending_path(uid)?);
+        Ok(())
+    }
+
+    /// the content saved in the editor is trusted
+    pub fn trust(uid: &str, data: &str) -> Result<()> {
+        let mut review = ProfilesStore::script_review(uid)?;
+        review.approved = Some(hash(data));
+        ProfilesStore::set_script_review(uid, &review)
+    }
+
+    pub fn is_approved(uid: &str, data: &str) -> bool {
+        ProfilesStore::script_review(uid)
+            .map_or(false, |review| review.approved == Some(hash(data)))
+    }
+
+    pub fn remove_pending(uid: &str) {
+        if let Ok(path) = pending_path(uid) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[test]
+fn test_classify_fetched() {
+    let review = ScriptReview {
+        approved: Some(hash("function main(params) { return params; }")),
+        rejected: Some(hash("evil")),
+        ..ScriptReview::default()
+    };
+    assert_eq!(
+        classify(&review, &hash("function main(params) { return params; }")),
+        Fetched::Unchanged
+    );
+    assert_eq!(classify(&review, &hash("evil")), Fetched::Rejected);
+    assert_eq!(classify(&review, &hash("changed")), Fetched::Changed);
+    assert_eq!(
+        classify(&ScriptReview::default(), &hash("first")),
+        Fetched::Changed
+    );
+}
@@ -0,0 +1,217 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{collections::HashSet, fmt, net::IpAddr};
+
+/// the rule types of clash and meta
+const RULE_TYPES: [&str; 21] = [
+    "DOMAIN",
+    "DOMAIN-SUFFIX",
+    "DOMAIN-KEYWORD",
+    "DOMAIN-REGEX",
+    "GEOSITE",
+    "GEOIP",
+    "IP-CIDR",
+    "IP-CIDR6",
+    "IP-SUFFIX",
+    "IP-ASN",
+    "SRC-GEOIP",
+    "SRC-IP-CIDR",
+    "SRC-PORT",
+    "DST-PORT",
+    "IN-PORT",
+    "PROCESS-NAME",
+    "PROCESS-PATH",
+    "NETWORK",
+    "RULE-SET",
+    "SCRIPT",
+    "MATCH",
+];
+
+/// only these types accept `no-resolve`
+const RESOLVE_TYPES: [&str; 6] = [
+    "GEOIP",
+    "IP-CIDR",
+    "IP-CIDR6",
+    "IP-SUFFIX",
+    "IP-ASN",
+    "RULE-SET",
+];
+
+/// the built-in targets, they are not in the proxies
+const BUILTIN_TARGETS: [&str; 5] = ["DIRECT", "REJECT", "REJECT-DROP", "PASS", "COMPATIBLE"];
+
+/// where the rule is put into the rules of the profile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", content = "index", rename_all = "snake_case")]
+pub enum RulePosition {
+    #[default]
+    Prepend,
+    /// before the final `MATCH` rule
+    Append,
+    /// before the n-th rule of the profile, from 0
+    Before(usize),
+}
+
+/// a rule of the user, kept apart from the subscriptions
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UserRule {
+    /// generated when the rule is added
+    #[serde(default)]
+    pub id: String,
+    /// e.g. `DOMAIN-SUFFIX`
+    #[serde(rename = "type")]
+    pub rule_type: String,
+    /// empty for `MATCH`
+    #[serde(default)]
+    pub payload: String,
+    /// the proxy or the group
+    pub target: String,
+    #[serde(default)]
+    pub no_resolve: bool,
+    #[serde(default)]
+    pub position: RulePosition,
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+impl fmt::Display for UserRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rule_type)?;
+        if !self.payload.is_empty() {
+            write!(f, ",{}", self.payload)?;
+        }
+        write!(f, ",{}", self.target)?;
+        if self.no_resolve {
+            write!(f, ",no-resolve")?;
+        }
+        Ok(())
+    }
+}
+
+impl UserRule {
+    /// check the syntax, and the target when the proxies are known
+    pub fn validate(&self, targets: Option<&HashSet<String>>) -> Result<()> {
+        let rule_type = self.rule_type.as_str();
+        let payload = self.payload.trim();
+
+        if !RULE_TYPES.contains(&rule_type) {
+            bail!("unsupported rule type `{rule_type}`");
+        }
+        if payload.contains(',') || self.target.contains(',') {
+            bail!("the payload and the target of the rule can not contain `,`");
+        }
+
+        match rule_type {
+            "MATCH" if !payload.is_empty() => bail!("the MATCH rule has no payload"),
+            "MATCH" => {}
+            _ if payload.is_empty() => bail!("the payload of the {rule_type} rule is empty"),
+            "IP-CIDR" | "IP-CIDR6" | "SRC-IP-CIDR" | "IP-SUFFIX" => check_cidr(payload)?,
+            "SRC-PORT" | "DST-PORT" | "IN-PORT" => check_port(payload)?,
+            "IP-ASN" if payload.parse::<u32>().is_err() => bail!("invalid asn `{payload}`"),
+            "NETWORK" if !matches!(payload.to_lowercase().as_str(), "tcp" | "udp") => {
+                bail!("the network should be tcp or udp")
+            }
+            _ => {}
+        }
+
+        if self.no_resolve && !RESOLVE_TYPES.contains(&rule_type) {
+            bail!("the {rule_type} rule does not support no-resolve");
+        }
+
+        let target = self.target.trim();
+        if target.is_empty() {
+            bail!("the target of the rule is empty");
+        }
+        if let Some(targets) = targets {
+            if !targets.contains(target) {
+                bail!("the target `{target}` does not exist");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_cidr(payload: &str) -> Result<()> {
+    let (ip, prefix) = payload.split_once('/').unwrap_or((payload, ""));
+    let max = match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(_)) => 32,
+        Ok(IpAddr::V6(_)) => 128,
+        Err(_) => bail!("invalid cidr `{payload}`"),
+    };
+    match prefix.is_empty() || prefix.parse::<u8>().is_ok_and(|p| p <= max) {
+        true => Ok(()),
+        false => bail!("invalid cidr prefix `{payload}`"),
+    }
+}
+
+/// `443` `8000-9000` `80/443`
+fn check_port(payload: &str) -> Result<()> {
+    for part in payload.split('/') {
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        match (start.parse::<u16>(), end.parse::<u16>()) {
+            (Ok(start), Ok(end)) if start <= end => {}
+            _ => bail!("invalid port `{payload}`"),
+        }
+    }
+    Ok(())
+}
+
+/// the names of the proxies and the groups of the config, with the built-in ones
+pub fn rule_targets(config: &Mapping) -> HashSet<String> {
+    let names = |key: &str| {
+        config
+            .get(key)
+            .and_then(|v| v.as_sequence())
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.get("name").and_then(Value::as_str))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    BUILTIN_TARGETS
+        .iter()
+        .map(|s| s.to_string())
+        .chain(names("proxies"))
+        .chain(names("proxy-groups"))
+        .collect()
+}
+
+#[test]
+fn test_validate_user_rule() {
+    let rule = |rule_type: &str, payload: &str, target: &str, no_resolve: bool| UserRule {
+        id: String::new(),
+        rule_type: rule_type.into(),
+        payload: payload.into(),
+        target: target.into(),
+        no_resolve,
+        position: RulePosition::Prepend,
+        enable: true,
+    };
+    let targets = HashSet::from(["DIRECT".to_string(), "Proxy".to_string()]);
+
+    let valid = [
+        rule("DOMAIN-SUFFIX", "google.com", "Proxy", false),
+        rule("IP-CIDR", "10.0.0.0/8", "DIRECT", true),
+        rule("DST-PORT", "8000-9000/443", "DIRECT", false),
+        rule("MATCH", "", "Proxy", false),
+    ];
+    assert!(valid.iter().all(|r| r.validate(Some(&targets)).is_ok()));
+    assert_eq!(valid[1].to_string(), "IP-CIDR,10.0.0.0/8,DIRECT,no-resolve");
+    assert_eq!(valid[3].to_string(), "MATCH,Proxy");
+
+    let invalid = [
+        rule("DOMAIN", "google.com", "Missing", false),
+        rule("DOMAIN", "", "Proxy", false),
+        rule("IP-CIDR", "10.0.0.0/33", "Proxy", false),
+        rule("DOMAIN", "a.com", "Proxy", true),
+        rule("UNKNOWN", "a.com", "Proxy", false),
+        rule("DST-PORT", "9000-80", "Proxy", false),
+    ];
+    assert!(invalid.iter().all(|r| r.validate(Some(&targets)).is_err()));
+}
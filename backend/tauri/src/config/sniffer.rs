@@ -0,0 +1,136 @@
+use super::{dns::check_pattern, ClashCore, CoreFeature};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SniffProtocol {
+    Http,
+    Tls,
+    Quic,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SniffPorts {
+    /// e.g. `443` `8000-9000`
+    pub ports: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub override_destination: Option<bool>,
+}
+
+/// the `sniffer` of mihomo applied on top of the profile when generating the runtime config
+/// the `None` fields keep the values of the profile, the lists replace the whole list
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SnifferOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable: Option<bool>,
+
+    /// sniff the connections of the fake-ip and the redir-host
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_dns_mapping: Option<bool>,
+
+    /// sniff the connections to the ips without the domains
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_pure_ip: Option<bool>,
+
+    /// replace the destination with the sniffed domain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub override_destination: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sniff: Option<BTreeMap<SniffProtocol, SniffPorts>>,
+
+    /// always sniff these domains, e.g. `+.v2ex.com`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_domain: Option<Vec<String>>,
+
+    /// never sniff these domains
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_domain: Option<Vec<String>>,
+}
+
+impl SnifferOverride {
+    pub fn validate(&self, core: &ClashCore) -> Result<()> {
+        if self != &Self::default() && !core.supports(CoreFeature::Sniffer) {
+            bail!("the sniffer is only supported by mihomo, `{core}` is in use");
+        }
+
+        for (protocol, sniff) in self.sniff.iter().flatten() {
+            if sniff.ports.is_empty() {
+                bail!("the ports of {protocol:?} are empty");
+            }
+            for ports in &sniff.ports {
+                let (start, end) = ports.split_once('-').unwrap_or((ports, ports));
+                match (start.trim().parse::<u16>(), end.trim().parse::<u16>()) {
+                    (Ok(start), Ok(end)) if start != 0 && start <= end => {}
+                    _ => bail!("invalid port `{ports}` of {protocol:?}"),
+                }
+            }
+        }
+
+        for pattern in self.force_domain.iter().flatten() {
+            check_pattern(pattern)?;
+        }
+        // skip-domain 可以是任意的域名，例如 `Mijia Cloud`
+        for domain in self.skip_domain.iter().flatten() {
+            if domain.trim().is_empty() {
+                bail!("the skipped domain is empty");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_validate_sniffer_override() {
+    let sniffer = SnifferOverride {
+        enable: Some(true),
+        sniff: Some(BTreeMap::from([
+            (
+                SniffProtocol::Http,
+                SniffPorts {
+                    ports: vec!["80".into(), "8080-8880".into()],
+                    override_destination: Some(true),
+                },
+            ),
+            (
+                SniffProtocol::Tls,
+                SniffPorts {
+                    ports: vec!["443".into()],
+                    override_destination: None,
+                },
+            ),
+        ])),
+        force_domain: Some(vec!["+.v2ex.com".into()]),
+        skip_domain: Some(vec!["Mijia Cloud".into()]),
+        ..SnifferOverride::default()
+    };
+    assert!(sniffer.validate(&ClashCore::Mihomo).is_ok());
+    assert!(sniffer.validate(&ClashCore::ClashPremium).is_err());
+    assert!(SnifferOverride::default()
+        .validate(&ClashCore::ClashRs)
+        .is_ok());
+
+    let value = serde_yaml::to_value(&sniffer).unwrap();
+    assert_eq!(
+        value["sniff"]["HTTP"]["ports"][1].as_str(),
+        Some("8080-8880")
+    );
+    assert!(value.get("parse-pure-ip").is_none());
+
+    let invalid = SnifferOverride {
+        sniff: Some(BTreeMap::from([(
+            SniffProtocol::Quic,
+            SniffPorts {
+                ports: vec!["9000-8000".into()],
+                override_destination: None,
+            },
+        )])),
+        ..SnifferOverride::default()
+    };
+    assert!(invalid.validate(&ClashCore::Mihomo).is_err());
+}
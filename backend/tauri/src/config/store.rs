@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+/// the `profile` of the core applied on top of the profile, what the core keeps in `cache.db`
+/// the `None` fields keep the values of the profile
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct StoreOverride {
+    /// keep the selections of the groups
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_selected: Option<bool>,
+
+    /// keep the fake-ip mappings across the restarts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_fake_ip: Option<bool>,
+}
+
+/// the values in effect, the defaults of mihomo if missing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StoreState {
+    pub store_selected: bool,
+    pub store_fake_ip: bool,
+}
+
+impl StoreState {
+    pub fn from_config(config: &Mapping) -> Self {
+        let profile = config.get("profile");
+        let flag = |key: &str| profile.and_then(|p| p.get(key)).and_then(Value::as_bool);
+        Self {
+            store_selected: flag("store-selected").unwrap_or(true),
+            store_fake_ip: flag("store-fake-ip").unwrap_or(false),
+        }
+    }
+}
+
+#[test]
+fn test_store_state() {
+    let config: Mapping = serde_yaml::from_str("profile: { store-fake-ip: true }").unwrap();
+    assert_eq!(
+        StoreState::from_config(&config),
+        StoreState {
+            store_selected: true,
+            store_fake_ip: true
+        }
+    );
+    assert!(!StoreState::from_config(&Mapping::new()).store_fake_ip);
+}
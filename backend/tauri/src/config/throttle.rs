@@ -0,0 +1,97 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// the bucket of the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleScope {
+    /// every connection has its own limit
+    Connection,
+    /// the connections to the same host share the limit
+    Host,
+}
+
+/// limit the bandwidth of the connections through the throttled inbound
+/// the first enabled rule matching the host is used
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ThrottleRule {
+    #[serde(default)]
+    pub id: String,
+    /// `*` for all, `example.com`, or `+.example.com` with the subdomains
+    pub host: String,
+    pub scope: ThrottleScope,
+    /// KB/s, 0 is unlimited
+    #[serde(default)]
+    pub download: u32,
+    #[serde(default)]
+    pub upload: u32,
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+impl ThrottleRule {
+    pub fn validate(&self) -> Result<()> {
+        let host = self.host.trim();
+        if host.is_empty() {
+            bail!("the host of the throttle rule is empty");
+        }
+        if host.contains([',', '/', ' ']) {
+            bail!("invalid host `{host}`");
+        }
+        if self.download == 0 && self.upload == 0 {
+            bail!("the throttle rule of `{host}` has no limit");
+        }
+        Ok(())
+    }
+
+    /// `host` is lowercase, without the port
+    pub fn matches(&self, host: &str) -> bool {
+        let pattern = self.host.trim().to_lowercase();
+        match pattern.strip_prefix("+.") {
+            _ if pattern == "*" => true,
+            Some(suffix) => {
+                host == suffix
+                    || host
+                        .strip_suffix(suffix)
+                        .is_some_and(|rest| rest.ends_with('.'))
+            }
+            None => host == pattern,
+        }
+    }
+}
+
+pub fn check_throttle_rules(rules: &[ThrottleRule]) -> Result<()> {
+    for rule in rules.iter().filter(|rule| rule.enable) {
+        rule.validate()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_throttle_rules() {
+    let rule = |host: &str| ThrottleRule {
+        id: String::new(),
+        host: host.into(),
+        scope: ThrottleScope::Host,
+        download: 512,
+        upload: 0,
+        enable: true,
+    };
+
+    assert!(rule("*").matches("example.com"));
+    assert!(rule("+.Example.com").matches("dl.example.com"));
+    assert!(rule("+.example.com").matches("example.com"));
+    assert!(!rule("+.example.com").matches("notexample.com"));
+    assert!(!rule("example.com").matches("dl.example.com"));
+    assert!(rule("a,b").validate().is_err());
+    assert!(ThrottleRule {
+        download: 0,
+        ..rule("example.com")
+    }
+    .validate()
+    .is_err());
+}
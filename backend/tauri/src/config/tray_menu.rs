@@ -0,0 +1,93 @@
+use crate::core::hotkey::HOTKEY_FUNCS;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TrayMenuAction {
+    /// the same functions as the hotkeys, e.g. `restart_clash`
+    Function { func: String },
+    /// run the program directly without a shell, only the saved ones are allowed
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// the extra items in the tray menu
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TrayMenuEntry {
+    pub label: String,
+    pub action: TrayMenuAction,
+    /// ask before running it
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+impl TrayMenuEntry {
+    pub fn validate(&self) -> Result<()> {
+        if self.label.trim().is_empty() {
+            bail!("the label of the tray menu item should not be empty");
+        }
+        match &self.action {
+            TrayMenuAction::Function { func } => {
+                if !HOTKEY_FUNCS.contains(&func.as_str()) {
+                    bail!("invalid function `{func}` of `{}`", self.label);
+                }
+            }
+            TrayMenuAction::Command { program, .. } => {
+                if program.trim().is_empty() {
+                    bail!("the program of `{}` should not be empty", self.label);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the fingerprint of the command to be allowed, `None` for the functions
+    pub fn command_hash(&self) -> Option<String> {
+        let TrayMenuAction::Command { program, args } = &self.action else {
+            return None;
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(program.as_bytes());
+        for arg in args {
+            // 分隔开，免得参数拼接后撞上
+            hasher.update([0]);
+            hasher.update(arg.as_bytes());
+        }
+        Some(hex::encode(hasher.finalize()))
+    }
+}
+
+#[test]
+fn test_tray_menu_entry() {
+    let command = |program: &str, args: &[&str]| TrayMenuEntry {
+        label: "Restart Router".into(),
+        action: TrayMenuAction::Command {
+            program: program.into(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        },
+        confirm: true,
+    };
+    assert!(command("/usr/local/bin/restart-router", &[])
+        .validate()
+        .is_ok());
+    assert!(command(" ", &[]).validate().is_err());
+    assert_ne!(
+        command("ssh", &["router", "reboot"]).command_hash(),
+        command("ssh", &["routerreboot"]).command_hash()
+    );
+
+    let function = TrayMenuEntry {
+        label: "Restart".into(),
+        action: TrayMenuAction::Function {
+            func: "restart_clash".into(),
+        },
+        confirm: false,
+    };
+    assert!(function.validate().is_ok());
+    assert_eq!(function.command_hash(), None);
+}
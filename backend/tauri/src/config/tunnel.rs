@@ -0,0 +1,167 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{collections::HashSet, net::SocketAddr, time::Duration};
+use tokio::{net::TcpStream, time::timeout};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelNetwork {
+    Tcp,
+    Udp,
+}
+
+/// forward a local port to the target through the proxy, the `tunnels` of the config
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Tunnel {
+    /// generated when the tunnel is added
+    #[serde(default)]
+    pub id: String,
+    pub network: Vec<TunnelNetwork>,
+    /// the local address to listen, e.g. `127.0.0.1:5432`
+    pub address: String,
+    /// the remote `host:port`
+    pub target: String,
+    /// the proxy or the group, the rules are used when it is empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunnelState {
+    /// the local port accepts the connections
+    Listening,
+    /// the tunnel should be running, but the port can not be connected
+    Unreachable,
+    /// disabled, or the core is not running
+    Inactive,
+    /// the udp tunnels can not be probed
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelStatus {
+    pub id: String,
+    pub state: TunnelState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl Tunnel {
+    /// check the addresses, and the proxy when the proxies are known
+    pub fn validate(&self, proxies: Option<&HashSet<String>>) -> Result<()> {
+        if self.network.is_empty() {
+            bail!("the network of the tunnel is empty");
+        }
+        let address = self.address.trim();
+        match address.parse::<SocketAddr>() {
+            Ok(addr) if addr.port() != 0 => {}
+            _ => bail!("invalid local address `{address}`, e.g. 127.0.0.1:5432"),
+        }
+        check_target(self.target.trim())?;
+
+        if let (Some(proxy), Some(proxies)) = (self.proxy.as_ref(), proxies) {
+            if !proxies.contains(proxy) {
+                bail!("the proxy `{proxy}` does not exist");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut map = Mapping::new();
+        let network = self
+            .network
+            .iter()
+            .map(|network| match network {
+                TunnelNetwork::Tcp => "tcp",
+                TunnelNetwork::Udp => "udp",
+            })
+            .collect::<Vec<_>>();
+        map.insert("network".into(), network.into());
+        map.insert("address".into(), self.address.trim().into());
+        map.insert("target".into(), self.target.trim().into());
+        if let Some(proxy) = &self.proxy {
+            map.insert("proxy".into(), proxy.as_str().into());
+        }
+        map.into()
+    }
+
+    /// try to connect to the local port
+    pub async fn probe(&self) -> TunnelStatus {
+        let status = |state, message: Option<String>| TunnelStatus {
+            id: self.id.clone(),
+            state,
+            message,
+        };
+        if !self.network.contains(&TunnelNetwork::Tcp) {
+            return status(TunnelState::Unknown, None);
+        }
+        let Ok(mut addr) = self.address.trim().parse::<SocketAddr>() else {
+            return status(TunnelState::Unreachable, Some("invalid address".into()));
+        };
+        // 监听在所有地址上时，连接本机
+        if addr.ip().is_unspecified() {
+            addr.set_ip([127, 0, 0, 1].into());
+        }
+        match timeout(Duration::from_secs(2), TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => status(TunnelState::Listening, None),
+            Ok(Err(err)) => status(TunnelState::Unreachable, Some(err.to_string())),
+            Err(_) => status(TunnelState::Unreachable, Some("timed out".into())),
+        }
+    }
+}
+
+/// `host:port`, the host can be a domain or an ip
+fn check_target(target: &str) -> Result<()> {
+    if target.parse::<SocketAddr>().is_ok() {
+        return Ok(());
+    }
+    let Some((host, port)) = target.rsplit_once(':') else {
+        bail!("the target `{target}` needs a port, e.g. db.example.com:5432");
+    };
+    if host.is_empty() || host.contains([':', '/', ' ']) {
+        bail!("invalid target host `{host}`");
+    }
+    match port.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(()),
+        _ => bail!("invalid target port `{port}`"),
+    }
+}
+
+#[test]
+fn test_validate_tunnel() {
+    let tunnel = |address: &str, target: &str, proxy: Option<&str>| Tunnel {
+        id: String::new(),
+        network: vec![TunnelNetwork::Tcp],
+        address: address.into(),
+        target: target.into(),
+        proxy: proxy.map(|s| s.to_string()),
+        enable: true,
+    };
+    let proxies = HashSet::from(["Proxy".to_string()]);
+
+    assert!(
+        tunnel("127.0.0.1:5432", "db.example.com:5432", Some("Proxy"))
+            .validate(Some(&proxies))
+            .is_ok()
+    );
+    assert!(tunnel("[::1]:53", "1.1.1.1:53", None)
+        .validate(Some(&proxies))
+        .is_ok());
+
+    assert!(tunnel("localhost:5432", "db:5432", None)
+        .validate(None)
+        .is_err());
+    assert!(tunnel("127.0.0.1:5432", "db", None).validate(None).is_err());
+    assert!(tunnel("127.0.0.1:5432", "db:5432", Some("Other"))
+        .validate(Some(&proxies))
+        .is_err());
+}
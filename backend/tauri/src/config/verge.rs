@@ -1,7 +1,14 @@
-use crate::utils::{dirs, help};
-use anyhow::Result;
-use log::LevelFilter;
+use super::{
+    bypass_to_no_proxy, bypass_to_platform, default_bypass, parse_bypass, AppRule, BypassRule,
+    DnsOverride, EventHook, HostsEntry, HttpPolicies, InboundAuth, InboundListener, PauseWindow,
+    ProxyChain, SnifferOverride, StoreOverride, ThrottleRule, Tunnel, UserRule,
+};
+use crate::utils::{dirs, help, secrets};
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use tracing::level_filters::LevelFilter;
+
+const CONTROLLER_PROXY_TOKEN_KEY: &str = "verge/controller_proxy_token";
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub enum ClashCore {
@@ -52,10 +59,133 @@ pub struct IVerge {
     /// app listening port for app singleton
     pub app_singleton_port: Option<u16>,
 
+    /// enable the localhost http api for the automation tools
+    pub enable_control_api: Option<bool>,
+
+    /// the listening port of the control api
+    pub control_api_port: Option<u16>,
+
+    /// the bearer token of the control api, generated on the first start
+    pub control_api_token: Option<String>,
+
+    /// proxy the core controller for the third-party dashboards, e.g. yacd
+    /// they use its token, the app keeps talking to the core directly
+    pub enable_controller_proxy: Option<bool>,
+
+    pub controller_proxy_port: Option<u16>,
+
+    /// the token given to the third-party dashboards, generated on the first start
+    /// saved to the keyring, the file keeps the placeholder
+    pub controller_proxy_token: Option<String>,
+
+    /// read only by default, the mutations are logged either way
+    pub controller_proxy_access: Option<RemoteAccess>,
+
+    /// expose the prometheus metrics on localhost
+    pub enable_metrics: Option<bool>,
+
+    /// the listening port of the `/metrics` endpoint
+    pub metrics_port: Option<u16>,
+
+    /// relay the system proxy through the app to limit the bandwidth, see `core/throttle.rs`
+    pub enable_throttle: Option<bool>,
+
+    /// the port of the throttled inbound, the system proxy points to it
+    pub throttle_port: Option<u16>,
+
+    /// 限速规则，按顺序匹配第一条
+    pub throttle_rules: Option<Vec<ThrottleRule>>,
+
+    /// record the requests to the external controller for debugging
+    pub enable_api_inspector: Option<bool>,
+
+    /// serve a management page to the lan, e.g. for a headless box
+    pub enable_remote_management: Option<bool>,
+
+    /// the listening port of the management page
+    pub remote_management_port: Option<u16>,
+
+    /// read-only by default, the password is kept in the keyring
+    pub remote_management_access: Option<RemoteAccess>,
+
+    /// the dns over https server for the app's own requests, e.g. the subscriptions and the updates
+    /// empty to use the system dns
+    pub app_dns_over_https: Option<String>,
+
+    /// the timeout, the retries and the proxy of the app's own requests, by purpose
+    pub http_policies: Option<HttpPolicies>,
+
     /// app log level
     /// silent | error | warn | info | debug | trace
     pub app_log_level: Option<String>,
 
+    /// the per-module filters appended to the level, e.g. `clash_verge::core::sync=trace,reqwest=debug`
+    pub app_log_filter: Option<String>,
+
+    /// 日志文件的格式，修改后重启生效
+    pub app_log_format: Option<LogFormat>,
+
+    /// 单个日志文件的大小上限，单位 MB，超过后轮转
+    pub app_log_max_size: Option<u64>,
+
+    /// 自动上传崩溃报告，需要用户主动开启
+    pub enable_crash_upload: Option<bool>,
+
+    /// 崩溃报告的上传地址
+    pub crash_upload_url: Option<String>,
+
+    /// 应用更新的渠道
+    pub update_channel: Option<UpdateChannel>,
+
+    /// 下载更新后等到退出时再安装
+    pub update_install_on_exit: Option<bool>,
+
+    /// 跳过的版本，不再提示更新
+    pub update_skipped_versions: Option<Vec<String>>,
+
+    /// github 下载的镜像前缀，如 `https://mirror.ghproxy.com/`
+    /// 为空时直接从 github 下载，未设置时用内置的镜像
+    pub github_mirror: Option<String>,
+
+    /// 全局的 dns 覆写，当前 profile 的覆写优先
+    pub dns_override: Option<DnsOverride>,
+
+    /// 托管的 hosts，合并到生成的配置里
+    pub hosts_entries: Option<Vec<HostsEntry>>,
+
+    /// 用户规则，生成配置时插入到订阅的规则里
+    pub user_rules: Option<Vec<UserRule>>,
+
+    /// 按进程分流，生成 PROCESS-NAME 规则放在最前面
+    pub app_rules: Option<Vec<AppRule>>,
+
+    /// 代理链，生成 relay 组或者 dialer-proxy 节点
+    pub proxy_chains: Option<Vec<ProxyChain>>,
+
+    /// 端口转发，写入 mihomo 的 tunnels
+    pub tunnels: Option<Vec<Tunnel>>,
+
+    /// 额外的入站监听，写入 mihomo 的 listeners
+    pub listeners: Option<Vec<InboundListener>>,
+
+    /// 出口探测，注入隐藏的分组和本地的 http 入站，通过每个节点查询出口 ip
+    pub enable_exit_probe: Option<bool>,
+
+    /// the port of the exit probe listener
+    pub exit_probe_port: Option<u16>,
+
+    /// mixed / http / socks 端口的认证，密码保存在 keyring
+    pub inbound_auth: Option<InboundAuth>,
+
+    /// mihomo 的 sniffer 覆写
+    pub sniffer_override: Option<SnifferOverride>,
+
+    /// 内核 `profile` 的覆写，cache.db 里保存哪些东西
+    pub store_override: Option<StoreOverride>,
+
+    /// the size limit of the downloaded core artifacts in MB, 0 disables the cache
+    pub artifact_cache_limit: Option<u64>,
+
     // i18n
     pub language: Option<String>,
 
@@ -72,13 +202,23 @@ pub struct IVerge {
     /// show memory info (only for Clash Meta)
     pub enable_memory_usage: Option<bool>,
 
+    /// 内核内存占用超过该值 (MB) 时提示重启内核，0 为不提示
+    pub memory_alert_threshold: Option<u64>,
+
     /// page transition animation, default is `slide`
     pub page_transition_animation: Option<String>,
 
     /// clash tun mode
     pub enable_tun_mode: Option<bool>,
 
-    /// windows service mode
+    /// tun 模式下将系统 dns 指向 tun 设备，退出时恢复
+    pub enable_tun_dns_override: Option<bool>,
+
+    /// tun 模式下 app 和内核自身的流量直连，默认开启
+    /// 避免订阅更新和检查更新绕回代理
+    pub enable_self_bypass: Option<bool>,
+
+    /// windows service mode, the privileged helper on macos
     #[serde(skip_serializing_if = "Option::is_none")]
     pub enable_service_mode: Option<bool>,
 
@@ -88,6 +228,25 @@ pub struct IVerge {
     /// not show the window on launch
     pub enable_silent_start: Option<bool>,
 
+    /// only hide the window when launched by the system
+    pub auto_launch_minimized: Option<bool>,
+
+    /// suspend the streams for the ui and throttle the others while the window is hidden
+    pub enable_low_power_mode: Option<bool>,
+
+    /// start the core on the first connection to the mixed port, applied on the next launch
+    pub enable_lazy_core: Option<bool>,
+
+    /// 启动时激活哪个 profile，上次使用的、固定的或者询问
+    pub startup_profile: Option<StartupProfile>,
+
+    /// seconds to wait for the network before starting the core
+    /// when launched by the system
+    pub auto_launch_delay: Option<u64>,
+
+    /// how to register the startup on windows
+    pub auto_launch_method: Option<AutoLaunchMethod>,
+
     /// set system proxy
     pub enable_system_proxy: Option<bool>,
 
@@ -95,21 +254,106 @@ pub struct IVerge {
     pub enable_proxy_guard: Option<bool>,
 
     /// set system proxy bypass
+    #[deprecated(note = "use `system_proxy_bypass_list` instead")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub system_proxy_bypass: Option<String>,
 
+    /// 结构化的系统代理绕过列表，按平台的格式写入系统
+    pub system_proxy_bypass_list: Option<Vec<BypassRule>>,
+
+    /// 使用 pac 自动配置代替全局代理
+    pub enable_proxy_pac: Option<bool>,
+
     /// proxy guard duration
     pub proxy_guard_duration: Option<u64>,
 
+    /// show the proxy groups in the tray menu
+    pub enable_tray_proxies: Option<bool>,
+
+    /// show the upload and download rates in the tray
+    pub enable_tray_traffic: Option<bool>,
+
+    /// 托盘菜单里自定义的项目，运行快捷键的功能或者命令
+    pub tray_menu: Option<Vec<TrayMenuEntry>>,
+
+    /// 内核崩溃、应用更新、订阅到期和流量告警的系统通知
+    pub enable_system_notification: Option<bool>,
+
+    /// 订阅到期前几天提醒，0 表示只在到期时提醒
+    pub expire_reminder_days: Option<u32>,
+
+    /// switch to this profile when the current one expires, none to keep it
+    pub expired_fallback_profile: Option<String>,
+
+    /// watch the clipboard for the share links and the subscription urls
+    pub enable_clipboard_watch: Option<bool>,
+
+    /// the interval of reading the clipboard, in seconds
+    pub clipboard_watch_interval: Option<u64>,
+
+    /// 不在通知和对话框里显示剪贴板的内容
+    pub clipboard_watch_privacy: Option<bool>,
+
+    /// 网络变化时触发的动作
+    pub network_rules: Option<Vec<NetworkRule>>,
+
+    /// the webdav endpoint of the backups, the password is kept in the keyring
+    pub webdav_url: Option<String>,
+
+    pub webdav_username: Option<String>,
+
+    /// 定时备份的间隔，单位小时，0 表示不自动备份
+    pub webdav_backup_interval: Option<u64>,
+
+    /// the old backups beyond this are removed
+    pub webdav_max_backups: Option<usize>,
+
+    /// 按时间或网络自动切换配置的规则
+    pub automation_rules: Option<Vec<AutomationRule>>,
+
+    /// 按时间段暂停系统代理或 tun，结束后恢复
+    pub pause_windows: Option<Vec<PauseWindow>>,
+
+    /// 将选中的事件推送到 webhook 或 mqtt
+    pub event_hook: Option<EventHook>,
+
+    /// 内核反复崩溃时执行的操作
+    pub core_failover: Option<CoreFailover>,
+
+    /// sync the profiles and the group selections between the devices
+    pub enable_sync: Option<bool>,
+
+    pub sync_provider: Option<SyncProvider>,
+
+    /// the secret access key is kept in the keyring
+    pub sync_s3: Option<SyncS3Config>,
+
+    /// 同步的间隔，单位分钟
+    pub sync_interval: Option<u64>,
+
+    pub sync_conflict_strategy: Option<SyncConflictStrategy>,
+
     /// theme setting
     pub theme_setting: Option<IVergeTheme>,
 
     /// web ui list
     pub web_ui_list: Option<Vec<String>>,
 
+    /// the port serving the downloaded web uis
+    pub web_ui_port: Option<u16>,
+
+    /// the downloaded web ui opened by default, e.g. `metacubexd`, empty for none
+    pub default_web_ui: Option<String>,
+
     /// clash core path
     #[serde(skip_serializing_if = "Option::is_none")]
     pub clash_core: Option<ClashCore>,
 
+    /// the core before switching to a profile with its own core
+    /// restored when switching to a profile without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile_core_fallback: Option<ClashCore>,
+
     /// hotkey map
     /// format: {func},{key}
     pub hotkeys: Option<Vec<String>>,
@@ -141,6 +385,10 @@ pub struct IVerge {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub window_size_state: Option<WindowState>,
 
+    /// the physical window state of each monitor layout, see `utils/window_state.rs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_states: Option<std::collections::BTreeMap<String, WindowState>>,
+
     /// 是否启用随机端口
     pub enable_random_port: Option<bool>,
 
@@ -148,6 +396,181 @@ pub struct IVerge {
     pub verge_mixed_port: Option<u16>,
 }
 
+/// the profile activated on launch
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", content = "uid", rename_all = "snake_case")]
+pub enum StartupProfile {
+    #[default]
+    LastUsed,
+    Pinned(String),
+    /// start with the last used one and let the user pick in the window
+    Ask,
+}
+
+/// what the remote management page can do
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteAccess {
+    #[default]
+    ReadOnly,
+    /// switch the profiles and the nodes, restart the core, etc.
+    ReadWrite,
+}
+
+/// the action triggered by the network monitor
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NetworkAction {
+    ReapplySystemProxy,
+    RestartCore,
+    SwitchProfile {
+        uid: String,
+    },
+    /// disable the system proxy while connected, e.g. the trusted office wifi
+    DisableProxy,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+/// where the synced files are stored
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncProvider {
+    /// reuse the endpoint of the webdav backups
+    #[default]
+    Webdav,
+    S3,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SyncS3Config {
+    pub endpoint: String,
+    pub bucket: String,
+    /// `us-east-1` by default
+    pub region: Option<String>,
+    pub access_key_id: String,
+}
+
+/// how to resolve the files changed on both sides
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncConflictStrategy {
+    /// keep the one modified later
+    #[default]
+    LastWriterWins,
+    /// keep both untouched until the user picks one
+    Manual,
+}
+
+/// the release channel of the app updates
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+/// the startup mechanism on windows
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoLaunchMethod {
+    /// `HKCU\Software\Microsoft\Windows\CurrentVersion\Run` registry key
+    #[default]
+    Registry,
+    /// a shortcut in the user's Startup folder
+    StartupFolder,
+    /// a logon scheduled task with the highest privileges
+    /// so that the service mode could be used without the uac prompt
+    ScheduledTask,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NetworkRule {
+    /// `None` matches every network change
+    pub ssid: Option<String>,
+    pub action: NetworkAction,
+}
+
+/// the rule evaluated by the automation engine
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AutomationRule {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enable: bool,
+    pub trigger: AutomationTrigger,
+    /// run in order, the failed action does not stop the others
+    pub actions: Vec<AutomationAction>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AutomationTrigger {
+    /// connected to the wifi
+    Ssid { ssid: String },
+    /// the network service on macos, the local address of the default route on others
+    Network { network: String },
+    /// every day at `HH:MM`, or only on the weekdays (1 is monday, 7 is sunday)
+    Time {
+        at: String,
+        weekdays: Option<Vec<u8>>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AutomationAction {
+    SwitchProfile {
+        uid: String,
+    },
+    SetSystemProxy {
+        enable: bool,
+    },
+    SetTunMode {
+        enable: bool,
+    },
+    /// the clash mode, `rule` `global` or `direct`
+    SetMode {
+        mode: String,
+    },
+}
+
+impl AutomationRule {
+    pub fn validate(&self) -> Result<()> {
+        if let AutomationTrigger::Time { at, weekdays } = &self.trigger {
+            if chrono::NaiveTime::parse_from_str(at, "%H:%M").is_err() {
+                bail!("invalid time `{at}` of the rule `{}`", self.name);
+            }
+            if let Some(day) = weekdays
+                .iter()
+                .flatten()
+                .find(|day| !(1..=7).contains(*day))
+            {
+                bail!("invalid weekday `{day}` of the rule `{}`", self.name);
+            }
+        }
+        for action in self.actions.iter() {
+            if let AutomationAction::SetMode { mode } = action {
+                if !["rule", "global", "direct"].contains(&mode.to_lowercase().as_str()) {
+                    bail!("invalid mode `{mode}` of the rule `{}`", self.name);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct WindowState {
     pub width: f64,
@@ -179,9 +602,19 @@ pub struct IVergeTheme {
 impl IVerge {
     pub fn new() -> Self {
         match dirs::verge_path().and_then(|path| help::read_yaml::<IVerge>(&path)) {
-            Ok(config) => config,
+            Ok(mut config) => {
+                config.migrate_bypass();
+                for listener in config.listeners.iter_mut().flatten() {
+                    listener.resolve_passwords();
+                }
+                config.controller_proxy_token = config
+                    .controller_proxy_token
+                    .as_deref()
+                    .map(secrets::resolve);
+                config
+            }
             Err(err) => {
-                log::error!(target: "app", "{err}");
+                tracing::error!("{err}");
                 Self::template()
             }
         }
@@ -199,24 +632,115 @@ impl IVerge {
             traffic_graph: Some(true),
             enable_memory_usage: Some(true),
             enable_auto_launch: Some(false),
+            enable_control_api: Some(false),
+            control_api_port: Some(33332),
+            enable_controller_proxy: Some(false),
+            controller_proxy_port: Some(33337),
+            enable_metrics: Some(false),
+            metrics_port: Some(33333),
+            enable_throttle: Some(false),
+            throttle_port: Some(33335),
+            enable_exit_probe: Some(false),
+            exit_probe_port: Some(33336),
+            artifact_cache_limit: Some(200),
+            enable_api_inspector: Some(false),
+            enable_remote_management: Some(false),
+            remote_management_port: Some(33334),
+            web_ui_port: Some(33338),
             enable_silent_start: Some(false),
+            enable_low_power_mode: Some(true),
+            enable_lazy_core: Some(false),
+            enable_tray_proxies: Some(true),
+            enable_tray_traffic: Some(false),
+            enable_system_notification: Some(true),
+            expire_reminder_days: Some(3),
+            enable_clipboard_watch: Some(false),
+            webdav_backup_interval: Some(0),
+            webdav_max_backups: Some(10),
+            enable_sync: Some(false),
+            sync_interval: Some(5),
+            clipboard_watch_interval: Some(2),
+            clipboard_watch_privacy: Some(true),
+            auto_launch_minimized: Some(false),
+            auto_launch_delay: Some(0),
             enable_system_proxy: Some(false),
             enable_random_port: Some(false),
             verge_mixed_port: Some(7890),
             enable_proxy_guard: Some(false),
+            enable_proxy_pac: Some(false),
             proxy_guard_duration: Some(30),
             auto_close_connection: Some(true),
             enable_builtin_enhanced: Some(true),
             enable_clash_fields: Some(true),
             page_transition_animation: Some("slide".into()),
             auto_log_clean: Some(60 * 24 * 7), // 7 days 自动清理日记
+            app_log_max_size: Some(10),
+            enable_crash_upload: Some(false),
+            update_channel: Some(UpdateChannel::Stable),
             ..Self::default()
         }
     }
 
+    /// 旧版本的绕过列表是纯文本，解析失败时保留原样
+    #[allow(deprecated)]
+    fn migrate_bypass(&mut self) {
+        if self.system_proxy_bypass_list.is_some() {
+            return;
+        }
+        if let Some(bypass) = self.system_proxy_bypass.as_ref() {
+            match parse_bypass(bypass) {
+                Ok(list) => {
+                    self.system_proxy_bypass_list = Some(list);
+                    self.system_proxy_bypass = None;
+                }
+                Err(err) => tracing::warn!("failed to migrate the bypass: {err}"),
+            }
+        }
+    }
+
+    /// the bypass string in the platform format
+    #[allow(deprecated)]
+    pub fn get_system_proxy_bypass(&self) -> String {
+        match (
+            self.system_proxy_bypass_list.as_ref(),
+            self.system_proxy_bypass.as_ref(),
+        ) {
+            (Some(list), _) => bypass_to_platform(list),
+            (None, Some(bypass)) => bypass.clone(),
+            (None, None) => bypass_to_platform(&default_bypass()),
+        }
+    }
+
+    /// the bypass list in the `no_proxy` format
+    #[allow(deprecated)]
+    pub fn get_no_proxy(&self) -> String {
+        let list = match (
+            self.system_proxy_bypass_list.as_ref(),
+            self.system_proxy_bypass.as_ref(),
+        ) {
+            (Some(list), _) => list.clone(),
+            (None, Some(bypass)) => parse_bypass(bypass).unwrap_or_else(|_| default_bypass()),
+            (None, None) => default_bypass(),
+        };
+        bypass_to_no_proxy(&list)
+    }
+
     /// Save IVerge App Config
+    /// the passwords of the listeners are saved to the keyring
     pub fn save_file(&self) -> Result<()> {
-        help::save_yaml(&dirs::verge_path()?, &self, Some("# Clash Nyanpasu Config"))
+        let mut config = self.clone();
+        for listener in config.listeners.iter_mut().flatten() {
+            listener.protect_passwords();
+        }
+        config.controller_proxy_token = config
+            .controller_proxy_token
+            .as_deref()
+            .map(|token| secrets::protect(CONTROLLER_PROXY_TOKEN_KEY, token));
+        help::save_yaml(
+            &dirs::verge_path()?,
+            &config,
+            Some("# Clash Nyanpasu Config"),
+        )
     }
 
     /// patch verge config
@@ -230,27 +754,109 @@ impl IVerge {
             };
         }
 
+        patch!(enable_control_api);
+        patch!(control_api_port);
+        patch!(control_api_token);
+        patch!(enable_controller_proxy);
+        patch!(controller_proxy_port);
+        patch!(controller_proxy_token);
+        patch!(controller_proxy_access);
+        patch!(enable_metrics);
+        patch!(metrics_port);
+        patch!(enable_throttle);
+        patch!(throttle_port);
+        patch!(throttle_rules);
+        patch!(enable_api_inspector);
+        patch!(enable_remote_management);
+        patch!(remote_management_port);
+        patch!(remote_management_access);
+        patch!(app_dns_over_https);
+        patch!(http_policies);
         patch!(app_log_level);
+        patch!(app_log_filter);
+        patch!(app_log_format);
+        patch!(app_log_max_size);
+        patch!(enable_crash_upload);
+        patch!(crash_upload_url);
+        patch!(update_channel);
+        patch!(update_install_on_exit);
+        patch!(update_skipped_versions);
+        patch!(github_mirror);
+        patch!(dns_override);
+        patch!(hosts_entries);
+        patch!(user_rules);
+        patch!(app_rules);
+        patch!(proxy_chains);
+        patch!(tunnels);
+        patch!(listeners);
+        patch!(enable_exit_probe);
+        patch!(exit_probe_port);
+        patch!(inbound_auth);
+        patch!(sniffer_override);
+        patch!(store_override);
+        patch!(artifact_cache_limit);
         patch!(language);
         patch!(theme_mode);
         patch!(theme_blur);
         patch!(traffic_graph);
         patch!(enable_memory_usage);
+        patch!(memory_alert_threshold);
         patch!(page_transition_animation);
 
         patch!(enable_tun_mode);
+        patch!(enable_tun_dns_override);
+        patch!(enable_self_bypass);
         patch!(enable_service_mode);
         patch!(enable_auto_launch);
         patch!(enable_silent_start);
+        patch!(enable_low_power_mode);
+        patch!(enable_lazy_core);
+        patch!(auto_launch_minimized);
+        patch!(startup_profile);
+        patch!(auto_launch_delay);
+        patch!(auto_launch_method);
         patch!(enable_system_proxy);
         patch!(enable_random_port);
         patch!(verge_mixed_port);
         patch!(enable_proxy_guard);
+        #[allow(deprecated)]
         patch!(system_proxy_bypass);
+        patch!(system_proxy_bypass_list);
+        #[allow(deprecated)]
+        if patch.system_proxy_bypass.is_some() && patch.system_proxy_bypass_list.is_none() {
+            self.system_proxy_bypass_list = None;
+            self.migrate_bypass();
+        }
+        patch!(enable_proxy_pac);
         patch!(proxy_guard_duration);
+        patch!(network_rules);
+        patch!(automation_rules);
+        patch!(pause_windows);
+        patch!(event_hook);
+        patch!(core_failover);
+        patch!(webdav_url);
+        patch!(webdav_username);
+        patch!(webdav_backup_interval);
+        patch!(webdav_max_backups);
+        patch!(enable_sync);
+        patch!(sync_provider);
+        patch!(sync_s3);
+        patch!(sync_interval);
+        patch!(sync_conflict_strategy);
+        patch!(enable_tray_proxies);
+        patch!(enable_tray_traffic);
+        patch!(tray_menu);
+        patch!(enable_system_notification);
+        patch!(expire_reminder_days);
+        patch!(expired_fallback_profile);
+        patch!(enable_clipboard_watch);
+        patch!(clipboard_watch_interval);
+        patch!(clipboard_watch_privacy);
 
         patch!(theme_setting);
         patch!(web_ui_list);
+        patch!(web_ui_port);
+        patch!(default_web_ui);
         patch!(clash_core);
         patch!(hotkeys);
 
@@ -263,6 +869,7 @@ impl IVerge {
         patch!(auto_log_clean);
         patch!(window_size_position);
         patch!(window_size_state);
+        patch!(window_states);
     }
 
     /// 在初始化前尝试拿到单例端口的值
@@ -282,16 +889,16 @@ impl IVerge {
     pub fn get_log_level(&self) -> LevelFilter {
         if let Some(level) = self.app_log_level.as_ref() {
             match level.to_lowercase().as_str() {
-                "silent" => LevelFilter::Off,
-                "error" => LevelFilter::Error,
-                "warn" => LevelFilter::Warn,
-                "info" => LevelFilter::Info,
-                "debug" => LevelFilter::Debug,
-                "trace" => LevelFilter::Trace,
-                _ => LevelFilter::Info,
+                "silent" => LevelFilter::OFF,
+                "error" => LevelFilter::ERROR,
+                "warn" => LevelFilter::WARN,
+                "info" => LevelFilter::INFO,
+                "debug" => LevelFilter::DEBUG,
+                "trace" => LevelFilter::TRACE,
+                _ => LevelFilter::INFO,
             }
         } else {
-            LevelFilter::Info
+            LevelFilter::INFO
         }
     }
 }
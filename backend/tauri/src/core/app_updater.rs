@@ -0,0 +1,415 @@
+//! the self updater of the app, the cores are updated by `updater.rs`
+
+use super::handle;
+use crate::{
+    config::{Config, IVerge, UpdateChannel},
+    feat,
+};
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{updater::UpdateResponse, Wry};
+
+const RELEASE_URL: &str = "https://github.com/keiko233/clash-nyanpasu/releases/download/updater";
+
+/// the mirrors use the proxy manifests, whose download urls go through the mirrors too
+const MIRRORS: [&str; 2] = ["https://mirror.ghproxy.com/", "https://ghproxy.com/"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppUpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    pub body: Option<String>,
+    pub date: Option<String>,
+    /// downloaded and waiting for the exit to be installed
+    pub staged: bool,
+}
+
+enum Staged {
+    /// the bundle is replaced, the new version runs after the restart
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    Installed(String),
+    /// the installer is downloaded and verified, it runs after the app exits
+    #[cfg(target_os = "windows")]
+    Pending(windows::Installer),
+}
+
+impl Staged {
+    fn version(&self) -> &str {
+        match self {
+            Staged::Installed(version) => version,
+            #[cfg(target_os = "windows")]
+            Staged::Pending(installer) => &installer.version,
+        }
+    }
+}
+
+pub struct AppUpdater {
+    /// the update found by the last check
+    latest: Mutex<Option<UpdateResponse<Wry>>>,
+    staged: Mutex<Option<Staged>>,
+}
+
+impl AppUpdater {
+    pub fn global() -> &'static AppUpdater {
+        static UPDATER: OnceCell<AppUpdater> = OnceCell::new();
+
+        UPDATER.get_or_init(|| AppUpdater {
+            latest: Mutex::new(None),
+            staged: Mutex::new(None),
+        })
+    }
+
+    /// check the update of the selected channel, the skipped versions are ignored
+    pub async fn check(&self) -> Result<Option<AppUpdateInfo>> {
+        let app_handle = { handle::Handle::global().app_handle.lock().clone() };
+        let app_handle = app_handle.ok_or(anyhow!("the app is not ready"))?;
+
+        let (channel, skipped, mirror) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.update_channel.unwrap_or_default(),
+                verge.update_skipped_versions.clone().unwrap_or_default(),
+                verge.github_mirror.clone(),
+            )
+        };
+
+        let update = tauri::updater::builder(app_handle)
+            .endpoints(&endpoints(channel, mirror.as_deref()))
+            .version_comparator(move |current, release| {
+                if skipped.contains(&release.version.to_string()) {
+                    return false;
+                }
+                // 从测试版切回稳定版时允许降级
+                match channel {
+                    UpdateChannel::Stable if !current.pre.is_empty() => release.version != current,
+                    _ => release.version > current,
+                }
+            })
+            .check()
+            .await?;
+
+        if !update.is_update_available() {
+            *self.latest.lock() = None;
+            return Ok(None);
+        }
+
+        let version = update.latest_version().to_string();
+        let info = AppUpdateInfo {
+            staged: self.staged_version().as_deref() == Some(version.as_str()),
+            version,
+            current_version: update.current_version().to_string(),
+            channel,
+            body: update.body().cloned(),
+            date: update.date().map(|date| date.to_string()),
+        };
+        *self.latest.lock() = Some(update);
+        Ok(Some(info))
+    }
+
+    async fn latest_or_check(&self) -> Result<UpdateResponse<Wry>> {
+        let latest = self.latest.lock().clone();
+        if let Some(update) = latest {
+            return Ok(update);
+        }
+        self.check().await?;
+        let latest = self.latest.lock().clone();
+        latest.ok_or(anyhow!("no update available"))
+    }
+
+    /// download and install the update, then restart the app
+    pub async fn install_now(&self) -> Result<()> {
+        let update = self.latest_or_check().await?;
+        update.download_and_install().await?;
+
+        let app_handle = { handle::Handle::global().app_handle.lock().clone() };
+        if let Some(app_handle) = app_handle {
+            app_handle.restart();
+        }
+        Ok(())
+    }
+
+    /// keep the update to be installed when the app exits
+    pub async fn stage(&self) -> Result<String> {
+        let update = self.latest_or_check().await?;
+        let version = update.latest_version().to_string();
+        if self.staged_version().as_deref() == Some(version.as_str()) {
+            return Ok(version);
+        }
+
+        // windows 的安装程序会结束当前进程，先下载好，退出时只运行安装程序
+        #[cfg(target_os = "windows")]
+        {
+            let installer = windows::download(&version).await?;
+            *self.staged.lock() = Some(Staged::Pending(installer));
+        }
+        // 其他平台只替换 bundle，重启后才会运行新版本
+        #[cfg(not(target_os = "windows"))]
+        {
+            update.download_and_install().await?;
+            *self.staged.lock() = Some(Staged::Installed(version.clone()));
+        }
+
+        tracing::info!("the update {version} is staged, installed on exit");
+        Ok(version)
+    }
+
+    pub fn staged_version(&self) -> Option<String> {
+        self.staged
+            .lock()
+            .as_ref()
+            .map(|staged| staged.version().to_string())
+    }
+
+    /// called when the app exits, the installer is started without waiting
+    pub fn install_on_exit(&self) {
+        #[cfg(target_os = "windows")]
+        if let Some(Staged::Pending(installer)) = self.staged.lock().take() {
+            tracing::info!("install the update {} on exit", installer.version);
+            if let Err(err) = installer.run() {
+                tracing::error!("failed to run the installer: {err}");
+            }
+        }
+    }
+
+    /// do not notify this version any more
+    pub async fn skip(&self, version: String) -> Result<()> {
+        let mut skipped = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            verge.update_skipped_versions.clone().unwrap_or_default()
+        };
+        if !skipped.contains(&version) {
+            skipped.push(version);
+        }
+        feat::patch_verge(IVerge {
+            update_skipped_versions: Some(skipped),
+            ..IVerge::default()
+        })
+        .await?;
+
+        *self.latest.lock() = None;
+        Ok(())
+    }
+}
+
+/// the configured mirror is tried first, an empty one means github only
+fn endpoints(channel: UpdateChannel, mirror: Option<&str>) -> Vec<String> {
+    let name = match channel {
+        UpdateChannel::Stable => "update",
+        UpdateChannel::Beta => "update-beta",
+        UpdateChannel::Nightly => "update-nightly",
+    };
+    let mirrors = match mirror.map(str::trim) {
+        Some("") => vec![],
+        Some(mirror) => {
+            let mirror = format!("{}/", mirror.trim_end_matches('/'));
+            let mut mirrors = vec![mirror.clone()];
+            mirrors.extend(
+                MIRRORS
+                    .iter()
+                    .map(|m| m.to_string())
+                    .filter(|m| *m != mirror),
+            );
+            mirrors
+        }
+        None => MIRRORS.iter().map(|m| m.to_string()).collect(),
+    };
+    let mut urls = mirrors
+        .iter()
+        .map(|mirror| format!("{mirror}{RELEASE_URL}/{name}-proxy.json"))
+        .collect::<Vec<_>>();
+    urls.push(format!("{RELEASE_URL}/{name}.json"));
+    urls
+}
+
+/// tauri can only download and install in one go, and its installer exits the app at once,
+/// so the installer is fetched and verified here the same way as tauri does
+#[cfg(target_os = "windows")]
+mod windows {
+    use super::endpoints;
+    use crate::{
+        config::{Config, HttpPurpose},
+        core::{handle, http},
+        utils::dirs,
+    };
+    use anyhow::{anyhow, bail, Context, Result};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use minisign_verify::{PublicKey, Signature};
+    use serde::Deserialize;
+    use std::{
+        collections::HashMap,
+        fs,
+        io::{Cursor, Read},
+        path::PathBuf,
+        process::Command,
+    };
+    use zip::ZipArchive;
+
+    #[derive(Deserialize)]
+    struct Manifest {
+        version: String,
+        platforms: HashMap<String, Artifact>,
+    }
+
+    #[derive(Deserialize)]
+    struct Artifact {
+        url: String,
+        signature: String,
+    }
+
+    pub struct Installer {
+        pub version: String,
+        path: PathBuf,
+    }
+
+    impl Installer {
+        pub fn run(&self) -> Result<()> {
+            let is_msi = self.path.extension().is_some_and(|ext| ext == "msi");
+            let mut command = match is_msi {
+                true => {
+                    let mut command = Command::new("msiexec.exe");
+                    command
+                        .arg("/i")
+                        .arg(&self.path)
+                        .args(["/passive", "/promptrestart"]);
+                    command
+                }
+                false => {
+                    let mut command = Command::new(&self.path);
+                    command.arg("/P");
+                    command
+                }
+            };
+            command.spawn()?;
+            Ok(())
+        }
+    }
+
+    /// the target name of the manifest, the same as the tauri updater
+    fn target() -> String {
+        let arch = match std::env::consts::ARCH {
+            "x86" => "i686",
+            arch => arch,
+        };
+        format!("windows-{arch}")
+    }
+
+    async fn fetch_artifact(version: &str) -> Result<Artifact> {
+        let (channel, mirror) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.update_channel.unwrap_or_default(),
+                verge.github_mirror.clone(),
+            )
+        };
+        let client = http::client(HttpPurpose::Updater)?;
+        let mut last_err = anyhow!("no update manifest available");
+        for url in endpoints(channel, mirror.as_deref()) {
+            let manifest = match client.get(&url).await {
+                Ok(response) => match response.error_for_status() {
+                    Ok(response) => response.json::<Manifest>().await.map_err(Into::into),
+                    Err(err) => Err(err.into()),
+                },
+                Err(err) => Err(err),
+            };
+            match manifest {
+                Ok(mut manifest) if manifest.version.trim_start_matches('v') == version => {
+                    return manifest
+                        .platforms
+                        .remove(&target())
+                        .ok_or(anyhow!("no installer for {}", target()));
+                }
+                Ok(manifest) => {
+                    last_err = anyhow!("{url} has the version {}", manifest.version);
+                }
+                Err(err) => last_err = err.context(format!("failed to fetch {url}")),
+            }
+        }
+        Err(last_err)
+    }
+
+    /// the key and the signature are the base64 of the minisign files
+    fn verify_signature(data: &[u8], signature: &str, pubkey: &str) -> Result<()> {
+        let decode = |text: &str| -> Result<String> {
+            Ok(String::from_utf8(STANDARD.decode(text.trim())?)?)
+        };
+        let pubkey = PublicKey::decode(&decode(pubkey)?)?;
+        let signature = Signature::decode(&decode(signature)?)?;
+        pubkey
+            .verify(data, &signature, true)
+            .context("the signature of the installer is invalid")
+    }
+
+    /// the bundles are the installers or the zips of them, `(file name, content)`
+    fn unpack(data: Vec<u8>, url: &str) -> Result<(String, Vec<u8>)> {
+        if !url.ends_with(".zip") {
+            let name = url.rsplit('/').next().unwrap_or_default().to_string();
+            return Ok((name, data));
+        }
+        let mut archive = ZipArchive::new(Cursor::new(data))?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let name = file.enclosed_name().and_then(|path| path.file_name());
+            let Some(name) = name.map(|name| name.to_string_lossy().into_owned()) else {
+                continue;
+            };
+            if name.ends_with(".exe") || name.ends_with(".msi") {
+                let mut content = vec![];
+                file.read_to_end(&mut content)?;
+                return Ok((name, content));
+            }
+        }
+        bail!("no installer in the bundle")
+    }
+
+    pub async fn download(version: &str) -> Result<Installer> {
+        let app_handle = { handle::Handle::global().app_handle.lock().clone() };
+        let app_handle = app_handle.ok_or(anyhow!("the app is not ready"))?;
+        let pubkey = app_handle.config().tauri.updater.pubkey.clone();
+
+        let artifact = fetch_artifact(version).await?;
+        let data = http::client(HttpPurpose::Updater)?
+            .get(&artifact.url)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec();
+        verify_signature(&data, &artifact.signature, &pubkey)?;
+
+        let (name, content) = unpack(data, &artifact.url)?;
+        if !name.ends_with(".exe") && !name.ends_with(".msi") {
+            bail!("unknown installer `{name}`");
+        }
+        // 只保留最新的一个安装程序
+        let dir = dirs::app_updates_dir()?;
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(name);
+        fs::write(&path, content)?;
+
+        Ok(Installer {
+            version: version.to_string(),
+            path,
+        })
+    }
+}
+
+#[test]
+fn test_endpoints() {
+    let urls = endpoints(UpdateChannel::Nightly, None);
+    assert_eq!(urls.len(), 3);
+    assert!(urls[0].ends_with("/updater/update-nightly-proxy.json"));
+    assert!(urls[2].ends_with("/updater/update-nightly.json"));
+    assert!(endpoints(UpdateChannel::Stable, None)[2].ends_with("/updater/update.json"));
+
+    let urls = endpoints(UpdateChannel::Stable, Some("https://ghproxy.com"));
+    assert_eq!(urls.len(), 3);
+    assert!(urls[0].starts_with("https://ghproxy.com/https://github.com/"));
+    assert_eq!(endpoints(UpdateChannel::Stable, Some("")).len(), 1);
+}
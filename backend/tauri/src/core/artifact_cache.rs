@@ -0,0 +1,196 @@
+//! the downloaded core artifacts, stored by the sha256 of the content
+//!
+//! `index.json` maps the download urls to the hashes, the files are named by the hashes.
+//! switching between mihomo and mihomo alpha or installing the same version again reuses
+//! the file, the least recently used ones are evicted beyond the size limit.
+use crate::{config::Config, utils::dirs};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::HashSet, fs, path::Path};
+
+const INDEX_FILE: &str = "index.json";
+
+/// MB
+const DEFAULT_LIMIT: u64 = 200;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CachedArtifact {
+    pub url: String,
+    /// the file name of the release
+    pub artifact: String,
+    pub sha256: String,
+    pub size: u64,
+    /// unix timestamp
+    pub last_used: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactCacheInfo {
+    pub entries: Vec<CachedArtifact>,
+    /// the bytes of the files, the same content is counted once
+    pub size: u64,
+    /// bytes, 0 disables the cache
+    pub limit: u64,
+}
+
+/// the index is read and written by the downloads at the same time
+static INDEX_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn limit() -> u64 {
+    let limit = { Config::verge().latest().artifact_cache_limit };
+    limit.unwrap_or(DEFAULT_LIMIT) * 1024 * 1024
+}
+
+fn load_index(dir: &Path) -> Vec<CachedArtifact> {
+    fs::read(dir.join(INDEX_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(dir: &Path, entries: &[CachedArtifact]) -> Result<()> {
+    fs::write(dir.join(INDEX_FILE), serde_json::to_vec_pretty(entries)?)?;
+    Ok(())
+}
+
+fn total_size(entries: &[CachedArtifact]) -> u64 {
+    let mut seen = HashSet::new();
+    entries
+        .iter()
+        .filter(|entry| seen.insert(&entry.sha256))
+        .map(|entry| entry.size)
+        .sum()
+}
+
+/// drop the least recently used entries until the files fit in the limit
+fn retain_within(entries: &mut Vec<CachedArtifact>, limit: u64) {
+    entries.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+    while entries.len() > 1 && total_size(entries) > limit {
+        entries.pop();
+    }
+    if total_size(entries) > limit {
+        entries.clear();
+    }
+}
+
+/// the cached content of the url, verified by the hash
+pub fn get(url: &str) -> Option<Vec<u8>> {
+    let dir = dirs::app_artifacts_dir().ok()?;
+    let _guard = INDEX_LOCK.lock();
+    let mut entries = load_index(&dir);
+    let entry = entries.iter_mut().find(|entry| entry.url == url)?;
+
+    let data = fs::read(dir.join(&entry.sha256)).ok();
+    let valid = data
+        .as_ref()
+        .is_some_and(|data| hex::encode(Sha256::digest(data)) == entry.sha256);
+    if !valid {
+        tracing::warn!("the cached artifact `{}` is broken", entry.artifact);
+        let _ = fs::remove_file(dir.join(&entry.sha256));
+        entries.retain(|entry| entry.url != url);
+        crate::log_err!(save_index(&dir, &entries));
+        return None;
+    }
+
+    entry.last_used = chrono::Local::now().timestamp();
+    crate::log_err!(save_index(&dir, &entries));
+    data
+}
+
+/// store the downloaded content, the same content is stored once
+pub fn put(url: &str, artifact: &str, data: &[u8]) -> Result<()> {
+    let limit = limit();
+    if limit == 0 || data.len() as u64 > limit {
+        return Ok(());
+    }
+    let dir = dirs::app_artifacts_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    let sha256 = hex::encode(Sha256::digest(data));
+    let _guard = INDEX_LOCK.lock();
+    let path = dir.join(&sha256);
+    if !path.exists() {
+        fs::write(&path, data)?;
+    }
+
+    let mut entries = load_index(&dir);
+    entries.retain(|entry| entry.url != url);
+    entries.push(CachedArtifact {
+        url: url.to_string(),
+        artifact: artifact.to_string(),
+        sha256,
+        size: data.len() as u64,
+        last_used: chrono::Local::now().timestamp(),
+    });
+    retain_within(&mut entries, limit);
+    save_index(&dir, &entries)?;
+
+    // 没有被引用的文件删掉
+    let referenced = entries
+        .iter()
+        .map(|entry| entry.sha256.as_str())
+        .collect::<HashSet<_>>();
+    for file in fs::read_dir(&dir)?.flatten() {
+        let name = file.file_name();
+        let name = name.to_string_lossy();
+        if name != INDEX_FILE && !referenced.contains(name.as_ref()) {
+            let _ = fs::remove_file(file.path());
+        }
+    }
+    Ok(())
+}
+
+pub fn info() -> Result<ArtifactCacheInfo> {
+    let dir = dirs::app_artifacts_dir()?;
+    let entries = {
+        let _guard = INDEX_LOCK.lock();
+        load_index(&dir)
+    };
+    Ok(ArtifactCacheInfo {
+        size: total_size(&entries),
+        entries,
+        limit: limit(),
+    })
+}
+
+/// remove all the cached artifacts, return the reclaimed bytes
+pub fn purge() -> Result<u64> {
+    let dir = dirs::app_artifacts_dir()?;
+    let _guard = INDEX_LOCK.lock();
+    let size = total_size(&load_index(&dir));
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    tracing::info!("the artifact cache is purged, {size} bytes reclaimed");
+    Ok(size)
+}
+
+#[test]
+fn test_retain_within() {
+    let entry = |url: &str, sha256: &str, size: u64, last_used: i64| CachedArtifact {
+        url: url.into(),
+        artifact: url.into(),
+        sha256: sha256.into(),
+        size,
+        last_used,
+    };
+    let mut entries = vec![
+        entry("mihomo", "a", 10, 1),
+        entry("mihomo-mirror", "a", 10, 4),
+        entry("alpha", "b", 10, 3),
+        entry("clash-rs", "c", 10, 2),
+    ];
+    // 相同的内容只算一次
+    assert_eq!(total_size(&entries), 30);
+
+    retain_within(&mut entries, 20);
+    let urls = entries.iter().map(|e| e.url.as_str()).collect::<Vec<_>>();
+    assert_eq!(urls, ["mihomo-mirror", "alpha"]);
+    assert_eq!(total_size(&entries), 20);
+
+    retain_within(&mut entries, 5);
+    assert!(entries.is_empty());
+}
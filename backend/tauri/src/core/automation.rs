@@ -0,0 +1,151 @@
+use super::{handle, network::NetworkState};
+use crate::{
+    config::{AutomationAction, AutomationRule, AutomationTrigger, Config, IVerge},
+    feat,
+};
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveTime, Timelike};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+
+/// evaluate the `automation_rules` on the network changes and the time
+/// the network triggers are evaluated by the network monitor with the `network_rules`,
+/// the time rules are checked by the `automation` job
+pub struct Automation {
+    /// the time rules fired in the current minute
+    fired: Mutex<HashSet<String>>,
+}
+
+impl Automation {
+    pub fn global() -> &'static Automation {
+        static AUTOMATION: OnceCell<Automation> = OnceCell::new();
+
+        AUTOMATION.get_or_init(|| Automation {
+            fired: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn rules() -> Vec<AutomationRule> {
+        let rules = { Config::verge().latest().automation_rules.clone() };
+        rules
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|rule| rule.enable)
+            .collect()
+    }
+
+    pub async fn on_network_changed(&self, current: &NetworkState) {
+        for rule in Self::rules() {
+            let matched = match &rule.trigger {
+                AutomationTrigger::Ssid { ssid } => current.matches(Some(ssid), None),
+                AutomationTrigger::Network { network } => current.matches(None, Some(network)),
+                AutomationTrigger::Time { .. } => false,
+            };
+            if matched {
+                run(&rule).await;
+            }
+        }
+    }
+
+    /// the job runs more than once a minute, fire each rule once
+    pub async fn on_tick(&self) {
+        let now = Local::now();
+        let minute = now.format("%Y-%m-%d %H:%M").to_string();
+        let weekday = now.weekday().number_from_monday() as u8;
+
+        for (index, rule) in Self::rules().into_iter().enumerate() {
+            if !matches_time(&rule.trigger, now.hour(), now.minute(), weekday) {
+                continue;
+            }
+
+            let key = format!("{index}::{}::{minute}", rule.name);
+            {
+                let mut fired = self.fired.lock();
+                fired.retain(|item| item.ends_with(&minute));
+                if !fired.insert(key) {
+                    continue;
+                }
+            }
+            run(&rule).await;
+        }
+    }
+}
+
+fn matches_time(trigger: &AutomationTrigger, hour: u32, minute: u32, weekday: u8) -> bool {
+    match trigger {
+        AutomationTrigger::Time { at, weekdays } => {
+            let at = match NaiveTime::parse_from_str(at, "%H:%M") {
+                Ok(at) => at,
+                Err(_) => return false,
+            };
+            at.hour() == hour
+                && at.minute() == minute
+                && weekdays
+                    .as_ref()
+                    .map_or(true, |days| days.contains(&weekday))
+        }
+        _ => false,
+    }
+}
+
+async fn run(rule: &AutomationRule) {
+    tracing::info!("run the automation rule `{}`", rule.name);
+
+    for action in rule.actions.iter() {
+        if let Err(err) = execute(action).await {
+            tracing::error!("failed to run the automation action {action:?}: {err}");
+        }
+    }
+    handle::Handle::emit("nyanpasu://automation-triggered", rule.name.clone());
+}
+
+async fn execute(action: &AutomationAction) -> Result<()> {
+    match action {
+        AutomationAction::SwitchProfile { uid } => {
+            let current = { Config::profiles().latest().get_current() };
+            if current.as_ref() != Some(uid) {
+                feat::switch_profile(uid.clone()).await?;
+                handle::Handle::refresh_profiles();
+            }
+        }
+        AutomationAction::SetSystemProxy { enable } => {
+            patch_verge(IVerge {
+                enable_system_proxy: Some(*enable),
+                ..IVerge::default()
+            })
+            .await?
+        }
+        AutomationAction::SetTunMode { enable } => {
+            patch_verge(IVerge {
+                enable_tun_mode: Some(*enable),
+                ..IVerge::default()
+            })
+            .await?
+        }
+        AutomationAction::SetMode { mode } => feat::change_clash_mode(mode.to_lowercase()),
+    }
+    Ok(())
+}
+
+async fn patch_verge(patch: IVerge) -> Result<()> {
+    feat::patch_verge(patch).await?;
+    handle::Handle::refresh_verge();
+    Ok(())
+}
+
+#[test]
+fn test_matches_time() {
+    let trigger = AutomationTrigger::Time {
+        at: "01:00".into(),
+        weekdays: Some(vec![1, 2, 3, 4, 5]),
+    };
+    assert!(matches_time(&trigger, 1, 0, 1));
+    assert!(!matches_time(&trigger, 1, 0, 6));
+    assert!(!matches_time(&trigger, 13, 0, 1));
+
+    let trigger = AutomationTrigger::Ssid {
+        ssid: "office".into(),
+    };
+    assert!(!matches_time(&trigger, 1, 0, 1));
+}
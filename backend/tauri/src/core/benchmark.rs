@@ -0,0 +1,121 @@
+//! test the urls of the user across the nodes, e.g. which node is the fastest for youtube
+
+use super::clash_api;
+use anyhow::{bail, Result};
+use futures_util::{stream, StreamExt};
+use serde::Serialize;
+use url::Url;
+
+/// the tests running at the same time
+const CONCURRENCY: usize = 8;
+
+const MAX_URLS: usize = 16;
+const MAX_NODES: usize = 64;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UrlTestCell {
+    /// none if failed
+    pub delay: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UrlTestMatrix {
+    pub urls: Vec<String>,
+    pub nodes: Vec<String>,
+    /// `results[node][url]`
+    pub results: Vec<Vec<UrlTestCell>>,
+    /// the fastest node of each url
+    pub fastest: Vec<Option<String>>,
+}
+
+fn check_input(urls: &[String], nodes: &[String]) -> Result<()> {
+    if urls.is_empty() || nodes.is_empty() {
+        bail!("select at least one url and one node");
+    }
+    if urls.len() > MAX_URLS || nodes.len() > MAX_NODES {
+        bail!("at most {MAX_URLS} urls and {MAX_NODES} nodes in a test");
+    }
+    for url in urls {
+        match Url::parse(url) {
+            Ok(parsed) if ["http", "https"].contains(&parsed.scheme()) => {}
+            _ => bail!("invalid url `{url}`"),
+        }
+    }
+    Ok(())
+}
+
+/// the timeout of each test is in milliseconds
+pub async fn test_url_matrix(
+    urls: Vec<String>,
+    nodes: Vec<String>,
+    timeout: u64,
+) -> Result<UrlTestMatrix> {
+    check_input(&urls, &nodes)?;
+
+    let tasks = nodes
+        .iter()
+        .enumerate()
+        .flat_map(|(row, node)| {
+            urls.iter()
+                .enumerate()
+                .map(move |(col, url)| (row, col, node, url))
+        })
+        .map(|(row, col, node, url)| async move {
+            let cell = match clash_api::test_proxy_delay(node, url, timeout).await {
+                Ok(res) if res.delay > 0 => UrlTestCell {
+                    delay: Some(res.delay),
+                    error: None,
+                },
+                Ok(_) => UrlTestCell {
+                    delay: None,
+                    error: Some("timeout".into()),
+                },
+                Err(err) => UrlTestCell {
+                    delay: None,
+                    error: Some(err.to_string()),
+                },
+            };
+            (row, col, cell)
+        });
+    let cells = stream::iter(tasks)
+        .buffer_unordered(CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    let empty = UrlTestCell {
+        delay: None,
+        error: None,
+    };
+    let mut results = vec![vec![empty; urls.len()]; nodes.len()];
+    for (row, col, cell) in cells {
+        results[row][col] = cell;
+    }
+
+    let fastest = (0..urls.len())
+        .map(|col| {
+            results
+                .iter()
+                .enumerate()
+                .filter_map(|(row, cells)| cells[col].delay.map(|delay| (delay, row)))
+                .min()
+                .map(|(_, row)| nodes[row].clone())
+        })
+        .collect();
+
+    Ok(UrlTestMatrix {
+        urls,
+        nodes,
+        results,
+        fastest,
+    })
+}
+
+#[test]
+fn test_check_input() {
+    let list = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert!(check_input(&list(&["https://www.youtube.com"]), &list(&["HK"])).is_ok());
+    assert!(check_input(&list(&["ftp://a.com"]), &list(&["HK"])).is_err());
+    assert!(check_input(&list(&["https://a.com"]), &[]).is_err());
+}
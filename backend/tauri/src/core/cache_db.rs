@@ -0,0 +1,89 @@
+//! the `cache.db` of the core, the selections and the fake-ip mappings
+//!
+//! the stale mappings survive the restarts if `store-fake-ip` is on, the domains then resolve
+//! to the ips of the previous config. the file is a bbolt db locked by the core, so it is
+//! only removed while the core is stopped.
+use super::{clash_api, handle, CoreManager};
+use crate::config::{ClashCore, Config, StoreState};
+use crate::utils::dirs;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_yaml::Value;
+use std::fs;
+
+const CACHE_FILE: &str = "cache.db";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheInfo {
+    pub exists: bool,
+    /// bytes
+    pub size: u64,
+    /// unix timestamp
+    pub modified: Option<i64>,
+    /// the `profile` of the running config
+    pub store: StoreState,
+    /// whether the dns is in the fake-ip mode
+    pub fake_ip: bool,
+}
+
+pub fn info() -> Result<CacheInfo> {
+    let path = dirs::app_home_dir()?.join(CACHE_FILE);
+    let meta = fs::metadata(&path).ok();
+    let modified = meta
+        .as_ref()
+        .and_then(|meta| meta.modified().ok())
+        .map(|time| chrono::DateTime::<chrono::Local>::from(time).timestamp());
+
+    let (store, fake_ip) = {
+        let runtime = Config::runtime();
+        let runtime = runtime.latest();
+        match runtime.config.as_ref() {
+            Some(config) => (
+                StoreState::from_config(config),
+                config
+                    .get("dns")
+                    .and_then(|dns| dns.get("enhanced-mode"))
+                    .and_then(Value::as_str)
+                    == Some("fake-ip"),
+            ),
+            None => (StoreState::from_config(&Default::default()), false),
+        }
+    };
+
+    Ok(CacheInfo {
+        exists: meta.is_some(),
+        size: meta.map(|meta| meta.len()).unwrap_or(0),
+        modified,
+        store,
+        fake_ip,
+    })
+}
+
+/// drop the fake-ip mappings of the running core, mihomo only
+pub async fn flush_fakeip() -> Result<()> {
+    let core = { Config::verge().latest().clash_core.clone() }.unwrap_or_default();
+    if !matches!(core, ClashCore::Mihomo | ClashCore::MihomoAlpha) {
+        bail!("flushing the fake-ip cache is only supported by mihomo");
+    }
+    clash_api::flush_fakeip().await?;
+    Ok(())
+}
+
+/// stop the core, remove the file and start the core again
+pub async fn clear() -> Result<()> {
+    let _ = tauri::async_runtime::spawn_blocking(|| CoreManager::global().stop_core()).await;
+
+    let path = dirs::app_home_dir()?.join(CACHE_FILE);
+    let removed = match fs::remove_file(&path) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    };
+
+    // 删不掉也要把内核拉起来
+    CoreManager::global().run_core().await?;
+    handle::Handle::refresh_clash();
+    removed?;
+    tracing::info!("the cache.db is cleared");
+    Ok(())
+}
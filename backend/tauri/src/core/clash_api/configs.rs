@@ -0,0 +1,44 @@
+use super::{ClashClient, Result, WriteOp, WriteQueue};
+use reqwest::Method;
+use serde_yaml::Mapping;
+use std::collections::HashMap;
+
+/// GET /configs
+pub async fn get_configs() -> Result<Mapping> {
+    let client = ClashClient::from_config()?;
+    ClashClient::json(client.request(Method::GET, &["configs"])?).await
+}
+
+/// PUT /configs
+/// path 是绝对路径
+pub async fn put_configs(path: &str) -> Result<()> {
+    let client = ClashClient::from_config()?;
+
+    let mut data = HashMap::new();
+    data.insert("path", path);
+
+    ClashClient::send(client.request(Method::PUT, &["configs"])?.json(&data)).await?;
+    Ok(())
+}
+
+/// PATCH /configs
+/// 排队执行，等待中的 patch 会合并成一个
+pub async fn patch_configs(config: &Mapping) -> Result<()> {
+    WriteQueue::global()
+        .push(WriteOp::PatchConfigs(config.clone()))
+        .await
+}
+
+pub(super) async fn send_patch_configs(config: &Mapping) -> Result<()> {
+    let client = ClashClient::from_config()?;
+    ClashClient::send(client.request(Method::PATCH, &["configs"])?.json(config)).await?;
+    Ok(())
+}
+
+/// POST /configs/geo
+/// 更新 geo 数据库，仅 meta 内核支持
+pub async fn update_geo() -> Result<()> {
+    let client = ClashClient::from_config()?;
+    ClashClient::send(client.request(Method::POST, &["configs", "geo"])?).await?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use super::{ClashClient, Result};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConnectionMetadata {
+    pub network: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    pub host: String,
+    #[serde(rename = "sourceIP")]
+    pub source_ip: String,
+    pub source_port: String,
+    #[serde(rename = "destinationIP")]
+    pub destination_ip: String,
+    pub destination_port: String,
+    pub process: Option<String>,
+    pub process_path: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionItem {
+    pub id: String,
+    pub metadata: ConnectionMetadata,
+    #[serde(default)]
+    pub upload: u64,
+    #[serde(default)]
+    pub download: u64,
+    pub start: String,
+    #[serde(default)]
+    pub chains: Vec<String>,
+    #[serde(default)]
+    pub rule: String,
+    #[serde(default)]
+    pub rule_payload: String,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionsRes {
+    #[serde(default)]
+    pub download_total: u64,
+    #[serde(default)]
+    pub upload_total: u64,
+    /// `null` when there is no connection
+    #[serde(default)]
+    pub connections: Option<Vec<ConnectionItem>>,
+}
+
+/// GET /connections
+pub async fn get_connections() -> Result<ConnectionsRes> {
+    let client = ClashClient::from_config()?;
+    ClashClient::json(client.request(Method::GET, &["connections"])?).await
+}
+
+/// DELETE /connections
+pub async fn close_all_connections() -> Result<()> {
+    let client = ClashClient::from_config()?;
+    ClashClient::send(client.request(Method::DELETE, &["connections"])?).await?;
+    Ok(())
+}
+
+/// DELETE /connections/{id}
+pub async fn close_connection(id: &str) -> Result<()> {
+    let client = ClashClient::from_config()?;
+    ClashClient::send(client.request(Method::DELETE, &["connections", id])?).await?;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use super::{ClashClient, Result};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct DnsAnswer {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: u16,
+    #[serde(rename = "TTL")]
+    pub ttl: u32,
+    pub data: String,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct DnsQueryRes {
+    #[serde(rename = "Status")]
+    pub status: u16,
+    #[serde(rename = "Answer", default)]
+    pub answer: Vec<DnsAnswer>,
+}
+
+/// GET /dns/query
+/// 使用内核的 dns 解析，仅 meta 内核支持
+pub async fn query_dns(name: &str, r#type: Option<&str>) -> Result<DnsQueryRes> {
+    let client = ClashClient::from_config()?;
+    let builder = client
+        .request(Method::GET, &["dns", "query"])?
+        .query(&[("name", name), ("type", r#type.unwrap_or("A"))]);
+    ClashClient::json(builder).await
+}
+
+/// POST /cache/fakeip/flush
+pub async fn flush_fakeip() -> Result<()> {
+    let client = ClashClient::from_config()?;
+    ClashClient::send(client.request(Method::POST, &["cache", "fakeip", "flush"])?).await?;
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! opt-in records of the requests to the external controller
+use crate::config::Config;
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// keep the latest records only
+const RECORDS_LEN: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestSource {
+    /// the backend, e.g. the tray and the automations
+    App,
+    /// the requests sent by the frontend directly
+    Webview,
+    /// the third-party dashboards through the controller proxy
+    External,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RequestRecord {
+    /// unix timestamp in milliseconds
+    #[serde(default)]
+    pub time: i64,
+    pub source: RequestSource,
+    pub method: String,
+    pub path: String,
+    /// until the response headers are received
+    pub duration_ms: u64,
+    /// none if the request is not responded, e.g. timeout
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RequestQuery {
+    /// the path contains it
+    pub path: Option<String>,
+    /// slower than it
+    pub min_duration_ms: Option<u64>,
+    pub only_failed: Option<bool>,
+    pub limit: Option<usize>,
+}
+
+pub struct Inspector {
+    records: Mutex<VecDeque<RequestRecord>>,
+}
+
+impl Inspector {
+    pub fn global() -> &'static Inspector {
+        static INSPECTOR: OnceCell<Inspector> = OnceCell::new();
+
+        INSPECTOR.get_or_init(|| Inspector {
+            records: Mutex::new(VecDeque::with_capacity(RECORDS_LEN)),
+        })
+    }
+
+    pub fn enabled() -> bool {
+        Config::verge()
+            .latest()
+            .enable_api_inspector
+            .unwrap_or(false)
+    }
+
+    /// ignored when the inspector is disabled
+    pub fn record(&self, mut record: RequestRecord) {
+        if !Self::enabled() {
+            return;
+        }
+        if record.time == 0 {
+            record.time = Local::now().timestamp_millis();
+        }
+
+        let mut records = self.records.lock();
+        if records.len() >= RECORDS_LEN {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// the latest first
+    pub fn query(&self, query: &RequestQuery) -> Vec<RequestRecord> {
+        let limit = query.limit.unwrap_or(RECORDS_LEN);
+        self.records
+            .lock()
+            .iter()
+            .rev()
+            .filter(|r| match query.path.as_ref() {
+                Some(path) => r.path.contains(path.as_str()),
+                None => true,
+            })
+            .filter(|r| r.duration_ms >= query.min_duration_ms.unwrap_or(0))
+            .filter(|r| {
+                !query.only_failed.unwrap_or(false)
+                    || r.error.is_some()
+                    || r.status.map_or(true, |s| s >= 400)
+            })
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().clear();
+    }
+}
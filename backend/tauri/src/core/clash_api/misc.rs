@@ -0,0 +1,66 @@
+use super::{ClashClient, Result};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// the upgrade will download the new core
+const UPGRADE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct VersionRes {
+    pub version: String,
+    #[serde(default)]
+    pub meta: bool,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct MemoryRes {
+    pub inuse: u64,
+    pub oslimit: u64,
+}
+
+/// GET /version
+pub async fn get_version() -> Result<VersionRes> {
+    let client = ClashClient::from_config()?;
+    ClashClient::json(client.request(Method::GET, &["version"])?).await
+}
+
+/// GET /memory
+/// 这个接口是一个持续的流，只取第一帧
+pub async fn get_memory() -> Result<MemoryRes> {
+    let client = ClashClient::from_config()?;
+    let mut response = ClashClient::send(client.request(Method::GET, &["memory"])?).await?;
+
+    let mut buf = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        buf.extend_from_slice(&chunk);
+
+        if let Some(end) = buf.iter().position(|b| *b == b'\n') {
+            buf.truncate(end);
+            break;
+        }
+    }
+
+    Ok(serde_json::from_slice(&buf)?)
+}
+
+/// POST /upgrade
+/// 内核自我更新，仅 meta 内核支持
+pub async fn upgrade_core() -> Result<()> {
+    let client = ClashClient::from_config()?;
+    let builder = client
+        .request(Method::POST, &["upgrade"])?
+        .timeout(UPGRADE_TIMEOUT);
+    ClashClient::send(builder).await?;
+    Ok(())
+}
+
+/// POST /upgrade/ui
+pub async fn upgrade_ui() -> Result<()> {
+    let client = ClashClient::from_config()?;
+    let builder = client
+        .request(Method::POST, &["upgrade", "ui"])?
+        .timeout(UPGRADE_TIMEOUT);
+    ClashClient::send(builder).await?;
+    Ok(())
+}
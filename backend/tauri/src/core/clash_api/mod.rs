@@ -0,0 +1,261 @@
+//! typed client of the clash external controller
+
+mod configs;
+mod connections;
+mod dns;
+mod inspector;
+mod misc;
+mod providers;
+mod proxies;
+mod write_queue;
+
+pub use configs::*;
+pub use connections::*;
+pub use dns::*;
+pub use inspector::*;
+pub use misc::*;
+pub use providers::*;
+pub use proxies::*;
+pub use write_queue::*;
+
+use crate::config::Config;
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// 默认的请求超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("failed to build the controller url: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+
+    #[error("invalid secret of the controller")]
+    InvalidSecret,
+
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("unauthorized, please check the secret of the controller")]
+    Unauthorized,
+
+    #[error("invalid response: {0}")]
+    Decode(#[from] serde_json::Error),
+
+    #[error("`{0}` not found")]
+    NotFound(String),
+
+    #[error("failed with status \"{status}\": {message}")]
+    Status { status: u16, message: String },
+
+    #[error("{0}")]
+    Queue(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// the error body of the controller
+#[derive(Deserialize)]
+struct ErrorRes {
+    message: String,
+}
+
+/// 内核控制器的客户端，每次请求前根据 clash 配置重新生成
+/// 端口或者 secret 变化后不需要额外处理
+pub struct ClashClient {
+    base: Url,
+    secret: Option<String>,
+    client: reqwest::Client,
+}
+
+impl ClashClient {
+    pub fn new(server: &str, secret: Option<String>) -> Result<Self> {
+        let base = Url::parse(&format!("http://{server}/"))?;
+        let client = reqwest::ClientBuilder::new()
+            .no_proxy()
+            .timeout(DEFAULT_TIMEOUT)
+            .build()?;
+        let secret = secret.filter(|s| !s.is_empty());
+
+        Ok(Self {
+            base,
+            secret,
+            client,
+        })
+    }
+
+    /// 根据clash info获取clash服务地址和secret
+    pub fn from_config() -> Result<Self> {
+        let info = { Config::clash().data().get_client_info() };
+        Self::new(&info.server, info.secret)
+    }
+
+    /// the segments will be percent-encoded, e.g. the name of proxies
+    pub fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = self.base.clone();
+        url.path_segments_mut()
+            .map_err(|_| url::ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    pub fn request(&self, method: Method, segments: &[&str]) -> Result<RequestBuilder> {
+        let mut builder = self.client.request(method, self.url(segments)?);
+
+        if let Some(secret) = self.secret.as_ref() {
+            let value = format!("Bearer {secret}")
+                .parse::<reqwest::header::HeaderValue>()
+                .map_err(|_| Error::InvalidSecret)?;
+            builder = builder.header(reqwest::header::AUTHORIZATION, value);
+        }
+
+        Ok(builder)
+    }
+
+    /// send the request and convert the non-2xx status to `Error`
+    pub async fn send(builder: RequestBuilder) -> Result<Response> {
+        if !Inspector::enabled() {
+            return Self::check(builder.send().await?).await;
+        }
+
+        let (client, request) = builder.build_split();
+        let request = request?;
+        let method = request.method().to_string();
+        let path = request.url().path().to_owned();
+
+        let now = Instant::now();
+        let result = client.execute(request).await;
+        Inspector::global().record(RequestRecord {
+            time: 0,
+            source: RequestSource::App,
+            method,
+            path,
+            duration_ms: now.elapsed().as_millis() as u64,
+            status: result.as_ref().ok().map(|res| res.status().as_u16()),
+            error: result.as_ref().err().map(|err| err.to_string()),
+        });
+        Self::check(result?).await
+    }
+
+    async fn check(response: Response) -> Result<Response> {
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let url = response.url().path().to_owned();
+        let message = match response.text().await {
+            Ok(text) => serde_json::from_str::<ErrorRes>(&text)
+                .map(|res| res.message)
+                .unwrap_or(text),
+            Err(err) => err.to_string(),
+        };
+
+        Err(match status {
+            StatusCode::UNAUTHORIZED => Error::Unauthorized,
+            StatusCode::NOT_FOUND => Error::NotFound(url),
+            status => Error::Status {
+                status: status.as_u16(),
+                message,
+            },
+        })
+    }
+
+    pub async fn json<T: DeserializeOwned>(builder: RequestBuilder) -> Result<T> {
+        Ok(Self::send(builder).await?.json::<T>().await?)
+    }
+}
+
+/// 根据clash info获取websocket的地址
+/// websocket 不方便带请求头，secret 通过 token 参数传递
+pub fn clash_ws_url(path: &str, query: &[(&str, &str)]) -> Result<Url> {
+    let info = { Config::clash().data().get_client_info() };
+
+    let mut url = Url::parse(&format!("ws://{}/{path}", info.server))?;
+    {
+        let mut pairs = url.query_pairs_mut();
+        pairs.extend_pairs(query);
+
+        if let Some(secret) = info.secret.filter(|s| !s.is_empty()) {
+            pairs.append_pair("token", &secret);
+        }
+    }
+
+    Ok(url)
+}
+
+/// 缩短clash的日志
+pub fn parse_log(log: String) -> String {
+    if log.starts_with("time=") && log.len() > 33 {
+        return log[33..].to_owned();
+    }
+    if log.len() > 9 {
+        return log[9..].to_owned();
+    }
+    log
+}
+
+/// 缩短clash -t的错误输出
+/// 仅适配 clash p核 8-26、clash meta 1.13.1
+pub fn parse_check_output(log: String) -> String {
+    let t = log.find("time=");
+    let m = log.find("msg=");
+    let mr = log.rfind('"');
+
+    if let (Some(_), Some(m), Some(mr)) = (t, m, mr) {
+        let e = match log.find("level=error msg=") {
+            Some(e) => e + 17,
+            None => m + 5,
+        };
+
+        if mr > m {
+            return log[e..mr].to_owned();
+        }
+    }
+
+    let l = log.find("error=");
+    let r = log.find("path=").or(Some(log.len()));
+
+    if let (Some(l), Some(r)) = (l, r) {
+        return log[(l + 6)..(r - 1)].to_owned();
+    }
+
+    log
+}
+
+#[test]
+fn test_client_url() {
+    let client = ClashClient::new("127.0.0.1:9090", Some("".into())).unwrap();
+    assert!(client.secret.is_none());
+
+    let url = client.url(&["proxies", "🇭🇰 HK/01", "delay"]).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "http://127.0.0.1:9090/proxies/%F0%9F%87%AD%F0%9F%87%B0%20HK%2F01/delay"
+    );
+}
+
+#[test]
+fn test_parse_check_output() {
+    let str1 = r#"xxxx\n time="2022-11-18T20:42:58+08:00" level=error msg="proxy 0: 'alpn' expected type 'string', got unconvertible type '[]interface {}'""#;
+    let str2 = r#"20:43:49 ERR [Config] configuration file test failed error=proxy 0: unsupport proxy type: hysteria path=xxx"#;
+    let str3 = r#"
+    "time="2022-11-18T21:38:01+08:00" level=info msg="Start initial configuration in progress"
+    time="2022-11-18T21:38:01+08:00" level=error msg="proxy 0: 'alpn' expected type 'string', got unconvertible type '[]interface {}'"
+    configuration file xxx\n
+    "#;
+
+    let res1 = parse_check_output(str1.into());
+    let res2 = parse_check_output(str2.into());
+    let res3 = parse_check_output(str3.into());
+
+    println!("res1: {res1}");
+    println!("res2: {res2}");
+    println!("res3: {res3}");
+
+    assert_eq!(res1, res3);
+}
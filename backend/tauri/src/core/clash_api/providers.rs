@@ -0,0 +1,94 @@
+use super::{ClashClient, ProxyItem, Result};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+/// the provider update may download the remote file
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionInfo {
+    #[serde(default)]
+    pub upload: u64,
+    #[serde(default)]
+    pub download: u64,
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub expire: u64,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyProviderItem {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    pub vehicle_type: String,
+    #[serde(default)]
+    pub proxies: Vec<ProxyItem>,
+    pub updated_at: Option<String>,
+    pub subscription_info: Option<SubscriptionInfo>,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleProviderItem {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    pub vehicle_type: String,
+    pub behavior: String,
+    #[serde(default)]
+    pub rule_count: u64,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct ProvidersRes<T> {
+    pub providers: HashMap<String, T>,
+}
+
+/// GET /providers/proxies
+pub async fn get_proxy_providers() -> Result<ProvidersRes<ProxyProviderItem>> {
+    let client = ClashClient::from_config()?;
+    ClashClient::json(client.request(Method::GET, &["providers", "proxies"])?).await
+}
+
+/// PUT /providers/proxies/{name}
+pub async fn update_proxy_provider(name: &str) -> Result<()> {
+    let client = ClashClient::from_config()?;
+    let builder = client
+        .request(Method::PUT, &["providers", "proxies", name])?
+        .timeout(UPDATE_TIMEOUT);
+    ClashClient::send(builder).await?;
+    Ok(())
+}
+
+/// GET /providers/proxies/{name}/healthcheck
+pub async fn healthcheck_proxy_provider(name: &str) -> Result<()> {
+    let client = ClashClient::from_config()?;
+    let builder = client
+        .request(Method::GET, &["providers", "proxies", name, "healthcheck"])?
+        .timeout(UPDATE_TIMEOUT);
+    ClashClient::send(builder).await?;
+    Ok(())
+}
+
+/// GET /providers/rules
+/// 仅 meta 内核支持
+pub async fn get_rule_providers() -> Result<ProvidersRes<RuleProviderItem>> {
+    let client = ClashClient::from_config()?;
+    ClashClient::json(client.request(Method::GET, &["providers", "rules"])?).await
+}
+
+/// PUT /providers/rules/{name}
+pub async fn update_rule_provider(name: &str) -> Result<()> {
+    let client = ClashClient::from_config()?;
+    let builder = client
+        .request(Method::PUT, &["providers", "rules", name])?
+        .timeout(UPDATE_TIMEOUT);
+    ClashClient::send(builder).await?;
+    Ok(())
+}
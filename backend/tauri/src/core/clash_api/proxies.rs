@@ -0,0 +1,118 @@
+use super::{ClashClient, Result, WriteOp, WriteQueue};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+const DEFAULT_TEST_URL: &str = "http://www.gstatic.com/generate_204";
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct DelayRes {
+    pub delay: u64,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct DelayHistory {
+    pub time: String,
+    pub delay: u64,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct ProxyItem {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    #[serde(default)]
+    pub udp: bool,
+    pub now: Option<String>,
+    pub all: Option<Vec<String>>,
+    #[serde(default)]
+    pub history: Vec<DelayHistory>,
+    /// the group is not shown, e.g. the exit probe
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct ProxiesRes {
+    pub proxies: HashMap<String, ProxyItem>,
+}
+
+fn test_url(url: Option<String>) -> String {
+    url.filter(|s| !s.is_empty())
+        .unwrap_or_else(|| DEFAULT_TEST_URL.into())
+}
+
+/// GET /proxies
+pub async fn get_proxies() -> Result<ProxiesRes> {
+    let client = ClashClient::from_config()?;
+    ClashClient::json(client.request(Method::GET, &["proxies"])?).await
+}
+
+/// GET /proxies/{name}
+pub async fn get_proxy(name: &str) -> Result<ProxyItem> {
+    let client = ClashClient::from_config()?;
+    ClashClient::json(client.request(Method::GET, &["proxies", name])?).await
+}
+
+/// PUT /proxies/{group}
+/// 切换分组的节点，和其他写操作一起排队执行
+pub async fn update_proxy(group: &str, name: &str) -> Result<()> {
+    let op = WriteOp::SelectProxy {
+        group: group.into(),
+        name: name.into(),
+    };
+    WriteQueue::global().push(op).await
+}
+
+pub(super) async fn put_proxy(group: &str, name: &str) -> Result<()> {
+    let client = ClashClient::from_config()?;
+
+    let mut data = HashMap::new();
+    data.insert("name", name);
+
+    let builder = client
+        .request(Method::PUT, &["proxies", group])?
+        .json(&data);
+    ClashClient::send(builder).await?;
+    Ok(())
+}
+
+/// GET /proxies/{name}/delay
+/// 获取代理延迟
+pub async fn get_proxy_delay(name: String, test_url: Option<String>) -> Result<DelayRes> {
+    test_proxy_delay(&name, &self::test_url(test_url), 10000).await
+}
+
+/// the timeout of the test is in milliseconds
+pub async fn test_proxy_delay(name: &str, test_url: &str, timeout: u64) -> Result<DelayRes> {
+    let client = ClashClient::from_config()?;
+
+    // 请求的超时要比测速的超时长一点
+    let builder = client
+        .request(Method::GET, &["proxies", name, "delay"])?
+        .timeout(Duration::from_millis(timeout) + Duration::from_secs(2))
+        .query(&[
+            ("timeout", timeout.to_string()),
+            ("url", test_url.to_string()),
+        ]);
+
+    ClashClient::json(builder).await
+}
+
+/// GET /group/{name}/delay
+/// 测试分组内所有节点的延迟，仅 meta 内核支持
+pub async fn get_group_delay(
+    name: &str,
+    test_url: Option<String>,
+    timeout: u64,
+) -> Result<HashMap<String, u64>> {
+    let client = ClashClient::from_config()?;
+    let test_url = self::test_url(test_url);
+
+    let builder = client
+        .request(Method::GET, &["group", name, "delay"])?
+        .timeout(Duration::from_millis(timeout) + Duration::from_secs(2))
+        .query(&[("timeout", timeout.to_string()), ("url", test_url)]);
+
+    ClashClient::json(builder).await
+}
@@ -0,0 +1,170 @@
+//! the writes to the controller are applied one by one in the order they are queued
+//! the pending writes to the same target are coalesced, e.g. the latest selection of a group wins
+use super::{configs, proxies, Error, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde_yaml::{Mapping, Value};
+use std::collections::VecDeque;
+use tokio::sync::{oneshot, Notify};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteOp {
+    /// PUT /proxies/{group}
+    SelectProxy { group: String, name: String },
+    /// PATCH /configs
+    PatchConfigs(Mapping),
+}
+
+impl WriteOp {
+    fn target_eq(&self, other: &WriteOp) -> bool {
+        match (self, other) {
+            (WriteOp::SelectProxy { group, .. }, WriteOp::SelectProxy { group: other, .. }) => {
+                group == other
+            }
+            (WriteOp::PatchConfigs(_), WriteOp::PatchConfigs(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// merge the later write of the same target into this one
+    fn coalesce(&mut self, other: &WriteOp) {
+        match (self, other) {
+            (WriteOp::SelectProxy { name, .. }, WriteOp::SelectProxy { name: other, .. }) => {
+                *name = other.clone();
+            }
+            (WriteOp::PatchConfigs(config), WriteOp::PatchConfigs(other)) => {
+                merge_mapping(config, other);
+            }
+            _ => {}
+        }
+    }
+
+    async fn apply(&self) -> Result<()> {
+        match self {
+            WriteOp::SelectProxy { group, name } => proxies::put_proxy(group, name).await,
+            WriteOp::PatchConfigs(config) => configs::send_patch_configs(config).await,
+        }
+    }
+}
+
+/// 嵌套的字段也按 key 合并
+fn merge_mapping(target: &mut Mapping, patch: &Mapping) {
+    for (key, value) in patch.iter() {
+        match (target.get_mut(key), value) {
+            (Some(Value::Mapping(target)), Value::Mapping(value)) => merge_mapping(target, value),
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+struct Job {
+    op: WriteOp,
+    waiters: Vec<oneshot::Sender<Result<()>>>,
+}
+
+pub struct WriteQueue {
+    pending: Mutex<VecDeque<Job>>,
+    notify: Notify,
+}
+
+impl WriteQueue {
+    pub fn global() -> &'static WriteQueue {
+        static WRITE_QUEUE: OnceCell<WriteQueue> = OnceCell::new();
+
+        WRITE_QUEUE.get_or_init(|| {
+            tauri::async_runtime::spawn(async {
+                WriteQueue::global().run().await;
+            });
+
+            WriteQueue {
+                pending: Mutex::new(VecDeque::new()),
+                notify: Notify::new(),
+            }
+        })
+    }
+
+    /// queue the write and wait until it is applied
+    pub async fn push(&self, op: WriteOp) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut pending = self.pending.lock();
+            // 正在执行的已经出队了，只合并还在等待的
+            match pending.iter_mut().find(|job| job.op.target_eq(&op)) {
+                Some(job) => {
+                    job.op.coalesce(&op);
+                    job.waiters.push(tx);
+                }
+                None => pending.push_back(Job {
+                    op,
+                    waiters: vec![tx],
+                }),
+            }
+        }
+        self.notify.notify_one();
+
+        rx.await
+            .unwrap_or_else(|_| Err(Error::Queue("the write queue is closed".into())))
+    }
+
+    async fn run(&self) {
+        loop {
+            let job = self.pending.lock().pop_front();
+            let Some(Job { op, waiters }) = job else {
+                self.notify.notified().await;
+                continue;
+            };
+
+            let result = op.apply().await;
+            if let Err(err) = result.as_ref() {
+                tracing::warn!("failed to apply the controller write {op:?}: {err}");
+            }
+
+            // Error 不能 clone，其余的调用方只拿到错误信息
+            let message = result.as_ref().err().map(|err| err.to_string());
+            let mut result = Some(result);
+            for tx in waiters {
+                let res = result.take().unwrap_or_else(|| match message.as_ref() {
+                    Some(message) => Err(Error::Queue(message.clone())),
+                    None => Ok(()),
+                });
+                let _ = tx.send(res);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_coalesce_writes() {
+    let mut select = WriteOp::SelectProxy {
+        group: "Proxy".into(),
+        name: "HK".into(),
+    };
+    let other_group = WriteOp::SelectProxy {
+        group: "Auto".into(),
+        name: "JP".into(),
+    };
+    assert!(!select.target_eq(&other_group));
+
+    let later = WriteOp::SelectProxy {
+        group: "Proxy".into(),
+        name: "US".into(),
+    };
+    assert!(select.target_eq(&later));
+    select.coalesce(&later);
+    assert_eq!(select, later);
+
+    let mut patch = WriteOp::PatchConfigs(
+        serde_yaml::from_str("{mode: rule, tun: {enable: true, stack: gvisor}}").unwrap(),
+    );
+    patch.coalesce(&WriteOp::PatchConfigs(
+        serde_yaml::from_str("{mode: global, tun: {stack: system}}").unwrap(),
+    ));
+    assert_eq!(
+        patch,
+        WriteOp::PatchConfigs(
+            serde_yaml::from_str("{mode: global, tun: {enable: true, stack: system}}").unwrap()
+        )
+    );
+}
@@ -0,0 +1,192 @@
+use super::{deep_link, handle};
+use crate::{
+    config::{Config, ImportStrategy, PrfItem},
+    feat,
+    utils::{
+        converter::{self, Detected},
+        i18n::{t, t_args},
+    },
+};
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tokio::{sync::oneshot, time::sleep};
+use wry::application::clipboard::Clipboard;
+
+/// the content longer than this is not parsed
+const MAX_LENGTH: usize = 64 * 1024;
+
+/// watch the clipboard for the share links and the subscription urls
+/// only the hash of the latest content is kept
+pub struct ClipboardWatcher {
+    latest: Mutex<Option<u64>>,
+
+    /// do not stack the dialogs
+    prompting: AtomicBool,
+
+    running: AtomicBool,
+}
+
+impl ClipboardWatcher {
+    pub fn global() -> &'static ClipboardWatcher {
+        static CLIPBOARD_WATCHER: OnceCell<ClipboardWatcher> = OnceCell::new();
+
+        CLIPBOARD_WATCHER.get_or_init(|| ClipboardWatcher {
+            latest: Mutex::new(None),
+            prompting: AtomicBool::new(false),
+            running: AtomicBool::new(false),
+        })
+    }
+
+    pub fn enabled() -> bool {
+        let enable = { Config::verge().latest().enable_clipboard_watch };
+        enable.unwrap_or(false)
+    }
+
+    /// the loop keeps running after disabled, the clipboard is not read
+    pub fn start(&'static self) {
+        if !Self::enabled() || self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        tauri::async_runtime::spawn(async move {
+            // 启用前已经在剪贴板里的内容不提示
+            let mut watching = false;
+            loop {
+                sleep(interval()).await;
+
+                if !Self::enabled() {
+                    watching = false;
+                    continue;
+                }
+                if self.prompting.load(Ordering::SeqCst) {
+                    continue;
+                }
+
+                let text = read_text().await.unwrap_or_default();
+                let hash = hash(&text);
+                let changed = self.latest.lock().replace(hash) != Some(hash);
+                let seeded = std::mem::replace(&mut watching, true);
+                if !changed || !seeded || text.len() > MAX_LENGTH {
+                    continue;
+                }
+
+                if let Some(detected) = converter::detect(&text) {
+                    self.prompting.store(true, Ordering::SeqCst);
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(err) = prompt(detected).await {
+                            tracing::error!("failed to import from the clipboard: {err}");
+                            handle::Handle::notice_message(
+                                "import_profile::error",
+                                format!("{err}"),
+                            );
+                        }
+                        self.prompting.store(false, Ordering::SeqCst);
+                    });
+                }
+            }
+        });
+    }
+}
+
+fn interval() -> Duration {
+    let interval = { Config::verge().latest().clipboard_watch_interval };
+    Duration::from_secs(interval.unwrap_or(2).max(1))
+}
+
+fn hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// the clipboard should be accessed on the main thread
+async fn read_text() -> Option<String> {
+    let app_handle = { handle::Handle::global().app_handle.lock().clone()? };
+
+    let (tx, rx) = oneshot::channel();
+    app_handle
+        .run_on_main_thread(move || {
+            let _ = tx.send(Clipboard::new().read_text());
+        })
+        .ok()?;
+    rx.await.ok().flatten()
+}
+
+/// notify the user and ask before importing
+async fn prompt(detected: Detected) -> Result<()> {
+    let privacy = { Config::verge().latest().clipboard_watch_privacy };
+    let privacy = privacy.unwrap_or(true);
+
+    let (title, mut message) = match &detected {
+        Detected::Proxies(proxies) => (
+            t("Import Proxies"),
+            t_args(
+                "Found {count} proxy share link(s) in the clipboard, import them as a new profile?",
+                &[("count", &proxies.len())],
+            ),
+        ),
+        Detected::Subscription(url) => {
+            let duplicate = {
+                let profiles = Config::profiles();
+                let profiles = profiles.latest();
+                profiles.find_by_url(url).map(|item| item.name.clone())
+            };
+            let message = match duplicate {
+                Some(name) => t_args(
+                    "Found the subscription url of \"{name}\" in the clipboard, update it?",
+                    &[("name", &name.unwrap_or_default())],
+                ),
+                None => t("Found a subscription url in the clipboard, import it as a new profile?"),
+            };
+            (t("Import Profile"), message)
+        }
+    };
+    handle::Handle::notify(&title, message.clone());
+
+    if !privacy {
+        let detail = match &detected {
+            Detected::Proxies(proxies) => proxies
+                .iter()
+                .filter_map(|proxy| proxy.get("name").and_then(|name| name.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Detected::Subscription(url) => url.clone(),
+        };
+        message = format!("{message}\n\n{detail}");
+    }
+
+    if !deep_link::confirm(&title, message).await {
+        tracing::info!("the clipboard import is cancelled");
+        return Ok(());
+    }
+    import(detected).await
+}
+
+async fn import(detected: Detected) -> Result<()> {
+    let item = match detected {
+        Detected::Proxies(proxies) => {
+            let desc = format!("{} proxies imported from the clipboard", proxies.len());
+            let file_data = converter::to_profile(proxies)?;
+            PrfItem::from_local("Clipboard".into(), desc, Some(file_data))?
+        }
+        Detected::Subscription(url) => {
+            // 已经导入过的订阅只更新，不再重复导入
+            feat::import_profile(url, None, Some(ImportStrategy::Merge)).await?;
+            handle::Handle::refresh_profiles();
+            handle::Handle::notice_message("import_profile::ok", "ok");
+            return Ok(());
+        }
+    };
+
+    Config::profiles().data().append_item(item)?;
+    handle::Handle::refresh_profiles();
+    handle::Handle::notice_message("import_profile::ok", "ok");
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use super::{clash_api, handle, selection, CoreManager};
+use crate::{
+    config::{Config, IVerge},
+    feat,
+    utils::dirs,
+};
+use anyhow::{bail, Result};
+use nanoid::nanoid;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+
+/// the actions to control the running instance
+/// shared by the cli and the local api
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    UpdateProfile { uid: String },
+    SwitchProfile { uid: String },
+    RestartCore,
+    SelectProxy { group: String, node: String },
+    SetSystemProxy { enable: bool },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<Result<Value>> for Response {
+    fn from(result: Result<Value>) -> Self {
+        match result {
+            Ok(data) => Response {
+                ok: true,
+                data: Some(data).filter(|v| !v.is_null()),
+                error: None,
+            },
+            Err(err) => Response {
+                ok: false,
+                data: None,
+                error: Some(format!("{err}")),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Status {
+    pub core: String,
+    /// `None` if the controller is not reachable
+    pub core_version: Option<String>,
+    pub mode: Option<String>,
+    pub mixed_port: u16,
+    pub system_proxy: bool,
+    pub tun_mode: bool,
+    pub current_profile: Option<String>,
+    pub current_profile_name: Option<String>,
+}
+
+pub async fn status() -> Status {
+    let (core, system_proxy, tun_mode) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.clash_core.clone().unwrap_or_default().to_string(),
+            verge.enable_system_proxy.unwrap_or(false),
+            verge.enable_tun_mode.unwrap_or(false),
+        )
+    };
+
+    let (mode, mixed_port) = {
+        let clash = Config::clash();
+        let clash = clash.latest();
+        (
+            clash
+                .0
+                .get("mode")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            clash.get_mixed_port(),
+        )
+    };
+
+    let (current_profile, current_profile_name) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        let current = profiles.get_current();
+        let name = current
+            .as_ref()
+            .and_then(|uid| profiles.get_item(uid).ok())
+            .and_then(|item| item.name.clone());
+        (current, name)
+    };
+
+    let core_version = clash_api::get_version().await.ok().map(|v| v.version);
+
+    Status {
+        core,
+        core_version,
+        mode,
+        mixed_port,
+        system_proxy,
+        tun_mode,
+        current_profile,
+        current_profile_name,
+    }
+}
+
+pub async fn execute(request: Request) -> Result<Value> {
+    tracing::info!("control request: {request:?}");
+
+    match request {
+        Request::Status => return Ok(serde_json::to_value(status().await)?),
+        Request::UpdateProfile { uid } => {
+            feat::update_profile(uid, None).await?;
+            handle::Handle::refresh_profiles();
+        }
+        Request::SwitchProfile { uid } => {
+            if Config::profiles().latest().get_item(&uid).is_err() {
+                bail!("profile `{uid}` not found");
+            }
+            feat::switch_profile(uid).await?;
+            handle::Handle::refresh_profiles();
+        }
+        Request::RestartCore => {
+            CoreManager::global().run_core().await?;
+            handle::Handle::refresh_clash();
+        }
+        Request::SelectProxy { group, node } => selection::select(group, node).await?,
+        Request::SetSystemProxy { enable } => {
+            feat::patch_verge(IVerge {
+                enable_system_proxy: Some(enable),
+                ..IVerge::default()
+            })
+            .await?;
+            handle::Handle::refresh_verge();
+        }
+    }
+    Ok(Value::Null)
+}
+
+/// the token file is only readable by the current user
+/// the cli reads it to talk to the running instance
+fn token_path() -> Result<std::path::PathBuf> {
+    Ok(dirs::app_home_dir()?.join("ipc.token"))
+}
+
+/// generate a new token on every launch
+pub fn ipc_token() -> &'static str {
+    static TOKEN: OnceCell<String> = OnceCell::new();
+
+    TOKEN.get_or_init(|| {
+        let token = nanoid!(32);
+        if let Err(err) = write_token(&token) {
+            tracing::error!("failed to write the ipc token: {err}");
+        }
+        token
+    })
+}
+
+fn write_token(token: &str) -> Result<()> {
+    let path = token_path()?;
+    fs::write(&path, token)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+pub fn read_token() -> Result<String> {
+    let path = token_path()?;
+    if !path.exists() {
+        bail!("the app is not running");
+    }
+    Ok(fs::read_to_string(path)?.trim().to_string())
+}
+
+#[test]
+fn test_request_serde() {
+    let request: Request =
+        serde_json::from_str(r#"{"action":"select_proxy","group":"Proxy","node":"HK"}"#).unwrap();
+    assert!(
+        matches!(request, Request::SelectProxy { group, node } if group == "Proxy" && node == "HK")
+    );
+
+    let request: Request = serde_json::from_str(r#"{"action":"status"}"#).unwrap();
+    assert!(matches!(request, Request::Status));
+}
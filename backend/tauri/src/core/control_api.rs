@@ -0,0 +1,207 @@
+use super::control::{self, Request, Response};
+use crate::{
+    config::{Config, IVerge},
+    utils::server::LocalServer,
+};
+use anyhow::{anyhow, Result};
+use nanoid::nanoid;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::convert::Infallible;
+use warp::{http::StatusCode, reject::Reject, Filter, Rejection, Reply};
+
+pub const DEFAULT_PORT: u16 = 33332;
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+#[derive(Deserialize)]
+struct SwitchProfileBody {
+    uid: String,
+}
+
+#[derive(Deserialize)]
+struct SelectProxyBody {
+    group: String,
+    node: String,
+}
+
+#[derive(Deserialize)]
+struct SystemProxyBody {
+    enable: bool,
+}
+
+/// an optional localhost http api for the automation tools
+/// every request should carry `Authorization: Bearer <control_api_token>`
+pub struct ControlApi {
+    server: LocalServer,
+}
+
+impl ControlApi {
+    pub fn global() -> &'static ControlApi {
+        static CONTROL_API: OnceCell<ControlApi> = OnceCell::new();
+
+        CONTROL_API.get_or_init(|| ControlApi {
+            server: LocalServer::default(),
+        })
+    }
+
+    /// apply the latest config, stop the server if disabled
+    pub async fn restart(&self) -> Result<()> {
+        self.stop().await;
+
+        let (enable, port) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_control_api.unwrap_or(false),
+                verge.control_api_port.unwrap_or(DEFAULT_PORT),
+            )
+        };
+        if !enable {
+            return Ok(());
+        }
+
+        let token = ensure_token()?;
+        let addr = self
+            .server
+            .start(|rx| {
+                warp::serve(routes(token)).try_bind_with_graceful_shutdown(
+                    ([127, 0, 0, 1], port),
+                    async {
+                        rx.await.ok();
+                    },
+                )
+            })
+            .await?;
+
+        tracing::info!("control api is listening on {addr}");
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        if self.server.stop().await {
+            tracing::info!("control api is stopped");
+        }
+    }
+}
+
+/// generate the token on the first start
+fn ensure_token() -> Result<String> {
+    let token = { Config::verge().latest().control_api_token.clone() };
+    match token.filter(|t| !t.is_empty()) {
+        Some(token) => Ok(token),
+        None => reset_token(),
+    }
+}
+
+/// generate a new token, the old one will be invalid after restarting
+pub fn reset_token() -> Result<String> {
+    let token = nanoid!(32);
+    Config::verge().draft().patch_config(IVerge {
+        control_api_token: Some(token.clone()),
+        ..IVerge::default()
+    });
+    Config::verge().apply();
+    Config::verge().data().save_file()?;
+    Ok(token)
+}
+
+fn routes(token: String) -> impl Filter<Extract = impl Reply, Error = Infallible> + Clone {
+    let status = warp::path!("status")
+        .and(warp::get())
+        .map(|| Request::Status);
+
+    let update_profile = warp::path!("profiles" / String / "update")
+        .and(warp::post())
+        .map(|uid| Request::UpdateProfile { uid });
+
+    let switch_profile = warp::path!("profiles" / "current")
+        .and(warp::put())
+        .and(warp::body::json())
+        .map(|body: SwitchProfileBody| Request::SwitchProfile { uid: body.uid });
+
+    let restart_core = warp::path!("core" / "restart")
+        .and(warp::post())
+        .map(|| Request::RestartCore);
+
+    let select_proxy = warp::path!("proxies")
+        .and(warp::put())
+        .and(warp::body::json())
+        .map(|body: SelectProxyBody| Request::SelectProxy {
+            group: body.group,
+            node: body.node,
+        });
+
+    let system_proxy = warp::path!("system-proxy")
+        .and(warp::put())
+        .and(warp::body::json())
+        .map(|body: SystemProxyBody| Request::SetSystemProxy {
+            enable: body.enable,
+        });
+
+    let requests = status
+        .or(update_profile)
+        .unify()
+        .or(switch_profile)
+        .unify()
+        .or(restart_core)
+        .unify()
+        .or(select_proxy)
+        .unify()
+        .or(system_proxy)
+        .unify();
+
+    auth(token)
+        .and(requests)
+        .and_then(|request: Request| async move {
+            let response = Response::from(control::execute(request).await);
+            let status = match response.ok {
+                true => StatusCode::OK,
+                false => StatusCode::BAD_REQUEST,
+            };
+            Ok::<_, Rejection>(warp::reply::with_status(
+                warp::reply::json(&response),
+                status,
+            ))
+        })
+        .recover(recover)
+}
+
+fn auth(token: String) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |auth: Option<String>| {
+            let authorized = auth
+                .as_deref()
+                .and_then(|auth| auth.strip_prefix("Bearer "))
+                .map_or(false, |auth| auth == token);
+
+            async move {
+                match authorized {
+                    true => Ok(()),
+                    false => Err(warp::reject::custom(Unauthorized)),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+async fn recover(err: Rejection) -> Result<impl Reply, Infallible> {
+    let (status, message) = if err.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "unauthorized")
+    } else if err.is_not_found() {
+        (StatusCode::NOT_FOUND, "not found")
+    } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED, "method not allowed")
+    } else {
+        (StatusCode::BAD_REQUEST, "bad request")
+    };
+
+    let response = Response::from(Err(anyhow!(message)));
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        status,
+    ))
+}
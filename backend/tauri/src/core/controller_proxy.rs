@@ -0,0 +1,395 @@
+//! a reverse proxy in front of the core controller for the third-party dashboards
+//!
+//! the dashboards get the token of the proxy instead of the core secret, so the access can
+//! be limited to read only while the app keeps the full access. the mutations of the
+//! dashboards are logged, including the denied ones.
+
+use super::{
+    clash_api::{self, Inspector, RequestRecord, RequestSource},
+    handle,
+};
+use crate::{
+    config::{Config, IVerge, RemoteAccess},
+    utils::{help, server::LocalServer},
+};
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use nanoid::nanoid;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{collections::VecDeque, convert::Infallible, time::Instant};
+use tokio_tungstenite::{connect_async, tungstenite::Message as CoreMessage};
+use warp::{
+    filters::path::FullPath,
+    http::{header, HeaderMap, Method, StatusCode},
+    hyper::{body::Bytes, Body},
+    reject::Reject,
+    ws::{Message, WebSocket, Ws},
+    Filter, Rejection, Reply,
+};
+
+pub const DEFAULT_PORT: u16 = 33337;
+
+/// keep the latest mutations only
+const MAX_MUTATIONS: usize = 200;
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalMutation {
+    /// unix timestamp
+    pub time: i64,
+    pub method: String,
+    pub path: String,
+    /// the `Origin` of the dashboard
+    pub origin: Option<String>,
+    /// false if denied by the read only access
+    pub allowed: bool,
+    /// none if the core is not responded
+    pub status: Option<u16>,
+}
+
+pub struct ControllerProxy {
+    server: LocalServer,
+    mutations: Mutex<VecDeque<ExternalMutation>>,
+}
+
+impl ControllerProxy {
+    pub fn global() -> &'static ControllerProxy {
+        static PROXY: OnceCell<ControllerProxy> = OnceCell::new();
+
+        PROXY.get_or_init(|| ControllerProxy {
+            server: LocalServer::default(),
+            mutations: Mutex::new(VecDeque::with_capacity(MAX_MUTATIONS)),
+        })
+    }
+
+    /// apply the latest config, stop the server if disabled
+    pub async fn restart(&self) -> Result<()> {
+        self.stop().await;
+
+        let (enable, port) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_controller_proxy.unwrap_or(false),
+                verge.controller_proxy_port.unwrap_or(DEFAULT_PORT),
+            )
+        };
+        if !enable {
+            return Ok(());
+        }
+
+        let token = ensure_token()?;
+        let addr = self
+            .server
+            .start(|rx| {
+                warp::serve(routes(token)).try_bind_with_graceful_shutdown(
+                    ([127, 0, 0, 1], port),
+                    async {
+                        rx.await.ok();
+                    },
+                )
+            })
+            .await?;
+
+        tracing::info!("controller proxy is listening on {addr}");
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        if self.server.stop().await {
+            tracing::info!("controller proxy is stopped");
+        }
+    }
+
+    /// the latest first
+    pub fn mutations(&self) -> Vec<ExternalMutation> {
+        self.mutations.lock().iter().rev().cloned().collect()
+    }
+
+    pub fn clear_mutations(&self) {
+        self.mutations.lock().clear();
+    }
+
+    fn record_mutation(&self, mutation: ExternalMutation) {
+        tracing::info!(
+            "external mutation {} {} from {}, {}",
+            mutation.method,
+            mutation.path,
+            mutation.origin.as_deref().unwrap_or("unknown"),
+            match (mutation.allowed, mutation.status) {
+                (false, _) => "denied".to_string(),
+                (true, Some(status)) => format!("status {status}"),
+                (true, None) => "failed".to_string(),
+            }
+        );
+        let mut mutations = self.mutations.lock();
+        if mutations.len() >= MAX_MUTATIONS {
+            mutations.pop_front();
+        }
+        mutations.push_back(mutation);
+    }
+}
+
+/// generate the token on the first start
+fn ensure_token() -> Result<String> {
+    let token = { Config::verge().latest().controller_proxy_token.clone() };
+    match token.filter(|t| !t.is_empty()) {
+        Some(token) => Ok(token),
+        None => reset_token(),
+    }
+}
+
+/// generate a new token, the dashboards using the old one are disconnected after restarting
+pub fn reset_token() -> Result<String> {
+    let token = nanoid!(32);
+    Config::verge().draft().patch_config(IVerge {
+        controller_proxy_token: Some(token.clone()),
+        ..IVerge::default()
+    });
+    Config::verge().apply();
+    Config::verge().data().save_file()?;
+    Ok(token)
+}
+
+fn is_mutation(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn routes(token: String) -> impl Filter<Extract = impl Reply, Error = Infallible> + Clone {
+    // the dashboards are served from other origins
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_headers(vec!["authorization", "content-type"])
+        .allow_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"]);
+
+    // websocket 不方便带请求头，和内核一样用 token 参数
+    let ws = warp::path::full()
+        .and(warp::query::<Vec<(String, String)>>())
+        .and(warp::ws())
+        .map(|path: FullPath, query: Vec<(String, String)>, ws: Ws| {
+            let path = path.as_str().to_string();
+            ws.on_upgrade(move |socket| pipe_ws(path, query, socket))
+        });
+
+    let http = warp::method()
+        .and(warp::path::full())
+        .and(warp::query::<Vec<(String, String)>>())
+        .and(warp::header::headers_cloned())
+        .and(warp::body::bytes())
+        .and_then(forward);
+
+    auth(token).and(ws.or(http)).recover(recover).with(cors)
+}
+
+fn auth(token: String) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::query::<Vec<(String, String)>>())
+        .and_then(move |auth: Option<String>, query: Vec<(String, String)>| {
+            let matches = |value: &str| help::constant_time_eq(value.as_bytes(), token.as_bytes());
+            let authorized = auth
+                .as_deref()
+                .and_then(|auth| auth.strip_prefix("Bearer "))
+                .map_or(false, matches)
+                || query.iter().any(|(k, v)| k == "token" && matches(v));
+
+            async move {
+                match authorized {
+                    true => Ok(()),
+                    false => Err(warp::reject::custom(Unauthorized)),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/// the query of the core request, the token of the proxy is not passed on
+fn core_query(query: &[(String, String)]) -> Vec<(&str, &str)> {
+    query
+        .iter()
+        .filter(|(k, _)| k != "token")
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect()
+}
+
+async fn forward(
+    method: Method,
+    path: FullPath,
+    query: Vec<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<warp::reply::Response, Rejection> {
+    let mutation = is_mutation(&method);
+    let origin = headers
+        .get(header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok())
+        .map(str::to_string);
+    let access = { Config::verge().latest().controller_proxy_access }.unwrap_or_default();
+
+    if mutation && access == RemoteAccess::ReadOnly {
+        ControllerProxy::global().record_mutation(ExternalMutation {
+            time: chrono::Local::now().timestamp(),
+            method: method.to_string(),
+            path: path.as_str().to_string(),
+            origin,
+            allowed: false,
+            status: None,
+        });
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "message": "the access is read only" })),
+            StatusCode::FORBIDDEN,
+        )
+        .into_response());
+    }
+
+    let now = Instant::now();
+    let result = send(&method, path.as_str(), &query, &headers, body).await;
+    let status = result.as_ref().ok().map(|res| res.status().as_u16());
+    Inspector::global().record(RequestRecord {
+        time: 0,
+        source: RequestSource::External,
+        method: method.to_string(),
+        path: path.as_str().to_string(),
+        duration_ms: now.elapsed().as_millis() as u64,
+        status,
+        error: result.as_ref().err().map(|err| err.to_string()),
+    });
+    if mutation {
+        ControllerProxy::global().record_mutation(ExternalMutation {
+            time: chrono::Local::now().timestamp(),
+            method: method.to_string(),
+            path: path.as_str().to_string(),
+            origin,
+            allowed: true,
+            status,
+        });
+        // 外部修改了选择或者配置，刷新界面
+        if status.is_some_and(|status| status < 400) {
+            handle::Handle::refresh_clash();
+        }
+    }
+
+    let mut res = match result {
+        Ok(res) => res,
+        Err(err) => {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "message": err.to_string() })),
+                StatusCode::BAD_GATEWAY,
+            )
+            .into_response())
+        }
+    };
+
+    // /traffic 之类的接口不走 websocket 时是持续的 chunked 响应
+    let (mut sender, body) = Body::channel();
+    let mut reply = warp::http::Response::new(body);
+    *reply.status_mut() = res.status();
+    if let Some(content_type) = res.headers().get(header::CONTENT_TYPE) {
+        reply
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, content_type.clone());
+    }
+    tauri::async_runtime::spawn(async move {
+        while let Ok(Some(chunk)) = res.chunk().await {
+            if sender.send_data(chunk).await.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(reply)
+}
+
+async fn send(
+    method: &Method,
+    path: &str,
+    query: &[(String, String)],
+    headers: &HeaderMap,
+    body: Bytes,
+) -> Result<reqwest::Response> {
+    let info = { Config::clash().data().get_client_info() };
+    // 路径已经是编码过的，直接拼接
+    let mut url = reqwest::Url::parse(&format!("http://{}{path}", info.server))?;
+    let query = core_query(query);
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+
+    let client = reqwest::ClientBuilder::new().no_proxy().build()?;
+    let mut builder = client.request(method.clone(), url).body(body);
+    if let Some(content_type) = headers.get(header::CONTENT_TYPE) {
+        builder = builder.header(header::CONTENT_TYPE, content_type.clone());
+    }
+    if let Some(secret) = info.secret.filter(|s| !s.is_empty()) {
+        builder = builder.bearer_auth(secret);
+    }
+    Ok(builder.send().await?)
+}
+
+/// forward the frames of the core to the dashboard, the streams are read only
+async fn pipe_ws(path: String, query: Vec<(String, String)>, socket: WebSocket) {
+    let url = match clash_api::clash_ws_url(path.trim_start_matches('/'), &core_query(&query)) {
+        Ok(url) => url,
+        Err(err) => {
+            tracing::warn!("invalid websocket path `{path}`: {err}");
+            return;
+        }
+    };
+    let (mut core, _) = match connect_async(url.as_str()).await {
+        Ok(stream) => stream,
+        Err(err) => {
+            tracing::warn!("failed to connect the core websocket `{path}`: {err}");
+            return;
+        }
+    };
+
+    let (mut tx, mut rx) = socket.split();
+    loop {
+        let message = tokio::select! {
+            message = core.next() => match message {
+                Some(Ok(CoreMessage::Text(text))) => Message::text(text),
+                Some(Ok(CoreMessage::Binary(data))) => Message::binary(data),
+                Some(Ok(_)) => continue,
+                _ => break,
+            },
+            message = rx.next() => match message {
+                Some(Ok(message)) if !message.is_close() => continue,
+                _ => break,
+            },
+        };
+        if tx.send(message).await.is_err() {
+            break;
+        }
+    }
+    let _ = core.close(None).await;
+    let _ = tx.close().await;
+}
+
+async fn recover(err: Rejection) -> Result<impl Reply, Infallible> {
+    let (status, message) = if err.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "unauthorized")
+    } else if err.is_not_found() {
+        (StatusCode::NOT_FOUND, "not found")
+    } else {
+        (StatusCode::BAD_REQUEST, "bad request")
+    };
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "message": message })),
+        status,
+    ))
+}
+
+#[test]
+fn test_core_query() {
+    let query = vec![
+        ("token".to_string(), "proxy".to_string()),
+        ("level".to_string(), "info".to_string()),
+    ];
+    assert_eq!(core_query(&query), [("level", "info")]);
+    assert!(is_mutation(&Method::PUT));
+    assert!(!is_mutation(&Method::GET));
+}
@@ -1,10 +1,16 @@
-use super::{clash_api, logger::Logger};
+use super::{
+    clash_api, core_archive, crash,
+    event_bus::{AppEvent, CoreState, EventBus},
+    lazy_core::LazyCore,
+    logger::Logger,
+    selection,
+};
 use crate::log_err;
 use crate::{config::*, utils::dirs};
 use anyhow::{bail, Context, Result};
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
-use std::{fs, io::Write, sync::Arc, time::Duration};
+use std::{fs, io::Write, path::PathBuf, sync::Arc, time::Duration};
 use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
 use tauri::api::process::{Command, CommandChild, CommandEvent};
 use tokio::time::sleep;
@@ -37,12 +43,20 @@ impl CoreManager {
                 system.refresh_all();
                 system.process(Pid::from_u32(pid)).map(|proc| {
                     if proc.name().contains("clash") {
-                        log::debug!(target: "app", "kill old clash process");
+                        tracing::debug!("kill old clash process");
                         proc.kill();
                     }
                 });
             });
 
+        // 按需启动，等第一个连接进来再启动
+        if LazyCore::enabled() {
+            match LazyCore::global().wait() {
+                Ok(_) => return Ok(()),
+                Err(err) => tracing::warn!("failed to wait for the first connection: {err}"),
+            }
+        }
+
         tauri::async_runtime::spawn(async {
             // 启动clash
             log_err!(Self::global().run_core().await);
@@ -54,14 +68,19 @@ impl CoreManager {
     /// 检查配置是否正确
     pub fn check_config(&self) -> Result<()> {
         let config_path = Config::generate_file(ConfigType::Check)?;
-        let config_path = dirs::path_to_str(&config_path)?;
+        self.check_config_file(&config_path)
+    }
+
+    /// 用内核的 `-t` 检查某个配置文件
+    pub fn check_config_file(&self, config_path: &PathBuf) -> Result<()> {
+        let config_path = dirs::path_to_str(config_path)?;
 
         let clash_core = { Config::verge().latest().clash_core.clone() };
         let clash_core = clash_core.unwrap_or(ClashCore::ClashPremium).to_string();
 
         let app_dir = dirs::app_home_dir()?;
         let app_dir = dirs::path_to_str(&app_dir)?;
-        log::debug!(target: "app", "check config in `{clash_core}`");
+        tracing::debug!("check config in `{clash_core}`");
         let output = Command::new_sidecar(clash_core)?
             .args(["-t", "-d", app_dir, "-f", config_path])
             .output()?;
@@ -83,10 +102,15 @@ impl CoreManager {
     pub async fn run_core(&self) -> Result<()> {
         let config_path = Config::generate_file(ConfigType::Run)?;
 
+        // 先把按需启动占用的端口让出来
+        if LazyCore::global().cancel() {
+            sleep(Duration::from_millis(100)).await;
+        }
+
         #[allow(unused_mut)]
         let mut should_kill = match self.sidecar.lock().take() {
             Some(child) => {
-                log::debug!(target: "app", "stop the core by sidecar");
+                tracing::debug!("stop the core by sidecar");
                 let _ = child.kill();
                 true
             }
@@ -95,22 +119,63 @@ impl CoreManager {
 
         #[cfg(target_os = "windows")]
         if *self.use_service_mode.lock() {
-            log::debug!(target: "app", "stop the core by service");
+            tracing::debug!("stop the core by service");
             log_err!(super::win_service::stop_core_by_service().await);
             should_kill = true;
         }
 
+        // 由 helper 运行的内核，app 刚启动时不需要重启
+        #[cfg(target_os = "macos")]
+        {
+            use super::mac_helper;
+
+            let restart = *self.use_service_mode.lock();
+            let enable = mac_helper::is_enabled();
+            *self.use_service_mode.lock() = enable;
+
+            if enable {
+                tracing::debug!("try to run core by the privileged helper");
+                match mac_helper::run_core_by_helper(&config_path, restart).await {
+                    Ok(_) => {
+                        EventBus::publish(AppEvent::CoreState {
+                            state: CoreState::Started,
+                        });
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        *self.use_service_mode.lock() = false;
+                        tracing::error!("{err}");
+                    }
+                }
+            } else if restart {
+                tracing::debug!("stop the core by the privileged helper");
+                log_err!(mac_helper::stop_core_by_helper().await);
+                should_kill = true;
+            }
+        }
+
         // 这里得等一会儿
         if should_kill {
             sleep(Duration::from_millis(500)).await;
         }
+
+        // 内核由 systemd 运行时只重新加载配置，开机后 tun 就已经可用
+        #[cfg(target_os = "linux")]
+        if super::systemd::is_core_active().await {
+            tracing::debug!("the core is run by systemd, reload the config");
+            clash_api::put_configs(dirs::path_to_str(&config_path)?).await?;
+            EventBus::publish(AppEvent::CoreState {
+                state: CoreState::Started,
+            });
+            return Ok(());
+        }
         #[cfg(target_os = "macos")]
         {
             let enable_tun = Config::verge().latest().enable_tun_mode.clone();
             let enable_tun = enable_tun.unwrap_or(false);
 
             if enable_tun {
-                log::debug!(target: "app", "try to set system dns");
+                tracing::debug!("try to set system dns");
 
                 match (|| async {
                     let tun_device_ip = Config::clash().clone().latest().get_tun_device_ip();
@@ -124,7 +189,7 @@ impl CoreManager {
                     Ok(_) => return Ok(()),
                     Err(err) => {
                         // 修改这个值，免得stop出错
-                        log::error!(target: "app", "{err}");
+                        tracing::error!("{err}");
                     }
                 }
             }
@@ -141,7 +206,7 @@ impl CoreManager {
 
             if enable {
                 // 服务模式启动失败就直接运行sidecar
-                log::debug!(target: "app", "try to run core in service mode");
+                tracing::debug!("try to run core in service mode");
 
                 match (|| async {
                     win_service::check_service().await?;
@@ -149,11 +214,16 @@ impl CoreManager {
                 })()
                 .await
                 {
-                    Ok(_) => return Ok(()),
+                    Ok(_) => {
+                        EventBus::publish(AppEvent::CoreState {
+                            state: CoreState::Started,
+                        });
+                        return Ok(());
+                    }
                     Err(err) => {
                         // 修改这个值，免得stop出错
                         *self.use_service_mode.lock() = false;
-                        log::error!(target: "app", "{err}");
+                        tracing::error!("{err}");
                     }
                 }
             }
@@ -171,17 +241,13 @@ impl CoreManager {
 
         let config_path = dirs::path_to_str(&config_path)?;
 
-        // fix #212
-        let args = match &clash_core {
-            ClashCore::Mihomo | ClashCore::MihomoAlpha => {
-                vec!["-m", "-d", app_dir, "-f", config_path]
-            }
-            ClashCore::ClashRs => vec!["-d", app_dir, "-c", config_path],
-            ClashCore::ClashPremium => vec!["-d", app_dir, "-f", config_path],
-        };
+        let args = core_args(&clash_core, app_dir, config_path);
 
+        let core_name = clash_core.to_string();
         let cmd = Command::new_sidecar(clash_core)?;
         let (mut rx, cmd_child) = cmd.args(args).spawn()?;
+        let core_pid = cmd_child.pid();
+        crash::record_core_start(core_name, core_pid);
 
         // 将pid写入文件中
         crate::log_err!((|| {
@@ -204,23 +270,36 @@ impl CoreManager {
                     CommandEvent::Stdout(line) => {
                         if is_clash {
                             let stdout = clash_api::parse_log(line.clone());
-                            log::info!(target: "app", "[clash]: {stdout}");
+                            tracing::info!("[clash]: {stdout}");
                         } else {
-                            log::info!(target: "app", "[clash]: {line}");
+                            tracing::info!("[clash]: {line}");
                         };
                         Logger::global().set_log(line);
                     }
                     CommandEvent::Stderr(err) => {
                         // let stdout = clash_api::parse_log(err.clone());
-                        log::error!(target: "app", "[clash]: {err}");
+                        tracing::error!("[clash]: {err}");
                         Logger::global().set_log(err);
                     }
                     CommandEvent::Error(err) => {
-                        log::error!(target: "app", "[clash]: {err}");
+                        tracing::error!("[clash]: {err}");
                         Logger::global().set_log(err);
                     }
-                    CommandEvent::Terminated(_) => {
-                        log::info!(target: "app", "clash core terminated");
+                    CommandEvent::Terminated(payload) => {
+                        tracing::info!(
+                            "clash core terminated, code {:?}, signal {:?}",
+                            payload.code,
+                            payload.signal
+                        );
+                        // sidecar 还是这个进程，说明不是主动停止的
+                        let unexpected = CoreManager::global()
+                            .sidecar
+                            .lock()
+                            .as_ref()
+                            .is_some_and(|child| child.pid() == core_pid);
+                        if unexpected {
+                            crash::record_core_exit(core_pid, payload.code, payload.signal);
+                        }
                         let _ = CoreManager::global().recover_core();
                         break;
                     }
@@ -229,9 +308,19 @@ impl CoreManager {
             }
         });
 
+        // 内核重启后恢复分组的选择，并让各个 stream 尽快重连
+        EventBus::publish(AppEvent::CoreState {
+            state: CoreState::Started,
+        });
+
         Ok(())
     }
 
+    /// 内核是否在运行，服务模式下由服务管理
+    pub fn is_running(&self) -> bool {
+        *self.use_service_mode.lock() || self.sidecar.lock().is_some()
+    }
+
     /// 重启内核
     pub fn recover_core(&'static self) -> Result<()> {
         // 服务模式不管
@@ -251,14 +340,22 @@ impl CoreManager {
             sleep(Duration::from_millis(6666)).await;
 
             if self.sidecar.lock().is_none() {
-                log::info!(target: "app", "recover clash core");
+                tracing::info!("recover clash core");
 
                 // 重新启动app
-                if let Err(err) = self.run_core().await {
-                    log::error!(target: "app", "failed to recover clash core");
-                    log::error!(target: "app", "{err}");
+                match self.run_core().await {
+                    Ok(_) => EventBus::publish(AppEvent::CoreState {
+                        state: CoreState::Recovered,
+                    }),
+                    Err(err) => {
+                        tracing::error!("failed to recover clash core");
+                        tracing::error!("{err}");
+                        EventBus::publish(AppEvent::CoreState {
+                            state: CoreState::RecoverFailed,
+                        });
 
-                    let _ = self.recover_core();
+                        let _ = self.recover_core();
+                    }
                 }
             }
         });
@@ -270,20 +367,29 @@ impl CoreManager {
     pub fn stop_core(&self) -> Result<()> {
         #[cfg(target_os = "windows")]
         if *self.use_service_mode.lock() {
-            log::debug!(target: "app", "stop the core by service");
+            tracing::debug!("stop the core by service");
             tauri::async_runtime::block_on(async move {
                 log_err!(super::win_service::stop_core_by_service().await);
             });
             return Ok(());
         }
 
+        #[cfg(target_os = "macos")]
+        if *self.use_service_mode.lock() {
+            tracing::debug!("stop the core by the privileged helper");
+            tauri::async_runtime::block_on(async move {
+                log_err!(super::mac_helper::stop_core_by_helper().await);
+            });
+            return Ok(());
+        }
+
         #[cfg(target_os = "macos")]
         {
             let enable_tun = Config::verge().latest().enable_tun_mode.clone();
             let enable_tun = enable_tun.unwrap_or(false);
 
             if enable_tun {
-                log::debug!(target: "app", "try to set system dns");
+                tracing::debug!("try to set system dns");
 
                 match (|| {
                     // 执行 networksetup -setdnsservers Wi-Fi "Empty"
@@ -295,14 +401,14 @@ impl CoreManager {
                     Err(err) => {
                         // 修改这个值，免得stop出错
                         *self.use_service_mode.lock() = false;
-                        log::error!(target: "app", "{err}");
+                        tracing::error!("{err}");
                     }
                 }
             }
         }
         let mut sidecar = self.sidecar.lock();
         if let Some(child) = sidecar.take() {
-            log::debug!(target: "app", "stop the core by sidecar");
+            tracing::debug!("stop the core by sidecar");
             let _ = child.kill();
         }
         Ok(())
@@ -316,18 +422,32 @@ impl CoreManager {
         //     bail!("invalid clash core name \"{clash_core}\"");
         // }
 
-        log::debug!(target: "app", "change core to `{clash_core}`");
+        tracing::debug!("change core to `{clash_core}`");
 
-        Config::verge().draft().clash_core = Some(clash_core);
+        let previous = { Config::verge().latest().clash_core.clone() }.unwrap_or_default();
+        let switching = previous != clash_core;
 
-        // 更新配置
-        Config::generate()?;
+        Config::verge().draft().clash_core = Some(clash_core.clone());
 
-        self.check_config()?;
+        // 更新配置，失败时丢掉草稿，否则会带进下一次 apply
+        if let Err(err) = Config::generate().and_then(|_| self.check_config()) {
+            Config::verge().discard();
+            Config::runtime().discard();
+            return Err(err);
+        }
 
         // 清掉旧日志
         Logger::global().clear_log();
 
+        // 先停掉旧的核心，cache.db 写完了再归档，换上新核心之前的状态
+        if switching {
+            let selections = core_archive::current_selections().await;
+            let _ =
+                tauri::async_runtime::spawn_blocking(|| CoreManager::global().stop_core()).await;
+            log_err!(core_archive::archive(&previous, selections));
+            log_err!(core_archive::restore(&clash_core));
+        }
+
         match self.run_core().await {
             Ok(_) => {
                 Config::verge().apply();
@@ -338,6 +458,11 @@ impl CoreManager {
             Err(err) => {
                 Config::verge().discard();
                 Config::runtime().discard();
+                // 换回旧核心的状态
+                if switching {
+                    core_archive::clear_selections();
+                    log_err!(core_archive::restore(&previous));
+                }
                 Err(err)
             }
         }
@@ -346,7 +471,7 @@ impl CoreManager {
     /// 更新proxies那些
     /// 如果涉及端口和外部控制则需要重启
     pub async fn update_config(&self) -> Result<()> {
-        log::debug!(target: "app", "try to update clash config");
+        tracing::debug!("try to update clash config");
 
         // 更新配置
         Config::generate()?;
@@ -354,6 +479,11 @@ impl CoreManager {
         // 检查配置是否正常
         self.check_config()?;
 
+        // 内核还没启动，启动时会用新的配置
+        if LazyCore::global().is_waiting() {
+            return Ok(());
+        }
+
         // 更新运行时配置
         let path = Config::generate_file(ConfigType::Run)?;
         let path = dirs::path_to_str(&path)?;
@@ -364,7 +494,7 @@ impl CoreManager {
                 Ok(_) => break,
                 Err(err) => {
                     if i < 4 {
-                        log::info!(target: "app", "{err}");
+                        tracing::info!("{err}");
                     } else {
                         bail!(err);
                     }
@@ -373,6 +503,25 @@ impl CoreManager {
             sleep(Duration::from_millis(250)).await;
         }
 
+        // profile 更新后分组的选择会被重置
+        log_err!(selection::restore().await);
+
         Ok(())
     }
 }
+
+/// the args to run the core, also used by the privileged helper on macos
+pub(super) fn core_args<'a>(
+    clash_core: &ClashCore,
+    app_dir: &'a str,
+    config_path: &'a str,
+) -> Vec<&'a str> {
+    // fix #212
+    match clash_core {
+        ClashCore::Mihomo | ClashCore::MihomoAlpha => {
+            vec!["-m", "-d", app_dir, "-f", config_path]
+        }
+        ClashCore::ClashRs => vec!["-d", app_dir, "-c", config_path],
+        ClashCore::ClashPremium => vec!["-d", app_dir, "-f", config_path],
+    }
+}
@@ -0,0 +1,110 @@
+//! archive the state of the core before switching to another one
+//!
+//! the cores share the home dir, so the `cache.db` written by one core is read by the next,
+//! and the groups go back to the defaults. each core keeps its own copy of the state in
+//! `core-archives/<core>`, restored when switching back. the runtime config is archived as
+//! well, to see what the core was running with.
+use super::clash_api;
+use crate::{
+    config::{ClashCore, EXIT_PROBE, RUNTIME_CONFIG},
+    utils::dirs,
+};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+const ENTRY_FILE: &str = "archive.json";
+
+/// the files written by the cores in the home dir
+const STATE_FILES: [&str; 1] = ["cache.db"];
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CoreArchive {
+    /// unix timestamp
+    pub time: i64,
+    /// group -> node, the selectors only
+    pub selections: HashMap<String, String>,
+}
+
+/// the selections to apply after the core starts, see `selection::restore`
+static PENDING: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn archive_dir(core: &ClashCore) -> Result<PathBuf> {
+    Ok(dirs::app_core_archive_dir()?.join(core.to_string()))
+}
+
+/// the selections of the running core, read before stopping it
+pub async fn current_selections() -> HashMap<String, String> {
+    let proxies = match clash_api::get_proxies().await {
+        Ok(res) => res.proxies,
+        Err(err) => {
+            tracing::debug!("failed to get the selections of the core: {err}");
+            return HashMap::new();
+        }
+    };
+    proxies
+        .into_values()
+        .filter(|item| item.r#type == "Selector" && item.name != EXIT_PROBE)
+        .filter_map(|item| Some((item.name, item.now?)))
+        .collect()
+}
+
+/// copy the runtime config and the state files of the stopped core
+pub fn archive(core: &ClashCore, selections: HashMap<String, String>) -> Result<()> {
+    let home = dirs::app_home_dir()?;
+    let dir = archive_dir(core)?;
+    fs::create_dir_all(&dir)?;
+
+    for file in STATE_FILES.iter().chain([RUNTIME_CONFIG].iter()) {
+        let path = home.join(file);
+        if path.exists() {
+            fs::copy(&path, dir.join(file))?;
+        }
+    }
+
+    let entry = CoreArchive {
+        time: chrono::Local::now().timestamp(),
+        selections,
+    };
+    fs::write(dir.join(ENTRY_FILE), serde_json::to_string_pretty(&entry)?)?;
+    tracing::info!("the state of `{core}` is archived");
+    Ok(())
+}
+
+/// copy back the state files of the core, the selections are applied after it starts.
+/// a core never archived keeps the current files
+pub fn restore(core: &ClashCore) -> Result<Option<CoreArchive>> {
+    let dir = archive_dir(core)?;
+    let entry = match fs::read_to_string(dir.join(ENTRY_FILE)) {
+        Ok(text) => serde_json::from_str::<CoreArchive>(&text)?,
+        Err(_) => return Ok(None),
+    };
+
+    let home = dirs::app_home_dir()?;
+    for file in STATE_FILES {
+        let path = dir.join(file);
+        match path.exists() {
+            true => fs::copy(&path, home.join(file)).map(|_| ())?,
+            // 切走之前没有这个文件，也不能用另一个核心的
+            false => {
+                let _ = fs::remove_file(home.join(file));
+            }
+        }
+    }
+
+    *PENDING.lock() = entry.selections.clone();
+    tracing::info!("the state of `{core}` is restored");
+    Ok(Some(entry))
+}
+
+/// the archived selections not applied yet
+pub fn take_selections() -> HashMap<String, String> {
+    std::mem::take(&mut *PENDING.lock())
+}
+
+/// drop the selections if the core failed to start
+pub fn clear_selections() {
+    PENDING.lock().clear();
+}
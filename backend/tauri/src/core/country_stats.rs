@@ -0,0 +1,328 @@
+//! the traffic of the connections grouped by the country of the destination
+//!
+//! the deltas of the `/connections` frames are added to daily buckets, the country is looked up
+//! in the Country.mmdb. the destinations without an ip or a country are counted as `unknown`.
+
+use super::{
+    node_info::GeoDb,
+    storage::Storage,
+    stream::{LowPowerPolicy, StreamHandler, StreamManager},
+};
+use chrono::{Duration as ChronoDuration, Local};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    net::IpAddr,
+    time::Duration,
+};
+
+const STORAGE_KEY: &str = "country_stats";
+
+/// keep the latest 30 days
+const HISTORY_DAYS: usize = 30;
+
+/// retry opening the mmdb, it may be downloaded later
+const GEO_DB_RETRY: i64 = 10 * 60;
+
+/// the lookup cache is cleared when it grows over the limit
+const IP_CACHE_LIMIT: usize = 4096;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CountryTraffic {
+    /// bytes
+    pub up: u64,
+    pub down: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CountryBucket {
+    /// the local date, `2024-01-31`
+    pub date: String,
+    /// the iso code of the country
+    pub countries: BTreeMap<String, CountryTraffic>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CountryStat {
+    pub country: String,
+    pub up: u64,
+    pub down: u64,
+    /// the share of the total traffic, 0 to 1
+    pub share: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectionsFrame {
+    /// meta 有时会返回 null
+    connections: Option<Vec<Connection>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Connection {
+    id: String,
+    upload: u64,
+    download: u64,
+    metadata: Metadata,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Metadata {
+    #[serde(rename = "destinationIP", default)]
+    destination_ip: String,
+    /// the resolved address of the remote dns connections
+    #[serde(rename = "remoteDestination", default)]
+    remote_destination: String,
+}
+
+impl Metadata {
+    fn ip(&self) -> Option<IpAddr> {
+        [&self.destination_ip, &self.remote_destination]
+            .into_iter()
+            .find_map(|addr| addr.parse().ok())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// the bytes of the connections in the last frame and the country
+    seen: HashMap<String, (u64, u64, String)>,
+    days: VecDeque<CountryBucket>,
+    ip_cache: HashMap<IpAddr, Option<String>>,
+    geo_db: Option<GeoDb>,
+    /// the last time of opening the mmdb
+    geo_db_tried: Option<i64>,
+    /// the minute of the last saving
+    saved_at: i64,
+}
+
+impl State {
+    fn country(&mut self, ip: Option<IpAddr>, now: i64) -> String {
+        let Some(ip) = ip else {
+            return UNKNOWN.to_string();
+        };
+        if let Some(country) = self.ip_cache.get(&ip) {
+            return country.clone().unwrap_or_else(|| UNKNOWN.to_string());
+        }
+
+        if self.geo_db.is_none()
+            && self
+                .geo_db_tried
+                .map_or(true, |tried| now - tried >= GEO_DB_RETRY)
+        {
+            self.geo_db_tried = Some(now);
+            match GeoDb::open() {
+                Ok(db) => self.geo_db = Some(db),
+                Err(err) => tracing::warn!("country stats is not available: {err}"),
+            }
+        }
+        // 数据库还没有时不缓存，等打开后重新查询
+        let Some(db) = self.geo_db.as_ref() else {
+            return UNKNOWN.to_string();
+        };
+
+        if self.ip_cache.len() >= IP_CACHE_LIMIT {
+            self.ip_cache.clear();
+        }
+        let country = db.country(ip);
+        self.ip_cache.insert(ip, country.clone());
+        country.unwrap_or_else(|| UNKNOWN.to_string())
+    }
+
+    fn add(&mut self, date: &str, country: &str, up: u64, down: u64) {
+        if up == 0 && down == 0 {
+            return;
+        }
+        if self.days.back().map_or(true, |bucket| bucket.date != date) {
+            self.days.push_back(CountryBucket {
+                date: date.to_string(),
+                countries: BTreeMap::new(),
+            });
+            while self.days.len() > HISTORY_DAYS {
+                self.days.pop_front();
+            }
+        }
+        if let Some(bucket) = self.days.back_mut() {
+            let traffic = bucket.countries.entry(country.to_string()).or_default();
+            traffic.up += up;
+            traffic.down += down;
+        }
+    }
+
+    fn push(&mut self, connections: Vec<Connection>, now: i64, date: &str) {
+        let mut alive = HashSet::with_capacity(connections.len());
+        for conn in connections {
+            let (delta_up, delta_down, country) = match self.seen.remove(&conn.id) {
+                // 连接复用同一个 id 时计数会重置
+                Some((up, down, country)) => (
+                    conn.upload.checked_sub(up).unwrap_or(conn.upload),
+                    conn.download.checked_sub(down).unwrap_or(conn.download),
+                    country,
+                ),
+                None => {
+                    let country = self.country(conn.metadata.ip(), now);
+                    (conn.upload, conn.download, country)
+                }
+            };
+            self.add(date, &country, delta_up, delta_down);
+            alive.insert(conn.id.clone());
+            self.seen
+                .insert(conn.id, (conn.upload, conn.download, country));
+        }
+        self.seen.retain(|id, _| alive.contains(id));
+    }
+}
+
+/// sum up the buckets since the date `since`, sorted by the total traffic
+fn summarize(days: &VecDeque<CountryBucket>, since: &str) -> Vec<CountryStat> {
+    let mut sum = BTreeMap::<&str, CountryTraffic>::new();
+    for bucket in days.iter().filter(|bucket| bucket.date.as_str() >= since) {
+        for (country, traffic) in &bucket.countries {
+            let entry = sum.entry(country).or_default();
+            entry.up += traffic.up;
+            entry.down += traffic.down;
+        }
+    }
+
+    let total = sum.values().map(|t| t.up + t.down).sum::<u64>();
+    let mut stats = sum
+        .into_iter()
+        .map(|(country, traffic)| CountryStat {
+            country: country.to_string(),
+            up: traffic.up,
+            down: traffic.down,
+            share: if total == 0 {
+                0.0
+            } else {
+                (traffic.up + traffic.down) as f64 / total as f64
+            },
+        })
+        .collect::<Vec<_>>();
+    stats.sort_by(|a, b| (b.up + b.down).cmp(&(a.up + a.down)));
+    stats
+}
+
+/// aggregate the connections by the destination country, saved to the storage
+pub struct CountryStats {
+    state: Mutex<State>,
+}
+
+impl CountryStats {
+    pub fn global() -> &'static CountryStats {
+        static COUNTRY_STATS: OnceCell<CountryStats> = OnceCell::new();
+
+        COUNTRY_STATS.get_or_init(|| {
+            let days = Storage::global()
+                .get_item::<VecDeque<CountryBucket>>(STORAGE_KEY)
+                .unwrap_or_else(|err| {
+                    tracing::error!("failed to load the country stats: {err}");
+                    None
+                })
+                .unwrap_or_default();
+            CountryStats {
+                state: Mutex::new(State {
+                    days,
+                    ..Default::default()
+                }),
+            }
+        })
+    }
+
+    pub fn start(&'static self) {
+        StreamManager::global().spawn(self);
+    }
+
+    /// the stats of the latest `days` days, 1 is today
+    pub fn stats(&self, days: u32) -> Vec<CountryStat> {
+        let days = days.clamp(1, HISTORY_DAYS as u32);
+        let since = Local::now().date_naive() - ChronoDuration::days(days as i64 - 1);
+        summarize(
+            &self.state.lock().days,
+            &since.format(DATE_FORMAT).to_string(),
+        )
+    }
+
+    pub fn clear(&self) -> anyhow::Result<()> {
+        let mut state = self.state.lock();
+        state.days.clear();
+        Storage::global().set_item(STORAGE_KEY, &state.days)
+    }
+}
+
+impl StreamHandler for CountryStats {
+    fn path(&self) -> &'static str {
+        "connections"
+    }
+
+    fn name(&self) -> &'static str {
+        "stats::country"
+    }
+
+    // 计数是累计的，丢掉中间的帧不影响统计
+    fn low_power(&self) -> LowPowerPolicy {
+        LowPowerPolicy::Throttle(Duration::from_secs(5))
+    }
+
+    fn on_message(&self, text: String) {
+        let frame = match serde_json::from_str::<ConnectionsFrame>(&text) {
+            Ok(frame) => frame,
+            Err(err) => {
+                tracing::trace!("invalid connections frame: {err}");
+                return;
+            }
+        };
+
+        let now = Local::now();
+        let timestamp = now.timestamp();
+        let minute = timestamp - timestamp.rem_euclid(60);
+
+        let mut state = self.state.lock();
+        let date = now.format(DATE_FORMAT).to_string();
+        state.push(frame.connections.unwrap_or_default(), timestamp, &date);
+
+        // 每分钟保存一次
+        if state.saved_at != minute {
+            state.saved_at = minute;
+            if let Err(err) = Storage::global().set_item(STORAGE_KEY, &state.days) {
+                tracing::error!("failed to save the country stats: {err}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_country_stats() {
+    let conn = |id: &str, upload, download| Connection {
+        id: id.to_string(),
+        upload,
+        download,
+        metadata: Metadata::default(),
+    };
+    let (date, next) = ("2024-01-31", "2024-02-01");
+
+    let mut state = State::default();
+    state.seen.insert("a".into(), (0, 0, "US".into()));
+    state.seen.insert("b".into(), (0, 0, "JP".into()));
+    state.push(vec![conn("a", 10, 50), conn("b", 5, 15)], 0, date);
+    state.push(vec![conn("a", 20, 70)], 0, date);
+    assert!(!state.seen.contains_key("b"));
+
+    // 第二天
+    state.push(vec![conn("a", 20, 90)], 0, next);
+    assert_eq!(state.days.len(), 2);
+
+    let stats = summarize(&state.days, date);
+    assert_eq!(stats[0].country, "US");
+    assert_eq!((stats[0].up, stats[0].down), (20, 90));
+    assert_eq!(stats[1].country, "JP");
+    assert!((stats[0].share - 110.0 / 130.0).abs() < 1e-9);
+
+    let stats = summarize(&state.days, next);
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].share, 1.0);
+}
@@ -0,0 +1,626 @@
+//! the crash reports of the app panics and the unexpected core exits
+//!
+//! the reports are saved as json in the `crashes` dir, nothing leaves this device
+//! unless the user opts in the upload, the urls in the reports are redacted.
+//!
+//! the native crashes (segfaults, illegal instructions...) end the process before any report,
+//! a handler leaves a file for them and the report is made on the next launch:
+//! a minidump on windows, the signal on unix. the minidumps are never uploaded.
+//! the fast fails of windows and the crashes of the webview processes are not captured.
+
+use super::{diagnostic::redact_urls, http, logger::Logger};
+use crate::{
+    config::{Config, HttpPurpose},
+    utils::dirs,
+};
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fs,
+    panic::Location,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// the reports kept in the dir, the oldest are removed
+const MAX_REPORTS: usize = 20;
+
+/// the recent core exits put into the reports
+const MAX_CORE_EXITS: usize = 10;
+
+/// the last core log lines put into the core exit reports
+const MAX_CORE_LOGS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashKind {
+    AppPanic,
+    CoreExit,
+    NativeCrash,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreExit {
+    pub core: String,
+    pub pid: u32,
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub time: i64,
+    /// seconds the core has run
+    pub uptime: Option<u64>,
+}
+
+/// what the app was doing, collected without touching the config locks
+/// the panic may happen while they are held
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateSummary {
+    /// seconds since the app started
+    pub app_uptime: u64,
+    pub core: Option<String>,
+    pub core_pid: Option<u32>,
+    pub core_uptime: Option<u64>,
+    pub recent_core_exits: Vec<CoreExit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    pub kind: CrashKind,
+    pub time: i64,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub thread: Option<String>,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: Option<String>,
+    pub state: StateSummary,
+    /// the last core logs, only for the core exits
+    #[serde(default)]
+    pub logs: Vec<String>,
+    /// the file name of the minidump in the dir, only on windows
+    #[serde(default)]
+    pub minidump: Option<String>,
+    #[serde(default)]
+    pub uploaded: bool,
+}
+
+struct CrashState {
+    started: Instant,
+    /// the running core, pid and the start time
+    core: Option<(String, u32, Instant)>,
+    exits: VecDeque<CoreExit>,
+}
+
+static STATE: Lazy<Mutex<CrashState>> = Lazy::new(|| {
+    Mutex::new(CrashState {
+        started: Instant::now(),
+        core: None,
+        exits: VecDeque::new(),
+    })
+});
+
+/// write a report when the app panics, then call the default hook
+/// and leave a file when the app crashes natively
+pub fn install_hook() {
+    Lazy::force(&STATE);
+
+    // 上次运行的原生崩溃
+    crate::log_err!(collect_native());
+    let native = dirs::app_crashes_dir().and_then(|dir| {
+        fs::create_dir_all(&dir)?;
+        platform::install(&dir.join(format!("{}.{}", new_id(), platform::EXTENSION)))
+    });
+    if let Err(err) = native {
+        eprintln!("failed to handle the native crashes: {err}");
+    }
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = panic_report(info.payload(), info.location());
+        match save(&report) {
+            Ok(path) => eprintln!("the crash report is saved to {}", path.display()),
+            Err(err) => eprintln!("failed to save the crash report: {err}"),
+        }
+        default_hook(info);
+    }));
+}
+
+/// called after the core sidecar is spawned
+pub fn record_core_start(core: String, pid: u32) {
+    STATE.lock().core = Some((core, pid, Instant::now()));
+}
+
+/// called when the core exits while it is still expected to run
+pub fn record_core_exit(pid: u32, code: Option<i32>, signal: Option<i32>) {
+    let exit = {
+        let mut state = STATE.lock();
+        let (core, uptime) = match &state.core {
+            Some((core, core_pid, started)) if *core_pid == pid => {
+                (core.clone(), Some(started.elapsed().as_secs()))
+            }
+            _ => ("unknown".to_string(), None),
+        };
+        let exit = CoreExit {
+            core,
+            pid,
+            code,
+            signal,
+            time: chrono::Local::now().timestamp(),
+            uptime,
+        };
+        state.exits.push_back(exit.clone());
+        while state.exits.len() > MAX_CORE_EXITS {
+            state.exits.pop_front();
+        }
+        state.core = None;
+        exit
+    };
+
+    let status = match (code, signal) {
+        (_, Some(signal)) => format!("signal {signal}"),
+        (Some(code), None) => format!("code {code}"),
+        (None, None) => "unknown status".to_string(),
+    };
+    let mut report = new_report(
+        CrashKind::CoreExit,
+        format!("the core {} exited with {status}", exit.core),
+    );
+
+    let logs = Logger::global().get_log();
+    let skip = logs.len().saturating_sub(MAX_CORE_LOGS);
+    report.logs = logs
+        .iter()
+        .skip(skip)
+        .map(|line| redact_urls(line))
+        .collect();
+
+    match save(&report) {
+        Ok(_) => tracing::warn!(
+            "{}, the crash report {} is saved",
+            report.message,
+            report.id
+        ),
+        Err(err) => tracing::error!("failed to save the crash report: {err:?}"),
+    }
+
+    tauri::async_runtime::spawn(async move {
+        if upload_enabled() {
+            crate::log_err!(upload(&report.id).await);
+        }
+    });
+}
+
+fn panic_report(payload: &(dyn Any + Send), location: Option<&Location>) -> CrashReport {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".into());
+
+    let mut report = new_report(CrashKind::AppPanic, redact_urls(&message));
+    report.thread = std::thread::current().name().map(|name| name.into());
+    report.location = location.map(|loc| format!("{}:{}:{}", loc.file(), loc.line(), loc.column()));
+    report.backtrace = Some(Backtrace::force_capture().to_string());
+    report
+}
+
+fn new_id() -> String {
+    let now = chrono::Local::now();
+    format!("{}-{}", now.format("%Y%m%d-%H%M%S"), nanoid::nanoid!(6))
+}
+
+fn new_report(kind: CrashKind, message: String) -> CrashReport {
+    CrashReport {
+        id: new_id(),
+        kind,
+        time: chrono::Local::now().timestamp(),
+        app_version: env!("CARGO_PKG_VERSION").into(),
+        os: std::env::consts::OS.into(),
+        arch: std::env::consts::ARCH.into(),
+        thread: None,
+        message,
+        location: None,
+        backtrace: None,
+        state: summary(),
+        logs: vec![],
+        minidump: None,
+        uploaded: false,
+    }
+}
+
+/// the files left by the handlers are named by the id of the run
+fn collect_native() -> Result<()> {
+    let Ok(entries) = fs::read_dir(dirs::app_crashes_dir()?) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path
+            .extension()
+            .is_some_and(|ext| ext == platform::EXTENSION)
+        {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|id| id.to_str()) else {
+            continue;
+        };
+        if !is_valid_id(id) || report_path(id)?.exists() {
+            continue;
+        }
+
+        let time = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .map(|time| chrono::DateTime::<chrono::Local>::from(time).timestamp())
+            .unwrap_or_else(|_| chrono::Local::now().timestamp());
+        let mut report = new_report(CrashKind::NativeCrash, platform::describe(&path));
+        report.id = id.into();
+        report.time = time;
+        // 这次运行的状态和崩溃无关
+        report.state = StateSummary::default();
+        report.minidump = platform::KEEP_FILE.then(|| format!("{id}.{}", platform::EXTENSION));
+        save(&report)?;
+        if !platform::KEEP_FILE {
+            fs::remove_file(&path)?;
+        }
+        tracing::warn!(
+            "{}, the crash report {} is saved",
+            report.message,
+            report.id
+        );
+    }
+    Ok(())
+}
+
+fn summary() -> StateSummary {
+    // 可能在持有锁的时候 panic，拿不到就算了
+    let Some(state) = STATE.try_lock_for(Duration::from_millis(100)) else {
+        return StateSummary::default();
+    };
+    let (core, core_pid, core_uptime) = match &state.core {
+        Some((core, pid, started)) => (
+            Some(core.clone()),
+            Some(*pid),
+            Some(started.elapsed().as_secs()),
+        ),
+        None => (None, None, None),
+    };
+    StateSummary {
+        app_uptime: state.started.elapsed().as_secs(),
+        core,
+        core_pid,
+        core_uptime,
+        recent_core_exits: state.exits.iter().cloned().collect(),
+    }
+}
+
+/// id 只由时间和 nanoid 组成
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn report_path(id: &str) -> Result<PathBuf> {
+    if !is_valid_id(id) {
+        bail!("invalid crash report id `{id}`");
+    }
+    Ok(dirs::app_crashes_dir()?.join(format!("{id}.json")))
+}
+
+fn save(report: &CrashReport) -> Result<PathBuf> {
+    let dir = dirs::app_crashes_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    let path = report_path(&report.id)?;
+    fs::write(&path, serde_json::to_vec_pretty(report)?)?;
+
+    // 只保留最近的报告
+    let reports = list();
+    for report in reports.iter().skip(MAX_REPORTS) {
+        let _ = remove(report);
+    }
+    Ok(path)
+}
+
+fn remove(report: &CrashReport) -> Result<()> {
+    // 只接受文件名
+    if let Some(minidump) = report
+        .minidump
+        .as_deref()
+        .filter(|name| name.strip_suffix(".dmp").is_some_and(is_valid_id))
+    {
+        let _ = fs::remove_file(dirs::app_crashes_dir()?.join(minidump));
+    }
+    fs::remove_file(report_path(&report.id)?)?;
+    Ok(())
+}
+
+/// the saved reports, the newest first
+pub fn list() -> Vec<CrashReport> {
+    let Ok(entries) = dirs::app_crashes_dir().and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+        return vec![];
+    };
+    let mut reports = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| {
+            let data = fs::read(entry.path()).ok()?;
+            serde_json::from_slice::<CrashReport>(&data).ok()
+        })
+        .collect::<Vec<_>>();
+    reports.sort_by(|a, b| b.time.cmp(&a.time));
+    reports
+}
+
+pub fn clear() -> Result<()> {
+    for report in list() {
+        remove(&report)?;
+    }
+    Ok(())
+}
+
+fn upload_enabled() -> bool {
+    let verge = Config::verge();
+    let verge = verge.latest();
+    verge.enable_crash_upload.unwrap_or(false)
+        && verge
+            .crash_upload_url
+            .as_ref()
+            .is_some_and(|url| !url.trim().is_empty())
+}
+
+/// post the report to the url of the config
+pub async fn upload(id: &str) -> Result<()> {
+    let path = report_path(id)?;
+    let mut report = serde_json::from_slice::<CrashReport>(&fs::read(&path)?)?;
+
+    let url = { Config::verge().latest().crash_upload_url.clone() };
+    let url = url
+        .filter(|url| !url.trim().is_empty())
+        .ok_or(anyhow!("the crash report url is not set"))?;
+
+    let client = http::client(HttpPurpose::Webhooks)?;
+    let request = client.request(Method::POST, url.trim()).json(&report);
+    client.send(request).await?.error_for_status()?;
+
+    report.uploaded = true;
+    fs::write(&path, serde_json::to_vec_pretty(&report)?)?;
+    tracing::info!("the crash report {id} is uploaded");
+    Ok(())
+}
+
+/// upload the reports left by the last runs, only when the user opts in
+pub async fn upload_pending() {
+    if !upload_enabled() {
+        return;
+    }
+    for report in list().into_iter().filter(|report| !report.uploaded) {
+        if let Err(err) = upload(&report.id).await {
+            tracing::warn!("failed to upload the crash report {}: {err}", report.id);
+            break;
+        }
+    }
+}
+
+/// write the minidump before the windows error reporting
+#[cfg(windows)]
+mod platform {
+    use anyhow::Result;
+    use once_cell::sync::OnceCell;
+    use std::{os::windows::ffi::OsStrExt, path::Path};
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, GENERIC_WRITE, INVALID_HANDLE_VALUE},
+        Storage::FileSystem::{CreateFileW, CREATE_ALWAYS, FILE_ATTRIBUTE_NORMAL},
+        System::{
+            Diagnostics::Debug::{
+                MiniDumpNormal, MiniDumpWriteDump, SetUnhandledExceptionFilter, EXCEPTION_POINTERS,
+                MINIDUMP_EXCEPTION_INFORMATION,
+            },
+            Threading::{GetCurrentProcess, GetCurrentProcessId, GetCurrentThreadId},
+        },
+    };
+
+    pub const EXTENSION: &str = "dmp";
+    /// the minidump is kept with the report
+    pub const KEEP_FILE: bool = true;
+
+    /// let the windows error reporting run after the filter
+    const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+
+    /// the filter should not allocate, the path is prepared before
+    static MINIDUMP: OnceCell<Vec<u16>> = OnceCell::new();
+
+    pub fn install(path: &Path) -> Result<()> {
+        let path = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let _ = MINIDUMP.set(path);
+        unsafe { SetUnhandledExceptionFilter(Some(filter)) };
+        Ok(())
+    }
+
+    unsafe extern "system" fn filter(info: *const EXCEPTION_POINTERS) -> i32 {
+        let Some(path) = MINIDUMP.get() else {
+            return EXCEPTION_CONTINUE_SEARCH;
+        };
+        let file = CreateFileW(
+            path.as_ptr(),
+            GENERIC_WRITE,
+            0,
+            std::ptr::null(),
+            CREATE_ALWAYS,
+            FILE_ATTRIBUTE_NORMAL,
+            0,
+        );
+        if file == INVALID_HANDLE_VALUE {
+            return EXCEPTION_CONTINUE_SEARCH;
+        }
+        let exception = MINIDUMP_EXCEPTION_INFORMATION {
+            ThreadId: GetCurrentThreadId(),
+            ExceptionPointers: info as *mut _,
+            ClientPointers: 0,
+        };
+        MiniDumpWriteDump(
+            GetCurrentProcess(),
+            GetCurrentProcessId(),
+            file,
+            MiniDumpNormal,
+            &exception,
+            std::ptr::null(),
+            std::ptr::null(),
+        );
+        CloseHandle(file);
+        EXCEPTION_CONTINUE_SEARCH
+    }
+
+    pub fn describe(path: &Path) -> String {
+        // the report may be uploaded, no user dir in the message
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        format!("the app crashed, the minidump `{name}` is saved in the crashes dir")
+    }
+}
+
+/// write the signal to the marker, then hand it to the previous handler
+#[cfg(unix)]
+mod platform {
+    use anyhow::{bail, Result};
+    use once_cell::sync::OnceCell;
+    use std::{ffi::CString, fs, os::unix::ffi::OsStrExt, path::Path, ptr::null_mut};
+
+    pub const EXTENSION: &str = "native";
+    /// the marker is removed after the report is saved
+    pub const KEEP_FILE: bool = false;
+
+    const SIGNALS: [libc::c_int; 5] = [
+        libc::SIGSEGV,
+        libc::SIGBUS,
+        libc::SIGILL,
+        libc::SIGFPE,
+        libc::SIGABRT,
+    ];
+
+    type Handler = extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void);
+
+    /// the handler can only make the async-signal-safe calls, everything is prepared before
+    static MARKER: OnceCell<CString> = OnceCell::new();
+    /// the handlers before ours, std reports the stack overflows in its own
+    static PREVIOUS: OnceCell<Vec<(libc::c_int, libc::sigaction)>> = OnceCell::new();
+
+    pub fn install(path: &Path) -> Result<()> {
+        let _ = MARKER.set(CString::new(path.as_os_str().as_bytes())?);
+
+        let mut previous = Vec::new();
+        for signal in SIGNALS {
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = handler as Handler as usize;
+                action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+                libc::sigemptyset(&mut action.sa_mask);
+                let mut old: libc::sigaction = std::mem::zeroed();
+                if libc::sigaction(signal, &action, &mut old) != 0 {
+                    bail!(
+                        "failed to handle the signal {signal}: {}",
+                        std::io::Error::last_os_error()
+                    );
+                }
+                previous.push((signal, old));
+            }
+        }
+        let _ = PREVIOUS.set(previous);
+        Ok(())
+    }
+
+    extern "C" fn handler(signal: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+        unsafe {
+            // 只记第一个信号，std 报告栈溢出之后还会 abort
+            if let Some(path) = MARKER.get() {
+                let mode: libc::c_uint = 0o600;
+                let fd = libc::open(
+                    path.as_ptr(),
+                    libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL,
+                    mode,
+                );
+                if fd >= 0 {
+                    let mut buf = [0u8; 10];
+                    let digits = itoa(signal.unsigned_abs(), &mut buf);
+                    libc::write(fd, digits.as_ptr() as *const libc::c_void, digits.len());
+                    libc::close(fd);
+                }
+            }
+
+            let previous = PREVIOUS
+                .get()
+                .and_then(|list| list.iter().find(|(s, _)| *s == signal));
+            match previous {
+                Some((_, action)) => libc::sigaction(signal, action, null_mut()),
+                None => libc::sigaction(signal, &std::mem::zeroed(), null_mut()),
+            };
+            // the faulting instruction runs again after returning, the previous handler gets the
+            // same fault (std reports the stack overflows). 其他进程发来的信号要重新发送
+            if info.is_null() || (*info).si_code <= 0 {
+                libc::raise(signal);
+            }
+        }
+    }
+
+    /// the decimal digits without allocating
+    fn itoa(mut n: u32, buf: &mut [u8; 10]) -> &[u8] {
+        let mut i = buf.len();
+        loop {
+            i -= 1;
+            buf[i] = b'0' + (n % 10) as u8;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        &buf[i..]
+    }
+
+    pub fn describe(path: &Path) -> String {
+        let signal = fs::read_to_string(path).unwrap_or_default();
+        match signal.trim().parse::<libc::c_int>() {
+            Ok(signal) => format!("the app crashed with the signal {signal}"),
+            Err(_) => "the app crashed with an unknown signal".into(),
+        }
+    }
+
+    #[test]
+    fn test_itoa() {
+        let mut buf = [0u8; 10];
+        assert_eq!(itoa(0, &mut buf), b"0");
+        assert_eq!(itoa(11, &mut buf), b"11");
+        assert_eq!(itoa(u32::MAX, &mut buf), b"4294967295");
+    }
+}
+
+#[cfg(not(any(windows, unix)))]
+mod platform {
+    use anyhow::{bail, Result};
+    use std::path::Path;
+
+    pub const EXTENSION: &str = "native";
+    pub const KEEP_FILE: bool = false;
+
+    pub fn install(_: &Path) -> Result<()> {
+        bail!("the native crashes are not captured on this platform")
+    }
+
+    pub fn describe(_: &Path) -> String {
+        "the app crashed".into()
+    }
+}
+
+#[test]
+fn test_valid_id() {
+    assert!(is_valid_id("20240101-120000-a_b-C1"));
+    assert!(!is_valid_id("../verge"));
+    assert!(!is_valid_id(""));
+}
@@ -0,0 +1,235 @@
+use super::handle;
+use crate::{
+    config::{Config, ImportStrategy, PrfItem},
+    feat,
+    utils::i18n::{t, t_args},
+};
+use anyhow::{anyhow, bail, Result};
+use tauri::api::dialog;
+use url::Url;
+
+/// the url schemes handled by the app
+/// `clash://` is used by the one-click import buttons of most providers
+pub const SCHEMES: [&str; 2] = ["clash", "nyanpasu"];
+
+/// `clash://install-config?url=<subscription>&name=<name>&activate=true`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallConfig {
+    pub url: String,
+    pub name: Option<String>,
+    pub activate: bool,
+}
+
+pub fn is_deep_link(arg: &str) -> bool {
+    SCHEMES
+        .iter()
+        .any(|scheme| arg.starts_with(&format!("{scheme}://")))
+}
+
+/// find the deep link passed by the system, e.g. `clash-nyanpasu.exe "clash://..."`
+pub fn from_args() -> Option<String> {
+    std::env::args().skip(1).find(|arg| is_deep_link(arg))
+}
+
+pub fn parse(link: &str) -> Result<InstallConfig> {
+    let link = Url::parse(link)?;
+    if !SCHEMES.contains(&link.scheme()) {
+        bail!("unsupported scheme `{}`", link.scheme());
+    }
+    if link.host_str() != Some("install-config") {
+        bail!(
+            "unsupported action `{}`",
+            link.host_str().unwrap_or_default()
+        );
+    }
+
+    let mut url = None;
+    let mut name = None;
+    let mut activate = false;
+    for (key, value) in link.query_pairs() {
+        match key.as_ref() {
+            "url" => url = Some(value.into_owned()),
+            "name" => name = Some(value.into_owned()).filter(|s| !s.is_empty()),
+            "activate" => activate = matches!(value.as_ref(), "1" | "true"),
+            _ => {}
+        }
+    }
+
+    let url = url.ok_or(anyhow!("the subscription url is missing"))?;
+    match Url::parse(&url) {
+        Ok(u) if u.scheme() == "http" || u.scheme() == "https" => {}
+        _ => bail!("invalid subscription url `{url}`"),
+    }
+
+    Ok(InstallConfig {
+        url,
+        name,
+        activate,
+    })
+}
+
+/// handle the deep link in background
+pub fn handle_link(link: String) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = install_config(&link).await {
+            tracing::error!("failed to handle the deep link: {err}");
+            handle::Handle::notice_message("import_profile::error", format!("{err}"));
+        }
+    });
+}
+
+async fn install_config(link: &str) -> Result<()> {
+    let request = parse(link)?;
+
+    let duplicate = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        profiles
+            .find_by_url(&request.url)
+            .map(|item| item.name.clone())
+    };
+    let message = match &duplicate {
+        Some(name) => t_args(
+            "The profile \"{name}\" has the same url, update it?\n\n{url}",
+            &[
+                ("name", &name.clone().unwrap_or_default()),
+                ("url", &request.url),
+            ],
+        ),
+        None => t_args(
+            "Import the profile from the following url?\n\n{url}",
+            &[("url", &request.url)],
+        ),
+    };
+    if !confirm(&t("Import Profile"), message).await {
+        tracing::info!("the deep link import is cancelled");
+        return Ok(());
+    }
+
+    let uid = match duplicate {
+        // 已经导入过的订阅只更新
+        Some(_) => {
+            let outcome =
+                feat::import_profile(request.url.clone(), None, Some(ImportStrategy::Merge))
+                    .await?;
+            outcome.uid().to_string()
+        }
+        None => {
+            let item = PrfItem::from_url(&request.url, request.name.clone(), None, None).await?;
+            let uid = item.uid.clone().unwrap_or_default();
+            Config::profiles().data().append_item(item)?;
+            uid
+        }
+    };
+    handle::Handle::refresh_profiles();
+
+    // 没有当前配置时直接启用
+    let has_current = { Config::profiles().latest().get_current().is_some() };
+    if request.activate || !has_current {
+        feat::switch_profile(uid).await?;
+        handle::Handle::refresh_profiles();
+    }
+
+    handle::Handle::notice_message("import_profile::ok", "ok");
+    Ok(())
+}
+
+/// ask the user with a native dialog
+pub async fn confirm(title: &str, message: String) -> bool {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let window = handle::Handle::global().get_window();
+
+    dialog::ask(window.as_ref(), title, message, move |yes| {
+        let _ = tx.send(yes);
+    });
+    rx.await.unwrap_or(false)
+}
+
+/// register the url schemes to the system
+/// macos reads the schemes from `Info.plist`
+#[cfg(target_os = "windows")]
+pub fn register() -> Result<()> {
+    use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+
+    let app_exe = dunce::canonicalize(std::env::current_exe()?)?;
+    let app_exe = crate::utils::dirs::path_to_str(&app_exe)?.to_string();
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    for scheme in SCHEMES {
+        let (key, _) = hkcu.create_subkey(format!("Software\\Classes\\{scheme}"))?;
+        key.set_value("", &format!("URL:{scheme} Protocol"))?;
+        key.set_value("URL Protocol", &"")?;
+
+        let (icon, _) = key.create_subkey("DefaultIcon")?;
+        icon.set_value("", &format!("\"{app_exe}\",0"))?;
+
+        let (command, _) = key.create_subkey("shell\\open\\command")?;
+        command.set_value("", &format!("\"{app_exe}\" \"%1\""))?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub fn register() -> Result<()> {
+    use std::process::Command;
+
+    // the appimage is mounted to a random path
+    let app_exe = match std::env::var_os("APPIMAGE") {
+        Some(path) => std::path::PathBuf::from(path),
+        None => std::env::current_exe()?,
+    };
+    let app_exe = crate::utils::dirs::path_to_str(&app_exe)?.to_string();
+
+    let file_name = "clash-nyanpasu-url-handler.desktop";
+    let dir = ::dirs::data_dir()
+        .ok_or(anyhow!("failed to get the data dir"))?
+        .join("applications");
+    std::fs::create_dir_all(&dir)?;
+
+    let mime_types = SCHEMES
+        .iter()
+        .map(|scheme| format!("x-scheme-handler/{scheme}"))
+        .collect::<Vec<_>>();
+
+    let desktop = format!(
+        "[Desktop Entry]\nType=Application\nName=Clash Nyanpasu\nExec=\"{app_exe}\" %u\nNoDisplay=true\nTerminal=false\nMimeType={};\n",
+        mime_types.join(";")
+    );
+    std::fs::write(dir.join(file_name), desktop)?;
+
+    let status = Command::new("xdg-mime")
+        .arg("default")
+        .arg(file_name)
+        .args(&mime_types)
+        .status()?;
+    if !status.success() {
+        bail!("failed to register the url schemes by xdg-mime");
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn register() -> Result<()> {
+    tauri_plugin_deep_link::register(SCHEMES[0], handle_link)
+        .map_err(|err| anyhow!("failed to listen the url events: {err}"))
+}
+
+#[test]
+fn test_parse() {
+    let request = parse(
+        "clash://install-config?url=https%3A%2F%2Fexample.com%2Fsub%3Ftoken%3D1&name=Example",
+    )
+    .unwrap();
+    assert_eq!(request.url, "https://example.com/sub?token=1");
+    assert_eq!(request.name.as_deref(), Some("Example"));
+    assert!(!request.activate);
+
+    let request =
+        parse("nyanpasu://install-config?url=https://example.com/sub&activate=true").unwrap();
+    assert!(request.activate);
+
+    assert!(parse("clash://install-config").is_err());
+    assert!(parse("clash://install-config?url=file:///etc/passwd").is_err());
+    assert!(parse("clash://unknown?url=https://example.com").is_err());
+    assert!(parse("https://install-config?url=https://example.com").is_err());
+}
@@ -0,0 +1,334 @@
+use super::{
+    clash_api, pac,
+    sysopt::{self, Sysopt},
+    CoreManager,
+};
+use crate::config::{ClashCore, Config};
+use serde::Serialize;
+use std::{
+    fmt::Display,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use sysproxy::{Autoproxy, Sysproxy};
+use tokio::{net::TcpStream, time::timeout};
+
+const TEST_URL: &str = "http://www.gstatic.com/generate_204";
+const TEST_DOMAIN: &str = "www.gstatic.com";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// not applicable, or the previous step failed
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub id: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// how to fix it, only for the failed checks
+    pub hint: Option<&'static str>,
+}
+
+impl DoctorCheck {
+    fn pass(id: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(id: &'static str, detail: impl Into<String>, hint: &'static str) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint),
+        }
+    }
+
+    fn skip(id: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Skip,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn passed(&self) -> bool {
+        self.status == CheckStatus::Pass
+    }
+}
+
+/// run the checklist in order, the later steps are skipped when the steps they rely on failed
+pub async fn run() -> Vec<DoctorCheck> {
+    let mut checks = vec![];
+
+    let core = check_core();
+    let core_alive = core.passed();
+    checks.push(core);
+
+    let controller = match core_alive {
+        true => check_controller().await,
+        false => DoctorCheck::skip("controller", "the core is not running"),
+    };
+    let controller_ok = controller.passed();
+    checks.push(controller);
+
+    let port = check_port().await;
+    let port_ok = port.passed();
+    checks.push(port);
+
+    checks.push(check_system_proxy());
+
+    checks.push(match controller_ok {
+        true => check_dns().await,
+        false => DoctorCheck::skip("dns", "the controller is unreachable"),
+    });
+
+    checks.push(match port_ok {
+        true => check_proxy_fetch().await,
+        false => DoctorCheck::skip("proxy_fetch", "the mixed port is not listening"),
+    });
+
+    checks
+}
+
+fn check_core() -> DoctorCheck {
+    match CoreManager::global().is_running() {
+        true => DoctorCheck::pass("core", "the core is running"),
+        false => DoctorCheck::fail(
+            "core",
+            "the core is not running",
+            "Restart the core from the tray, or check the core logs for the startup errors",
+        ),
+    }
+}
+
+async fn check_controller() -> DoctorCheck {
+    match clash_api::get_version().await {
+        Ok(res) => DoctorCheck::pass("controller", format!("version {}", res.version)),
+        Err(err) => DoctorCheck::fail(
+            "controller",
+            err.to_string(),
+            "Check the `external-controller` and `secret` of the clash config, the port may be used by another program",
+        ),
+    }
+}
+
+fn mixed_port() -> u16 {
+    let verge_port = { Config::verge().latest().verge_mixed_port };
+    verge_port.unwrap_or(Config::clash().latest().get_mixed_port())
+}
+
+async fn check_port() -> DoctorCheck {
+    let port = mixed_port();
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let err = match timeout(Duration::from_secs(3), TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => {
+            return DoctorCheck::pass("port", format!("the mixed port {port} is listening"))
+        }
+        Ok(Err(err)) => err.to_string(),
+        Err(_) => "timed out".to_string(),
+    };
+    DoctorCheck::fail(
+        "port",
+        format!("failed to connect to the mixed port {port}: {err}"),
+        "The port may be used by another program, change the mixed port or enable the random port",
+    )
+}
+
+fn check_system_proxy() -> DoctorCheck {
+    let (enable, pac) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.enable_system_proxy.unwrap_or(false),
+            verge.enable_proxy_pac.unwrap_or(false),
+        )
+    };
+    if !enable {
+        return DoctorCheck::skip("system_proxy", "the system proxy is disabled");
+    }
+    if Sysopt::global().is_sysproxy_paused() {
+        return DoctorCheck::skip(
+            "system_proxy",
+            "the system proxy is paused by the network rules",
+        );
+    }
+
+    match pac {
+        true => evaluate_auto_proxy(Autoproxy::get_auto_proxy(), &pac::pac_url()),
+        // 限速开启时系统代理指向限速的入口，而不是混合端口
+        false => evaluate_system_proxy(Sysproxy::get_system_proxy(), sysopt::proxy_port()),
+    }
+}
+
+fn evaluate_system_proxy(proxy: Result<Sysproxy, impl Display>, port: u16) -> DoctorCheck {
+    match proxy {
+        Ok(proxy) if proxy.enable && proxy.port == port => {
+            DoctorCheck::pass("system_proxy", format!("{}:{}", proxy.host, proxy.port))
+        }
+        Ok(proxy) => DoctorCheck::fail(
+            "system_proxy",
+            match proxy.enable {
+                true => format!(
+                    "the system proxy points to {}:{}, expected port {port}",
+                    proxy.host, proxy.port
+                ),
+                false => "the system proxy is not set".into(),
+            },
+            "Toggle the system proxy again, another program may have overwritten it, enable the proxy guard to keep it",
+        ),
+        Err(err) => DoctorCheck::fail(
+            "system_proxy",
+            err.to_string(),
+            "Failed to read the system proxy settings, set the proxy manually",
+        ),
+    }
+}
+
+fn evaluate_auto_proxy(proxy: Result<Autoproxy, impl Display>, url: &str) -> DoctorCheck {
+    match proxy {
+        Ok(proxy) if proxy.enable && proxy.url == url => {
+            DoctorCheck::pass("system_proxy", format!("pac {}", proxy.url))
+        }
+        Ok(proxy) => DoctorCheck::fail(
+            "system_proxy",
+            match proxy.enable {
+                true => format!("the pac points to {}, expected {url}", proxy.url),
+                false => "the pac is not set".into(),
+            },
+            "Toggle the system proxy again, another program may have overwritten it, enable the proxy guard to keep it",
+        ),
+        Err(err) => DoctorCheck::fail(
+            "system_proxy",
+            err.to_string(),
+            "Failed to read the pac settings, set the pac url manually",
+        ),
+    }
+}
+
+async fn check_dns() -> DoctorCheck {
+    let core = { Config::verge().latest().clash_core.clone() };
+    // 只有 meta 内核有 dns 查询接口
+    if matches!(
+        core,
+        Some(ClashCore::ClashPremium) | Some(ClashCore::ClashRs)
+    ) {
+        return DoctorCheck::skip("dns", "the core does not support the dns query");
+    }
+
+    match clash_api::query_dns(TEST_DOMAIN, None).await {
+        Ok(res) if !res.answer.is_empty() => {
+            let answers = res
+                .answer
+                .iter()
+                .map(|a| a.data.as_str())
+                .collect::<Vec<_>>();
+            DoctorCheck::pass("dns", format!("{TEST_DOMAIN} -> {}", answers.join(", ")))
+        }
+        Ok(res) => DoctorCheck::fail(
+            "dns",
+            format!("no answer for {TEST_DOMAIN}, status {}", res.status),
+            "Check the `dns` section of the profile, the nameservers may be blocked by the network",
+        ),
+        Err(err) => DoctorCheck::fail(
+            "dns",
+            err.to_string(),
+            "Check the `dns` section of the profile, the nameservers may be blocked by the network",
+        ),
+    }
+}
+
+async fn check_proxy_fetch() -> DoctorCheck {
+    let port = mixed_port();
+    let client = reqwest::Proxy::all(format!("http://127.0.0.1:{port}")).and_then(|proxy| {
+        reqwest::ClientBuilder::new()
+            .proxy(proxy)
+            .timeout(TIMEOUT)
+            .build()
+    });
+    let client = match client {
+        Ok(client) => client,
+        Err(err) => return DoctorCheck::skip("proxy_fetch", err.to_string()),
+    };
+
+    let start = Instant::now();
+    match client.get(TEST_URL).send().await {
+        Ok(response) if response.status().as_u16() == 204 => DoctorCheck::pass(
+            "proxy_fetch",
+            format!("{TEST_URL} in {}ms", start.elapsed().as_millis()),
+        ),
+        Ok(response) => DoctorCheck::fail(
+            "proxy_fetch",
+            format!("unexpected status {}", response.status()),
+            "The request was answered by something else, check the rules and the selected proxy",
+        ),
+        Err(err) => DoctorCheck::fail(
+            "proxy_fetch",
+            err.to_string(),
+            "Check the selected proxy and the rules, try another proxy or update the subscription",
+        ),
+    }
+}
+
+#[test]
+fn test_evaluate_system_proxy() {
+    let proxy = |enable, port| {
+        Ok::<_, String>(Sysproxy {
+            enable,
+            host: "127.0.0.1".into(),
+            port,
+            bypass: String::new(),
+        })
+    };
+    assert_eq!(
+        evaluate_system_proxy(proxy(true, 7890), 7890).status,
+        CheckStatus::Pass
+    );
+    // 限速的入口和混合端口不同
+    assert_eq!(
+        evaluate_system_proxy(proxy(true, 7890), 7891).status,
+        CheckStatus::Fail
+    );
+    assert_eq!(
+        evaluate_system_proxy(proxy(false, 7890), 7890).status,
+        CheckStatus::Fail
+    );
+    assert_eq!(
+        evaluate_system_proxy(Err::<Sysproxy, _>("unsupported"), 7890).status,
+        CheckStatus::Fail
+    );
+}
+
+#[test]
+fn test_evaluate_auto_proxy() {
+    let url = "http://127.0.0.1:33331/pac";
+    let proxy = |enable, url: &str| {
+        Ok::<_, String>(Autoproxy {
+            enable,
+            url: url.into(),
+        })
+    };
+    assert_eq!(
+        evaluate_auto_proxy(proxy(true, url), url).status,
+        CheckStatus::Pass
+    );
+    assert_eq!(
+        evaluate_auto_proxy(proxy(true, "http://127.0.0.1:1/pac"), url).status,
+        CheckStatus::Fail
+    );
+    assert_eq!(
+        evaluate_auto_proxy(proxy(false, url), url).status,
+        CheckStatus::Fail
+    );
+}
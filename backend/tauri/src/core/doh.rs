@@ -0,0 +1,329 @@
+//! resolve the hosts of the app's own requests with dns over https
+//! the subscriptions and the updates fail when the isp poisons the dns
+//! the server is queried with the wire format of RFC 8484, e.g. `https://1.1.1.1/dns-query`
+use crate::config::Config;
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    ClientBuilder, Url,
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::join;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// the ttl of the answers is clamped to this range
+const MIN_TTL: u64 = 60;
+const MAX_TTL: u64 = 3600;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const DNS_MESSAGE: &str = "application/dns-message";
+
+struct Cached {
+    ips: Vec<IpAddr>,
+    expires: Instant,
+}
+
+pub struct DohResolver {
+    client: reqwest::Client,
+    cache: Mutex<HashMap<String, Cached>>,
+}
+
+impl DohResolver {
+    pub fn global() -> &'static DohResolver {
+        static DOH_RESOLVER: OnceCell<DohResolver> = OnceCell::new();
+
+        DOH_RESOLVER.get_or_init(|| DohResolver {
+            // 服务器本身的域名走系统 dns，填 ip 可以避开
+            client: reqwest::ClientBuilder::new()
+                .use_rustls_tls()
+                .no_proxy()
+                .timeout(TIMEOUT)
+                .build()
+                .expect("failed to build the doh client"),
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn lookup(&self, server: &str, host: &str) -> Result<Vec<IpAddr>> {
+        if let Some(cached) = self.cache.lock().get(host) {
+            if cached.expires > Instant::now() {
+                return Ok(cached.ips.clone());
+            }
+        }
+
+        let (v4, v6) = join!(
+            self.query(server, host, TYPE_A),
+            self.query(server, host, TYPE_AAAA)
+        );
+        // 没有 ipv6 记录很常见，只要有一个成功就行
+        let (mut ips, ttl) = match (v4, v6) {
+            (Err(err), Err(_)) => return Err(err),
+            (v4, v6) => {
+                let (mut ips, ttl) = v4.unwrap_or((vec![], MAX_TTL));
+                let (v6_ips, v6_ttl) = v6.unwrap_or((vec![], MAX_TTL));
+                ips.extend(v6_ips);
+                (ips, ttl.min(v6_ttl))
+            }
+        };
+        ips.dedup();
+        if ips.is_empty() {
+            bail!("no address of `{host}` from the doh server");
+        }
+
+        self.cache.lock().insert(
+            host.to_string(),
+            Cached {
+                ips: ips.clone(),
+                expires: Instant::now() + Duration::from_secs(ttl.clamp(MIN_TTL, MAX_TTL)),
+            },
+        );
+        Ok(ips)
+    }
+
+    async fn query(&self, server: &str, host: &str, kind: u16) -> Result<(Vec<IpAddr>, u64)> {
+        let response = self
+            .client
+            .post(server)
+            .header("content-type", DNS_MESSAGE)
+            .header("accept", DNS_MESSAGE)
+            .body(build_query(host, kind)?)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        parse_answers(&response, kind)
+    }
+
+    /// drop the cached answers, e.g. after changing the server
+    pub fn clear(&self) {
+        self.cache.lock().clear();
+    }
+}
+
+/// a recursive query of one question, the id is 0 so the responses can be cached
+fn build_query(host: &str, kind: u16) -> Result<Vec<u8>> {
+    let mut message = vec![0, 0, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            bail!("invalid host `{host}`");
+        }
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+    if message.len() > 12 + 255 {
+        bail!("the host `{host}` is too long");
+    }
+    message.extend_from_slice(&kind.to_be_bytes());
+    message.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(message)
+}
+
+/// read the dns message without copying, every read checks the length
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos + len;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(anyhow!("the dns message is truncated"))?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// the names are not needed, only skipped, a compression pointer ends the name
+    fn skip_name(&mut self) -> Result<()> {
+        loop {
+            let len = self.take(1)?[0];
+            match len {
+                0 => return Ok(()),
+                len if len & 0xC0 == 0xC0 => {
+                    self.take(1)?;
+                    return Ok(());
+                }
+                len => {
+                    self.take(len as usize)?;
+                }
+            }
+        }
+    }
+}
+
+fn parse_answers(message: &[u8], kind: u16) -> Result<(Vec<IpAddr>, u64)> {
+    let mut reader = Reader {
+        data: message,
+        pos: 0,
+    };
+    let _id = reader.u16()?;
+    let flags = reader.u16()?;
+    // 0 是 NOERROR，其余都算失败
+    let rcode = flags & 0x000F;
+    if rcode != 0 {
+        bail!("the doh server answered with rcode {rcode}");
+    }
+    let questions = reader.u16()?;
+    let answers = reader.u16()?;
+    reader.take(4)?;
+
+    for _ in 0..questions {
+        reader.skip_name()?;
+        reader.take(4)?;
+    }
+
+    // CNAME 的记录也在 Answer 里，只取需要的类型
+    let mut result = vec![];
+    for _ in 0..answers {
+        reader.skip_name()?;
+        let (rtype, class, ttl) = (reader.u16()?, reader.u16()?, reader.u32()?);
+        let len = reader.u16()? as usize;
+        let data = reader.take(len)?;
+        if rtype != kind || class != CLASS_IN {
+            continue;
+        }
+        let ip = match (rtype, data.len()) {
+            (TYPE_A, 4) => IpAddr::from(Ipv4Addr::new(data[0], data[1], data[2], data[3])),
+            (TYPE_AAAA, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(data);
+                IpAddr::from(Ipv6Addr::from(octets))
+            }
+            _ => continue,
+        };
+        result.push((ip, ttl as u64));
+    }
+    let ttl = result.iter().map(|(_, ttl)| *ttl).min().unwrap_or(MAX_TTL);
+    Ok((result.into_iter().map(|(ip, _)| ip).collect(), ttl))
+}
+
+/// the configured server, `None` to use the system dns
+pub fn server() -> Option<String> {
+    Config::verge()
+        .latest()
+        .app_dns_over_https
+        .clone()
+        .filter(|server| !server.trim().is_empty())
+}
+
+pub fn validate_server(server: &str) -> Result<()> {
+    if server.trim().is_empty() {
+        return Ok(());
+    }
+    let url = Url::parse(server.trim()).map_err(|err| anyhow!("invalid doh server: {err}"))?;
+    if url.scheme() != "https" {
+        bail!("the doh server must be an https url");
+    }
+    if url.host().is_none() {
+        bail!("the doh server has no host");
+    }
+    Ok(())
+}
+
+struct AppResolver {
+    server: String,
+}
+
+impl Resolve for AppResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let server = self.server.clone();
+        Box::pin(async move {
+            let host = name.as_str();
+            let ips = match DohResolver::global().lookup(&server, host).await {
+                Ok(ips) => ips,
+                // 服务器不可用时退回系统 dns，总比请求直接失败好
+                Err(err) => {
+                    tracing::warn!(
+                        "failed to resolve `{host}` with doh, use the system dns: {err}"
+                    );
+                    tokio::net::lookup_host((host, 0))
+                        .await?
+                        .map(|addr| addr.ip())
+                        .collect()
+                }
+            };
+            let addrs: Addrs = Box::new(ips.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(addrs)
+        })
+    }
+}
+
+/// resolve the hosts with the configured doh server, keep the builder if not set
+pub fn apply(builder: ClientBuilder) -> ClientBuilder {
+    match server() {
+        Some(server) => builder.dns_resolver(Arc::new(AppResolver {
+            server: server.trim().to_string(),
+        })),
+        None => builder,
+    }
+}
+
+#[test]
+fn test_parse_answers() {
+    let query = build_query("example.com", TYPE_A).unwrap();
+    assert_eq!(
+        &query[12..],
+        b"\x07example\x03com\x00\x00\x01\x00\x01".as_slice()
+    );
+    assert!(build_query("a..com", TYPE_A).is_err());
+
+    let mut response = query.clone();
+    // QR RD RA, NOERROR, three answers
+    response[2..4].copy_from_slice(&[0x81, 0x80]);
+    response[6..8].copy_from_slice(&[0, 3]);
+    let answer = |rtype: u16, ttl: u32, data: &[u8]| {
+        let mut record = vec![0xC0, 0x0C];
+        record.extend_from_slice(&rtype.to_be_bytes());
+        record.extend_from_slice(&CLASS_IN.to_be_bytes());
+        record.extend_from_slice(&ttl.to_be_bytes());
+        record.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        record.extend_from_slice(data);
+        record
+    };
+    response.extend(answer(5, 300, b"\x03cdn\xC0\x0C"));
+    response.extend(answer(TYPE_A, 120, &[93, 184, 216, 34]));
+    response.extend(answer(TYPE_A, 600, &[93, 184, 216, 35]));
+
+    let (ips, ttl) = parse_answers(&response, TYPE_A).unwrap();
+    assert_eq!(
+        ips,
+        vec![
+            "93.184.216.34".parse::<IpAddr>().unwrap(),
+            "93.184.216.35".parse().unwrap()
+        ]
+    );
+    assert_eq!(ttl, 120);
+
+    // NXDOMAIN
+    let mut failed = query.clone();
+    failed[2..4].copy_from_slice(&[0x81, 0x83]);
+    assert!(parse_answers(&failed, TYPE_A).is_err());
+    assert!(parse_answers(&response[..response.len() - 2], TYPE_A).is_err());
+
+    assert!(validate_server("https://1.1.1.1/dns-query").is_ok());
+    assert!(validate_server("").is_ok());
+    assert!(validate_server("http://1.1.1.1/dns-query").is_err());
+}
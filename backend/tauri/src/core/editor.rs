@@ -0,0 +1,470 @@
+//! the backend of the yaml editor: format, lint and apply with the core check
+//!
+//! the positions of the syntax errors are exact, the ones of the schema errors
+//! are the first line mentioning the key or the name.
+
+use super::{handle, CoreManager};
+use crate::{
+    config::{check_config_keys, rule_targets, ClashCore, Config, PrfItem},
+    feat,
+};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::{collections::HashSet, io::Write};
+
+const PORT_KEYS: [&str; 5] = [
+    "port",
+    "socks-port",
+    "mixed-port",
+    "redir-port",
+    "tproxy-port",
+];
+const BOOL_KEYS: [&str; 3] = ["allow-lan", "ipv6", "unified-delay"];
+const MODES: [&str; 4] = ["rule", "global", "direct", "script"];
+const LOG_LEVELS: [&str; 5] = ["silent", "error", "warning", "info", "debug"];
+
+/// the logic rules contain commas in the payload
+const LOGIC_RULES: [&str; 4] = ["AND", "OR", "NOT", "SUB-RULE"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// one-based, none if unknown
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatResult {
+    pub content: String,
+    /// the comments can not be kept by the formatter
+    pub comments_dropped: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApplyResult {
+    pub saved: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Config,
+    Merge,
+    Script,
+}
+
+impl FileKind {
+    fn of(item: &PrfItem) -> Self {
+        match item.itype.as_deref() {
+            Some("merge") => FileKind::Merge,
+            Some("script") => FileKind::Script,
+            _ => FileKind::Config,
+        }
+    }
+}
+
+pub fn format(content: &str) -> Result<FormatResult> {
+    let value = serde_yaml::from_str::<Value>(content)?;
+    let comments_dropped = content.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with('#') || line.contains(" #")
+    });
+
+    Ok(FormatResult {
+        content: serde_yaml::to_string(&value)?,
+        comments_dropped,
+    })
+}
+
+fn syntax_error(err: &serde_yaml::Error) -> Diagnostic {
+    let location = err.location();
+    Diagnostic {
+        line: location.as_ref().map(|l| l.line()),
+        column: location.as_ref().map(|l| l.column()),
+        severity: Severity::Error,
+        message: err.to_string(),
+    }
+}
+
+/// the error of `clash -t`, e.g. `yaml: unmarshal errors: line 12: ...`
+fn core_error(err: &anyhow::Error) -> Diagnostic {
+    static LINE: Lazy<Regex> = Lazy::new(|| Regex::new(r"line (\d+)").unwrap());
+
+    let message = err.to_string();
+    let line = LINE
+        .captures(&message)
+        .and_then(|caps| caps[1].parse().ok());
+    Diagnostic {
+        line,
+        column: None,
+        severity: Severity::Error,
+        message,
+    }
+}
+
+pub fn lint(item: &PrfItem, content: &str) -> Vec<Diagnostic> {
+    let core = { Config::verge().latest().clash_core.clone() };
+    lint_content(FileKind::of(item), content, &core.unwrap_or_default())
+}
+
+fn lint_content(kind: FileKind, content: &str, core: &ClashCore) -> Vec<Diagnostic> {
+    if kind == FileKind::Script {
+        return vec![];
+    }
+
+    let mapping = match serde_yaml::from_str::<Value>(content) {
+        Ok(Value::Mapping(mapping)) => mapping,
+        Ok(Value::Null) => Mapping::new(),
+        Ok(_) => {
+            return vec![Diagnostic {
+                line: Some(1),
+                column: Some(1),
+                severity: Severity::Error,
+                message: "the file should be a mapping".into(),
+            }]
+        }
+        Err(err) => return vec![syntax_error(&err)],
+    };
+
+    let mut linter = Linter::new(content);
+    match kind {
+        FileKind::Config => linter.check_config(&mapping, core),
+        FileKind::Merge => linter.check_merge(&mapping),
+        FileKind::Script => {}
+    }
+    linter.diagnostics
+}
+
+struct Linter<'a> {
+    lines: Vec<&'a str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn new(content: &'a str) -> Self {
+        Self {
+            lines: content.lines().collect(),
+            diagnostics: vec![],
+        }
+    }
+
+    /// the first matched line, one-based
+    fn locate(&self, matches: impl Fn(&str) -> bool) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| matches(line))
+            .map(|index| index + 1)
+    }
+
+    fn locate_key(&self, key: &str) -> Option<usize> {
+        self.locate(|line| line.starts_with(&format!("{key}:")))
+    }
+
+    fn locate_text(&self, text: &str) -> Option<usize> {
+        self.locate(|line| line.contains(text))
+    }
+
+    fn push(&mut self, line: Option<usize>, severity: Severity, message: String) {
+        let column = line
+            .and_then(|line| self.lines.get(line - 1))
+            .map(|text| text.len() - text.trim_start().len() + 1);
+        self.diagnostics.push(Diagnostic {
+            line,
+            column,
+            severity,
+            message,
+        });
+    }
+
+    fn check_config(&mut self, config: &Mapping, core: &ClashCore) {
+        for key in PORT_KEYS {
+            let invalid = config
+                .get(key)
+                .map_or(false, |v| v.as_u64().map_or(true, |port| port > 65535));
+            if invalid {
+                let line = self.locate_key(key);
+                self.push(line, Severity::Error, format!("`{key}` should be a port"));
+            }
+        }
+        for key in BOOL_KEYS {
+            if config.get(key).map_or(false, |v| !v.is_bool()) {
+                let line = self.locate_key(key);
+                self.push(
+                    line,
+                    Severity::Error,
+                    format!("`{key}` should be a boolean"),
+                );
+            }
+        }
+        self.check_enum(config, "mode", &MODES);
+        self.check_enum(config, "log-level", &LOG_LEVELS);
+
+        let mut names = HashSet::new();
+        for key in ["proxies", "proxy-groups"] {
+            for name in self.check_named_list(config, key) {
+                if !names.insert(name.clone()) {
+                    let line = self.locate_text(&name);
+                    self.push(line, Severity::Error, format!("duplicated name `{name}`"));
+                }
+            }
+        }
+
+        let targets = rule_targets(config);
+        self.check_groups(config, &targets);
+        self.check_rules(config.get("rules"), "rules", Some(&targets));
+
+        for warning in check_config_keys(config, core) {
+            let line = warning.key.as_deref().and_then(|key| self.locate_key(key));
+            self.push(line, Severity::Warning, warning.message);
+        }
+    }
+
+    /// the targets of the rules may be in the profile, they are not checked
+    fn check_merge(&mut self, merge: &Mapping) {
+        for key in ["prepend-rules", "append-rules"] {
+            self.check_rules(merge.get(key), key, None);
+        }
+        for key in [
+            "prepend-proxies",
+            "append-proxies",
+            "prepend-proxy-groups",
+            "append-proxy-groups",
+        ] {
+            self.check_named_list(merge, key);
+        }
+    }
+
+    fn check_enum(&mut self, config: &Mapping, key: &str, values: &[&str]) {
+        let Some(value) = config.get(key) else {
+            return;
+        };
+        let valid = value.as_str().map_or(false, |value| {
+            values.contains(&value.to_lowercase().as_str())
+        });
+        if !valid {
+            let line = self.locate_key(key);
+            self.push(
+                line,
+                Severity::Warning,
+                format!("`{key}` should be one of {}", values.join(", ")),
+            );
+        }
+    }
+
+    /// the items should be mappings with the `name` and the `type`, return the names
+    fn check_named_list(&mut self, config: &Mapping, key: &str) -> Vec<String> {
+        let Some(value) = config.get(key) else {
+            return vec![];
+        };
+        let Some(list) = value.as_sequence() else {
+            let line = self.locate_key(key);
+            self.push(line, Severity::Error, format!("`{key}` should be a list"));
+            return vec![];
+        };
+
+        let mut names = vec![];
+        for (index, item) in list.iter().enumerate() {
+            let name = item.get("name").and_then(Value::as_str);
+            let kind = item.get("type").and_then(Value::as_str);
+            match (name, kind) {
+                (Some(name), Some(_)) => names.push(name.to_string()),
+                (Some(name), None) => {
+                    let line = self.locate_text(name);
+                    self.push(line, Severity::Error, format!("`{name}` has no type"));
+                }
+                _ => {
+                    let line = self.locate_key(key);
+                    self.push(
+                        line,
+                        Severity::Error,
+                        format!("the item {} of `{key}` has no name", index + 1),
+                    );
+                }
+            }
+        }
+        names
+    }
+
+    fn check_groups(&mut self, config: &Mapping, targets: &HashSet<String>) {
+        let groups = config.get("proxy-groups").and_then(Value::as_sequence);
+        for group in groups.into_iter().flatten() {
+            let Some(name) = group.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let proxies = group.get("proxies").and_then(Value::as_sequence);
+            for proxy in proxies.into_iter().flatten().filter_map(Value::as_str) {
+                if !targets.contains(proxy) {
+                    let line = self.locate_text(name);
+                    self.push(
+                        line,
+                        Severity::Warning,
+                        format!("the group `{name}` uses the unknown proxy `{proxy}`"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_rules(&mut self, rules: Option<&Value>, key: &str, targets: Option<&HashSet<String>>) {
+        let Some(rules) = rules else {
+            return;
+        };
+        let Some(rules) = rules.as_sequence() else {
+            let line = self.locate_key(key);
+            self.push(line, Severity::Error, format!("`{key}` should be a list"));
+            return;
+        };
+
+        for rule in rules {
+            let Some(rule) = rule.as_str() else {
+                let line = self.locate_key(key);
+                self.push(
+                    line,
+                    Severity::Error,
+                    format!("the `{key}` should be strings"),
+                );
+                continue;
+            };
+            let parts = rule.split(',').map(str::trim).collect::<Vec<_>>();
+            if LOGIC_RULES.contains(&parts[0]) {
+                continue;
+            }
+
+            let target = match parts[0] {
+                "MATCH" | "FINAL" => parts.get(1),
+                _ => parts.get(2),
+            };
+            match target {
+                None => {
+                    let line = self.locate_text(rule);
+                    self.push(line, Severity::Error, format!("invalid rule `{rule}`"));
+                }
+                Some(target) if targets.map_or(false, |targets| !targets.contains(*target)) => {
+                    let line = self.locate_text(rule);
+                    self.push(
+                        line,
+                        Severity::Warning,
+                        format!("the target `{target}` of the rule is not found"),
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// test the whole profile with the core
+/// the content may have the credentials, the temp file has a random name and 0600 on unix,
+/// it is removed when dropped
+fn check_with_core(content: &str) -> Result<()> {
+    let mut file = tempfile::Builder::new()
+        .prefix("clash-nyanpasu-editor-check-")
+        .suffix(".yaml")
+        .tempfile()?;
+    file.write_all(content.as_bytes())?;
+    file.flush()?;
+    CoreManager::global().check_config_file(&file.path().to_path_buf())
+}
+
+/// the enhanced files are checked with the generated config
+/// the old file is restored if the check fails
+fn check_enhanced(item: &PrfItem, content: &str) -> Result<()> {
+    let old = item.read_file()?;
+    item.save_file(content.to_string())?;
+
+    let result = Config::generate().and_then(|_| CoreManager::global().check_config());
+    if result.is_err() {
+        Config::runtime().discard();
+        item.save_file(old)?;
+    }
+    result
+}
+
+/// lint, check with the core, then save and apply if the file is in use
+pub async fn apply(uid: String, content: String) -> Result<ApplyResult> {
+    let (item, active) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        let item = profiles.get_item(&uid)?.clone();
+        let active = profiles.get_current().as_ref() == Some(&uid)
+            || profiles.chain.iter().flatten().any(|chain| chain == &uid);
+        (item, active)
+    };
+
+    let mut diagnostics = lint(&item, &content);
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        return Ok(ApplyResult {
+            saved: false,
+            diagnostics,
+        });
+    }
+
+    let checked = match FileKind::of(&item) {
+        FileKind::Config => check_with_core(&content).and_then(|_| item.save_file(content)),
+        // 没有使用的增强文件不影响当前配置，直接保存
+        _ if !active => item.save_file(content),
+        _ => check_enhanced(&item, &content),
+    };
+    if let Err(err) = checked {
+        diagnostics.push(core_error(&err));
+        return Ok(ApplyResult {
+            saved: false,
+            diagnostics,
+        });
+    }
+
+    if active {
+        feat::update_core_config().await?;
+    }
+    handle::Handle::refresh_profiles();
+    Ok(ApplyResult {
+        saved: true,
+        diagnostics,
+    })
+}
+
+#[test]
+fn test_lint_config() {
+    let content = "\
+mixed-port: 78900
+proxies:
+  - { name: HK, type: ss }
+  - { name: HK, type: vmess }
+proxy-groups:
+  - { name: Proxy, type: select, proxies: [HK, JP] }
+rules:
+  - DOMAIN,a.com,Proxy
+  - DOMAIN,b.com,US
+  - MATCH,DIRECT
+";
+    let diagnostics = lint_content(FileKind::Config, content, &ClashCore::Mihomo);
+    let find = |text: &str| diagnostics.iter().find(|d| d.message.contains(text));
+
+    assert_eq!(find("`mixed-port`").unwrap().line, Some(1));
+    assert_eq!(
+        find("duplicated name `HK`").unwrap().severity,
+        Severity::Error
+    );
+    assert_eq!(find("unknown proxy `JP`").unwrap().line, Some(6));
+    assert_eq!(find("`US`").unwrap().line, Some(9));
+
+    let diagnostics = lint_content(
+        FileKind::Merge,
+        "prepend-rules: [a, b\n",
+        &ClashCore::Mihomo,
+    );
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].line.is_some());
+}
@@ -0,0 +1,112 @@
+//! the typed events inside the backend, the modules subscribe to them instead of calling each other
+//!
+//! every event is mirrored to the frontend as `nyanpasu://event`, the payload is
+//! `{ "type": "<event>", "data": <fields> }`, see `IAppEvent` in `src/services/types.d.ts`
+use super::{handle, network::NetworkState};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::future::Future;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// the events are dropped for the slow subscribers beyond it
+const CAPACITY: usize = 64;
+
+pub const FRONTEND_EVENT: &str = "nyanpasu://event";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoreState {
+    /// started or restarted with a new config
+    Started,
+    /// restarted after exiting unexpectedly
+    Recovered,
+    /// failed to restart after exiting unexpectedly
+    RecoverFailed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileChange {
+    /// the remote profile is downloaded again
+    Updated,
+    /// it becomes the current one
+    Switched,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum AppEvent {
+    /// `{ "state": "started" | "recovered" | "recover_failed" }`
+    CoreState { state: CoreState },
+    /// `{ "uid": string, "change": "updated" | "switched" }`
+    ProfileChanged { uid: String, change: ProfileChange },
+    /// `{ "group": string, "name": string }`
+    ProxySelected { group: String, name: String },
+    /// `{ "previous": NetworkState, "current": NetworkState }`
+    NetworkChanged {
+        previous: NetworkState,
+        current: NetworkState,
+    },
+}
+
+pub struct EventBus {
+    sender: broadcast::Sender<AppEvent>,
+}
+
+impl EventBus {
+    pub fn global() -> &'static EventBus {
+        static EVENT_BUS: OnceCell<EventBus> = OnceCell::new();
+
+        EVENT_BUS.get_or_init(|| EventBus {
+            sender: broadcast::channel(CAPACITY).0,
+        })
+    }
+
+    pub fn publish(event: AppEvent) {
+        tracing::debug!("publish the event {event:?}");
+        handle::Handle::emit(FRONTEND_EVENT, &event);
+        // 没有订阅者时会返回错误，不用管
+        let _ = Self::global().sender.send(event);
+    }
+
+    /// handle the events one by one in the background
+    /// only the events published after subscribing are received
+    pub fn subscribe<F, Fut>(name: &'static str, handler: F)
+    where
+        F: Fn(AppEvent) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let mut rx = Self::global().sender.subscribe();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => handler(event).await,
+                    Err(RecvError::Lagged(count)) => {
+                        tracing::warn!("the subscriber `{name}` missed {count} events");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
+
+#[test]
+fn test_event_payload() {
+    let event = AppEvent::ProxySelected {
+        group: "Proxy".into(),
+        name: "HK".into(),
+    };
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        serde_json::json!({ "type": "proxy_selected", "data": { "group": "Proxy", "name": "HK" } })
+    );
+
+    let event = AppEvent::CoreState {
+        state: CoreState::RecoverFailed,
+    };
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        serde_json::json!({ "type": "core_state", "data": { "state": "recover_failed" } })
+    );
+}
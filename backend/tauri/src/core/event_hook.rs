@@ -0,0 +1,168 @@
+//! publish the selected events to a webhook or a mqtt topic for the home automation
+
+use super::{
+    event_bus::{AppEvent, CoreState, EventBus, ProfileChange},
+    http,
+};
+use crate::{
+    config::{Config, EventHook, HookEvent, HookTarget, HttpPurpose},
+    utils::secrets,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::Method;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS, Transport};
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+use sysinfo::{System, SystemExt};
+use tokio::time::timeout;
+use url::Url;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+struct EventPayload<'a> {
+    event: &'a str,
+    time: i64,
+    hostname: Option<String>,
+    data: Value,
+}
+
+/// the mqtt password is kept in the os keyring, not in the `verge.yaml`
+pub fn set_password(username: &str, password: &str) -> Result<()> {
+    secrets::set(&format!("mqtt::{username}"), password)
+}
+
+fn get_password(username: &str) -> Result<Option<String>> {
+    secrets::get(&format!("mqtt::{username}"))
+}
+
+/// send the event in the background if it is subscribed, the failures are only logged
+pub fn publish(event: HookEvent, data: Value) {
+    let hook = { Config::verge().latest().event_hook.clone() };
+    let Some(hook) = hook.filter(|hook| hook.subscribed(event)) else {
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = send(&hook, event.as_str(), data).await {
+            tracing::warn!("failed to publish the event `{}`: {err}", event.as_str());
+        }
+    });
+}
+
+/// forward the internal events to the hook
+pub fn subscribe_events() {
+    EventBus::subscribe("event_hook", |event| async move {
+        match event {
+            AppEvent::CoreState { state } if state != CoreState::Started => publish(
+                HookEvent::CoreCrashed,
+                serde_json::json!({ "recovered": state == CoreState::Recovered }),
+            ),
+            AppEvent::ProfileChanged {
+                uid,
+                change: ProfileChange::Updated,
+            } => {
+                let name = {
+                    let profiles = Config::profiles();
+                    let profiles = profiles.latest();
+                    profiles
+                        .get_item(&uid)
+                        .ok()
+                        .and_then(|item| item.name.clone())
+                };
+                publish(
+                    HookEvent::ProfileUpdated,
+                    serde_json::json!({ "uid": uid, "name": name }),
+                );
+            }
+            AppEvent::ProxySelected { group, name } => publish(
+                HookEvent::NodeSwitched,
+                serde_json::json!({ "group": group, "node": name }),
+            ),
+            _ => {}
+        }
+    });
+}
+
+/// send a test event with the saved config
+pub async fn test() -> Result<()> {
+    let hook = { Config::verge().latest().event_hook.clone() };
+    let hook = hook.ok_or(anyhow!("the event hook is not configured"))?;
+    send(&hook, "test", Value::Null).await
+}
+
+async fn send(hook: &EventHook, event: &str, data: Value) -> Result<()> {
+    let payload = serde_json::to_vec(&EventPayload {
+        event,
+        time: chrono::Local::now().timestamp(),
+        hostname: System::new().host_name(),
+        data,
+    })?;
+
+    match &hook.target {
+        HookTarget::Webhook { url } => post_webhook(url, payload).await,
+        HookTarget::Mqtt {
+            broker,
+            topic,
+            username,
+        } => publish_mqtt(broker, topic, username.as_deref(), payload).await,
+    }
+}
+
+async fn post_webhook(url: &str, payload: Vec<u8>) -> Result<()> {
+    let client = http::client(HttpPurpose::Webhooks)?;
+    let request = client
+        .request(Method::POST, url)
+        .header("content-type", "application/json")
+        .body(payload);
+    let res = client.send(request).await?;
+    if !res.status().is_success() {
+        bail!("the webhook responded {}", res.status());
+    }
+    Ok(())
+}
+
+/// connect for each event, the events are rare
+async fn publish_mqtt(
+    broker: &str,
+    topic: &str,
+    username: Option<&str>,
+    payload: Vec<u8>,
+) -> Result<()> {
+    let url = Url::parse(broker).context("invalid mqtt broker")?;
+    let tls = url.scheme() == "mqtts";
+    let host = url.host_str().ok_or(anyhow!("invalid mqtt broker"))?;
+    let port = url.port().unwrap_or(if tls { 8883 } else { 1883 });
+
+    let client_id = format!("clash-nyanpasu-{}", nanoid::nanoid!(8));
+    let mut options = MqttOptions::new(client_id, host, port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some(username) = username.filter(|name| !name.is_empty()) {
+        let password = get_password(username)?.unwrap_or_default();
+        options.set_credentials(username, password);
+    }
+    if tls {
+        options.set_transport(Transport::tls_with_default_config());
+    }
+
+    let (client, mut eventloop) = AsyncClient::new(options, 10);
+    client
+        .publish(topic, QoS::AtLeastOnce, false, payload)
+        .await?;
+
+    // 事件循环负责连接和发送，收到 PubAck 才算成功
+    timeout(TIMEOUT, async {
+        loop {
+            if let Event::Incoming(Packet::PubAck(_)) = eventloop.poll().await? {
+                return Ok::<_, anyhow::Error>(());
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow!("publishing to the mqtt broker timed out"))??;
+
+    let _ = client.disconnect().await;
+    let _ = timeout(Duration::from_secs(1), eventloop.poll()).await;
+    Ok(())
+}
@@ -0,0 +1,209 @@
+//! export the connections and the traffic history for the offline analysis
+
+use super::{
+    clash_api::{self, ConnectionItem},
+    traffic_history::{TrafficBucket, TrafficHistory},
+};
+use crate::utils::dirs;
+use anyhow::{anyhow, bail, Result};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// all the fields are optional, matched case-insensitively by substring
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ConnectionFilter {
+    pub host: Option<String>,
+    pub process: Option<String>,
+    pub rule: Option<String>,
+    /// a proxy or a group in the chains
+    pub chain: Option<String>,
+}
+
+impl ConnectionFilter {
+    fn matches(&self, item: &ConnectionItem) -> bool {
+        let contains = |value: &str, pattern: &Option<String>| {
+            pattern
+                .as_ref()
+                .filter(|pattern| !pattern.is_empty())
+                .map_or(true, |pattern| {
+                    value.to_lowercase().contains(&pattern.to_lowercase())
+                })
+        };
+        let host = match item.metadata.host.is_empty() {
+            true => &item.metadata.destination_ip,
+            false => &item.metadata.host,
+        };
+        contains(host, &self.host)
+            && contains(
+                item.metadata.process.as_deref().unwrap_or_default(),
+                &self.process,
+            )
+            && contains(&item.rule, &self.rule)
+            && (self.chain.is_none() || item.chains.iter().any(|c| contains(c, &self.chain)))
+    }
+}
+
+/// the time range of the traffic history, unix timestamps
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct TimeRange {
+    pub from: i64,
+    pub to: i64,
+}
+
+/// quote the field if needed, RFC 4180
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+        + "\n"
+}
+
+fn connections_csv(items: &[ConnectionItem]) -> String {
+    let header = [
+        "id",
+        "start",
+        "network",
+        "type",
+        "host",
+        "source",
+        "destination",
+        "process",
+        "rule",
+        "rule_payload",
+        "chains",
+        "upload",
+        "download",
+    ];
+    let mut csv = csv_row(&header.map(String::from));
+    for item in items {
+        let meta = &item.metadata;
+        csv += &csv_row(&[
+            item.id.clone(),
+            item.start.clone(),
+            meta.network.clone(),
+            meta.r#type.clone(),
+            meta.host.clone(),
+            format!("{}:{}", meta.source_ip, meta.source_port),
+            format!("{}:{}", meta.destination_ip, meta.destination_port),
+            meta.process.clone().unwrap_or_default(),
+            item.rule.clone(),
+            item.rule_payload.clone(),
+            item.chains.join(" > "),
+            item.upload.to_string(),
+            item.download.to_string(),
+        ]);
+    }
+    csv
+}
+
+fn traffic_csv(buckets: &[TrafficBucket]) -> String {
+    let mut csv = csv_row(&["time".into(), "upload".into(), "download".into()]);
+    for bucket in buckets {
+        let time = Local
+            .timestamp_opt(bucket.time, 0)
+            .single()
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_else(|| bucket.time.to_string());
+        csv += &csv_row(&[time, bucket.up.to_string(), bucket.down.to_string()]);
+    }
+    csv
+}
+
+/// write to the download dir like the diagnostic bundle
+fn write_export(name: &str, format: ExportFormat, content: String) -> Result<PathBuf> {
+    let dir = tauri::api::path::download_dir()
+        .or_else(|| dirs::app_home_dir().ok())
+        .ok_or(anyhow!("failed to get the download dir"))?;
+    let path = dir.join(format!(
+        "nyanpasu-{name}-{}.{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    ));
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// the active connections of the core
+pub async fn export_connections(format: ExportFormat, filter: ConnectionFilter) -> Result<PathBuf> {
+    let items = clash_api::get_connections()
+        .await?
+        .connections
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|item| filter.matches(item))
+        .collect::<Vec<_>>();
+
+    let content = match format {
+        ExportFormat::Csv => connections_csv(&items),
+        ExportFormat::Json => serde_json::to_string_pretty(&items)?,
+    };
+    write_export("connections", format, content)
+}
+
+pub fn export_traffic_history(range: TimeRange, format: ExportFormat) -> Result<PathBuf> {
+    if range.from >= range.to {
+        bail!("invalid time range");
+    }
+    let buckets = TrafficHistory::global().range(range.from, range.to);
+
+    let content = match format {
+        ExportFormat::Csv => traffic_csv(&buckets),
+        ExportFormat::Json => serde_json::to_string_pretty(&buckets)?,
+    };
+    write_export("traffic", format, content)
+}
+
+#[test]
+fn test_connections_csv() {
+    use super::clash_api::ConnectionMetadata;
+
+    let item = ConnectionItem {
+        id: "1".into(),
+        metadata: ConnectionMetadata {
+            host: "a.com".into(),
+            process: Some("chrome.exe".into()),
+            ..Default::default()
+        },
+        rule: "DOMAIN".into(),
+        rule_payload: "a,b".into(),
+        chains: vec!["HK".into(), "Proxy".into()],
+        ..Default::default()
+    };
+    let csv = connections_csv(&[item.clone()]);
+    assert!(csv.lines().nth(1).unwrap().contains(",\"a,b\",HK > Proxy,"));
+
+    let filter = |process: &str| ConnectionFilter {
+        process: Some(process.into()),
+        chain: Some("hk".into()),
+        ..Default::default()
+    };
+    assert!(filter("Chrome").matches(&item));
+    assert!(!filter("firefox").matches(&item));
+}
@@ -0,0 +1,245 @@
+//! the fallback actions when the core keeps failing
+//!
+//! every crash the watchdog in `CoreManager::recover_core` reports is counted, once there
+//! are `threshold` of them within the window the configured actions run in order.
+//! the count is reset after the run, so each new setup gets its own chance.
+
+use super::{
+    event_bus::{AppEvent, CoreState, EventBus},
+    handle,
+    notification::{NotificationKind, Notifications},
+    sys_dns, CoreManager,
+};
+use crate::{
+    config::{Config, FailoverAction, IProfiles, IVerge},
+    feat, log_err,
+    utils::i18n::{t, t_args},
+};
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailoverResult {
+    pub action: FailoverAction,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailoverRun {
+    /// unix timestamp
+    pub time: i64,
+    pub failures: usize,
+    pub results: Vec<FailoverResult>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailoverStatus {
+    /// the failures counted in the current window
+    pub failures: usize,
+    pub last_run: Option<FailoverRun>,
+}
+
+pub struct Failover {
+    /// the timestamps of the failures
+    failures: Mutex<VecDeque<i64>>,
+    last_run: Mutex<Option<FailoverRun>>,
+    running: AtomicBool,
+}
+
+/// drop the failures out of the window, return the remaining count
+fn prune_failures(failures: &mut VecDeque<i64>, now: i64, window: u64) -> usize {
+    while failures
+        .front()
+        .is_some_and(|time| now - time >= window as i64)
+    {
+        failures.pop_front();
+    }
+    failures.len()
+}
+
+impl Failover {
+    pub fn global() -> &'static Failover {
+        static FAILOVER: OnceCell<Failover> = OnceCell::new();
+
+        FAILOVER.get_or_init(|| Failover {
+            failures: Mutex::new(VecDeque::new()),
+            last_run: Mutex::new(None),
+            running: AtomicBool::new(false),
+        })
+    }
+
+    pub fn subscribe_events(&'static self) {
+        EventBus::subscribe("failover", move |event| async move {
+            if let AppEvent::CoreState {
+                state: CoreState::Recovered | CoreState::RecoverFailed,
+            } = event
+            {
+                self.on_failure().await;
+            }
+        });
+    }
+
+    pub fn status(&self) -> FailoverStatus {
+        let window = { Config::verge().latest().core_failover.clone() }
+            .map(|failover| failover.window)
+            .unwrap_or(0);
+        let now = chrono::Local::now().timestamp();
+        FailoverStatus {
+            failures: prune_failures(&mut self.failures.lock(), now, window),
+            last_run: self.last_run.lock().clone(),
+        }
+    }
+
+    async fn on_failure(&self) {
+        let config = { Config::verge().latest().core_failover.clone() };
+        let Some(config) = config.filter(|config| config.enable) else {
+            return;
+        };
+
+        let now = chrono::Local::now().timestamp();
+        let failures = {
+            let mut failures = self.failures.lock();
+            failures.push_back(now);
+            let count = prune_failures(&mut failures, now, config.window);
+            if count < config.threshold {
+                return;
+            }
+            failures.clear();
+            count
+        };
+        // 切换内核时也可能失败，不要重入
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        tracing::warn!("the core failed {failures} times, run the failover actions");
+        let mut results = Vec::with_capacity(config.actions.len());
+        for action in config.actions {
+            let result = run_action(&action, failures).await;
+            if let Err(err) = &result {
+                tracing::error!("failed to run the failover action {action:?}: {err}");
+            }
+            results.push(FailoverResult {
+                action,
+                error: result.err().map(|err| err.to_string()),
+            });
+        }
+
+        *self.last_run.lock() = Some(FailoverRun {
+            time: now,
+            failures,
+            results,
+        });
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+async fn run_action(action: &FailoverAction, failures: usize) -> Result<()> {
+    match action {
+        FailoverAction::SwitchProfile { uid } => switch_profile(uid).await,
+        FailoverAction::SwitchCore { core } => {
+            CoreManager::global()
+                .change_core(Some(core.clone()))
+                .await?;
+            handle::Handle::refresh_verge();
+            Ok(())
+        }
+        FailoverAction::DisableSystemProxy => disable_proxy().await,
+        FailoverAction::Notify => {
+            Notifications::global().push(
+                NotificationKind::CoreCrashed,
+                t("Clash Core Crashed"),
+                t_args(
+                    "The clash core failed {count} times, the failover actions are run",
+                    &[("count", &failures)],
+                ),
+                None,
+            );
+            Ok(())
+        }
+    }
+}
+
+/// the core is down, so the config can't be reloaded by the api like `feat::switch_profile`
+async fn switch_profile(uid: &str) -> Result<()> {
+    let uid = uid.to_string();
+    Config::profiles().latest().get_item(&uid)?;
+    Config::profiles().draft().patch_config(IProfiles {
+        current: Some(uid),
+        ..IProfiles::default()
+    })?;
+
+    let result = async {
+        Config::generate()?;
+        CoreManager::global().run_core().await
+    }
+    .await;
+    match result {
+        Ok(_) => {
+            Config::profiles().apply();
+            Config::profiles().data().save_file()?;
+            handle::Handle::refresh_profiles();
+            handle::Handle::refresh_clash();
+            Ok(())
+        }
+        Err(err) => {
+            Config::profiles().discard();
+            Err(err)
+        }
+    }
+}
+
+/// go back to the raw connectivity
+/// the core is usually dead here, the tun is turned off without the api unless it is running
+async fn disable_proxy() -> Result<()> {
+    // 单独关闭系统代理，后面关闭 tun 失败时不会被回滚
+    feat::patch_verge(IVerge {
+        enable_system_proxy: Some(false),
+        ..IVerge::default()
+    })
+    .await?;
+
+    let tun = { Config::verge().latest().enable_tun_mode.unwrap_or(false) };
+    if tun {
+        Config::verge().draft().patch_config(IVerge {
+            enable_tun_mode: Some(false),
+            ..IVerge::default()
+        });
+        let result = async {
+            if CoreManager::global().is_running() {
+                feat::update_core_config().await
+            } else {
+                Config::generate()
+            }
+        }
+        .await;
+        match result {
+            Ok(()) => {
+                Config::verge().apply();
+                Config::verge().data().save_file()?;
+            }
+            Err(err) => {
+                Config::verge().discard();
+                return Err(err);
+            }
+        }
+        log_err!(sys_dns::update());
+        log_err!(handle::Handle::update_systray_part());
+    }
+    handle::Handle::refresh_verge();
+    Ok(())
+}
+
+#[test]
+fn test_prune_failures() {
+    let mut failures = VecDeque::from([100, 200, 250, 290]);
+    assert_eq!(prune_failures(&mut failures, 300, 100), 2);
+    assert_eq!(failures, [250, 290]);
+    assert_eq!(prune_failures(&mut failures, 1000, 100), 0);
+}
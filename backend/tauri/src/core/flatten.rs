@@ -0,0 +1,737 @@
+//! export the running config as a single static file for the raw mihomo, e.g. on a router or a nas
+//!
+//! the runtime config already has the chain applied. the proxy providers are resolved into
+//! `proxies` and the names are added to the groups using them, the rule providers become inline
+//! ones (mihomo 1.18+). what can't be resolved is kept as is and reported in the warnings.
+
+use super::rule_set::{provider_payload, read_provider};
+use crate::{
+    config::{Config, EXIT_PROBE},
+    enhance::injected_rules,
+    utils::{dirs, help},
+};
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use nanoid::nanoid;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// the fields of the proxies templated with `env_secrets`
+const SECRET_FIELDS: &[&str] = &[
+    "password",
+    "uuid",
+    "private-key",
+    "pre-shared-key",
+    "psk",
+    "auth",
+    "auth-str",
+    "obfs-password",
+    "token",
+];
+
+const ENV_PREFIX: &str = "NYANPASU";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FlattenTarget {
+    /// the output file, the download dir by default
+    pub path: Option<String>,
+    /// replace the secrets with `${NYANPASU_...}` and write the values to a `.env` file beside,
+    /// substitute them with e.g. `envsubst` on the device
+    #[serde(default)]
+    pub env_secrets: bool,
+    /// listen on all the interfaces, for a router serving the lan
+    #[serde(default)]
+    pub allow_lan: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlattenResult {
+    pub path: String,
+    pub env_path: Option<String>,
+    /// the proxies added from the providers
+    pub proxies: usize,
+    /// the rule providers made inline
+    pub rule_providers: usize,
+    pub warnings: Vec<String>,
+}
+
+/// mihomo splits the filters with backticks
+fn compile_filter(filter: Option<&Value>) -> Result<Vec<Regex>> {
+    filter
+        .and_then(Value::as_str)
+        .into_iter()
+        .flat_map(|filter| filter.split('`'))
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|err| anyhow!("invalid filter `{pattern}`: {err}"))
+        })
+        .collect()
+}
+
+/// `filter` `exclude-filter` `exclude-type` of the providers and the groups
+struct ProxyFilter {
+    filter: Vec<Regex>,
+    exclude: Vec<Regex>,
+    exclude_type: Vec<String>,
+}
+
+impl ProxyFilter {
+    fn new(options: &Mapping) -> Result<Self> {
+        Ok(ProxyFilter {
+            filter: compile_filter(options.get("filter"))?,
+            exclude: compile_filter(options.get("exclude-filter"))?,
+            exclude_type: options
+                .get("exclude-type")
+                .and_then(Value::as_str)
+                .map(|types| types.split('|').map(str::to_lowercase).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    fn matches(&self, name: &str, proxy_type: &str) -> bool {
+        (self.filter.is_empty() || self.filter.iter().any(|re| re.is_match(name)))
+            && !self.exclude.iter().any(|re| re.is_match(name))
+            && !self.exclude_type.contains(&proxy_type.to_lowercase())
+    }
+}
+
+fn proxy_field<'a>(proxy: &'a Value, key: &str) -> &'a str {
+    proxy.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+/// the proxies of the provider after its filters and `override`
+async fn load_proxy_provider(name: &str, provider: &Mapping) -> Result<Vec<Value>> {
+    let proxies = match provider.get("type").and_then(Value::as_str) {
+        Some("inline") => provider.get("payload").cloned(),
+        _ => {
+            let text = read_provider(name, provider).await?;
+            // 订阅格式（base64 / uri）的内容要内核自己解析
+            let data = serde_yaml::from_str::<Mapping>(&text)
+                .map_err(|_| anyhow!("the content of the proxy provider `{name}` is not yaml"))?;
+            data.get("proxies").cloned()
+        }
+    };
+    let proxies = match proxies {
+        Some(Value::Sequence(proxies)) => proxies,
+        _ => return Err(anyhow!("the proxy provider `{name}` has no proxies")),
+    };
+
+    let filter = ProxyFilter::new(provider)?;
+    let overrides = provider
+        .get("override")
+        .and_then(Value::as_mapping)
+        .cloned()
+        .unwrap_or_default();
+    let affix = |key: &str| {
+        overrides
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    };
+    let (prefix, suffix) = (affix("additional-prefix"), affix("additional-suffix"));
+
+    Ok(proxies
+        .into_iter()
+        .filter(|proxy| filter.matches(proxy_field(proxy, "name"), proxy_field(proxy, "type")))
+        .filter_map(|proxy| {
+            let name = format!("{prefix}{}{suffix}", proxy_field(&proxy, "name"));
+            let mut proxy = proxy.as_mapping()?.clone();
+            for (key, value) in overrides.iter() {
+                if !matches!(
+                    key.as_str(),
+                    Some("additional-prefix" | "additional-suffix")
+                ) {
+                    proxy.insert(key.clone(), value.clone());
+                }
+            }
+            proxy.insert("name".into(), name.into());
+            Some(Value::Mapping(proxy))
+        })
+        .collect())
+}
+
+fn sequence<'a>(config: &'a mut Mapping, key: &str) -> &'a mut Vec<Value> {
+    if !matches!(config.get(key), Some(Value::Sequence(_))) {
+        config.insert(key.into(), Value::Sequence(vec![]));
+    }
+    match config.get_mut(key) {
+        Some(Value::Sequence(seq)) => seq,
+        _ => unreachable!(),
+    }
+}
+
+/// move the proxies of the providers into `proxies` and the groups, return the count
+async fn resolve_proxy_providers(config: &mut Mapping, warnings: &mut Vec<String>) -> usize {
+    let providers = config
+        .get("proxy-providers")
+        .and_then(Value::as_mapping)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut names = sequence(config, "proxies")
+        .iter()
+        .map(|proxy| proxy_field(proxy, "name").to_string())
+        .collect::<HashSet<_>>();
+    // provider 的名字和它的节点（名字、类型）
+    let mut resolved = Vec::new();
+    let mut added = vec![];
+    for (name, provider) in providers.iter() {
+        let (Some(name), Some(provider)) = (name.as_str(), provider.as_mapping()) else {
+            continue;
+        };
+        let proxies = match load_proxy_provider(name, provider).await {
+            Ok(proxies) => proxies,
+            Err(err) => {
+                warnings.push(format!("{err:#}, the provider is kept"));
+                continue;
+            }
+        };
+        let mut members = vec![];
+        for proxy in proxies {
+            let proxy_name = proxy_field(&proxy, "name").to_string();
+            // 重名的节点内核会拒绝启动
+            if !names.insert(proxy_name.clone()) {
+                warnings.push(format!(
+                    "the proxy `{proxy_name}` of the provider `{name}` is duplicated, skipped"
+                ));
+                continue;
+            }
+            members.push((proxy_name, proxy_field(&proxy, "type").to_string()));
+            added.push(proxy);
+        }
+        resolved.push((name.to_string(), members));
+    }
+
+    let count = added.len();
+    sequence(config, "proxies").extend(added);
+    let mut remaining = providers.clone();
+    for (name, _) in resolved.iter() {
+        remaining.remove(name.as_str());
+    }
+    match remaining.is_empty() {
+        true => config.remove("proxy-providers"),
+        false => config.insert("proxy-providers".into(), Value::Mapping(remaining)),
+    };
+
+    for group in sequence(config, "proxy-groups").iter_mut() {
+        let Some(group) = group.as_mapping_mut() else {
+            continue;
+        };
+        let group_name = group
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let all = group.get("include-all-providers").and_then(Value::as_bool) == Some(true);
+        let used = group
+            .get("use")
+            .and_then(Value::as_sequence)
+            .map(|used| {
+                used.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_else(Vec::<String>::new);
+
+        let filter = ProxyFilter::new(group).unwrap_or_else(|err| {
+            warnings.push(format!(
+                "{err:#} in the group `{group_name}`, all the proxies of the providers are added"
+            ));
+            ProxyFilter {
+                filter: vec![],
+                exclude: vec![],
+                exclude_type: vec![],
+            }
+        });
+        let mut members = vec![];
+        let mut kept = vec![];
+        for (name, proxies) in resolved.iter() {
+            if !all && !used.contains(name) {
+                continue;
+            }
+            members.extend(
+                proxies
+                    .iter()
+                    .filter(|(proxy, kind)| filter.matches(proxy, kind))
+                    .map(|(proxy, _)| Value::String(proxy.clone())),
+            );
+        }
+        for name in used {
+            if !resolved.iter().any(|(resolved, _)| *resolved == name) {
+                kept.push(Value::String(name));
+            }
+        }
+        if all {
+            group.remove("include-all-providers");
+            // 没解析的 provider 还要用
+            kept = providers
+                .keys()
+                .filter_map(Value::as_str)
+                .filter(|name| !resolved.iter().any(|(resolved, _)| resolved == *name))
+                .map(|name| Value::String(name.to_string()))
+                .collect();
+        }
+
+        match kept.is_empty() {
+            true => group.remove("use"),
+            false => group.insert("use".into(), Value::Sequence(kept)),
+        };
+        let has_use = group.contains_key("use");
+        let proxies = sequence(group, "proxies");
+        proxies.extend(members);
+        if proxies.is_empty() && !has_use {
+            warnings.push(format!(
+                "the group `{group_name}` has no proxies, DIRECT is added"
+            ));
+            sequence(group, "proxies").push("DIRECT".into());
+        }
+    }
+    count
+}
+
+/// make the rule providers inline, return the count
+async fn resolve_rule_providers(config: &mut Mapping, warnings: &mut Vec<String>) -> usize {
+    let Some(Value::Mapping(providers)) = config.get_mut("rule-providers") else {
+        return 0;
+    };
+
+    let mut count = 0;
+    for (name, provider) in providers.iter_mut() {
+        let (Some(name), Some(mapping)) = (name.as_str(), provider.as_mapping()) else {
+            continue;
+        };
+        if mapping.get("type").and_then(Value::as_str) == Some("inline") {
+            continue;
+        }
+        let payload = match provider_payload(name, mapping).await {
+            Ok(payload) => payload,
+            Err(err) => {
+                warnings.push(format!("{err:#}, the provider is kept"));
+                continue;
+            }
+        };
+
+        let mut inline = Mapping::new();
+        inline.insert("type".into(), "inline".into());
+        if let Some(behavior) = mapping.get("behavior") {
+            inline.insert("behavior".into(), behavior.clone());
+        }
+        inline.insert(
+            "payload".into(),
+            Value::Sequence(payload.into_iter().map(Value::String).collect()),
+        );
+        *provider = Value::Mapping(inline);
+        count += 1;
+    }
+    count
+}
+
+/// drop what the app adds for this desktop, the device runs the config on its own
+fn strip_app_parts(config: &mut Mapping, injected: &HashSet<String>, warnings: &mut Vec<String>) {
+    let is_probe = |item: &Value| proxy_field(item, "name") == EXIT_PROBE;
+    sequence(config, "listeners").retain(|listener| !is_probe(listener));
+    sequence(config, "proxy-groups").retain(|group| !is_probe(group));
+    for key in ["listeners", "proxy-groups"] {
+        if matches!(config.get(key), Some(Value::Sequence(seq)) if seq.is_empty()) {
+            config.remove(key);
+        }
+    }
+
+    if let Some(Value::Sequence(rules)) = config.get_mut("rules") {
+        let count = rules.len();
+        rules.retain(|rule| rule.as_str().map_or(true, |rule| !injected.contains(rule)));
+        if rules.len() < count {
+            warnings.push(format!(
+                "{} process rules of the app are removed",
+                count - rules.len()
+            ));
+        }
+    }
+
+    if config.remove("interface-name").is_some() {
+        warnings.push("`interface-name` is removed, it is an interface of this device".into());
+    }
+    let tun = config
+        .get("tun")
+        .and_then(|tun| tun.get("enable"))
+        .and_then(Value::as_bool);
+    if tun == Some(true) {
+        warnings.push("the `tun` of this desktop is kept, check it for the device".into());
+    }
+}
+
+/// the paths of the local machine make no sense on the device
+fn adapt_for_device(config: &mut Mapping, target: &FlattenTarget, warnings: &mut Vec<String>) {
+    if config.remove("external-ui").is_some() {
+        warnings.push("`external-ui` is removed, it is a local path".into());
+    }
+    if target.allow_lan {
+        config.insert("allow-lan".into(), true.into());
+        config.insert("bind-address".into(), "*".into());
+        // 控制器开放到局域网，不能没有密钥
+        let secret = config.get("secret").and_then(Value::as_str);
+        if config.contains_key("external-controller") && secret.map_or(true, str::is_empty) {
+            config.insert("secret".into(), nanoid!(32).into());
+            warnings.push(
+                "the `secret` is empty, a random one is generated for the controller on the lan"
+                    .into(),
+            );
+        }
+        if let Some(controller) = config.get("external-controller").and_then(Value::as_str) {
+            if let Some((_, port)) = controller.rsplit_once(':') {
+                config.insert(
+                    "external-controller".into(),
+                    format!("0.0.0.0:{port}").into(),
+                );
+            }
+        }
+    }
+}
+
+fn env_name(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|part| {
+            part.chars()
+                .map(|c| match c.is_ascii_alphanumeric() {
+                    true => c.to_ascii_uppercase(),
+                    false => '_',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// replace the value with the placeholder and record it
+fn template(value: &mut Value, name: String, vars: &mut Vec<(String, String)>) {
+    let plain = match value {
+        Value::String(value) => value.clone(),
+        Value::Number(value) => value.to_string(),
+        _ => return,
+    };
+    *value = Value::String(format!("${{{name}}}"));
+    vars.push((name, plain));
+}
+
+/// template the secrets as the env placeholders, return the names and the values
+fn template_secrets(config: &mut Mapping) -> Vec<(String, String)> {
+    let mut vars = vec![];
+    if let Some(secret) = config.get_mut("secret") {
+        template(secret, env_name(&[ENV_PREFIX, "SECRET"]), &mut vars);
+    }
+    if let Some(Value::Sequence(auth)) = config.get_mut("authentication") {
+        for (index, user) in auth.iter_mut().enumerate() {
+            template(
+                user,
+                env_name(&[ENV_PREFIX, "AUTH", &index.to_string()]),
+                &mut vars,
+            );
+        }
+    }
+
+    for (index, proxy) in sequence(config, "proxies").iter_mut().enumerate() {
+        let index = index.to_string();
+        let Some(proxy) = proxy.as_mapping_mut() else {
+            continue;
+        };
+        for field in SECRET_FIELDS {
+            if let Some(value) = proxy.get_mut(*field) {
+                template(
+                    value,
+                    env_name(&[ENV_PREFIX, "PROXY", &index, field]),
+                    &mut vars,
+                );
+            }
+            // shadow-tls 等插件的密码
+            if let Some(value) = proxy
+                .get_mut("plugin-opts")
+                .and_then(Value::as_mapping_mut)
+                .and_then(|opts| opts.get_mut(*field))
+            {
+                template(
+                    value,
+                    env_name(&[ENV_PREFIX, "PROXY", &index, "PLUGIN", field]),
+                    &mut vars,
+                );
+            }
+        }
+        // wireguard 的多个 peer
+        if let Some(Value::Sequence(peers)) = proxy.get_mut("peers") {
+            for (peer_index, peer) in peers.iter_mut().enumerate() {
+                let peer_index = peer_index.to_string();
+                for field in SECRET_FIELDS {
+                    if let Some(value) = peer.get_mut(*field) {
+                        template(
+                            value,
+                            env_name(&[ENV_PREFIX, "PROXY", &index, "PEER", &peer_index, field]),
+                            &mut vars,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // the auth of the listeners
+    if let Some(Value::Sequence(listeners)) = config.get_mut("listeners") {
+        for (index, listener) in listeners.iter_mut().enumerate() {
+            let Some(Value::Sequence(users)) = listener.get_mut("users") else {
+                continue;
+            };
+            for (user_index, user) in users.iter_mut().enumerate() {
+                if let Some(password) = user.get_mut("password") {
+                    template(
+                        password,
+                        env_name(&[
+                            ENV_PREFIX,
+                            "LISTENER",
+                            &index.to_string(),
+                            "USER",
+                            &user_index.to_string(),
+                            "PASSWORD",
+                        ]),
+                        &mut vars,
+                    );
+                }
+            }
+        }
+    }
+
+    // the urls of the kept providers may have the subscription tokens
+    for (key, kind) in [
+        ("proxy-providers", "PROXY_PROVIDER"),
+        ("rule-providers", "RULE_PROVIDER"),
+    ] {
+        let Some(Value::Mapping(providers)) = config.get_mut(key) else {
+            continue;
+        };
+        for (index, (_, provider)) in providers.iter_mut().enumerate() {
+            if let Some(url) = provider.get_mut("url") {
+                template(
+                    url,
+                    env_name(&[ENV_PREFIX, kind, &index.to_string(), "URL"]),
+                    &mut vars,
+                );
+            }
+        }
+    }
+    vars
+}
+
+/// `NAME='value'`, readable by the shells and the dotenv files
+fn env_file(vars: &[(String, String)]) -> String {
+    vars.iter()
+        .map(|(name, value)| format!("{name}='{}'\n", value.replace('\'', r"'\''")))
+        .collect()
+}
+
+/// only readable by the user, it has the plain secrets
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::{
+            fs::Permissions,
+            io::Write,
+            os::unix::fs::{OpenOptionsExt, PermissionsExt},
+        };
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        // 已经存在的文件不会按 mode 改权限
+        file.set_permissions(Permissions::from_mode(0o600))?;
+        file.write_all(data)?;
+    }
+    #[cfg(not(unix))]
+    fs::write(path, data)?;
+
+    Ok(())
+}
+
+fn output_path(target: &FlattenTarget) -> Result<PathBuf> {
+    if let Some(path) = target.path.as_ref().filter(|path| !path.trim().is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    let dir = tauri::api::path::download_dir()
+        .or_else(|| dirs::app_home_dir().ok())
+        .ok_or(anyhow!("failed to get the download dir"))?;
+    Ok(dir.join(format!(
+        "nyanpasu-flattened-{}.yaml",
+        Local::now().format("%Y%m%d-%H%M%S")
+    )))
+}
+
+pub async fn export_flattened_config(target: FlattenTarget) -> Result<FlattenResult> {
+    let config = { Config::runtime().latest().config.clone() };
+    let mut config = config.ok_or(anyhow!("the runtime config is not generated yet"))?;
+
+    let mut warnings = vec![];
+    let proxies = resolve_proxy_providers(&mut config, &mut warnings).await;
+    let rule_providers = resolve_rule_providers(&mut config, &mut warnings).await;
+    let injected = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        injected_rules(
+            verge.app_rules.as_ref(),
+            &verge.clash_core.clone().unwrap_or_default(),
+        )
+    };
+    strip_app_parts(&mut config, &injected, &mut warnings);
+    adapt_for_device(&mut config, &target, &mut warnings);
+
+    let path = output_path(&target)?;
+    let env_path = match target.env_secrets {
+        true => {
+            let vars = template_secrets(&mut config);
+            let env_path = path.with_extension("env");
+            write_private(&env_path, env_file(&vars).as_bytes())?;
+            Some(env_path.to_string_lossy().to_string())
+        }
+        false => None,
+    };
+    let prefix = format!(
+        "# Flattened by Clash Nyanpasu at {}, run it with mihomo -f",
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    );
+    help::save_yaml(&path, &config, Some(&prefix))?;
+
+    Ok(FlattenResult {
+        path: path.to_string_lossy().to_string(),
+        env_path,
+        proxies,
+        rule_providers,
+        warnings,
+    })
+}
+
+#[test]
+fn test_template_secrets() {
+    let mut config = serde_yaml::from_str::<Mapping>(
+        "secret: abc\nproxies:\n  - {name: a, type: ss, password: \"it's\", plugin-opts: {password: p}}\n  - {name: b, type: vmess, uuid: 1234}\n",
+    )
+    .unwrap();
+    let vars = template_secrets(&mut config);
+    let names = vars
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "NYANPASU_SECRET",
+            "NYANPASU_PROXY_0_PASSWORD",
+            "NYANPASU_PROXY_0_PLUGIN_PASSWORD",
+            "NYANPASU_PROXY_1_UUID"
+        ]
+    );
+    assert_eq!(
+        config.get("secret").unwrap().as_str(),
+        Some("${NYANPASU_SECRET}")
+    );
+    assert!(env_file(&vars).contains("NYANPASU_PROXY_0_PASSWORD='it'\\''s'\n"));
+
+    let mut config = serde_yaml::from_str::<Mapping>(
+        "proxies:
+  - {name: wg, type: wireguard, private-key: k, peers: [{server: 1.1.1.1, public-key: p, pre-shared-key: s}]}
+listeners:
+  - {name: in, type: mixed, port: 7899, users: [{username: u, password: pw}]}
+proxy-providers:
+  sub: {type: http, url: 'https://example.com/sub?token=t', path: ./sub.yaml}
+rule-providers:
+  ads: {type: file, path: ./ads.yaml}
+",
+    )
+    .unwrap();
+    let vars = template_secrets(&mut config);
+    let vars = vars
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vars,
+        [
+            ("NYANPASU_PROXY_0_PRIVATE_KEY", "k"),
+            ("NYANPASU_PROXY_0_PEER_0_PRE_SHARED_KEY", "s"),
+            ("NYANPASU_LISTENER_0_USER_0_PASSWORD", "pw"),
+            (
+                "NYANPASU_PROXY_PROVIDER_0_URL",
+                "https://example.com/sub?token=t"
+            ),
+        ]
+    );
+    assert_eq!(
+        config["listeners"][0]["users"][0]["password"].as_str(),
+        Some("${NYANPASU_LISTENER_0_USER_0_PASSWORD}")
+    );
+    assert_eq!(
+        config["proxies"][0]["peers"][0]["public-key"].as_str(),
+        Some("p")
+    );
+
+    let filter = ProxyFilter::new(
+        &serde_yaml::from_str("{filter: 'HK`JP', exclude-filter: Test, exclude-type: Http}")
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(filter.matches("HK 01", "ss"));
+    assert!(!filter.matches("HK Test", "ss"));
+    assert!(!filter.matches("JP 01", "http"));
+    assert!(!filter.matches("US 01", "ss"));
+
+    let mut config = serde_yaml::from_str::<Mapping>(
+        "external-controller: 127.0.0.1:9090
+secret: ''
+",
+    )
+    .unwrap();
+    let target = FlattenTarget {
+        allow_lan: true,
+        ..FlattenTarget::default()
+    };
+    let mut warnings = vec![];
+    adapt_for_device(&mut config, &target, &mut warnings);
+    assert_eq!(
+        config.get("external-controller").unwrap().as_str(),
+        Some("0.0.0.0:9090")
+    );
+    assert_eq!(config.get("secret").unwrap().as_str().unwrap().len(), 32);
+    assert_eq!(warnings.len(), 1);
+
+    let mut config = serde_yaml::from_str::<Mapping>(
+        "interface-name: en0
+tun: {enable: true}
+listeners:
+  - {name: nyanpasu-exit-probe, type: http, port: 33336, listen: 127.0.0.1, proxy: nyanpasu-exit-probe}
+proxy-groups:
+  - {name: Proxy, type: select, proxies: [DIRECT]}
+  - {name: nyanpasu-exit-probe, type: select, include-all: true, hidden: true}
+rules:
+  - PROCESS-NAME,clash-nyanpasu,DIRECT
+  - PROCESS-NAME,curl,Proxy
+  - MATCH,Proxy
+",
+    )
+    .unwrap();
+    let injected = HashSet::from(["PROCESS-NAME,clash-nyanpasu,DIRECT".to_string()]);
+    let mut warnings = vec![];
+    strip_app_parts(&mut config, &injected, &mut warnings);
+    assert!(!config.contains_key("listeners"));
+    assert!(!config.contains_key("interface-name"));
+    assert_eq!(config["proxy-groups"].as_sequence().unwrap().len(), 1);
+    assert_eq!(
+        config["rules"],
+        Value::from(vec!["PROCESS-NAME,curl,Proxy", "MATCH,Proxy"])
+    );
+    assert_eq!(warnings.len(), 3);
+}
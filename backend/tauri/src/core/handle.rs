@@ -3,8 +3,9 @@ use crate::log_err;
 use anyhow::{bail, Result};
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
+use serde::Serialize;
 use std::sync::Arc;
-use tauri::{AppHandle, Manager, Window};
+use tauri::{api::notification::Notification, AppHandle, Manager, Window};
 
 #[derive(Debug, Default, Clone)]
 pub struct Handle {
@@ -56,6 +57,22 @@ impl Handle {
         }
     }
 
+    /// show a system notification
+    pub fn notify<T: Into<String>, B: Into<String>>(title: T, body: B) {
+        let app_handle = Self::global().app_handle.lock();
+        if let Some(app_handle) = app_handle.as_ref() {
+            let identifier = app_handle.config().tauri.bundle.identifier.clone();
+            log_err!(Notification::new(identifier).title(title).body(body).show());
+        }
+    }
+
+    /// emit an event with payload to the main window
+    pub fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+        if let Some(window) = Self::global().get_window() {
+            log_err!(window.emit(event, payload));
+        }
+    }
+
     pub fn update_systray() -> Result<()> {
         let app_handle = Self::global().app_handle.lock();
         if app_handle.is_none() {
@@ -72,6 +89,8 @@ impl Handle {
             bail!("update_systray unhandled error");
         }
         Tray::update_part(app_handle.as_ref().unwrap())?;
+        #[cfg(target_os = "linux")]
+        super::linux_status::LinuxStatus::global().refresh();
         Ok(())
     }
 }
@@ -1,11 +1,67 @@
+use super::proxy_env::ShellType;
+#[cfg(target_os = "linux")]
+use super::{linux_status, portal_shortcuts::PortalShortcuts};
 use crate::{config::Config, feat, log_err};
 use anyhow::{bail, Result};
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
-use std::{collections::HashMap, sync::Arc};
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
 use tauri::{AppHandle, GlobalShortcutManager};
 use wry::application::accelerator::Accelerator;
 
+/// the functions could be bound to a hotkey
+pub const HOTKEY_FUNCS: [&str; 16] = [
+    "open_or_close_dashboard",
+    "open_dashboard",
+    "clash_mode_rule",
+    "clash_mode_global",
+    "clash_mode_direct",
+    "clash_mode_script",
+    "toggle_system_proxy",
+    "enable_system_proxy",
+    "disable_system_proxy",
+    "toggle_tun_mode",
+    "enable_tun_mode",
+    "disable_tun_mode",
+    "toggle_pause",
+    "switch_next_profile",
+    "copy_env",
+    "restart_clash",
+];
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HotkeyIssue {
+    /// the same shortcut is bound to several functions
+    Conflict {
+        key: String,
+        funcs: Vec<String>,
+    },
+    InvalidKey {
+        key: String,
+        func: String,
+    },
+    UnknownFunction {
+        func: String,
+    },
+}
+
+impl std::fmt::Display for HotkeyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Conflict { key, funcs } => {
+                write!(f, "hotkey `{key}` is bound to {}", funcs.join(", "))
+            }
+            Self::InvalidKey { key, func } => write!(f, "invalid hotkey `{key}` of `{func}`"),
+            Self::UnknownFunction { func } => write!(f, "invalid function \"{func}\""),
+        }
+    }
+}
+
 pub struct Hotkey {
     current: Arc<Mutex<Vec<String>>>, // 保存当前的热键设置
 
@@ -35,6 +91,14 @@ impl Hotkey {
 
         let verge = Config::verge();
 
+        #[cfg(target_os = "linux")]
+        if use_portal() {
+            let hotkeys = verge.latest().hotkeys.clone().unwrap_or_default();
+            PortalShortcuts::global().bind(hotkeys.clone());
+            *self.current.lock() = hotkeys;
+            return Ok(());
+        }
+
         if let Some(hotkeys) = verge.latest().hotkeys.as_ref() {
             for hotkey in hotkeys.iter() {
                 let mut iter = hotkey.split(',');
@@ -48,7 +112,7 @@ impl Hotkey {
                     _ => {
                         let key = key.unwrap_or("None");
                         let func = func.unwrap_or("None");
-                        log::error!(target: "app", "invalid hotkey `{key}`:`{func}`");
+                        tracing::error!("invalid hotkey `{key}`:`{func}`");
                     }
                 }
             }
@@ -83,35 +147,73 @@ impl Hotkey {
             manager.unregister(hotkey)?;
         }
 
-        let f = match func.trim() {
-            "open_or_close_dashboard" => feat::toggle_dashboard,
-            "clash_mode_rule" => || feat::change_clash_mode("rule".into()),
-            "clash_mode_global" => || feat::change_clash_mode("global".into()),
-            "clash_mode_direct" => || feat::change_clash_mode("direct".into()),
-            "clash_mode_script" => || feat::change_clash_mode("script".into()),
-            "toggle_system_proxy" => feat::toggle_system_proxy,
-            "enable_system_proxy" => feat::enable_system_proxy,
-            "disable_system_proxy" => feat::disable_system_proxy,
-            "toggle_tun_mode" => feat::toggle_tun_mode,
-            "enable_tun_mode" => feat::enable_tun_mode,
-            "disable_tun_mode" => feat::disable_tun_mode,
-
-            _ => bail!("invalid function \"{func}\""),
-        };
-
-        manager.register(hotkey, f)?;
-        log::info!(target: "app", "register hotkey {hotkey} {func}");
+        manager.register(hotkey, function(func)?)?;
+        tracing::info!("register hotkey {hotkey} {func}");
         Ok(())
     }
 
     fn unregister(&self, hotkey: &str) -> Result<()> {
         self.get_manager()?.unregister(hotkey)?;
-        log::info!(target: "app", "unregister hotkey {hotkey}");
+        tracing::info!("unregister hotkey {hotkey}");
         Ok(())
     }
 
+    /// find the invalid and conflicting hotkeys
+    /// `Ctrl+Shift+P` and `shift+control+p` are treated as the same key
+    pub fn check(hotkeys: &[String]) -> Vec<HotkeyIssue> {
+        let mut issues = Vec::new();
+        let mut bound = HashMap::<String, (String, Vec<String>)>::new();
+
+        for hotkey in hotkeys {
+            let (func, key) = match hotkey.split_once(',') {
+                Some((func, key)) => (func.trim(), key.trim()),
+                None => continue,
+            };
+
+            if !HOTKEY_FUNCS.contains(&func) {
+                issues.push(HotkeyIssue::UnknownFunction { func: func.into() });
+                continue;
+            }
+            if Self::check_key(key).is_err() {
+                issues.push(HotkeyIssue::InvalidKey {
+                    key: key.into(),
+                    func: func.into(),
+                });
+                continue;
+            }
+
+            bound
+                .entry(normalize_key(key))
+                .or_insert_with(|| (key.to_string(), Vec::new()))
+                .1
+                .push(func.to_string());
+        }
+
+        let mut conflicts = bound
+            .into_values()
+            .filter(|(_, funcs)| funcs.len() > 1)
+            .map(|(key, funcs)| HotkeyIssue::Conflict { key, funcs })
+            .collect::<Vec<_>>();
+        conflicts.sort_by_key(|issue| issue.to_string());
+
+        issues.extend(conflicts);
+        issues
+    }
+
     pub fn update(&self, new_hotkeys: Vec<String>) -> Result<()> {
+        if let Some(issue) = Self::check(&new_hotkeys).first() {
+            bail!("{issue}");
+        }
+
         let mut current = self.current.lock();
+
+        #[cfg(target_os = "linux")]
+        if use_portal() {
+            PortalShortcuts::global().bind(new_hotkeys.clone());
+            *current = new_hotkeys;
+            return Ok(());
+        }
+
         let old_map = Self::get_map_from_vec(&current);
         let new_map = Self::get_map_from_vec(&new_hotkeys);
 
@@ -185,6 +287,65 @@ impl Hotkey {
     }
 }
 
+/// the x11 key grabs don't work on wayland, the shortcuts are bound through the portal
+#[cfg(target_os = "linux")]
+fn use_portal() -> bool {
+    linux_status::session_type() == linux_status::SessionType::Wayland
+}
+
+/// the functions bound to the hotkeys, also used by the tray menu
+pub fn function(func: &str) -> Result<fn()> {
+    let f: fn() = match func.trim() {
+        "open_or_close_dashboard" => feat::toggle_dashboard,
+        "clash_mode_rule" => || feat::change_clash_mode("rule".into()),
+        "clash_mode_global" => || feat::change_clash_mode("global".into()),
+        "clash_mode_direct" => || feat::change_clash_mode("direct".into()),
+        "clash_mode_script" => || feat::change_clash_mode("script".into()),
+        "open_dashboard" => feat::open_dashboard,
+        "toggle_system_proxy" => feat::toggle_system_proxy,
+        "enable_system_proxy" => feat::enable_system_proxy,
+        "disable_system_proxy" => feat::disable_system_proxy,
+        "toggle_tun_mode" => feat::toggle_tun_mode,
+        "enable_tun_mode" => feat::enable_tun_mode,
+        "disable_tun_mode" => feat::disable_tun_mode,
+        "toggle_pause" => feat::toggle_pause,
+        "switch_next_profile" => feat::switch_next_profile,
+        "copy_env" => || feat::copy_clash_env(ShellType::platform_default()),
+        "restart_clash" => feat::restart_clash_core,
+
+        _ => bail!("invalid function \"{func}\""),
+    };
+    Ok(f)
+}
+
+/// the modifiers are sorted, the aliases are unified
+pub(crate) fn normalize_key(key: &str) -> String {
+    let mut modifiers = BTreeSet::new();
+    let mut code = String::new();
+
+    for part in key.split('+').map(|k| k.trim().to_uppercase()) {
+        let modifier = match part.as_str() {
+            "CTRL" | "CONTROL" => "CONTROL",
+            "SHIFT" => "SHIFT",
+            "ALT" | "OPTION" => "ALT",
+            "SUPER" | "CMD" | "COMMAND" | "META" => "SUPER",
+            #[cfg(target_os = "macos")]
+            "CMDORCTRL" | "CMDORCONTROL" | "COMMANDORCONTROL" | "COMMANDORCTRL" => "SUPER",
+            #[cfg(not(target_os = "macos"))]
+            "CMDORCTRL" | "CMDORCONTROL" | "COMMANDORCONTROL" | "COMMANDORCTRL" => "CONTROL",
+            _ => {
+                code = part;
+                continue;
+            }
+        };
+        modifiers.insert(modifier);
+    }
+
+    let mut parts = modifiers.into_iter().map(String::from).collect::<Vec<_>>();
+    parts.push(code);
+    parts.join("+")
+}
+
 impl Drop for Hotkey {
     fn drop(&mut self) {
         if let Ok(mut manager) = self.get_manager() {
@@ -192,3 +353,28 @@ impl Drop for Hotkey {
         }
     }
 }
+
+#[test]
+fn test_check_hotkeys() {
+    assert_eq!(normalize_key("shift + Ctrl + p"), "CONTROL+SHIFT+P");
+    assert_eq!(normalize_key("Control+Shift+P"), "CONTROL+SHIFT+P");
+
+    let hotkeys = vec![
+        "toggle_system_proxy,Ctrl+Shift+P".to_string(),
+        "toggle_tun_mode,shift+control+p".to_string(),
+        "open_dashboard,Alt+D".to_string(),
+        "unknown,Alt+U".to_string(),
+    ];
+    let issues = Hotkey::check(&hotkeys);
+    assert_eq!(issues.len(), 2);
+    assert_eq!(
+        issues[0],
+        HotkeyIssue::UnknownFunction {
+            func: "unknown".into()
+        }
+    );
+    assert!(matches!(
+        &issues[1],
+        HotkeyIssue::Conflict { funcs, .. } if funcs == &["toggle_system_proxy", "toggle_tun_mode"]
+    ));
+}
@@ -0,0 +1,125 @@
+//! the clients of the app's own requests, built with the policy of each purpose
+//!
+//! the timeout, the retries and the proxy are configured in `http_policies` of the settings, the
+//! doh server applies to all of them. the requests to the core, the service, the local servers and
+//! the probes with their own proxy don't go through here.
+
+use super::{doh, CoreManager};
+use crate::config::{Config, HttpProxy, HttpPurpose};
+use anyhow::Result;
+use reqwest::{ClientBuilder, IntoUrl, Method, RequestBuilder, Response};
+use std::time::Duration;
+use sysproxy::Sysproxy;
+
+/// the first retry waits 1s, doubled each time
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    retries: u32,
+}
+
+impl HttpClient {
+    pub async fn get(&self, url: impl IntoUrl + Clone) -> Result<Response> {
+        send_with_retry(self.retries, || self.client.get(url.clone())).await
+    }
+
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// send the built request with the retries, the streamed bodies can't be retried
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        match request.try_clone() {
+            Some(_) => send_with_retry(self.retries, || request.try_clone().unwrap()).await,
+            None => Ok(request.send().await?),
+        }
+    }
+}
+
+fn policy(purpose: HttpPurpose) -> (u64, u32, Option<HttpProxy>) {
+    let policies = { Config::verge().latest().http_policies.clone() };
+    policies.unwrap_or_default().resolve(purpose)
+}
+
+/// `None` to connect directly
+fn proxy_url(proxy: &HttpProxy) -> Option<String> {
+    match proxy {
+        HttpProxy::Direct => None,
+        HttpProxy::System => match Sysproxy::get_system_proxy() {
+            Ok(p @ Sysproxy { enable: true, .. }) => Some(format!("http://{}:{}", p.host, p.port)),
+            _ => None,
+        },
+        HttpProxy::Core => CoreManager::global().is_running().then(|| {
+            let port = Config::verge()
+                .latest()
+                .verge_mixed_port
+                .unwrap_or(Config::clash().latest().get_mixed_port());
+            format!("http://127.0.0.1:{port}")
+        }),
+        HttpProxy::Custom(url) => Some(url.trim().to_string()),
+    }
+}
+
+/// replace the proxy of the builder, the proxy env vars are ignored
+pub fn apply_proxy(builder: ClientBuilder, proxy: &HttpProxy) -> Result<ClientBuilder> {
+    let builder = builder.no_proxy();
+    Ok(match proxy_url(proxy) {
+        Some(url) => builder.proxy(reqwest::Proxy::all(url)?),
+        None => builder,
+    })
+}
+
+/// the builder with the policy except the retries, for the callers adding their own options
+pub fn builder(purpose: HttpPurpose) -> Result<ClientBuilder> {
+    let (timeout, _, proxy) = policy(purpose);
+    let mut builder = doh::apply(ClientBuilder::new().use_rustls_tls());
+    if timeout > 0 {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+    // 没有设置代理的策略时保留环境变量里的代理
+    match proxy {
+        Some(proxy) => apply_proxy(builder, &proxy),
+        None => Ok(builder),
+    }
+}
+
+pub fn client(purpose: HttpPurpose) -> Result<HttpClient> {
+    Ok(HttpClient {
+        client: builder(purpose)?.build()?,
+        retries: retries(purpose),
+    })
+}
+
+pub fn retries(purpose: HttpPurpose) -> u32 {
+    policy(purpose).1
+}
+
+fn should_retry(res: &reqwest::Result<Response>) -> bool {
+    match res {
+        Ok(res) => res.status().is_server_error(),
+        Err(err) => err.is_connect() || err.is_timeout(),
+    }
+}
+
+/// the 4xx responses are returned as is, the callers check the status
+pub async fn send_with_retry(
+    retries: u32,
+    request: impl Fn() -> RequestBuilder,
+) -> Result<Response> {
+    let mut attempt = 0;
+    loop {
+        let res = request().send().await;
+        if attempt >= retries || !should_retry(&res) {
+            return Ok(res?);
+        }
+        let delay = RETRY_DELAY * 2u32.pow(attempt);
+        match &res {
+            Ok(res) => tracing::debug!("retry in {delay:?}, the server answered {}", res.status()),
+            Err(err) => tracing::debug!("retry in {delay:?}, the request failed: {err}"),
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
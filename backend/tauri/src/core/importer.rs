@@ -0,0 +1,492 @@
+//! import the profiles and the settings of Clash for Windows and Clash Verge
+//!
+//! the data dir is converted into a plan first, the preview shows its report without touching
+//! anything. the profiles are copied with their files, the remote ones are not downloaded again.
+//! everything that can't be translated is listed in the report instead of failing the import.
+
+use super::{handle, onboarding::KnownClient, CoreManager};
+use crate::{
+    config::{ClashCore, Config, IProfiles, IVerge, PrfItem, PrfOption, PrfSelected},
+    feat,
+    utils::help,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{collections::HashMap, fs, path::Path};
+
+/// the verge settings with the same meaning here
+const SETTINGS: &[&str] = &[
+    "language",
+    "theme_mode",
+    "theme_blur",
+    "theme_setting",
+    "traffic_graph",
+    "enable_memory_usage",
+    "enable_silent_start",
+    "system_proxy_bypass",
+    "enable_proxy_guard",
+    "proxy_guard_duration",
+    "auto_close_connection",
+    "default_latency_test",
+    "enable_clash_fields",
+    "enable_builtin_enhanced",
+    "proxy_layout_column",
+    "verge_mixed_port",
+    "enable_random_port",
+    "auto_log_clean",
+    "hotkeys",
+    "web_ui_list",
+    "clash_core",
+];
+
+/// the settings changing the system, left for the user to turn on
+const SYSTEM_SETTINGS: &[&str] = &[
+    "enable_tun_mode",
+    "enable_service_mode",
+    "enable_system_proxy",
+    "enable_auto_launch",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedProfile {
+    pub name: String,
+    /// remote | local | merge | script
+    pub kind: String,
+    /// whether it is in the chain after importing
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedItem {
+    pub item: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub client: KnownClient,
+    pub profiles: Vec<ImportedProfile>,
+    /// the keys of the imported settings
+    pub settings: Vec<String>,
+    pub skipped: Vec<SkippedItem>,
+    /// the new uid of the profile in use of the client
+    pub current: Option<String>,
+}
+
+impl ImportReport {
+    fn skip(&mut self, item: impl Into<String>, reason: impl Into<String>) {
+        self.skipped.push(SkippedItem {
+            item: item.into(),
+            reason: reason.into(),
+        });
+    }
+}
+
+struct ImportPlan {
+    /// with the file data
+    items: Vec<PrfItem>,
+    /// the new uids
+    chain: Vec<String>,
+    current: Option<String>,
+    settings: Mapping,
+    report: ImportReport,
+}
+
+impl ImportPlan {
+    fn new(client: KnownClient) -> Self {
+        ImportPlan {
+            items: vec![],
+            chain: vec![],
+            current: None,
+            settings: Mapping::new(),
+            report: ImportReport {
+                client,
+                profiles: vec![],
+                settings: vec![],
+                skipped: vec![],
+                current: None,
+            },
+        }
+    }
+
+    fn push(&mut self, item: PrfItem, enabled: bool) {
+        self.report.profiles.push(ImportedProfile {
+            name: item.name.clone().unwrap_or_default(),
+            kind: item.itype.clone().unwrap_or_default(),
+            enabled,
+        });
+        self.items.push(item);
+    }
+}
+
+/// `profiles.yaml` of clash verge
+#[derive(Deserialize)]
+struct VergeProfiles {
+    current: Option<String>,
+    chain: Option<Vec<String>>,
+    items: Option<Vec<VergeItem>>,
+}
+
+#[derive(Deserialize)]
+struct VergeItem {
+    uid: Option<String>,
+    #[serde(rename = "type")]
+    itype: Option<String>,
+    name: Option<String>,
+    desc: Option<String>,
+    file: Option<String>,
+    url: Option<String>,
+    selected: Option<Vec<PrfSelected>>,
+    option: Option<VergeOption>,
+}
+
+#[derive(Default, Deserialize)]
+struct VergeOption {
+    user_agent: Option<String>,
+    with_proxy: Option<bool>,
+    self_proxy: Option<bool>,
+    update_interval: Option<u64>,
+    /// clash verge rev, the enhancements of each profile
+    merge: Option<String>,
+    script: Option<String>,
+}
+
+/// `profiles/list.yml` of clash for windows
+#[derive(Deserialize)]
+struct CfwList {
+    files: Vec<CfwFile>,
+    index: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct CfwFile {
+    /// the file name, e.g. `1700000000000.yml`
+    time: String,
+    name: Option<String>,
+    url: Option<String>,
+    selected: Option<Vec<PrfSelected>>,
+    /// hours
+    interval: Option<f64>,
+}
+
+fn read_yaml<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let data =
+        fs::read_to_string(path).with_context(|| format!("failed to read `{}`", path.display()))?;
+    serde_yaml::from_str(&data).with_context(|| format!("invalid yaml `{}`", path.display()))
+}
+
+fn new_item(kind: &str, name: String, desc: String, data: String) -> Result<PrfItem> {
+    let mut item = match kind {
+        "merge" => PrfItem::from_merge(name, desc)?,
+        "script" => PrfItem::from_script(name, desc)?,
+        "remote" => {
+            let uid = help::get_uid("r");
+            PrfItem {
+                file: Some(format!("{uid}.yaml")),
+                uid: Some(uid),
+                itype: Some("remote".into()),
+                name: Some(name),
+                desc: Some(desc),
+                updated: Some(chrono::Local::now().timestamp() as usize),
+                ..PrfItem::default()
+            }
+        }
+        _ => PrfItem::from_local(name, desc, None)?,
+    };
+    item.file_data = Some(data);
+    Ok(item)
+}
+
+fn plan_verge(client: KnownClient, dir: &Path) -> Result<ImportPlan> {
+    let mut plan = ImportPlan::new(client);
+    let profiles = read_yaml::<VergeProfiles>(&dir.join("profiles.yaml"))?;
+    let chain = profiles.chain.unwrap_or_default();
+
+    // 旧的 uid 到新的 uid
+    let mut uids = HashMap::new();
+    for old in profiles.items.unwrap_or_default() {
+        let name = old.name.clone().unwrap_or_else(|| "Imported".into());
+        let kind = old.itype.clone().unwrap_or_default();
+        if !matches!(kind.as_str(), "remote" | "local" | "merge" | "script") {
+            plan.report
+                .skip(name, format!("the `{kind}` items are not supported"));
+            continue;
+        }
+        let Some(file) = old.file.as_ref() else {
+            plan.report.skip(name, "the file is missing");
+            continue;
+        };
+        let data = match fs::read_to_string(dir.join("profiles").join(file)) {
+            Ok(data) => data,
+            Err(err) => {
+                plan.report
+                    .skip(name, format!("failed to read the file: {err}"));
+                continue;
+            }
+        };
+
+        let option = old.option.unwrap_or_default();
+        if option.merge.is_some() || option.script.is_some() {
+            plan.report.skip(
+                format!("{name} (enhancements)"),
+                "the merge and the script of each profile are imported as the global ones, not enabled",
+            );
+        }
+
+        let desc = old.desc.unwrap_or_default();
+        let mut item = new_item(&kind, name, desc, data)?;
+        if kind == "remote" {
+            item.url = old.url;
+            item.option = Some(PrfOption {
+                user_agent: option.user_agent,
+                with_proxy: option.with_proxy,
+                self_proxy: option.self_proxy,
+                update_interval: option.update_interval,
+                ..PrfOption::default()
+            });
+        } else if kind == "local" {
+            item.option = option.update_interval.map(|interval| PrfOption {
+                update_interval: Some(interval),
+                ..PrfOption::default()
+            });
+        }
+        item.selected = old.selected;
+
+        let uid = item.uid.clone().unwrap_or_default();
+        let enabled = old.uid.as_ref().map_or(false, |old| chain.contains(old));
+        if let Some(old) = old.uid {
+            if profiles.current.as_ref() == Some(&old) {
+                plan.current = Some(uid.clone());
+            }
+            uids.insert(old, uid);
+        }
+        plan.push(item, enabled);
+    }
+    plan.chain = chain
+        .iter()
+        .filter_map(|old| uids.get(old).cloned())
+        .collect();
+
+    let path = dir.join("verge.yaml");
+    if path.exists() {
+        plan_verge_settings(&mut plan, read_yaml(&path)?);
+    }
+    Ok(plan)
+}
+
+fn plan_verge_settings(plan: &mut ImportPlan, verge: Mapping) {
+    for (key, value) in verge {
+        let Some(key) = key.as_str().map(str::to_string) else {
+            continue;
+        };
+        if value.is_null() {
+            continue;
+        }
+        if SYSTEM_SETTINGS.contains(&key.as_str()) {
+            plan.report
+                .skip(key, "it changes the system, turn it on in the settings");
+            continue;
+        }
+        if !SETTINGS.contains(&key.as_str()) {
+            plan.report.skip(key, "not supported");
+            continue;
+        }
+
+        // clash verge rev 的内核叫 verge-mihomo
+        let value = match (key.as_str(), value.as_str()) {
+            ("clash_core", Some(core)) => {
+                Value::String(core.trim_start_matches("verge-").to_string())
+            }
+            _ => value,
+        };
+        let mut single = Mapping::new();
+        single.insert(key.clone().into(), value.clone());
+        match serde_yaml::from_value::<IVerge>(Value::Mapping(single)) {
+            Ok(_) => {
+                plan.settings.insert(key.clone().into(), value);
+                plan.report.settings.push(key);
+            }
+            Err(err) => plan.report.skip(key, format!("invalid value: {err}")),
+        }
+    }
+}
+
+fn plan_cfw(dir: &Path) -> Result<ImportPlan> {
+    let mut plan = ImportPlan::new(KnownClient::ClashForWindows);
+    let list = read_yaml::<CfwList>(&dir.join("profiles").join("list.yml"))?;
+
+    for (index, file) in list.files.into_iter().enumerate() {
+        let name = file.name.clone().unwrap_or_else(|| file.time.clone());
+        let data = match fs::read_to_string(dir.join("profiles").join(&file.time)) {
+            Ok(data) => data,
+            Err(err) => {
+                plan.report
+                    .skip(name, format!("failed to read the file: {err}"));
+                continue;
+            }
+        };
+
+        let url = file.url.filter(|url| !url.trim().is_empty());
+        let kind = if url.is_some() { "remote" } else { "local" };
+        let mut item = new_item(kind, name, "Imported from Clash for Windows".into(), data)?;
+        item.url = url;
+        item.selected = file.selected;
+        if kind == "remote" {
+            item.option = Some(PrfOption {
+                update_interval: file
+                    .interval
+                    .filter(|hours| *hours > 0.0)
+                    .map(|hours| (hours * 60.0) as u64),
+                ..PrfOption::default()
+            });
+        }
+
+        if list.index == Some(index) {
+            plan.current = item.uid.clone();
+        }
+        plan.push(item, false);
+    }
+
+    // parsers 和其他设置没有对应的功能
+    let path = dir.join("cfw-settings.yaml");
+    if path.exists() {
+        let settings = read_yaml::<Mapping>(&path)?;
+        let parsers = settings
+            .get("parsers")
+            .and_then(|parsers| parsers.as_sequence())
+            .map_or(0, |parsers| parsers.len());
+        if parsers > 0 {
+            plan.report.skip(
+                format!("parsers ({parsers})"),
+                "convert them to the merge or the script items by hand",
+            );
+        }
+        let keys = settings
+            .keys()
+            .filter_map(|key| key.as_str())
+            .filter(|key| *key != "parsers")
+            .collect::<Vec<_>>();
+        if !keys.is_empty() {
+            plan.report.skip(
+                "cfw-settings.yaml",
+                format!("not translated: {}", keys.join(", ")),
+            );
+        }
+    }
+    Ok(plan)
+}
+
+/// the name of the profile in use, none if nothing to import
+pub fn current_name(client: KnownClient, dir: &Path) -> Option<String> {
+    match client {
+        KnownClient::ClashVerge | KnownClient::ClashVergeRev => {
+            let profiles = read_yaml::<VergeProfiles>(&dir.join("profiles.yaml")).ok()?;
+            let current = profiles.current?;
+            profiles
+                .items?
+                .into_iter()
+                .find(|item| item.uid.as_ref() == Some(&current))
+                .map(|item| item.name.unwrap_or_else(|| "Imported".into()))
+        }
+        KnownClient::ClashForWindows => {
+            let list = read_yaml::<CfwList>(&dir.join("profiles").join("list.yml")).ok()?;
+            let file = list.files.into_iter().nth(list.index?)?;
+            Some(file.name.unwrap_or(file.time))
+        }
+    }
+}
+
+fn plan(client: KnownClient) -> Result<ImportPlan> {
+    let dir = client
+        .dir()
+        .ok_or_else(|| anyhow!("the data of {} is not found", client.label()))?;
+    match client {
+        KnownClient::ClashVerge | KnownClient::ClashVergeRev => plan_verge(client, &dir),
+        KnownClient::ClashForWindows => plan_cfw(&dir),
+    }
+}
+
+/// what will be imported, nothing is changed
+pub async fn preview(client: KnownClient) -> Result<ImportReport> {
+    let plan = tokio::task::spawn_blocking(move || plan(client)).await??;
+    Ok(plan.report)
+}
+
+/// the imported items are appended, the imported chain is enabled after the current one
+/// the imported current profile is used only if there is no current one
+pub async fn import(client: KnownClient) -> Result<ImportReport> {
+    let mut plan = tokio::task::spawn_blocking(move || plan(client)).await??;
+    plan.report.current = plan.current.clone();
+    for item in plan.items {
+        Config::profiles().data().append_item(item)?;
+    }
+
+    let (current, chain) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        (
+            profiles.get_current(),
+            profiles.chain.clone().unwrap_or_default(),
+        )
+    };
+    let switch = plan.current.filter(|_| current.is_none());
+    if switch.is_some() || !plan.chain.is_empty() {
+        let mut chain = chain;
+        chain.extend(plan.chain);
+        feat::patch_profiles(IProfiles {
+            current: switch,
+            chain: Some(chain),
+            ..IProfiles::default()
+        })
+        .await?;
+    }
+
+    // 内核要真正切换过去，只改设置不会换掉运行中的内核
+    let core = plan.settings.remove("clash_core");
+    if !plan.settings.is_empty() {
+        let patch = serde_yaml::from_value::<IVerge>(Value::Mapping(plan.settings))?;
+        feat::patch_verge(patch).await?;
+    }
+    if let Some(core) = core {
+        let core = serde_yaml::from_value::<ClashCore>(core)?;
+        let current = { Config::verge().latest().clash_core.clone() };
+        if Some(&core) != current.as_ref() {
+            if let Err(err) = CoreManager::global().change_core(Some(core)).await {
+                plan.report.settings.retain(|key| key != "clash_core");
+                plan.report
+                    .skip("clash_core", format!("failed to switch the core: {err}"));
+            }
+        }
+    }
+
+    handle::Handle::refresh_profiles();
+    handle::Handle::refresh_verge();
+    Ok(plan.report)
+}
+
+#[test]
+fn test_plan_verge_settings() {
+    let verge = serde_yaml::from_str::<Mapping>(
+        "language: zh\nclash_core: verge-mihomo\nenable_tun_mode: true\nverge_mixed_port: abc\nunknown_key: 1\ntheme_mode: null\n",
+    )
+    .unwrap();
+    let mut plan = ImportPlan::new(KnownClient::ClashVergeRev);
+    plan_verge_settings(&mut plan, verge);
+
+    assert_eq!(plan.report.settings, ["language", "clash_core"]);
+    assert_eq!(
+        plan.settings.get("clash_core").and_then(|v| v.as_str()),
+        Some("mihomo")
+    );
+    let skipped = plan
+        .report
+        .skipped
+        .iter()
+        .map(|item| item.item.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        skipped,
+        ["enable_tun_mode", "verge_mixed_port", "unknown_key"]
+    );
+}
@@ -0,0 +1,319 @@
+//! the binary channel of the high frequency streams
+//!
+//! the frames are encoded by msgpack and sent through the websocket of the embed server,
+//! instead of the json events of the webview. the connections are delta encoded,
+//! a subscriber gets a full snapshot first, and again when it falls behind.
+
+use super::{
+    log_stream::LogItem,
+    stream::{LowPowerPolicy, StreamHandler, StreamManager},
+    tray::Traffic,
+};
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::broadcast::{self, error::RecvError};
+use warp::ws::{Message, WebSocket};
+
+/// the frames buffered for a slow subscriber
+const CHANNEL_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Connections,
+    Traffic,
+    Logs,
+}
+
+impl Channel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "connections" => Some(Channel::Connections),
+            "traffic" => Some(Channel::Traffic),
+            "logs" => Some(Channel::Logs),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ConnectionItem {
+    id: String,
+    upload: u64,
+    download: u64,
+    /// the metadata, the chains and the rule, passed through as is
+    #[serde(flatten)]
+    rest: Map<String, Value>,
+}
+
+/// the frame sent by `/connections`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawConnections {
+    upload_total: u64,
+    download_total: u64,
+    /// meta 有时会返回 null
+    connections: Option<Vec<ConnectionItem>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectionUpdate {
+    id: String,
+    upload: u64,
+    download: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConnectionsFrame {
+    Full {
+        seq: u64,
+        upload_total: u64,
+        download_total: u64,
+        connections: Vec<ConnectionItem>,
+    },
+    /// against the frame of `seq - 1`
+    Delta {
+        seq: u64,
+        upload_total: u64,
+        download_total: u64,
+        added: Vec<ConnectionItem>,
+        updated: Vec<ConnectionUpdate>,
+        removed: Vec<String>,
+    },
+}
+
+#[derive(Debug, Default)]
+struct ConnectionsState {
+    seq: u64,
+    upload_total: u64,
+    download_total: u64,
+    connections: Vec<ConnectionItem>,
+}
+
+impl ConnectionsState {
+    fn full(&self) -> ConnectionsFrame {
+        ConnectionsFrame::Full {
+            seq: self.seq,
+            upload_total: self.upload_total,
+            download_total: self.download_total,
+            connections: self.connections.clone(),
+        }
+    }
+
+    /// keep the new snapshot and return the changes
+    fn apply(&mut self, raw: RawConnections) -> ConnectionsFrame {
+        let connections = raw.connections.unwrap_or_default();
+        let mut old = self
+            .connections
+            .iter()
+            .map(|item| (item.id.as_str(), item))
+            .collect::<HashMap<_, _>>();
+
+        let mut added = vec![];
+        let mut updated = vec![];
+        for item in connections.iter() {
+            match old.remove(item.id.as_str()) {
+                Some(prev) if prev.upload == item.upload && prev.download == item.download => {}
+                Some(_) => updated.push(ConnectionUpdate {
+                    id: item.id.clone(),
+                    upload: item.upload,
+                    download: item.download,
+                }),
+                None => added.push(item.clone()),
+            }
+        }
+        let removed = old.into_keys().map(|id| id.to_string()).collect();
+
+        self.seq += 1;
+        self.upload_total = raw.upload_total;
+        self.download_total = raw.download_total;
+        self.connections = connections;
+
+        ConnectionsFrame::Delta {
+            seq: self.seq,
+            upload_total: self.upload_total,
+            download_total: self.download_total,
+            added,
+            updated,
+            removed,
+        }
+    }
+}
+
+pub struct IpcStream {
+    connections: Mutex<ConnectionsState>,
+    senders: HashMap<Channel, broadcast::Sender<Arc<Vec<u8>>>>,
+}
+
+/// the `/connections` stream, only started after the first subscriber
+struct ConnectionsHandler;
+
+/// the tray consumes `/traffic` too, so it needs another name
+struct TrafficHandler;
+
+impl IpcStream {
+    pub fn global() -> &'static IpcStream {
+        static IPC_STREAM: OnceCell<IpcStream> = OnceCell::new();
+
+        IPC_STREAM.get_or_init(|| IpcStream {
+            connections: Mutex::new(ConnectionsState::default()),
+            senders: [Channel::Connections, Channel::Traffic, Channel::Logs]
+                .into_iter()
+                .map(|channel| (channel, broadcast::channel(CHANNEL_CAPACITY).0))
+                .collect(),
+        })
+    }
+
+    pub fn has_subscribers(&self, channel: Channel) -> bool {
+        self.senders[&channel].receiver_count() > 0
+    }
+
+    /// encode the frame only when someone is listening
+    pub fn send<T: Serialize>(&self, channel: Channel, frame: &T) {
+        if !self.has_subscribers(channel) {
+            return;
+        }
+        match rmp_serde::to_vec_named(frame) {
+            Ok(data) => {
+                let _ = self.senders[&channel].send(Arc::new(data));
+            }
+            Err(err) => tracing::error!("failed to encode the {channel:?} frame: {err}"),
+        }
+    }
+
+    fn full_connections(&self) -> Option<Vec<u8>> {
+        let frame = self.connections.lock().full();
+        rmp_serde::to_vec_named(&frame).ok()
+    }
+
+    /// forward the frames to the websocket until it is closed
+    pub async fn serve(&'static self, channel: Channel, socket: WebSocket) {
+        let mut frames = self.senders[&channel].subscribe();
+        match channel {
+            Channel::Connections => StreamManager::global().spawn(&ConnectionsHandler),
+            Channel::Traffic => StreamManager::global().spawn(&TrafficHandler),
+            Channel::Logs => {}
+        }
+
+        let (mut tx, mut rx) = socket.split();
+        if channel == Channel::Connections {
+            if let Some(data) = self.full_connections() {
+                if tx.send(Message::binary(data)).await.is_err() {
+                    return;
+                }
+            }
+        }
+
+        loop {
+            let data = tokio::select! {
+                frame = frames.recv() => match frame {
+                    Ok(data) => data.to_vec(),
+                    // 落后太多就重新发送完整的快照，旧的帧由前端按 seq 丢弃
+                    Err(RecvError::Lagged(_)) if channel == Channel::Connections => {
+                        match self.full_connections() {
+                            Some(data) => data,
+                            None => continue,
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+                message = rx.next() => match message {
+                    Some(Ok(message)) if !message.is_close() => continue,
+                    _ => break,
+                },
+            };
+            if tx.send(Message::binary(data)).await.is_err() {
+                break;
+            }
+        }
+        tracing::debug!("the {channel:?} subscriber is closed");
+    }
+
+    pub fn send_logs(&self, items: &[LogItem]) {
+        self.send(Channel::Logs, &items);
+    }
+}
+
+impl StreamHandler for ConnectionsHandler {
+    fn path(&self) -> &'static str {
+        "connections"
+    }
+
+    fn low_power(&self) -> LowPowerPolicy {
+        LowPowerPolicy::Suspend
+    }
+
+    fn on_message(&self, text: String) {
+        let stream = IpcStream::global();
+        match serde_json::from_str::<RawConnections>(&text) {
+            Ok(raw) => {
+                let frame = stream.connections.lock().apply(raw);
+                stream.send(Channel::Connections, &frame);
+            }
+            Err(err) => tracing::trace!("invalid connections frame: {err}"),
+        }
+    }
+}
+
+impl StreamHandler for TrafficHandler {
+    fn path(&self) -> &'static str {
+        "traffic"
+    }
+
+    fn name(&self) -> &'static str {
+        "ipc::traffic"
+    }
+
+    fn low_power(&self) -> LowPowerPolicy {
+        LowPowerPolicy::Suspend
+    }
+
+    fn on_message(&self, text: String) {
+        match serde_json::from_str::<Traffic>(&text) {
+            Ok(traffic) => IpcStream::global().send(Channel::Traffic, &traffic),
+            Err(err) => tracing::trace!("invalid traffic frame: {err}"),
+        }
+    }
+}
+
+#[test]
+fn test_connections_delta() {
+    let raw = |json: &str| serde_json::from_str::<RawConnections>(json).unwrap();
+
+    let mut state = ConnectionsState::default();
+    state.apply(raw(r#"{"uploadTotal":1,"downloadTotal":2,"connections":[
+            {"id":"a","upload":1,"download":1,"rule":"Match"},
+            {"id":"b","upload":1,"download":1}]}"#));
+    let frame = state.apply(raw(r#"{"uploadTotal":3,"downloadTotal":4,"connections":[
+            {"id":"a","upload":5,"download":1,"rule":"Match"},
+            {"id":"c","upload":0,"download":0}]}"#));
+
+    match frame {
+        ConnectionsFrame::Delta {
+            seq,
+            added,
+            updated,
+            removed,
+            ..
+        } => {
+            assert_eq!(seq, 2);
+            assert_eq!(added.len(), 1);
+            assert_eq!(added[0].id, "c");
+            assert_eq!(updated.len(), 1);
+            assert_eq!(updated[0].upload, 5);
+            assert_eq!(removed, vec!["b".to_string()]);
+        }
+        _ => panic!("expected the delta frame"),
+    }
+
+    let frame = state.apply(raw(
+        r#"{"uploadTotal":3,"downloadTotal":4,"connections":null}"#,
+    ));
+    assert!(matches!(frame, ConnectionsFrame::Delta { removed, .. } if removed.len() == 2));
+    assert!(rmp_serde::to_vec_named(&state.full()).is_ok());
+}
@@ -0,0 +1,315 @@
+use super::storage::Storage;
+use crate::config::{get_inbound_password, Config};
+use anyhow::{bail, Result};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Serialize;
+use std::{net::IpAddr, process::Command};
+
+#[cfg_attr(target_os = "macos", allow(unused))]
+const RULE_NAME: &str = "Clash Nyanpasu LAN";
+const STORAGE_KEY: &str = "lan:firewall_ports";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LanAccessInfo {
+    pub allow_lan: bool,
+    /// the local ipv4 addresses reachable from the lan
+    pub ips: Vec<String>,
+    pub port: u16,
+    /// the users of the inbound auth, empty when the auth is disabled
+    pub auth_users: Vec<String>,
+    /// e.g. `http://192.168.1.2:7890`, rendered as a qr code by the frontend
+    /// with the first user when the auth is enabled
+    pub qr_payload: Option<String>,
+}
+
+fn allow_lan() -> bool {
+    Config::clash()
+        .latest()
+        .0
+        .get("allow-lan")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn mixed_port() -> u16 {
+    Config::verge()
+        .latest()
+        .verge_mixed_port
+        .unwrap_or(Config::clash().latest().get_mixed_port())
+}
+
+/// the inbound ports which should be reachable from the lan
+fn lan_ports() -> Vec<u16> {
+    let mut ports = vec![mixed_port()];
+
+    let clash = Config::clash();
+    let clash = clash.latest();
+    for key in ["port", "socks-port"] {
+        let port = clash.0.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as u16;
+        if port != 0 && !ports.contains(&port) {
+            ports.push(port);
+        }
+    }
+    ports
+}
+
+/// the local ipv4 addresses reachable from the lan, the primary one first
+pub fn lan_ips() -> Result<Vec<String>> {
+    let mut ips = local_ip_address::list_afinet_netifas()?
+        .into_iter()
+        .filter_map(|(_, ip)| match ip {
+            IpAddr::V4(ip) if !ip.is_loopback() && !ip.is_link_local() => Some(ip),
+            _ => None,
+        })
+        // 排除 tun 设备的地址
+        .filter(|ip| ip.octets()[..2] != [198, 18])
+        .map(|ip| ip.to_string())
+        .collect::<Vec<_>>();
+    ips.sort();
+    ips.dedup();
+
+    // 默认路由的地址排在前面
+    if let Ok(IpAddr::V4(primary)) = local_ip_address::local_ip() {
+        let primary = primary.to_string();
+        if let Some(index) = ips.iter().position(|ip| ip == &primary) {
+            ips.swap(0, index);
+        }
+    }
+    Ok(ips)
+}
+
+pub fn get_lan_access_info() -> Result<LanAccessInfo> {
+    let port = mixed_port();
+    let ips = lan_ips()?;
+
+    let auth_users = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        verge
+            .inbound_auth
+            .as_ref()
+            .filter(|auth| auth.enable)
+            .map(|auth| auth.users.clone())
+            .unwrap_or_default()
+    };
+    let credential = auth_users
+        .first()
+        .and_then(|user| {
+            let password = get_inbound_password(user).ok()?;
+            let encode = |s: &str| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string();
+            Some(format!("{}:{}@", encode(user), encode(&password)))
+        })
+        .unwrap_or_default();
+    let qr_payload = ips
+        .first()
+        .map(|ip| format!("http://{credential}{ip}:{port}"));
+
+    Ok(LanAccessInfo {
+        allow_lan: allow_lan(),
+        ips,
+        port,
+        auth_users,
+        qr_payload,
+    })
+}
+
+/// open the firewall for the lan ports when `allow-lan` is enabled
+/// and remove the rules when disabled
+pub fn update_firewall() -> Result<()> {
+    let opened = Storage::global()
+        .get_item::<Vec<u16>>(STORAGE_KEY)?
+        .unwrap_or_default();
+    let ports = match allow_lan() {
+        true => lan_ports(),
+        false => Vec::new(),
+    };
+
+    if opened == ports {
+        return Ok(());
+    }
+
+    if !opened.is_empty() {
+        close_ports(&opened)?;
+        Storage::global().remove_item(STORAGE_KEY)?;
+        tracing::info!("firewall rules removed for ports {opened:?}");
+    }
+    if !ports.is_empty() {
+        open_ports(&ports)?;
+        Storage::global().set_item(STORAGE_KEY, &ports)?;
+        tracing::info!("firewall rules added for ports {ports:?}");
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(target_os = "windows", allow(unused))]
+fn run(program: &str, args: &[String]) -> Result<()> {
+    let status = Command::new(program).args(args).status()?;
+    if !status.success() {
+        bail!("`{program}` failed with status {status}");
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn netsh(args: &[String]) -> Result<()> {
+    use deelevate::{PrivilegeLevel, Token};
+    use runas::Command as RunasCommand;
+
+    let level = Token::with_current_process()?.privilege_level()?;
+    let status = match level {
+        PrivilegeLevel::NotPrivileged => {
+            RunasCommand::new("netsh").args(args).show(false).status()?
+        }
+        _ => {
+            use std::os::windows::process::CommandExt;
+            Command::new("netsh")
+                .args(args)
+                .creation_flags(0x08000000)
+                .status()?
+        }
+    };
+    if !status.success() {
+        bail!("netsh failed with status {status}");
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn open_ports(ports: &[u16]) -> Result<()> {
+    let ports = ports
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    for protocol in ["TCP", "UDP"] {
+        netsh(&[
+            "advfirewall".into(),
+            "firewall".into(),
+            "add".into(),
+            "rule".into(),
+            format!("name={RULE_NAME}"),
+            "dir=in".into(),
+            "action=allow".into(),
+            format!("protocol={protocol}"),
+            format!("localport={ports}"),
+        ])?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn close_ports(_ports: &[u16]) -> Result<()> {
+    netsh(&[
+        "advfirewall".into(),
+        "firewall".into(),
+        "delete".into(),
+        "rule".into(),
+        format!("name={RULE_NAME}"),
+    ])
+}
+
+/// the application firewall of macos works with apps instead of ports
+#[cfg(target_os = "macos")]
+fn socketfilterfw(action: &str) -> Result<()> {
+    const FW: &str = "/usr/libexec/ApplicationFirewall/socketfilterfw";
+
+    let output = Command::new(FW).arg("--getglobalstate").output()?;
+    if !String::from_utf8_lossy(&output.stdout).contains("enabled") {
+        return Ok(());
+    }
+
+    let clash_core = { Config::verge().latest().clash_core.clone() };
+    let path = crate::utils::dirs::core_path(&clash_core.unwrap_or_default().to_string())?;
+    let path = crate::utils::dirs::path_to_str(&path)?;
+
+    let shell = match action {
+        "open" => format!("{FW} --add '{path}' && {FW} --unblockapp '{path}'"),
+        _ => format!("{FW} --remove '{path}'"),
+    };
+    run(
+        "osascript",
+        &[
+            "-e".into(),
+            format!("do shell script \"{shell}\" with administrator privileges"),
+        ],
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn open_ports(_ports: &[u16]) -> Result<()> {
+    socketfilterfw("open")
+}
+
+#[cfg(target_os = "macos")]
+fn close_ports(_ports: &[u16]) -> Result<()> {
+    socketfilterfw("close")
+}
+
+/// support ufw and firewalld, do nothing if there is no active firewall
+#[cfg(target_os = "linux")]
+fn linux_firewall() -> Option<&'static str> {
+    let active = |program: &str, args: &[&str], expect: &str| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .map_or(false, |output| {
+                String::from_utf8_lossy(&output.stdout).contains(expect)
+            })
+    };
+
+    if active("systemctl", &["is-active", "ufw"], "active") {
+        Some("ufw")
+    } else if active("systemctl", &["is-active", "firewalld"], "active") {
+        Some("firewalld")
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn linux_rules(ports: &[u16], open: bool) -> Result<()> {
+    let mut args = Vec::new();
+
+    match linux_firewall() {
+        Some("ufw") => {
+            for port in ports {
+                args.push("ufw".to_string());
+                if !open {
+                    args.push("delete".into());
+                }
+                args.extend([
+                    "allow".into(),
+                    format!("{port}"),
+                    "comment".into(),
+                    format!("'{RULE_NAME}'"),
+                ]);
+                args.push("&&".into());
+            }
+        }
+        Some(_) => {
+            let action = if open { "--add-port" } else { "--remove-port" };
+            for port in ports {
+                for protocol in ["tcp", "udp"] {
+                    args.push(format!("firewall-cmd {action}={port}/{protocol}"));
+                    args.push("&&".into());
+                }
+            }
+        }
+        None => return Ok(()),
+    }
+    args.pop();
+
+    run("pkexec", &["sh".into(), "-c".into(), args.join(" ")])
+}
+
+#[cfg(target_os = "linux")]
+fn open_ports(ports: &[u16]) -> Result<()> {
+    linux_rules(ports, true)
+}
+
+#[cfg(target_os = "linux")]
+fn close_ports(ports: &[u16]) -> Result<()> {
+    linux_rules(ports, false)
+}
@@ -0,0 +1,123 @@
+//! start the core on demand, for the users who use the proxy occasionally
+//! the mixed port is held by the app until the first connection comes in,
+//! then it is handed over to the core, the other clients connecting meanwhile need to retry
+use super::CoreManager;
+use crate::config::Config;
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::time::Duration;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+    time::sleep,
+};
+
+pub struct LazyCore {
+    cancel: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl LazyCore {
+    pub fn global() -> &'static LazyCore {
+        static LAZY_CORE: OnceCell<LazyCore> = OnceCell::new();
+
+        LAZY_CORE.get_or_init(|| LazyCore {
+            cancel: Mutex::new(None),
+        })
+    }
+
+    /// the tun traffic doesn't come from the mixed port
+    pub fn enabled() -> bool {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        verge.enable_lazy_core.unwrap_or(false)
+            && !verge.enable_tun_mode.unwrap_or(false)
+            && !verge.enable_service_mode.unwrap_or(false)
+    }
+
+    /// the core is not started yet
+    pub fn is_waiting(&self) -> bool {
+        self.cancel.lock().is_some()
+    }
+
+    /// listen on the mixed port instead of starting the core
+    pub fn wait(&'static self) -> Result<()> {
+        self.cancel();
+
+        let (port, allow_lan) = {
+            let clash = Config::clash();
+            let clash = clash.latest();
+            let allow_lan = clash.0.get("allow-lan").and_then(|v| v.as_bool());
+            (clash.get_mixed_port(), allow_lan.unwrap_or(false))
+        };
+        let ip = match allow_lan {
+            true => [0, 0, 0, 0],
+            false => [127, 0, 0, 1],
+        };
+        // 同步绑定，端口被占用时直接返回错误
+        let listener = std::net::TcpListener::bind((ip, port))?;
+        listener.set_nonblocking(true)?;
+
+        let (tx, rx) = oneshot::channel::<()>();
+        *self.cancel.lock() = Some(tx);
+        tracing::info!("the core will be started on the first connection to {port}");
+
+        tauri::async_runtime::spawn(async move {
+            let accepted = match TcpListener::from_std(listener) {
+                Ok(listener) => tokio::select! {
+                    accepted = listener.accept() => accepted,
+                    _ = rx => return,
+                },
+                Err(err) => Err(err),
+            };
+            // run_core 之前已经取消了
+            if self.cancel.lock().take().is_none() {
+                return;
+            }
+
+            tracing::info!("start the core on demand");
+            if let Err(err) = CoreManager::global().run_core().await {
+                tracing::error!("failed to start the core on demand: {err}");
+                return;
+            }
+
+            match accepted {
+                Ok((socket, _)) => {
+                    if let Err(err) = hand_over(socket, port).await {
+                        tracing::debug!("failed to hand over the first connection: {err}");
+                    }
+                }
+                Err(err) => tracing::warn!("failed to accept the connection: {err}"),
+            }
+        });
+        Ok(())
+    }
+
+    /// release the port, return true if it was waiting
+    pub fn cancel(&self) -> bool {
+        match self.cancel.lock().take() {
+            Some(tx) => {
+                let _ = tx.send(());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// forward the first connection after the core listens
+async fn hand_over(mut socket: TcpStream, port: u16) -> Result<()> {
+    let mut upstream = None;
+    for _ in 0..50 {
+        match TcpStream::connect(("127.0.0.1", port)).await {
+            Ok(stream) => {
+                upstream = Some(stream);
+                break;
+            }
+            Err(_) => sleep(Duration::from_millis(100)).await,
+        }
+    }
+    let mut upstream = upstream.ok_or(anyhow!("the core is not listening on {port}"))?;
+    tokio::io::copy_bidirectional(&mut socket, &mut upstream).await?;
+    Ok(())
+}
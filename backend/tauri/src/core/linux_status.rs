@@ -0,0 +1,314 @@
+#![cfg(target_os = "linux")]
+//! the tray and the status on the linux desktops
+//!
+//! the tray is an appindicator (status notifier item), libappindicator falls back to
+//! the legacy xembed tray by itself, the window is kept open if neither is available.
+//! the status and the toggles are exposed on the session bus for the desktop extensions:
+//! `busctl --user introspect moe.elaina.clash.nyanpasu /moe/elaina/clash/nyanpasu`
+use super::{
+    event_bus::{AppEvent, EventBus},
+    CoreManager,
+};
+use crate::{config::Config, feat, log_err};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use zbus::{dbus_interface, Connection, ConnectionBuilder};
+
+const BUS_NAME: &str = "moe.elaina.clash.nyanpasu";
+const OBJECT_PATH: &str = "/moe/elaina/clash/nyanpasu";
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+
+const MODES: [&str; 4] = ["rule", "global", "direct", "script"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayProtocol {
+    /// the status notifier host of kde, or gnome with the appindicator extension
+    AppIndicator,
+    /// the xembed system tray of the x11 desktops
+    Legacy,
+    /// e.g. gnome on wayland without the extension
+    Unavailable,
+}
+
+impl TrayProtocol {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TrayProtocol::AppIndicator => "app_indicator",
+            TrayProtocol::Legacy => "legacy",
+            TrayProtocol::Unavailable => "unavailable",
+        }
+    }
+}
+
+fn select_protocol(watcher: bool, x11: bool) -> TrayProtocol {
+    match (watcher, x11) {
+        (true, _) => TrayProtocol::AppIndicator,
+        (false, true) => TrayProtocol::Legacy,
+        (false, false) => TrayProtocol::Unavailable,
+    }
+}
+
+async fn has_watcher() -> zbus::Result<bool> {
+    let conn = Connection::session().await?;
+    let proxy = zbus::fdo::DBusProxy::new(&conn).await?;
+    Ok(proxy.name_has_owner(WATCHER.try_into()?).await?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionType {
+    X11,
+    /// no xembed tray, no global position of the windows, the shortcuts go through the portal
+    Wayland,
+    /// e.g. a tty, treated as x11
+    Unknown,
+}
+
+/// xwayland sets `DISPLAY` too, `XDG_SESSION_TYPE` is trusted first
+fn parse_session(session: Option<&str>, display: bool, wayland_display: bool) -> SessionType {
+    match session {
+        Some("x11") => SessionType::X11,
+        Some("wayland") => SessionType::Wayland,
+        Some(_) => SessionType::Unknown,
+        None if wayland_display => SessionType::Wayland,
+        None if display => SessionType::X11,
+        None => SessionType::Unknown,
+    }
+}
+
+pub fn session_type() -> SessionType {
+    static SESSION_TYPE: OnceCell<SessionType> = OnceCell::new();
+
+    *SESSION_TYPE.get_or_init(|| {
+        let session = std::env::var("XDG_SESSION_TYPE").ok();
+        let session = parse_session(
+            session.as_deref(),
+            std::env::var_os("DISPLAY").is_some(),
+            std::env::var_os("WAYLAND_DISPLAY").is_some(),
+        );
+        tracing::info!("the desktop session is {session:?}");
+        session
+    })
+}
+
+/// called in the setup, not in the async runtime
+fn detect() -> TrayProtocol {
+    let watcher = tauri::async_runtime::block_on(has_watcher()).unwrap_or_else(|err| {
+        tracing::warn!("failed to find the status notifier watcher: {err}");
+        false
+    });
+    let protocol = select_protocol(watcher, session_type() != SessionType::Wayland);
+    match protocol {
+        TrayProtocol::Unavailable => {
+            tracing::warn!("no tray on this desktop, install the appindicator extension for gnome")
+        }
+        _ => tracing::info!("the tray protocol is {}", protocol.as_str()),
+    }
+    protocol
+}
+
+/// how the app works on the current desktop, shown in the settings
+#[derive(Debug, Clone, Serialize)]
+pub struct DesktopSession {
+    pub session: SessionType,
+    pub tray: TrayProtocol,
+    /// `native` or `portal`
+    pub global_shortcuts: &'static str,
+    /// why the portal shortcuts are not working
+    pub shortcuts_error: Option<String>,
+    /// the window position could not be restored on wayland
+    pub window_position: bool,
+}
+
+pub fn desktop_session() -> DesktopSession {
+    let session = session_type();
+    let wayland = session == SessionType::Wayland;
+    DesktopSession {
+        session,
+        tray: LinuxStatus::global().tray_protocol(),
+        global_shortcuts: if wayland { "portal" } else { "native" },
+        shortcuts_error: super::portal_shortcuts::PortalShortcuts::global().error(),
+        window_position: !wayland,
+    }
+}
+
+pub struct LinuxStatus {
+    protocol: OnceCell<TrayProtocol>,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl LinuxStatus {
+    pub fn global() -> &'static LinuxStatus {
+        static LINUX_STATUS: OnceCell<LinuxStatus> = OnceCell::new();
+
+        LINUX_STATUS.get_or_init(|| LinuxStatus {
+            protocol: OnceCell::new(),
+            connection: Mutex::new(None),
+        })
+    }
+
+    pub fn tray_protocol(&self) -> TrayProtocol {
+        *self.protocol.get_or_init(detect)
+    }
+
+    /// detect the tray and serve the status on the session bus
+    pub fn start(&'static self) {
+        self.protocol.get_or_init(detect);
+
+        tauri::async_runtime::spawn(async move {
+            let conn = ConnectionBuilder::session()
+                .and_then(|builder| builder.name(BUS_NAME))
+                .and_then(|builder| builder.serve_at(OBJECT_PATH, StatusObject));
+            match conn {
+                Ok(builder) => match builder.build().await {
+                    Ok(conn) => *self.connection.lock() = Some(conn),
+                    Err(err) => tracing::warn!("failed to serve the status on dbus: {err}"),
+                },
+                Err(err) => tracing::warn!("failed to serve the status on dbus: {err}"),
+            }
+        });
+
+        EventBus::subscribe("linux_status", move |event| async move {
+            if let AppEvent::CoreState { .. } | AppEvent::ProfileChanged { .. } = event {
+                self.refresh();
+            }
+        });
+    }
+
+    /// emit `PropertiesChanged` for the extensions
+    pub fn refresh(&self) {
+        let Some(conn) = self.connection.lock().clone() else {
+            return;
+        };
+        tauri::async_runtime::spawn(async move {
+            log_err!(notify_changed(&conn).await);
+        });
+    }
+}
+
+async fn notify_changed(conn: &Connection) -> zbus::Result<()> {
+    let iface = conn
+        .object_server()
+        .interface::<_, StatusObject>(OBJECT_PATH)
+        .await?;
+    let ctxt = iface.signal_context();
+    let object = iface.get().await;
+    object.system_proxy_changed(ctxt).await?;
+    object.tun_mode_changed(ctxt).await?;
+    object.mode_changed(ctxt).await?;
+    object.core_running_changed(ctxt).await?;
+    object.current_profile_changed(ctxt).await?;
+    Ok(())
+}
+
+struct StatusObject;
+
+#[dbus_interface(name = "moe.elaina.clash.nyanpasu.Status1")]
+impl StatusObject {
+    #[dbus_interface(property)]
+    fn system_proxy(&self) -> bool {
+        let enable = { Config::verge().latest().enable_system_proxy };
+        enable.unwrap_or(false)
+    }
+
+    #[dbus_interface(property)]
+    fn set_system_proxy(&mut self, enable: bool) {
+        match enable {
+            true => feat::enable_system_proxy(),
+            false => feat::disable_system_proxy(),
+        }
+    }
+
+    #[dbus_interface(property)]
+    fn tun_mode(&self) -> bool {
+        let enable = { Config::verge().latest().enable_tun_mode };
+        enable.unwrap_or(false)
+    }
+
+    #[dbus_interface(property)]
+    fn set_tun_mode(&mut self, enable: bool) {
+        match enable {
+            true => feat::enable_tun_mode(),
+            false => feat::disable_tun_mode(),
+        }
+    }
+
+    /// `rule` `global` `direct` or `script`
+    #[dbus_interface(property)]
+    fn mode(&self) -> String {
+        let mode = { Config::clash().latest().0.get("mode").cloned() };
+        mode.and_then(|mode| mode.as_str().map(|mode| mode.to_lowercase()))
+            .unwrap_or("rule".into())
+    }
+
+    #[dbus_interface(property)]
+    fn set_mode(&mut self, mode: String) {
+        match MODES.contains(&mode.as_str()) {
+            true => feat::change_clash_mode(mode),
+            false => tracing::warn!("invalid clash mode `{mode}` from dbus"),
+        }
+    }
+
+    #[dbus_interface(property)]
+    fn core_running(&self) -> bool {
+        CoreManager::global().is_running()
+    }
+
+    /// the name of the current profile, empty if none
+    #[dbus_interface(property)]
+    fn current_profile(&self) -> String {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        profiles
+            .get_current()
+            .and_then(|uid| profiles.get_item(&uid).ok())
+            .and_then(|item| item.name.clone())
+            .unwrap_or_default()
+    }
+
+    #[dbus_interface(property)]
+    fn tray_protocol(&self) -> String {
+        LinuxStatus::global().tray_protocol().as_str().into()
+    }
+
+    fn show_window(&self) {
+        feat::open_dashboard();
+    }
+
+    fn toggle_system_proxy(&self) {
+        feat::toggle_system_proxy();
+    }
+
+    fn toggle_tun_mode(&self) {
+        feat::toggle_tun_mode();
+    }
+
+    fn restart_core(&self) {
+        feat::restart_clash_core();
+    }
+}
+
+#[test]
+fn test_select_protocol() {
+    assert_eq!(select_protocol(true, false), TrayProtocol::AppIndicator);
+    assert_eq!(select_protocol(true, true), TrayProtocol::AppIndicator);
+    assert_eq!(select_protocol(false, true), TrayProtocol::Legacy);
+    assert_eq!(select_protocol(false, false), TrayProtocol::Unavailable);
+}
+
+#[test]
+fn test_parse_session() {
+    assert_eq!(
+        parse_session(Some("wayland"), true, true),
+        SessionType::Wayland
+    );
+    assert_eq!(parse_session(Some("x11"), true, false), SessionType::X11);
+    assert_eq!(
+        parse_session(Some("tty"), false, false),
+        SessionType::Unknown
+    );
+    assert_eq!(parse_session(None, true, true), SessionType::Wayland);
+    assert_eq!(parse_session(None, true, false), SessionType::X11);
+}
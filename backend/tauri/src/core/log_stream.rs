@@ -0,0 +1,263 @@
+use super::{
+    handle,
+    ipc_stream::{Channel, IpcStream},
+    low_power::LowPower,
+    stream::{LowPowerPolicy, StreamHandler, StreamManager},
+};
+use crate::{
+    config::Config,
+    utils::{dirs, logging::RotatingFile},
+};
+use chrono::{Local, TimeZone};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::sleep;
+
+const BUFFER_LEN: usize = 1000;
+const FILE_MAX_SIZE: u64 = 5 * 1024 * 1024;
+const FILE_MAX_COUNT: usize = 5;
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    #[serde(alias = "warn")]
+    Warning,
+    Error,
+}
+
+impl LogLevel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LogItem {
+    /// unix timestamp in millis
+    pub time: i64,
+    pub level: LogLevel,
+    pub payload: String,
+}
+
+/// the frame sent by `/logs`
+#[derive(Debug, Deserialize)]
+struct RawLog {
+    #[serde(rename = "type")]
+    level: LogLevel,
+    payload: String,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct LogFilter {
+    /// minimum level
+    pub level: Option<LogLevel>,
+    /// case insensitive keyword
+    pub contains: Option<String>,
+    /// only logs after this timestamp (millis)
+    pub since: Option<i64>,
+    /// only return the latest n logs
+    pub limit: Option<usize>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, item: &LogItem) -> bool {
+        if let Some(level) = self.level {
+            if item.level < level {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if item.time <= since {
+                return false;
+            }
+        }
+        match self.contains.as_ref().filter(|s| !s.is_empty()) {
+            Some(keyword) => item
+                .payload
+                .to_lowercase()
+                .contains(&keyword.to_lowercase()),
+            None => true,
+        }
+    }
+}
+
+/// consume the `/logs` websocket of the core
+/// keep the latest logs in memory and only push the matched logs to the frontend
+pub struct LogStream {
+    buffer: Arc<Mutex<VecDeque<LogItem>>>,
+
+    /// the filter subscribed by the frontend
+    /// `None` means no one is listening
+    filter: Arc<Mutex<Option<LogFilter>>>,
+
+    /// matched logs waiting to be emitted
+    pending: Arc<Mutex<Vec<LogItem>>>,
+
+    /// all the logs waiting to be sent to the binary channel
+    binary_pending: Arc<Mutex<Vec<LogItem>>>,
+
+    writer: Arc<Mutex<Option<RotatingFile>>>,
+
+    running: AtomicBool,
+}
+
+impl LogStream {
+    pub fn global() -> &'static LogStream {
+        static LOG_STREAM: OnceCell<LogStream> = OnceCell::new();
+
+        LOG_STREAM.get_or_init(|| LogStream {
+            buffer: Arc::new(Mutex::new(VecDeque::with_capacity(BUFFER_LEN))),
+            filter: Arc::new(Mutex::new(None)),
+            pending: Arc::new(Mutex::new(Vec::new())),
+            binary_pending: Arc::new(Mutex::new(Vec::new())),
+            writer: Arc::new(Mutex::new(None)),
+            running: AtomicBool::new(false),
+        })
+    }
+
+    pub fn start(&'static self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let file = dirs::app_logs_dir()
+            .and_then(|dir| RotatingFile::new(dir.join("clash"), FILE_MAX_SIZE, FILE_MAX_COUNT));
+        match file {
+            Ok(file) => *self.writer.lock() = Some(file),
+            Err(err) => tracing::error!("failed to open the clash log file: {err}"),
+        }
+
+        StreamManager::global().spawn(self);
+
+        tauri::async_runtime::spawn(async move {
+            loop {
+                sleep(FLUSH_INTERVAL).await;
+                self.flush();
+            }
+        });
+    }
+
+    fn push(&self, item: LogItem) {
+        if let Some(writer) = self.writer.lock().as_mut() {
+            let time = Local
+                .timestamp_millis_opt(item.time)
+                .single()
+                .unwrap_or_else(Local::now)
+                .format("%Y-%m-%d %H:%M:%S");
+            let line = format!("{time} {} {}\n", item.level.as_str(), item.payload);
+            crate::log_err!(writer.write_all(line.as_bytes()));
+        }
+
+        // the window is hidden in the low power mode, the lines are still kept in the buffer
+        if let Some(filter) = self.filter.lock().as_ref() {
+            if filter.matches(&item) && !LowPower::global().is_active() {
+                self.pending.lock().push(item.clone());
+            }
+        }
+
+        if IpcStream::global().has_subscribers(Channel::Logs) {
+            self.binary_pending.lock().push(item.clone());
+        }
+
+        let mut buffer = self.buffer.lock();
+        if buffer.len() >= BUFFER_LEN {
+            buffer.pop_front();
+        }
+        buffer.push_back(item);
+    }
+
+    fn flush(&self) {
+        let items = std::mem::take(&mut *self.pending.lock());
+        if !items.is_empty() {
+            handle::Handle::emit("verge://clash-logs", items);
+        }
+
+        let items = std::mem::take(&mut *self.binary_pending.lock());
+        if !items.is_empty() {
+            IpcStream::global().send_logs(&items);
+        }
+    }
+
+    /// query the logs in memory
+    pub fn query(&self, filter: &LogFilter) -> Vec<LogItem> {
+        let buffer = self.buffer.lock();
+        let mut items = buffer
+            .iter()
+            .filter(|item| filter.matches(item))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if let Some(limit) = filter.limit {
+            let skip = items.len().saturating_sub(limit);
+            items.drain(..skip);
+        }
+        items
+    }
+
+    /// only the logs matched the filter will be pushed to the frontend
+    pub fn subscribe(&self, filter: LogFilter) {
+        *self.filter.lock() = Some(filter);
+        self.pending.lock().clear();
+    }
+
+    pub fn unsubscribe(&self) {
+        *self.filter.lock() = None;
+        self.pending.lock().clear();
+    }
+
+    pub fn clear(&self) {
+        self.buffer.lock().clear();
+        self.pending.lock().clear();
+    }
+}
+
+impl StreamHandler for LogStream {
+    fn path(&self) -> &'static str {
+        "logs"
+    }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let level = Config::clash()
+            .data()
+            .0
+            .get("log-level")
+            .and_then(|v| v.as_str())
+            .unwrap_or("info")
+            .to_owned();
+        vec![("level".into(), level)]
+    }
+
+    /// the log file needs every line, the frontend is only emitted to when visible
+    fn low_power(&self) -> LowPowerPolicy {
+        LowPowerPolicy::Keep
+    }
+
+    fn on_message(&self, text: String) {
+        match serde_json::from_str::<RawLog>(&text) {
+            Ok(raw) => self.push(LogItem {
+                time: Local::now().timestamp_millis(),
+                level: raw.level,
+                payload: raw.payload,
+            }),
+            Err(err) => tracing::trace!("invalid log frame: {err}"),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! save the power while the main window is hidden or minimized
+//! the streams only for the ui are suspended, the others are throttled, see `LowPowerPolicy`
+use crate::config::Config;
+use once_cell::sync::OnceCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::watch;
+
+pub struct LowPower {
+    /// whether the main window is hidden, minimized or destroyed
+    hidden: AtomicBool,
+    active: watch::Sender<bool>,
+}
+
+impl LowPower {
+    pub fn global() -> &'static LowPower {
+        static LOW_POWER: OnceCell<LowPower> = OnceCell::new();
+
+        LOW_POWER.get_or_init(|| LowPower {
+            // 启动时还没有窗口
+            hidden: AtomicBool::new(true),
+            active: watch::channel(false).0,
+        })
+    }
+
+    pub fn enabled() -> bool {
+        let enable = { Config::verge().latest().enable_low_power_mode };
+        enable.unwrap_or(true)
+    }
+
+    pub fn is_active(&self) -> bool {
+        *self.active.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.active.subscribe()
+    }
+
+    pub fn set_hidden(&self, hidden: bool) {
+        self.hidden.store(hidden, Ordering::SeqCst);
+        self.refresh();
+    }
+
+    /// called after the window state or the config changed
+    pub fn refresh(&self) {
+        let active = Self::enabled() && self.hidden.load(Ordering::SeqCst);
+        let changed = self.active.send_if_modified(|value| {
+            let changed = *value != active;
+            *value = active;
+            changed
+        });
+        if changed {
+            tracing::debug!("low power mode: {active}");
+        }
+    }
+}
@@ -0,0 +1,591 @@
+#![cfg(target_os = "macos")]
+//! the privileged helper on macos, a launchd daemon running the app binary as root
+//!
+//! it runs the core for the tun mode and changes the system proxy and dns,
+//! so the app never asks for the password again, and the core owned by the daemon
+//! keeps the tun working when the app restarts or crashes.
+//! `SMAppService` needs the daemon plist signed inside the bundle, so the daemon is
+//! installed by `launchctl bootstrap` with the administrator privileges once.
+//! the app bundle is writable by the user, so the cores are copied to a root-owned dir
+//! when installing, and their hashes are checked before every start.
+use super::{control::Response, core::core_args};
+use crate::{
+    config::{ClashCore, Config},
+    utils::dirs,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+    time::Duration,
+};
+use sysproxy::{Autoproxy, Sysproxy};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+    process::Child,
+    signal::unix::{signal, SignalKind},
+    sync::Mutex,
+};
+
+/// launch the binary as the helper
+pub const HELPER_ARG: &str = "--privileged-helper";
+
+const LABEL: &str = "moe.elaina.clash.nyanpasu.helper";
+const HELPER_PATH: &str = "/Library/PrivilegedHelperTools/moe.elaina.clash.nyanpasu.helper";
+const PLIST_PATH: &str = "/Library/LaunchDaemons/moe.elaina.clash.nyanpasu.helper.plist";
+const SOCKET_PATH: &str = "/var/run/moe.elaina.clash.nyanpasu.helper.sock";
+const LOG_PATH: &str = "/var/log/moe.elaina.clash.nyanpasu.helper.log";
+const CORE_LOG_PATH: &str = "/var/log/moe.elaina.clash.nyanpasu.core.log";
+const CORES_DIR: &str = "/Library/PrivilegedHelperTools/moe.elaina.clash.nyanpasu.cores";
+/// the sha256 of the copied cores by name
+const CORES_MANIFEST: &str = "cores.json";
+const PLIST_TEMPLATE: &str = include_str!("mac_helper.plist");
+
+const CORES: [ClashCore; 4] = [
+    ClashCore::ClashPremium,
+    ClashCore::ClashRs,
+    ClashCore::Mihomo,
+    ClashCore::MihomoAlpha,
+];
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HelperRequest {
+    Status,
+    /// keep the running core if `restart` is false and the config is the same
+    StartCore {
+        core: ClashCore,
+        config_dir: String,
+        config_file: String,
+        restart: bool,
+    },
+    StopCore,
+    SetProxy {
+        enable: bool,
+        host: String,
+        port: u16,
+        bypass: String,
+        /// the current network service if none
+        service: Option<String>,
+    },
+    SetAutoProxy {
+        enable: bool,
+        url: String,
+    },
+    /// empty `servers` means the dhcp ones
+    SetDns {
+        network: String,
+        servers: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct HelperStatus {
+    /// the pid of the core run by the helper
+    pub core_pid: Option<u32>,
+    pub config_file: Option<String>,
+}
+
+/// whether the core and the system settings go through the helper
+pub fn is_enabled() -> bool {
+    let enable = { Config::verge().latest().enable_service_mode };
+    enable.unwrap_or(false) && Path::new(PLIST_PATH).exists()
+}
+
+fn digest(path: &Path) -> Option<String> {
+    let data = std::fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(data)))
+}
+
+/// the cores next to the app
+fn bundle_cores() -> Result<Vec<(ClashCore, PathBuf)>> {
+    let mut cores = vec![];
+    for core in CORES {
+        let path = dirs::core_path(&core.to_string())?;
+        if path.exists() {
+            cores.push((core, path));
+        }
+    }
+    Ok(cores)
+}
+
+fn read_manifest() -> Result<HashMap<String, String>> {
+    let data = std::fs::read(Path::new(CORES_DIR).join(CORES_MANIFEST))
+        .context("the cores of the privileged helper are missing, please install it again")?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// the installed helper is a copy of the app and the cores,
+/// it needs to be installed again after updating either of them
+pub fn is_outdated() -> bool {
+    let current = std::env::current_exe().ok().and_then(|exe| digest(&exe));
+    if current.is_none() || current != digest(Path::new(HELPER_PATH)) {
+        return true;
+    }
+    let (Ok(manifest), Ok(cores)) = (read_manifest(), bundle_cores()) else {
+        return true;
+    };
+    cores
+        .iter()
+        .any(|(core, path)| manifest.get(&core.to_string()) != digest(path).as_ref())
+}
+
+/// send a request to the helper, blocking for a short time
+pub fn request(request: &HelperRequest) -> Result<Value> {
+    let stream = std::os::unix::net::UnixStream::connect(SOCKET_PATH)
+        .context("failed to connect to the privileged helper")?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut writer = &stream;
+    writer.write_all(format!("{}\n", serde_json::to_string(request)?).as_bytes())?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let response = serde_json::from_str::<Response>(&line)
+        .context("failed to parse the privileged helper response")?;
+    match response.ok {
+        true => Ok(response.data.unwrap_or_default()),
+        false => bail!(response.error.unwrap_or("unknown error".into())),
+    }
+}
+
+async fn request_async(req: HelperRequest) -> Result<Value> {
+    tokio::task::spawn_blocking(move || request(&req)).await?
+}
+
+pub async fn check_helper() -> Result<HelperStatus> {
+    let status = request_async(HelperRequest::Status).await?;
+    Ok(serde_json::from_value(status)?)
+}
+
+/// start the core by the helper
+/// `restart` is false when the app just launched, so the running core is taken over
+pub(super) async fn run_core_by_helper(config_file: &PathBuf, restart: bool) -> Result<()> {
+    if is_outdated() {
+        tracing::warn!("the privileged helper is outdated, please install it again");
+    }
+
+    let core = { Config::verge().latest().clash_core.clone() };
+    let config_dir = dirs::app_home_dir()?;
+    let request = HelperRequest::StartCore {
+        core: core.unwrap_or_default(),
+        config_dir: dirs::path_to_str(&config_dir)?.into(),
+        config_file: dirs::path_to_str(config_file)?.into(),
+        restart,
+    };
+    request_async(request).await?;
+    Ok(())
+}
+
+pub(super) async fn stop_core_by_helper() -> Result<()> {
+    request_async(HelperRequest::StopCore).await?;
+    Ok(())
+}
+
+pub fn set_system_proxy(proxy: &Sysproxy, service: Option<&str>) -> Result<()> {
+    request(&HelperRequest::SetProxy {
+        enable: proxy.enable,
+        host: proxy.host.clone(),
+        port: proxy.port,
+        bypass: proxy.bypass.clone(),
+        service: service.map(|service| service.to_string()),
+    })?;
+    Ok(())
+}
+
+pub fn set_auto_proxy(proxy: &Autoproxy) -> Result<()> {
+    request(&HelperRequest::SetAutoProxy {
+        enable: proxy.enable,
+        url: proxy.url.clone(),
+    })?;
+    Ok(())
+}
+
+pub fn set_dns(network: &str, servers: &[String]) -> Result<()> {
+    request(&HelperRequest::SetDns {
+        network: network.into(),
+        servers: servers.to_vec(),
+    })?;
+    Ok(())
+}
+
+/// `'it'\''s'`
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// run the shell script as root, the system asks for the password
+fn run_as_admin(script: &str) -> Result<()> {
+    let script = script.replace('\\', r"\\").replace('"', "\\\"");
+    let script = format!("do shell script \"{script}\" with administrator privileges");
+    let output = Command::new("osascript").args(["-e", &script]).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("failed to run as the administrator: {}", stderr.trim());
+    }
+    Ok(())
+}
+
+fn current_uid() -> Result<u32> {
+    let output = Command::new("id").arg("-u").output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().parse()?)
+}
+
+/// copy the app and the cores to the helper tools and load the daemon
+/// 该函数应该在协程或者线程中执行，避免授权弹窗阻塞主线程
+pub async fn install_helper() -> Result<()> {
+    let exe = std::env::current_exe()?;
+
+    let plist = PLIST_TEMPLATE
+        .replace("{label}", LABEL)
+        .replace("{program}", HELPER_PATH)
+        .replace("{arg}", HELPER_ARG)
+        .replace("{uid}", &current_uid()?.to_string())
+        .replace("{log}", LOG_PATH);
+    let temp = dirs::app_home_dir()?.join("helper.plist");
+    std::fs::write(&temp, plist)?;
+
+    // 哈希在复制前算好，root 复制的就是这些文件
+    let cores = bundle_cores()?;
+    let manifest = cores
+        .iter()
+        .filter_map(|(core, path)| Some((core.to_string(), digest(path)?)))
+        .collect::<HashMap<_, _>>();
+    let manifest_temp = dirs::app_home_dir()?.join("helper-cores.json");
+    std::fs::write(&manifest_temp, serde_json::to_vec(&manifest)?)?;
+
+    let mut script = vec![
+        // 重新安装时先卸载旧的
+        format!("launchctl bootout system/{LABEL} 2>/dev/null;"),
+        "mkdir -p /Library/PrivilegedHelperTools &&".into(),
+        format!(
+            "cp {} {HELPER_PATH} &&",
+            shell_quote(&exe.display().to_string())
+        ),
+        format!("chown root:wheel {HELPER_PATH} && chmod 755 {HELPER_PATH} &&"),
+        format!("rm -rf {CORES_DIR} && mkdir -m 755 {CORES_DIR} &&"),
+    ];
+    for (core, path) in cores.iter() {
+        script.push(format!(
+            "cp {} {CORES_DIR}/{core} && chmod 755 {CORES_DIR}/{core} &&",
+            shell_quote(&path.display().to_string())
+        ));
+    }
+    script.extend([
+        format!(
+            "cp {} {CORES_DIR}/{CORES_MANIFEST} && chmod 644 {CORES_DIR}/{CORES_MANIFEST} &&",
+            shell_quote(&manifest_temp.display().to_string())
+        ),
+        format!("chown -R root:wheel {CORES_DIR} &&"),
+        format!(
+            "cp {} {PLIST_PATH} &&",
+            shell_quote(&temp.display().to_string())
+        ),
+        format!("chown root:wheel {PLIST_PATH} && chmod 644 {PLIST_PATH} &&"),
+        format!("launchctl bootstrap system {PLIST_PATH}"),
+    ]);
+    let script = script.join(" ");
+    let result = tokio::task::spawn_blocking(move || run_as_admin(&script)).await?;
+    let _ = std::fs::remove_file(temp);
+    let _ = std::fs::remove_file(manifest_temp);
+    result?;
+
+    // 等 daemon 创建 socket
+    for _ in 0..20 {
+        if check_helper().await.is_ok() {
+            tracing::info!("the privileged helper is installed");
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    bail!("the privileged helper is installed but not running")
+}
+
+pub async fn uninstall_helper() -> Result<()> {
+    if let Err(err) = stop_core_by_helper().await {
+        tracing::debug!("failed to stop the core by the helper: {err}");
+    }
+
+    let script = [
+        format!("launchctl bootout system/{LABEL} 2>/dev/null;"),
+        format!("rm -f {PLIST_PATH} {HELPER_PATH} {SOCKET_PATH};"),
+        format!("rm -rf {CORES_DIR}"),
+    ]
+    .join(" ");
+    tokio::task::spawn_blocking(move || run_as_admin(&script)).await??;
+    tracing::info!("the privileged helper is uninstalled");
+    Ok(())
+}
+
+/// run as the helper if launched with `--privileged-helper`
+/// return the exit code, `None` means launching the app as usual
+pub fn run() -> Option<i32> {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some(HELPER_ARG) {
+        return None;
+    }
+
+    let mut uid = None;
+    while let Some(arg) = args.next() {
+        if arg == "--uid" {
+            uid = args.next().and_then(|uid| uid.parse::<u32>().ok());
+        }
+    }
+    let Some(uid) = uid else {
+        eprintln!("usage: {HELPER_ARG} --uid <uid>");
+        return Some(2);
+    };
+
+    let daemon = Arc::new(Daemon {
+        uid,
+        core: Mutex::new(None),
+    });
+    match tauri::async_runtime::block_on(daemon.serve()) {
+        Ok(_) => Some(0),
+        Err(err) => {
+            eprintln!("error: {err}");
+            Some(1)
+        }
+    }
+}
+
+struct RunningCore {
+    child: Child,
+    core: ClashCore,
+    config_file: String,
+}
+
+struct Daemon {
+    /// the user installed the helper, the only one allowed to connect
+    uid: u32,
+    core: Mutex<Option<RunningCore>>,
+}
+
+impl Daemon {
+    async fn serve(self: Arc<Self>) -> Result<()> {
+        let _ = std::fs::remove_file(SOCKET_PATH);
+        let listener = UnixListener::bind(SOCKET_PATH)?;
+        std::os::unix::fs::chown(SOCKET_PATH, Some(self.uid), None)?;
+        std::fs::set_permissions(SOCKET_PATH, std::fs::Permissions::from_mode(0o600))?;
+        eprintln!("the helper is listening on {SOCKET_PATH}");
+
+        // launchctl bootout 时把内核也停掉
+        let mut terminate = signal(SignalKind::terminate())?;
+
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => accepted?.0,
+                _ = terminate.recv() => break,
+            };
+
+            let allowed = stream
+                .peer_cred()
+                .map_or(false, |cred| cred.uid() == self.uid || cred.uid() == 0);
+            if !allowed {
+                eprintln!("reject the connection from another user");
+                continue;
+            }
+
+            let daemon = self.clone();
+            tokio::spawn(async move {
+                if let Err(err) = daemon.handle(stream).await {
+                    eprintln!("failed to handle the request: {err}");
+                }
+            });
+        }
+
+        self.stop_core().await?;
+        let _ = std::fs::remove_file(SOCKET_PATH);
+        Ok(())
+    }
+
+    async fn handle(&self, stream: UnixStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut line = String::new();
+        tokio::io::BufReader::new(reader)
+            .read_line(&mut line)
+            .await?;
+
+        let result = match serde_json::from_str::<HelperRequest>(&line) {
+            Ok(request) => self.execute(request).await,
+            Err(err) => Err(anyhow!("invalid request: {err}")),
+        };
+        let response = Response::from(result);
+        writer
+            .write_all(format!("{}\n", serde_json::to_string(&response)?).as_bytes())
+            .await?;
+        Ok(())
+    }
+
+    async fn execute(&self, request: HelperRequest) -> Result<Value> {
+        match request {
+            HelperRequest::Status => Ok(serde_json::to_value(self.status().await)?),
+            HelperRequest::StartCore {
+                core,
+                config_dir,
+                config_file,
+                restart,
+            } => {
+                self.start_core(core, &config_dir, &config_file, restart)
+                    .await?;
+                Ok(serde_json::to_value(self.status().await)?)
+            }
+            HelperRequest::StopCore => {
+                self.stop_core().await?;
+                Ok(Value::Null)
+            }
+            HelperRequest::SetProxy {
+                enable,
+                host,
+                port,
+                bypass,
+                service,
+            } => {
+                let proxy = Sysproxy {
+                    enable,
+                    host,
+                    port,
+                    bypass,
+                };
+                match service {
+                    Some(service) => proxy.set_system_proxy_on(&service)?,
+                    None => proxy.set_system_proxy()?,
+                }
+                Ok(Value::Null)
+            }
+            HelperRequest::SetAutoProxy { enable, url } => {
+                Autoproxy { enable, url }.set_auto_proxy()?;
+                Ok(Value::Null)
+            }
+            HelperRequest::SetDns { network, servers } => {
+                if servers
+                    .iter()
+                    .any(|server| server.parse::<std::net::IpAddr>().is_err())
+                {
+                    bail!("invalid dns servers {servers:?}");
+                }
+                let mut command = Command::new("networksetup");
+                command.arg("-setdnsservers").arg(&network);
+                match servers.is_empty() {
+                    true => command.arg("Empty"),
+                    false => command.args(&servers),
+                };
+                let status = command.status()?;
+                if !status.success() {
+                    bail!("failed to set the dns with status {status}");
+                }
+                Ok(Value::Null)
+            }
+        }
+    }
+
+    async fn status(&self) -> HelperStatus {
+        let mut core = self.core.lock().await;
+        // 内核自己退出了
+        if let Some(running) = core.as_mut() {
+            if !matches!(running.child.try_wait(), Ok(None)) {
+                *core = None;
+            }
+        }
+        HelperStatus {
+            core_pid: core.as_ref().and_then(|running| running.child.id()),
+            config_file: core.as_ref().map(|running| running.config_file.clone()),
+        }
+    }
+
+    async fn start_core(
+        &self,
+        core: ClashCore,
+        config_dir: &str,
+        config_file: &str,
+        restart: bool,
+    ) -> Result<()> {
+        // 内核以 root 运行，只接受该用户自己的配置目录
+        let config_dir = Path::new(config_dir).canonicalize()?;
+        let config_path = Path::new(config_file).canonicalize()?;
+        if std::fs::metadata(&config_dir)?.uid() != self.uid {
+            bail!("the config dir is not owned by the user");
+        }
+        if !config_path.starts_with(&config_dir) {
+            bail!("the config file is not in the config dir");
+        }
+
+        // app 重新打开时接管正在运行的内核，tun 不会断开
+        if !restart && self.status().await.core_pid.is_some() {
+            let running = self.core.lock().await;
+            if let Some(running) = running.as_ref() {
+                if running.core == core && running.config_file == config_file {
+                    return Ok(());
+                }
+            }
+        }
+        self.stop_core().await?;
+
+        let program = verified_core(&core)?;
+        let config_dir = dirs::path_to_str(&config_dir)?;
+        let config_path = dirs::path_to_str(&config_path)?;
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(CORE_LOG_PATH)?;
+        let child = tokio::process::Command::new(program)
+            .args(core_args(&core, config_dir, config_path))
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .spawn()?;
+        eprintln!("the core `{core}` is started, pid {:?}", child.id());
+
+        *self.core.lock().await = Some(RunningCore {
+            child,
+            core,
+            config_file: config_file.into(),
+        });
+        Ok(())
+    }
+
+    async fn stop_core(&self) -> Result<()> {
+        if let Some(mut running) = self.core.lock().await.take() {
+            running.child.kill().await?;
+            eprintln!("the core is stopped");
+        }
+        Ok(())
+    }
+}
+
+/// the core copied when installing, root-owned and not changed since
+fn verified_core(core: &ClashCore) -> Result<PathBuf> {
+    let manifest = read_manifest()?;
+    let expected = manifest.get(&core.to_string()).ok_or(anyhow!(
+        "the core `{core}` is not installed to the privileged helper"
+    ))?;
+
+    let program = Path::new(CORES_DIR).join(core.to_string());
+    let meta = std::fs::symlink_metadata(&program)?;
+    if !meta.is_file() || meta.uid() != 0 || meta.mode() & 0o022 != 0 {
+        bail!("the core `{core}` of the privileged helper is not owned by root");
+    }
+    if digest(&program).as_ref() != Some(expected) {
+        bail!("the core `{core}` of the privileged helper is modified, please install it again");
+    }
+    Ok(program)
+}
+
+#[test]
+fn test_quote() {
+    assert_eq!(
+        shell_quote("/Applications/Clash Nyanpasu.app"),
+        "'/Applications/Clash Nyanpasu.app'"
+    );
+    assert_eq!(shell_quote("it's"), r"'it'\''s'");
+}
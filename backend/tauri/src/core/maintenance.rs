@@ -0,0 +1,181 @@
+//! the periodic cleanup of the files the app leaves behind
+//!
+//! the app logs and the core logs older than `auto_log_clean`, the stale profile
+//! snapshots, and the download dirs of the core updates interrupted by a crash or a kill.
+use super::{trash, updater::TEMP_PREFIX};
+use crate::{
+    config::Config,
+    utils::{dirs, logging},
+};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// the updates finish in minutes, the older dirs are orphaned
+const TEMP_MAX_AGE: Duration = Duration::from_secs(24 * 3600);
+
+/// the reclaimed bytes of each kind
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaintenanceReport {
+    /// unix timestamp
+    pub time: i64,
+    pub app_logs: u64,
+    pub core_logs: u64,
+    pub snapshots: u64,
+    pub temp_dirs: u64,
+    /// the removed files and dirs
+    pub removed: usize,
+}
+
+impl MaintenanceReport {
+    pub fn total(&self) -> u64 {
+        self.app_logs + self.core_logs + self.snapshots + self.temp_dirs
+    }
+}
+
+static LAST_REPORT: Lazy<Mutex<Option<MaintenanceReport>>> = Lazy::new(|| Mutex::new(None));
+
+/// the report of the last run since the app started
+pub fn last_report() -> Option<MaintenanceReport> {
+    LAST_REPORT.lock().clone()
+}
+
+pub fn run() -> Result<MaintenanceReport> {
+    let minutes = { Config::verge().latest().auto_log_clean.unwrap_or(0) };
+    let mut report = MaintenanceReport {
+        time: chrono::Local::now().timestamp(),
+        ..Default::default()
+    };
+
+    // 0 为不清理日志
+    if minutes > 0 {
+        let max_age = Duration::from_secs(minutes as u64 * 60);
+        let logs_dir = dirs::app_logs_dir()?;
+        (report.app_logs, report.removed) =
+            prune_files(&logs_dir, max_age, |name| name.ends_with(".log"));
+        let (bytes, removed) = prune_files(&logs_dir.join("clash"), max_age, |name| {
+            name.ends_with(".log")
+        });
+        report.core_logs = bytes;
+        report.removed += removed;
+    }
+
+    let trash_dir = dirs::app_trash_dir()?;
+    let before = dir_size(&trash_dir);
+    let count = fs::read_dir(&trash_dir).map(|dir| dir.count()).unwrap_or(0);
+    trash::cleanup()?;
+    report.snapshots = before.saturating_sub(dir_size(&trash_dir));
+    report.removed +=
+        count.saturating_sub(fs::read_dir(&trash_dir).map(|dir| dir.count()).unwrap_or(0));
+
+    let (bytes, removed) = prune_temp_dirs(&std::env::temp_dir());
+    report.temp_dirs = bytes;
+    report.removed += removed;
+
+    tracing::info!(
+        "maintenance done, {} files removed, {} bytes reclaimed",
+        report.removed,
+        report.total()
+    );
+    *LAST_REPORT.lock() = Some(report.clone());
+    Ok(report)
+}
+
+fn is_expired(path: &Path, max_age: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| SystemTime::now().duration_since(time).ok())
+        .is_some_and(|age| age > max_age)
+}
+
+/// remove the matched files older than `max_age`, return the bytes and the count
+fn prune_files(dir: &Path, max_age: Duration, matches: impl Fn(&str) -> bool) -> (u64, usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    let mut result = (0, 0);
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        if !path.is_file() || !matches(&name.to_string_lossy()) || !is_expired(&path, max_age) {
+            continue;
+        }
+        // 正在写的日志不删
+        if logging::is_active_file(&path) {
+            continue;
+        }
+        let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+        match fs::remove_file(&path) {
+            Ok(_) => {
+                tracing::debug!("delete the expired file `{}`", path.display());
+                result.0 += size;
+                result.1 += 1;
+            }
+            Err(err) => tracing::warn!("failed to delete `{}`: {err}", path.display()),
+        }
+    }
+    result
+}
+
+fn prune_temp_dirs(temp: &Path) -> (u64, usize) {
+    let Ok(entries) = fs::read_dir(temp) else {
+        return (0, 0);
+    };
+    let mut result = (0, 0);
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        if !name.to_string_lossy().starts_with(TEMP_PREFIX)
+            || !path.is_dir()
+            || !is_expired(&path, TEMP_MAX_AGE)
+        {
+            continue;
+        }
+        let size = dir_size(&path);
+        match fs::remove_dir_all(&path) {
+            Ok(_) => {
+                tracing::debug!("delete the orphaned dir `{}`", path.display());
+                result.0 += size;
+                result.1 += 1;
+            }
+            Err(err) => tracing::warn!("failed to delete `{}`: {err}", path.display()),
+        }
+    }
+    result
+}
+
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|meta| meta.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[test]
+fn test_prune_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("old.log"), "12345").unwrap();
+    fs::write(dir.path().join("keep.txt"), "12345").unwrap();
+    std::thread::sleep(Duration::from_millis(20));
+
+    let (bytes, removed) = prune_files(dir.path(), Duration::from_millis(10), |name| {
+        name.ends_with(".log")
+    });
+    assert_eq!((bytes, removed), (5, 1));
+    assert!(dir.path().join("keep.txt").exists());
+    assert_eq!(dir_size(dir.path()), 5);
+}
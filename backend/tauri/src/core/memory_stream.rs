@@ -0,0 +1,120 @@
+use super::{
+    handle,
+    stream::{LowPowerPolicy, StreamHandler, StreamManager},
+};
+use crate::config::Config;
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// the core pushes a frame every second, keep the latest 10 minutes
+const HISTORY_LEN: usize = 600;
+const MB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MemorySample {
+    /// unix timestamp in millis
+    #[serde(default)]
+    pub time: i64,
+    pub inuse: u64,
+    pub oslimit: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryAlert {
+    pub inuse: u64,
+    /// bytes
+    pub threshold: u64,
+}
+
+/// consume the `/memory` websocket of the core (only for Clash Meta)
+pub struct MemoryStream {
+    history: Arc<Mutex<VecDeque<MemorySample>>>,
+
+    /// avoid alerting on every frame
+    /// reset after the usage drops below 90% of the threshold
+    alerted: AtomicBool,
+}
+
+impl MemoryStream {
+    pub fn global() -> &'static MemoryStream {
+        static MEMORY_STREAM: OnceCell<MemoryStream> = OnceCell::new();
+
+        MEMORY_STREAM.get_or_init(|| MemoryStream {
+            history: Arc::new(Mutex::new(VecDeque::with_capacity(HISTORY_LEN))),
+            alerted: AtomicBool::new(false),
+        })
+    }
+
+    pub fn start(&'static self) {
+        StreamManager::global().spawn(self);
+    }
+
+    pub fn history(&self) -> Vec<MemorySample> {
+        self.history.lock().iter().cloned().collect()
+    }
+
+    fn push(&self, sample: MemorySample) {
+        self.check_threshold(sample.inuse);
+
+        let mut history = self.history.lock();
+        if history.len() >= HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(sample.clone());
+        drop(history);
+
+        handle::Handle::emit("verge://clash-memory", sample);
+    }
+
+    fn check_threshold(&self, inuse: u64) {
+        let threshold = { Config::verge().latest().memory_alert_threshold };
+        let threshold = match threshold {
+            Some(mb) if mb > 0 => mb * MB,
+            _ => return,
+        };
+
+        if inuse > threshold {
+            if !self.alerted.swap(true, Ordering::SeqCst) {
+                tracing::warn!("core memory usage {}MB exceeds the threshold", inuse / MB);
+                handle::Handle::emit("verge://memory-alert", MemoryAlert { inuse, threshold });
+            }
+        } else if inuse < threshold / 10 * 9 {
+            self.alerted.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+impl StreamHandler for MemoryStream {
+    fn path(&self) -> &'static str {
+        "memory"
+    }
+
+    /// keep alerting, but less often
+    fn low_power(&self) -> LowPowerPolicy {
+        LowPowerPolicy::Throttle(Duration::from_secs(10))
+    }
+
+    fn on_message(&self, text: String) {
+        match serde_json::from_str::<MemorySample>(&text) {
+            Ok(mut sample) => {
+                // the first frame is always 0
+                if sample.inuse == 0 {
+                    return;
+                }
+                sample.time = Local::now().timestamp_millis();
+                self.push(sample);
+            }
+            Err(err) => tracing::trace!("invalid memory frame: {err}"),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! an optional prometheus `/metrics` endpoint on localhost
+
+use super::{
+    clash_api,
+    tasks::{task::TaskRunResult, TaskManager},
+    traffic_history::TrafficHistory,
+    CoreManager,
+};
+use crate::{config::Config, utils::server::LocalServer};
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use std::fmt::Write;
+use warp::Filter;
+
+pub const DEFAULT_PORT: u16 = 33333;
+
+pub struct Metrics {
+    server: LocalServer,
+}
+
+impl Metrics {
+    pub fn global() -> &'static Metrics {
+        static METRICS: OnceCell<Metrics> = OnceCell::new();
+
+        METRICS.get_or_init(|| Metrics {
+            server: LocalServer::default(),
+        })
+    }
+
+    /// apply the latest config, stop the server if disabled
+    pub async fn restart(&self) -> Result<()> {
+        self.stop().await;
+
+        let (enable, port) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_metrics.unwrap_or(false),
+                verge.metrics_port.unwrap_or(DEFAULT_PORT),
+            )
+        };
+        if !enable {
+            return Ok(());
+        }
+
+        let route = warp::path!("metrics").and(warp::get()).then(|| async {
+            warp::reply::with_header(render().await, "content-type", "text/plain; version=0.0.4")
+        });
+        let addr = self
+            .server
+            .start(|rx| {
+                warp::serve(route).try_bind_with_graceful_shutdown(([127, 0, 0, 1], port), async {
+                    rx.await.ok();
+                })
+            })
+            .await?;
+
+        tracing::info!("metrics are exported on http://{addr}/metrics");
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        if self.server.stop().await {
+            tracing::info!("metrics exporter is stopped");
+        }
+    }
+}
+
+type Labels<'a> = Vec<(&'a str, String)>;
+
+/// the prometheus text format
+#[derive(Default)]
+struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    fn metric(&mut self, name: &str, kind: &str, help: &str, samples: &[(Labels, f64)]) {
+        if samples.is_empty() {
+            return;
+        }
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+        for (labels, value) in samples {
+            let labels = labels
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+                .collect::<Vec<_>>();
+            match labels.is_empty() {
+                true => writeln!(self.out, "{name} {value}"),
+                false => writeln!(self.out, "{name}{{{}}} {value}", labels.join(",")),
+            }
+            .ok();
+        }
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.metric(name, "gauge", help, &[(vec![], value)]);
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// collected on every scrape, the metrics of the core are skipped if it is down
+async fn render() -> String {
+    let mut writer = MetricsWriter::default();
+    let running = CoreManager::global().is_running();
+    writer.gauge(
+        "nyanpasu_core_up",
+        "Whether the clash core is running.",
+        running as u8 as f64,
+    );
+
+    if let Some(traffic) = TrafficHistory::global().latest().filter(|_| running) {
+        writer.gauge(
+            "nyanpasu_traffic_upload_bytes_per_second",
+            "The current upload rate.",
+            traffic.up as f64,
+        );
+        writer.gauge(
+            "nyanpasu_traffic_download_bytes_per_second",
+            "The current download rate.",
+            traffic.down as f64,
+        );
+    }
+
+    if running {
+        if let Ok(res) = clash_api::get_connections().await {
+            writer.metric(
+                "nyanpasu_traffic_upload_bytes_total",
+                "counter",
+                "The uploaded bytes since the core started.",
+                &[(vec![], res.upload_total as f64)],
+            );
+            writer.metric(
+                "nyanpasu_traffic_download_bytes_total",
+                "counter",
+                "The downloaded bytes since the core started.",
+                &[(vec![], res.download_total as f64)],
+            );
+            writer.gauge(
+                "nyanpasu_connections",
+                "The active connections.",
+                res.connections.map_or(0, |list| list.len()) as f64,
+            );
+        }
+
+        if let Ok(res) = clash_api::get_proxies().await {
+            let mut groups = res
+                .proxies
+                .into_values()
+                .filter_map(|item| {
+                    let now = item.now?;
+                    Some((vec![("group", item.name), ("node", now)], 1.0))
+                })
+                .collect::<Vec<_>>();
+            groups.sort_by(|a, b| a.0.cmp(&b.0));
+            writer.metric(
+                "nyanpasu_group_selected",
+                "gauge",
+                "The selected node of the group.",
+                &groups,
+            );
+        }
+    }
+
+    let tasks = TaskManager::global().read().list();
+    let runs = tasks
+        .iter()
+        .filter_map(|task| task.last_run().map(|run| (task.name.clone(), run.clone())))
+        .collect::<Vec<_>>();
+    writer.metric(
+        "nyanpasu_task_last_run_timestamp_seconds",
+        "gauge",
+        "The time of the last run of the task.",
+        &runs
+            .iter()
+            .map(|(name, (time, _))| (vec![("task", name.clone())], *time as f64))
+            .collect::<Vec<_>>(),
+    );
+    writer.metric(
+        "nyanpasu_task_last_run_success",
+        "gauge",
+        "Whether the last run of the task succeeded.",
+        &runs
+            .iter()
+            .map(|(name, (_, result))| {
+                let ok = matches!(result, TaskRunResult::Ok);
+                (vec![("task", name.clone())], ok as u8 as f64)
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    let profiles = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        profiles
+            .get_items()
+            .into_iter()
+            .flatten()
+            .filter(|item| item.url.is_some())
+            .filter_map(|item| {
+                let labels = vec![
+                    ("uid", item.uid.clone()?),
+                    ("name", item.name.clone().unwrap_or_default()),
+                ];
+                Some((labels, item.updated? as f64))
+            })
+            .collect::<Vec<_>>()
+    };
+    writer.metric(
+        "nyanpasu_profile_updated_timestamp_seconds",
+        "gauge",
+        "The time of the last update of the remote profile.",
+        &profiles,
+    );
+
+    writer.out
+}
+
+#[test]
+fn test_metrics_writer() {
+    let mut writer = MetricsWriter::default();
+    writer.gauge("nyanpasu_core_up", "Whether the core is running.", 1.0);
+    writer.metric(
+        "nyanpasu_group_selected",
+        "gauge",
+        "The selected node.",
+        &[(
+            vec![("group", "Proxy".into()), ("node", "HK \"01\"".into())],
+            1.0,
+        )],
+    );
+
+    assert_eq!(
+        writer.out,
+        "# HELP nyanpasu_core_up Whether the core is running.\n\
+         # TYPE nyanpasu_core_up gauge\n\
+         nyanpasu_core_up 1\n\
+         # HELP nyanpasu_group_selected The selected node.\n\
+         # TYPE nyanpasu_group_selected gauge\n\
+         nyanpasu_group_selected{group=\"Proxy\",node=\"HK \\\"01\\\"\"} 1\n"
+    );
+}
@@ -1,14 +1,77 @@
+pub mod app_updater;
+pub mod artifact_cache;
+pub mod automation;
+pub mod benchmark;
+pub mod cache_db;
 pub mod clash_api;
+pub mod clipboard;
+pub mod control;
+pub mod control_api;
+pub mod controller_proxy;
 mod core;
+pub mod core_archive;
+pub mod country_stats;
+pub mod crash;
+pub mod deep_link;
+pub mod diagnostic;
+pub mod doctor;
+pub mod doh;
+pub mod editor;
+pub mod event_bus;
+pub mod event_hook;
+pub mod export;
+pub mod failover;
+pub mod flatten;
 pub mod handle;
 pub mod hotkey;
+pub mod http;
+pub mod importer;
+pub mod ipc_stream;
+pub mod lan;
+pub mod lazy_core;
+pub mod linux_status;
+pub mod log_stream;
 pub mod logger;
+pub mod low_power;
+pub mod mac_helper;
+pub mod maintenance;
 pub mod manager;
+pub mod memory_stream;
+pub mod metrics;
+pub mod network;
+pub mod node_info;
+pub mod notification;
+pub mod onboarding;
+pub mod pac;
+pub mod pause;
+pub mod portal_shortcuts;
+pub mod power;
+pub mod preview;
+pub mod process;
+pub mod profile_hooks;
+pub mod proxy_env;
+pub mod query;
+pub mod remote;
+pub mod repair;
+pub mod rule_set;
+pub mod selection;
+pub mod snapshot;
 pub mod storage;
+pub mod stream;
+pub mod sync;
+pub mod sys_dns;
 pub mod sysopt;
+pub mod systemd;
 pub mod tasks;
+pub mod throttle;
+pub mod traffic_history;
+pub mod trash;
 pub mod tray;
+pub mod tun;
 pub mod updater;
+pub mod web_ui;
+pub mod webdav;
+pub mod win_autostart;
 pub mod win_service;
 pub mod win_uwp;
 pub use self::core::*;
@@ -0,0 +1,248 @@
+use super::{
+    automation::Automation,
+    event_bus::{AppEvent, EventBus},
+    handle,
+    sysopt::Sysopt,
+    CoreManager,
+};
+use crate::{
+    config::{Config, NetworkAction},
+    feat, log_err,
+};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::sleep;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const CONNECTIVITY_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Connectivity {
+    #[default]
+    Unknown,
+    Online,
+    /// the request is redirected to a login page
+    CaptivePortal,
+    Offline,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct NetworkState {
+    /// the network service on macos, the local address of the default route on others
+    pub network: Option<String>,
+    pub ssid: Option<String>,
+    /// the interfaces with an ipv4 address
+    pub interfaces: Vec<String>,
+    pub connectivity: Connectivity,
+}
+
+impl NetworkState {
+    /// the condition of the network rules and the automation triggers, `None` matches any
+    pub fn matches(&self, ssid: Option<&String>, network: Option<&String>) -> bool {
+        ssid.map_or(true, |ssid| self.ssid.as_ref() == Some(ssid))
+            && network.map_or(true, |network| self.network.as_ref() == Some(network))
+    }
+
+    fn same_network(&self, other: &NetworkState) -> bool {
+        self.network == other.network
+            && self.ssid == other.ssid
+            && self.interfaces == other.interfaces
+    }
+}
+
+/// watch the network changes and run the actions configured in `network_rules`
+pub struct NetworkMonitor {
+    state: Arc<Mutex<NetworkState>>,
+
+    running: AtomicBool,
+}
+
+impl NetworkMonitor {
+    pub fn global() -> &'static NetworkMonitor {
+        static NETWORK_MONITOR: OnceCell<NetworkMonitor> = OnceCell::new();
+
+        NETWORK_MONITOR.get_or_init(|| NetworkMonitor {
+            state: Arc::new(Mutex::new(NetworkState::default())),
+            running: AtomicBool::new(false),
+        })
+    }
+
+    pub fn state(&self) -> NetworkState {
+        self.state.lock().clone()
+    }
+
+    pub fn start(&'static self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        tauri::async_runtime::spawn(async move {
+            let mut initialized = false;
+
+            loop {
+                let mut current = match tokio::task::spawn_blocking(snapshot).await {
+                    Ok(current) => current,
+                    Err(_) => {
+                        sleep(CHECK_INTERVAL).await;
+                        continue;
+                    }
+                };
+                let previous = self.state();
+
+                if !initialized || !current.same_network(&previous) {
+                    current.connectivity = check_connectivity().await;
+                    *self.state.lock() = current.clone();
+
+                    if initialized {
+                        tracing::info!(
+                            "network changed: {:?} -> {:?}",
+                            previous.network,
+                            current.network
+                        );
+                        self.react(&current).await;
+                        EventBus::publish(AppEvent::NetworkChanged { previous, current });
+                    }
+                    initialized = true;
+                }
+
+                sleep(CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn react(&self, current: &NetworkState) {
+        let rules = { Config::verge().latest().network_rules.clone() };
+        let rules = rules
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|rule| current.matches(rule.ssid.as_ref(), None))
+            .collect::<Vec<_>>();
+
+        let mut disable_proxy = false;
+
+        for rule in rules {
+            tracing::debug!("run network action {:?}", rule.action);
+
+            match rule.action {
+                NetworkAction::ReapplySystemProxy => {
+                    log_err!(Sysopt::global().update_sysproxy());
+                }
+                NetworkAction::RestartCore => {
+                    log_err!(CoreManager::global().run_core().await);
+                    handle::Handle::refresh_clash();
+                }
+                NetworkAction::SwitchProfile { uid } => {
+                    let current = { Config::profiles().latest().get_current() };
+                    if current.as_ref() != Some(&uid) {
+                        log_err!(feat::switch_profile(uid).await);
+                    }
+                }
+                NetworkAction::DisableProxy => disable_proxy = true,
+            }
+        }
+
+        // 进入受信任的网络时暂停系统代理，离开后恢复
+        log_err!(Sysopt::global().pause_sysproxy(disable_proxy));
+
+        // 自动化规则的网络触发条件也在这里处理
+        Automation::global().on_network_changed(current).await;
+    }
+}
+
+fn snapshot() -> NetworkState {
+    let mut interfaces = local_ip_address::list_afinet_netifas()
+        .map(|list| {
+            list.into_iter()
+                .filter(|(_, ip)| ip.is_ipv4() && !ip.is_loopback())
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    interfaces.sort();
+    interfaces.dedup();
+
+    NetworkState {
+        network: sysproxy::current_network().ok(),
+        ssid: current_ssid(),
+        interfaces,
+        connectivity: Connectivity::Unknown,
+    }
+}
+
+/// the default route has a local address
+pub fn is_network_up() -> bool {
+    local_ip_address::local_ip().is_ok()
+}
+
+async fn check_connectivity() -> Connectivity {
+    let client = match reqwest::ClientBuilder::new()
+        .no_proxy()
+        .timeout(Duration::from_secs(5))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+    {
+        Ok(client) => client,
+        Err(_) => return Connectivity::Unknown,
+    };
+
+    match client.get(CONNECTIVITY_URL).send().await {
+        Ok(response) if response.status().as_u16() == 204 => Connectivity::Online,
+        Ok(_) => Connectivity::CaptivePortal,
+        Err(_) => Connectivity::Offline,
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command.args(args);
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000);
+    }
+
+    let output = command.output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "macos")]
+fn current_ssid() -> Option<String> {
+    // Current Wi-Fi Network: xxx
+    let output = command_output("networksetup", &["-getairportnetwork", "en0"])?;
+    let (_, ssid) = output.split_once("Network:")?;
+    Some(ssid.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(target_os = "windows")]
+fn current_ssid() -> Option<String> {
+    let output = command_output("netsh", &["wlan", "show", "interfaces"])?;
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        match key.trim() == "SSID" {
+            true => Some(value.trim().to_string()).filter(|s| !s.is_empty()),
+            false => None,
+        }
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn current_ssid() -> Option<String> {
+    // yes:xxx
+    let output = command_output("nmcli", &["-t", "-f", "active,ssid", "dev", "wifi"])?;
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("yes:"))
+        .map(|ssid| ssid.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
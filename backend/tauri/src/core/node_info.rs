@@ -0,0 +1,394 @@
+//! the country and the asn of the nodes
+//!
+//! the entry is the resolved server of the node, looked up in the local mmdb files.
+//! the server is resolved by the dns of the core or the doh first, the system dns returns
+//! the fake ip under the tun.
+//! the exit is queried through the node by the exit probe, a hidden group and a local
+//! listener injected into the config, see `enhance/listener.rs`. the results are cached
+//! by the node name, and looked up again after the server changes.
+
+use super::{clash_api, doh};
+use crate::{
+    config::{Config, EXIT_PROBE},
+    utils::dirs,
+};
+use anyhow::{anyhow, bail, Result};
+use futures_util::{stream, StreamExt};
+use maxminddb::{geoip2, Reader};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::{collections::HashMap, net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
+use tokio::net::lookup_host;
+
+/// the entries are looked up again after an hour, the server could be a ddns
+const ENTRY_TTL: i64 = 3600;
+const CONCURRENCY: usize = 16;
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// return the ip as plain text
+const IP_ECHO_URLS: [&str; 2] = ["https://api.ipify.org", "https://ifconfig.me/ip"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IpInfo {
+    pub ip: IpAddr,
+    /// iso code, e.g. `HK`
+    pub country: Option<String>,
+    pub asn: Option<u32>,
+    pub as_org: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeInfo {
+    pub name: String,
+    pub server: String,
+    pub entry: Option<IpInfo>,
+    /// none before probed
+    pub exit: Option<IpInfo>,
+    pub error: Option<String>,
+    pub updated_at: i64,
+}
+
+static CACHE: Lazy<Mutex<HashMap<String, NodeInfo>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// only one probe at a time, they share the group of the exit probe
+static PROBE: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+/// the country and the asn databases, either could be missing
+pub struct GeoDb {
+    country: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoDb {
+    pub fn open() -> Result<GeoDb> {
+        let home = dirs::app_home_dir()?;
+        let open = |files: &[&str]| {
+            files
+                .iter()
+                .map(|file| home.join(file))
+                .find(|path| path.exists())
+                .and_then(|path: PathBuf| match Reader::open_readfile(&path) {
+                    Ok(reader) => Some(reader),
+                    Err(err) => {
+                        tracing::warn!("failed to open `{}`: {err}", path.display());
+                        None
+                    }
+                })
+        };
+
+        let db = GeoDb {
+            country: open(&["Country.mmdb"]),
+            asn: open(&["ASN.mmdb", "GeoLite2-ASN.mmdb"]),
+        };
+        if db.country.is_none() && db.asn.is_none() {
+            bail!("the Country.mmdb not found");
+        }
+        Ok(db)
+    }
+
+    /// the iso code of the country
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        self.country
+            .as_ref()
+            .and_then(|reader| reader.lookup::<geoip2::Country>(ip).ok())
+            .and_then(|res| res.country?.iso_code.map(str::to_string))
+    }
+
+    fn lookup(&self, ip: IpAddr) -> IpInfo {
+        let country = self.country(ip);
+        let asn = self
+            .asn
+            .as_ref()
+            .and_then(|reader| reader.lookup::<geoip2::Asn>(ip).ok());
+        IpInfo {
+            ip,
+            country,
+            asn: asn.as_ref().and_then(|asn| asn.autonomous_system_number),
+            as_org: asn
+                .as_ref()
+                .and_then(|asn| asn.autonomous_system_organization)
+                .map(str::to_string),
+        }
+    }
+}
+
+/// the cached infos
+pub fn cached() -> Vec<NodeInfo> {
+    let mut list = CACHE.lock().values().cloned().collect::<Vec<_>>();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+/// look up the nodes, all the nodes if `names` is none,
+/// and query the exit ip through each node if `exit` is true
+pub async fn enrich(names: Option<Vec<String>>, exit: bool) -> Result<Vec<NodeInfo>> {
+    let mut servers = node_servers().await;
+    if let Some(names) = &names {
+        servers.retain(|name, _| names.contains(name));
+    }
+    if servers.is_empty() {
+        bail!("no node to look up");
+    }
+    let db = Arc::new(GeoDb::open()?);
+    let now = chrono::Local::now().timestamp();
+
+    // 服务器没变并且没过期的不再解析
+    let stale = {
+        let cache = CACHE.lock();
+        servers
+            .iter()
+            .filter(|(name, server)| match cache.get(*name) {
+                Some(info) => {
+                    info.server != **server
+                        || info.entry.is_none()
+                        || now - info.updated_at > ENTRY_TTL
+                }
+                None => true,
+            })
+            .map(|(name, server)| (name.clone(), server.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    let resolved = stream::iter(stale)
+        .map(|(name, server)| {
+            let db = db.clone();
+            async move {
+                let entry = resolve(&server).await.map(|ip| db.lookup(ip));
+                (name, server, entry)
+            }
+        })
+        .buffer_unordered(CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+    {
+        let mut cache = CACHE.lock();
+        for (name, server, entry) in resolved {
+            // 服务器变了，之前的出口也不算数了
+            let exit = cache
+                .get(&name)
+                .filter(|info| info.server == server)
+                .and_then(|info| info.exit.clone());
+            let (entry, error) = match entry {
+                Ok(entry) => (Some(entry), None),
+                Err(err) => (None, Some(err.to_string())),
+            };
+            cache.insert(
+                name.clone(),
+                NodeInfo {
+                    name,
+                    server,
+                    entry,
+                    exit,
+                    error,
+                    updated_at: now,
+                },
+            );
+        }
+    }
+
+    if exit {
+        probe_exits(servers.keys().cloned().collect(), &db).await?;
+    }
+
+    let cache = CACHE.lock();
+    let mut list = servers
+        .keys()
+        .filter_map(|name| cache.get(name).cloned())
+        .collect::<Vec<_>>();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(list)
+}
+
+async fn resolve(server: &str) -> Result<IpAddr> {
+    if let Ok(ip) = server.trim_matches(['[', ']']).parse::<IpAddr>() {
+        return Ok(ip);
+    }
+    let ips = tokio::time::timeout(RESOLVE_TIMEOUT, lookup(server))
+        .await
+        .map_err(|_| anyhow!("failed to resolve `{server}`: timeout"))?;
+    ips.into_iter()
+        .find(|ip| !is_fake_ip(ip))
+        .ok_or(anyhow!("failed to resolve `{server}`"))
+}
+
+/// the dns of the core, then the doh of the app, then the system dns
+async fn lookup(server: &str) -> Vec<IpAddr> {
+    // meta 内核的 dns 接口不经过 fake-ip
+    if let Ok(res) = clash_api::query_dns(server, Some("A")).await {
+        let ips = res
+            .answer
+            .iter()
+            .filter_map(|answer| answer.data.parse::<IpAddr>().ok())
+            .collect::<Vec<_>>();
+        if !ips.is_empty() {
+            return ips;
+        }
+    }
+    if let Some(doh) = doh::server() {
+        match doh::DohResolver::global().lookup(doh.trim(), server).await {
+            Ok(ips) => return ips,
+            Err(err) => tracing::debug!("failed to resolve `{server}` with doh: {err}"),
+        }
+    }
+    match lookup_host((server, 0)).await {
+        Ok(addrs) => addrs.map(|addr| addr.ip()).collect(),
+        Err(err) => {
+            tracing::debug!("failed to resolve `{server}`: {err}");
+            vec![]
+        }
+    }
+}
+
+/// the fake-ip range of the cores, `198.18.0.0/15`
+fn is_fake_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            a == 198 && b & 0xfe == 18
+        }
+        IpAddr::V6(_) => false,
+    }
+}
+
+/// select each node in the probe group, and query the ip through the probe listener
+async fn probe_exits(names: Vec<String>, db: &GeoDb) -> Result<()> {
+    let port = probe_port().ok_or(anyhow!("the exit probe is not enabled"))?;
+    let _guard = PROBE.lock().await;
+
+    for name in names {
+        let exit = probe_exit(&name, port).await.map(|ip| db.lookup(ip));
+        let mut cache = CACHE.lock();
+        if let Some(info) = cache.get_mut(&name) {
+            match exit {
+                Ok(exit) => {
+                    info.exit = Some(exit);
+                    info.error = None;
+                }
+                Err(err) => {
+                    tracing::debug!("failed to probe the exit of `{name}`: {err}");
+                    info.error = Some(err.to_string());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn probe_exit(name: &str, port: u16) -> Result<IpAddr> {
+    clash_api::update_proxy(EXIT_PROBE, name).await?;
+
+    // 每个节点用新的连接，不复用上一个节点的
+    let client = reqwest::ClientBuilder::new()
+        .proxy(reqwest::Proxy::all(format!("http://127.0.0.1:{port}"))?)
+        .pool_max_idle_per_host(0)
+        .timeout(PROBE_TIMEOUT)
+        .build()?;
+
+    let mut last_err = anyhow!("no ip echo service");
+    for url in IP_ECHO_URLS {
+        // 一个服务出错就换下一个
+        let text = async {
+            client
+                .get(url)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await
+        };
+        let text = match text.await {
+            Ok(text) => text,
+            Err(err) => {
+                last_err = err.into();
+                continue;
+            }
+        };
+        return text
+            .trim()
+            .parse::<IpAddr>()
+            .map_err(|_| anyhow!("invalid ip `{}` from {url}", text.trim()));
+    }
+    Err(last_err)
+}
+
+/// the port of the probe listener in the running config
+fn probe_port() -> Option<u16> {
+    let runtime = Config::runtime();
+    let runtime = runtime.latest();
+    let listeners = runtime.config.as_ref()?.get("listeners")?.as_sequence()?;
+    listeners
+        .iter()
+        .find(|listener| listener.get("name").and_then(Value::as_str) == Some(EXIT_PROBE))
+        .and_then(|listener| listener.get("port")?.as_u64())
+        .map(|port| port as u16)
+}
+
+/// the servers of the nodes in the config and in the files of the proxy providers
+async fn node_servers() -> HashMap<String, String> {
+    let (proxies, providers) = {
+        let runtime = Config::runtime();
+        let runtime = runtime.latest();
+        let config = runtime.config.as_ref();
+        let field = |key: &str| config.and_then(|config| config.get(key)).cloned();
+        (field("proxies"), field("proxy-providers"))
+    };
+
+    let mut servers = HashMap::new();
+    collect_servers(proxies.as_ref(), &mut servers);
+
+    let home = dirs::app_home_dir().ok();
+    let paths = providers
+        .as_ref()
+        .and_then(Value::as_mapping)
+        .into_iter()
+        .flat_map(|providers| providers.values())
+        .filter_map(|provider| provider.get("path").and_then(Value::as_str))
+        .filter_map(|path| home.as_ref().map(|home| home.join(path)))
+        .collect::<Vec<_>>();
+    for path in paths {
+        // 订阅格式的 provider 解析不了，跳过
+        let Ok(text) = tokio::fs::read_to_string(&path).await else {
+            continue;
+        };
+        if let Ok(provider) = serde_yaml::from_str::<Mapping>(&text) {
+            collect_servers(provider.get("proxies"), &mut servers);
+        }
+    }
+    servers
+}
+
+fn collect_servers(proxies: Option<&Value>, servers: &mut HashMap<String, String>) {
+    let proxies = proxies.and_then(Value::as_sequence).into_iter().flatten();
+    for proxy in proxies {
+        let name = proxy.get("name").and_then(Value::as_str);
+        let server = proxy.get("server").and_then(Value::as_str);
+        if let (Some(name), Some(server)) = (name, server) {
+            servers.insert(name.to_string(), server.to_string());
+        }
+    }
+}
+
+#[test]
+fn test_collect_servers() {
+    let proxies: Value = serde_yaml::from_str(
+        r#"
+- { name: HK 01, type: ss, server: hk.example.com, port: 443 }
+- { name: JP 01, type: vmess, server: "1.2.3.4", port: 443 }
+- { name: broken, type: ss }
+"#,
+    )
+    .unwrap();
+
+    let mut servers = HashMap::new();
+    collect_servers(Some(&proxies), &mut servers);
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers["HK 01"], "hk.example.com");
+    assert_eq!(servers["JP 01"], "1.2.3.4");
+
+    assert!(is_fake_ip(&"198.18.0.5".parse().unwrap()));
+    assert!(is_fake_ip(&"198.19.255.1".parse().unwrap()));
+    assert!(!is_fake_ip(&"198.20.0.1".parse().unwrap()));
+}
@@ -0,0 +1,474 @@
+use super::{
+    app_updater::AppUpdater,
+    event_bus::{AppEvent, CoreState, EventBus, ProfileChange},
+    event_hook, handle, CoreManager,
+};
+use crate::{
+    config::{Config, HookEvent, PrfItem},
+    feat,
+    utils::i18n::{t, t_args},
+};
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+/// the recent notifications kept for the dashboard
+const MAX_ITEMS: usize = 50;
+
+/// how often the subscriptions and the app update are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// notify when the used traffic reaches the percent of the quota
+const QUOTA_PERCENT: usize = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    CoreCrashed,
+    UpdateAvailable,
+    SubscriptionExpiring,
+    QuotaAlert,
+    /// a custom tray menu item failed
+    CommandFailed,
+    /// the notify hook of a profile
+    ProfileUpdated,
+}
+
+/// what to do when the notification is clicked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum NotificationAction {
+    /// open a page of the dashboard, e.g. `/profile`
+    OpenPage(String),
+    RunUpdate,
+    RestartCore,
+    UpdateProfile(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationItem {
+    pub id: u32,
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub action: Option<NotificationAction>,
+    pub time: i64,
+}
+
+/// the os notifications with click actions
+/// the clicks are routed back by the os on linux only, the toasts on windows and the
+/// notification center on macos don't report the click to the app with the current notifier.
+/// the dashboard lists the recent items and runs the actions on all platforms
+pub struct Notifications {
+    items: Mutex<VecDeque<NotificationItem>>,
+
+    /// the last time of the keyed notifications, avoid repeating
+    notified: Mutex<HashMap<String, Instant>>,
+
+    /// the page to open after the window created
+    pending_page: Mutex<Option<String>>,
+
+    next_id: AtomicU32,
+
+    running: AtomicBool,
+}
+
+impl Notifications {
+    pub fn global() -> &'static Notifications {
+        static NOTIFICATIONS: OnceCell<Notifications> = OnceCell::new();
+
+        NOTIFICATIONS.get_or_init(|| Notifications {
+            items: Mutex::new(VecDeque::new()),
+            notified: Mutex::new(HashMap::new()),
+            pending_page: Mutex::new(None),
+            next_id: AtomicU32::new(1),
+            running: AtomicBool::new(false),
+        })
+    }
+
+    pub fn enabled() -> bool {
+        let enable = { Config::verge().latest().enable_system_notification };
+        enable.unwrap_or(true)
+    }
+
+    /// check the subscriptions and the app update periodically
+    pub fn start(&'static self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        tauri::async_runtime::spawn(async move {
+            loop {
+                self.check_profiles();
+                if let Err(err) = self.check_update().await {
+                    tracing::debug!("failed to check the app update: {err}");
+                }
+                sleep(CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    pub fn push(
+        &self,
+        kind: NotificationKind,
+        title: String,
+        body: String,
+        action: Option<NotificationAction>,
+    ) {
+        if !Self::enabled() {
+            return;
+        }
+
+        let item = NotificationItem {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            kind,
+            title,
+            body,
+            action,
+            time: chrono::Local::now().timestamp(),
+        };
+
+        {
+            let mut items = self.items.lock();
+            items.push_front(item.clone());
+            items.truncate(MAX_ITEMS);
+        }
+        handle::Handle::emit("nyanpasu://notification", item.clone());
+        show(item);
+    }
+
+    /// skip if the same key is notified within the cooldown, return false if skipped
+    pub fn push_keyed(
+        &self,
+        key: String,
+        cooldown: Duration,
+        kind: NotificationKind,
+        title: String,
+        body: String,
+        action: Option<NotificationAction>,
+    ) -> bool {
+        {
+            let mut notified = self.notified.lock();
+            if let Some(last) = notified.get(&key) {
+                if last.elapsed() < cooldown {
+                    return false;
+                }
+            }
+            notified.insert(key, Instant::now());
+        }
+        self.push(kind, title, body, action);
+        true
+    }
+
+    pub fn list(&self) -> Vec<NotificationItem> {
+        self.items.lock().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.items.lock().clear();
+    }
+
+    pub fn take_pending_page(&self) -> Option<String> {
+        self.pending_page.lock().take()
+    }
+
+    /// run the action of a notification
+    pub async fn run(&self, id: u32) -> Result<()> {
+        let action = {
+            let items = self.items.lock();
+            let item = items
+                .iter()
+                .find(|item| item.id == id)
+                .ok_or(anyhow!("the notification `{id}` is not found"))?;
+            item.action.clone()
+        };
+
+        match action {
+            Some(action) => self.execute(action).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn execute(&self, action: NotificationAction) -> Result<()> {
+        tracing::info!("run the notification action {action:?}");
+
+        match action {
+            NotificationAction::OpenPage(page) => {
+                // 窗口刚创建时前端还没开始监听
+                let exists = handle::Handle::global().get_window().is_some();
+                if exists {
+                    handle::Handle::emit("nyanpasu://navigate", page);
+                } else {
+                    *self.pending_page.lock() = Some(page);
+                }
+                feat::open_dashboard();
+            }
+            NotificationAction::RunUpdate => {
+                AppUpdater::global().install_now().await?;
+            }
+            NotificationAction::RestartCore => {
+                CoreManager::global().run_core().await?;
+                handle::Handle::refresh_clash();
+            }
+            NotificationAction::UpdateProfile(uid) => {
+                feat::update_profile(uid, None).await?;
+                handle::Handle::refresh_profiles();
+            }
+        }
+        Ok(())
+    }
+
+    /// the subscriptions that are about to expire or run out of traffic
+    pub fn check_profiles(&self) {
+        let now = chrono::Local::now().timestamp();
+
+        let (items, current_uid) = {
+            let profiles = Config::profiles();
+            let profiles = profiles.latest();
+            (
+                profiles.get_items().cloned().unwrap_or_default(),
+                profiles.get_current(),
+            )
+        };
+        let (remind_days, fallback) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.expire_reminder_days.unwrap_or(3) as i64,
+                verge.expired_fallback_profile.clone(),
+            )
+        };
+        let expired = |item: &PrfItem| {
+            item.extra.as_ref().map_or(false, |extra| {
+                extra.expire > 0 && (extra.expire as i64) <= now
+            })
+        };
+
+        if let Some(current) = items
+            .iter()
+            .find(|item| item.uid.is_some() && item.uid == current_uid)
+            .filter(|item| expired(item))
+        {
+            let fallback = fallback
+                .filter(|uid| !uid.is_empty() && current.uid.as_ref() != Some(uid))
+                .and_then(|uid| items.iter().find(|item| item.uid.as_ref() == Some(&uid)))
+                .filter(|item| !expired(item));
+            if let Some(fallback) = fallback {
+                self.switch_expired(current, fallback);
+            }
+        }
+
+        for item in items {
+            let (uid, extra) = match (item.uid, item.extra) {
+                (Some(uid), Some(extra)) => (uid, extra),
+                _ => continue,
+            };
+            let name = item.name.unwrap_or(uid.clone());
+            let action = Some(NotificationAction::UpdateProfile(uid.clone()));
+
+            let expire = extra.expire as i64;
+            let remind = remind_days > 0 && expire - now < remind_days * 24 * 60 * 60;
+            if expire > 0 && (remind || expire <= now) {
+                let body = match expire > now {
+                    true => t_args(
+                        "The subscription \"{name}\" expires in {days} day(s)",
+                        &[
+                            ("name", &name),
+                            ("days", &((expire - now) / (24 * 60 * 60) + 1)),
+                        ],
+                    ),
+                    false => t_args(
+                        "The subscription \"{name}\" has expired",
+                        &[("name", &name)],
+                    ),
+                };
+                self.push_keyed(
+                    format!("expire::{uid}::{expire}::{}", expire <= now),
+                    Duration::MAX,
+                    NotificationKind::SubscriptionExpiring,
+                    t("Subscription Expiring"),
+                    body,
+                    action.clone(),
+                );
+            }
+
+            let used = extra.upload + extra.download;
+            if extra.total > 0 && used * 100 / extra.total >= QUOTA_PERCENT {
+                let percent = (used * 100 / extra.total).min(100);
+                let pushed = self.push_keyed(
+                    format!("quota::{uid}::{}", extra.total),
+                    Duration::MAX,
+                    NotificationKind::QuotaAlert,
+                    t("Traffic Quota"),
+                    t_args(
+                        "The subscription \"{name}\" has used {percent}% of the traffic",
+                        &[("name", &name), ("percent", &percent)],
+                    ),
+                    action,
+                );
+                if pushed {
+                    event_hook::publish(
+                        HookEvent::QuotaExceeded,
+                        serde_json::json!({
+                            "uid": uid,
+                            "name": name,
+                            "percent": percent,
+                            "used": used,
+                            "total": extra.total,
+                        }),
+                    );
+                }
+            }
+        }
+    }
+
+    /// switch away from the expired current profile
+    fn switch_expired(&self, current: &PrfItem, fallback: &PrfItem) {
+        let (Some(uid), Some(fallback_uid)) = (current.uid.clone(), fallback.uid.clone()) else {
+            return;
+        };
+        let name = current.name.clone().unwrap_or(uid.clone());
+        let fallback_name = fallback.name.clone().unwrap_or(fallback_uid.clone());
+        tracing::info!("the profile `{uid}` has expired, switch to `{fallback_uid}`");
+
+        tauri::async_runtime::spawn(async move {
+            match feat::switch_profile(fallback_uid).await {
+                Ok(_) => {
+                    handle::Handle::refresh_profiles();
+                    Notifications::global().push_keyed(
+                        format!("expire::switch::{uid}"),
+                        Duration::from_secs(24 * 60 * 60),
+                        NotificationKind::SubscriptionExpiring,
+                        t("Subscription Expired"),
+                        t_args(
+                            "The subscription \"{name}\" has expired, switched to \"{fallback}\"",
+                            &[("name", &name), ("fallback", &fallback_name)],
+                        ),
+                        Some(NotificationAction::OpenPage("/profile".into())),
+                    );
+                }
+                Err(err) => tracing::error!("failed to switch the expired profile: {err}"),
+            }
+        });
+    }
+
+    pub async fn check_update(&self) -> Result<()> {
+        let Some(update) = AppUpdater::global().check().await? else {
+            return Ok(());
+        };
+        let version = update.version;
+
+        let install_on_exit = { Config::verge().latest().update_install_on_exit };
+        if install_on_exit.unwrap_or(false) {
+            if !update.staged {
+                AppUpdater::global().stage().await?;
+                self.push_keyed(
+                    format!("update::{version}"),
+                    Duration::MAX,
+                    NotificationKind::UpdateAvailable,
+                    t("Update Downloaded"),
+                    t_args(
+                        "Clash Nyanpasu {version} will be installed when the app exits",
+                        &[("version", &version)],
+                    ),
+                    None,
+                );
+            }
+        } else {
+            self.push_keyed(
+                format!("update::{version}"),
+                Duration::MAX,
+                NotificationKind::UpdateAvailable,
+                t("Update Available"),
+                t_args(
+                    "Clash Nyanpasu {version} is available, click to update",
+                    &[("version", &version)],
+                ),
+                Some(NotificationAction::RunUpdate),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn subscribe_events(&'static self) {
+        EventBus::subscribe("notification", move |event| async move {
+            match event {
+                AppEvent::CoreState {
+                    state: CoreState::Recovered,
+                } => self.core_crashed(true),
+                AppEvent::CoreState {
+                    state: CoreState::RecoverFailed,
+                } => self.core_crashed(false),
+                // 订阅信息更新后重新检查到期时间和流量
+                AppEvent::ProfileChanged {
+                    change: ProfileChange::Updated,
+                    ..
+                } => self.check_profiles(),
+                _ => {}
+            }
+        });
+    }
+
+    /// called when the core exits unexpectedly
+    pub fn core_crashed(&self, recovered: bool) {
+        let (body, action) = match recovered {
+            true => (
+                "The clash core exited unexpectedly and has been restarted",
+                NotificationAction::OpenPage("/logs".into()),
+            ),
+            false => (
+                "The clash core exited unexpectedly and failed to restart",
+                NotificationAction::RestartCore,
+            ),
+        };
+        // 内核反复崩溃时不要一直弹通知
+        self.push_keyed(
+            format!("core::{recovered}"),
+            Duration::from_secs(5 * 60),
+            NotificationKind::CoreCrashed,
+            t("Clash Core Crashed"),
+            t(body),
+            Some(action),
+        );
+    }
+}
+
+/// the xdg notification server reports the clicks
+#[cfg(target_os = "linux")]
+fn show(item: NotificationItem) {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname("Clash Nyanpasu")
+        .summary(&item.title)
+        .body(&item.body);
+    if item.action.is_some() {
+        notification.action("default", &t("Open"));
+    }
+
+    let id = item.id;
+    std::thread::spawn(move || match notification.show() {
+        Ok(notification) => notification.wait_for_action(|action| {
+            if action == "default" {
+                tauri::async_runtime::spawn(async move {
+                    if let Err(err) = Notifications::global().run(id).await {
+                        tracing::error!("failed to run the notification action: {err}");
+                    }
+                });
+            }
+        }),
+        Err(err) => tracing::error!("failed to show the notification: {err}"),
+    });
+}
+
+/// the click only brings the app to the front, the action is run from the dashboard
+#[cfg(not(target_os = "linux"))]
+fn show(item: NotificationItem) {
+    handle::Handle::notify(item.title, item.body);
+}
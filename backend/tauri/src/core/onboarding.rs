@@ -0,0 +1,405 @@
+//! the backend of the first-run wizard
+//!
+//! every step is a command validated on its own, the choices are kept in a draft and applied
+//! together by `apply`. importing the data of another client is done right away by the
+//! importer, the imported profile in use is switched to when applying.
+
+use super::{
+    handle, http,
+    importer::{self, ImportReport},
+    storage::Storage,
+    CoreManager,
+};
+use crate::{
+    config::{ClashCore, Config, HttpPurpose, IVerge},
+    feat,
+    utils::{dirs, resolve},
+};
+use anyhow::{bail, Context, Result};
+use futures_util::future::join_all;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tauri::api::path::{data_dir, home_dir};
+use url::Url;
+
+const FINISHED_KEY: &str = "onboarding_finished";
+
+const VERGE_REV_ID: &str = "io.github.clash-verge-rev.clash-verge-rev";
+
+/// the ui languages
+const LANGUAGES: &[&str] = &["en", "zh", "ru"];
+
+/// the name and the prefix, empty for github directly
+const MIRRORS: &[(&str, &str)] = &[
+    ("GitHub", ""),
+    ("mirror.ghproxy.com", "https://mirror.ghproxy.com/"),
+    ("ghproxy.com", "https://ghproxy.com/"),
+];
+
+const PROBE_URL: &str = "https://github.com/keiko233/clash-nyanpasu/raw/dev/manifest/version.json";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const CN_TIMEZONES: &[&str] = &[
+    "Asia/Shanghai",
+    "Asia/Chongqing",
+    "Asia/Chungking",
+    "Asia/Harbin",
+    "Asia/Urumqi",
+    "PRC",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingEnv {
+    /// e.g. `zh-CN`
+    pub locale: Option<String>,
+    /// e.g. `Asia/Shanghai`
+    pub timezone: Option<String>,
+    /// the iso code guessed from the locale
+    pub region: Option<String>,
+    /// the proposed ui language
+    pub language: &'static str,
+    /// github is usually slow, a mirror is proposed
+    pub in_china: bool,
+}
+
+pub fn detect_env() -> OnboardingEnv {
+    let locale = sys_locale::get_locale();
+    let timezone = iana_time_zone::get_timezone().ok();
+    let region = locale.as_deref().and_then(parse_region);
+    let in_china = region.as_deref() == Some("CN")
+        || timezone
+            .as_deref()
+            .map_or(false, |tz| CN_TIMEZONES.contains(&tz));
+    OnboardingEnv {
+        language: suggest_language(locale.as_deref().unwrap_or_default()),
+        locale,
+        timezone,
+        region,
+        in_china,
+    }
+}
+
+/// `zh-CN` `zh_CN.UTF-8` `zh-Hans-CN`
+fn parse_region(locale: &str) -> Option<String> {
+    let locale = locale.split(['.', '@']).next()?;
+    locale
+        .split(['-', '_'])
+        .skip(1)
+        .find(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(|part| part.to_ascii_uppercase())
+}
+
+fn suggest_language(locale: &str) -> &'static str {
+    let lang = locale.split(['-', '_', '.']).next().unwrap_or_default();
+    LANGUAGES
+        .iter()
+        .find(|each| each.eq_ignore_ascii_case(lang))
+        .copied()
+        .unwrap_or("en")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MirrorProbe {
+    pub name: &'static str,
+    /// the prefix, empty for github directly
+    pub url: &'static str,
+    /// ms
+    pub latency: Option<u64>,
+    pub error: Option<String>,
+    pub recommended: bool,
+}
+
+/// fetch a small file through each mirror, the fastest one is recommended
+pub async fn probe_mirrors() -> Result<Vec<MirrorProbe>> {
+    // 和更新走同样的代理，不重试
+    let client = http::builder(HttpPurpose::Updater)?
+        .timeout(PROBE_TIMEOUT)
+        .build()?;
+    let mut probes = join_all(MIRRORS.iter().map(|(name, url)| {
+        let client = client.clone();
+        async move {
+            let start = Instant::now();
+            let res = client
+                .get(format!("{url}{PROBE_URL}"))
+                .send()
+                .await
+                .and_then(|res| res.error_for_status());
+            let (latency, error) = match res {
+                Ok(_) => (Some(start.elapsed().as_millis() as u64), None),
+                Err(err) => (None, Some(err.to_string())),
+            };
+            MirrorProbe {
+                name,
+                url,
+                latency,
+                error,
+                recommended: false,
+            }
+        }
+    }))
+    .await;
+
+    if let Some(index) = recommend(&probes, detect_env().in_china) {
+        probes[index].recommended = true;
+    }
+    Ok(probes)
+}
+
+/// github directly if reachable outside china, otherwise the fastest
+fn recommend(probes: &[MirrorProbe], in_china: bool) -> Option<usize> {
+    let reachable = || {
+        probes
+            .iter()
+            .enumerate()
+            .filter(|(_, probe)| probe.latency.is_some())
+    };
+    if !in_china {
+        if let Some((index, _)) = reachable().find(|(_, probe)| probe.url.is_empty()) {
+            return Some(index);
+        }
+    }
+    reachable()
+        .min_by_key(|(_, probe)| probe.latency)
+        .map(|(index, _)| index)
+}
+
+/// empty for github directly
+pub fn validate_mirror(mirror: &str) -> Result<()> {
+    let mirror = mirror.trim();
+    if mirror.is_empty() {
+        return Ok(());
+    }
+    let url = Url::parse(mirror).with_context(|| format!("invalid mirror `{mirror}`"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("the mirror `{mirror}` should be a http(s) url");
+    }
+    Ok(())
+}
+
+/// the clients whose config can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KnownClient {
+    ClashVerge,
+    ClashVergeRev,
+    ClashForWindows,
+}
+
+impl KnownClient {
+    const ALL: [KnownClient; 3] = [
+        KnownClient::ClashVerge,
+        KnownClient::ClashVergeRev,
+        KnownClient::ClashForWindows,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KnownClient::ClashVerge => "Clash Verge",
+            KnownClient::ClashVergeRev => "Clash Verge Rev",
+            KnownClient::ClashForWindows => "Clash for Windows",
+        }
+    }
+
+    /// the data dir if exists
+    pub fn dir(&self) -> Option<PathBuf> {
+        // clash verge 的目录和我们的一样，不能导入自己
+        let own = dirs::app_home_dir().ok();
+        self.candidates()
+            .into_iter()
+            .find(|dir| dir.is_dir() && own.as_ref() != Some(dir))
+    }
+
+    /// the data dirs of the platform, the first existing one is used
+    fn candidates(&self) -> Vec<PathBuf> {
+        let (Some(home), Some(data)) = (home_dir(), data_dir()) else {
+            return vec![];
+        };
+        match self {
+            // `~/.config/clash-verge` on all the platforms, see `app_home_dir`
+            KnownClient::ClashVerge => vec![home.join(".config").join("clash-verge")],
+            // the app data dir of tauri:
+            // linux `~/.local/share`, macos `~/Library/Application Support`, windows `%APPDATA%`
+            KnownClient::ClashVergeRev => vec![data.join(VERGE_REV_ID)],
+            // `%USERPROFILE%\.config\clash` on windows, `~/.config/clash` on macos and linux
+            KnownClient::ClashForWindows => vec![home.join(".config").join("clash")],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedClient {
+    pub client: KnownClient,
+    pub name: &'static str,
+    pub dir: PathBuf,
+    /// the name of the profile in use, none if nothing to import
+    pub profile: Option<String>,
+}
+
+pub fn detect_clients() -> Vec<DetectedClient> {
+    KnownClient::ALL
+        .iter()
+        .filter_map(|client| {
+            let dir = client.dir()?;
+            Some(DetectedClient {
+                client: *client,
+                name: client.label(),
+                profile: importer::current_name(*client, &dir),
+                dir,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OnboardingDraft {
+    pub language: Option<String>,
+    pub github_mirror: Option<String>,
+    pub clash_core: Option<ClashCore>,
+    /// the uid of the imported profile
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingState {
+    pub finished: bool,
+    pub draft: OnboardingDraft,
+}
+
+pub struct Onboarding {
+    draft: Mutex<OnboardingDraft>,
+}
+
+impl Onboarding {
+    pub fn global() -> &'static Onboarding {
+        static ONBOARDING: OnceCell<Onboarding> = OnceCell::new();
+
+        ONBOARDING.get_or_init(|| Onboarding {
+            draft: Mutex::new(OnboardingDraft::default()),
+        })
+    }
+
+    /// the users with profiles are treated as finished
+    fn finished() -> bool {
+        let finished = Storage::global()
+            .get_item::<bool>(FINISHED_KEY)
+            .ok()
+            .flatten()
+            .unwrap_or(false);
+        let has_profiles = {
+            let profiles = Config::profiles();
+            let profiles = profiles.latest();
+            profiles
+                .get_items()
+                .map_or(false, |items| !items.is_empty())
+        };
+        finished || has_profiles
+    }
+
+    pub fn state(&self) -> OnboardingState {
+        OnboardingState {
+            finished: Self::finished(),
+            draft: self.draft.lock().clone(),
+        }
+    }
+
+    pub fn set_language(&self, language: String) -> Result<()> {
+        if !LANGUAGES.contains(&language.as_str()) {
+            bail!("unsupported language `{language}`");
+        }
+        self.draft.lock().language = Some(language);
+        Ok(())
+    }
+
+    pub fn set_mirror(&self, mirror: String) -> Result<()> {
+        validate_mirror(&mirror)?;
+        self.draft.lock().github_mirror = Some(mirror.trim().to_string());
+        Ok(())
+    }
+
+    /// the core should be installed, return its version
+    pub async fn set_core(&self, core: ClashCore) -> Result<String> {
+        let check = core.clone();
+        let version = tokio::task::spawn_blocking(move || resolve::resolve_core_version(&check))
+            .await?
+            .with_context(|| format!("the core `{core}` is not available"))?;
+        self.draft.lock().clash_core = Some(core);
+        Ok(version)
+    }
+
+    /// import the profiles and the settings of the client,
+    /// the subscriptions are kept as the remote profiles with their urls
+    pub async fn import_client(&self, client: KnownClient) -> Result<ImportReport> {
+        let report = importer::import(client).await?;
+        if report.current.is_some() {
+            self.draft.lock().profile = report.current.clone();
+        }
+        Ok(report)
+    }
+
+    /// apply the draft and finish the wizard
+    pub async fn apply(&self) -> Result<()> {
+        let draft = self.draft.lock().clone();
+
+        if draft.language.is_some() || draft.github_mirror.is_some() {
+            feat::patch_verge(IVerge {
+                language: draft.language,
+                github_mirror: draft.github_mirror,
+                ..IVerge::default()
+            })
+            .await?;
+        }
+
+        let current = { Config::verge().latest().clash_core.clone() };
+        if let Some(core) = draft
+            .clash_core
+            .filter(|core| Some(core) != current.as_ref())
+        {
+            CoreManager::global().change_core(Some(core)).await?;
+        }
+
+        if let Some(uid) = draft.profile {
+            feat::switch_profile(uid).await?;
+        }
+
+        self.finish()?;
+        handle::Handle::refresh_verge();
+        Ok(())
+    }
+
+    /// skip the rest of the steps, the imported profile is kept
+    pub fn finish(&self) -> Result<()> {
+        *self.draft.lock() = OnboardingDraft::default();
+        Storage::global().set_item(FINISHED_KEY, &true)
+    }
+}
+
+#[test]
+fn test_onboarding_env() {
+    assert_eq!(parse_region("zh-CN").as_deref(), Some("CN"));
+    assert_eq!(parse_region("zh_CN.UTF-8").as_deref(), Some("CN"));
+    assert_eq!(parse_region("zh-Hans-CN").as_deref(), Some("CN"));
+    assert_eq!(parse_region("en"), None);
+    assert_eq!(suggest_language("ru-RU"), "ru");
+    assert_eq!(suggest_language("ja-JP"), "en");
+
+    let probe = |url, latency| MirrorProbe {
+        name: "",
+        url,
+        latency,
+        error: None,
+        recommended: false,
+    };
+    let probes = [
+        probe("", Some(800)),
+        probe("https://mirror.ghproxy.com/", Some(200)),
+        probe("https://ghproxy.com/", None),
+    ];
+    assert_eq!(recommend(&probes, false), Some(0));
+    assert_eq!(recommend(&probes, true), Some(1));
+    assert_eq!(recommend(&probes[2..], true), None);
+}
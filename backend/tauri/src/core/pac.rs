@@ -0,0 +1,86 @@
+use crate::config::{Config, IVerge};
+use std::net::Ipv4Addr;
+
+/// the pac script is served by the embed server
+pub fn pac_url() -> String {
+    let port = IVerge::get_singleton_port();
+    format!("http://127.0.0.1:{port}/pac")
+}
+
+/// generate the pac script with the current port and bypass list
+pub fn current() -> String {
+    let port = super::sysopt::proxy_port();
+    let bypass = { Config::verge().latest().get_system_proxy_bypass() };
+
+    generate(port, &bypass)
+}
+
+/// the bypass list is the same as the system proxy
+/// e.g. `localhost,127.0.0.1/8,*.local,<local>` or `localhost;192.168.*`
+pub fn generate(port: u16, bypass: &str) -> String {
+    let mut conditions = Vec::new();
+
+    for item in bypass.split([',', ';']).map(str::trim) {
+        if item.is_empty() {
+            continue;
+        }
+
+        if item == "<local>" {
+            conditions.push("isPlainHostName(host)".to_string());
+            continue;
+        }
+
+        if let Some((ip, mask)) = parse_cidr(item) {
+            conditions.push(format!("(isIp && isInNet(host, \"{ip}\", \"{mask}\"))"));
+            continue;
+        }
+
+        let pattern = item.replace('\\', "\\\\").replace('"', "\\\"");
+        conditions.push(format!("shExpMatch(host, \"{pattern}\")"));
+    }
+
+    let conditions = match conditions.is_empty() {
+        true => "false".to_string(),
+        false => conditions.join(" ||\n    "),
+    };
+
+    format!(
+        r#"function FindProxyForURL(url, host) {{
+  var isIp = /^\d+\.\d+\.\d+\.\d+$/.test(host);
+  if (
+    {conditions}
+  ) {{
+    return "DIRECT";
+  }}
+  return "PROXY 127.0.0.1:{port}; SOCKS5 127.0.0.1:{port}; DIRECT";
+}}
+"#
+    )
+}
+
+/// only ipv4 cidr is supported by `isInNet`
+fn parse_cidr(item: &str) -> Option<(Ipv4Addr, Ipv4Addr)> {
+    let (ip, len) = item.split_once('/')?;
+    let ip = ip.parse::<Ipv4Addr>().ok()?;
+    let len = len.parse::<u32>().ok().filter(|l| *l <= 32)?;
+    let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+
+    Some((ip, Ipv4Addr::from(mask)))
+}
+
+#[test]
+fn test_generate() {
+    let script = generate(7890, "localhost,192.168.0.0/16;*.local,<local>");
+
+    assert!(script.contains(r#"shExpMatch(host, "localhost")"#));
+    assert!(script.contains(r#"isInNet(host, "192.168.0.0", "255.255.0.0")"#));
+    assert!(script.contains(r#"shExpMatch(host, "*.local")"#));
+    assert!(script.contains("isPlainHostName(host)"));
+    assert!(script.contains("PROXY 127.0.0.1:7890"));
+
+    assert_eq!(
+        parse_cidr("10.0.0.0/0").unwrap().1,
+        Ipv4Addr::new(0, 0, 0, 0)
+    );
+    assert!(parse_cidr("::1/128").is_none());
+}
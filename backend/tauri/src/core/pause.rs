@@ -0,0 +1,273 @@
+//! pause the system proxy / tun in the `pause_windows` of the verge, or manually by `toggle`
+//! the settings before the pause are saved to a file, so that they can be restored after a restart
+
+use super::handle;
+use crate::{
+    config::{Config, IVerge, PauseWindow},
+    feat, log_err,
+    utils::{dirs, help},
+};
+use anyhow::Result;
+use chrono::{Datelike, Local};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PauseState {
+    /// the window which paused the proxy
+    pub window: String,
+    /// the values before the pause, none if not paused by the window
+    /// or changed by the user while paused, those are not restored
+    pub system_proxy: Option<bool>,
+    pub tun: Option<bool>,
+    /// the mode before the manual pause, it goes direct while paused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// paused by the user, only resumed by the user
+    #[serde(default)]
+    pub manual: bool,
+    pub since: i64,
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(dirs::app_home_dir()?.join("proxy_pause.yaml"))
+}
+
+pub struct ProxyPause {
+    state: Mutex<Option<PauseState>>,
+    /// the window resumed by the user, not paused again until it ends
+    overridden: Mutex<Option<String>>,
+    /// on_tick and toggle check the state and apply it as one step
+    running: tokio::sync::Mutex<()>,
+    /// the verge is being patched by the pause itself
+    applying: AtomicBool,
+}
+
+impl ProxyPause {
+    pub fn global() -> &'static ProxyPause {
+        static PAUSE: OnceCell<ProxyPause> = OnceCell::new();
+
+        PAUSE.get_or_init(|| {
+            // 暂停期间退出的话，下次启动时恢复
+            let state = state_path()
+                .ok()
+                .filter(|path| path.exists())
+                .and_then(|path| help::read_yaml::<PauseState>(&path).ok());
+            ProxyPause {
+                state: Mutex::new(state),
+                overridden: Mutex::new(None),
+                running: tokio::sync::Mutex::new(()),
+                applying: AtomicBool::new(false),
+            }
+        })
+    }
+
+    pub fn state(&self) -> Option<PauseState> {
+        self.state.lock().clone()
+    }
+
+    fn active_window() -> Option<PauseWindow> {
+        let windows = { Config::verge().latest().pause_windows.clone() };
+        let now = Local::now();
+        let weekday = now.weekday().number_from_monday() as u8;
+        windows
+            .unwrap_or_default()
+            .into_iter()
+            .find(|window| window.enable && window.contains(now.time(), weekday))
+    }
+
+    /// called by the `proxy_pause` job, only acts on entering or leaving the windows
+    /// the user can still turn on the proxy manually in the window
+    pub async fn on_tick(&self) {
+        let _running = self.running.lock().await;
+        let active = Self::active_window();
+        let overridden = {
+            let mut overridden = self.overridden.lock();
+            if active.as_ref().map(|window| &window.name) != overridden.as_ref() {
+                *overridden = None;
+            }
+            overridden.is_some()
+        };
+        let state = self.state();
+        let result = match (active, state) {
+            (Some(window), None) if !overridden => self.pause(&window).await,
+            (None, Some(state)) if !state.manual => self.resume(&state).await,
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            tracing::error!("failed to update the proxy pause: {err}");
+        }
+    }
+
+    async fn pause(&self, window: &PauseWindow) -> Result<()> {
+        let (system_proxy, tun) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_system_proxy.unwrap_or(false),
+                verge.enable_tun_mode.unwrap_or(false),
+            )
+        };
+        let state = PauseState {
+            window: window.name.clone(),
+            system_proxy: window.system_proxy.then_some(system_proxy),
+            tun: window.tun.then_some(tun),
+            mode: None,
+            manual: false,
+            since: Local::now().timestamp(),
+        };
+        tracing::info!("pause the proxy by the window `{}`", window.name);
+        self.apply(state).await
+    }
+
+    /// pause the system proxy, the tun and go direct, or resume the exact previous state
+    /// return whether the proxy is paused
+    pub async fn toggle(&self) -> Result<bool> {
+        let _running = self.running.lock().await;
+        if let Some(state) = self.state() {
+            self.resume(&state).await?;
+            // 在窗口内手动恢复的，直到窗口结束都不再暂停
+            *self.overridden.lock() = Self::active_window().map(|window| window.name);
+            return Ok(false);
+        }
+
+        let (system_proxy, tun) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_system_proxy.unwrap_or(false),
+                verge.enable_tun_mode.unwrap_or(false),
+            )
+        };
+        let mode = current_mode();
+        let state = PauseState {
+            window: String::new(),
+            system_proxy: Some(system_proxy),
+            tun: Some(tun),
+            mode: (mode != "direct").then_some(mode),
+            manual: true,
+            since: Local::now().timestamp(),
+        };
+        tracing::info!("pause the proxy manually");
+        self.apply(state).await?;
+        Ok(true)
+    }
+
+    /// save the state first, then turn off all at once, undo if any step failed
+    async fn apply(&self, state: PauseState) -> Result<()> {
+        help::save_yaml(&state_path()?, &state, Some("# Clash Nyanpasu Proxy Pause"))?;
+        *self.state.lock() = Some(state.clone());
+
+        let result: Result<()> = async {
+            self.patch_verge(IVerge {
+                enable_system_proxy: state.system_proxy.filter(|on| *on).map(|_| false),
+                enable_tun_mode: state.tun.filter(|on| *on).map(|_| false),
+                ..IVerge::default()
+            })
+            .await?;
+            if state.mode.is_some() {
+                if let Err(err) = feat::set_clash_mode("direct").await {
+                    log_err!(self.restore_verge(&state).await);
+                    return Err(err);
+                }
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(err) = result {
+            self.clear()?;
+            return Err(err);
+        }
+
+        handle::Handle::emit("nyanpasu://proxy-pause", Some(state));
+        log_err!(handle::Handle::update_systray_part());
+        Ok(())
+    }
+
+    async fn resume(&self, state: &PauseState) -> Result<()> {
+        match state.manual {
+            true => tracing::info!("resume the proxy paused manually"),
+            false => tracing::info!("resume the proxy paused by the window `{}`", state.window),
+        }
+        self.restore_verge(state).await?;
+        if let Some(mode) = &state.mode {
+            feat::set_clash_mode(mode).await?;
+        }
+
+        self.clear()?;
+        handle::Handle::emit("nyanpasu://proxy-pause", None::<PauseState>);
+        log_err!(handle::Handle::update_systray_part());
+        Ok(())
+    }
+
+    async fn restore_verge(&self, state: &PauseState) -> Result<()> {
+        self.patch_verge(IVerge {
+            enable_system_proxy: state.system_proxy.filter(|on| *on),
+            enable_tun_mode: state.tun.filter(|on| *on),
+            ..IVerge::default()
+        })
+        .await
+    }
+
+    async fn patch_verge(&self, patch: IVerge) -> Result<()> {
+        if patch.enable_system_proxy.is_none() && patch.enable_tun_mode.is_none() {
+            return Ok(());
+        }
+        self.applying.store(true, Ordering::SeqCst);
+        let result = feat::patch_verge(patch).await;
+        self.applying.store(false, Ordering::SeqCst);
+        result?;
+        handle::Handle::refresh_verge();
+        Ok(())
+    }
+
+    /// called after the verge is patched, the settings changed by the user while paused
+    /// are kept as they are after resuming
+    pub fn on_verge_patched(&self, system_proxy: bool, tun: bool) {
+        if self.applying.load(Ordering::SeqCst) || !(system_proxy || tun) {
+            return;
+        }
+        let mut state = self.state.lock();
+        let Some(state) = state.as_mut() else {
+            return;
+        };
+        if system_proxy {
+            state.system_proxy = None;
+        }
+        if tun {
+            state.tun = None;
+        }
+        if let Ok(path) = state_path() {
+            log_err!(help::save_yaml(
+                &path,
+                &*state,
+                Some("# Clash Nyanpasu Proxy Pause")
+            ));
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        let path = state_path()?;
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        *self.state.lock() = None;
+        Ok(())
+    }
+}
+
+fn current_mode() -> String {
+    let clash = Config::clash();
+    let clash = clash.latest();
+    clash
+        .0
+        .get("mode")
+        .and_then(|mode| mode.as_str())
+        .unwrap_or("rule")
+        .to_string()
+}
@@ -0,0 +1,258 @@
+#![cfg(target_os = "linux")]
+//! the global shortcuts on wayland through `org.freedesktop.portal.GlobalShortcuts`
+//!
+//! the x11 key grabs only work when an xwayland window is focused, so the portal is used instead.
+//! the desktop asks the user to confirm the shortcuts, the keys in the settings are the preferred
+//! triggers and could be changed in the system settings.
+use super::hotkey;
+use anyhow::{anyhow, bail, Result};
+use futures_util::StreamExt;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use tauri::async_runtime::JoinHandle;
+use zbus::{
+    zvariant::{DynamicType, ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    Connection, Proxy,
+};
+
+const PORTAL_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SHORTCUTS_IFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+
+struct Session {
+    conn: Connection,
+    handle: OwnedObjectPath,
+    listener: JoinHandle<()>,
+}
+
+pub struct PortalShortcuts {
+    session: tokio::sync::Mutex<Option<Session>>,
+    error: Mutex<Option<String>>,
+}
+
+impl PortalShortcuts {
+    pub fn global() -> &'static PortalShortcuts {
+        static PORTAL_SHORTCUTS: OnceCell<PortalShortcuts> = OnceCell::new();
+
+        PORTAL_SHORTCUTS.get_or_init(|| PortalShortcuts {
+            session: tokio::sync::Mutex::new(None),
+            error: Mutex::new(None),
+        })
+    }
+
+    /// the last error of the portal, e.g. the desktop has no such portal
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().clone()
+    }
+
+    /// bind all the hotkeys `func,key` again in a new session
+    /// the portal could not rebind the shortcuts of a session
+    pub fn bind(&'static self, hotkeys: Vec<String>) {
+        tauri::async_runtime::spawn(async move {
+            let mut session = self.session.lock().await;
+            if let Some(old) = session.take() {
+                old.close().await;
+            }
+
+            let hotkeys = hotkeys
+                .into_iter()
+                .filter(|hotkey| hotkey.split_once(',').is_some())
+                .collect::<Vec<_>>();
+            if hotkeys.is_empty() {
+                *self.error.lock() = None;
+                return;
+            }
+
+            match Session::create(&hotkeys).await {
+                Ok(new) => {
+                    *session = Some(new);
+                    *self.error.lock() = None;
+                }
+                Err(err) => {
+                    tracing::warn!("failed to bind the global shortcuts through the portal: {err}");
+                    *self.error.lock() = Some(err.to_string());
+                }
+            }
+        });
+    }
+}
+
+impl Session {
+    async fn create(hotkeys: &[String]) -> Result<Session> {
+        let conn = Connection::session().await?;
+
+        let token = next_token();
+        let options = HashMap::from([
+            ("handle_token", Value::from(token.as_str())),
+            ("session_handle_token", Value::from(token.as_str())),
+        ]);
+        let results = request(&conn, "CreateSession", &(options,), &token).await?;
+        let handle = results
+            .get("session_handle")
+            .and_then(|value| match &**value {
+                Value::Str(handle) => ObjectPath::try_from(handle.as_str()).ok(),
+                Value::ObjectPath(handle) => Some(handle.clone()),
+                _ => None,
+            })
+            .map(OwnedObjectPath::from)
+            .ok_or(anyhow!("no session handle from the portal"))?;
+
+        // the id is the whole `func,key`, a function could have several keys
+        let shortcuts = hotkeys
+            .iter()
+            .filter_map(|hotkey| {
+                let (func, key) = hotkey.split_once(',')?;
+                let options = HashMap::from([
+                    ("description", Value::from(func.trim().to_string())),
+                    ("preferred_trigger", Value::from(trigger(key))),
+                ]);
+                Some((hotkey.clone(), options))
+            })
+            .collect::<Vec<_>>();
+        let token = next_token();
+        let options = HashMap::from([("handle_token", Value::from(token.as_str()))]);
+        let body = (&handle, shortcuts, "", options);
+        let results = request(&conn, "BindShortcuts", &body, &token).await?;
+        let bound = results
+            .get("shortcuts")
+            .and_then(|value| match &**value {
+                Value::Array(shortcuts) => Some(shortcuts.get().len()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        tracing::info!(
+            "bound {bound} of {} global shortcuts through the portal",
+            hotkeys.len()
+        );
+
+        let listener = tauri::async_runtime::spawn(listen(conn.clone(), handle.clone()));
+        Ok(Session {
+            conn,
+            handle,
+            listener,
+        })
+    }
+
+    async fn close(self) {
+        self.listener.abort();
+        let closed = async {
+            let session = Proxy::new(
+                &self.conn,
+                PORTAL_NAME,
+                self.handle.as_str(),
+                "org.freedesktop.portal.Session",
+            )
+            .await?;
+            session.call_method("Close", &()).await?;
+            zbus::Result::Ok(())
+        };
+        if let Err(err) = closed.await {
+            tracing::debug!("failed to close the shortcuts session: {err}");
+        }
+    }
+}
+
+fn next_token() -> String {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    format!("nyanpasu_{}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// call a method returning a request, and wait for its `Response`
+async fn request<B>(
+    conn: &Connection,
+    method: &str,
+    body: &B,
+    token: &str,
+) -> Result<HashMap<String, OwnedValue>>
+where
+    B: serde::Serialize + DynamicType,
+{
+    // 先订阅再调用，免得错过了回应
+    let sender = conn
+        .unique_name()
+        .ok_or(anyhow!("no unique name on the session bus"))?
+        .as_str()
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let path = format!("{PORTAL_PATH}/request/{sender}/{token}");
+    let request = Proxy::new(conn, PORTAL_NAME, path, "org.freedesktop.portal.Request").await?;
+    let mut responses = request.receive_signal("Response").await?;
+
+    let portal = Proxy::new(conn, PORTAL_NAME, PORTAL_PATH, SHORTCUTS_IFACE).await?;
+    portal.call_method(method, body).await?;
+
+    let response = responses
+        .next()
+        .await
+        .ok_or(anyhow!("no response from the portal"))?;
+    let (code, results) = response.body::<(u32, HashMap<String, OwnedValue>)>()?;
+    match code {
+        0 => Ok(results),
+        1 => bail!("the shortcuts are cancelled by the user"),
+        _ => bail!("`{method}` of the portal failed"),
+    }
+}
+
+async fn listen(conn: Connection, handle: OwnedObjectPath) {
+    let activated = async {
+        let portal = Proxy::new(&conn, PORTAL_NAME, PORTAL_PATH, SHORTCUTS_IFACE).await?;
+        let mut signals = portal.receive_signal("Activated").await?;
+        while let Some(signal) = signals.next().await {
+            let Ok((session, id, _, _)) =
+                signal.body::<(OwnedObjectPath, String, u64, HashMap<String, OwnedValue>)>()
+            else {
+                continue;
+            };
+            if session != handle {
+                continue;
+            }
+            let Some((func, _)) = id.split_once(',') else {
+                continue;
+            };
+            match hotkey::function(func) {
+                Ok(f) => f(),
+                Err(err) => tracing::error!("{err}"),
+            }
+        }
+        zbus::Result::Ok(())
+    };
+    if let Err(err) = activated.await {
+        tracing::error!("failed to listen to the global shortcuts: {err}");
+    }
+}
+
+/// `CmdOrCtrl+Shift+P` to `CTRL+SHIFT+p` of the xdg shortcuts spec
+fn trigger(key: &str) -> String {
+    hotkey::normalize_key(key)
+        .split('+')
+        .map(|part| match part {
+            "CONTROL" => "CTRL".to_string(),
+            "SUPER" => "LOGO".to_string(),
+            "ALT" | "SHIFT" => part.to_string(),
+            code => {
+                // `KeyP` and `Digit1` of the accelerators
+                let code = code
+                    .strip_prefix("KEY")
+                    .or(code.strip_prefix("DIGIT"))
+                    .filter(|code| code.len() == 1)
+                    .unwrap_or(code);
+                match code.len() {
+                    1 => code.to_lowercase(),
+                    _ => code.to_string(),
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+#[test]
+fn test_trigger() {
+    assert_eq!(trigger("CmdOrCtrl+Shift+P"), "CTRL+SHIFT+p");
+    assert_eq!(trigger("Super+Alt+KeyD"), "ALT+LOGO+d");
+    assert_eq!(trigger("Ctrl+F1"), "CTRL+F1");
+}
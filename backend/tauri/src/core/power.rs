@@ -0,0 +1,247 @@
+//! recover after the system wakes up from sleep
+//! the wake comes from the notifications of the system: `PrepareForSleep` of logind on linux,
+//! the suspend/resume notification on windows and `NSWorkspaceDidWakeNotification` on macos,
+//! the gap of the wall clock between two ticks is the fallback if they can't be received
+use super::{
+    clash_api, handle, lazy_core::LazyCore, network, stream::StreamManager, sysopt::Sysopt,
+    tasks::jobs::ProfilesJobGuard, CoreManager,
+};
+use crate::{config::Config, log_err};
+use once_cell::sync::OnceCell;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime},
+};
+use tokio::time::sleep;
+
+const TICK: Duration = Duration::from_secs(10);
+/// a larger gap means the system was sleeping
+const WAKE_GAP: Duration = Duration::from_secs(30);
+/// wait for the network after waking up
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct PowerMonitor {
+    running: AtomicBool,
+}
+
+impl PowerMonitor {
+    pub fn global() -> &'static PowerMonitor {
+        static POWER_MONITOR: OnceCell<PowerMonitor> = OnceCell::new();
+
+        POWER_MONITOR.get_or_init(|| PowerMonitor {
+            running: AtomicBool::new(false),
+        })
+    }
+
+    pub fn start(&'static self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = platform::listen().await {
+                tracing::warn!(
+                    "failed to listen to the system wake, detect it by the clock: {err}"
+                );
+            }
+            self.watch_clock().await;
+        });
+    }
+
+    /// the timers don't run while the system sleeps, a large gap of the wall clock means a wake
+    async fn watch_clock(&self) {
+        let mut last = SystemTime::now();
+        loop {
+            sleep(TICK).await;
+
+            let now = SystemTime::now();
+            // 时钟被往回调的时候 duration_since 会失败
+            let gap = now.duration_since(last).unwrap_or_default();
+            last = now;
+
+            if is_wake(gap) {
+                tracing::info!("the system woke up after {}s", gap.as_secs());
+                self.on_wake().await;
+                last = SystemTime::now();
+            }
+        }
+    }
+
+    async fn on_wake(&self) {
+        // 网卡可能还没连上
+        let started = SystemTime::now();
+        while !network::is_network_up() {
+            if started.elapsed().unwrap_or_default() > NETWORK_TIMEOUT {
+                tracing::warn!("the network is still down after waking up");
+                break;
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+
+        // the core may be killed or hang while sleeping
+        // 按需启动的内核还没启动，不需要重启
+        let core = CoreManager::global();
+        if LazyCore::global().is_waiting() {
+            tracing::debug!("the core is waiting for the first connection, skip the restart");
+        } else if !core.is_running() || clash_api::get_version().await.is_err() {
+            tracing::info!("restart the core after waking up");
+            log_err!(core.run_core().await);
+            handle::Handle::refresh_clash();
+        }
+
+        // 有些系统唤醒后会重置代理设置
+        let system_proxy = { Config::verge().latest().enable_system_proxy };
+        if system_proxy.unwrap_or(false) {
+            log_err!(Sysopt::global().update_sysproxy());
+        }
+
+        // the sockets are usually dead but not closed yet
+        StreamManager::global().restart_all();
+
+        // the timers are delayed by the sleep
+        ProfilesJobGuard::global().lock().catch_up();
+
+        handle::Handle::emit("verge://system-woke", ());
+    }
+}
+
+/// called from the threads of the system notifications
+fn on_system_wake() {
+    tracing::info!("the system woke up");
+    tauri::async_runtime::spawn(PowerMonitor::global().on_wake());
+}
+
+/// return when the notifications can't be received any more
+#[cfg(target_os = "linux")]
+mod platform {
+    use anyhow::Result;
+    use futures_util::StreamExt;
+    use zbus::{Connection, Proxy};
+
+    pub async fn listen() -> Result<()> {
+        let conn = Connection::system().await?;
+        let manager = Proxy::new(
+            &conn,
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        )
+        .await?;
+        let mut signals = manager.receive_signal("PrepareForSleep").await?;
+        // true before sleeping, false after waking up
+        while let Some(signal) = signals.next().await {
+            if let Ok(false) = signal.body::<bool>() {
+                super::on_system_wake();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use anyhow::{bail, Result};
+    use std::ffi::c_void;
+    use windows_sys::Win32::{
+        Foundation::{ERROR_SUCCESS, HANDLE},
+        System::Power::{
+            PowerRegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+        },
+        UI::WindowsAndMessaging::{DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC},
+    };
+
+    unsafe extern "system" fn callback(
+        _context: *const c_void,
+        r#type: u32,
+        _setting: *const c_void,
+    ) -> u32 {
+        if r#type == PBT_APMRESUMEAUTOMATIC {
+            super::on_system_wake();
+        }
+        ERROR_SUCCESS
+    }
+
+    pub async fn listen() -> Result<()> {
+        register()?;
+        std::future::pending().await
+    }
+
+    /// the raw pointers are not `Send`, they must not live across the await
+    fn register() -> Result<()> {
+        // 注册一直有效，参数不能释放
+        let params = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+            Callback: Some(callback),
+            Context: std::ptr::null_mut(),
+        }));
+        let mut registration = std::ptr::null_mut();
+        let code = unsafe {
+            PowerRegisterSuspendResumeNotification(
+                DEVICE_NOTIFY_CALLBACK,
+                params as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as HANDLE,
+                &mut registration,
+            )
+        };
+        if code != ERROR_SUCCESS {
+            bail!("PowerRegisterSuspendResumeNotification failed with {code}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use anyhow::{bail, Result};
+    use block::ConcreteBlock;
+    use objc::{class, msg_send, runtime::Object, sel, sel_impl};
+    use std::ptr::null_mut;
+
+    pub async fn listen() -> Result<()> {
+        register()?;
+        std::future::pending().await
+    }
+
+    /// the observer and the block are not `Send`, they must not live across the await
+    fn register() -> Result<()> {
+        let observer: *mut Object = unsafe {
+            let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let center: *mut Object = msg_send![workspace, notificationCenter];
+            let name: *mut Object = msg_send![
+                class!(NSString),
+                stringWithUTF8String: b"NSWorkspaceDidWakeNotification\0".as_ptr()
+            ];
+            // the block is copied by the notification center
+            let block = ConcreteBlock::new(|_: *mut Object| super::on_system_wake()).copy();
+            msg_send![
+                center,
+                addObserverForName: name
+                object: null_mut::<Object>()
+                queue: null_mut::<Object>()
+                usingBlock: &*block
+            ]
+        };
+        if observer.is_null() {
+            bail!("failed to observe NSWorkspaceDidWakeNotification");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+mod platform {
+    use anyhow::{bail, Result};
+
+    pub async fn listen() -> Result<()> {
+        bail!("the wake notifications are not supported on this platform")
+    }
+}
+
+fn is_wake(gap: Duration) -> bool {
+    gap > TICK + WAKE_GAP
+}
+
+#[test]
+fn test_is_wake() {
+    assert!(!is_wake(TICK));
+    assert!(!is_wake(TICK + Duration::from_secs(5)));
+    assert!(is_wake(Duration::from_secs(3600)));
+}
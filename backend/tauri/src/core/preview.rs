@@ -0,0 +1,226 @@
+//! preview what the next `apply` will change in the running config
+use crate::{config::Config, enhance};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChange {
+    /// e.g. `dns.nameserver`, `proxies[HK 01]`, `rules[12]`
+    pub path: String,
+    pub kind: DiffKind,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigPreview {
+    pub changes: Vec<ConfigChange>,
+    /// script logs of the regenerated config
+    pub chain_logs: HashMap<String, Vec<(String, String)>>,
+}
+
+/// regenerate the config without touching the runtime one,
+/// then diff it against the currently loaded config
+pub fn preview() -> ConfigPreview {
+    let (config, _, chain_logs) = enhance::enhance();
+    let current = { Config::runtime().latest().config.clone() };
+    let current = current.unwrap_or_default();
+
+    let mut changes = vec![];
+    diff_mapping("", &current, &config, &mut changes);
+    ConfigPreview {
+        changes,
+        chain_logs,
+    }
+}
+
+fn key_str(key: &Value) -> String {
+    match key {
+        Value::String(s) => s.clone(),
+        _ => serde_yaml::to_string(key)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{path}.{key}"),
+    }
+}
+
+fn push(out: &mut Vec<ConfigChange>, path: String, before: Option<&Value>, after: Option<&Value>) {
+    let kind = match (before, after) {
+        (None, _) => DiffKind::Added,
+        (_, None) => DiffKind::Removed,
+        _ => DiffKind::Modified,
+    };
+    out.push(ConfigChange {
+        path,
+        kind,
+        before: before.cloned(),
+        after: after.cloned(),
+    });
+}
+
+fn diff_value(path: String, before: &Value, after: &Value, out: &mut Vec<ConfigChange>) {
+    if before == after {
+        return;
+    }
+    match (before, after) {
+        (Value::Mapping(a), Value::Mapping(b)) => diff_mapping(&path, a, b, out),
+        (Value::Sequence(a), Value::Sequence(b)) => diff_sequence(&path, a, b, out),
+        _ => push(out, path, Some(before), Some(after)),
+    }
+}
+
+fn diff_mapping(path: &str, before: &Mapping, after: &Mapping, out: &mut Vec<ConfigChange>) {
+    for (key, value) in before.iter() {
+        let sub = join(path, &key_str(key));
+        match after.get(key) {
+            Some(next) => diff_value(sub, value, next, out),
+            None => push(out, sub, Some(value), None),
+        }
+    }
+    for (key, value) in after.iter() {
+        if !before.contains_key(key) {
+            push(out, join(path, &key_str(key)), None, Some(value));
+        }
+    }
+}
+
+fn item_name(value: &Value) -> Option<&str> {
+    value.as_mapping()?.get("name")?.as_str()
+}
+
+fn named(list: &[Value]) -> Option<Vec<(&str, &Value)>> {
+    list.iter().map(|v| item_name(v).map(|n| (n, v))).collect()
+}
+
+fn diff_sequence(path: &str, before: &[Value], after: &[Value], out: &mut Vec<ConfigChange>) {
+    // proxies / proxy-groups 之类按 name 对比
+    if let (Some(a), Some(b)) = (named(before), named(after)) {
+        let a_map = a.iter().copied().collect::<HashMap<_, _>>();
+        let b_map = b.iter().copied().collect::<HashMap<_, _>>();
+        if a_map.len() == a.len() && b_map.len() == b.len() {
+            for (name, value) in a.iter() {
+                let sub = format!("{path}[{name}]");
+                match b_map.get(name) {
+                    Some(next) => diff_value(sub, value, next, out),
+                    None => push(out, sub, Some(value), None),
+                }
+            }
+            for (name, value) in b.iter() {
+                if !a_map.contains_key(name) {
+                    push(out, format!("{path}[{name}]"), None, Some(value));
+                }
+            }
+            // 两边都有的项顺序变了
+            let a_kept = a.iter().filter(|(n, _)| b_map.contains_key(n));
+            let b_kept = b.iter().filter(|(n, _)| a_map.contains_key(n));
+            if !a_kept.map(|(n, _)| n).eq(b_kept.map(|(n, _)| n)) {
+                push_reorder(path, before, after, out);
+            }
+            return;
+        }
+    }
+
+    // rules 之类按内容对比，重复项按次数计算
+    let mut counts = HashMap::<&Value, isize>::new();
+    after
+        .iter()
+        .for_each(|v| *counts.entry(v).or_default() += 1);
+    let mut changed = false;
+    for (index, value) in before.iter().enumerate() {
+        match counts.get_mut(value) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => {
+                changed = true;
+                push(out, format!("{path}[{index}]"), Some(value), None);
+            }
+        }
+    }
+    let mut counts = HashMap::<&Value, isize>::new();
+    before
+        .iter()
+        .for_each(|v| *counts.entry(v).or_default() += 1);
+    for (index, value) in after.iter().enumerate() {
+        match counts.get_mut(value) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => {
+                changed = true;
+                push(out, format!("{path}[{index}]"), None, Some(value));
+            }
+        }
+    }
+    // 内容一样只是顺序变了
+    if !changed {
+        push_reorder(path, before, after, out);
+    }
+}
+
+fn push_reorder(path: &str, before: &[Value], after: &[Value], out: &mut Vec<ConfigChange>) {
+    out.push(ConfigChange {
+        path: path.to_string(),
+        kind: DiffKind::Modified,
+        before: Some(Value::Sequence(before.to_vec())),
+        after: Some(Value::Sequence(after.to_vec())),
+    });
+}
+
+#[test]
+fn test_diff_config() {
+    let before = serde_yaml::from_str::<Mapping>(
+        r#"
+mode: rule
+dns: { enable: true, ipv6: false }
+proxies:
+  - { name: a, server: 1.1.1.1 }
+  - { name: b, server: 2.2.2.2 }
+rules: ["DOMAIN,a.com,DIRECT", "MATCH,a"]
+"#,
+    )
+    .unwrap();
+    let after = serde_yaml::from_str::<Mapping>(
+        r#"
+mode: global
+dns: { enable: true }
+proxies:
+  - { name: a, server: 1.0.0.1 }
+  - { name: c, server: 3.3.3.3 }
+rules: ["DOMAIN,a.com,DIRECT", "GEOIP,CN,DIRECT", "MATCH,a"]
+tun: { enable: true }
+"#,
+    )
+    .unwrap();
+
+    let mut changes = vec![];
+    diff_mapping("", &before, &after, &mut changes);
+    let list = changes
+        .iter()
+        .map(|c| (c.path.as_str(), c.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        list,
+        vec![
+            ("mode", DiffKind::Modified),
+            ("dns.ipv6", DiffKind::Removed),
+            ("proxies[a].server", DiffKind::Modified),
+            ("proxies[b]", DiffKind::Removed),
+            ("proxies[c]", DiffKind::Added),
+            ("rules[1]", DiffKind::Added),
+            ("tun", DiffKind::Added),
+        ]
+    );
+}
@@ -0,0 +1,157 @@
+//! list the running processes for the app rules picker
+//! the icons are only extracted on windows
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+use sysinfo::{ProcessExt, System, SystemExt};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessInfo {
+    /// the name used by `PROCESS-NAME`
+    pub name: String,
+    pub path: Option<String>,
+    /// the png data url of the icon
+    pub icon: Option<String>,
+}
+
+/// the processes with the same name are listed once
+pub fn list_processes() -> Vec<ProcessInfo> {
+    let mut system = System::new();
+    system.refresh_processes();
+
+    let mut processes = BTreeMap::new();
+    for process in system.processes().values() {
+        let name = process.name().to_string();
+        if name.is_empty() || processes.contains_key(&name.to_lowercase()) {
+            continue;
+        }
+        let path = Some(process.exe()).filter(|path| !path.as_os_str().is_empty());
+        processes.insert(
+            name.to_lowercase(),
+            ProcessInfo {
+                name,
+                path: path.map(|path| path.to_string_lossy().to_string()),
+                icon: path.and_then(cached_icon),
+            },
+        );
+    }
+    processes.into_values().collect()
+}
+
+/// the icons of the same executable do not change
+fn cached_icon(path: &Path) -> Option<String> {
+    static ICONS: OnceCell<Mutex<HashMap<PathBuf, Option<String>>>> = OnceCell::new();
+
+    let icons = ICONS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(icon) = icons.lock().get(path) {
+        return icon.clone();
+    }
+    let icon = icon_of(path);
+    icons.lock().insert(path.to_path_buf(), icon.clone());
+    icon
+}
+
+#[cfg(not(target_os = "windows"))]
+fn icon_of(_path: &Path) -> Option<String> {
+    None
+}
+
+/// the small icon of the executable, encoded as png
+#[cfg(target_os = "windows")]
+fn icon_of(path: &Path) -> Option<String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use image::{ImageOutputFormat, RgbaImage};
+    use std::{ffi::c_void, io::Cursor, mem, os::windows::ffi::OsStrExt, ptr};
+    use windows_sys::Win32::{
+        Graphics::Gdi::{
+            DeleteObject, GetDC, GetDIBits, GetObjectW, ReleaseDC, BITMAP, BITMAPINFO,
+            BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+        },
+        UI::{
+            Shell::ExtractIconExW,
+            WindowsAndMessaging::{DestroyIcon, GetIconInfo, HICON, ICONINFO},
+        },
+    };
+
+    let wide = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+
+    let pixels = unsafe {
+        let mut icon: HICON = 0;
+        if ExtractIconExW(wide.as_ptr(), 0, ptr::null_mut(), &mut icon, 1) == 0 || icon == 0 {
+            return None;
+        }
+        let mut info: ICONINFO = mem::zeroed();
+        if GetIconInfo(icon, &mut info) == 0 {
+            DestroyIcon(icon);
+            return None;
+        }
+
+        let mut bitmap: BITMAP = mem::zeroed();
+        GetObjectW(
+            info.hbmColor,
+            mem::size_of::<BITMAP>() as i32,
+            &mut bitmap as *mut _ as *mut c_void,
+        );
+        let (width, height) = (bitmap.bmWidth, bitmap.bmHeight);
+
+        let mut header: BITMAPINFO = mem::zeroed();
+        header.bmiHeader = BITMAPINFOHEADER {
+            biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // 负数表示从上到下
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            ..mem::zeroed()
+        };
+        let mut buffer = vec![0u8; (width * height * 4).max(0) as usize];
+        let dc = GetDC(0);
+        let lines = GetDIBits(
+            dc,
+            info.hbmColor,
+            0,
+            height as u32,
+            buffer.as_mut_ptr() as *mut c_void,
+            &mut header,
+            DIB_RGB_COLORS,
+        );
+        ReleaseDC(0, dc);
+
+        DeleteObject(info.hbmColor);
+        DeleteObject(info.hbmMask);
+        DestroyIcon(icon);
+
+        if lines == 0 || buffer.is_empty() {
+            return None;
+        }
+        (width as u32, height as u32, buffer)
+    };
+
+    let (width, height, mut buffer) = pixels;
+    // BGRA -> RGBA，旧的图标没有 alpha 通道
+    let opaque = buffer.chunks(4).all(|pixel| pixel[3] == 0);
+    for pixel in buffer.chunks_mut(4) {
+        pixel.swap(0, 2);
+        if opaque {
+            pixel[3] = 0xff;
+        }
+    }
+
+    let image = RgbaImage::from_raw(width, height, buffer)?;
+    let mut png = Cursor::new(vec![]);
+    image.write_to(&mut png, ImageOutputFormat::Png).ok()?;
+    Some(format!(
+        "data:image/png;base64,{}",
+        STANDARD.encode(png.into_inner())
+    ))
+}
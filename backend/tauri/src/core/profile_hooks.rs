@@ -0,0 +1,104 @@
+//! the hooks run after the timer updates a profile
+//!
+//! the hooks run in order and a failed one doesn't stop the rest. the results are attached to
+//! the update record, the failures also fail the task so that they show in the task history.
+
+use super::{
+    doctor::{self, CheckStatus},
+    handle,
+    notification::{NotificationAction, NotificationKind, Notifications},
+    CoreManager,
+};
+use crate::{
+    config::{Config, IProfiles, ProfileHook, ProfileHookResult},
+    enhance, log_err,
+    utils::i18n::{t, t_args},
+};
+use anyhow::{bail, Result};
+
+/// run the hooks of the profile, fail if any of them failed
+pub async fn run(uid: &str) -> Result<()> {
+    let (name, hooks) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        let item = profiles.get_item(&uid.to_string())?;
+        let hooks = item.option.as_ref().and_then(|option| option.hooks.clone());
+        (item.name.clone().unwrap_or_default(), hooks)
+    };
+    let Some(hooks) = hooks.filter(|hooks| !hooks.is_empty()) else {
+        return Ok(());
+    };
+
+    let mut results: Vec<ProfileHookResult> = Vec::with_capacity(hooks.len());
+    for hook in hooks {
+        let failed = results.iter().filter(|res| res.error.is_some()).count();
+        let error = run_hook(&hook, uid, &name, failed).await.err().map(|err| {
+            tracing::warn!("the hook {hook:?} of the profile `{uid}` failed: {err:#}");
+            format!("{err:#}")
+        });
+        results.push(ProfileHookResult { hook, error });
+    }
+
+    let errors = results
+        .iter()
+        .filter_map(|res| res.error.clone())
+        .collect::<Vec<_>>();
+    let total = results.len();
+    log_err!(IProfiles::record_hooks(uid, results));
+    handle::Handle::refresh_profiles();
+
+    if !errors.is_empty() {
+        bail!(
+            "{} of {total} hooks failed: {}",
+            errors.len(),
+            errors.join("; ")
+        );
+    }
+    Ok(())
+}
+
+async fn run_hook(hook: &ProfileHook, uid: &str, name: &str, failed: usize) -> Result<()> {
+    match hook {
+        ProfileHook::Notify => {
+            let body = match failed {
+                0 => t_args("The profile \"{name}\" is updated", &[("name", &name)]),
+                _ => t_args(
+                    "The profile \"{name}\" is updated, {failed} hooks failed",
+                    &[("name", &name), ("failed", &failed)],
+                ),
+            };
+            Notifications::global().push(
+                NotificationKind::ProfileUpdated,
+                t("Profile Updated"),
+                body,
+                Some(NotificationAction::OpenPage("/profile".into())),
+            );
+            Ok(())
+        }
+        ProfileHook::RunChain { chain } => {
+            let config = { Config::profiles().latest().item_mapping(&uid.to_string())? };
+            let chain = chain.clone();
+            let logs =
+                tokio::task::spawn_blocking(move || enhance::run_chain(config, &chain)).await??;
+            tracing::debug!("the hook chain of the profile `{uid}` logs: {logs:?}");
+            Ok(())
+        }
+        ProfileHook::RestartCore => {
+            CoreManager::global().run_core().await?;
+            handle::Handle::refresh_clash();
+            Ok(())
+        }
+        ProfileHook::ConnectivityCheck => {
+            let failed = doctor::run()
+                .await
+                .into_iter()
+                .filter(|check| check.status == CheckStatus::Fail)
+                .map(|check| format!("{}: {}", check.id, check.detail))
+                .collect::<Vec<_>>();
+            if !failed.is_empty() {
+                bail!("the connectivity check failed, {}", failed.join(", "));
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellType {
+    /// bash, zsh and other posix shells
+    #[serde(alias = "bash", alias = "zsh")]
+    Sh,
+    Fish,
+    #[serde(alias = "ps", alias = "pwsh")]
+    Powershell,
+    Cmd,
+}
+
+impl ShellType {
+    /// the shell used by default on the platform
+    pub fn platform_default() -> Self {
+        match cfg!(target_os = "windows") {
+            true => Self::Powershell,
+            false => Self::Sh,
+        }
+    }
+}
+
+/// the proxy environment variables of the mixed port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProxyEnv {
+    pub http_proxy: String,
+    pub all_proxy: String,
+    pub no_proxy: String,
+}
+
+impl ProxyEnv {
+    pub fn current() -> Self {
+        let (port, no_proxy) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge
+                    .verge_mixed_port
+                    .unwrap_or(Config::clash().data().get_mixed_port()),
+                verge.get_no_proxy(),
+            )
+        };
+        Self::new(port, no_proxy)
+    }
+
+    pub fn new(port: u16, no_proxy: String) -> Self {
+        Self {
+            http_proxy: format!("http://127.0.0.1:{port}"),
+            all_proxy: format!("socks5://127.0.0.1:{port}"),
+            no_proxy,
+        }
+    }
+
+    /// both the lowercase and uppercase names, tools read one of them
+    fn vars(&self) -> Vec<(&'static str, &str)> {
+        let mut vars = vec![
+            ("http_proxy", self.http_proxy.as_str()),
+            ("https_proxy", self.http_proxy.as_str()),
+            ("all_proxy", self.all_proxy.as_str()),
+        ];
+        if !self.no_proxy.is_empty() {
+            vars.push(("no_proxy", self.no_proxy.as_str()));
+        }
+
+        let upper = [
+            ("HTTP_PROXY", self.http_proxy.as_str()),
+            ("HTTPS_PROXY", self.http_proxy.as_str()),
+            ("ALL_PROXY", self.all_proxy.as_str()),
+            ("NO_PROXY", self.no_proxy.as_str()),
+        ];
+        vars.extend(upper.into_iter().take(vars.len()));
+        vars
+    }
+
+    pub fn render(&self, shell: ShellType) -> String {
+        let vars = self.vars();
+
+        match shell {
+            ShellType::Sh => {
+                let pairs = vars
+                    .iter()
+                    .map(|(k, v)| format!("{k}=\"{v}\""))
+                    .collect::<Vec<_>>();
+                format!("export {}", pairs.join(" "))
+            }
+            ShellType::Fish => vars
+                .iter()
+                .map(|(k, v)| format!("set -gx {k} \"{v}\""))
+                .collect::<Vec<_>>()
+                .join("; "),
+            // powershell 的环境变量不区分大小写
+            ShellType::Powershell => vars
+                .iter()
+                .filter(|(k, _)| k.chars().all(|c| !c.is_ascii_lowercase()))
+                .map(|(k, v)| format!("$env:{k}=\"{v}\""))
+                .collect::<Vec<_>>()
+                .join("; "),
+            // cmd 的 `set` 会把 `&&` 前的空格也算进值里，只能分行
+            ShellType::Cmd => vars
+                .iter()
+                .filter(|(k, _)| k.chars().all(|c| !c.is_ascii_lowercase()))
+                .map(|(k, v)| format!("set {k}={v}"))
+                .collect::<Vec<_>>()
+                .join("\r\n"),
+        }
+    }
+}
+
+#[test]
+fn test_render_proxy_env() {
+    let env = ProxyEnv::new(7890, "localhost,.local".into());
+
+    assert_eq!(
+        env.render(ShellType::Sh),
+        "export http_proxy=\"http://127.0.0.1:7890\" https_proxy=\"http://127.0.0.1:7890\" all_proxy=\"socks5://127.0.0.1:7890\" no_proxy=\"localhost,.local\" HTTP_PROXY=\"http://127.0.0.1:7890\" HTTPS_PROXY=\"http://127.0.0.1:7890\" ALL_PROXY=\"socks5://127.0.0.1:7890\" NO_PROXY=\"localhost,.local\""
+    );
+    assert_eq!(
+        env.render(ShellType::Cmd),
+        "set HTTP_PROXY=http://127.0.0.1:7890\r\nset HTTPS_PROXY=http://127.0.0.1:7890\r\nset ALL_PROXY=socks5://127.0.0.1:7890\r\nset NO_PROXY=localhost,.local"
+    );
+
+    let env = ProxyEnv::new(7890, String::new());
+    assert_eq!(
+        env.render(ShellType::Powershell),
+        "$env:HTTP_PROXY=\"http://127.0.0.1:7890\"; $env:HTTPS_PROXY=\"http://127.0.0.1:7890\"; $env:ALL_PROXY=\"socks5://127.0.0.1:7890\""
+    );
+}
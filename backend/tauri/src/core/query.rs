@@ -0,0 +1,349 @@
+//! the paginated and filtered queries of the large datasets
+//! the proxies and the connections are filtered in rust, the webview only holds a page
+
+use super::clash_api::{self, ConnectionItem, ProxyItem};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageQuery {
+    /// starts from 0
+    #[serde(default)]
+    pub page: usize,
+    pub page_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// the count after filtering
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+pub(super) fn paginate<T>(items: Vec<T>, query: &PageQuery) -> Page<T> {
+    let page_size = query
+        .page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let total = items.len();
+    let items = items
+        .into_iter()
+        .skip(query.page.saturating_mul(page_size))
+        .take(page_size)
+        .collect();
+
+    Page {
+        items,
+        total,
+        page: query.page,
+        page_size,
+    }
+}
+
+fn contains_ignore_case(text: &str, keyword: &str) -> bool {
+    text.to_lowercase().contains(keyword)
+}
+
+/// the proxy without the member list and the delay history
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyRow {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    pub udp: bool,
+    /// the selected proxy of the group
+    pub now: Option<String>,
+    /// the member count of the group
+    pub size: Option<usize>,
+    /// the latest delay, 0 means timeout
+    pub delay: Option<u64>,
+}
+
+impl From<&ProxyItem> for ProxyRow {
+    fn from(item: &ProxyItem) -> Self {
+        Self {
+            name: item.name.clone(),
+            r#type: item.r#type.clone(),
+            udp: item.udp,
+            now: item.now.clone(),
+            size: item.all.as_ref().map(|all| all.len()),
+            delay: item.history.last().map(|history| history.delay),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProxyFilter {
+    /// case insensitive keyword of the name
+    pub keyword: Option<String>,
+    /// only the members of the group, in the order of the group
+    pub group: Option<String>,
+    /// e.g. `Shadowsocks` `Selector`, case insensitive
+    pub r#type: Option<String>,
+    pub udp: Option<bool>,
+    /// only the proxies whose latest delay test succeeded
+    pub alive: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxySortKey {
+    Name,
+    Type,
+    /// the untested and the timeout ones are always the last
+    Delay,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ProxySort {
+    pub key: ProxySortKey,
+    #[serde(default)]
+    pub desc: bool,
+}
+
+/// GET /proxies then filter, sort and paginate
+pub async fn list_proxies(
+    page: PageQuery,
+    filter: Option<ProxyFilter>,
+    sort: Option<ProxySort>,
+) -> Result<Page<ProxyRow>> {
+    let proxies = clash_api::get_proxies().await?.proxies;
+    let filter = filter.unwrap_or_default();
+
+    let mut rows = match &filter.group {
+        Some(group) => proxies
+            .get(group)
+            .and_then(|group| group.all.as_ref())
+            .map(|all| {
+                all.iter()
+                    .filter_map(|name| proxies.get(name))
+                    .map(ProxyRow::from)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default(),
+        None => {
+            // HashMap 没有顺序，默认按名字排
+            let mut rows = proxies.values().map(ProxyRow::from).collect::<Vec<_>>();
+            rows.sort_by(|a, b| a.name.cmp(&b.name));
+            rows
+        }
+    };
+
+    rows.retain(|row| filter.matches(row));
+    if let Some(sort) = sort {
+        sort_proxies(&mut rows, sort);
+    }
+    Ok(paginate(rows, &page))
+}
+
+impl ProxyFilter {
+    fn matches(&self, row: &ProxyRow) -> bool {
+        if let Some(keyword) = self.keyword.as_ref().filter(|k| !k.is_empty()) {
+            if !contains_ignore_case(&row.name, &keyword.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(r#type) = &self.r#type {
+            if !row.r#type.eq_ignore_ascii_case(r#type) {
+                return false;
+            }
+        }
+        if self.udp.is_some_and(|udp| udp != row.udp) {
+            return false;
+        }
+        if let Some(alive) = self.alive {
+            let is_alive = row.delay.is_some_and(|delay| delay > 0);
+            if alive != is_alive {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn sort_proxies(rows: &mut [ProxyRow], sort: ProxySort) {
+    rows.sort_by(|a, b| {
+        let ordering = match sort.key {
+            ProxySortKey::Name => a.name.cmp(&b.name),
+            ProxySortKey::Type => a.r#type.cmp(&b.r#type),
+            ProxySortKey::Delay => {
+                let delay = |row: &ProxyRow| row.delay.filter(|delay| *delay > 0);
+                match (delay(a), delay(b)) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    // 不可用的始终排在最后，不受 desc 影响
+                    (Some(_), None) => return Ordering::Less,
+                    (None, Some(_)) => return Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
+        };
+        match sort.desc {
+            true => ordering.reverse(),
+            false => ordering,
+        }
+    });
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConnectionFilter {
+    /// case insensitive keyword of the host, the ip, the process, the chains and the rule
+    pub keyword: Option<String>,
+    /// `tcp` or `udp`
+    pub network: Option<String>,
+    pub process: Option<String>,
+    pub rule: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionSortKey {
+    Start,
+    Upload,
+    Download,
+    Host,
+    Process,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ConnectionSort {
+    pub key: ConnectionSortKey,
+    #[serde(default)]
+    pub desc: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionsPage {
+    #[serde(flatten)]
+    pub page: Page<ConnectionItem>,
+    pub upload_total: u64,
+    pub download_total: u64,
+}
+
+/// GET /connections then filter, sort and paginate
+pub async fn list_connections(
+    page: PageQuery,
+    filter: Option<ConnectionFilter>,
+    sort: Option<ConnectionSort>,
+) -> Result<ConnectionsPage> {
+    let res = clash_api::get_connections().await?;
+    let filter = filter.unwrap_or_default();
+
+    let mut items = res.connections.unwrap_or_default();
+    items.retain(|item| filter.matches(item));
+    if let Some(sort) = sort {
+        sort_connections(&mut items, sort);
+    }
+
+    Ok(ConnectionsPage {
+        page: paginate(items, &page),
+        upload_total: res.upload_total,
+        download_total: res.download_total,
+    })
+}
+
+impl ConnectionFilter {
+    fn matches(&self, item: &ConnectionItem) -> bool {
+        let metadata = &item.metadata;
+        if let Some(keyword) = self.keyword.as_ref().filter(|k| !k.is_empty()) {
+            let keyword = keyword.to_lowercase();
+            let matched = [
+                metadata.host.as_str(),
+                metadata.destination_ip.as_str(),
+                metadata.process.as_deref().unwrap_or_default(),
+                item.rule.as_str(),
+                item.rule_payload.as_str(),
+            ]
+            .into_iter()
+            .chain(item.chains.iter().map(|s| s.as_str()))
+            .any(|text| contains_ignore_case(text, &keyword));
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(network) = &self.network {
+            if !metadata.network.eq_ignore_ascii_case(network) {
+                return false;
+            }
+        }
+        if let Some(process) = &self.process {
+            if metadata.process.as_deref() != Some(process.as_str()) {
+                return false;
+            }
+        }
+        if let Some(rule) = &self.rule {
+            if &item.rule != rule {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn sort_connections(items: &mut [ConnectionItem], sort: ConnectionSort) {
+    // start 是 RFC3339 格式，可以直接按字符串比较
+    items.sort_by(|a, b| {
+        let ordering = match sort.key {
+            ConnectionSortKey::Start => a.start.cmp(&b.start),
+            ConnectionSortKey::Upload => a.upload.cmp(&b.upload),
+            ConnectionSortKey::Download => a.download.cmp(&b.download),
+            ConnectionSortKey::Host => {
+                let host = |item: &ConnectionItem| match item.metadata.host.is_empty() {
+                    true => item.metadata.destination_ip.clone(),
+                    false => item.metadata.host.clone(),
+                };
+                host(a).cmp(&host(b))
+            }
+            ConnectionSortKey::Process => a.metadata.process.cmp(&b.metadata.process),
+        };
+        match sort.desc {
+            true => ordering.reverse(),
+            false => ordering,
+        }
+    });
+}
+
+#[test]
+fn test_query_proxies() {
+    let row = |name: &str, delay: Option<u64>| ProxyRow {
+        name: name.into(),
+        r#type: "Shadowsocks".into(),
+        udp: true,
+        now: None,
+        size: None,
+        delay,
+    };
+    let mut rows = vec![row("b", Some(0)), row("a", Some(120)), row("c", Some(80))];
+
+    let filter = ProxyFilter {
+        alive: Some(true),
+        ..ProxyFilter::default()
+    };
+    assert_eq!(rows.iter().filter(|r| filter.matches(r)).count(), 2);
+
+    sort_proxies(
+        &mut rows,
+        ProxySort {
+            key: ProxySortKey::Delay,
+            desc: true,
+        },
+    );
+    let names = rows.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["a", "c", "b"]);
+
+    let page = paginate(
+        (0..250).collect::<Vec<_>>(),
+        &PageQuery {
+            page: 2,
+            page_size: None,
+        },
+    );
+    assert_eq!(page.total, 250);
+    assert_eq!(page.items.len(), 50);
+    assert_eq!(page.items[0], 200);
+}
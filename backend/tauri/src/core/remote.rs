@@ -0,0 +1,503 @@
+//! serve a management page to the lan, so a headless box can be managed in the browser
+//!
+//! the page is a single html file calling the json api below,
+//! the actions are the same as the control api and the cli.
+
+use super::{
+    clash_api,
+    control::{self, Request, Response},
+    lan,
+};
+use crate::{
+    config::{Config, RemoteAccess},
+    utils::{help, secrets, server::LocalServer},
+};
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+use warp::{http::StatusCode, reject::Reject, Filter, Rejection, Reply};
+
+pub const DEFAULT_PORT: u16 = 33334;
+const PASSWORD_KEY: &str = "remote::password";
+
+/// the failed logins of an address within the window before it is blocked
+const MAX_FAILURES: u32 = 5;
+const FAILURE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+const INDEX_HTML: &str = include_str!("remote.html");
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+#[derive(Debug)]
+struct Forbidden;
+
+impl Reject for Forbidden {}
+
+#[derive(Debug)]
+struct TooManyAttempts;
+
+impl Reject for TooManyAttempts {}
+
+#[derive(Debug)]
+struct CrossOrigin;
+
+impl Reject for CrossOrigin {}
+
+#[derive(Debug)]
+struct NotJson;
+
+impl Reject for NotJson {}
+
+/// the writes without parameters still take a json body, e.g. `{}`
+#[derive(Deserialize)]
+struct EmptyBody {}
+
+#[derive(Deserialize)]
+struct SwitchProfileBody {
+    uid: String,
+}
+
+#[derive(Deserialize)]
+struct SelectProxyBody {
+    group: String,
+    node: String,
+}
+
+#[derive(Deserialize)]
+struct SystemProxyBody {
+    enable: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteProfile {
+    pub uid: String,
+    pub name: Option<String>,
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteGroup {
+    pub name: String,
+    pub r#type: String,
+    pub now: Option<String>,
+    pub all: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteManagementInfo {
+    pub enable: bool,
+    pub access: RemoteAccess,
+    pub has_password: bool,
+    /// e.g. `http://192.168.1.2:33334`
+    pub urls: Vec<String>,
+}
+
+/// the password is kept in the os keyring, not in the `verge.yaml`
+/// an empty password removes it, the read-only page is open to the lan then
+pub fn set_password(password: &str) -> Result<()> {
+    match password.is_empty() {
+        true => secrets::delete(PASSWORD_KEY),
+        false => secrets::set(PASSWORD_KEY, password),
+    }
+}
+
+fn get_password() -> Result<Option<String>> {
+    Ok(secrets::get(PASSWORD_KEY)?.filter(|password| !password.is_empty()))
+}
+
+pub fn get_info() -> Result<RemoteManagementInfo> {
+    let (enable, port, access) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.enable_remote_management.unwrap_or(false),
+            verge.remote_management_port.unwrap_or(DEFAULT_PORT),
+            verge.remote_management_access.unwrap_or_default(),
+        )
+    };
+    let urls = lan::lan_ips()?
+        .into_iter()
+        .map(|ip| format!("http://{ip}:{port}"))
+        .collect();
+
+    Ok(RemoteManagementInfo {
+        enable,
+        access,
+        has_password: get_password()?.is_some(),
+        urls,
+    })
+}
+
+pub struct RemoteManagement {
+    server: LocalServer,
+}
+
+impl RemoteManagement {
+    pub fn global() -> &'static RemoteManagement {
+        static REMOTE_MANAGEMENT: OnceCell<RemoteManagement> = OnceCell::new();
+
+        REMOTE_MANAGEMENT.get_or_init(|| RemoteManagement {
+            server: LocalServer::default(),
+        })
+    }
+
+    /// apply the latest config, stop the server if disabled
+    pub async fn restart(&self) -> Result<()> {
+        self.stop().await;
+
+        let (enable, port, access) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_remote_management.unwrap_or(false),
+                verge.remote_management_port.unwrap_or(DEFAULT_PORT),
+                verge.remote_management_access.unwrap_or_default(),
+            )
+        };
+        if !enable {
+            return Ok(());
+        }
+
+        let password = get_password()?;
+        // 可写的模式必须设置密码
+        if access == RemoteAccess::ReadWrite && password.is_none() {
+            bail!("set a password before enabling the read-write remote management");
+        }
+
+        let addr = self
+            .server
+            .start(|rx| {
+                warp::serve(routes(access, password)).try_bind_with_graceful_shutdown(
+                    ([0, 0, 0, 0], port),
+                    async {
+                        rx.await.ok();
+                    },
+                )
+            })
+            .await?;
+
+        tracing::info!("remote management is listening on {addr}, {access:?}");
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        if self.server.stop().await {
+            tracing::info!("remote management is stopped");
+        }
+    }
+}
+
+fn list_profiles() -> Result<Value> {
+    let profiles = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        let current = profiles.get_current();
+        profiles
+            .get_items()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                let uid = item.uid.clone()?;
+                Some(RemoteProfile {
+                    current: current.as_ref() == Some(&uid),
+                    uid,
+                    name: item.name.clone(),
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    Ok(serde_json::to_value(profiles)?)
+}
+
+/// only the groups, in the order of the `GLOBAL` group like the dashboard, `GLOBAL` is the last
+async fn list_groups() -> Result<Value> {
+    let mut proxies = clash_api::get_proxies().await?.proxies;
+    let order = proxies
+        .get("GLOBAL")
+        .and_then(|global| global.all.clone())
+        .unwrap_or_default();
+
+    let groups = order
+        .iter()
+        .filter_map(|name| proxies.remove(name))
+        .chain(proxies.remove("GLOBAL"))
+        .filter_map(|item| {
+            Some(RemoteGroup {
+                all: item.all?,
+                name: item.name,
+                r#type: item.r#type,
+                now: item.now,
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(serde_json::to_value(groups)?)
+}
+
+fn reply(result: Result<Value>) -> warp::reply::Response {
+    let response = Response::from(result);
+    let status = match response.ok {
+        true => StatusCode::OK,
+        false => StatusCode::BAD_REQUEST,
+    };
+    warp::reply::with_status(warp::reply::json(&response), status).into_response()
+}
+
+fn routes(
+    access: RemoteAccess,
+    password: Option<String>,
+) -> impl Filter<Extract = impl Reply, Error = Infallible> + Clone {
+    let index = warp::path::end()
+        .and(warp::get())
+        .map(|| warp::reply::html(INDEX_HTML).into_response());
+
+    let status = warp::path!("api" / "status")
+        .and(warp::get())
+        .then(|| async { reply(control::execute(Request::Status).await) });
+
+    let access_info = warp::path!("api" / "access")
+        .and(warp::get())
+        .map(move || reply(Ok(serde_json::json!({ "access": access }))));
+
+    let profiles = warp::path!("api" / "profiles")
+        .and(warp::get())
+        .map(|| reply(list_profiles()));
+
+    let groups = warp::path!("api" / "proxies")
+        .and(warp::get())
+        .then(|| async { reply(list_groups().await) });
+
+    let update_profile = warp::path!("api" / "profiles" / String / "update")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(|uid, _: EmptyBody| Request::UpdateProfile { uid });
+
+    let switch_profile = warp::path!("api" / "profiles" / "current")
+        .and(warp::put())
+        .and(warp::body::json())
+        .map(|body: SwitchProfileBody| Request::SwitchProfile { uid: body.uid });
+
+    let restart_core = warp::path!("api" / "core" / "restart")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(|_: EmptyBody| Request::RestartCore);
+
+    let select_proxy = warp::path!("api" / "proxies")
+        .and(warp::put())
+        .and(warp::body::json())
+        .map(|body: SelectProxyBody| Request::SelectProxy {
+            group: body.group,
+            node: body.node,
+        });
+
+    let system_proxy = warp::path!("api" / "system-proxy")
+        .and(warp::put())
+        .and(warp::body::json())
+        .map(|body: SystemProxyBody| Request::SetSystemProxy {
+            enable: body.enable,
+        });
+
+    let writes = update_profile
+        .or(switch_profile)
+        .unify()
+        .or(restart_core)
+        .unify()
+        .or(select_proxy)
+        .unify()
+        .or(system_proxy)
+        .unify()
+        .and(writable(access))
+        .and(same_origin())
+        .and(json_content())
+        .then(|request: Request| async move { reply(control::execute(request).await) });
+
+    let routes = index
+        .or(status)
+        .unify()
+        .or(access_info)
+        .unify()
+        .or(profiles)
+        .unify()
+        .or(groups)
+        .unify()
+        .or(writes)
+        .unify();
+
+    auth(password).and(routes).recover(recover)
+}
+
+fn writable(access: RemoteAccess) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::any()
+        .and_then(move || async move {
+            match access {
+                RemoteAccess::ReadWrite => Ok(()),
+                RemoteAccess::ReadOnly => Err(warp::reject::custom(Forbidden)),
+            }
+        })
+        .untuple_one()
+}
+
+/// a page of another site can post to the lan address,
+/// so the writes must come from the page itself
+fn same_origin() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("origin")
+        .and(warp::header::optional::<String>("host"))
+        .and_then(|origin: Option<String>, host: Option<String>| async move {
+            match is_same_origin(origin.as_deref(), host.as_deref()) {
+                true => Ok(()),
+                false => Err(warp::reject::custom(CrossOrigin)),
+            }
+        })
+        .untuple_one()
+}
+
+/// no origin is sent by the non-browser clients, e.g. curl
+fn is_same_origin(origin: Option<&str>, host: Option<&str>) -> bool {
+    match (origin, host) {
+        (None, _) => true,
+        (Some(origin), Some(host)) => origin
+            .strip_prefix("http://")
+            .map_or(false, |origin| origin.eq_ignore_ascii_case(host)),
+        (Some(_), None) => false,
+    }
+}
+
+/// a form of another site can't send json without the cors preflight, which is never allowed
+fn json_content() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("content-type")
+        .and_then(|content_type: Option<String>| async move {
+            let is_json = content_type
+                .as_deref()
+                .and_then(|content_type| content_type.split(';').next())
+                .map_or(false, |mime| {
+                    mime.trim().eq_ignore_ascii_case("application/json")
+                });
+            match is_json {
+                true => Ok(()),
+                false => Err(warp::reject::custom(NotJson)),
+            }
+        })
+        .untuple_one()
+}
+
+/// the failed logins by address, reset after the window
+fn failures() -> &'static Mutex<HashMap<IpAddr, (u32, Instant)>> {
+    static FAILURES: OnceCell<Mutex<HashMap<IpAddr, (u32, Instant)>>> = OnceCell::new();
+    FAILURES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn check_basic_auth(header: Option<&str>, password: &str) -> bool {
+    header
+        .and_then(|auth| auth.strip_prefix("Basic "))
+        .and_then(|auth| STANDARD.decode(auth.trim()).ok())
+        .and_then(|auth| String::from_utf8(auth).ok())
+        // 用户名随意，只校验密码
+        .and_then(|auth| {
+            auth.split_once(':')
+                .map(|(_, p)| help::constant_time_eq(p.as_bytes(), password.as_bytes()))
+        })
+        .unwrap_or(false)
+}
+
+/// the http basic auth, the browser asks the password
+fn auth(password: Option<String>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(move |addr: Option<SocketAddr>, header: Option<String>| {
+            let result = match &password {
+                None => Ok(()),
+                Some(password) => {
+                    let ip = addr.map(|addr| addr.ip());
+                    let mut failures = failures().lock();
+                    failures.retain(|_, (_, since)| since.elapsed() < FAILURE_WINDOW);
+
+                    let blocked = ip
+                        .and_then(|ip| failures.get(&ip))
+                        .map_or(false, |(count, _)| *count >= MAX_FAILURES);
+                    if blocked {
+                        Err(warp::reject::custom(TooManyAttempts))
+                    } else if check_basic_auth(header.as_deref(), password) {
+                        Ok(())
+                    } else {
+                        // 浏览器第一次请求不带认证头，不计入失败次数
+                        if let (Some(ip), Some(_)) = (ip, &header) {
+                            failures.entry(ip).or_insert((0, Instant::now())).0 += 1;
+                        }
+                        Err(warp::reject::custom(Unauthorized))
+                    }
+                }
+            };
+            async move { result }
+        })
+        .untuple_one()
+}
+
+async fn recover(err: Rejection) -> Result<warp::reply::Response, Infallible> {
+    let (status, message) = if err.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "unauthorized")
+    } else if err.find::<TooManyAttempts>().is_some() {
+        (StatusCode::TOO_MANY_REQUESTS, "too many failed attempts")
+    } else if err.find::<Forbidden>().is_some() {
+        (StatusCode::FORBIDDEN, "the remote management is read-only")
+    } else if err.find::<CrossOrigin>().is_some() {
+        (StatusCode::FORBIDDEN, "cross-origin request")
+    } else if err.find::<NotJson>().is_some() {
+        (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "the content type must be application/json",
+        )
+    } else if err.is_not_found() {
+        (StatusCode::NOT_FOUND, "not found")
+    } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED, "method not allowed")
+    } else {
+        (StatusCode::BAD_REQUEST, "bad request")
+    };
+
+    let response = Response::from(Err(anyhow!(message)));
+    let reply = warp::reply::with_status(warp::reply::json(&response), status);
+    Ok(match status {
+        StatusCode::UNAUTHORIZED => warp::reply::with_header(
+            reply,
+            "www-authenticate",
+            "Basic realm=\"Clash Nyanpasu\", charset=\"UTF-8\"",
+        )
+        .into_response(),
+        _ => reply.into_response(),
+    })
+}
+
+#[test]
+fn test_check_basic_auth() {
+    let header = format!("Basic {}", STANDARD.encode("admin:p@ss:word"));
+    assert!(check_basic_auth(Some(&header), "p@ss:word"));
+    assert!(!check_basic_auth(Some(&header), "p@ss"));
+    assert!(!check_basic_auth(Some("Bearer p@ss:word"), "p@ss:word"));
+    assert!(!check_basic_auth(None, "p@ss:word"));
+}
+
+#[test]
+fn test_is_same_origin() {
+    assert!(is_same_origin(None, Some("192.168.1.2:33334")));
+    assert!(is_same_origin(
+        Some("http://192.168.1.2:33334"),
+        Some("192.168.1.2:33334")
+    ));
+    assert!(!is_same_origin(
+        Some("http://evil.example"),
+        Some("192.168.1.2:33334")
+    ));
+    assert!(!is_same_origin(Some("null"), Some("192.168.1.2:33334")));
+    assert!(!is_same_origin(Some("http://192.168.1.2:33334"), None));
+}
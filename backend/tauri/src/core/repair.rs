@@ -0,0 +1,254 @@
+//! fix the most common broken installs in one click
+//! each step is independent, a failed step doesn't stop the others
+use super::{handle, updater::Updater};
+use crate::{
+    config::{ClashCore, IClashTemp, IProfiles, IVerge},
+    utils::{dirs, help, init},
+};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::{fs, path::PathBuf};
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+
+const CORES: [ClashCore; 4] = [
+    ClashCore::ClashPremium,
+    ClashCore::ClashRs,
+    ClashCore::Mihomo,
+    ClashCore::MihomoAlpha,
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepairStep {
+    pub id: &'static str,
+    pub ok: bool,
+    /// what was done, or why it failed
+    pub detail: String,
+}
+
+impl RepairStep {
+    fn new(id: &'static str, result: Result<String>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                id,
+                ok: true,
+                detail,
+            },
+            Err(err) => {
+                tracing::warn!("repair step `{id}` failed: {err}");
+                Self {
+                    id,
+                    ok: false,
+                    detail: err.to_string(),
+                }
+            }
+        }
+    }
+}
+
+pub fn repair_installation() -> Vec<RepairStep> {
+    let steps = vec![
+        RepairStep::new("data_dirs", repair_data_dirs()),
+        RepairStep::new("resources", repair_resources()),
+        RepairStep::new("cores", repair_cores()),
+        RepairStep::new("permissions", repair_permissions()),
+        RepairStep::new("stale_pid", clear_stale_pid()),
+        RepairStep::new("url_scheme", register_url_scheme()),
+    ];
+    tracing::info!(
+        "repair finished, {} of {} steps succeeded",
+        steps.iter().filter(|step| step.ok).count(),
+        steps.len()
+    );
+    steps
+}
+
+/// recreate the missing dirs and config files
+fn repair_data_dirs() -> Result<String> {
+    let mut created = vec![];
+
+    for dir in [
+        dirs::app_home_dir()?,
+        dirs::app_profiles_dir()?,
+        dirs::app_logs_dir()?,
+        dirs::app_crashes_dir()?,
+    ] {
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+            created.push(dir);
+        }
+    }
+
+    let clash_path = dirs::clash_path()?;
+    if !clash_path.exists() {
+        help::save_yaml(
+            &clash_path,
+            &IClashTemp::template().0,
+            Some("# Clash Nyanpasu"),
+        )?;
+        created.push(clash_path);
+    }
+    let verge_path = dirs::verge_path()?;
+    if !verge_path.exists() {
+        help::save_yaml(&verge_path, &IVerge::template(), Some("# Clash Nyanpasu"))?;
+        created.push(verge_path);
+    }
+    let profiles_path = dirs::profiles_path()?;
+    if !profiles_path.exists() {
+        help::save_yaml(
+            &profiles_path,
+            &IProfiles::template(),
+            Some("# Clash Nyanpasu"),
+        )?;
+        created.push(profiles_path);
+    }
+
+    Ok(match created.is_empty() {
+        true => "nothing is missing".into(),
+        false => format!("created {}", join_paths(&created)),
+    })
+}
+
+/// overwrite the geo databases and the other resources with the bundled ones
+fn repair_resources() -> Result<String> {
+    let package_info = handle::Handle::global()
+        .app_handle
+        .lock()
+        .as_ref()
+        .map(|app_handle| app_handle.package_info().clone())
+        .ok_or(anyhow!("the app is not initialized"))?;
+    let copied = init::copy_resources(&package_info, true)?;
+    Ok(format!("{copied} files restored"))
+}
+
+/// the cores are installed next to the app, the missing or empty current core is downloaded again
+fn repair_cores() -> Result<String> {
+    let mut found = vec![];
+    for core in CORES.iter() {
+        let path = dirs::core_path(&core.to_string())?;
+        // 被杀软清空的文件也算丢失
+        if fs::metadata(&path).map_or(true, |meta| meta.len() == 0) {
+            continue;
+        }
+        set_executable(&path)?;
+        found.push(core.to_string());
+    }
+
+    let current = { crate::config::Config::verge().latest().clash_core.clone() };
+    let current = current.unwrap_or_default();
+    if !found.contains(&current.to_string()) {
+        // 修复在 spawn_blocking 里执行，可以阻塞等待下载
+        tauri::async_runtime::block_on(reinstall_core(&current)).map_err(|err| {
+            anyhow!("the core `{current}` is missing and failed to download it again, {err}")
+        })?;
+        found.push(format!("{current} (downloaded again)"));
+    }
+    Ok(format!("found {}", found.join(", ")))
+}
+
+async fn reinstall_core(core: &ClashCore) -> Result<()> {
+    let mut updater = Updater::global().write().await;
+    updater.fetch_latest().await?;
+    updater.update_core(core).await
+}
+
+#[cfg(unix)]
+fn set_executable(path: &PathBuf) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut perms = fs::metadata(path)?.permissions();
+    if perms.mode() & 0o111 != 0o111 {
+        perms.set_mode(perms.mode() | 0o755);
+        fs::set_permissions(path, perms)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &PathBuf) -> Result<()> {
+    Ok(())
+}
+
+/// only the owner gets the write permission, the configs have the secrets
+#[cfg(unix)]
+fn set_writable(perms: &mut fs::Permissions) {
+    use std::os::unix::fs::PermissionsExt;
+
+    perms.set_mode(perms.mode() | 0o600);
+}
+
+/// the read-only attribute on windows, the acl is not touched
+#[cfg(not(unix))]
+fn set_writable(perms: &mut fs::Permissions) {
+    #[allow(clippy::permissions_set_readonly_false)]
+    perms.set_readonly(false);
+}
+
+/// the read-only config files can't be saved, e.g. copied from a backup or another disk
+fn repair_permissions() -> Result<String> {
+    let mut fixed = vec![];
+    for dir in [dirs::app_home_dir()?, dirs::app_profiles_dir()?] {
+        for entry in fs::read_dir(&dir)?.flatten() {
+            let path = entry.path();
+            let mut perms = match entry.metadata() {
+                Ok(meta) if meta.is_file() => meta.permissions(),
+                _ => continue,
+            };
+            if perms.readonly() {
+                set_writable(&mut perms);
+                fs::set_permissions(&path, perms)?;
+                fixed.push(path);
+            }
+        }
+    }
+
+    Ok(match fixed.is_empty() {
+        true => "all files are writable".into(),
+        false => format!("made {} writable", join_paths(&fixed)),
+    })
+}
+
+/// the pid file left by a crash points to a dead process or a reused pid
+fn clear_stale_pid() -> Result<String> {
+    let path = dirs::clash_pid_path()?;
+    let pid = match fs::read_to_string(&path) {
+        Ok(pid) => pid,
+        Err(_) => return Ok("no pid file".into()),
+    };
+
+    // pid 可能被其他进程复用了，只认内核的进程名
+    let running = pid.trim().parse::<u32>().ok().map_or(false, |pid| {
+        let mut system = System::new();
+        system.refresh_processes();
+        system.process(Pid::from_u32(pid)).map_or(false, |proc| {
+            CORES
+                .iter()
+                .any(|core| proc.name().starts_with(&core.to_string()))
+        })
+    });
+    if running {
+        return Ok("the core is running".into());
+    }
+
+    fs::remove_file(&path)?;
+    Ok("removed the stale pid file".into())
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn register_url_scheme() -> Result<String> {
+    super::deep_link::register()?;
+    Ok("registered".into())
+}
+
+/// macos reads the schemes from `Info.plist`
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn register_url_scheme() -> Result<String> {
+    Ok("not needed on this platform".into())
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
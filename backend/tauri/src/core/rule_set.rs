@@ -0,0 +1,801 @@
+//! browse the entries of the rule providers and the geosite categories
+//!
+//! the parsed sets are cached for a while, so the search and the paging do not fetch again.
+//! the counts of the rules are computed in the background after the rules, the providers
+//! or the geodata change, see [`stats`].
+
+use super::{
+    http,
+    query::{paginate, Page, PageQuery},
+};
+use crate::{
+    config::{Config, HttpPurpose},
+    utils::dirs,
+};
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    hash::{Hash, Hasher},
+    net::IpAddr,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// the entries matching the target returned at most
+const MAX_MATCHED: usize = 50;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", content = "name", rename_all = "snake_case")]
+pub enum RuleSetSource {
+    /// the name in `rule-providers`
+    Provider(String),
+    /// e.g. `cn` `google@cn`
+    Geosite(String),
+}
+
+impl RuleSetSource {
+    fn key(&self) -> String {
+        match self {
+            RuleSetSource::Provider(name) => format!("provider:{name}"),
+            RuleSetSource::Geosite(name) => format!("geosite:{}", name.to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Full,
+    Suffix,
+    Keyword,
+    Regex,
+    /// `*` matches one level
+    Wildcard,
+    IpCidr,
+    /// the classical rules that can not be matched by a domain
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSetEntry {
+    pub kind: EntryKind,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RuleSetFilter {
+    /// case insensitive keyword of the entries
+    pub keyword: Option<String>,
+    /// the domain or the ip to test
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSetPage {
+    #[serde(flatten)]
+    pub page: Page<RuleSetEntry>,
+    /// `domain` `ipcidr` `classical` or `geosite`
+    pub behavior: String,
+    /// the entries matching the target, none without the target
+    pub matched: Option<Vec<RuleSetEntry>>,
+}
+
+struct RuleSet {
+    behavior: String,
+    entries: Vec<RuleSetEntry>,
+}
+
+static CACHE: Lazy<Mutex<HashMap<String, (Instant, Arc<RuleSet>)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStats {
+    pub name: String,
+    pub behavior: String,
+    pub count: usize,
+    /// e.g. the mrs format, or the file is not downloaded
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleStats {
+    /// the rules of the config by the type, e.g. `GEOSITE` `DOMAIN-SUFFIX`
+    pub rules: BTreeMap<String, usize>,
+    pub providers: Vec<ProviderStats>,
+    /// the domains of the geosite categories used by the rules, none if not found
+    pub geosite: BTreeMap<String, Option<usize>>,
+    /// unix timestamp of the computing
+    pub updated_at: i64,
+    /// the counts are being computed again, these are of the last time
+    pub pending: bool,
+}
+
+#[derive(Default)]
+struct StatsCache {
+    fingerprint: Option<u64>,
+    stats: Option<RuleStats>,
+    pending: bool,
+}
+
+static STATS: Lazy<Mutex<StatsCache>> = Lazy::new(|| Mutex::new(StatsCache::default()));
+
+/// load the set, then filter and paginate the entries
+pub async fn browse(
+    source: RuleSetSource,
+    filter: Option<RuleSetFilter>,
+    page: PageQuery,
+) -> Result<RuleSetPage> {
+    let set = load(&source).await?;
+    let filter = filter.unwrap_or_default();
+
+    let matched = filter
+        .target
+        .as_deref()
+        .map(str::trim)
+        .filter(|target| !target.is_empty())
+        .map(|target| {
+            let target = target.to_lowercase();
+            set.entries
+                .iter()
+                .filter(|entry| entry.matches(&target))
+                .take(MAX_MATCHED)
+                .cloned()
+                .collect()
+        });
+
+    let entries = match filter.keyword.as_ref().filter(|k| !k.is_empty()) {
+        Some(keyword) => {
+            let keyword = keyword.to_lowercase();
+            set.entries
+                .iter()
+                .filter(|entry| entry.value.to_lowercase().contains(&keyword))
+                .cloned()
+                .collect()
+        }
+        None => set.entries.clone(),
+    };
+
+    Ok(RuleSetPage {
+        page: paginate(entries, &page),
+        behavior: set.behavior.clone(),
+        matched,
+    })
+}
+
+async fn load(source: &RuleSetSource) -> Result<Arc<RuleSet>> {
+    let key = source.key();
+    {
+        let mut cache = CACHE.lock();
+        cache.retain(|_, (time, _)| time.elapsed() < CACHE_TTL);
+        if let Some((_, set)) = cache.get(&key) {
+            return Ok(set.clone());
+        }
+    }
+
+    let set = Arc::new(match source {
+        RuleSetSource::Provider(name) => load_provider(name).await?,
+        RuleSetSource::Geosite(name) => {
+            let name = name.clone();
+            tokio::task::spawn_blocking(move || load_geosite(&name)).await??
+        }
+    });
+    CACHE.lock().insert(key, (Instant::now(), set.clone()));
+    Ok(set)
+}
+
+/// the cached counts, computed again in the background if anything changed
+/// none before the first computing is done
+pub fn stats() -> Option<RuleStats> {
+    let fingerprint = fingerprint();
+    let mut cache = STATS.lock();
+    if cache.fingerprint != Some(fingerprint) && !cache.pending {
+        cache.pending = true;
+        tauri::async_runtime::spawn(async move {
+            let result = compute_stats().await;
+            let mut cache = STATS.lock();
+            cache.pending = false;
+            // 失败了也记下，免得反复计算
+            cache.fingerprint = Some(fingerprint);
+            match result {
+                Ok(stats) => cache.stats = Some(stats),
+                Err(err) => tracing::warn!("failed to count the rules: {err}"),
+            }
+        });
+    }
+    let pending = cache.pending;
+    cache
+        .stats
+        .clone()
+        .map(|stats| RuleStats { pending, ..stats })
+}
+
+/// the rules, the providers and the mtime of the provider files and the geosite
+/// the core rewrites the files after updating the providers or the geodata
+fn fingerprint() -> u64 {
+    let (rules, providers) = {
+        let runtime = Config::runtime();
+        let runtime = runtime.latest();
+        let config = runtime.config.as_ref();
+        let field = |key: &str| config.and_then(|config| config.get(key)).cloned();
+        (field("rules"), field("rule-providers"))
+    };
+
+    let mut hasher = DefaultHasher::new();
+    rules.hash(&mut hasher);
+    providers.hash(&mut hasher);
+
+    let home = dirs::app_home_dir().ok();
+    let paths = providers
+        .as_ref()
+        .and_then(Value::as_mapping)
+        .into_iter()
+        .flat_map(|providers| providers.values())
+        .filter_map(|provider| provider.get("path").and_then(Value::as_str))
+        .filter_map(|path| home.as_ref().map(|home| home.join(path)))
+        .chain(geosite_path().ok());
+    for path in paths {
+        let mtime = std::fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        (path, mtime).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+async fn compute_stats() -> Result<RuleStats> {
+    let (rules, providers) = {
+        let runtime = Config::runtime();
+        let runtime = runtime.latest();
+        let config = runtime.config.as_ref();
+        let rules = config
+            .and_then(|config| config.get("rules"))
+            .and_then(Value::as_sequence)
+            .map(|rules| string_list(rules))
+            .unwrap_or_default();
+        let providers = config
+            .and_then(|config| config.get("rule-providers"))
+            .and_then(Value::as_mapping)
+            .map(|providers| {
+                providers
+                    .keys()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        (rules, providers)
+    };
+
+    let mut counts = BTreeMap::<String, usize>::new();
+    let mut categories = vec![];
+    for rule in &rules {
+        let mut parts = rule.split(',').map(str::trim);
+        let rule_type = parts.next().unwrap_or_default().to_uppercase();
+        if rule_type == "GEOSITE" {
+            if let Some(name) = parts.next().map(str::to_lowercase) {
+                if !categories.contains(&name) {
+                    categories.push(name);
+                }
+            }
+        }
+        *counts.entry(rule_type).or_default() += 1;
+    }
+
+    let mut provider_stats = vec![];
+    for name in providers {
+        let (behavior, count, error) = match load_provider(&name).await {
+            Ok(set) => (set.behavior, set.entries.len(), None),
+            Err(err) => (String::new(), 0, Some(err.to_string())),
+        };
+        provider_stats.push(ProviderStats {
+            name,
+            behavior,
+            count,
+            error,
+        });
+    }
+
+    // 一次读完所有用到的分类
+    let geosite = match categories.is_empty() {
+        true => BTreeMap::new(),
+        false => {
+            let found = tokio::task::spawn_blocking({
+                let categories = categories.clone();
+                move || count_geosite(&categories)
+            })
+            .await??;
+            categories
+                .into_iter()
+                .map(|name| {
+                    let count = found.get(&name).copied();
+                    (name, count)
+                })
+                .collect()
+        }
+    };
+
+    // 浏览的缓存也可能过期了
+    CACHE.lock().clear();
+    Ok(RuleStats {
+        rules: counts,
+        providers: provider_stats,
+        geosite,
+        updated_at: chrono::Local::now().timestamp(),
+        pending: false,
+    })
+}
+
+async fn load_provider(name: &str) -> Result<RuleSet> {
+    let provider = {
+        let runtime = Config::runtime();
+        let runtime = runtime.latest();
+        runtime
+            .config
+            .as_ref()
+            .and_then(|config| config.get("rule-providers"))
+            .and_then(|providers| providers.get(name))
+            .and_then(|provider| provider.as_mapping().cloned())
+    };
+    let provider = provider.ok_or(anyhow!("the rule provider `{name}` not found"))?;
+    let field = |key: &str| {
+        provider
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    let behavior = field("behavior").unwrap_or("classical".into());
+    let lines = provider_payload(name, &provider).await?;
+    let entries = lines
+        .iter()
+        .map(|line| parse_entry(line, &behavior))
+        .collect();
+    Ok(RuleSet { behavior, entries })
+}
+
+/// the lines of the rule provider, the inline payload or the content of the file
+pub(super) async fn provider_payload(name: &str, provider: &Mapping) -> Result<Vec<String>> {
+    let field = |key: &str| provider.get(key).and_then(Value::as_str);
+    if field("format") == Some("mrs") {
+        bail!("the mrs format of `{name}` is not supported");
+    }
+    if field("type") == Some("inline") {
+        return Ok(provider
+            .get("payload")
+            .and_then(Value::as_sequence)
+            .map(|payload| string_list(payload))
+            .unwrap_or_default());
+    }
+
+    let text = read_provider(name, provider).await?;
+    match field("format").unwrap_or("yaml") {
+        "text" => Ok(text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect()),
+        _ => {
+            let data = serde_yaml::from_str::<Mapping>(&text)?;
+            Ok(data
+                .get("payload")
+                .and_then(Value::as_sequence)
+                .map(|payload| string_list(payload))
+                .unwrap_or_default())
+        }
+    }
+}
+
+fn string_list(list: &[Value]) -> Vec<String> {
+    list.iter()
+        .filter_map(Value::as_str)
+        .map(|s| s.trim().to_string())
+        .collect()
+}
+
+/// the local file first, the http providers are fetched when it is missing
+/// also used for the proxy providers
+pub(super) async fn read_provider(name: &str, provider: &Mapping) -> Result<String> {
+    if let Some(path) = provider.get("path").and_then(Value::as_str) {
+        let path = safe_path(&dirs::app_home_dir()?, path).ok_or(anyhow!(
+            "the path of the provider `{name}` is out of the home dir"
+        ))?;
+        if let Ok(text) = tokio::fs::read_to_string(&path).await {
+            return Ok(text);
+        }
+    }
+
+    let url = provider
+        .get("url")
+        .and_then(Value::as_str)
+        .ok_or(anyhow!("the provider `{name}` has no local file nor url"))?;
+
+    let response = http::client(HttpPurpose::Geodata)?
+        .get(url)
+        .await?
+        .error_for_status()?;
+    Ok(response.text().await?)
+}
+
+/// the path in the home dir, checked like the `IsSafePath` of mihomo
+fn safe_path(home: &Path, path: &str) -> Option<PathBuf> {
+    let mut safe = PathBuf::new();
+    for component in home.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !safe.pop() {
+                    return None;
+                }
+            }
+            component => safe.push(component),
+        }
+    }
+    safe.starts_with(home).then_some(safe)
+}
+
+fn parse_entry(line: &str, behavior: &str) -> RuleSetEntry {
+    let entry = |kind, value: &str| RuleSetEntry {
+        kind,
+        value: value.to_string(),
+    };
+
+    match behavior {
+        "domain" => match line {
+            _ if line.starts_with("+.") => entry(EntryKind::Suffix, &line[2..]),
+            _ if line.starts_with('.') => entry(EntryKind::Suffix, &line[1..]),
+            _ if line.contains('*') => entry(EntryKind::Wildcard, line),
+            _ => entry(EntryKind::Full, line),
+        },
+        "ipcidr" => entry(EntryKind::IpCidr, line),
+        _ => {
+            let mut parts = line.split(',').map(str::trim);
+            let rule_type = parts.next().unwrap_or_default().to_uppercase();
+            let payload = parts.next().unwrap_or_default();
+            match rule_type.as_str() {
+                "DOMAIN" => entry(EntryKind::Full, payload),
+                "DOMAIN-SUFFIX" => entry(EntryKind::Suffix, payload),
+                "DOMAIN-KEYWORD" => entry(EntryKind::Keyword, payload),
+                "DOMAIN-REGEX" => entry(EntryKind::Regex, payload),
+                "IP-CIDR" | "IP-CIDR6" => entry(EntryKind::IpCidr, payload),
+                _ => entry(EntryKind::Other, line),
+            }
+        }
+    }
+}
+
+impl RuleSetEntry {
+    /// `target` is lowercase
+    fn matches(&self, target: &str) -> bool {
+        let value = self.value.to_lowercase();
+        match self.kind {
+            EntryKind::Full => target == value,
+            EntryKind::Suffix => {
+                target == value
+                    || target
+                        .strip_suffix(value.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            }
+            EntryKind::Keyword => target.contains(&value),
+            EntryKind::Regex => regex::Regex::new(&self.value)
+                .map(|re| re.is_match(target))
+                .unwrap_or(false),
+            EntryKind::Wildcard => {
+                let labels = target.split('.').collect::<Vec<_>>();
+                let patterns = value.split('.').collect::<Vec<_>>();
+                labels.len() == patterns.len()
+                    && labels
+                        .iter()
+                        .zip(patterns.iter())
+                        .all(|(label, pattern)| *pattern == "*" || label == pattern)
+            }
+            EntryKind::IpCidr => match target.parse::<IpAddr>() {
+                Ok(ip) => cidr_contains(&value, ip),
+                Err(_) => false,
+            },
+            EntryKind::Other => false,
+        }
+    }
+}
+
+fn cidr_contains(cidr: &str, ip: IpAddr) -> bool {
+    let (net, prefix) = cidr.split_once('/').unwrap_or((cidr, ""));
+    let Ok(net) = net.parse::<IpAddr>() else {
+        return false;
+    };
+    match (net, ip) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => {
+            let prefix = prefix.parse::<u32>().unwrap_or(32).min(32);
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(net) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) => {
+            let prefix = prefix.parse::<u32>().unwrap_or(128).min(128);
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(net) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+/// the geosite.dat is the protobuf of v2ray
+/// `GeoSiteList { repeated GeoSite entry = 1; }`
+/// `GeoSite { string country_code = 1; repeated Domain domain = 2; }`
+/// `Domain { Type type = 1; string value = 2; repeated Attribute attribute = 3; }`
+/// `Attribute { string key = 1; ... }`
+fn load_geosite(name: &str) -> Result<RuleSet> {
+    let data = std::fs::read(geosite_path()?)?;
+
+    // cn@ads 只保留带 ads 属性的域名
+    let (code, attr) = match name.split_once('@') {
+        Some((code, attr)) => (code, Some(attr)),
+        None => (name, None),
+    };
+
+    let mut list = ProtoReader::new(&data);
+    while let Some((field, site)) = list.next_bytes()? {
+        if field != 1 {
+            continue;
+        }
+        let mut site = ProtoReader::new(site);
+        let mut domains = vec![];
+        let mut matched = false;
+        while let Some((field, value)) = site.next_bytes()? {
+            match field {
+                1 if String::from_utf8_lossy(value).eq_ignore_ascii_case(code) => matched = true,
+                1 => break,
+                2 => domains.push(value),
+                _ => {}
+            }
+        }
+        if !matched {
+            continue;
+        }
+
+        let entries = domains
+            .into_iter()
+            .map(parse_geosite_domain)
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|(_, attrs)| attr.map_or(true, |attr| attrs.iter().any(|a| a == attr)))
+            .map(|(entry, _)| entry)
+            .collect();
+        return Ok(RuleSet {
+            behavior: "geosite".into(),
+            entries,
+        });
+    }
+    bail!("the geosite category `{name}` not found")
+}
+
+/// the domains of the categories, e.g. `cn` `cn@ads`, in one pass
+/// the categories not found are absent
+fn count_geosite(names: &[String]) -> Result<HashMap<String, usize>> {
+    let data = std::fs::read(geosite_path()?)?;
+    let names = names
+        .iter()
+        .map(|name| match name.split_once('@') {
+            Some((code, attr)) => (name, code, Some(attr)),
+            None => (name, name.as_str(), None),
+        })
+        .collect::<Vec<_>>();
+
+    let mut counts = HashMap::new();
+    let mut list = ProtoReader::new(&data);
+    while let Some((field, site)) = list.next_bytes()? {
+        if field != 1 {
+            continue;
+        }
+        let mut site = ProtoReader::new(site);
+        let mut matched = vec![];
+        while let Some((field, value)) = site.next_bytes()? {
+            match field {
+                1 => {
+                    let code = String::from_utf8_lossy(value);
+                    matched = names
+                        .iter()
+                        .filter(|(_, c, _)| c.eq_ignore_ascii_case(&code))
+                        .collect();
+                    if matched.is_empty() {
+                        break;
+                    }
+                    for (name, _, _) in &matched {
+                        counts.insert(name.to_string(), 0);
+                    }
+                }
+                2 => {
+                    let (_, attrs) = parse_geosite_domain(value)?;
+                    for (name, _, attr) in &matched {
+                        if attr.map_or(true, |attr| attrs.iter().any(|a| a == attr)) {
+                            *counts.entry(name.to_string()).or_default() += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(counts)
+}
+
+fn geosite_path() -> Result<PathBuf> {
+    let home = dirs::app_home_dir()?;
+    ["geosite.dat", "GeoSite.dat"]
+        .into_iter()
+        .map(|file| home.join(file))
+        .find(|path| path.exists())
+        .ok_or(anyhow!("the geosite.dat not found"))
+}
+
+fn parse_geosite_domain(data: &[u8]) -> Result<(RuleSetEntry, Vec<String>)> {
+    let mut reader = ProtoReader::new(data);
+    let mut kind = EntryKind::Keyword;
+    let mut value = String::new();
+    let mut attrs = vec![];
+
+    while let Some((field, wire)) = reader.next_field()? {
+        match (field, wire) {
+            (1, ProtoValue::Varint(t)) => {
+                kind = match t {
+                    0 => EntryKind::Keyword,
+                    1 => EntryKind::Regex,
+                    2 => EntryKind::Suffix,
+                    _ => EntryKind::Full,
+                }
+            }
+            (2, ProtoValue::Bytes(bytes)) => value = String::from_utf8_lossy(bytes).into(),
+            (3, ProtoValue::Bytes(bytes)) => {
+                let mut attr = ProtoReader::new(bytes);
+                while let Some((field, value)) = attr.next_bytes()? {
+                    if field == 1 {
+                        attrs.push(String::from_utf8_lossy(value).into());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((RuleSetEntry { kind, value }, attrs))
+}
+
+enum ProtoValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// just enough protobuf to read the geosite
+struct ProtoReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.buf.get(self.pos).ok_or(anyhow!("unexpected eof"))?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("invalid varint")
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.buf.len());
+        let end = end.ok_or(anyhow!("unexpected eof"))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u64, ProtoValue<'a>)>> {
+        if self.pos >= self.buf.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 0x07 {
+            0 => ProtoValue::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                ProtoValue::Fixed
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                ProtoValue::Bytes(self.take(len)?)
+            }
+            5 => {
+                self.take(4)?;
+                ProtoValue::Fixed
+            }
+            wire => bail!("unsupported wire type {wire}"),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+
+    /// the next length delimited field, the others are skipped
+    fn next_bytes(&mut self) -> Result<Option<(u64, &'a [u8])>> {
+        while let Some((field, value)) = self.next_field()? {
+            if let ProtoValue::Bytes(bytes) = value {
+                return Ok(Some((field, bytes)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[test]
+fn test_rule_set_entries() {
+    let suffix = parse_entry("+.google.com", "domain");
+    assert!(suffix.matches("www.google.com"));
+    assert!(suffix.matches("google.com"));
+    assert!(!suffix.matches("notgoogle.com"));
+
+    let wildcard = parse_entry("*.example.com", "domain");
+    assert!(wildcard.matches("a.example.com"));
+    assert!(!wildcard.matches("a.b.example.com"));
+
+    let cidr = parse_entry("IP-CIDR,10.0.0.0/8,no-resolve", "classical");
+    assert_eq!(cidr.kind, EntryKind::IpCidr);
+    assert!(cidr.matches("10.1.2.3"));
+    assert!(!cidr.matches("11.0.0.1"));
+    assert!(parse_entry("2400:3200::/32", "ipcidr").matches("2400:3200::1"));
+
+    // GeoSite { country_code: "CN", domain: [{ type: Domain, value: "qq.com", attribute: [{ key: "ads" }] }] }
+    let domain = [
+        &[0x08, 0x02, 0x12, 0x06][..],
+        b"qq.com",
+        &[0x1a, 0x05, 0x0a, 0x03],
+        b"ads",
+    ]
+    .concat();
+    let site = [
+        &[0x0a, 0x02][..],
+        b"CN",
+        &[0x12, domain.len() as u8],
+        &domain,
+    ]
+    .concat();
+    let mut reader = ProtoReader::new(&site);
+    let (_, code) = reader.next_bytes().unwrap().unwrap();
+    assert_eq!(code, b"CN");
+    let (_, domain) = reader.next_bytes().unwrap().unwrap();
+    let (entry, attrs) = parse_geosite_domain(domain).unwrap();
+    assert_eq!(entry.kind, EntryKind::Suffix);
+    assert!(entry.matches("im.qq.com"));
+    assert_eq!(attrs, vec!["ads".to_string()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_safe_path() {
+    let home = Path::new("/home/user/.config/clash-verge");
+    assert_eq!(
+        safe_path(home, "./ruleset/../providers/a.yaml"),
+        Some(home.join("providers/a.yaml"))
+    );
+    assert_eq!(
+        safe_path(home, "/home/user/.config/clash-verge/a.yaml"),
+        Some(home.join("a.yaml"))
+    );
+    assert_eq!(safe_path(home, "../clash/config.yaml"), None);
+    assert_eq!(safe_path(home, "/etc/passwd"), None);
+    assert_eq!(safe_path(home, "../../../../../../etc/passwd"), None);
+}
@@ -0,0 +1,376 @@
+use super::{
+    clash_api::{self, ProxyItem},
+    core_archive,
+    event_bus::{AppEvent, CoreState, EventBus},
+    handle,
+    tray::TrayProxies,
+};
+use crate::config::{Config, PrfSelected, EXIT_PROBE};
+use anyhow::{bail, Result};
+use futures_util::{stream, StreamExt};
+use serde::Serialize;
+use std::{collections::HashMap, time::Duration};
+use tokio::time::sleep;
+
+/// 记录当前 profile 某个分组的手动选择
+pub fn record(group: &str, now: &str) -> Result<()> {
+    let profiles = Config::profiles();
+    let mut profiles = profiles.data();
+
+    let uid = match profiles.get_current() {
+        Some(uid) => uid,
+        None => bail!("failed to get the current profile"),
+    };
+
+    profiles.patch_selected(&uid, group, now)
+}
+
+/// 选择节点并记录到当前 profile 中
+pub async fn select(group: String, name: String) -> Result<()> {
+    clash_api::update_proxy(&group, &name).await?;
+    crate::log_err!(record(&group, &name));
+    EventBus::publish(AppEvent::ProxySelected { group, name });
+    Ok(())
+}
+
+/// 将当前 profile 记录的选择重新应用到内核中
+/// profile 更新或者内核重启后，分组会回到默认的节点
+pub async fn restore() -> Result<()> {
+    let selected = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+
+        match profiles.get_current() {
+            Some(uid) => profiles
+                .get_item(&uid)
+                .ok()
+                .and_then(|item| item.selected.clone())
+                .unwrap_or_default(),
+            None => return Ok(()),
+        }
+    };
+
+    let mut selected_map = selected
+        .into_iter()
+        .filter_map(|PrfSelected { name, now }| Some((name?, now?)))
+        .collect::<HashMap<String, String>>();
+
+    // 切换回来的核心，按切走时的选择恢复
+    selected_map.extend(core_archive::take_selections());
+
+    if selected_map.is_empty() {
+        return Ok(());
+    }
+
+    let proxies = clash_api::get_proxies().await?.proxies;
+
+    let mut changed = false;
+    for (group, now) in selected_map.iter() {
+        let item = match proxies.get(group) {
+            Some(item) => item,
+            None => continue,
+        };
+
+        // 只有 Selector 才能手动选择
+        if item.r#type != "Selector" || item.now.as_ref() == Some(now) {
+            continue;
+        }
+
+        // 订阅更新后节点可能已经不存在了
+        if !item.all.as_ref().map_or(false, |all| all.contains(now)) {
+            tracing::debug!("skip restoring `{group}`, node `{now}` is gone");
+            continue;
+        }
+
+        match clash_api::update_proxy(group, now).await {
+            Ok(_) => changed = true,
+            Err(err) => tracing::error!("failed to restore `{group}`: {err}"),
+        }
+    }
+
+    if changed {
+        tracing::info!("group selections restored");
+        handle::Handle::refresh_clash();
+    }
+
+    Ok(())
+}
+
+/// 等待控制器可用后再恢复选择
+/// 内核刚启动时控制器可能还没监听
+pub fn restore_later() {
+    tauri::async_runtime::spawn(async {
+        for _ in 0..10 {
+            sleep(Duration::from_millis(500)).await;
+
+            if clash_api::get_proxies().await.is_ok() {
+                crate::log_err!(restore().await);
+                TrayProxies::global().refresh().await;
+                return;
+            }
+        }
+
+        tracing::warn!("controller is not ready, skip restoring selections");
+    });
+}
+
+/// a group changed by the bulk selections
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkSelected {
+    pub group: String,
+    pub from: Option<String>,
+    pub to: String,
+}
+
+/// the tests running at the same time, without the group delay api
+const CONCURRENCY: usize = 8;
+const TEST_TIMEOUT: u64 = 5000;
+
+/// the built-in types can't be tested
+const BUILTIN_TYPES: [&str; 5] = ["Direct", "Reject", "RejectDrop", "Pass", "Compatible"];
+
+/// the aliases of the common regions, the short codes match whole words only
+const REGIONS: [(&str, &[&str]); 8] = [
+    ("HK", &["🇭🇰", "香港", "Hong Kong", "HongKong", "HK"]),
+    ("TW", &["🇹🇼", "台湾", "臺灣", "Taiwan", "TW"]),
+    ("JP", &["🇯🇵", "日本", "Japan", "Tokyo", "JP"]),
+    ("SG", &["🇸🇬", "新加坡", "狮城", "Singapore", "SG"]),
+    (
+        "US",
+        &["🇺🇸", "美国", "United States", "America", "US", "USA"],
+    ),
+    ("KR", &["🇰🇷", "韩国", "Korea", "Seoul", "KR"]),
+    (
+        "GB",
+        &["🇬🇧", "英国", "United Kingdom", "London", "GB", "UK"],
+    ),
+    ("DE", &["🇩🇪", "德国", "Germany", "Frankfurt", "DE"]),
+];
+
+/// the selector groups except the exit probe, GLOBAL is only changed by the reset
+fn selectors(proxies: &HashMap<String, ProxyItem>, global: bool) -> Vec<&ProxyItem> {
+    let mut groups = proxies
+        .values()
+        .filter(|item| item.r#type == "Selector")
+        .filter(|item| global || item.name != "GLOBAL")
+        .filter(|item| item.name != EXIT_PROBE)
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    groups
+}
+
+/// whether the node name is in the region, e.g. `HK` or `香港`
+fn in_region(name: &str, region: &str) -> bool {
+    let region = region.trim();
+    let aliases = REGIONS
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(region))
+        .map(|(_, aliases)| aliases.to_vec())
+        .unwrap_or_else(|| vec![region]);
+
+    let lower = name.to_lowercase();
+    aliases.iter().any(|alias| {
+        if alias.len() > 3 || !alias.is_ascii() {
+            return lower.contains(&alias.to_lowercase());
+        }
+        // 短的代码按单词匹配，避免 US 匹配到 RUS
+        name.split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| word.eq_ignore_ascii_case(alias))
+    })
+}
+
+async fn apply(changes: &[BulkSelected]) {
+    for change in changes {
+        if let Err(err) = select(change.group.clone(), change.to.clone()).await {
+            tracing::error!(
+                "failed to select `{}` of `{}`: {err}",
+                change.to,
+                change.group
+            );
+        }
+    }
+    if !changes.is_empty() {
+        handle::Handle::refresh_clash();
+        TrayProxies::global().refresh().await;
+    }
+}
+
+/// the delays of the group members, none if failed
+async fn member_delays(
+    group: &ProxyItem,
+    proxies: &HashMap<String, ProxyItem>,
+    test_url: &str,
+) -> HashMap<String, u64> {
+    let members = group
+        .all
+        .iter()
+        .flatten()
+        .filter(|name| {
+            proxies
+                .get(*name)
+                .map_or(true, |item| !BUILTIN_TYPES.contains(&item.r#type.as_str()))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    // meta 内核可以一次测完整个分组
+    if let Ok(delays) =
+        clash_api::get_group_delay(&group.name, Some(test_url.into()), TEST_TIMEOUT).await
+    {
+        return delays
+            .into_iter()
+            .filter(|(name, delay)| *delay > 0 && members.contains(name))
+            .collect();
+    }
+
+    stream::iter(members)
+        .map(|name| async move {
+            let delay = clash_api::test_proxy_delay(&name, test_url, TEST_TIMEOUT).await;
+            (name, delay.map(|res| res.delay).unwrap_or(0))
+        })
+        .buffer_unordered(CONCURRENCY)
+        .filter(|(_, delay)| futures_util::future::ready(*delay > 0))
+        .collect()
+        .await
+}
+
+/// select the fastest node in every selector group
+pub async fn select_fastest(test_url: Option<String>) -> Result<Vec<BulkSelected>> {
+    let test_url = test_url
+        .filter(|url| !url.is_empty())
+        .or_else(|| Config::verge().latest().default_latency_test.clone())
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| "http://www.gstatic.com/generate_204".into());
+    let proxies = clash_api::get_proxies().await?.proxies;
+
+    let mut changes = Vec::new();
+    for group in selectors(&proxies, false) {
+        let delays = member_delays(group, &proxies, &test_url).await;
+        let fastest = delays.into_iter().min_by_key(|(_, delay)| *delay);
+        match fastest {
+            Some((name, _)) if group.now.as_ref() != Some(&name) => changes.push(BulkSelected {
+                group: group.name.clone(),
+                from: group.now.clone(),
+                to: name,
+            }),
+            Some(_) => {}
+            None => tracing::debug!("no available node in `{}`", group.name),
+        }
+    }
+
+    apply(&changes).await;
+    Ok(changes)
+}
+
+/// select a node of the region in every selector group having one,
+/// the one with the lowest latest delay is preferred
+pub async fn pin_region(region: String) -> Result<Vec<BulkSelected>> {
+    if region.trim().is_empty() {
+        bail!("the region is empty");
+    }
+    let proxies = clash_api::get_proxies().await?.proxies;
+    let latest = |name: &str| {
+        proxies
+            .get(name)
+            .and_then(|item| item.history.last())
+            .map(|history| history.delay)
+            .filter(|delay| *delay > 0)
+            .unwrap_or(u64::MAX)
+    };
+
+    let mut changes = Vec::new();
+    for group in selectors(&proxies, false) {
+        // 已经在这个地区的就不动了
+        if group
+            .now
+            .as_ref()
+            .is_some_and(|now| in_region(now, &region))
+        {
+            continue;
+        }
+        let candidates = group
+            .all
+            .iter()
+            .flatten()
+            .filter(|name| in_region(name, &region));
+        if let Some(name) = candidates.min_by_key(|name| latest(name)) {
+            changes.push(BulkSelected {
+                group: group.name.clone(),
+                from: group.now.clone(),
+                to: name.clone(),
+            });
+        }
+    }
+
+    if changes.is_empty() {
+        bail!("no node of the region `{}` is found", region.trim());
+    }
+    apply(&changes).await;
+    Ok(changes)
+}
+
+/// select the first node in every selector group, and forget the records
+pub async fn reset_default() -> Result<Vec<BulkSelected>> {
+    let proxies = clash_api::get_proxies().await?.proxies;
+
+    let mut changes = Vec::new();
+    for group in selectors(&proxies, true) {
+        let Some(first) = group.all.as_ref().and_then(|all| all.first()) else {
+            continue;
+        };
+        if group.now.as_ref() == Some(first) {
+            continue;
+        }
+        match clash_api::update_proxy(&group.name, first).await {
+            Ok(_) => changes.push(BulkSelected {
+                group: group.name.clone(),
+                from: group.now.clone(),
+                to: first.clone(),
+            }),
+            Err(err) => tracing::error!("failed to reset `{}`: {err}", group.name),
+        }
+    }
+
+    {
+        let profiles = Config::profiles();
+        let mut profiles = profiles.data();
+        if let Some(uid) = profiles.get_current() {
+            profiles.clear_selected(&uid)?;
+        }
+    }
+
+    for change in changes.iter() {
+        EventBus::publish(AppEvent::ProxySelected {
+            group: change.group.clone(),
+            name: change.to.clone(),
+        });
+    }
+    if !changes.is_empty() {
+        handle::Handle::refresh_clash();
+        TrayProxies::global().refresh().await;
+    }
+    Ok(changes)
+}
+
+/// restore the selections whenever the core starts
+pub fn subscribe_events() {
+    EventBus::subscribe("selection", |event| async move {
+        if let AppEvent::CoreState {
+            state: CoreState::Started,
+        } = event
+        {
+            restore_later();
+        }
+    });
+}
+
+#[test]
+fn test_in_region() {
+    assert!(in_region("🇭🇰 香港 01", "HK"));
+    assert!(in_region("HK-IPLC x2", "hk"));
+    assert!(in_region("Hong Kong 02", "HK"));
+    assert!(in_region("US 01", "US"));
+    assert!(!in_region("RUS 01", "US"));
+    assert!(!in_region("Australia", "US"));
+    assert!(in_region("Japan Tokyo", "Tokyo"));
+}
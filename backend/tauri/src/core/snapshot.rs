@@ -0,0 +1,151 @@
+//! the whole state needed by the ui at startup, in one ipc call
+use super::{clash_api, CoreManager};
+use crate::config::{ClashCore, ClashInfo, Config, IProfiles, IVerge};
+use serde::Serialize;
+use std::time::Duration;
+use sysproxy::Sysproxy;
+use tokio::time::timeout;
+
+/// 控制器没响应时不要卡住界面
+const GROUPS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreStatus {
+    pub running: bool,
+    pub core: Option<ClashCore>,
+    pub mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GroupSelection {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    pub now: Option<String>,
+    pub all: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemProxyState {
+    /// the setting
+    pub enable: bool,
+    /// what the os is using
+    pub active: bool,
+    pub server: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSnapshot {
+    pub core: CoreStatus,
+    pub verge: IVerge,
+    pub profiles: IProfiles,
+    /// the ports and the controller
+    pub clash_info: ClashInfo,
+    /// empty if the controller is unavailable
+    pub groups: Vec<GroupSelection>,
+    pub system_proxy: SystemProxyState,
+}
+
+pub async fn get() -> AppSnapshot {
+    let verge = { Config::verge().latest().clone() };
+    let profiles = { Config::profiles().latest().clone() };
+    let (clash_info, mode) = {
+        let clash = Config::clash();
+        let clash = clash.latest();
+        let mode = clash
+            .0
+            .get("mode")
+            .and_then(|mode| mode.as_str())
+            .map(|mode| mode.to_lowercase());
+        (clash.get_client_info(), mode)
+    };
+
+    let running = CoreManager::global().is_running();
+    let groups = match running {
+        true => match timeout(GROUPS_TIMEOUT, clash_api::get_proxies()).await {
+            Ok(Ok(res)) => collect_groups(res),
+            Ok(Err(err)) => {
+                tracing::debug!("failed to fetch the groups for the snapshot: {err}");
+                Vec::new()
+            }
+            Err(_) => Vec::new(),
+        },
+        false => Vec::new(),
+    };
+
+    let current = Sysproxy::get_system_proxy().ok();
+    let system_proxy = SystemProxyState {
+        enable: verge.enable_system_proxy.unwrap_or(false),
+        active: current.as_ref().map_or(false, |proxy| proxy.enable),
+        server: current.map(|proxy| format!("{}:{}", proxy.host, proxy.port)),
+    };
+
+    AppSnapshot {
+        core: CoreStatus {
+            running,
+            core: verge.clash_core.clone(),
+            mode,
+        },
+        verge,
+        profiles,
+        clash_info,
+        groups,
+        system_proxy,
+    }
+}
+
+/// in the order of `GLOBAL`, which is the order in the config, and `GLOBAL` at last
+fn collect_groups(res: clash_api::ProxiesRes) -> Vec<GroupSelection> {
+    let proxies = res.proxies;
+    let mut names = proxies
+        .get("GLOBAL")
+        .and_then(|global| global.all.clone())
+        .unwrap_or_default();
+    names.push("GLOBAL".into());
+
+    names
+        .iter()
+        .filter_map(|name| proxies.get(name))
+        .filter_map(|item| {
+            Some(GroupSelection {
+                name: item.name.clone(),
+                r#type: item.r#type.clone(),
+                now: item.now.clone(),
+                all: item.all.clone()?,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_collect_groups() {
+    use clash_api::ProxyItem;
+
+    let item = |name: &str, all: Option<Vec<&str>>| ProxyItem {
+        name: name.into(),
+        r#type: "Selector".into(),
+        now: all
+            .as_ref()
+            .and_then(|all| all.first())
+            .map(|now| now.to_string()),
+        all: all.map(|all| all.into_iter().map(String::from).collect()),
+        ..ProxyItem::default()
+    };
+    let proxies = [
+        item("GLOBAL", Some(vec!["HK", "Proxy", "Auto"])),
+        item("Auto", Some(vec!["HK"])),
+        item("Proxy", Some(vec!["Auto", "HK"])),
+        item("HK", None),
+    ]
+    .into_iter()
+    .map(|item| (item.name.clone(), item))
+    .collect();
+
+    let groups = collect_groups(clash_api::ProxiesRes { proxies });
+    let names = groups
+        .iter()
+        .map(|group| group.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Proxy", "Auto", "GLOBAL"]);
+    assert_eq!(groups[0].now.as_deref(), Some("Auto"));
+}
@@ -1,5 +1,6 @@
 use crate::utils::dirs;
 use rocksdb::MultiThreaded;
+use serde::{de::DeserializeOwned, Serialize};
 use std::sync::{Arc, OnceLock};
 
 /// storage is a wrapper or called a facade for the rocksdb
@@ -25,6 +26,26 @@ impl Storage {
         &self.instance
     }
 
+    /// get a json value by key
+    pub fn get_item<T: DeserializeOwned>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        match self.instance.get(key.as_bytes())? {
+            Some(mut value) => Ok(Some(simd_json::from_slice(&mut value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// save a value as json
+    pub fn set_item<T: Serialize>(&self, key: &str, value: &T) -> anyhow::Result<()> {
+        let value = simd_json::to_vec(value)?;
+        self.instance.put(key.as_bytes(), value)?;
+        Ok(())
+    }
+
+    pub fn remove_item(&self, key: &str) -> anyhow::Result<()> {
+        self.instance.delete(key.as_bytes())?;
+        Ok(())
+    }
+
     pub fn destroy(&self) -> Result<(), rocksdb::Error> {
         rocksdb::DB::destroy(&rocksdb::Options::default(), &self.path)
     }
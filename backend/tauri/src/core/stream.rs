@@ -0,0 +1,274 @@
+use super::{
+    clash_api,
+    event_bus::{AppEvent, CoreState, EventBus},
+    handle,
+    low_power::LowPower,
+};
+use anyhow::Result;
+use futures_util::StreamExt;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{sync::Notify, time::sleep};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum StreamState {
+    Connecting,
+    Connected,
+    /// waiting `delay` millis before the next attempt
+    Reconnecting {
+        attempt: u32,
+        delay: u64,
+    },
+    /// closed in the low power mode
+    Suspended,
+}
+
+/// what a stream does in the low power mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LowPowerPolicy {
+    Keep,
+    /// close the websocket until the window is shown again
+    Suspend,
+    /// drop the frames received within the interval
+    Throttle(Duration),
+}
+
+/// a websocket stream of the external controller, e.g. `/logs` `/traffic`
+pub trait StreamHandler: Send + Sync {
+    /// the controller path without the leading slash
+    fn path(&self) -> &'static str;
+
+    /// the key of the stream state, different handlers of the same path need different names
+    fn name(&self) -> &'static str {
+        self.path()
+    }
+
+    /// query params, rebuilt on every connection
+    /// so that the changes of the config will be applied after reconnecting
+    fn query(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    fn low_power(&self) -> LowPowerPolicy {
+        LowPowerPolicy::Keep
+    }
+
+    fn on_message(&self, text: String);
+}
+
+/// keep all the controller streams alive
+/// streams will be reconnected with exponential backoff after the core restarts or the system sleeps
+pub struct StreamManager {
+    states: Arc<Mutex<HashMap<&'static str, StreamState>>>,
+
+    /// skip the backoff and reconnect immediately
+    wake: Arc<Notify>,
+
+    /// close the connected streams and reconnect
+    restart: Arc<Notify>,
+}
+
+impl StreamManager {
+    pub fn global() -> &'static StreamManager {
+        static STREAM_MANAGER: OnceCell<StreamManager> = OnceCell::new();
+
+        STREAM_MANAGER.get_or_init(|| StreamManager {
+            states: Arc::new(Mutex::new(HashMap::new())),
+            wake: Arc::new(Notify::new()),
+            restart: Arc::new(Notify::new()),
+        })
+    }
+
+    pub fn spawn<H: StreamHandler>(&'static self, handler: &'static H) {
+        let name = handler.name();
+        if self.states.lock().contains_key(name) {
+            return;
+        }
+        self.set_state(name, StreamState::Connecting);
+
+        tauri::async_runtime::spawn(async move {
+            let mut attempt = 0u32;
+
+            loop {
+                if handler.low_power() == LowPowerPolicy::Suspend {
+                    let mut power = LowPower::global().subscribe();
+                    if *power.borrow() {
+                        self.set_state(name, StreamState::Suspended);
+                        let _ = power.wait_for(|active| !active).await;
+                    }
+                }
+                self.set_state(name, StreamState::Connecting);
+
+                match self.connect(handler).await {
+                    Ok(true) => {
+                        tracing::debug!("stream `{name}` suspended");
+                        attempt = 0;
+                        continue;
+                    }
+                    Ok(false) => {
+                        tracing::debug!("stream `{name}` closed");
+                        attempt = 0;
+                    }
+                    Err(err) => {
+                        tracing::debug!("failed to connect stream `{name}`: {err}");
+                    }
+                }
+
+                attempt = attempt.saturating_add(1);
+                let delay = backoff(attempt);
+                self.set_state(
+                    name,
+                    StreamState::Reconnecting {
+                        attempt,
+                        delay: delay.as_millis() as u64,
+                    },
+                );
+
+                tokio::select! {
+                    _ = sleep(delay) => {}
+                    _ = self.wake.notified() => {}
+                }
+            }
+        });
+    }
+
+    /// return `Err` only if the handshake failed, `Ok(true)` if suspended
+    async fn connect<H: StreamHandler>(&self, handler: &'static H) -> Result<bool> {
+        let path = handler.path();
+        let query = handler.query();
+        let query = query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect::<Vec<_>>();
+
+        let url = clash_api::clash_ws_url(path, &query)?;
+        let (mut stream, _) = connect_async(url.as_str()).await?;
+        self.set_state(handler.name(), StreamState::Connected);
+
+        let policy = handler.low_power();
+        let mut power = LowPower::global().subscribe();
+        let mut last_frame: Option<Instant> = None;
+        if policy == LowPowerPolicy::Suspend && *power.borrow_and_update() {
+            let _ = stream.close(None).await;
+            return Ok(true);
+        }
+
+        loop {
+            let message = tokio::select! {
+                message = stream.next() => match message {
+                    Some(message) => message,
+                    None => break,
+                },
+                _ = self.restart.notified() => {
+                    let _ = stream.close(None).await;
+                    break;
+                }
+                _ = power.changed(), if policy == LowPowerPolicy::Suspend => {
+                    if *power.borrow() {
+                        let _ = stream.close(None).await;
+                        return Ok(true);
+                    }
+                    continue;
+                }
+            };
+
+            match message {
+                Ok(Message::Text(text)) => {
+                    if let LowPowerPolicy::Throttle(interval) = policy {
+                        let skip = *power.borrow()
+                            && last_frame.is_some_and(|last| last.elapsed() < interval);
+                        if skip {
+                            continue;
+                        }
+                        last_frame = Some(Instant::now());
+                    }
+                    handler.on_message(text)
+                }
+                Ok(Message::Close(_)) => break,
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::debug!("stream `{path}` error: {err}");
+                    break;
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn set_state(&self, name: &'static str, state: StreamState) {
+        let mut states = self.states.lock();
+        if states.get(name) == Some(&state) {
+            return;
+        }
+        states.insert(name, state.clone());
+        drop(states);
+
+        handle::Handle::emit("verge://stream-state", (name, state));
+    }
+
+    pub fn states(&self) -> HashMap<String, StreamState> {
+        self.states
+            .lock()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    /// reconnect the waiting streams immediately
+    /// e.g. the core is restarted
+    pub fn reconnect_all(&self) {
+        self.wake.notify_waiters();
+    }
+
+    /// reconnect all the streams including the connected ones
+    /// e.g. the sockets are dead after the system sleeps
+    pub fn restart_all(&self) {
+        self.restart.notify_waiters();
+        self.reconnect_all();
+    }
+
+    /// the controller needs a moment to listen after the core starts
+    pub fn reconnect_later(&'static self) {
+        tauri::async_runtime::spawn(async move {
+            sleep(Duration::from_secs(1)).await;
+            self.reconnect_all();
+        });
+    }
+
+    pub fn subscribe_events(&'static self) {
+        EventBus::subscribe("stream", move |event| async move {
+            if let AppEvent::CoreState {
+                state: CoreState::Started,
+            } = event
+            {
+                self.reconnect_later();
+            }
+        });
+    }
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1).min(16));
+    BACKOFF_BASE.saturating_mul(factor).min(BACKOFF_MAX)
+}
+
+#[test]
+fn test_backoff() {
+    assert_eq!(backoff(1), Duration::from_millis(500));
+    assert_eq!(backoff(2), Duration::from_secs(1));
+    assert_eq!(backoff(4), Duration::from_secs(4));
+    assert_eq!(backoff(7), Duration::from_secs(30));
+    assert_eq!(backoff(u32::MAX), Duration::from_secs(30));
+}
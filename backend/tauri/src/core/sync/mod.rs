@@ -0,0 +1,418 @@
+//! continuous sync of the profiles between the devices
+//!
+//! the remote keeps an `index.json` with the revision of every file,
+//! the contents are stored as objects named by their sha256.
+//! the local side remembers the revision and the hash of the last sync,
+//! so the changes of both sides can be told apart from the conflicts.
+
+mod remote;
+
+pub use remote::set_s3_secret;
+
+use super::{handle, storage::Storage, trash};
+use crate::{
+    config::{Config, IProfiles, SyncConflictStrategy},
+    feat,
+    utils::{dirs, secrets},
+};
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::PathBuf,
+};
+use sysinfo::{System, SystemExt};
+
+const INDEX: &str = "index.json";
+const STATE_KEY: &str = "sync_state";
+const DEVICE_KEY: &str = "sync_device_id";
+const LAST_SYNC_KEY: &str = "sync_last_time";
+/// the attempts when the remote index is changed during the sync
+const MAX_ATTEMPTS: usize = 3;
+
+#[derive(Debug, thiserror::Error)]
+#[error("the remote index is changed by another device, try again later")]
+struct IndexChanged;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RemoteIndex {
+    files: BTreeMap<String, RemoteRevision>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RemoteRevision {
+    revision: u64,
+    /// `None` means the file is deleted
+    hash: Option<String>,
+    device: String,
+    /// the modified time of the file on the pushing device, the time of the push if deleted
+    modified: i64,
+}
+
+/// the revision and the hash of the last sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Base {
+    revision: u64,
+    hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncConflict {
+    pub path: String,
+    /// the modified time of the local file, `None` if deleted locally
+    pub local_modified: Option<i64>,
+    pub remote_modified: i64,
+    pub remote_device: String,
+    pub remote_deleted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPick {
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub pushed: Vec<String>,
+    pub pulled: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+pub struct SyncManager {
+    /// the conflicts waiting for the manual pick
+    conflicts: Mutex<Vec<SyncConflict>>,
+
+    /// the picks applied on the next sync
+    picks: Mutex<HashMap<String, SyncPick>>,
+
+    running: tokio::sync::Mutex<()>,
+}
+
+impl SyncManager {
+    pub fn global() -> &'static SyncManager {
+        static SYNC: OnceCell<SyncManager> = OnceCell::new();
+
+        SYNC.get_or_init(|| SyncManager {
+            conflicts: Mutex::new(vec![]),
+            picks: Mutex::new(HashMap::new()),
+            running: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn conflicts(&self) -> Vec<SyncConflict> {
+        self.conflicts.lock().clone()
+    }
+
+    /// pick a side of the conflict and sync again
+    pub async fn resolve(&self, path: String, pick: SyncPick) -> Result<SyncReport> {
+        let exists = self.conflicts.lock().iter().any(|c| c.path == path);
+        if !exists {
+            bail!("the conflict of `{path}` is not found");
+        }
+        self.picks.lock().insert(path, pick);
+        self.sync().await
+    }
+
+    /// sync if enabled and the interval passed
+    pub async fn sync_if_due(&self) -> Result<()> {
+        let (enable, interval) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_sync.unwrap_or(false),
+                verge.sync_interval.unwrap_or(5),
+            )
+        };
+        if !enable || interval == 0 {
+            return Ok(());
+        }
+
+        let last = Storage::global()
+            .get_item::<i64>(LAST_SYNC_KEY)?
+            .unwrap_or(0);
+        if chrono::Local::now().timestamp() - last < interval as i64 * 60 {
+            return Ok(());
+        }
+        self.sync().await.map(|_| ())
+    }
+
+    pub async fn sync(&self) -> Result<SyncReport> {
+        let _guard = self
+            .running
+            .try_lock()
+            .map_err(|_| anyhow!("the sync is already running"))?;
+
+        let picks = std::mem::take(&mut *self.picks.lock());
+        // 其他设备同时写了索引，重新读取后再同步
+        for _ in 0..MAX_ATTEMPTS {
+            match self.sync_once(&picks).await {
+                Err(err) if err.is::<IndexChanged>() => {
+                    tracing::info!("the remote index is changed by another device, retry");
+                }
+                result => return result,
+            }
+        }
+        Err(IndexChanged.into())
+    }
+
+    async fn sync_once(&self, picks: &HashMap<String, SyncPick>) -> Result<SyncReport> {
+        let remote = remote::from_config()?;
+        let strategy = { Config::verge().latest().sync_conflict_strategy };
+        let strategy = strategy.unwrap_or_default();
+        let device = device_name();
+        let now = chrono::Local::now().timestamp();
+
+        let (index, version) = remote.get_versioned(INDEX).await?;
+        let mut index = match index {
+            Some(data) => serde_json::from_slice::<RemoteIndex>(&data)?,
+            None => RemoteIndex::default(),
+        };
+        let mut state = Storage::global()
+            .get_item::<BTreeMap<String, Base>>(STATE_KEY)?
+            .unwrap_or_default();
+        let local = local_files()?;
+
+        let paths = local
+            .keys()
+            .chain(index.files.keys())
+            .chain(state.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        let mut report = SyncReport::default();
+        let mut index_changed = false;
+        let mut snapshotted = false;
+
+        for path in paths {
+            let base = state.get(&path).cloned().unwrap_or_default();
+            let local_file = match local.get(&path) {
+                Some(file) => Some((file.clone(), secrets::read_resolved(file)?)),
+                None => None,
+            };
+            let local_hash = local_file.as_ref().map(|(_, data)| sha256(data));
+            let remote_rev = index.files.get(&path).cloned();
+            let remote_hash = remote_rev.as_ref().and_then(|r| r.hash.clone());
+            let remote_revision = remote_rev.as_ref().map(|r| r.revision).unwrap_or(0);
+
+            let local_changed = local_hash != base.hash;
+            let remote_changed = remote_revision != base.revision;
+
+            let pick = match (local_changed, remote_changed) {
+                (false, false) => continue,
+                (true, false) => SyncPick::Local,
+                (false, true) => SyncPick::Remote,
+                _ if local_hash == remote_hash => {
+                    // 两边改成了一样的内容
+                    state.insert(
+                        path,
+                        Base {
+                            revision: remote_revision,
+                            hash: local_hash,
+                        },
+                    );
+                    continue;
+                }
+                // 远端的索引被清空了，重新上传
+                _ if remote_rev.is_none() => SyncPick::Local,
+                _ => {
+                    let remote_rev = remote_rev.clone().unwrap_or_default();
+                    let local_modified = local_file
+                        .as_ref()
+                        .and_then(|(file, _)| modified_time(file));
+
+                    match (picks.get(&path), strategy) {
+                        (Some(pick), _) => *pick,
+                        // 两边都是文件的修改时间，本地删除的按现在算
+                        (None, SyncConflictStrategy::LastWriterWins) => {
+                            match local_modified.unwrap_or(now) >= remote_rev.modified {
+                                true => SyncPick::Local,
+                                false => SyncPick::Remote,
+                            }
+                        }
+                        (None, SyncConflictStrategy::Manual) => {
+                            report.conflicts.push(SyncConflict {
+                                path,
+                                local_modified,
+                                remote_modified: remote_rev.modified,
+                                remote_device: remote_rev.device,
+                                remote_deleted: remote_rev.hash.is_none(),
+                            });
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            match pick {
+                SyncPick::Local => {
+                    let modified = local_file
+                        .as_ref()
+                        .and_then(|(file, _)| modified_time(file))
+                        .unwrap_or(now);
+                    if let (Some((_, data)), Some(hash)) = (local_file, &local_hash) {
+                        remote.put(hash, data).await?;
+                    }
+                    let revision = remote_revision.max(base.revision) + 1;
+                    index.files.insert(
+                        path.clone(),
+                        RemoteRevision {
+                            revision,
+                            hash: local_hash.clone(),
+                            device: device.clone(),
+                            modified,
+                        },
+                    );
+                    index_changed = true;
+                    state.insert(
+                        path.clone(),
+                        Base {
+                            revision,
+                            hash: local_hash,
+                        },
+                    );
+                    report.pushed.push(path);
+                }
+                SyncPick::Remote => {
+                    // 覆盖本地的文件前先存一份快照
+                    if !snapshotted {
+                        trash::snapshot_all(trash::ProfileOperation::Import)?;
+                        snapshotted = true;
+                    }
+                    let target = local_path(&path)?;
+                    match &remote_hash {
+                        Some(hash) => {
+                            let data = remote
+                                .get(hash)
+                                .await?
+                                .ok_or(anyhow!("the object of `{path}` is missing"))?;
+                            if &sha256(&data) != hash {
+                                bail!("the object of `{path}` is corrupted");
+                            }
+                            fs::write(&target, data)?;
+                        }
+                        None if target.exists() => fs::remove_file(&target)?,
+                        None => {}
+                    }
+                    state.insert(
+                        path.clone(),
+                        Base {
+                            revision: remote_revision,
+                            hash: remote_hash,
+                        },
+                    );
+                    report.pulled.push(path);
+                }
+            }
+        }
+
+        if index_changed {
+            let data = serde_json::to_vec(&index)?;
+            if !remote.put_if_match(INDEX, data, &version).await? {
+                // 内容按哈希存放，已上传的对象不用回滚
+                return Err(IndexChanged.into());
+            }
+        }
+        Storage::global().set_item(STATE_KEY, &state)?;
+        Storage::global().set_item(LAST_SYNC_KEY, &now)?;
+        *self.conflicts.lock() = report.conflicts.clone();
+
+        if !report.pulled.is_empty() {
+            reload().await?;
+        }
+
+        tracing::info!(
+            "sync finished, pushed {}, pulled {}, conflicts {}",
+            report.pushed.len(),
+            report.pulled.len(),
+            report.conflicts.len()
+        );
+        if !report.conflicts.is_empty() {
+            handle::Handle::emit("nyanpasu://sync-conflict", report.conflicts.len());
+        }
+        Ok(report)
+    }
+}
+
+/// the synced files, `relative path -> absolute path`
+/// the subscriptions and the group selections are all in these files
+fn local_files() -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+
+    let profiles = dirs::profiles_path()?;
+    if profiles.exists() {
+        files.insert("profiles.yaml".to_string(), profiles);
+    }
+
+    let profiles_dir = dirs::app_profiles_dir()?;
+    if profiles_dir.exists() {
+        for entry in fs::read_dir(&profiles_dir)?.flatten() {
+            let path = entry.path();
+            if path.is_file() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                files.insert(format!("profiles/{name}"), path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn local_path(path: &str) -> Result<PathBuf> {
+    if path == "profiles.yaml" {
+        return dirs::profiles_path();
+    }
+    match path.strip_prefix("profiles/") {
+        // 防止远端的路径跳出配置目录
+        Some(name) if !name.is_empty() && !name.contains(['/', '\\']) && name != ".." => {
+            Ok(dirs::app_profiles_dir()?.join(name))
+        }
+        _ => bail!("invalid synced path `{path}`"),
+    }
+}
+
+/// reload the profiles after pulled and apply them to the core
+async fn reload() -> Result<()> {
+    *Config::profiles().draft() = IProfiles::from_file()?;
+    Config::profiles().apply();
+    // 拉取的文件里是明文，重新保存一次移到 keyring
+    Config::profiles().data().save_file()?;
+    feat::update_core_config().await?;
+    handle::Handle::refresh_profiles();
+    Ok(())
+}
+
+fn sha256(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn modified_time(path: &PathBuf) -> Option<i64> {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    Some(chrono::DateTime::<chrono::Local>::from(modified).timestamp())
+}
+
+/// the hostname with a random id, the id is generated once for each device
+fn device_name() -> String {
+    let id = match Storage::global().get_item::<String>(DEVICE_KEY) {
+        Ok(Some(id)) => id,
+        _ => {
+            let id = nanoid::nanoid!(8);
+            let _ = Storage::global().set_item(DEVICE_KEY, &id);
+            id
+        }
+    };
+    let hostname = System::new().host_name().unwrap_or("unknown".into());
+    format!("{hostname}-{id}")
+}
+
+#[test]
+fn test_local_path() {
+    assert!(local_path("profiles/a.yaml").is_ok());
+    assert!(local_path("profiles/../verge.yaml").is_err());
+    assert!(local_path("profiles/..").is_err());
+    assert!(local_path("verge.yaml").is_err());
+}
@@ -0,0 +1,237 @@
+use crate::{
+    config::{Config, HttpPurpose, SyncProvider},
+    core::{
+        http::{self, HttpClient},
+        webdav::WebDav,
+    },
+    utils::secrets,
+};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::{header::ETAG, Method, RequestBuilder, Response, StatusCode};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+/// the dir of the webdav endpoint, or the key prefix of the s3 bucket
+pub const SYNC_DIR: &str = "clash-nyanpasu-sync";
+
+/// the storage of the synced files, the names are flat
+#[async_trait]
+pub trait SyncRemote: Send + Sync {
+    /// `None` if not found
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>>;
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<()>;
+
+    /// the object with the version it was read at
+    async fn get_versioned(&self, name: &str) -> Result<(Option<Vec<u8>>, Version)>;
+
+    /// write only if the object is still at `version`, `false` if another device changed it
+    async fn put_if_match(&self, name: &str, data: Vec<u8>, version: &Version) -> Result<bool>;
+}
+
+/// the version of a remote object, checked by the conditional put
+#[derive(Debug, Clone)]
+pub enum Version {
+    Missing,
+    ETag(String),
+    /// the server doesn't return the etags, the put can't be guarded
+    Unknown,
+}
+
+/// `If-None-Match: *` keeps two devices from creating the object at once
+fn precondition(builder: RequestBuilder, version: &Version) -> RequestBuilder {
+    match version {
+        Version::Missing => builder.header("If-None-Match", "*"),
+        Version::ETag(etag) => builder.header("If-Match", etag),
+        Version::Unknown => builder,
+    }
+}
+
+async fn read_versioned(response: Response) -> Result<(Option<Vec<u8>>, Version)> {
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok((None, Version::Missing));
+    }
+    let response = response.error_for_status()?;
+    let version = match response.headers().get(ETAG).and_then(|v| v.to_str().ok()) {
+        Some(etag) => Version::ETag(etag.to_string()),
+        None => Version::Unknown,
+    };
+    Ok((Some(response.bytes().await?.to_vec()), version))
+}
+
+fn check_put(response: Response) -> Result<bool> {
+    if response.status() == StatusCode::PRECONDITION_FAILED {
+        return Ok(false);
+    }
+    response.error_for_status()?;
+    Ok(true)
+}
+
+#[async_trait]
+impl SyncRemote for WebDav {
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        WebDav::get(self, name).await
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        self.upload(name, data).await
+    }
+
+    async fn get_versioned(&self, name: &str) -> Result<(Option<Vec<u8>>, Version)> {
+        read_versioned(self.send(self.request(Method::GET, name)?).await?).await
+    }
+
+    async fn put_if_match(&self, name: &str, data: Vec<u8>, version: &Version) -> Result<bool> {
+        self.ensure_dir().await?;
+        let request = precondition(self.request(Method::PUT, name)?, version);
+        check_put(self.send(request.body(data)).await?)
+    }
+}
+
+pub fn from_config() -> Result<Box<dyn SyncRemote>> {
+    let provider = { Config::verge().latest().sync_provider };
+    match provider.unwrap_or_default() {
+        SyncProvider::Webdav => Ok(Box::new(WebDav::from_config(SYNC_DIR)?)),
+        SyncProvider::S3 => Ok(Box::new(S3::from_config()?)),
+    }
+}
+
+/// the secret access key is kept in the os keyring
+pub fn set_s3_secret(access_key_id: &str, secret: &str) -> Result<()> {
+    secrets::set(&format!("s3::{access_key_id}"), secret)
+}
+
+fn get_s3_secret(access_key_id: &str) -> Result<String> {
+    secrets::get(&format!("s3::{access_key_id}"))?.ok_or(anyhow!("the s3 secret is not set"))
+}
+
+/// the s3 compatible storage with the path style urls, signed by aws signature v4
+pub struct S3 {
+    client: HttpClient,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret: String,
+}
+
+impl S3 {
+    fn from_config() -> Result<Self> {
+        let config = { Config::verge().latest().sync_s3.clone() };
+        let config = config.ok_or(anyhow!("the s3 storage is not configured"))?;
+        let secret = get_s3_secret(&config.access_key_id)?;
+
+        Ok(Self {
+            client: http::client(HttpPurpose::Sync)?,
+            endpoint: Url::parse(&config.endpoint).context("invalid s3 endpoint")?,
+            bucket: config.bucket,
+            region: config.region.unwrap_or("us-east-1".into()),
+            access_key_id: config.access_key_id,
+            secret,
+        })
+    }
+
+    fn request(&self, method: Method, name: &str, body: &[u8]) -> Result<RequestBuilder> {
+        let mut url = self.endpoint.clone();
+        url.set_path(&format!("/{}/{SYNC_DIR}/{name}", self.bucket));
+
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(body));
+        let host = match url.port() {
+            Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\nhost;x-amz-content-sha256;x-amz-date\n{payload_hash}",
+            method.as_str(),
+            url.path(),
+        );
+        let scope = format!("{}/{}/s3/aws4_request", &amz_date[..8], self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = signing_key(&self.secret, &amz_date[..8], &self.region, "s3");
+        let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={signature}",
+            self.access_key_id
+        );
+
+        Ok(self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization))
+    }
+}
+
+#[async_trait]
+impl SyncRemote for S3 {
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let response = self
+            .client
+            .send(self.request(Method::GET, name, &[])?)
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        let request = self.request(Method::PUT, name, &data)?.body(data);
+        self.client.send(request).await?.error_for_status()?;
+        Ok(())
+    }
+
+    async fn get_versioned(&self, name: &str) -> Result<(Option<Vec<u8>>, Version)> {
+        read_versioned(
+            self.client
+                .send(self.request(Method::GET, name, &[])?)
+                .await?,
+        )
+        .await
+    }
+
+    async fn put_if_match(&self, name: &str, data: Vec<u8>, version: &Version) -> Result<bool> {
+        let request = precondition(self.request(Method::PUT, name, &data)?, version);
+        check_put(self.client.send(request.body(data)).await?)
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // hmac accepts the keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{secret}").as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+#[test]
+fn test_signing_key() {
+    // the example of the aws documents
+    let key = signing_key(
+        "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        "20120215",
+        "us-east-1",
+        "iam",
+    );
+    assert_eq!(
+        hex::encode(key),
+        "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+    );
+}
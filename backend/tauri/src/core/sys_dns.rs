@@ -0,0 +1,209 @@
+//! override the system dns with the tun device in tun mode
+//! the original servers are saved to a file, so that they can be restored after a crash
+
+use crate::{
+    config::Config,
+    utils::{dirs, help},
+};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, process::Command};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct DnsBackup {
+    /// network service on macos, interface alias on windows
+    network: String,
+    /// empty means the dns is provided by dhcp
+    servers: Vec<String>,
+}
+
+fn backup_path() -> Result<PathBuf> {
+    Ok(dirs::app_home_dir()?.join("dns_backup.yaml"))
+}
+
+/// apply or restore according to the current config
+pub fn update() -> Result<()> {
+    let (enable_tun, enable_override) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.enable_tun_mode.unwrap_or(false),
+            verge.enable_tun_dns_override.unwrap_or(false),
+        )
+    };
+
+    match enable_tun && enable_override {
+        true => apply(),
+        false => restore(),
+    }
+}
+
+/// point the system dns to the tun device
+pub fn apply() -> Result<()> {
+    let dns = { Config::clash().data().get_tun_device_ip() };
+    let path = backup_path()?;
+
+    // 已经备份过说明已经设置了，不要覆盖原来的设置
+    if !path.exists() {
+        let network = current_network()?;
+        let servers = get_dns(&network)?;
+
+        let backup = DnsBackup { network, servers };
+        help::save_yaml(&path, &backup, Some("# Original System DNS"))?;
+    }
+
+    let backup = help::read_yaml::<DnsBackup>(&path)?;
+    set_dns(&backup.network, &[dns.clone()])?;
+    flush_cache();
+
+    tracing::info!("override the system dns of `{}` with {dns}", backup.network);
+    Ok(())
+}
+
+/// restore the original dns if it was overridden
+pub fn restore() -> Result<()> {
+    let path = backup_path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let backup = help::read_yaml::<DnsBackup>(&path)?;
+    set_dns(&backup.network, &backup.servers)?;
+    flush_cache();
+    std::fs::remove_file(path)?;
+
+    tracing::info!("restore the system dns of `{}`", backup.network);
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn current_network() -> Result<String> {
+    Ok(sysproxy::current_network()?)
+}
+
+#[cfg(target_os = "macos")]
+fn get_dns(network: &str) -> Result<Vec<String>> {
+    let output = Command::new("networksetup")
+        .args(["-getdnsservers", network])
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // "There aren't any DNS Servers set on Wi-Fi."
+    Ok(stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.parse::<std::net::IpAddr>().is_ok())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+fn set_dns(network: &str, servers: &[String]) -> Result<()> {
+    // networksetup 修改 dns 需要管理员权限
+    if super::mac_helper::is_enabled() {
+        return super::mac_helper::set_dns(network, servers);
+    }
+
+    let mut args = vec!["-setdnsservers".to_string(), network.to_string()];
+    match servers.is_empty() {
+        true => args.push("Empty".into()),
+        false => args.extend(servers.iter().cloned()),
+    }
+
+    let status = Command::new("networksetup").args(args).status()?;
+    if !status.success() {
+        bail!("failed to set the dns with status {status}");
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn flush_cache() {
+    let _ = Command::new("dscacheutil").arg("-flushcache").status();
+}
+
+#[cfg(target_os = "windows")]
+fn powershell(script: &str) -> Result<String> {
+    use std::os::windows::process::CommandExt;
+
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .creation_flags(0x08000000)
+        .output()?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// the interface of the default route, excluding the tun device
+#[cfg(target_os = "windows")]
+fn current_network() -> Result<String> {
+    let tun = { Config::clash().data().get_tun_device_ip() };
+    let script = format!(
+        "Get-NetRoute -DestinationPrefix 0.0.0.0/0 | Sort-Object RouteMetric | \
+         Where-Object {{ (Get-NetIPAddress -InterfaceIndex $_.InterfaceIndex -AddressFamily IPv4).IPAddress -ne '{tun}' }} | \
+         Select-Object -First 1 -ExpandProperty InterfaceAlias"
+    );
+    let alias = powershell(&script)?;
+    if alias.is_empty() {
+        bail!("failed to get the default network interface");
+    }
+    Ok(alias)
+}
+
+#[cfg(target_os = "windows")]
+fn get_dns(network: &str) -> Result<Vec<String>> {
+    // dhcp 分配的 dns 不能被恢复成静态的
+    let script = format!(
+        "(Get-ItemProperty -Path \"HKLM:\\SYSTEM\\CurrentControlSet\\Services\\Tcpip\\Parameters\\Interfaces\\$((Get-NetAdapter -Name '{network}').InterfaceGuid)\").NameServer"
+    );
+    let servers = powershell(&script)?;
+    Ok(servers
+        .split([',', ' '])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+fn set_dns(network: &str, servers: &[String]) -> Result<()> {
+    let script = match servers.is_empty() {
+        true => {
+            format!("Set-DnsClientServerAddress -InterfaceAlias '{network}' -ResetServerAddresses")
+        }
+        false => format!(
+            "Set-DnsClientServerAddress -InterfaceAlias '{network}' -ServerAddresses {}",
+            servers.join(",")
+        ),
+    };
+    powershell(&script)?;
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn flush_cache() {
+    let _ = powershell("Clear-DnsClientCache");
+}
+
+/// the dns hijack of the tun works well with systemd-resolved
+#[cfg(target_os = "linux")]
+fn current_network() -> Result<String> {
+    bail!("overriding the system dns is not supported on linux")
+}
+
+#[cfg(target_os = "linux")]
+fn get_dns(_network: &str) -> Result<Vec<String>> {
+    Ok(Vec::new())
+}
+
+#[cfg(target_os = "linux")]
+fn set_dns(_network: &str, _servers: &[String]) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn flush_cache() {
+    let _ = Command::new("resolvectl").arg("flush-caches").status();
+}
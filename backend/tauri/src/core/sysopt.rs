@@ -1,12 +1,36 @@
+use super::{
+    event_bus::{AppEvent, EventBus},
+    pac,
+    throttle::Throttle,
+};
 use crate::{config::Config, log_err};
 use anyhow::{anyhow, Result};
 use auto_launch::{AutoLaunch, AutoLaunchBuilder};
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
 use std::sync::Arc;
-use sysproxy::Sysproxy;
+use sysproxy::{Autoproxy, Sysproxy};
 use tauri::{async_runtime::Mutex as TokioMutex, utils::platform::current_exe};
 
+/// passed to the app when launched by the system
+/// a launch agent is used on macos, the login items do not support arguments
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+/// whether the app is launched by the system startup
+pub fn is_autostart() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+/// the port of the system proxy and the pac, the throttled inbound if enabled
+pub fn proxy_port() -> u16 {
+    Throttle::global().port().unwrap_or_else(|| {
+        Config::verge()
+            .latest()
+            .verge_mixed_port
+            .unwrap_or(Config::clash().data().get_mixed_port())
+    })
+}
+
 pub struct Sysopt {
     /// current system proxy setting
     cur_sysproxy: Arc<Mutex<Option<Sysproxy>>>,
@@ -15,20 +39,24 @@ pub struct Sysopt {
     /// recover it when exit
     old_sysproxy: Arc<Mutex<Option<Sysproxy>>>,
 
+    /// current pac setting, only enabled in pac mode
+    cur_autoproxy: Arc<Mutex<Option<Autoproxy>>>,
+
+    /// record the original pac setting
+    old_autoproxy: Arc<Mutex<Option<Autoproxy>>>,
+
     /// helps to auto launch the app
     auto_launch: Arc<Mutex<Option<AutoLaunch>>>,
 
     /// record whether the guard async is running or not
     guard_state: Arc<TokioMutex<bool>>,
-}
 
-#[cfg(target_os = "windows")]
-static DEFAULT_BYPASS: &str = "localhost;127.*;192.168.*;10.*;172.16.*;<local>";
-#[cfg(target_os = "linux")]
-static DEFAULT_BYPASS: &str = "localhost,127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,::1";
-#[cfg(target_os = "macos")]
-static DEFAULT_BYPASS: &str =
-    "127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,localhost,*.local,*.crashlytics.com,<local>";
+    /// the active network when the proxy was applied
+    cur_network: Arc<Mutex<Option<String>>>,
+
+    /// turned off by a network rule until leaving the network, not saved to the config
+    proxy_paused: Arc<Mutex<bool>>,
+}
 
 impl Sysopt {
     pub fn global() -> &'static Sysopt {
@@ -37,40 +65,57 @@ impl Sysopt {
         SYSOPT.get_or_init(|| Sysopt {
             cur_sysproxy: Arc::new(Mutex::new(None)),
             old_sysproxy: Arc::new(Mutex::new(None)),
+            cur_autoproxy: Arc::new(Mutex::new(None)),
+            old_autoproxy: Arc::new(Mutex::new(None)),
             auto_launch: Arc::new(Mutex::new(None)),
             guard_state: Arc::new(TokioMutex::new(false)),
+            cur_network: Arc::new(Mutex::new(None)),
+            proxy_paused: Arc::new(Mutex::new(false)),
         })
     }
 
     /// init the sysproxy
     pub fn init_sysproxy(&self) -> Result<()> {
-        let port = Config::verge()
-            .latest()
-            .verge_mixed_port
-            .unwrap_or(Config::clash().data().get_mixed_port());
+        let port = proxy_port();
 
-        let (enable, bypass) = {
+        let (enable, pac, bypass) = {
             let verge = Config::verge();
             let verge = verge.latest();
             (
                 verge.enable_system_proxy.clone().unwrap_or(false),
-                verge.system_proxy_bypass.clone(),
+                verge.enable_proxy_pac.clone().unwrap_or(false),
+                verge.get_system_proxy_bypass(),
             )
         };
 
+        let enable = enable && !*self.proxy_paused.lock();
+
         let current = Sysproxy {
-            enable,
+            enable: enable && !pac,
             host: String::from("127.0.0.1"),
             port,
-            bypass: bypass.unwrap_or(DEFAULT_BYPASS.into()),
+            bypass,
+        };
+        let autoproxy = Autoproxy {
+            enable: enable && pac,
+            url: pac::pac_url(),
         };
 
         if enable {
             let old = Sysproxy::get_system_proxy().map_or(None, |p| Some(p));
-            current.set_system_proxy()?;
+            let old_auto = Autoproxy::get_auto_proxy().map_or(None, |p| Some(p));
+
+            // pac 模式下设置自动配置地址，而不是全局代理
+            match pac {
+                true => set_auto(&autoproxy)?,
+                false => set_proxy(&current, None)?,
+            }
 
             *self.old_sysproxy.lock() = old;
             *self.cur_sysproxy.lock() = Some(current);
+            *self.old_autoproxy.lock() = old_auto;
+            *self.cur_autoproxy.lock() = Some(autoproxy);
+            *self.cur_network.lock() = sysproxy::current_network().ok();
         }
 
         // run the system proxy guard
@@ -89,25 +134,62 @@ impl Sysopt {
             return self.init_sysproxy();
         }
 
-        let (enable, bypass) = {
+        let (enable, pac, bypass) = {
             let verge = Config::verge();
             let verge = verge.latest();
             (
                 verge.enable_system_proxy.clone().unwrap_or(false),
-                verge.system_proxy_bypass.clone(),
+                verge.enable_proxy_pac.clone().unwrap_or(false),
+                verge.get_system_proxy_bypass(),
             )
         };
+        let enable = enable && !*self.proxy_paused.lock();
         let mut sysproxy = cur_sysproxy.take().unwrap();
 
-        sysproxy.enable = enable;
-        sysproxy.bypass = bypass.unwrap_or(DEFAULT_BYPASS.into());
+        sysproxy.enable = enable && !pac;
+        sysproxy.port = proxy_port();
+        sysproxy.bypass = bypass;
+
+        let mut cur_autoproxy = self.cur_autoproxy.lock();
+        let mut autoproxy = cur_autoproxy.take().unwrap_or_default();
+        let was_auto = autoproxy.enable;
+
+        autoproxy.enable = enable && pac;
+        autoproxy.url = pac::pac_url();
+
+        // 先关闭另一种模式，避免 linux 下 mode 被覆盖
+        if autoproxy.enable {
+            set_proxy(&sysproxy, None)?;
+            set_auto(&autoproxy)?;
+        } else {
+            if was_auto {
+                set_auto(&autoproxy)?;
+            }
+            set_proxy(&sysproxy, None)?;
+        }
 
-        sysproxy.set_system_proxy()?;
         *cur_sysproxy = Some(sysproxy);
+        *cur_autoproxy = Some(autoproxy);
+        *self.cur_network.lock() = sysproxy::current_network().ok();
 
         Ok(())
     }
 
+    /// paused by the network rules
+    pub fn is_sysproxy_paused(&self) -> bool {
+        *self.proxy_paused.lock()
+    }
+
+    /// turn off the system proxy without changing `enable_system_proxy`
+    pub fn pause_sysproxy(&self, paused: bool) -> Result<()> {
+        let previous = std::mem::replace(&mut *self.proxy_paused.lock(), paused);
+        if previous == paused {
+            return Ok(());
+        }
+        tracing::info!("system proxy paused: {paused}");
+        self.update_sysproxy()
+    }
+
     /// reset the sysproxy
     pub fn reset_sysproxy(&self) -> Result<()> {
         let mut cur_sysproxy = self.cur_sysproxy.lock();
@@ -122,19 +204,36 @@ impl Sysopt {
 
             if old.enable && port_same {
                 old.enable = false;
-                log::info!(target: "app", "reset proxy by disabling the original proxy");
+                tracing::info!("reset proxy by disabling the original proxy");
             } else {
-                log::info!(target: "app", "reset proxy to the original proxy");
+                tracing::info!("reset proxy to the original proxy");
             }
 
-            old.set_system_proxy()?;
+            set_proxy(&old, None)?;
         } else if let Some(mut cur @ Sysproxy { enable: true, .. }) = cur_sysproxy {
             // 没有原代理，就按现在的代理设置disable即可
-            log::info!(target: "app", "reset proxy by disabling the current proxy");
+            tracing::info!("reset proxy by disabling the current proxy");
             cur.enable = false;
-            cur.set_system_proxy()?;
+            set_proxy(&cur, None)?;
         } else {
-            log::info!(target: "app", "reset proxy with no action");
+            tracing::info!("reset proxy with no action");
+        }
+
+        let cur_autoproxy = self.cur_autoproxy.lock().take();
+        let old_autoproxy = self.old_autoproxy.lock().take();
+
+        if let Some(mut cur @ Autoproxy { enable: true, .. }) = cur_autoproxy {
+            match old_autoproxy {
+                Some(old) if old.enable && old.url != cur.url => {
+                    tracing::info!("reset pac to the original url");
+                    set_auto(&old)?;
+                }
+                _ => {
+                    tracing::info!("reset pac by disabling the current url");
+                    cur.enable = false;
+                    set_auto(&cur)?;
+                }
+            }
         }
 
         Ok(())
@@ -162,19 +261,6 @@ impl Sysopt {
         #[cfg(target_os = "windows")]
         let app_path = format!("\"{app_path}\"");
 
-        // use the /Applications/Clash Nyanpasu.app path
-        #[cfg(target_os = "macos")]
-        let app_path = (|| -> Option<String> {
-            let path = std::path::PathBuf::from(&app_path);
-            let path = path.parent()?.parent()?.parent()?;
-            let extension = path.extension()?.to_str()?;
-            match extension == "app" {
-                true => Some(path.as_os_str().to_str()?.to_string()),
-                false => None,
-            }
-        })()
-        .unwrap_or(app_path);
-
         // fix #403
         #[cfg(target_os = "linux")]
         let app_path = {
@@ -196,6 +282,8 @@ impl Sysopt {
         let auto = AutoLaunchBuilder::new()
             .set_app_name(app_name)
             .set_app_path(&app_path)
+            .set_args(&[AUTOSTART_ARG])
+            .set_use_launch_agent(cfg!(target_os = "macos"))
             .build()?;
 
         // 避免在开发时将自启动关了
@@ -206,16 +294,17 @@ impl Sysopt {
 
         #[cfg(target_os = "macos")]
         {
-            if enable && !auto.is_enabled().unwrap_or(false) {
-                // 避免重复设置登录项
-                let _ = auto.disable();
-                auto.enable()?;
-            } else if !enable {
-                let _ = auto.disable();
+            remove_login_item(&app_exe);
+            match enable {
+                true => auto.enable()?,
+                false => log_err!(auto.disable()),
             }
         }
 
-        #[cfg(not(target_os = "macos"))]
+        #[cfg(target_os = "windows")]
+        apply_windows_launch(&auto, enable)?;
+
+        #[cfg(target_os = "linux")]
         if enable {
             auto.enable()?;
         }
@@ -237,6 +326,10 @@ impl Sysopt {
         let enable = enable.unwrap_or(false);
         let auto_launch = auto_launch.as_ref().unwrap();
 
+        #[cfg(target_os = "windows")]
+        apply_windows_launch(auto_launch, enable)?;
+
+        #[cfg(not(target_os = "windows"))]
         match enable {
             true => auto_launch.enable()?,
             false => log_err!(auto_launch.disable()), // 忽略关闭的错误
@@ -267,14 +360,15 @@ impl Sysopt {
             loop {
                 sleep(Duration::from_secs(wait_secs)).await;
 
-                let (enable, guard, guard_duration, bypass) = {
+                let (enable, pac, guard, guard_duration, bypass) = {
                     let verge = Config::verge();
                     let verge = verge.latest();
                     (
                         verge.enable_system_proxy.clone().unwrap_or(false),
+                        verge.enable_proxy_pac.clone().unwrap_or(false),
                         verge.enable_proxy_guard.clone().unwrap_or(false),
                         verge.proxy_guard_duration.clone().unwrap_or(10),
-                        verge.system_proxy_bypass.clone(),
+                        verge.get_system_proxy_bypass(),
                     )
                 };
 
@@ -286,23 +380,31 @@ impl Sysopt {
                 // update duration
                 wait_secs = guard_duration;
 
-                log::debug!(target: "app", "try to guard the system proxy");
+                if *Sysopt::global().proxy_paused.lock() {
+                    continue;
+                }
 
-                let port = {
-                    Config::verge()
-                        .latest()
-                        .verge_mixed_port
-                        .unwrap_or(Config::clash().data().get_mixed_port())
-                };
+                tracing::debug!("try to guard the system proxy");
+
+                if pac {
+                    let autoproxy = Autoproxy {
+                        enable: true,
+                        url: pac::pac_url(),
+                    };
+                    log_err!(set_auto(&autoproxy));
+                    continue;
+                }
+
+                let port = proxy_port();
 
                 let sysproxy = Sysproxy {
                     enable: true,
                     host: "127.0.0.1".into(),
                     port,
-                    bypass: bypass.unwrap_or(DEFAULT_BYPASS.into()),
+                    bypass,
                 };
 
-                log_err!(sysproxy.set_system_proxy());
+                log_err!(set_proxy(&sysproxy, None));
             }
 
             let mut state = guard_state.lock().await;
@@ -310,4 +412,123 @@ impl Sysopt {
             drop(state);
         });
     }
+
+    pub fn subscribe_events(&'static self) {
+        EventBus::subscribe("sysopt", move |event| async move {
+            if let AppEvent::NetworkChanged { previous, current } = event {
+                if previous.network != current.network {
+                    log_err!(self.on_network_changed());
+                }
+            }
+        });
+    }
+
+    /// reapply the system proxy after the active network changes
+    /// e.g. switching from Wi-Fi to Ethernet, macos only sets the proxy for one network service
+    pub fn on_network_changed(&self) -> Result<()> {
+        let enable = { Config::verge().latest().enable_system_proxy.clone() };
+        if !enable.unwrap_or(false) {
+            return Ok(());
+        }
+
+        // 代理设置在哪个网络服务上
+        let previous = self.cur_network.lock().clone();
+        if previous == sysproxy::current_network().ok() {
+            return Ok(());
+        }
+
+        // 移除上一个网络服务的代理
+        #[cfg(target_os = "macos")]
+        if let Some(previous) = previous.as_deref() {
+            let sysproxy = self.cur_sysproxy.lock().clone();
+            if let Some(mut sysproxy) = sysproxy {
+                sysproxy.enable = false;
+                log_err!(set_proxy(&sysproxy, Some(previous)));
+            }
+        }
+
+        self.update_sysproxy()
+    }
+}
+
+/// through the privileged helper on macos if the service mode is on
+/// `service` is the current network service if none
+fn set_proxy(proxy: &Sysproxy, service: Option<&str>) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    if super::mac_helper::is_enabled() {
+        match super::mac_helper::set_system_proxy(proxy, service) {
+            Ok(_) => return Ok(()),
+            Err(err) => tracing::warn!("failed to set the proxy by the helper: {err}"),
+        }
+    }
+
+    match service {
+        #[cfg(target_os = "macos")]
+        Some(service) => proxy.set_system_proxy_on(service)?,
+        _ => proxy.set_system_proxy()?,
+    }
+    Ok(())
+}
+
+fn set_auto(proxy: &Autoproxy) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    if super::mac_helper::is_enabled() {
+        match super::mac_helper::set_auto_proxy(proxy) {
+            Ok(_) => return Ok(()),
+            Err(err) => tracing::warn!("failed to set the pac by the helper: {err}"),
+        }
+    }
+
+    proxy.set_auto_proxy()?;
+    Ok(())
+}
+
+/// the login item of the old versions, it would launch the app twice
+#[cfg(target_os = "macos")]
+fn remove_login_item(app_exe: &std::path::Path) {
+    // /Applications/Clash Nyanpasu.app
+    let app = app_exe
+        .ancestors()
+        .find(|path| path.extension().is_some_and(|ext| ext == "app"))
+        .and_then(|path| path.to_str());
+    if let Some(app) = app {
+        let item = AutoLaunch::new("", app, false, &[] as &[&str]);
+        if item.is_enabled().unwrap_or(false) {
+            log_err!(item.disable());
+        }
+    }
+}
+
+/// register the startup by the `auto_launch_method`
+/// and remove the other mechanisms to avoid launching twice
+#[cfg(target_os = "windows")]
+fn apply_windows_launch(auto: &AutoLaunch, enable: bool) -> Result<()> {
+    use super::win_autostart;
+    use crate::config::AutoLaunchMethod;
+
+    let method = { Config::verge().latest().auto_launch_method };
+    let method = method.unwrap_or_default();
+
+    let app_exe = dunce::canonicalize(current_exe()?)?;
+    let app_name = auto.get_app_name();
+
+    if enable && method == AutoLaunchMethod::Registry {
+        auto.enable()?;
+    } else if auto.is_enabled().unwrap_or(false) {
+        log_err!(auto.disable());
+    }
+
+    // 每次都重新创建快捷方式，避免安装路径变化后失效
+    let folder = enable && method == AutoLaunchMethod::StartupFolder;
+    if folder || win_autostart::is_startup_folder_enabled(app_name) {
+        win_autostart::set_startup_folder(app_name, &app_exe, AUTOSTART_ARG, folder)?;
+    }
+
+    // 计划任务需要 UAC 授权，只在状态变化时修改
+    let task = enable && method == AutoLaunchMethod::ScheduledTask;
+    if task != win_autostart::is_scheduled_task_enabled(app_name) {
+        win_autostart::set_scheduled_task(app_name, &app_exe, AUTOSTART_ARG, task)?;
+    }
+
+    Ok(())
 }
@@ -0,0 +1,423 @@
+#![cfg(target_os = "linux")]
+//! the systemd units of the core or the service-only mode
+//!
+//! the system unit runs the core as the user with the network capabilities,
+//! so the tun works at boot before logging in. the app only reloads the config
+//! through the api while the core unit is running, instead of running its own core.
+//! the core is copied to a stable path when installing, the dir of the app is a temporary
+//! mount for the AppImage, and the core of the system unit is owned by root.
+use super::{core::core_args, CoreManager};
+use crate::{
+    config::{ClashCore, Config, RUNTIME_CONFIG},
+    utils::dirs,
+};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+use zbus::{zvariant::OwnedObjectPath, Connection, Proxy};
+
+const SYSTEMD_NAME: &str = "org.freedesktop.systemd1";
+const SYSTEMD_PATH: &str = "/org/freedesktop/systemd1";
+/// the system unit grants the network capabilities, the user can't replace the core
+const SYSTEM_CORE_DIR: &str = "/usr/local/lib/clash-nyanpasu";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitScope {
+    /// `~/.config/systemd/user`, started after logging in
+    User,
+    /// `/etc/systemd/system`, started at boot, needs the administrator
+    System,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitTarget {
+    /// only the core with the last generated config
+    Core,
+    /// the whole app without the window, see `--service`
+    ServiceOnly,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct UnitOptions {
+    pub scope: UnitScope,
+    pub target: UnitTarget,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UnitStatus {
+    pub name: String,
+    pub path: String,
+    pub installed: bool,
+    /// `enabled` `disabled` etc. of `systemctl is-enabled`
+    pub file_state: Option<String>,
+    /// `active` `inactive` `failed` etc.
+    pub active_state: String,
+    pub sub_state: String,
+    pub main_pid: Option<u32>,
+}
+
+impl UnitOptions {
+    pub fn name(&self) -> &'static str {
+        match self.target {
+            UnitTarget::Core => "clash-nyanpasu-core.service",
+            UnitTarget::ServiceOnly => "clash-nyanpasu.service",
+        }
+    }
+
+    fn path(&self) -> Result<PathBuf> {
+        let dir = match self.scope {
+            UnitScope::User => ::dirs::config_dir()
+                .ok_or(anyhow!("failed to get the config dir"))?
+                .join("systemd/user"),
+            UnitScope::System => PathBuf::from("/etc/systemd/system"),
+        };
+        Ok(dir.join(self.name()))
+    }
+
+    /// the copy of the core run by the unit, replaced when installing again
+    fn core_path(&self) -> Result<PathBuf> {
+        let dir = match self.scope {
+            UnitScope::User => ::dirs::data_local_dir()
+                .ok_or(anyhow!("failed to get the data dir"))?
+                .join("clash-nyanpasu"),
+            UnitScope::System => PathBuf::from(SYSTEM_CORE_DIR),
+        };
+        Ok(dir.join("core"))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        // 主程序离不开图形会话
+        if self.scope == UnitScope::System && self.target == UnitTarget::ServiceOnly {
+            bail!("the service-only mode needs the graphical session, use the user unit");
+        }
+        Ok(())
+    }
+
+    /// run `systemctl` with pkexec for the system units
+    fn systemctl(&self, args: &[&str]) -> Result<()> {
+        let mut command = match self.scope {
+            UnitScope::User => {
+                let mut command = Command::new("systemctl");
+                command.arg("--user");
+                command
+            }
+            UnitScope::System => {
+                let mut command = Command::new("pkexec");
+                command.arg("systemctl");
+                command
+            }
+        };
+        let output = command.args(args).output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("`systemctl {}` failed: {}", args.join(" "), stderr.trim());
+        }
+        Ok(())
+    }
+}
+
+/// `ExecStart` splits the args by spaces and expands the `%` specifiers
+fn quote(arg: &str) -> String {
+    let arg = arg
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{arg}\"")
+}
+
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', r"'\''"))
+}
+
+fn current_core() -> ClashCore {
+    let clash_core = { Config::verge().latest().clash_core.clone() };
+    clash_core.unwrap_or_default()
+}
+
+fn current_user() -> Result<String> {
+    let output = Command::new("id").arg("-un").output()?;
+    let user = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if user.is_empty() {
+        bail!("failed to get the current user");
+    }
+    Ok(user)
+}
+
+/// the content of the unit file
+pub fn generate(options: &UnitOptions) -> Result<String> {
+    options.validate()?;
+
+    // (description, exec, dependencies, wanted_by, service)
+    let (description, exec, deps, wanted_by, service) = match options.target {
+        UnitTarget::Core => {
+            let clash_core = current_core();
+            let core_path = options.core_path()?;
+            let app_dir = dirs::app_home_dir()?;
+            let config_path = app_dir.join(RUNTIME_CONFIG);
+
+            let args = core_args(
+                &clash_core,
+                dirs::path_to_str(&app_dir)?,
+                dirs::path_to_str(&config_path)?,
+            );
+            let exec = std::iter::once(dirs::path_to_str(&core_path)?)
+                .chain(args)
+                .map(quote)
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            match options.scope {
+                UnitScope::User => (
+                    "Clash Nyanpasu Core",
+                    exec,
+                    String::new(),
+                    "default.target",
+                    String::new(),
+                ),
+                // 以当前用户运行，只给网络相关的权限
+                UnitScope::System => (
+                    "Clash Nyanpasu Core",
+                    exec,
+                    "After=network-online.target\nWants=network-online.target\n".to_string(),
+                    "multi-user.target",
+                    format!(
+                        "User={}\n\
+                         AmbientCapabilities=CAP_NET_ADMIN CAP_NET_BIND_SERVICE\n\
+                         CapabilityBoundingSet=CAP_NET_ADMIN CAP_NET_BIND_SERVICE\n",
+                        current_user()?
+                    ),
+                ),
+            }
+        }
+        UnitTarget::ServiceOnly => {
+            let exe = std::env::current_exe()?;
+            let exec = format!(
+                "{} {}",
+                quote(dirs::path_to_str(&exe)?),
+                crate::utils::cli::SERVICE_ONLY_ARG
+            );
+            (
+                "Clash Nyanpasu",
+                exec,
+                "After=graphical-session.target\nPartOf=graphical-session.target\n".to_string(),
+                "graphical-session.target",
+                String::new(),
+            )
+        }
+    };
+
+    Ok(format!(
+        "# Generated by Clash Nyanpasu\n\
+         [Unit]\nDescription={description}\n{deps}\n\
+         [Service]\nType=simple\nExecStart={exec}\nRestart=on-failure\nRestartSec=5\n{service}\n\
+         [Install]\nWantedBy={wanted_by}\n"
+    ))
+}
+
+/// write the unit file and reload systemd, the core config is generated
+/// and the current core is copied before
+pub async fn install(options: &UnitOptions) -> Result<()> {
+    let unit = generate(options)?;
+    // (the core next to the app, the copy run by the unit)
+    let core = match options.target {
+        UnitTarget::Core => {
+            Config::generate_file(crate::config::ConfigType::Run)?;
+            let clash_core = current_core();
+            Some((
+                dirs::core_path(&clash_core.to_string())?,
+                options.core_path()?,
+            ))
+        }
+        UnitTarget::ServiceOnly => None,
+    };
+
+    let path = options.path()?;
+    match options.scope {
+        UnitScope::User => {
+            if let Some((source, target)) = &core {
+                if let Some(dir) = target.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                // 运行中的内核不能直接覆盖
+                let temp = target.with_extension("new");
+                std::fs::copy(source, &temp)?;
+                std::fs::rename(temp, target)?;
+            }
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, unit)?;
+            options.systemctl(&["daemon-reload"])?;
+        }
+        UnitScope::System => {
+            let temp = dirs::app_home_dir()?.join(options.name());
+            std::fs::write(&temp, unit)?;
+            // 内核和 unit 一起安装，只授权一次
+            let mut script = format!(
+                "install -m 644 {} {}",
+                shell_quote(&temp),
+                shell_quote(&path)
+            );
+            if let Some((source, target)) = &core {
+                script = format!(
+                    "install -D -m 755 -o root -g root {} {} && {script}",
+                    shell_quote(source),
+                    shell_quote(target)
+                );
+            }
+            let output = Command::new("pkexec").args(["sh", "-c", &script]).output();
+            let _ = std::fs::remove_file(&temp);
+            let output = output?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                bail!("failed to install the unit: {}", stderr.trim());
+            }
+            options.systemctl(&["daemon-reload"])?;
+        }
+    }
+    tracing::info!("install the systemd unit {}", path.display());
+    Ok(())
+}
+
+pub async fn uninstall(options: &UnitOptions) -> Result<()> {
+    let path = options.path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+    // 停止并删除，system 的只授权一次
+    let mut files = vec![shell_quote(&path)];
+    if options.target == UnitTarget::Core {
+        files.push(shell_quote(&options.core_path()?));
+    }
+    let script = format!(
+        "systemctl {scope} disable --now {name}; rm -f {files} && systemctl {scope} daemon-reload",
+        files = files.join(" "),
+        scope = match options.scope {
+            UnitScope::User => "--user",
+            UnitScope::System => "",
+        },
+        name = options.name(),
+    );
+    let mut command = match options.scope {
+        UnitScope::User => Command::new("sh"),
+        UnitScope::System => {
+            let mut command = Command::new("pkexec");
+            command.arg("sh");
+            command
+        }
+    };
+    let status = command.arg("-c").arg(script).status()?;
+    if !status.success() {
+        bail!("failed to uninstall the unit with status {status}");
+    }
+    tracing::info!("uninstall the systemd unit {}", path.display());
+
+    if options.target == UnitTarget::Core {
+        CoreManager::global().run_core().await?;
+    }
+    Ok(())
+}
+
+/// enable and start, or disable and stop the unit
+pub async fn set_enabled(options: &UnitOptions, enable: bool) -> Result<()> {
+    let action = if enable { "enable" } else { "disable" };
+    if options.target != UnitTarget::Core {
+        return options.systemctl(&[action, "--now", options.name()]);
+    }
+
+    // 先让出端口，之后 app 根据 unit 的状态决定是否运行自己的内核
+    if enable {
+        CoreManager::global().stop_core()?;
+    }
+    let result = options.systemctl(&[action, "--now", options.name()]);
+    CoreManager::global().run_core().await?;
+    result
+}
+
+/// query the unit through dbus
+pub async fn status(options: &UnitOptions) -> Result<UnitStatus> {
+    let path = options.path()?;
+    let conn = match options.scope {
+        UnitScope::User => Connection::session().await?,
+        UnitScope::System => Connection::system().await?,
+    };
+    let manager = Proxy::new(
+        &conn,
+        SYSTEMD_NAME,
+        SYSTEMD_PATH,
+        "org.freedesktop.systemd1.Manager",
+    )
+    .await?;
+
+    // 没有安装时会报错
+    let file_state: Option<String> = manager
+        .call("GetUnitFileState", &(options.name(),))
+        .await
+        .ok();
+    let unit_path: OwnedObjectPath = manager.call("LoadUnit", &(options.name(),)).await?;
+    let unit = Proxy::new(
+        &conn,
+        SYSTEMD_NAME,
+        unit_path.as_str(),
+        "org.freedesktop.systemd1.Unit",
+    )
+    .await?;
+    let service = Proxy::new(
+        &conn,
+        SYSTEMD_NAME,
+        unit_path.as_str(),
+        "org.freedesktop.systemd1.Service",
+    )
+    .await?;
+    let main_pid = service.get_property::<u32>("MainPID").await.unwrap_or(0);
+
+    Ok(UnitStatus {
+        name: options.name().into(),
+        installed: path.exists(),
+        path: path.display().to_string(),
+        file_state,
+        active_state: unit.get_property("ActiveState").await?,
+        sub_state: unit.get_property("SubState").await?,
+        main_pid: (main_pid != 0).then_some(main_pid),
+    })
+}
+
+/// the core is run by a unit of any scope
+pub async fn is_core_active() -> bool {
+    for scope in [UnitScope::System, UnitScope::User] {
+        let options = UnitOptions {
+            scope,
+            target: UnitTarget::Core,
+        };
+        if !options.path().map_or(false, |path| path.exists()) {
+            continue;
+        }
+        if let Ok(status) = status(&options).await {
+            if status.active_state == "active" {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[test]
+fn test_quote() {
+    assert_eq!(
+        quote("/opt/Clash Nyanpasu/mihomo"),
+        "\"/opt/Clash Nyanpasu/mihomo\""
+    );
+    assert_eq!(quote("100%\"x\""), "\"100%%\\\"x\\\"\"");
+
+    let options = UnitOptions {
+        scope: UnitScope::System,
+        target: UnitTarget::ServiceOnly,
+    };
+    assert!(options.validate().is_err());
+    assert_eq!(options.name(), "clash-nyanpasu.service");
+}
@@ -0,0 +1,41 @@
+use super::JobExt;
+use crate::core::{
+    automation::Automation,
+    tasks::{
+        executor::{AsyncJobExecutor, TaskExecutor},
+        task::{Task, TaskSchedule},
+    },
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+
+const AUTOMATION_TASK_NAME: &str = "automation";
+
+/// check the time rules of the automation
+#[derive(Clone, Default)]
+pub struct AutomationJob;
+
+#[async_trait]
+impl AsyncJobExecutor for AutomationJob {
+    async fn execute(&self) -> Result<()> {
+        Automation::global().on_tick().await;
+        Ok(())
+    }
+}
+
+impl JobExt for AutomationJob {
+    fn name(&self) -> &'static str {
+        AUTOMATION_TASK_NAME
+    }
+
+    fn setup(&self) -> Option<Task> {
+        Some(Task {
+            name: AUTOMATION_TASK_NAME.to_string(),
+            // 间隔小于一分钟，避免错过某一分钟
+            schedule: TaskSchedule::Interval(Duration::from_secs(20)),
+            executor: TaskExecutor::Async(Box::new(self.clone())),
+            ..Default::default()
+        })
+    }
+}
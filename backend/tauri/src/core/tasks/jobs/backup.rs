@@ -0,0 +1,42 @@
+use super::JobExt;
+use crate::core::{
+    tasks::{
+        executor::{AsyncJobExecutor, TaskExecutor},
+        task::{Task, TaskSchedule},
+    },
+    webdav,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+
+const WEBDAV_BACKUP_TASK_NAME: &str = "webdav_backup";
+
+/// upload a backup when the last one is older than `webdav_backup_interval`
+#[derive(Clone, Default)]
+pub struct WebDavBackupJob;
+
+#[async_trait]
+impl AsyncJobExecutor for WebDavBackupJob {
+    async fn execute(&self) -> Result<()> {
+        webdav::backup_if_due().await.map_err(|err| {
+            tracing::error!("failed to backup to webdav: {err}");
+            err
+        })
+    }
+}
+
+impl JobExt for WebDavBackupJob {
+    fn name(&self) -> &'static str {
+        WEBDAV_BACKUP_TASK_NAME
+    }
+
+    fn setup(&self) -> Option<Task> {
+        Some(Task {
+            name: WEBDAV_BACKUP_TASK_NAME.to_string(),
+            schedule: TaskSchedule::Interval(Duration::from_secs(10 * 60)),
+            executor: TaskExecutor::Async(Box::new(self.clone())),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,40 @@
+use super::JobExt;
+use crate::core::{
+    maintenance,
+    tasks::{
+        executor::{AsyncJobExecutor, TaskExecutor},
+        task::{Task, TaskSchedule},
+    },
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+
+const MAINTENANCE_TASK_NAME: &str = "maintenance";
+
+/// prune the expired logs, the stale snapshots and the orphaned temp dirs
+#[derive(Clone, Default)]
+pub struct MaintenanceJob;
+
+#[async_trait]
+impl AsyncJobExecutor for MaintenanceJob {
+    async fn execute(&self) -> Result<()> {
+        tokio::task::spawn_blocking(maintenance::run).await??;
+        Ok(())
+    }
+}
+
+impl JobExt for MaintenanceJob {
+    fn name(&self) -> &'static str {
+        MAINTENANCE_TASK_NAME
+    }
+
+    fn setup(&self) -> Option<Task> {
+        Some(Task {
+            name: MAINTENANCE_TASK_NAME.to_string(),
+            schedule: TaskSchedule::Interval(Duration::from_secs(60 * 60)), // 1 小时清理一次
+            executor: TaskExecutor::Async(Box::new(self.clone())),
+            ..Default::default()
+        })
+    }
+}
@@ -1,5 +1,9 @@
-mod logger;
+mod automation;
+mod backup;
+mod maintenance;
+mod pause;
 mod profiles;
+mod sync;
 
 use super::{
     task::Task,
@@ -25,8 +29,13 @@ impl JobsManager {
     }
 
     pub fn global_register() -> Result<()> {
-        let jobs: Vec<Box<dyn JobExt + Send + Sync>> =
-            vec![Box::<logger::ClearLogsJob>::default() as Box<dyn JobExt + Send + Sync>];
+        let jobs: Vec<Box<dyn JobExt + Send + Sync>> = vec![
+            Box::<maintenance::MaintenanceJob>::default() as Box<dyn JobExt + Send + Sync>,
+            Box::<automation::AutomationJob>::default(),
+            Box::<pause::ProxyPauseJob>::default(),
+            Box::<backup::WebDavBackupJob>::default(),
+            Box::<sync::SyncJob>::default(),
+        ];
         for job in jobs {
             let task = job.setup();
             if let Some(task) = task {
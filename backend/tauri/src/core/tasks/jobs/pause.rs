@@ -0,0 +1,40 @@
+use super::JobExt;
+use crate::core::{
+    pause::ProxyPause,
+    tasks::{
+        executor::{AsyncJobExecutor, TaskExecutor},
+        task::{Task, TaskSchedule},
+    },
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+
+const PROXY_PAUSE_TASK_NAME: &str = "proxy_pause";
+
+/// pause and resume the proxy by the `pause_windows`
+#[derive(Clone, Default)]
+pub struct ProxyPauseJob;
+
+#[async_trait]
+impl AsyncJobExecutor for ProxyPauseJob {
+    async fn execute(&self) -> Result<()> {
+        ProxyPause::global().on_tick().await;
+        Ok(())
+    }
+}
+
+impl JobExt for ProxyPauseJob {
+    fn name(&self) -> &'static str {
+        PROXY_PAUSE_TASK_NAME
+    }
+
+    fn setup(&self) -> Option<Task> {
+        Some(Task {
+            name: PROXY_PAUSE_TASK_NAME.to_string(),
+            schedule: TaskSchedule::Interval(Duration::from_secs(20)),
+            executor: TaskExecutor::Async(Box::new(self.clone())),
+            ..Default::default()
+        })
+    }
+}
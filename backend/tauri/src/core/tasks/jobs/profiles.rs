@@ -3,7 +3,7 @@ use super::super::{
     executor::TaskExecutor,
     task::{Task, TaskID, TaskManager, TaskSchedule},
 };
-use crate::{config::Config, feat};
+use crate::{config::Config, core::profile_hooks, feat};
 use anyhow::Result;
 use async_trait::async_trait;
 use parking_lot::Mutex;
@@ -31,14 +31,13 @@ impl ProfileUpdater {
 #[async_trait]
 impl AsyncJobExecutor for ProfileUpdater {
     async fn execute(&self) -> Result<()> {
-        log::info!(target: "app", "running timer task `{}`", self.0);
-        match feat::update_profile(self.0.clone(), None).await {
-            Ok(_) => Ok(()),
-            Err(err) => {
-                log::error!(target: "app", "failed to update profile: {err}");
-                Err(err)
-            }
+        tracing::info!("running timer task `{}`", self.0);
+        if let Err(err) = feat::update_profile(self.0.clone(), None).await {
+            tracing::error!("failed to update profile: {err}");
+            return Err(err);
         }
+        // 钩子失败也算任务失败，记录在任务历史里
+        profile_hooks::run(&self.0).await
     }
 }
 
@@ -68,7 +67,12 @@ impl ProfilesJobGuard {
     /// restore timer
     pub fn init(&mut self) -> Result<()> {
         self.refresh();
+        self.catch_up();
+        Ok(())
+    }
 
+    /// run the overdue updates now, e.g. after starting or waking up
+    pub fn catch_up(&self) {
         let cur_timestamp = chrono::Local::now().timestamp();
 
         let task_map = &self.task_map;
@@ -95,8 +99,6 @@ impl ProfilesJobGuard {
                     }
                 })
         }
-
-        Ok(())
     }
 
     /// Correctly update all cron tasks
@@ -0,0 +1,42 @@
+use super::JobExt;
+use crate::core::{
+    sync::SyncManager,
+    tasks::{
+        executor::{AsyncJobExecutor, TaskExecutor},
+        task::{Task, TaskSchedule},
+    },
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+
+const SYNC_TASK_NAME: &str = "sync";
+
+/// sync the profiles when the last sync is older than `sync_interval`
+#[derive(Clone, Default)]
+pub struct SyncJob;
+
+#[async_trait]
+impl AsyncJobExecutor for SyncJob {
+    async fn execute(&self) -> Result<()> {
+        SyncManager::global().sync_if_due().await.map_err(|err| {
+            tracing::error!("failed to sync the profiles: {err}");
+            err
+        })
+    }
+}
+
+impl JobExt for SyncJob {
+    fn name(&self) -> &'static str {
+        SYNC_TASK_NAME
+    }
+
+    fn setup(&self) -> Option<Task> {
+        Some(Task {
+            name: SYNC_TASK_NAME.to_string(),
+            schedule: TaskSchedule::Interval(Duration::from_secs(60)),
+            executor: TaskExecutor::Async(Box::new(self.clone())),
+            ..Default::default()
+        })
+    }
+}
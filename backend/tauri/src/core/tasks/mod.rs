@@ -1,7 +1,7 @@
-mod events;
+pub mod events;
 pub mod executor;
 pub mod jobs;
-mod storage;
+pub mod storage;
 pub mod task;
 mod utils;
 
@@ -4,9 +4,9 @@ use super::utils::Result;
 use super::{events::TaskEvent, task::TaskID};
 use crate::core::storage::Storage;
 use crate::core::tasks::task::Task;
-use log::debug;
 use std::str;
 use std::sync::{Arc, OnceLock};
+use tracing::debug;
 
 pub struct EventsGuard;
 
@@ -94,6 +94,13 @@ impl Default for Task {
     }
 }
 
+impl Task {
+    /// the time and the result of the last run
+    pub fn last_run(&self) -> Option<&(Timestamp, TaskRunResult)> {
+        self.last_run.as_ref()
+    }
+}
+
 pub type Timestamp = i64;
 
 // 参数校验失败
@@ -0,0 +1,371 @@
+//! the throttled inbound, limits the bandwidth by the throttle rules
+//!
+//! none of the cores can limit the bandwidth, so the app listens on another port and relays
+//! the connections to the mixed port, the system proxy points to this port while enabled.
+//! the host is read from the http request or the socks5 handshake, the other bytes are
+//! relayed as they are, so the inbound auth still works. the tun traffic is not limited.
+
+use crate::{
+    config::{Config, ThrottleRule, ThrottleScope},
+    utils::server::LocalServer,
+};
+use anyhow::{bail, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::sleep,
+};
+
+pub const DEFAULT_PORT: u16 = 33335;
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// a token bucket, bursts up to one second of the rate
+struct Limiter {
+    /// bytes per second
+    rate: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl Limiter {
+    fn new(kbps: u32) -> Self {
+        let rate = kbps as f64 * 1024.;
+        Self {
+            rate,
+            state: Mutex::new((rate, Instant::now())),
+        }
+    }
+
+    /// wait until the bytes are allowed, the tokens could be owed
+    async fn consume(&self, bytes: usize) {
+        let wait = {
+            let mut state = self.state.lock();
+            let (tokens, last) = &mut *state;
+            let now = Instant::now();
+            *tokens =
+                (*tokens + now.duration_since(*last).as_secs_f64() * self.rate).min(self.rate);
+            *last = now;
+            *tokens -= bytes as f64;
+            match *tokens < 0. {
+                true => Duration::from_secs_f64(-*tokens / self.rate),
+                false => Duration::ZERO,
+            }
+        };
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Limits {
+    download: Option<Arc<Limiter>>,
+    upload: Option<Arc<Limiter>>,
+}
+
+pub struct Throttle {
+    server: LocalServer,
+    /// the listening port
+    port: Mutex<Option<u16>>,
+    /// the limiters shared by the connections to the same host
+    shared: Mutex<HashMap<String, Arc<Limiter>>>,
+}
+
+impl Throttle {
+    pub fn global() -> &'static Throttle {
+        static THROTTLE: OnceCell<Throttle> = OnceCell::new();
+
+        THROTTLE.get_or_init(|| Throttle {
+            server: LocalServer::default(),
+            port: Mutex::new(None),
+            shared: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// the listening port, the system proxy uses it instead of the mixed port
+    pub fn port(&self) -> Option<u16> {
+        *self.port.lock()
+    }
+
+    /// apply the latest config, stop the inbound if disabled
+    pub async fn restart(&'static self) -> Result<()> {
+        self.stop().await;
+
+        let (enable, port) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                verge.enable_throttle.unwrap_or(false),
+                verge.throttle_port.unwrap_or(DEFAULT_PORT),
+            )
+        };
+        if !enable {
+            return Ok(());
+        }
+
+        // 同步绑定，端口被占用时直接返回错误
+        let addr = self
+            .server
+            .start(|mut rx| -> Result<_> {
+                let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+                listener.set_nonblocking(true)?;
+                let addr = listener.local_addr()?;
+                let listener = TcpListener::from_std(listener)?;
+                // 停止时 listener 随任务一起释放，已有的连接继续转发
+                let server = async move {
+                    loop {
+                        tokio::select! {
+                            _ = &mut rx => break,
+                            accepted = listener.accept() => match accepted {
+                                Ok((stream, _)) => {
+                                    tauri::async_runtime::spawn(async move {
+                                        if let Err(err) = self.handle(stream).await {
+                                            tracing::debug!("throttled connection failed: {err}");
+                                        }
+                                    });
+                                }
+                                Err(err) => tracing::warn!("failed to accept the connection: {err}"),
+                            },
+                        }
+                    }
+                };
+                Ok((addr, server))
+            })
+            .await?;
+        *self.port.lock() = Some(port);
+
+        tracing::info!("the throttled inbound is listening on {addr}");
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        self.port.lock().take();
+        if self.server.stop().await {
+            self.shared.lock().clear();
+            tracing::info!("the throttled inbound is stopped");
+        }
+    }
+
+    async fn handle(&self, mut client: TcpStream) -> Result<()> {
+        let port = {
+            Config::verge()
+                .latest()
+                .verge_mixed_port
+                .unwrap_or(Config::clash().latest().get_mixed_port())
+        };
+        let mut upstream = TcpStream::connect(("127.0.0.1", port)).await?;
+        let _ = client.set_nodelay(true);
+        let _ = upstream.set_nodelay(true);
+
+        let mut buf = vec![0u8; BUFFER_SIZE];
+        let n = client.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        let host = match buf[0] {
+            0x05 => socks5_handshake(&mut client, &mut upstream, &buf[..n]).await?,
+            _ => {
+                upstream.write_all(&buf[..n]).await?;
+                http_host(&buf[..n])
+            }
+        };
+
+        let limits = host
+            .map(|host| self.limits(&host.to_lowercase()))
+            .unwrap_or_default();
+        if limits.download.is_none() && limits.upload.is_none() {
+            tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+            return Ok(());
+        }
+
+        let (client_read, client_write) = client.into_split();
+        let (upstream_read, upstream_write) = upstream.into_split();
+        let _ = tokio::join!(
+            copy(client_read, upstream_write, limits.upload),
+            copy(upstream_read, client_write, limits.download),
+        );
+        Ok(())
+    }
+
+    /// the limiters of the first matching rule
+    fn limits(&self, host: &str) -> Limits {
+        let rule = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            verge.throttle_rules.as_ref().and_then(|rules| {
+                rules
+                    .iter()
+                    .find(|rule| rule.enable && rule.matches(host))
+                    .cloned()
+            })
+        };
+        let Some(rule) = rule else {
+            return Limits::default();
+        };
+
+        Limits {
+            download: self.limiter(&rule, host, "download", rule.download),
+            upload: self.limiter(&rule, host, "upload", rule.upload),
+        }
+    }
+
+    fn limiter(
+        &self,
+        rule: &ThrottleRule,
+        host: &str,
+        direction: &str,
+        kbps: u32,
+    ) -> Option<Arc<Limiter>> {
+        if kbps == 0 {
+            return None;
+        }
+        if rule.scope == ThrottleScope::Connection {
+            return Some(Arc::new(Limiter::new(kbps)));
+        }
+
+        // 改了速率就换一个新的
+        let key = format!("{}:{host}:{direction}:{kbps}", rule.id);
+        let mut shared = self.shared.lock();
+        shared.retain(|_, limiter| Arc::strong_count(limiter) > 1);
+        let limiter = shared
+            .entry(key)
+            .or_insert_with(|| Arc::new(Limiter::new(kbps)));
+        Some(limiter.clone())
+    }
+}
+
+async fn copy<R, W>(mut reader: R, mut writer: W, limiter: Option<Arc<Limiter>>) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; BUFFER_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        if let Some(limiter) = &limiter {
+            limiter.consume(n).await;
+        }
+        writer.write_all(&buf[..n]).await?;
+    }
+    writer.shutdown().await?;
+    Ok(())
+}
+
+/// relay the greeting, the auth and the request, return the host of the request
+async fn socks5_handshake(
+    client: &mut TcpStream,
+    upstream: &mut TcpStream,
+    greeting: &[u8],
+) -> Result<Option<String>> {
+    upstream.write_all(greeting).await?;
+    let mut reply = [0u8; 2];
+    upstream.read_exact(&mut reply).await?;
+    client.write_all(&reply).await?;
+
+    let mut buf = vec![0u8; 512];
+    // 用户名密码认证
+    if reply[1] == 0x02 {
+        let n = client.read(&mut buf).await?;
+        upstream.write_all(&buf[..n]).await?;
+        upstream.read_exact(&mut reply).await?;
+        client.write_all(&reply).await?;
+        if reply[1] != 0x00 {
+            bail!("the socks5 auth is rejected");
+        }
+    } else if reply[1] == 0xff {
+        bail!("no acceptable socks5 auth method");
+    }
+
+    let n = client.read(&mut buf).await?;
+    upstream.write_all(&buf[..n]).await?;
+    Ok(socks5_host(&buf[..n]))
+}
+
+/// `VER CMD RSV ATYP DST.ADDR DST.PORT`
+fn socks5_host(request: &[u8]) -> Option<String> {
+    let addr = request.get(4..)?;
+    match request.get(3)? {
+        0x01 => {
+            let ip: [u8; 4] = addr.get(..4)?.try_into().ok()?;
+            Some(std::net::Ipv4Addr::from(ip).to_string())
+        }
+        0x03 => {
+            let len = *addr.first()? as usize;
+            let domain = addr.get(1..1 + len)?;
+            Some(String::from_utf8_lossy(domain).into())
+        }
+        0x04 => {
+            let ip: [u8; 16] = addr.get(..16)?.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(ip).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// the host of `CONNECT host:port`, `GET http://host/path`, or the `Host` header
+fn http_host(request: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(request);
+    let mut lines = text.lines();
+    let mut parts = lines.next()?.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+
+    let authority = match method {
+        "CONNECT" => Some(target),
+        _ => target
+            .split_once("://")
+            .and_then(|(_, rest)| rest.split('/').next()),
+    };
+    let authority = authority.map(str::to_string).or_else(|| {
+        lines.take_while(|line| !line.is_empty()).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case("host")
+                .then(|| value.trim().to_string())
+        })
+    })?;
+
+    // 去掉端口和用户信息，ipv6 在方括号里
+    let authority = authority.rsplit('@').next()?;
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next()?,
+        None => authority.split(':').next()?,
+    };
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+#[test]
+fn test_request_host() {
+    assert_eq!(
+        http_host(b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n"),
+        Some("example.com".into())
+    );
+    assert_eq!(
+        http_host(b"GET http://[::1]:8080/index.html HTTP/1.1\r\n\r\n"),
+        Some("::1".into())
+    );
+    assert_eq!(
+        http_host(b"GET / HTTP/1.1\r\nHost: dl.example.com\r\n\r\n"),
+        Some("dl.example.com".into())
+    );
+
+    let request = [
+        &[0x05, 0x01, 0x00, 0x03, 0x0b][..],
+        b"example.com",
+        &[0x01, 0xbb],
+    ]
+    .concat();
+    assert_eq!(socks5_host(&request), Some("example.com".into()));
+    assert_eq!(
+        socks5_host(&[0x05, 0x01, 0x00, 0x01, 10, 0, 0, 1, 0x00, 0x50]),
+        Some("10.0.0.1".into())
+    );
+}
@@ -0,0 +1,115 @@
+use super::{
+    storage::Storage,
+    stream::{StreamHandler, StreamManager},
+    tray::Traffic,
+};
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+const STORAGE_KEY: &str = "traffic_history";
+
+/// one bucket a minute, keep the latest 7 days
+const HISTORY_LEN: usize = 7 * 24 * 60;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TrafficBucket {
+    /// the unix timestamp of the start of the minute
+    pub time: i64,
+    /// bytes in the minute
+    pub up: u64,
+    pub down: u64,
+}
+
+/// record the `/traffic` frames by minute for the exports
+/// the finished buckets are saved to the storage
+pub struct TrafficHistory {
+    history: Mutex<VecDeque<TrafficBucket>>,
+    /// the rates of the latest frame
+    latest: Mutex<Option<Traffic>>,
+}
+
+impl TrafficHistory {
+    pub fn global() -> &'static TrafficHistory {
+        static TRAFFIC_HISTORY: OnceCell<TrafficHistory> = OnceCell::new();
+
+        TRAFFIC_HISTORY.get_or_init(|| {
+            let history = Storage::global()
+                .get_item::<VecDeque<TrafficBucket>>(STORAGE_KEY)
+                .unwrap_or_else(|err| {
+                    tracing::error!("failed to load the traffic history: {err}");
+                    None
+                })
+                .unwrap_or_default();
+            TrafficHistory {
+                history: Mutex::new(history),
+                latest: Mutex::new(None),
+            }
+        })
+    }
+
+    pub fn start(&'static self) {
+        StreamManager::global().spawn(self);
+    }
+
+    /// the buckets in `[from, to)`, unix timestamps
+    pub fn range(&self, from: i64, to: i64) -> Vec<TrafficBucket> {
+        self.history
+            .lock()
+            .iter()
+            .filter(|bucket| bucket.time >= from && bucket.time < to)
+            .cloned()
+            .collect()
+    }
+
+    pub fn latest(&self) -> Option<Traffic> {
+        *self.latest.lock()
+    }
+
+    fn push(&self, traffic: Traffic, now: i64) {
+        *self.latest.lock() = Some(traffic);
+        let minute = now - now.rem_euclid(60);
+
+        let mut history = self.history.lock();
+        match history.back_mut() {
+            Some(bucket) if bucket.time == minute => {
+                bucket.up += traffic.up;
+                bucket.down += traffic.down;
+                return;
+            }
+            _ => {}
+        }
+        history.push_back(TrafficBucket {
+            time: minute,
+            up: traffic.up,
+            down: traffic.down,
+        });
+        while history.len() > HISTORY_LEN {
+            history.pop_front();
+        }
+
+        // 新的一分钟开始时保存，最多丢失一分钟的数据
+        if let Err(err) = Storage::global().set_item(STORAGE_KEY, &*history) {
+            tracing::error!("failed to save the traffic history: {err}");
+        }
+    }
+}
+
+impl StreamHandler for TrafficHistory {
+    fn path(&self) -> &'static str {
+        "traffic"
+    }
+
+    fn name(&self) -> &'static str {
+        "history::traffic"
+    }
+
+    fn on_message(&self, text: String) {
+        match serde_json::from_str::<Traffic>(&text) {
+            Ok(traffic) => self.push(traffic, Local::now().timestamp()),
+            Err(err) => tracing::trace!("invalid traffic frame: {err}"),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! snapshot the profiles before the destructive operations so that they can be undone
+//! each snapshot is a dir in `trash`, with the profiles and the files of the affected items
+//! undoing only restores what the operation changed, the later changes are kept
+use super::handle;
+use crate::{
+    config::{Config, IProfiles, PrfItem, ProfileUpdateRecord, ProfilesStore, ScriptReview},
+    feat, log_err,
+    utils::{dirs, help, secrets},
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs};
+
+const ENTRY_FILE: &str = "entry.json";
+const PROFILES_FILE: &str = "profiles.yaml";
+/// the script review and the update history of the affected items
+const STATES_FILE: &str = "states.json";
+
+/// keep the latest snapshots within the days
+const KEEP: usize = 20;
+const MAX_AGE: i64 = 7 * 24 * 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileOperation {
+    Delete,
+    Reorder,
+    Chain,
+    /// overwritten by the restored backup, the synced files or a replacing import
+    Import,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub operation: ProfileOperation,
+    /// unix timestamp
+    pub time: i64,
+    /// the affected items
+    pub uids: Vec<String>,
+    /// the copied files in the profiles dir
+    pub files: Vec<String>,
+}
+
+/// the storage of an item removed with it by `ProfilesStore::save`
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ItemState {
+    review: ScriptReview,
+    /// the latest first
+    history: Vec<ProfileUpdateRecord>,
+}
+
+/// save the current profiles and the files of the items before changing them
+pub fn snapshot(operation: ProfileOperation, uids: &[String]) -> Result<TrashEntry> {
+    let now = chrono::Local::now();
+    let id = format!(
+        "{}-{}",
+        now.format("%Y%m%d%H%M%S%3f"),
+        serde_json::to_value(operation)?
+            .as_str()
+            .unwrap_or_default()
+    );
+    let dir = dirs::app_trash_dir()?.join(&id);
+    fs::create_dir_all(&dir)?;
+
+    let mut profiles = { Config::profiles().latest().clone() };
+    let profiles_dir = dirs::app_profiles_dir()?;
+    let mut files = vec![];
+    let mut states = HashMap::new();
+    for item in profiles.items.iter_mut().flatten() {
+        let Some(uid) = item.uid.clone() else {
+            continue;
+        };
+        // 链接不明文落盘，删除的订阅在清理快照时才删掉 secret
+        if let Some(url) = item.url.as_ref() {
+            item.url = Some(secrets::protect(&IProfiles::url_secret_key(&uid), url));
+        }
+        if !uids.contains(&uid) {
+            continue;
+        }
+        let state = ItemState {
+            review: ProfilesStore::script_review(&uid)?,
+            history: ProfilesStore::history(&uid)?,
+        };
+        states.insert(uid.clone(), state);
+        if let Some(file) = item.file.as_ref() {
+            let path = profiles_dir.join(file);
+            if path.exists() {
+                fs::copy(&path, dir.join(file))?;
+                files.push(file.clone());
+            }
+        }
+    }
+    help::save_yaml(&dir.join(PROFILES_FILE), &profiles, None)?;
+    fs::write(dir.join(STATES_FILE), serde_json::to_vec(&states)?)?;
+
+    let entry = TrashEntry {
+        id,
+        operation,
+        time: now.timestamp(),
+        uids: uids.to_vec(),
+        files,
+    };
+    fs::write(dir.join(ENTRY_FILE), serde_json::to_vec_pretty(&entry)?)?;
+    tracing::debug!("snapshot the profiles before {operation:?}: {}", entry.id);
+
+    log_err!(cleanup());
+    Ok(entry)
+}
+
+/// save the files of all items, e.g. before they are overwritten
+pub fn snapshot_all(operation: ProfileOperation) -> Result<TrashEntry> {
+    let uids = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        profiles
+            .items
+            .iter()
+            .flatten()
+            .filter_map(|item| item.uid.clone())
+            .collect::<Vec<_>>()
+    };
+    snapshot(operation, &uids)
+}
+
+/// the latest first
+pub fn list() -> Result<Vec<TrashEntry>> {
+    let trash_dir = dirs::app_trash_dir()?;
+    if !trash_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut entries = fs::read_dir(trash_dir)?
+        .flatten()
+        .filter_map(|dir| {
+            let data = fs::read(dir.path().join(ENTRY_FILE)).ok()?;
+            serde_json::from_slice::<TrashEntry>(&data).ok()
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(entries)
+}
+
+/// restore what the latest operation changed and remove it from the trash
+pub async fn undo_last() -> Result<TrashEntry> {
+    let entry = list()?
+        .into_iter()
+        .next()
+        .ok_or(anyhow!("there is no profile operation to undo"))?;
+    let dir = dirs::app_trash_dir()?.join(&entry.id);
+
+    let profiles_dir = dirs::app_profiles_dir()?;
+    fs::create_dir_all(&profiles_dir)?;
+    for file in entry.files.iter() {
+        fs::copy(dir.join(file), profiles_dir.join(file))?;
+    }
+
+    let snapshot = IProfiles::from_path(&dir.join(PROFILES_FILE))?;
+    {
+        let profiles = Config::profiles();
+        let mut profiles = profiles.draft();
+        match entry.operation {
+            ProfileOperation::Delete | ProfileOperation::Import => {
+                restore_items(&mut profiles, &snapshot, &entry.uids)
+            }
+            ProfileOperation::Reorder => restore_order(&mut profiles, &snapshot),
+            ProfileOperation::Chain => profiles.chain = snapshot.chain.clone(),
+        }
+    }
+    Config::profiles().apply();
+    Config::profiles().data().save_file()?;
+
+    // 保存后再写回，删除的条目在保存时会清掉这些记录
+    let states = fs::read(dir.join(STATES_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_slice::<HashMap<String, ItemState>>(&data).ok())
+        .unwrap_or_default();
+    for (uid, state) in states {
+        restore_state(&uid, state)?;
+    }
+    fs::remove_dir_all(&dir)?;
+    tracing::info!("undo the profile operation {:?}", entry.operation);
+
+    feat::update_core_config().await?;
+    handle::Handle::refresh_profiles();
+    Ok(entry)
+}
+
+/// put back the items of the snapshot, at the same positions if they are gone
+fn restore_items(profiles: &mut IProfiles, snapshot: &IProfiles, uids: &[String]) {
+    let saved = snapshot.items.as_deref().unwrap_or_default();
+    let items = profiles.items.get_or_insert_with(Vec::new);
+    for (index, item) in saved.iter().enumerate() {
+        if !item.uid.as_ref().map_or(false, |uid| uids.contains(uid)) {
+            continue;
+        }
+        match items.iter_mut().find(|current| current.uid == item.uid) {
+            Some(current) => *current = item.clone(),
+            None => items.insert(index.min(items.len()), item.clone()),
+        }
+    }
+    if profiles.current.is_none() {
+        profiles.current = snapshot.current.clone();
+    }
+}
+
+/// the items in the order of the snapshot, the ones added later stay at the end
+fn restore_order(profiles: &mut IProfiles, snapshot: &IProfiles) {
+    let order = snapshot
+        .items
+        .iter()
+        .flatten()
+        .filter_map(|item| item.uid.clone())
+        .collect::<Vec<_>>();
+    if let Some(items) = profiles.items.as_mut() {
+        items.sort_by_key(|item: &PrfItem| {
+            item.uid
+                .as_ref()
+                .and_then(|uid| order.iter().position(|saved| saved == uid))
+                .unwrap_or(usize::MAX)
+        });
+    }
+}
+
+fn restore_state(uid: &str, state: ItemState) -> Result<()> {
+    // 待审核的脚本文件删除时已经清掉了，重新下载时会再进入审核
+    let review = ScriptReview {
+        pending: None,
+        ..state.review
+    };
+    ProfilesStore::set_script_review(uid, &review)?;
+    let existing = ProfilesStore::history(uid)?;
+    for record in state.history.iter().rev() {
+        if !existing.iter().any(|other| other.time == record.time) {
+            ProfilesStore::record_update(uid, record)?;
+        }
+    }
+    Ok(())
+}
+
+fn stale_entries(entries: &[TrashEntry], now: i64) -> Vec<&TrashEntry> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(index, entry)| *index >= KEEP || now - entry.time > MAX_AGE)
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+/// remove the stale snapshots and the secrets of the items gone for good
+pub fn cleanup() -> Result<()> {
+    let entries = list()?;
+    let stale = stale_entries(&entries, chrono::Local::now().timestamp());
+    if stale.is_empty() {
+        return Ok(());
+    }
+
+    let trash_dir = dirs::app_trash_dir()?;
+    let profiles = { Config::profiles().latest().clone() };
+    // 还在其他快照里的不能删
+    let referenced = |uid: &String| {
+        profiles.get_item(uid).is_ok()
+            || entries
+                .iter()
+                .filter(|other| !stale.iter().any(|entry| entry.id == other.id))
+                .any(|other| other.uids.contains(uid))
+    };
+    for entry in stale.iter() {
+        fs::remove_dir_all(trash_dir.join(&entry.id))?;
+        for uid in entry.uids.iter().filter(|uid| !referenced(uid)) {
+            log_err!(secrets::delete(&IProfiles::url_secret_key(uid)));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_stale_entries() {
+    let now = 10 * 24 * 3600;
+    let entries = (0..KEEP + 2)
+        .map(|index| TrashEntry {
+            id: format!("{index:03}"),
+            operation: ProfileOperation::Delete,
+            time: match index {
+                1 => now - MAX_AGE - 1,
+                _ => now - index as i64,
+            },
+            uids: vec![],
+            files: vec![],
+        })
+        .collect::<Vec<_>>();
+
+    let stale = stale_entries(&entries, now)
+        .into_iter()
+        .map(|entry| entry.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(stale, vec!["001", "020", "021"]);
+}
+
+#[test]
+fn test_restore_items() {
+    let profiles = |uids: &[&str]| {
+        let yaml = uids
+            .iter()
+            .map(|uid| format!("  - uid: {uid}\n    name: {uid}\n"))
+            .collect::<String>();
+        serde_yaml::from_str::<IProfiles>(&format!("current: a\nitems:\n{yaml}")).unwrap()
+    };
+    let uids = |profiles: &IProfiles| {
+        profiles
+            .items
+            .iter()
+            .flatten()
+            .filter_map(|item| item.uid.clone())
+            .collect::<Vec<_>>()
+    };
+
+    // b 被删除后又新建了 d
+    let snapshot = profiles(&["a", "b", "c"]);
+    let mut current = profiles(&["a", "c", "d"]);
+    restore_items(&mut current, &snapshot, &["b".into()]);
+    assert_eq!(uids(&current), vec!["a", "b", "c", "d"]);
+
+    let mut current = profiles(&["c", "d", "a"]);
+    restore_order(&mut current, &snapshot);
+    assert_eq!(uids(&current), vec!["a", "c", "d"]);
+}
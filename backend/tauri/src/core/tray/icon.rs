@@ -0,0 +1,119 @@
+use anyhow::Result;
+use image::{ImageFormat, Rgba, RgbaImage};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use tauri::{Icon, SystemTrayHandle};
+
+const BASE_ICON: &[u8] = include_bytes!("../../../icons/win-tray-icon.png");
+
+const PINK: Rgba<u8> = Rgba([0xf4, 0x8f, 0xb1, 0xff]);
+const BLUE: Rgba<u8> = Rgba([0x42, 0xa5, 0xf5, 0xff]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrayIconState {
+    Normal,
+    SystemProxy,
+    Tun,
+}
+
+impl TrayIconState {
+    /// tun mode takes precedence over the system proxy
+    pub fn from(system_proxy: bool, tun_mode: bool) -> Self {
+        match (system_proxy, tun_mode) {
+            (_, true) => Self::Tun,
+            (true, false) => Self::SystemProxy,
+            _ => Self::Normal,
+        }
+    }
+
+    fn badge(&self) -> Option<Rgba<u8>> {
+        match self {
+            Self::Normal => None,
+            Self::SystemProxy => Some(PINK),
+            Self::Tun => Some(BLUE),
+        }
+    }
+}
+
+/// the base icon with a colored badge at the bottom right corner
+/// the icons are generated once and cached
+pub fn icon(state: TrayIconState) -> Result<Icon> {
+    static CACHE: OnceCell<Mutex<HashMap<TrayIconState, RgbaImage>>> = OnceCell::new();
+
+    let cache = CACHE.get_or_init(Default::default);
+    let mut cache = cache.lock();
+
+    let image = match cache.get(&state) {
+        Some(image) => image.clone(),
+        None => {
+            let image = render(state)?;
+            cache.insert(state, image.clone());
+            image
+        }
+    };
+
+    let (width, height) = image.dimensions();
+    Ok(Icon::Rgba {
+        rgba: image.into_raw(),
+        width,
+        height,
+    })
+}
+
+/// only set the icon when the state changes
+pub fn update_icon(tray: &SystemTrayHandle, state: TrayIconState) -> Result<()> {
+    static CURRENT: Mutex<Option<TrayIconState>> = Mutex::new(None);
+
+    let mut current = CURRENT.lock();
+    if *current == Some(state) {
+        return Ok(());
+    }
+
+    tray.set_icon(icon(state)?)?;
+    // the template icons are drawn in a single color, the badge would be invisible
+    #[cfg(target_os = "macos")]
+    tray.set_icon_as_template(state.badge().is_none())?;
+
+    *current = Some(state);
+    Ok(())
+}
+
+fn render(state: TrayIconState) -> Result<RgbaImage> {
+    let mut image = image::load_from_memory_with_format(BASE_ICON, ImageFormat::Png)?.into_rgba8();
+
+    if let Some(color) = state.badge() {
+        draw_badge(&mut image, color);
+    }
+    Ok(image)
+}
+
+/// a filled circle with a transparent ring to separate from the icon
+fn draw_badge(image: &mut RgbaImage, color: Rgba<u8>) {
+    let (width, height) = image.dimensions();
+    let radius = width.min(height) as f32 / 5.0;
+    let ring = radius * 1.3;
+    let (cx, cy) = (width as f32 - ring, height as f32 - ring);
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        let distance = (dx * dx + dy * dy).sqrt();
+
+        if distance <= radius {
+            *pixel = color;
+        } else if distance <= ring {
+            *pixel = Rgba([0, 0, 0, 0]);
+        }
+    }
+}
+
+#[test]
+fn test_draw_badge() {
+    let mut image = RgbaImage::from_pixel(32, 32, Rgba([0, 0, 0, 0xff]));
+    draw_badge(&mut image, PINK);
+
+    assert_eq!(*image.get_pixel(0, 0), Rgba([0, 0, 0, 0xff]));
+    // the center of the badge
+    let center = (32.0 - 32.0 / 5.0 * 1.3) as u32;
+    assert_eq!(*image.get_pixel(center, center), PINK);
+}
@@ -0,0 +1,122 @@
+use crate::{
+    config::{Config, TrayMenuAction, TrayMenuEntry},
+    core::{
+        deep_link, hotkey,
+        notification::{NotificationAction, NotificationKind, Notifications},
+        storage::Storage,
+    },
+    utils::i18n::{t, t_args},
+};
+use anyhow::{anyhow, bail, Result};
+use std::{collections::HashSet, time::Duration};
+use tauri::CustomMenuItem;
+use tokio::{process::Command, time::timeout};
+
+/// the menu id of the items, `custom_menu::{index}`
+const MENU_PREFIX: &str = "custom_menu::";
+
+/// the hashes of the commands saved in the settings
+const ALLOWED_KEY: &str = "tray_menu_commands";
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// the user defined items in the tray menu, running the functions or the commands
+pub struct TrayMenu;
+
+impl TrayMenu {
+    fn entries() -> Vec<TrayMenuEntry> {
+        let entries = { Config::verge().latest().tray_menu.clone() };
+        entries.unwrap_or_default()
+    }
+
+    pub fn items() -> Vec<CustomMenuItem> {
+        Self::entries()
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| CustomMenuItem::new(format!("{MENU_PREFIX}{index}"), entry.label))
+            .collect()
+    }
+
+    /// only the commands saved from the settings are allowed to run
+    /// the ones came with the restored or synced config need to be saved again
+    pub fn allow(entries: &[TrayMenuEntry]) -> Result<()> {
+        let allowed = entries
+            .iter()
+            .filter_map(|entry| entry.command_hash())
+            .collect::<Vec<_>>();
+        Storage::global().set_item(ALLOWED_KEY, &allowed)
+    }
+
+    fn is_allowed(entry: &TrayMenuEntry) -> bool {
+        let Some(hash) = entry.command_hash() else {
+            return true;
+        };
+        Storage::global()
+            .get_item::<HashSet<String>>(ALLOWED_KEY)
+            .ok()
+            .flatten()
+            .map_or(false, |allowed| allowed.contains(&hash))
+    }
+
+    /// return false if the id is not a custom item
+    pub fn on_click(id: &str) -> bool {
+        let Some(index) = id.strip_prefix(MENU_PREFIX) else {
+            return false;
+        };
+        let Some(entry) = index
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| Self::entries().into_iter().nth(index))
+        else {
+            return true;
+        };
+
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = Self::run(&entry).await {
+                tracing::error!("failed to run the tray menu item `{}`: {err}", entry.label);
+                Notifications::global().push(
+                    NotificationKind::CommandFailed,
+                    entry.label.clone(),
+                    err.to_string(),
+                    Some(NotificationAction::OpenPage("/logs".into())),
+                );
+            }
+        });
+        true
+    }
+
+    async fn run(entry: &TrayMenuEntry) -> Result<()> {
+        if entry.confirm {
+            let message = t_args("Run \"{label}\"?", &[("label", &entry.label)]);
+            if !deep_link::confirm("Clash Nyanpasu", message).await {
+                return Ok(());
+            }
+        }
+
+        match &entry.action {
+            TrayMenuAction::Function { func } => hotkey::function(func)?(),
+            TrayMenuAction::Command { program, args } => {
+                if !Self::is_allowed(entry) {
+                    bail!(t(
+                        "The command is not allowed, please save it in the settings again"
+                    ));
+                }
+
+                let mut command = Command::new(program);
+                command.args(args).kill_on_drop(true);
+                #[cfg(target_os = "windows")]
+                command.creation_flags(0x08000000);
+
+                let output = timeout(COMMAND_TIMEOUT, command.output())
+                    .await
+                    .map_err(|_| anyhow!(t("The command timed out")))??;
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    bail!("exited with {}: {}", output.status, stderr.trim());
+                }
+                tracing::info!("the tray menu item `{}` finished", entry.label);
+            }
+        }
+        Ok(())
+    }
+}
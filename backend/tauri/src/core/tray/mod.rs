@@ -0,0 +1,228 @@
+use crate::{
+    cmds,
+    config::Config,
+    feat,
+    utils::{i18n, resolve},
+};
+use anyhow::Result;
+use tauri::{
+    api, AppHandle, CustomMenuItem, Manager, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem,
+    SystemTraySubmenu,
+};
+
+use super::{pause::ProxyPause, proxy_env::ShellType, storage};
+
+mod icon;
+mod menu;
+mod proxies;
+mod traffic;
+pub use self::icon::*;
+pub use self::menu::*;
+pub use self::proxies::*;
+pub use self::traffic::*;
+
+pub struct Tray {}
+
+impl Tray {
+    pub fn tray_menu(app_handle: &AppHandle) -> SystemTrayMenu {
+        let lang = i18n::language();
+        let t = |key: &str| i18n::translate(&lang, key, &[]);
+
+        let version = env!("NYANPASU_VERSION");
+
+        let menu = SystemTrayMenu::new()
+            .add_item(CustomMenuItem::new("open_window", t("Dashboard")))
+            .add_native_item(SystemTrayMenuItem::Separator)
+            .add_item(CustomMenuItem::new("rule_mode", t("Rule Mode")))
+            .add_item(CustomMenuItem::new("global_mode", t("Global Mode")))
+            .add_item(CustomMenuItem::new("direct_mode", t("Direct Mode")))
+            .add_item(CustomMenuItem::new("script_mode", t("Script Mode")))
+            .add_native_item(SystemTrayMenuItem::Separator);
+
+        // 代理分组
+        let groups = TrayProxies::global().menu();
+        let menu = match groups.is_empty() {
+            true => menu,
+            false => groups
+                .into_iter()
+                .fold(menu, |menu, group| menu.add_submenu(group))
+                .add_native_item(SystemTrayMenuItem::Separator),
+        };
+
+        // 自定义的项目
+        let items = TrayMenu::items();
+        let menu = match items.is_empty() {
+            true => menu,
+            false => items
+                .into_iter()
+                .fold(menu, |menu, item| menu.add_item(item))
+                .add_native_item(SystemTrayMenuItem::Separator),
+        };
+
+        menu.add_item(CustomMenuItem::new("system_proxy", t("System Proxy")))
+            .add_item(CustomMenuItem::new("tun_mode", t("TUN Mode")))
+            .add_item(CustomMenuItem::new("pause_proxy", t("Pause Proxy")))
+            .add_submenu(SystemTraySubmenu::new(
+                t("Copy Env"),
+                SystemTrayMenu::new()
+                    .add_item(CustomMenuItem::new("copy_env_sh", "Bash / Zsh"))
+                    .add_item(CustomMenuItem::new("copy_env_fish", "Fish"))
+                    .add_item(CustomMenuItem::new("copy_env_powershell", "PowerShell"))
+                    .add_item(CustomMenuItem::new("copy_env_cmd", "CMD")),
+            ))
+            .add_submenu(SystemTraySubmenu::new(
+                t("Open Dir"),
+                SystemTrayMenu::new()
+                    .add_item(CustomMenuItem::new("open_app_dir", t("App Dir")))
+                    .add_item(CustomMenuItem::new("open_core_dir", t("Core Dir")))
+                    .add_item(CustomMenuItem::new("open_logs_dir", t("Logs Dir"))),
+            ))
+            .add_submenu(SystemTraySubmenu::new(
+                t("More"),
+                SystemTrayMenu::new()
+                    .add_item(CustomMenuItem::new("restart_clash", t("Restart Clash")))
+                    .add_item(CustomMenuItem::new("restart_app", t("Restart App")))
+                    .add_item(
+                        CustomMenuItem::new(
+                            "app_version",
+                            i18n::translate(&lang, "Version {version}", &[("version", &version)]),
+                        )
+                        .disabled(),
+                    ),
+            ))
+            .add_native_item(SystemTrayMenuItem::Separator)
+            .add_item(CustomMenuItem::new("quit", t("Quit")).accelerator("CmdOrControl+Q"))
+    }
+
+    pub fn update_systray(app_handle: &AppHandle) -> Result<()> {
+        app_handle
+            .tray_handle()
+            .set_menu(Tray::tray_menu(app_handle))?;
+        Tray::update_part(app_handle)?;
+        Ok(())
+    }
+
+    pub fn update_part(app_handle: &AppHandle) -> Result<()> {
+        let lang = i18n::language();
+        let t = |key: &str| i18n::translate(&lang, key, &[]);
+        let mode = {
+            Config::clash()
+                .latest()
+                .0
+                .get("mode")
+                .map(|val| val.as_str().unwrap_or("rule"))
+                .unwrap_or("rule")
+                .to_owned()
+        };
+
+        let tray = app_handle.tray_handle();
+
+        let _ = tray.get_item("rule_mode").set_selected(mode == "rule");
+        let _ = tray.get_item("global_mode").set_selected(mode == "global");
+        let _ = tray.get_item("direct_mode").set_selected(mode == "direct");
+        let _ = tray.get_item("script_mode").set_selected(mode == "script");
+
+        let verge = Config::verge();
+        let verge = verge.latest();
+        let system_proxy = verge.enable_system_proxy.as_ref().unwrap_or(&false);
+        let tun_mode = verge.enable_tun_mode.as_ref().unwrap_or(&false);
+
+        crate::log_err!(update_icon(
+            &tray,
+            TrayIconState::from(*system_proxy, *tun_mode)
+        ));
+
+        let _ = tray.get_item("system_proxy").set_selected(*system_proxy);
+        let _ = tray.get_item("tun_mode").set_selected(*tun_mode);
+        let _ = tray
+            .get_item("pause_proxy")
+            .set_selected(ProxyPause::global().state().is_some());
+
+        let switch_map = {
+            let mut map = std::collections::HashMap::new();
+            map.insert(true, t("On"));
+            map.insert(false, t("Off"));
+            map
+        };
+
+        let traffic = TrayTraffic::global().latest();
+
+        // windows 不支持标题，速率显示在提示中
+        #[cfg(not(target_os = "windows"))]
+        let _ = tray.set_title(&traffic.map_or(String::new(), |traffic| {
+            format!(
+                "↑ {} ↓ {}",
+                format_rate(traffic.up),
+                format_rate(traffic.down)
+            )
+        }));
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let tooltip = format!(
+                "{}: {}\n{}: {}",
+                t("System Proxy"),
+                switch_map[system_proxy],
+                t("TUN Mode"),
+                switch_map[tun_mode]
+            );
+            #[cfg(target_os = "windows")]
+            let tooltip = match traffic {
+                Some(traffic) => format!(
+                    "{tooltip}\n↑ {}  ↓ {}",
+                    format_rate(traffic.up),
+                    format_rate(traffic.down)
+                ),
+                None => tooltip,
+            };
+            let _ = tray.set_tooltip(&tooltip);
+        }
+
+        Ok(())
+    }
+
+    pub fn on_system_tray_event(app_handle: &AppHandle, event: SystemTrayEvent) {
+        match event {
+            SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+                mode @ ("rule_mode" | "global_mode" | "direct_mode" | "script_mode") => {
+                    let mode = &mode[0..mode.len() - 5];
+                    feat::change_clash_mode(mode.into());
+                }
+
+                "open_window" => resolve::create_window(app_handle),
+                "system_proxy" => feat::toggle_system_proxy(),
+                "tun_mode" => feat::toggle_tun_mode(),
+                "pause_proxy" => feat::toggle_pause(),
+                "copy_env_sh" => feat::copy_clash_env(ShellType::Sh),
+                "copy_env_fish" => feat::copy_clash_env(ShellType::Fish),
+                "copy_env_powershell" => feat::copy_clash_env(ShellType::Powershell),
+                "copy_env_cmd" => feat::copy_clash_env(ShellType::Cmd),
+                "open_app_dir" => crate::log_err!(cmds::open_app_dir()),
+                "open_core_dir" => crate::log_err!(cmds::open_core_dir()),
+                "open_logs_dir" => crate::log_err!(cmds::open_logs_dir()),
+                "restart_clash" => feat::restart_clash_core(),
+                "restart_app" => api::process::restart(&app_handle.env()),
+                "quit" => {
+                    let _ = resolve::save_window_state(app_handle, true);
+
+                    resolve::resolve_reset();
+                    api::process::kill_children();
+                    app_handle.exit(0);
+                    // flush all data to disk
+                    storage::Storage::global().destroy().unwrap();
+                    std::process::exit(0);
+                }
+                id => {
+                    if !TrayMenu::on_click(id) {
+                        TrayProxies::global().on_click(id);
+                    }
+                }
+            },
+            #[cfg(target_os = "windows")]
+            SystemTrayEvent::LeftClick { .. } => {
+                resolve::create_window(app_handle);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    config::Config,
+    core::{
+        clash_api,
+        event_bus::{AppEvent, EventBus},
+        handle, selection,
+    },
+};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tauri::{CustomMenuItem, SystemTrayMenu, SystemTraySubmenu};
+use tokio::time::sleep;
+
+/// the delay badges are refreshed periodically
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// the menu id of the nodes, `select_proxy::{group index}::{node index}`
+const SELECT_PREFIX: &str = "select_proxy::";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayNode {
+    pub name: String,
+    /// the latest delay, `Some(0)` means timeout
+    pub delay: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayGroup {
+    pub name: String,
+    pub r#type: String,
+    pub now: Option<String>,
+    pub nodes: Vec<TrayNode>,
+}
+
+impl TrayGroup {
+    /// only the selector could be switched manually
+    pub fn selectable(&self) -> bool {
+        self.r#type == "Selector"
+    }
+}
+
+/// cache the proxy groups to build the tray menu synchronously
+pub struct TrayProxies {
+    groups: Mutex<Vec<TrayGroup>>,
+
+    running: AtomicBool,
+}
+
+impl TrayProxies {
+    pub fn global() -> &'static TrayProxies {
+        static TRAY_PROXIES: OnceCell<TrayProxies> = OnceCell::new();
+
+        TRAY_PROXIES.get_or_init(|| TrayProxies {
+            groups: Mutex::new(Vec::new()),
+            running: AtomicBool::new(false),
+        })
+    }
+
+    pub fn enabled() -> bool {
+        let enable = { Config::verge().latest().enable_tray_proxies };
+        enable.unwrap_or(true)
+    }
+
+    /// refresh the groups periodically
+    pub fn start(&'static self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        tauri::async_runtime::spawn(async move {
+            loop {
+                self.refresh().await;
+                sleep(REFRESH_INTERVAL).await;
+            }
+        });
+    }
+
+    /// fetch the groups from the controller, rebuild the menu if changed
+    pub async fn refresh(&self) {
+        if !Self::enabled() {
+            return;
+        }
+
+        let groups = match clash_api::get_proxies().await {
+            Ok(res) => collect_groups(res),
+            Err(err) => {
+                tracing::debug!("failed to fetch the tray proxies: {err}");
+                Vec::new()
+            }
+        };
+
+        let changed = {
+            let mut cached = self.groups.lock();
+            let changed = *cached != groups;
+            *cached = groups;
+            changed
+        };
+        if changed {
+            crate::log_err!(handle::Handle::update_systray());
+        }
+    }
+
+    pub fn refresh_later(&'static self) {
+        tauri::async_runtime::spawn(async move { self.refresh().await });
+    }
+
+    pub fn subscribe_events(&'static self) {
+        EventBus::subscribe("tray_proxies", move |event| async move {
+            if let AppEvent::ProxySelected { .. } = event {
+                self.refresh_later();
+            }
+        });
+    }
+
+    /// the submenus of the groups, empty if disabled or the core is not running
+    pub fn menu(&self) -> Vec<SystemTraySubmenu> {
+        if !Self::enabled() {
+            return Vec::new();
+        }
+
+        self.groups
+            .lock()
+            .iter()
+            .enumerate()
+            .map(|(gi, group)| {
+                let menu = group.nodes.iter().enumerate().fold(
+                    SystemTrayMenu::new(),
+                    |menu, (ni, node)| {
+                        let mut item = CustomMenuItem::new(
+                            format!("{SELECT_PREFIX}{gi}::{ni}"),
+                            node_label(node),
+                        );
+                        if group.now.as_ref() == Some(&node.name) {
+                            item = item.selected();
+                        }
+                        if !group.selectable() {
+                            item = item.disabled();
+                        }
+                        menu.add_item(item)
+                    },
+                );
+
+                let title = match group.now.as_ref() {
+                    Some(now) => format!("{}: {now}", group.name),
+                    None => group.name.clone(),
+                };
+                SystemTraySubmenu::new(title, menu)
+            })
+            .collect()
+    }
+
+    /// handle the click of a node, ignore the other ids
+    pub fn on_click(&self, id: &str) {
+        let indexes = match id.strip_prefix(SELECT_PREFIX) {
+            Some(indexes) => indexes,
+            None => return,
+        };
+
+        let target = indexes.split_once("::").and_then(|(gi, ni)| {
+            let (gi, ni) = (gi.parse::<usize>().ok()?, ni.parse::<usize>().ok()?);
+            let groups = self.groups.lock();
+            let group = groups.get(gi)?;
+            let node = group.nodes.get(ni)?;
+            Some((group.name.clone(), node.name.clone()))
+        });
+
+        if let Some((group, node)) = target {
+            tauri::async_runtime::spawn(async move {
+                match selection::select(group, node).await {
+                    Ok(_) => handle::Handle::refresh_clash(),
+                    Err(err) => tracing::error!("failed to select the proxy: {err}"),
+                }
+            });
+        }
+    }
+}
+
+fn node_label(node: &TrayNode) -> String {
+    match node.delay {
+        Some(0) => format!("{}    timeout", node.name),
+        Some(delay) => format!("{}    {delay}ms", node.name),
+        None => node.name.clone(),
+    }
+}
+
+/// keep the order of the config by `GLOBAL`
+fn collect_groups(res: clash_api::ProxiesRes) -> Vec<TrayGroup> {
+    let mode = {
+        Config::clash()
+            .latest()
+            .0
+            .get("mode")
+            .and_then(|v| v.as_str())
+            .unwrap_or("rule")
+            .to_lowercase()
+    };
+
+    let proxies = res.proxies;
+    let mut names = proxies
+        .get("GLOBAL")
+        .and_then(|global| global.all.clone())
+        .unwrap_or_default();
+    // 全局模式下只有 GLOBAL 分组生效
+    match mode.as_str() {
+        "global" => names = vec!["GLOBAL".into()],
+        "direct" => return Vec::new(),
+        _ => {}
+    }
+
+    names
+        .iter()
+        .filter_map(|name| proxies.get(name))
+        .filter(|item| item.all.is_some() && !item.hidden)
+        .map(|item| TrayGroup {
+            name: item.name.clone(),
+            r#type: item.r#type.clone(),
+            now: item.now.clone(),
+            nodes: item
+                .all
+                .iter()
+                .flatten()
+                .map(|name| TrayNode {
+                    name: name.clone(),
+                    delay: proxies
+                        .get(name)
+                        .and_then(|node| node.history.last())
+                        .map(|history| history.delay),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_node_label() {
+    let node = |delay| TrayNode {
+        name: "HK".into(),
+        delay,
+    };
+    assert_eq!(node_label(&node(None)), "HK");
+    assert_eq!(node_label(&node(Some(0))), "HK    timeout");
+    assert_eq!(node_label(&node(Some(120))), "HK    120ms");
+}
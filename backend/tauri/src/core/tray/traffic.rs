@@ -0,0 +1,122 @@
+use crate::{
+    config::Config,
+    core::{
+        handle,
+        stream::{LowPowerPolicy, StreamHandler, StreamManager},
+    },
+};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// the frame sent by `/traffic` every second, bytes per second
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Traffic {
+    pub up: u64,
+    pub down: u64,
+}
+
+/// render the rates into the tray
+/// the title on macos and linux, the tooltip on windows
+pub struct TrayTraffic {
+    latest: Arc<Mutex<Option<Traffic>>>,
+
+    running: AtomicBool,
+}
+
+impl TrayTraffic {
+    pub fn global() -> &'static TrayTraffic {
+        static TRAY_TRAFFIC: OnceCell<TrayTraffic> = OnceCell::new();
+
+        TRAY_TRAFFIC.get_or_init(|| TrayTraffic {
+            latest: Arc::new(Mutex::new(None)),
+            running: AtomicBool::new(false),
+        })
+    }
+
+    pub fn enabled() -> bool {
+        let enable = { Config::verge().latest().enable_tray_traffic };
+        enable.unwrap_or(false)
+    }
+
+    /// the stream keeps running after disabled, the frames are ignored
+    pub fn start(&'static self) {
+        if !Self::enabled() || self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        StreamManager::global().spawn(self);
+    }
+
+    /// `None` if disabled or no frame received
+    pub fn latest(&self) -> Option<Traffic> {
+        match Self::enabled() {
+            true => *self.latest.lock(),
+            false => None,
+        }
+    }
+
+    /// clear the title after disabled
+    pub fn clear(&self) {
+        *self.latest.lock() = None;
+        crate::log_err!(handle::Handle::update_systray_part());
+    }
+}
+
+impl StreamHandler for TrayTraffic {
+    fn path(&self) -> &'static str {
+        "traffic"
+    }
+
+    /// the tray is still visible, update it less often
+    fn low_power(&self) -> LowPowerPolicy {
+        LowPowerPolicy::Throttle(Duration::from_secs(3))
+    }
+
+    fn on_message(&self, text: String) {
+        if !Self::enabled() {
+            return;
+        }
+
+        match serde_json::from_str::<Traffic>(&text) {
+            Ok(traffic) => {
+                let changed = self.latest.lock().replace(traffic) != Some(traffic);
+                if changed {
+                    crate::log_err!(handle::Handle::update_systray_part());
+                }
+            }
+            Err(err) => tracing::trace!("invalid traffic frame: {err}"),
+        }
+    }
+}
+
+/// 1024 based, e.g. `512 B/s` `1.5 KB/s` `12 MB/s`
+pub fn format_rate(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit == 0 || value >= 10.0 {
+        true => format!("{:.0} {}/s", value, UNITS[unit]),
+        false => format!("{:.1} {}/s", value, UNITS[unit]),
+    }
+}
+
+#[test]
+fn test_format_rate() {
+    assert_eq!(format_rate(0), "0 B/s");
+    assert_eq!(format_rate(512), "512 B/s");
+    assert_eq!(format_rate(1536), "1.5 KB/s");
+    assert_eq!(format_rate(12 * 1024 * 1024), "12 MB/s");
+}
@@ -0,0 +1,317 @@
+use super::{clash_api, handle, CoreManager};
+use crate::{config::Config, utils::dirs};
+use anyhow::{bail, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{sync::Arc, time::Duration};
+use sysinfo::{NetworksExt, System, SystemExt};
+use tauri::async_runtime::Mutex as TokioMutex;
+use tokio::time::sleep;
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(10);
+/// give up recovering after failed too many times
+const MAX_RECOVER_TIMES: u32 = 3;
+
+/// the interface name prefixes of the common vpn / tun softwares
+const VPN_PREFIXES: [&str; 12] = [
+    "tun",
+    "tap",
+    "wg",
+    "ppp",
+    "wintun",
+    "wireguard",
+    "openvpn",
+    "zerotier",
+    "zt",
+    "tailscale",
+    "nordlynx",
+    "utun",
+];
+
+/// the name mihomo gives the tun device when `device` is not set, `utunN` on macos
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_DEVICE: &str = "Meta";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TunStatus {
+    /// tun mode is enabled in the settings
+    pub enabled: bool,
+    /// the core is able to create the tun device
+    pub privileged: bool,
+    /// the tun device name reported by the core
+    pub device: Option<String>,
+    /// the tun is enabled in the core and its interface exists in the system
+    pub running: bool,
+    /// other vpn / tun devices which may hijack the routes
+    pub conflicts: Vec<String>,
+    /// how to grant the privileges, e.g. `service` `setcap` `setuid`
+    pub hint: Option<String>,
+}
+
+pub struct TunManager {
+    status: Arc<Mutex<TunStatus>>,
+
+    /// record whether the monitor is running or not
+    monitor_state: Arc<TokioMutex<bool>>,
+}
+
+impl TunManager {
+    pub fn global() -> &'static TunManager {
+        static TUN_MANAGER: OnceCell<TunManager> = OnceCell::new();
+
+        TUN_MANAGER.get_or_init(|| TunManager {
+            status: Arc::new(Mutex::new(TunStatus::default())),
+            monitor_state: Arc::new(TokioMutex::new(false)),
+        })
+    }
+
+    pub fn status(&self) -> TunStatus {
+        self.status.lock().clone()
+    }
+
+    /// check the privileges, the tun device and the conflicts
+    pub async fn check(&self) -> TunStatus {
+        let enabled = { Config::verge().latest().enable_tun_mode.unwrap_or(false) };
+        let privileged = check_privileges().await;
+
+        let (device, enabled_in_core) = match clash_api::get_configs().await {
+            Ok(configs) => {
+                let tun = configs.get("tun").and_then(|v| v.as_mapping());
+                let enabled = tun
+                    .and_then(|tun| tun.get("enable"))
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let device = tun
+                    .and_then(|tun| tun.get("device"))
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string());
+                (device, enabled)
+            }
+            Err(_) => (None, false),
+        };
+
+        // 控制器只回显配置，接口被删掉后仍然是 enable
+        let interfaces = interfaces();
+        let running = enabled_in_core && interface_exists(&interfaces, device.as_deref());
+        let conflicts = find_conflicts(&interfaces, device.as_deref());
+
+        let hint = match privileged {
+            true => None,
+            false if cfg!(target_os = "windows") => Some("service".into()),
+            false if cfg!(target_os = "macos") => Some("helper".into()),
+            false => Some("setcap".into()),
+        };
+
+        let status = TunStatus {
+            enabled,
+            privileged,
+            device,
+            running,
+            conflicts,
+            hint,
+        };
+
+        let changed = {
+            let mut cur = self.status.lock();
+            let changed = cur.running != status.running || cur.conflicts != status.conflicts;
+            *cur = status.clone();
+            changed
+        };
+        if changed {
+            handle::Handle::emit("verge://tun-status", status.clone());
+        }
+
+        status
+    }
+
+    /// watch the tun device while tun mode is enabled
+    /// restart the core when the device is gone, e.g. removed by other vpn softwares
+    pub fn start_monitor(&'static self) {
+        let monitor_state = self.monitor_state.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut state = monitor_state.lock().await;
+            if *state {
+                return;
+            }
+            *state = true;
+            drop(state);
+
+            let mut failed = 0u32;
+
+            loop {
+                sleep(MONITOR_INTERVAL).await;
+
+                let status = self.check().await;
+                if !status.enabled {
+                    break;
+                }
+                if status.running {
+                    failed = 0;
+                    continue;
+                }
+                if !status.privileged || failed >= MAX_RECOVER_TIMES {
+                    continue;
+                }
+
+                failed += 1;
+                tracing::warn!("tun device is not running, try to recover ({failed})");
+                if let Err(err) = CoreManager::global().run_core().await {
+                    tracing::error!("failed to recover tun: {err}");
+                }
+            }
+
+            let mut state = monitor_state.lock().await;
+            *state = false;
+            drop(state);
+        });
+    }
+}
+
+fn interfaces() -> Vec<String> {
+    let mut system = System::new();
+    system.refresh_networks_list();
+    system
+        .networks()
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// the tun interface of the core is in the system
+fn interface_exists(interfaces: &[String], device: Option<&str>) -> bool {
+    match device {
+        Some(device) => interfaces.iter().any(|name| name == device),
+        #[cfg(not(target_os = "macos"))]
+        None => interfaces.iter().any(|name| name == DEFAULT_DEVICE),
+        // utun 的序号不固定，看默认路由是否经过 utun
+        #[cfg(target_os = "macos")]
+        None => default_route_interface().is_some_and(|name| name.starts_with("utun")),
+    }
+}
+
+/// `interface: en0` in the output of `route -n get default`
+#[cfg(target_os = "macos")]
+fn default_route_interface() -> Option<String> {
+    let output = std::process::Command::new("route")
+        .args(["-n", "get", "default"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("interface:"))
+        .map(|name| name.trim().to_string())
+}
+
+fn find_conflicts(interfaces: &[String], device: Option<&str>) -> Vec<String> {
+    let mut conflicts = interfaces
+        .iter()
+        .filter(|name| Some(name.as_str()) != device)
+        // macos uses utun for the system services
+        .filter(|name| !(cfg!(target_os = "macos") && name.starts_with("utun")))
+        .filter(|name| {
+            let lower = name.to_lowercase();
+            VPN_PREFIXES.iter().any(|prefix| lower.starts_with(prefix))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    conflicts.sort();
+    conflicts
+}
+
+#[cfg_attr(target_os = "windows", allow(unused))]
+fn core_path() -> Result<std::path::PathBuf> {
+    let clash_core = { Config::verge().latest().clash_core.clone() };
+    let clash_core = clash_core.unwrap_or_default();
+    dirs::core_path(&clash_core.to_string())
+}
+
+#[cfg(target_os = "windows")]
+async fn check_privileges() -> bool {
+    use deelevate::{PrivilegeLevel, Token};
+
+    let service_mode = {
+        Config::verge()
+            .latest()
+            .enable_service_mode
+            .unwrap_or(false)
+    };
+    if service_mode && super::win_service::check_service().await.is_ok() {
+        return true;
+    }
+
+    Token::with_current_process()
+        .and_then(|token| token.privilege_level())
+        .map_or(false, |level| level != PrivilegeLevel::NotPrivileged)
+}
+
+/// the core is run by the privileged helper, or owned by root with the setuid bit
+#[cfg(target_os = "macos")]
+async fn check_privileges() -> bool {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    if super::mac_helper::is_enabled() && super::mac_helper::check_helper().await.is_ok() {
+        return true;
+    }
+
+    core_path()
+        .and_then(|path| Ok(std::fs::metadata(path)?))
+        .map_or(false, |meta| {
+            meta.uid() == 0 && meta.permissions().mode() & 0o4000 != 0
+        })
+}
+
+/// the core needs `cap_net_admin` or the app is running as root
+#[cfg(target_os = "linux")]
+async fn check_privileges() -> bool {
+    use std::process::Command;
+
+    let is_root = Command::new("id")
+        .arg("-u")
+        .output()
+        .map_or(false, |output| {
+            String::from_utf8_lossy(&output.stdout).trim() == "0"
+        });
+    if is_root {
+        return true;
+    }
+
+    core_path()
+        .and_then(|path| Ok(Command::new("getcap").arg(path).output()?))
+        .map_or(false, |output| {
+            String::from_utf8_lossy(&output.stdout).contains("cap_net_admin")
+        })
+}
+
+/// ask the user to grant the privileges to the core
+pub async fn grant_privileges() -> Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        super::win_service::install_service().await?;
+    }
+
+    // 安装 helper 代替 setuid，之后不再需要密码
+    #[cfg(target_os = "macos")]
+    {
+        super::mac_helper::install_helper().await?;
+        Config::verge().draft().enable_service_mode = Some(true);
+        Config::verge().apply();
+        Config::verge().data().save_file()?;
+        handle::Handle::refresh_verge();
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let clash_core = { Config::verge().latest().clash_core.clone() };
+        super::manager::grant_permission(clash_core.unwrap_or_default().to_string())?;
+    }
+
+    if !check_privileges().await {
+        bail!("the core is still unprivileged");
+    }
+
+    // 需要重启内核才能生效
+    CoreManager::global().run_core().await
+}
@@ -1,17 +1,26 @@
 use std::{collections::HashMap, io::Cursor, path::Path, sync::OnceLock};
 
-use crate::config::ClashCore;
+use crate::config::{ClashCore, Config, HttpPurpose};
 use anyhow::{anyhow, Result};
 use gunzip::Decompressor;
-use log::debug;
 use serde::{de, Deserialize, Serialize};
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::PermissionsExt;
-use tempfile::{tempdir, TempDir};
+use tempfile::{Builder as TempBuilder, TempDir};
 use tokio::{join, sync::RwLock, task::spawn_blocking};
+use tracing::debug;
 use zip::ZipArchive;
 
-use super::CoreManager;
+use super::{
+    artifact_cache,
+    http::{self, HttpClient},
+    CoreManager,
+};
+
+/// the prefix of the download dirs, left in the temp dir if the app is killed while updating
+pub const TEMP_PREFIX: &str = "nyanpasu-core-";
+
+const DEFAULT_MIRROR: &str = "https://mirror.ghproxy.com/github.com";
 
 pub struct Updater {
     manifest_version: ManifestVersion,
@@ -22,7 +31,7 @@ impl Default for Updater {
     fn default() -> Self {
         Self {
             manifest_version: ManifestVersion::default(),
-            mirror: "https://mirror.ghproxy.com/github.com".to_string(),
+            mirror: DEFAULT_MIRROR.to_string(),
         }
     }
 }
@@ -106,40 +115,29 @@ impl Updater {
     }
 
     pub async fn fetch_latest(&mut self) -> Result<()> {
+        self.mirror = github_mirror();
         let latest = get_latest_version_manifest(self.mirror.as_str());
         let mihomo_alpha_version = self.get_mihomo_alpha_version();
         let (latest, mihomo_alpha_version) = join!(latest, mihomo_alpha_version);
-        log::debug!("latest version: {:?}", latest);
+        tracing::debug!("latest version: {:?}", latest);
         self.manifest_version = latest?;
-        log::debug!("mihomo alpha version: {:?}", mihomo_alpha_version);
+        tracing::debug!("mihomo alpha version: {:?}", mihomo_alpha_version);
         self.manifest_version.latest.mihomo_alpha = mihomo_alpha_version?;
         Ok(())
     }
 
     async fn get_mihomo_alpha_version(&self) -> Result<String> {
-        let client = reqwest::Client::new();
+        let client = client()?;
         let url = format!(
             "{}/{}",
             self.mirror.as_str(),
             "MetaCubeX/mihomo/releases/download/Prerelease-Alpha/version.txt"
         );
-        Ok(client
-            .get(url)
-            .send()
-            .await?
-            .text()
-            .await?
-            .trim()
-            .to_string())
+        Ok(client.get(url).await?.text().await?.trim().to_string())
     }
 
     pub async fn update_core(&self, core_type: &ClashCore) -> Result<()> {
-        let current_core = crate::config::Config::verge()
-            .latest()
-            .clash_core
-            .clone()
-            .unwrap_or_default();
-        let tmp_dir = tempdir()?;
+        let tmp_dir = TempBuilder::new().prefix(TEMP_PREFIX).tempdir()?;
         // 1. download core
         debug!("downloading core");
         let artifact = self.download_core(core_type, &tmp_dir).await?;
@@ -152,29 +150,33 @@ impl Updater {
             decompress_and_set_permission(&core_type_ref, &tmp_dir_path, &artifact_ref)
         })
         .await??;
-        // 3. if core is used, close it
-        if current_core == *core_type {
-            CoreManager::global().stop_core()?;
-        }
-        // 4. replace core
-        #[cfg(target_os = "windows")]
-        let target_core = format!("{}.exe", core_type);
-        #[cfg(not(target_os = "windows"))]
-        let target_core = core_type.clone().to_string();
-        let core_dir = tauri::utils::platform::current_exe()?;
-        let core_dir = core_dir.parent().ok_or(anyhow!("failed to get core dir"))?;
-        let target_core = core_dir.join(target_core);
-        debug!("copying core to {:?}", target_core);
-        std::fs::copy(
-            tmp_dir.path().join(core_type.clone().to_string()),
-            target_core,
-        )?;
-
-        // 5. if core is used before, restart it
-        if current_core == *core_type {
-            CoreManager::global().run_core().await?;
-        }
-        Ok(())
+        replace_core(core_type, &tmp_dir).await
+    }
+
+    /// install the core from a local file for the offline machines
+    /// the archives from the release page are accepted too, return the version of the core
+    pub async fn install_core_from_file(path: &Path, core_type: &ClashCore) -> Result<String> {
+        let fname = path
+            .file_name()
+            .ok_or(anyhow!("invalid file path"))?
+            .to_string_lossy()
+            .into_owned();
+        let tmp_dir = TempBuilder::new().prefix(TEMP_PREFIX).tempdir()?;
+        std::fs::copy(path, tmp_dir.path().join(&fname))?;
+
+        let core_type_ref = core_type.clone();
+        let tmp_dir_path = tmp_dir.path().to_owned();
+        let version = spawn_blocking(move || {
+            decompress_and_set_permission(&core_type_ref, &tmp_dir_path, &fname)?;
+            let tmp_core = tmp_dir_path.join(core_type_ref.to_string());
+            check_binary_arch(&std::fs::read(&tmp_core)?)?;
+            probe_version(&tmp_core, &core_type_ref)
+        })
+        .await??;
+        debug!("installing {core_type} {version} from {path:?}");
+
+        replace_core(core_type, &tmp_dir).await?;
+        Ok(version)
     }
 
     async fn download_core(&self, core_type: &ClashCore, tmp_dir: &TempDir) -> Result<String> {
@@ -234,13 +236,161 @@ impl Updater {
         debug!("file path: {:?}", file_path);
         let mut dst = std::fs::File::create(&file_path)?;
 
-        let client = reqwest::Client::new();
-        let mut buff = Cursor::new(client.get(url).send().await?.bytes().await?);
+        // 下载过的同一个文件不再下载
+        let data = match artifact_cache::get(&url) {
+            Some(data) => {
+                debug!("use the cached artifact: {}", artifact);
+                data
+            }
+            None => {
+                let client = client()?;
+                let data = client
+                    .get(&url)
+                    .await?
+                    .error_for_status()?
+                    .bytes()
+                    .await?
+                    .to_vec();
+                crate::log_err!(artifact_cache::put(&url, &artifact, &data));
+                data
+            }
+        };
+        let mut buff = Cursor::new(data);
         std::io::copy(&mut buff, &mut dst)?;
         Ok(artifact)
     }
 }
 
+/// stop the core if it's in use, replace the binary with the one in `tmp_dir` and restart it
+async fn replace_core(core_type: &ClashCore, tmp_dir: &TempDir) -> Result<()> {
+    let current_core = crate::config::Config::verge()
+        .latest()
+        .clash_core
+        .clone()
+        .unwrap_or_default();
+    // 3. if core is used, close it
+    if current_core == *core_type {
+        CoreManager::global().stop_core()?;
+    }
+    // 4. replace core
+    #[cfg(target_os = "windows")]
+    let target_core = format!("{}.exe", core_type);
+    #[cfg(not(target_os = "windows"))]
+    let target_core = core_type.clone().to_string();
+    let core_dir = tauri::utils::platform::current_exe()?;
+    let core_dir = core_dir.parent().ok_or(anyhow!("failed to get core dir"))?;
+    let target_core = core_dir.join(target_core);
+    debug!("copying core to {:?}", target_core);
+    std::fs::copy(
+        tmp_dir.path().join(core_type.clone().to_string()),
+        target_core,
+    )?;
+
+    // 5. if core is used before, restart it
+    if current_core == *core_type {
+        CoreManager::global().run_core().await?;
+    }
+    Ok(())
+}
+
+/// make sure the binary is built for this os and cpu, a wrong one fails to start silently
+fn check_binary_arch(bin: &[u8]) -> Result<()> {
+    let u16_at = |offset: usize| {
+        bin.get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let u32_at = |offset: usize| {
+        bin.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    let (os, arch) = match bin {
+        [0x7f, b'E', b'L', b'F', ..] => (
+            "linux",
+            match u16_at(18) {
+                Some(0x3e) => "x86_64",
+                Some(0xb7) => "aarch64",
+                _ => "unknown",
+            },
+        ),
+        [b'M', b'Z', ..] => {
+            let pe = u32_at(0x3c).unwrap_or_default() as usize;
+            if bin.get(pe..pe + 4) != Some(b"PE\0\0".as_slice()) {
+                anyhow::bail!("invalid windows executable");
+            }
+            (
+                "windows",
+                match u16_at(pe + 4) {
+                    Some(0x8664) => "x86_64",
+                    Some(0xaa64) => "aarch64",
+                    _ => "unknown",
+                },
+            )
+        }
+        [0xcf, 0xfa, 0xed, 0xfe, ..] => (
+            "macos",
+            match u32_at(4) {
+                Some(0x0100_0007) => "x86_64",
+                Some(0x0100_000c) => "aarch64",
+                _ => "unknown",
+            },
+        ),
+        // universal binary
+        [0xca, 0xfe, 0xba, 0xbe, ..] => ("macos", std::env::consts::ARCH),
+        _ => anyhow::bail!("the file is not an executable"),
+    };
+
+    if os != std::env::consts::OS || arch != std::env::consts::ARCH {
+        anyhow::bail!(
+            "the core is built for {os}-{arch}, but this is {}-{}",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+    }
+    Ok(())
+}
+
+/// run `-v` of the new core, a broken binary or a wrong core type fails here
+fn probe_version(path: &Path, core_type: &ClashCore) -> Result<String> {
+    use std::process::{Command, Stdio};
+
+    let arg = match core_type {
+        ClashCore::ClashRs => "-V",
+        _ => "-v",
+    };
+    let mut child = Command::new(path)
+        .arg(arg)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // 防止卡住
+    let started = std::time::Instant::now();
+    while child.try_wait()?.is_none() {
+        if started.elapsed() > std::time::Duration::from_secs(5) {
+            let _ = child.kill();
+            anyhow::bail!("the core doesn't respond to `{arg}`");
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    let out = child.wait_with_output()?;
+    let out = String::from_utf8_lossy(&out.stdout);
+    if !out.to_lowercase().contains(core_name_hint(core_type)) {
+        anyhow::bail!("the file is not a {core_type} core");
+    }
+    crate::utils::resolve::parse_core_version(&out)
+        .ok_or(anyhow!("failed to get the version of the core"))
+}
+
+/// the name printed by `-v`, e.g. `Mihomo Meta v1.18.0 ...`
+fn core_name_hint(core_type: &ClashCore) -> &'static str {
+    match core_type {
+        ClashCore::ClashPremium | ClashCore::ClashRs => "clash",
+        ClashCore::Mihomo | ClashCore::MihomoAlpha => "mihomo",
+    }
+}
+
 fn decompress_and_set_permission(
     core_type: &ClashCore,
     tmp_path: &Path,
@@ -293,19 +443,28 @@ fn decompress_and_set_permission(
     Ok(())
 }
 
+/// the configured mirror, the builtin one if not set
+fn github_mirror() -> String {
+    let mirror = { Config::verge().latest().github_mirror.clone() };
+    match mirror.as_deref().map(str::trim) {
+        None => DEFAULT_MIRROR.to_string(),
+        Some("") => "https://github.com".to_string(),
+        Some(prefix) => format!("{}/github.com", prefix.trim_end_matches('/')),
+    }
+}
+
+fn client() -> Result<HttpClient> {
+    http::client(HttpPurpose::Updater)
+}
+
 pub async fn get_latest_version_manifest(mirror: &str) -> Result<ManifestVersion> {
     let url = format!(
         "{}/keiko233/clash-nyanpasu/raw/dev/manifest/version.json",
         mirror
     );
-    log::debug!("{}", url);
-    let client = reqwest::Client::new();
-    Ok(client
-        .get(url)
-        .send()
-        .await?
-        .json::<ManifestVersion>()
-        .await?)
+    tracing::debug!("{}", url);
+    let client = client()?;
+    Ok(client.get(url).await?.json::<ManifestVersion>().await?)
 }
 
 enum CoreTypeMeta {
@@ -333,3 +492,28 @@ fn get_download_path(core_type: CoreTypeMeta, artifact: String) -> String {
         ),
     }
 }
+
+#[test]
+fn test_check_binary_arch() {
+    assert!(check_binary_arch(b"#!/bin/sh").is_err());
+
+    let mut elf = vec![0x7f, b'E', b'L', b'F'];
+    elf.resize(20, 0);
+    elf[18] = 0x3e;
+    let result = check_binary_arch(&elf);
+    assert_eq!(
+        result.is_ok(),
+        std::env::consts::OS == "linux" && std::env::consts::ARCH == "x86_64"
+    );
+
+    let mut pe = vec![0u8; 0x90];
+    pe[..2].copy_from_slice(b"MZ");
+    pe[0x3c] = 0x80;
+    pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+    pe[0x84..0x86].copy_from_slice(&0xaa64u16.to_le_bytes());
+    let result = check_binary_arch(&pe);
+    assert_eq!(
+        result.is_ok(),
+        std::env::consts::OS == "windows" && std::env::consts::ARCH == "aarch64"
+    );
+}
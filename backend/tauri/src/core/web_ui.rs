@@ -0,0 +1,391 @@
+//! the third-party web uis, downloaded and served on localhost
+//!
+//! only the gh-pages commits pinned in `manifest/web-ui.json` are installed, the archives are
+//! checked against the sha256 of the pins. the pins are built into the app and updated with
+//! the other manifests by `pnpm generate:manifest`.
+//!
+//! the controller address and the secret are put into the url when opening, so the web ui
+//! connects without the setup page. the controller proxy is preferred when it is enabled,
+//! the web uis then get its token instead of the core secret.
+
+use super::{http, maintenance::dir_size};
+use crate::{
+    config::{Config, HttpPurpose},
+    utils::{dirs, server::LocalServer},
+};
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Cursor},
+    path::{Component, Path, PathBuf},
+};
+use tokio::sync::Mutex;
+use url::form_urlencoded;
+use zip::ZipArchive;
+
+pub const DEFAULT_PORT: u16 = 33338;
+
+/// how the web ui reads the controller from the url
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SetupStyle {
+    /// `/?hostname=&port=&secret=`
+    Query,
+    /// `/#/setup?hostname=&port=&secret=`
+    Hash,
+}
+
+struct WebUiSource {
+    id: &'static str,
+    name: &'static str,
+    /// the built pages are in the gh-pages branch of the repo
+    repo: &'static str,
+    setup: SetupStyle,
+}
+
+const SOURCES: &[WebUiSource] = &[
+    WebUiSource {
+        id: "yacd",
+        name: "Yacd",
+        repo: "MetaCubeX/Yacd-meta",
+        setup: SetupStyle::Query,
+    },
+    WebUiSource {
+        id: "metacubexd",
+        name: "MetaCubeXD",
+        repo: "MetaCubeX/metacubexd",
+        setup: SetupStyle::Hash,
+    },
+    WebUiSource {
+        id: "zashboard",
+        name: "Zashboard",
+        repo: "Zephyruso/zashboard",
+        setup: SetupStyle::Hash,
+    },
+];
+
+/// the web ui of the id, the gh-pages commit and the sha256 of its archive
+#[derive(Debug, Deserialize)]
+struct WebUiPin {
+    commit: String,
+    sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebUiManifest {
+    pins: HashMap<String, WebUiPin>,
+}
+
+static PINS: Lazy<HashMap<String, WebUiPin>> = Lazy::new(|| {
+    serde_json::from_str::<WebUiManifest>(include_str!("../../../../manifest/web-ui.json"))
+        .map(|manifest| manifest.pins)
+        .unwrap_or_else(|err| {
+            tracing::error!("invalid web ui manifest: {err}");
+            HashMap::new()
+        })
+});
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebUiInfo {
+    pub id: String,
+    pub name: String,
+    /// can be installed, the web uis without a pin in this build can't
+    pub pinned: bool,
+    pub installed: bool,
+    /// bytes
+    pub size: u64,
+    /// unix timestamp
+    pub installed_at: Option<i64>,
+}
+
+fn source(id: &str) -> Result<&'static WebUiSource> {
+    SOURCES
+        .iter()
+        .find(|source| source.id == id)
+        .ok_or(anyhow!("unknown web ui `{id}`"))
+}
+
+fn install_dir(id: &str) -> Result<PathBuf> {
+    Ok(dirs::app_web_ui_dir()?.join(id))
+}
+
+pub fn list() -> Result<Vec<WebUiInfo>> {
+    SOURCES
+        .iter()
+        .map(|source| {
+            let dir = install_dir(source.id)?;
+            let installed = dir.join("index.html").exists();
+            let installed_at = fs::metadata(&dir)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .filter(|_| installed)
+                .map(|time| chrono::DateTime::<chrono::Local>::from(time).timestamp());
+            Ok(WebUiInfo {
+                id: source.id.to_string(),
+                name: source.name.to_string(),
+                pinned: PINS.contains_key(source.id),
+                installed,
+                size: if installed { dir_size(&dir) } else { 0 },
+                installed_at,
+            })
+        })
+        .collect()
+}
+
+/// download the pinned build, replace the installed one
+pub async fn install(id: &str) -> Result<()> {
+    let source = source(id)?;
+    let pin = PINS.get(id).ok_or(anyhow!(
+        "the web ui `{}` is not pinned in this build",
+        source.name
+    ))?;
+    let url = format!(
+        "https://github.com/{}/archive/{}.zip",
+        source.repo, pin.commit
+    );
+    let data = http::client(HttpPurpose::Updater)?
+        .get(url)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    verify(&data, pin)?;
+
+    let dir = install_dir(id)?;
+    let id = id.to_string();
+    tokio::task::spawn_blocking(move || extract(&data, &dir)).await??;
+    tracing::info!("the web ui `{id}` is installed");
+    Ok(())
+}
+
+fn verify(data: &[u8], pin: &WebUiPin) -> Result<()> {
+    let sha256 = hex::encode(Sha256::digest(data));
+    if !sha256.eq_ignore_ascii_case(&pin.sha256) {
+        bail!(
+            "the sha256 of the web ui archive is {sha256}, {} is expected",
+            pin.sha256
+        );
+    }
+    Ok(())
+}
+
+/// extract to a temp dir first, the installed one is kept if the archive is broken
+fn extract(data: &[u8], dir: &Path) -> Result<()> {
+    let parent = dir.parent().ok_or(anyhow!("invalid web ui dir"))?;
+    fs::create_dir_all(parent)?;
+    let temp = tempfile::Builder::new()
+        .prefix(".web-ui-")
+        .tempdir_in(parent)?;
+
+    let mut archive = ZipArchive::new(Cursor::new(data)).context("invalid web ui archive")?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        // 去掉 `<repo>-<commit>/` 这一层
+        let Some(path) = file.enclosed_name().map(strip_root) else {
+            bail!("invalid file `{}` in the web ui archive", file.name());
+        };
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+        let target = temp.path().join(path);
+        if file.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut file, &mut fs::File::create(&target)?)?;
+        }
+    }
+    if !temp.path().join("index.html").exists() {
+        bail!("the index.html is missing in the web ui archive");
+    }
+
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::rename(temp.into_path(), dir)?;
+    Ok(())
+}
+
+fn strip_root(path: &Path) -> PathBuf {
+    path.components()
+        .skip(1)
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect()
+}
+
+pub fn remove(id: &str) -> Result<()> {
+    source(id)?;
+    let dir = install_dir(id)?;
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// the controller the web ui connects to, the host, the port and the secret
+fn controller() -> Result<(String, u16, String)> {
+    let proxy = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        verge.enable_controller_proxy.unwrap_or(false).then(|| {
+            (
+                verge.controller_proxy_port,
+                verge.controller_proxy_token.clone(),
+            )
+        })
+    };
+    if let Some((Some(port), Some(token))) = proxy {
+        return Ok(("127.0.0.1".into(), port, token));
+    }
+
+    let info = { Config::clash().latest().get_client_info() };
+    let (host, port) = info
+        .server
+        .rsplit_once(':')
+        .ok_or(anyhow!("invalid external controller `{}`", info.server))?;
+    let port = port.parse()?;
+    Ok((host.to_string(), port, info.secret.unwrap_or_default()))
+}
+
+fn build_url(
+    port: u16,
+    source: &WebUiSource,
+    (host, controller_port, secret): (String, u16, String),
+) -> String {
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("hostname", &host)
+        .append_pair("port", &controller_port.to_string())
+        .append_pair("secret", &secret)
+        .finish();
+    match source.setup {
+        SetupStyle::Query => format!("http://127.0.0.1:{port}/{}/?{query}", source.id),
+        SetupStyle::Hash => format!("http://127.0.0.1:{port}/{}/#/setup?{query}", source.id),
+    }
+}
+
+/// serve the installed web uis, started on the first open
+pub struct WebUiServer {
+    server: LocalServer,
+    /// the listening port, none if not running
+    port: Mutex<Option<u16>>,
+}
+
+impl WebUiServer {
+    pub fn global() -> &'static WebUiServer {
+        static SERVER: OnceCell<WebUiServer> = OnceCell::new();
+
+        SERVER.get_or_init(|| WebUiServer {
+            server: LocalServer::default(),
+            port: Mutex::new(None),
+        })
+    }
+
+    fn port() -> u16 {
+        let port = { Config::verge().latest().web_ui_port };
+        port.unwrap_or(DEFAULT_PORT)
+    }
+
+    /// start the server if not running, return the port
+    async fn ensure_started(&self) -> Result<u16> {
+        let mut running = self.port.lock().await;
+        if let Some(port) = *running {
+            return Ok(port);
+        }
+
+        let port = Self::port();
+        let root = dirs::app_web_ui_dir()?;
+        let addr = self
+            .server
+            .start(|rx| {
+                warp::serve(warp::fs::dir(root)).try_bind_with_graceful_shutdown(
+                    ([127, 0, 0, 1], port),
+                    async {
+                        rx.await.ok();
+                    },
+                )
+            })
+            .await?;
+        *running = Some(port);
+
+        tracing::info!("web ui server is listening on {addr}");
+        Ok(port)
+    }
+
+    /// apply the new port if it is running
+    pub async fn restart(&self) -> Result<()> {
+        if self.stop().await {
+            self.ensure_started().await?;
+        }
+        Ok(())
+    }
+
+    /// return false if not running
+    pub async fn stop(&self) -> bool {
+        let mut running = self.port.lock().await;
+        running.take();
+        let stopped = self.server.stop().await;
+        if stopped {
+            tracing::info!("web ui server is stopped");
+        }
+        stopped
+    }
+
+    /// open the web ui in the browser, the default one if `id` is none
+    pub async fn open(&self, id: Option<String>) -> Result<()> {
+        let id = id
+            .or_else(|| Config::verge().latest().default_web_ui.clone())
+            .filter(|id| !id.is_empty())
+            .ok_or(anyhow!("no default web ui is selected"))?;
+        let source = source(&id)?;
+        if !install_dir(&id)?.join("index.html").exists() {
+            bail!("the web ui `{}` is not installed", source.name);
+        }
+
+        let port = self.ensure_started().await?;
+        open::that(build_url(port, source, controller()?))?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_build_url() {
+    let controller = || ("127.0.0.1".to_string(), 9090, "a b".to_string());
+    assert_eq!(
+        build_url(33338, source("yacd").unwrap(), controller()),
+        "http://127.0.0.1:33338/yacd/?hostname=127.0.0.1&port=9090&secret=a+b"
+    );
+    assert_eq!(
+        build_url(33338, source("metacubexd").unwrap(), controller()),
+        "http://127.0.0.1:33338/metacubexd/#/setup?hostname=127.0.0.1&port=9090&secret=a+b"
+    );
+    assert_eq!(
+        strip_root(Path::new("metacubexd-0123abc/assets/index.js")),
+        Path::new("assets/index.js")
+    );
+}
+
+#[test]
+fn test_web_ui_pins() {
+    // 构建里的 pin 必须对应已知的来源
+    for (id, pin) in PINS.iter() {
+        assert!(source(id).is_ok(), "unknown web ui `{id}`");
+        assert_eq!(pin.commit.len(), 40, "{id}");
+        assert_eq!(
+            hex::decode(&pin.sha256).map(|hash| hash.len()).ok(),
+            Some(32),
+            "{id}"
+        );
+    }
+
+    let pin = WebUiPin {
+        commit: "0".repeat(40),
+        sha256: "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824".into(),
+    };
+    assert!(verify(b"hello", &pin).is_ok());
+    assert!(verify(b"hello!", &pin).is_err());
+}
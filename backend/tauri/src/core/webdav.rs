@@ -0,0 +1,523 @@
+use super::{
+    handle,
+    http::{self, HttpClient},
+    storage::Storage,
+};
+use crate::{
+    config::{Config, HttpPurpose, IProfiles},
+    utils::{dirs, resolve, secrets},
+};
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Cursor, Read, Write},
+    path::PathBuf,
+    time::Duration,
+};
+use sysinfo::{System, SystemExt};
+use url::Url;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+/// the backups are put into this dir of the endpoint
+const REMOTE_DIR: &str = "clash-nyanpasu";
+const BACKUP_PREFIX: &str = "nyanpasu-backup-";
+const MANIFEST: &str = "manifest.json";
+
+/// the layout version of the archive
+const BACKUP_VERSION: u32 = 1;
+
+/// the storage key of the last backup time
+const LAST_BACKUP_KEY: &str = "webdav_last_backup";
+/// the config files at the top of the backup, only these and `profiles/*` are restored
+const CONFIG_FILES: [&str; 3] = ["verge.yaml", "config.yaml", "profiles.yaml"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub app_version: String,
+    pub hostname: Option<String>,
+    pub created_at: i64,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteBackup {
+    pub name: String,
+    pub size: Option<u64>,
+    pub modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+/// what will change after restoring a backup
+#[derive(Debug, Clone, Serialize)]
+pub struct RestorePreview {
+    pub name: String,
+    pub manifest: BackupManifest,
+    pub changes: Vec<FileChange>,
+}
+
+/// the password is kept in the os keyring, not in the `verge.yaml`
+pub fn set_password(username: &str, password: &str) -> Result<()> {
+    secrets::set(&format!("webdav::{username}"), password)
+}
+
+fn get_password(username: &str) -> Result<String> {
+    secrets::get(&format!("webdav::{username}"))?.ok_or(anyhow!("the webdav password is not set"))
+}
+
+/// a minimal webdav client, the files are put into a flat dir of the endpoint
+pub struct WebDav {
+    client: HttpClient,
+    /// ends with `/`
+    base: Url,
+    username: String,
+    password: String,
+}
+
+impl WebDav {
+    pub fn from_config(dir: &str) -> Result<Self> {
+        let (url, username) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (verge.webdav_url.clone(), verge.webdav_username.clone())
+        };
+        let url = url
+            .filter(|url| !url.is_empty())
+            .ok_or(anyhow!("the webdav endpoint is not configured"))?;
+        let username = username.unwrap_or_default();
+        let password = match username.is_empty() {
+            true => String::new(),
+            false => get_password(&username)?,
+        };
+
+        let mut base = Url::parse(&url).context("invalid webdav endpoint")?;
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        let base = base.join(&format!("{dir}/"))?;
+
+        let client = http::client(HttpPurpose::Sync)?;
+
+        Ok(Self {
+            client,
+            base,
+            username,
+            password,
+        })
+    }
+
+    pub(super) fn request(&self, method: Method, name: &str) -> Result<RequestBuilder> {
+        let url = self.base.join(name)?;
+        let builder = self.client.request(method, url);
+        Ok(match self.username.is_empty() {
+            true => builder,
+            false => builder.basic_auth(&self.username, Some(&self.password)),
+        })
+    }
+
+    pub(super) async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        self.client.send(request).await
+    }
+
+    /// `405` means the dir exists
+    pub(super) async fn ensure_dir(&self) -> Result<()> {
+        let response = self
+            .send(self.request(Method::from_bytes(b"MKCOL")?, "")?)
+            .await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            status => bail!("failed to create the webdav dir: {status}"),
+        }
+    }
+
+    pub async fn upload(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        self.ensure_dir().await?;
+        self.send(self.request(Method::PUT, name)?.body(data))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// `None` if not found
+    pub async fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let response = self.send(self.request(Method::GET, name)?).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    async fn download(&self, name: &str) -> Result<Vec<u8>> {
+        let response = self
+            .send(self.request(Method::GET, name)?)
+            .await?
+            .error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        self.send(self.request(Method::DELETE, name)?)
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// the backups sorted by name, the latest first
+    async fn list(&self) -> Result<Vec<RemoteBackup>> {
+        let request = self
+            .request(Method::from_bytes(b"PROPFIND")?, "")?
+            .header("Depth", "1");
+        let response = self.send(request).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let xml = response.error_for_status()?.text().await?;
+
+        let mut backups = parse_propfind(&xml)
+            .into_iter()
+            .filter(|backup| backup.name.starts_with(BACKUP_PREFIX))
+            .collect::<Vec<_>>();
+        backups.sort_by(|a, b| b.name.cmp(&a.name));
+        Ok(backups)
+    }
+}
+
+/// the files in the backup, `relative path -> absolute path`
+fn local_files() -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    for path in [
+        dirs::verge_path()?,
+        dirs::clash_path()?,
+        dirs::profiles_path()?,
+    ] {
+        if path.exists() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            files.insert(name.into_owned(), path);
+        }
+    }
+
+    let profiles_dir = dirs::app_profiles_dir()?;
+    if profiles_dir.exists() {
+        for entry in fs::read_dir(&profiles_dir)?.flatten() {
+            let path = entry.path();
+            if path.is_file() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                files.insert(format!("profiles/{name}"), path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn create_archive() -> Result<(String, Vec<u8>)> {
+    let files = local_files()?;
+    let now = chrono::Local::now();
+    let manifest = BackupManifest {
+        version: BACKUP_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").into(),
+        hostname: System::new().host_name(),
+        created_at: now.timestamp(),
+        files: files.keys().cloned().collect(),
+    };
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+    zip.start_file(MANIFEST, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    for (name, path) in files.iter() {
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(&secrets::read_resolved(path)?)?;
+    }
+    let data = zip.finish()?.into_inner();
+
+    let name = format!("{BACKUP_PREFIX}{}.zip", now.format("%Y%m%d-%H%M%S"));
+    Ok((name, data))
+}
+
+fn read_archive(data: &[u8]) -> Result<(BackupManifest, BTreeMap<String, Vec<u8>>)> {
+    let mut zip = ZipArchive::new(Cursor::new(data)).context("invalid backup archive")?;
+
+    let manifest: BackupManifest = {
+        let file = zip
+            .by_name(MANIFEST)
+            .context("the manifest is missing in the backup")?;
+        serde_json::from_reader(file)?
+    };
+    if manifest.version > BACKUP_VERSION {
+        bail!(
+            "the backup is created by a newer version {}",
+            manifest.app_version
+        );
+    }
+
+    let mut files = BTreeMap::new();
+    for name in manifest.files.iter() {
+        // 密钥、服务配置、内核等其他文件一律不恢复
+        if !is_restorable(name) {
+            bail!("invalid file `{name}` in the backup");
+        }
+        let mut file = zip.by_name(name)?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        files.insert(name.clone(), buf);
+    }
+    Ok((manifest, files))
+}
+
+fn is_restorable(name: &str) -> bool {
+    if CONFIG_FILES.contains(&name) {
+        return true;
+    }
+    match name.strip_prefix("profiles/") {
+        Some(file) => {
+            !file.is_empty() && file != "." && file != ".." && !file.contains(['/', '\\'])
+        }
+        None => false,
+    }
+}
+
+fn diff(files: &BTreeMap<String, Vec<u8>>) -> Result<Vec<FileChange>> {
+    let local = local_files()?;
+
+    let mut changes = files
+        .iter()
+        .map(|(path, data)| {
+            let kind = match local.get(path).map(|path| secrets::read_resolved(path)) {
+                None => ChangeKind::Added,
+                Some(Ok(current)) if &current == data => ChangeKind::Unchanged,
+                Some(_) => ChangeKind::Modified,
+            };
+            FileChange {
+                path: path.clone(),
+                kind,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // 只删除备份里没有的 profile 文件
+    changes.extend(
+        local
+            .keys()
+            .filter(|path| path.starts_with("profiles/") && !files.contains_key(*path))
+            .map(|path| FileChange {
+                path: path.clone(),
+                kind: ChangeKind::Removed,
+            }),
+    );
+    Ok(changes)
+}
+
+/// create a backup and upload it, remove the old ones beyond `webdav_max_backups`
+pub async fn backup() -> Result<String> {
+    let webdav = WebDav::from_config(REMOTE_DIR)?;
+    let (name, data) = create_archive()?;
+    webdav.upload(&name, data).await?;
+    tracing::info!("uploaded the backup `{name}`");
+
+    Storage::global().set_item(LAST_BACKUP_KEY, &chrono::Local::now().timestamp())?;
+
+    let max = { Config::verge().latest().webdav_max_backups };
+    let max = max.unwrap_or(10).max(1);
+    for backup in webdav.list().await?.into_iter().skip(max) {
+        if let Err(err) = webdav.delete(&backup.name).await {
+            tracing::warn!("failed to delete the old backup `{}`: {err}", backup.name);
+        }
+    }
+    Ok(name)
+}
+
+/// called by the `webdav_backup` job
+pub async fn backup_if_due() -> Result<()> {
+    let hours = { Config::verge().latest().webdav_backup_interval };
+    let hours = match hours {
+        Some(hours) if hours > 0 => hours as i64,
+        _ => return Ok(()),
+    };
+
+    let last = Storage::global()
+        .get_item::<i64>(LAST_BACKUP_KEY)?
+        .unwrap_or(0);
+    if chrono::Local::now().timestamp() - last < hours * 60 * 60 {
+        return Ok(());
+    }
+    backup().await.map(|_| ())
+}
+
+pub async fn list_backups() -> Result<Vec<RemoteBackup>> {
+    WebDav::from_config(REMOTE_DIR)?.list().await
+}
+
+pub async fn preview_restore(name: &str) -> Result<RestorePreview> {
+    let data = WebDav::from_config(REMOTE_DIR)?.download(name).await?;
+    let (manifest, files) = read_archive(&data)?;
+    let changes = diff(&files)?;
+    Ok(RestorePreview {
+        name: name.into(),
+        manifest,
+        changes,
+    })
+}
+
+/// overwrite the local files and restart the app to reload them
+pub async fn restore(name: &str) -> Result<()> {
+    let data = WebDav::from_config(REMOTE_DIR)?.download(name).await?;
+    let (_, files) = read_archive(&data)?;
+    let changes = diff(&files)?;
+    super::trash::snapshot_all(super::trash::ProfileOperation::Import)?;
+
+    let home = dirs::app_home_dir()?;
+    fs::create_dir_all(dirs::app_profiles_dir()?)?;
+    for change in changes {
+        let path = home.join(&change.path);
+        match change.kind {
+            ChangeKind::Added | ChangeKind::Modified => {
+                fs::write(&path, &files[&change.path])
+                    .with_context(|| format!("failed to restore `{}`", change.path))?;
+            }
+            ChangeKind::Removed => fs::remove_file(&path)?,
+            ChangeKind::Unchanged => {}
+        }
+    }
+
+    // the profiles are loaded from the storage, import the restored file
+    let profiles = IProfiles::from_file()?;
+    *Config::profiles().draft() = profiles;
+    Config::profiles().apply();
+    Config::profiles().data().save_file()?;
+    tracing::info!("restored the backup `{name}`, restarting");
+
+    tauri::async_runtime::spawn(async {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let app_handle = { handle::Handle::global().app_handle.lock().clone() };
+        if let Some(app_handle) = app_handle {
+            resolve::resolve_reset();
+            app_handle.restart();
+        }
+    });
+    Ok(())
+}
+
+/// the `href` and the props of each `response` in the `multistatus`
+/// the namespace prefix differs between the servers
+fn parse_propfind(xml: &str) -> Vec<RemoteBackup> {
+    elements(xml, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let href = elements(response, "href").into_iter().next()?;
+            let href = percent_encoding::percent_decode_str(href.trim()).decode_utf8_lossy();
+            let name = href.trim_end_matches('/').rsplit('/').next()?.to_string();
+
+            let size = elements(response, "getcontentlength")
+                .into_iter()
+                .next()
+                .and_then(|size| size.trim().parse().ok());
+            let modified = elements(response, "getlastmodified")
+                .into_iter()
+                .next()
+                .map(|modified| modified.trim().to_string());
+
+            Some(RemoteBackup {
+                name,
+                size,
+                modified,
+            })
+        })
+        .collect()
+}
+
+/// the inner text of the elements with the local name
+fn elements<'a>(xml: &'a str, local_name: &str) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        if tag.starts_with('/') || tag.ends_with('/') {
+            continue;
+        }
+        if name.rsplit(':').next() != Some(local_name) {
+            continue;
+        }
+
+        let close = format!("</{name}>");
+        if let Some(close_at) = rest.find(&close) {
+            result.push(&rest[..close_at]);
+            rest = &rest[close_at + close.len()..];
+        }
+    }
+    result
+}
+
+#[test]
+fn test_is_restorable() {
+    assert!(is_restorable("verge.yaml"));
+    assert!(is_restorable("profiles.yaml"));
+    assert!(is_restorable("profiles/rXyZ.yaml"));
+
+    assert!(!is_restorable("secrets.json"));
+    assert!(!is_restorable("storage.db/CURRENT"));
+    assert!(!is_restorable("service/config.json"));
+    assert!(!is_restorable("profiles/"));
+    assert!(!is_restorable("profiles/.."));
+    assert!(!is_restorable("profiles/a/b.yaml"));
+    assert!(!is_restorable("profiles/..\\verge.yaml"));
+    assert!(!is_restorable("../verge.yaml"));
+    assert!(!is_restorable("/etc/passwd"));
+}
+
+#[test]
+fn test_parse_propfind() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:multistatus xmlns:D="DAV:">
+  <D:response>
+    <D:href>/dav/clash-nyanpasu/</D:href>
+    <D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>/dav/clash-nyanpasu/nyanpasu-backup-20240101-120000.zip</D:href>
+    <D:propstat>
+      <D:prop>
+        <D:getcontentlength>2048</D:getcontentlength>
+        <D:getlastmodified>Mon, 01 Jan 2024 12:00:00 GMT</D:getlastmodified>
+      </D:prop>
+    </D:propstat>
+  </D:response>
+</D:multistatus>"#;
+
+    let list = parse_propfind(xml);
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].name, "clash-nyanpasu");
+    assert_eq!(list[1].name, "nyanpasu-backup-20240101-120000.zip");
+    assert_eq!(list[1].size, Some(2048));
+    assert_eq!(
+        list[1].modified.as_deref(),
+        Some("Mon, 01 Jan 2024 12:00:00 GMT")
+    );
+}
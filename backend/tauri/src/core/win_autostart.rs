@@ -0,0 +1,119 @@
+#![cfg(target_os = "windows")]
+
+use crate::utils::dirs;
+use anyhow::{anyhow, bail, Result};
+use deelevate::{PrivilegeLevel, Token};
+use runas::Command as RunasCommand;
+use std::{os::windows::process::CommandExt, path::PathBuf, process::Command as StdCommand};
+
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// `%APPDATA%\Microsoft\Windows\Start Menu\Programs\Startup\{app_name}.lnk`
+fn shortcut_path(app_name: &str) -> Result<PathBuf> {
+    let appdata = std::env::var_os("APPDATA").ok_or(anyhow!("failed to get APPDATA"))?;
+
+    Ok(PathBuf::from(appdata)
+        .join("Microsoft")
+        .join("Windows")
+        .join("Start Menu")
+        .join("Programs")
+        .join("Startup")
+        .join(format!("{app_name}.lnk")))
+}
+
+/// create or remove the shortcut in the Startup folder
+pub fn set_startup_folder(
+    app_name: &str,
+    app_exe: &PathBuf,
+    args: &str,
+    enable: bool,
+) -> Result<()> {
+    let shortcut = shortcut_path(app_name)?;
+
+    if !enable {
+        if shortcut.exists() {
+            std::fs::remove_file(shortcut)?;
+        }
+        return Ok(());
+    }
+
+    let work_dir = app_exe
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_default();
+
+    // 单引号在 powershell 中需要转义
+    let escape = |s: &str| s.replace('\'', "''");
+    let script = format!(
+        "$s = (New-Object -ComObject WScript.Shell).CreateShortcut('{}'); $s.TargetPath = '{}'; $s.Arguments = '{}'; $s.WorkingDirectory = '{}'; $s.Save()",
+        escape(dirs::path_to_str(&shortcut)?),
+        escape(dirs::path_to_str(app_exe)?),
+        escape(args),
+        escape(dirs::path_to_str(&work_dir)?),
+    );
+
+    let status = StdCommand::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()?;
+
+    if !status.success() {
+        bail!("failed to create the startup shortcut");
+    }
+    Ok(())
+}
+
+pub fn is_startup_folder_enabled(app_name: &str) -> bool {
+    shortcut_path(app_name).map_or(false, |p| p.exists())
+}
+
+pub fn is_scheduled_task_enabled(app_name: &str) -> bool {
+    StdCommand::new("schtasks")
+        .args(["/Query", "/TN", app_name])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_or(false, |output| output.status.success())
+}
+
+/// create or remove the logon task which runs with the highest privileges
+/// 需要管理员权限，该函数应该在线程中执行，避免UAC弹窗阻塞主线程
+pub fn set_scheduled_task(
+    app_name: &str,
+    app_exe: &PathBuf,
+    args: &str,
+    enable: bool,
+) -> Result<()> {
+    if !enable && !is_scheduled_task_enabled(app_name) {
+        return Ok(());
+    }
+
+    let command = format!("\"{}\" {args}", dirs::path_to_str(app_exe)?);
+    let args = match enable {
+        true => vec![
+            "/Create", "/TN", app_name, "/TR", &command, "/SC", "ONLOGON", "/RL", "HIGHEST", "/F",
+        ],
+        false => vec!["/Delete", "/TN", app_name, "/F"],
+    };
+
+    let token = Token::with_current_process()?;
+    let level = token.privilege_level()?;
+
+    let status = match level {
+        PrivilegeLevel::NotPrivileged => RunasCommand::new("schtasks")
+            .args(&args)
+            .show(false)
+            .status()?,
+        _ => StdCommand::new("schtasks")
+            .args(&args)
+            .creation_flags(CREATE_NO_WINDOW)
+            .status()?,
+    };
+
+    if !status.success() {
+        bail!(
+            "failed to update the scheduled task with status {}",
+            status.code().unwrap_or(-1)
+        );
+    }
+    Ok(())
+}
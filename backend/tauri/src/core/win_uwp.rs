@@ -1,10 +1,42 @@
 #![cfg(target_os = "windows")]
+//! the loopback exemption of the uwp apps, the same as `CheckNetIsolation LoopbackExempt`
+//!
+//! the store apps are not allowed to connect to `127.0.0.1` by default, so the local proxy
+//! doesn't work for them. listing the apps needs no privilege, but changing the exemptions
+//! does, the app runs itself elevated with `--uwp-loopback <file>` in that case.
 
 use crate::utils::dirs;
 use anyhow::{bail, Result};
 use deelevate::{PrivilegeLevel, Token};
 use runas::Command as RunasCommand;
-use std::process::Command as StdCommand;
+use serde::Serialize;
+use std::{collections::BTreeSet, ffi::c_void, process::Command as StdCommand, ptr};
+use windows_sys::Win32::{
+    NetworkManagement::WindowsFirewall::{
+        NetworkIsolationEnumAppContainers, NetworkIsolationFreeAppContainers,
+        NetworkIsolationGetAppContainerConfig, NetworkIsolationSetAppContainerConfig,
+        INET_FIREWALL_APP_CONTAINER, NETISO_FLAG_FORCE_COMPUTE_BINARIES,
+    },
+    Security::{
+        Authorization::{ConvertSidToStringSidW, ConvertStringSidToSidW},
+        SID_AND_ATTRIBUTES,
+    },
+    System::Memory::LocalFree,
+    UI::Shell::SHLoadIndirectString,
+};
+
+/// the hidden arg of the elevated process, followed by the json file of the exempted sids
+pub const LOOPBACK_ARG: &str = "--uwp-loopback";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UwpApp {
+    /// the app container sid, `S-1-15-2-...`
+    pub sid: String,
+    pub name: String,
+    pub display_name: String,
+    pub package: String,
+    pub exempted: bool,
+}
 
 pub async fn invoke_uwptools() -> Result<()> {
     let binary_path = dirs::service_path()?;
@@ -24,3 +56,193 @@ pub async fn invoke_uwptools() -> Result<()> {
 
     Ok(())
 }
+
+/// all the app containers with their exemption states, sorted by the display name
+pub fn list_apps() -> Result<Vec<UwpApp>> {
+    let exempted = exempted_sids()?;
+
+    let mut apps = unsafe {
+        let mut count = 0u32;
+        let mut containers: *mut INET_FIREWALL_APP_CONTAINER = ptr::null_mut();
+        let code = NetworkIsolationEnumAppContainers(
+            NETISO_FLAG_FORCE_COMPUTE_BINARIES as u32,
+            &mut count,
+            &mut containers,
+        );
+        if code != 0 {
+            bail!("failed to enumerate the app containers: {code}");
+        }
+
+        let mut apps = Vec::with_capacity(count as usize);
+        for i in 0..count as usize {
+            let container = &*containers.add(i);
+            let Some(sid) = sid_to_string(container.appContainerSid as *mut c_void) else {
+                continue;
+            };
+            let name = from_wide(container.appContainerName);
+            let display_name = match from_wide(container.displayName) {
+                name if name.starts_with("@{") => resolve_indirect(&name),
+                name => name,
+            };
+            apps.push(UwpApp {
+                exempted: exempted.contains(&sid),
+                sid,
+                display_name: if display_name.is_empty() {
+                    name.clone()
+                } else {
+                    display_name
+                },
+                name,
+                package: from_wide(container.packageFullName),
+            });
+        }
+        NetworkIsolationFreeAppContainers(containers);
+        apps
+    };
+
+    apps.sort_by_key(|app| app.display_name.to_lowercase());
+    Ok(apps)
+}
+
+/// exempt or restore the apps, elevated if needed
+pub async fn set_loopback(sids: Vec<String>, exempt: bool) -> Result<()> {
+    let mut exempted = exempted_sids()?;
+    for sid in sids {
+        match exempt {
+            true => exempted.insert(sid),
+            false => exempted.remove(&sid),
+        };
+    }
+
+    let token = Token::with_current_process()?;
+    if !matches!(token.privilege_level()?, PrivilegeLevel::NotPrivileged) {
+        return set_exempted(&exempted);
+    }
+
+    // 提权后整体写入，只弹一次 uac
+    let file = dirs::app_home_dir()?.join("uwp-loopback.json");
+    std::fs::write(&file, serde_json::to_string(&exempted)?)?;
+    let exe = std::env::current_exe()?;
+    let arg = file.clone();
+    let status = tauri::async_runtime::spawn_blocking(move || {
+        RunasCommand::new(exe)
+            .arg(LOOPBACK_ARG)
+            .arg(arg)
+            .show(false)
+            .status()
+    })
+    .await?;
+    let _ = std::fs::remove_file(&file);
+
+    match status?.code() {
+        Some(0) => Ok(()),
+        code => bail!("failed to set the loopback exemption, exit code {code:?}"),
+    }
+}
+
+/// the elevated process, return the exit code
+/// `None` means launching the app as usual
+pub fn run() -> Option<i32> {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some(LOOPBACK_ARG) {
+        return None;
+    }
+
+    let result = args
+        .next()
+        .ok_or(anyhow::anyhow!("usage: {LOOPBACK_ARG} <file>"))
+        .and_then(|file| Ok(std::fs::read_to_string(file)?))
+        .and_then(|content| Ok(serde_json::from_str::<BTreeSet<String>>(&content)?))
+        .and_then(|sids| set_exempted(&sids));
+    match result {
+        Ok(_) => Some(0),
+        Err(err) => {
+            eprintln!("error: {err}");
+            Some(1)
+        }
+    }
+}
+
+fn exempted_sids() -> Result<BTreeSet<String>> {
+    unsafe {
+        let mut count = 0u32;
+        let mut sids: *mut SID_AND_ATTRIBUTES = ptr::null_mut();
+        let code = NetworkIsolationGetAppContainerConfig(&mut count, &mut sids);
+        if code != 0 {
+            bail!("failed to get the loopback exemptions: {code}");
+        }
+        // 文档没说怎么释放，和 CheckNetIsolation 一样交给进程
+        Ok((0..count as usize)
+            .filter_map(|i| sid_to_string((*sids.add(i)).Sid))
+            .collect())
+    }
+}
+
+/// replace the whole exemption list, needs the administrator
+fn set_exempted(sids: &BTreeSet<String>) -> Result<()> {
+    unsafe {
+        let mut attributes = Vec::with_capacity(sids.len());
+        for sid in sids {
+            let wide = to_wide(sid);
+            let mut psid: *mut c_void = ptr::null_mut();
+            if ConvertStringSidToSidW(wide.as_ptr(), &mut psid) == 0 {
+                tracing::warn!("skip the invalid sid {sid}");
+                continue;
+            }
+            attributes.push(SID_AND_ATTRIBUTES {
+                Sid: psid,
+                Attributes: 0,
+            });
+        }
+
+        let code =
+            NetworkIsolationSetAppContainerConfig(attributes.len() as u32, attributes.as_ptr());
+        for attribute in &attributes {
+            LocalFree(attribute.Sid as isize);
+        }
+        if code != 0 {
+            bail!("failed to set the loopback exemptions: {code}");
+        }
+    }
+    Ok(())
+}
+
+unsafe fn sid_to_string(sid: *mut c_void) -> Option<String> {
+    let mut string: *mut u16 = ptr::null_mut();
+    if sid.is_null() || ConvertSidToStringSidW(sid, &mut string) == 0 {
+        return None;
+    }
+    let sid = from_wide(string);
+    LocalFree(string as isize);
+    Some(sid)
+}
+
+/// `@{Package?ms-resource://...}` of the display names
+fn resolve_indirect(source: &str) -> String {
+    let wide = to_wide(source);
+    let mut buffer = [0u16; 512];
+    let code = unsafe {
+        SHLoadIndirectString(
+            wide.as_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+            ptr::null(),
+        )
+    };
+    match code {
+        0 => unsafe { from_wide(buffer.as_mut_ptr()) },
+        _ => String::new(),
+    }
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+unsafe fn from_wide(p: *mut u16) -> String {
+    if p.is_null() {
+        return String::new();
+    }
+    let len = (0..).take_while(|&i| *p.add(i) != 0).count();
+    String::from_utf16_lossy(std::slice::from_raw_parts(p, len))
+}
@@ -0,0 +1,186 @@
+use crate::config::{rule_targets, AppRule, ClashCore, CoreFeature};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+
+/// put the `PROCESS-NAME` rules of the apps in front of the rules
+/// they are more specific than the rules of the profile and the user
+pub fn use_app_rules(
+    mut config: Mapping,
+    rules: Option<Vec<AppRule>>,
+    core: &ClashCore,
+) -> Mapping {
+    let rules = rules
+        .into_iter()
+        .flatten()
+        .filter(|rule| rule.enable)
+        .collect::<Vec<_>>();
+    if rules.is_empty() {
+        return config;
+    }
+    if !core.supports(CoreFeature::ProcessRules) {
+        tracing::warn!("the app rules are skipped, `{core}` does not support the process rules");
+        return config;
+    }
+
+    let targets = rule_targets(&config);
+    let rules_key = Value::from("rules");
+    let origin = config
+        .get(&rules_key)
+        .and_then(|v| v.as_sequence().cloned())
+        .unwrap_or_default();
+
+    let mut result = vec![];
+    for rule in rules {
+        match rule.validate(Some(&targets)) {
+            Ok(()) => result.push(Value::from(rule.to_rule())),
+            Err(err) => tracing::warn!("skip the app rule `{}`: {err}", rule.process),
+        }
+    }
+    result.extend(origin);
+
+    config.insert(rules_key, Value::from(result));
+    config
+}
+
+/// the process names of the app and the core, with `.exe` on windows
+fn self_processes(core: &ClashCore) -> Vec<String> {
+    let app = std::env::current_exe()
+        .ok()
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| format!("clash-nyanpasu{}", std::env::consts::EXE_SUFFIX));
+    let core = format!("{core}{}", std::env::consts::EXE_SUFFIX);
+    vec![app, core]
+}
+
+fn self_bypass_rules(core: &ClashCore) -> Vec<String> {
+    self_processes(core)
+        .into_iter()
+        .map(|process| format!("PROCESS-NAME,{process},DIRECT"))
+        .collect()
+}
+
+/// the rules put in by `use_app_rules` and `use_self_bypass`, they only make sense on this device
+pub fn injected_rules(rules: Option<&Vec<AppRule>>, core: &ClashCore) -> HashSet<String> {
+    let mut injected = self_bypass_rules(core).into_iter().collect::<HashSet<_>>();
+    injected.extend(
+        rules
+            .into_iter()
+            .flatten()
+            .filter(|rule| rule.enable)
+            .map(AppRule::to_rule),
+    );
+    injected
+}
+
+/// in the tun mode the traffic of the app itself is captured too,
+/// the updater and the subscriptions could loop through the proxy or fail when the proxy is broken
+/// it is the same `PROCESS-NAME` rules on every platform, windows included
+pub fn use_self_bypass(mut config: Mapping, enable: bool, core: &ClashCore) -> Mapping {
+    if !enable || !core.supports(CoreFeature::ProcessRules) {
+        return config;
+    }
+
+    let rules_key = Value::from("rules");
+    let origin = config
+        .get(&rules_key)
+        .and_then(|v| v.as_sequence().cloned())
+        .unwrap_or_default();
+
+    let mut result = self_bypass_rules(core)
+        .into_iter()
+        .map(Value::from)
+        .collect::<Vec<_>>();
+    result.extend(origin);
+
+    config.insert(rules_key, Value::from(result));
+    config
+}
+
+/// the core skips the rules in the global mode, the bypass is lost there
+/// only warn about it, the bypass is on by default and the global mode was allowed before.
+/// the rules are kept in the config, they work again after switching back to the rule mode
+pub fn warn_self_bypass(
+    mode: Option<&str>,
+    enable_tun: bool,
+    self_bypass: bool,
+    core: &ClashCore,
+) -> bool {
+    let global = mode.map_or(false, |mode| mode.eq_ignore_ascii_case("global"));
+    let lost = global && enable_tun && self_bypass && core.supports(CoreFeature::ProcessRules);
+    if lost {
+        tracing::warn!(
+            "the global mode routes the app itself through the tun, the self bypass is skipped"
+        );
+    }
+    lost
+}
+
+#[test]
+fn test_use_app_rules() {
+    let config = serde_yaml::from_str::<Mapping>(
+        r#"
+proxy-groups:
+  - name: Proxy
+rules:
+  - MATCH,Proxy
+"#,
+    )
+    .unwrap();
+    let rule = |process: &str, target: &str| AppRule {
+        id: String::new(),
+        process: process.into(),
+        target: target.into(),
+        enable: true,
+    };
+
+    let rules = vec![rule("chrome.exe", "Proxy"), rule("qq.exe", "Missing")];
+    let result = use_app_rules(config.clone(), Some(rules.clone()), &ClashCore::Mihomo);
+    assert_eq!(
+        result["rules"],
+        serde_yaml::from_str::<Value>("[PROCESS-NAME,chrome.exe,Proxy, MATCH,Proxy]").unwrap()
+    );
+
+    let result = use_app_rules(config.clone(), Some(rules), &ClashCore::ClashRs);
+    assert_eq!(result, config);
+}
+
+#[test]
+fn test_use_self_bypass() {
+    let config = serde_yaml::from_str::<Mapping>("rules: [MATCH,Proxy]").unwrap();
+
+    let result = use_self_bypass(config.clone(), true, &ClashCore::Mihomo);
+    let rules = result["rules"].as_sequence().unwrap();
+    assert_eq!(rules.len(), 3);
+    assert_eq!(
+        rules[1],
+        Value::from(format!(
+            "PROCESS-NAME,mihomo{},DIRECT",
+            std::env::consts::EXE_SUFFIX
+        ))
+    );
+    assert_eq!(rules[2], Value::from("MATCH,Proxy"));
+
+    assert_eq!(
+        use_self_bypass(config.clone(), false, &ClashCore::Mihomo),
+        config
+    );
+}
+
+#[test]
+fn test_warn_self_bypass() {
+    let core = ClashCore::Mihomo;
+    assert!(warn_self_bypass(Some("global"), true, true, &core));
+    assert!(warn_self_bypass(Some("Global"), true, true, &core));
+    assert!(!warn_self_bypass(Some("rule"), true, true, &core));
+    assert!(!warn_self_bypass(Some("global"), false, true, &core));
+    assert!(!warn_self_bypass(Some("global"), true, false, &core));
+    assert!(!warn_self_bypass(
+        Some("global"),
+        true,
+        true,
+        &ClashCore::ClashRs
+    ));
+}
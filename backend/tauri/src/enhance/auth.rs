@@ -0,0 +1,22 @@
+use crate::config::{ClashCore, CoreFeature, InboundAuth};
+use serde_yaml::{Mapping, Value};
+
+/// override the `authentication` of the profile when the auth is enabled
+pub fn use_auth(mut config: Mapping, auth: Option<InboundAuth>, core: &ClashCore) -> Mapping {
+    let Some(auth) = auth.filter(|auth| auth.enable) else {
+        return config;
+    };
+    let credentials = auth.credentials();
+    if credentials.is_empty() {
+        return config;
+    }
+
+    config.insert("authentication".into(), Value::from(credentials));
+    if !auth.skip_auth_prefixes.is_empty() && core.supports(CoreFeature::SkipAuthPrefixes) {
+        config.insert(
+            "skip-auth-prefixes".into(),
+            Value::from(auth.skip_auth_prefixes),
+        );
+    }
+    config
+}
@@ -1,5 +1,5 @@
 use crate::{
-    config::{ClashCore, PrfItem},
+    config::{ClashCore, PrfItem, RemoteScript},
     utils::{dirs, help},
 };
 use serde_yaml::Mapping;
@@ -37,10 +37,18 @@ impl From<&PrfItem> for Option<ChainItem> {
         }
 
         match itype {
-            "script" => Some(ChainItem {
-                uid,
-                data: ChainType::Script(fs::read_to_string(path).ok()?),
-            }),
+            "script" => {
+                let data = fs::read_to_string(path).ok()?;
+                // 远程脚本只运行确认过的内容
+                if RemoteScript::is_remote(item) && !RemoteScript::is_approved(&uid, &data) {
+                    tracing::warn!("skip the remote script `{uid}` not approved yet");
+                    return None;
+                }
+                Some(ChainItem {
+                    uid,
+                    data: ChainType::Script(data),
+                })
+            }
             "merge" => Some(ChainItem {
                 uid,
                 data: ChainType::Merge(help::read_merge_mapping(&path).ok()?),
@@ -0,0 +1,149 @@
+use crate::config::{DnsOverride, HostsEntry};
+use serde_yaml::{Mapping, Value};
+
+/// apply the dns override on the `dns` and the `hosts` of the config
+pub fn use_dns(mut config: Mapping, dns: Option<DnsOverride>) -> Mapping {
+    let Some(dns) = dns else {
+        return config;
+    };
+
+    let dns_key = Value::from("dns");
+    let mut dns_val = config
+        .get(&dns_key)
+        .and_then(|val| val.as_mapping().cloned())
+        .unwrap_or_default();
+
+    let mut revise = |key: &str, val: Value| {
+        dns_val.insert(Value::from(key), val);
+    };
+    if let Some(enable) = dns.enable {
+        revise("enable", enable.into());
+    }
+    if let Some(ipv6) = dns.ipv6 {
+        revise("ipv6", ipv6.into());
+    }
+    if let Some(mode) = dns.enhanced_mode {
+        revise("enhanced-mode", mode.into());
+    }
+    if let Some(list) = dns.nameserver {
+        revise("nameserver", list.into());
+    }
+    if let Some(list) = dns.default_nameserver {
+        revise("default-nameserver", list.into());
+    }
+    if let Some(list) = dns.fallback {
+        revise("fallback", list.into());
+    }
+    if let Some(list) = dns.fake_ip_filter {
+        revise("fake-ip-filter", list.into());
+    }
+
+    if let Some(hosts) = dns.hosts.filter(|hosts| !hosts.is_empty()) {
+        let hosts_key = Value::from("hosts");
+        let mut hosts_val = config
+            .get(&hosts_key)
+            .and_then(|val| val.as_mapping().cloned())
+            .unwrap_or_default();
+        for (domain, ip) in hosts {
+            hosts_val.insert(domain.into(), ip.into());
+        }
+        config.insert(hosts_key, hosts_val.into());
+        // 否则 hosts 不会生效
+        dns_val.insert("use-hosts".into(), true.into());
+    }
+
+    config.insert(dns_key, dns_val.into());
+    config
+}
+
+/// merge the enabled entries into the `hosts`, a domain with several ips becomes a list
+pub fn use_hosts(mut config: Mapping, entries: Option<Vec<HostsEntry>>) -> Mapping {
+    let mut merged: Vec<(String, Vec<String>)> = vec![];
+    for entry in entries.into_iter().flatten().filter(|e| e.enable) {
+        match merged
+            .iter_mut()
+            .find(|(domain, _)| *domain == entry.domain)
+        {
+            Some((_, ips)) if !ips.contains(&entry.ip) => ips.push(entry.ip),
+            Some(_) => {}
+            None => merged.push((entry.domain, vec![entry.ip])),
+        }
+    }
+    if merged.is_empty() {
+        return config;
+    }
+
+    let hosts_key = Value::from("hosts");
+    let mut hosts_val = config
+        .get(&hosts_key)
+        .and_then(|val| val.as_mapping().cloned())
+        .unwrap_or_default();
+    for (domain, mut ips) in merged {
+        let ips = match ips.len() {
+            1 => Value::from(ips.remove(0)),
+            _ => Value::from(ips),
+        };
+        hosts_val.insert(domain.into(), ips);
+    }
+    config.insert(hosts_key, hosts_val.into());
+
+    let dns_key = Value::from("dns");
+    let mut dns_val = config
+        .get(&dns_key)
+        .and_then(|val| val.as_mapping().cloned())
+        .unwrap_or_default();
+    dns_val.insert("use-hosts".into(), true.into());
+    config.insert(dns_key, dns_val.into());
+    config
+}
+
+#[test]
+fn test_use_dns() {
+    let config = serde_yaml::from_str::<Mapping>(
+        r#"
+dns:
+  enable: true
+  nameserver: [8.8.8.8]
+  fallback: [1.1.1.1]
+hosts:
+  a.com: 1.1.1.1
+"#,
+    )
+    .unwrap();
+
+    let global = DnsOverride {
+        nameserver: Some(vec!["223.5.5.5".into()]),
+        hosts: Some([("b.com".to_string(), "2.2.2.2".to_string())].into()),
+        ..DnsOverride::default()
+    };
+    let profile = DnsOverride {
+        nameserver: Some(vec!["119.29.29.29".into()]),
+        enhanced_mode: Some("fake-ip".into()),
+        ..DnsOverride::default()
+    };
+    let config = use_dns(config, DnsOverride::merge(Some(global), Some(profile)));
+
+    let dns = config["dns"].as_mapping().unwrap();
+    assert_eq!(dns["nameserver"], Value::from(vec!["119.29.29.29"]));
+    assert_eq!(dns["fallback"], Value::from(vec!["1.1.1.1"]));
+    assert_eq!(dns["enhanced-mode"], Value::from("fake-ip"));
+    assert_eq!(dns["use-hosts"], Value::from(true));
+    assert_eq!(config["hosts"].as_mapping().unwrap().len(), 2);
+
+    let entry = |domain: &str, ip: &str, enable: bool| HostsEntry {
+        domain: domain.into(),
+        ip: ip.into(),
+        enable,
+    };
+    let config = use_hosts(
+        config,
+        Some(vec![
+            entry("a.com", "3.3.3.3", true),
+            entry("a.com", "4.4.4.4", true),
+            entry("c.com", "5.5.5.5", false),
+        ]),
+    );
+    let hosts = config["hosts"].as_mapping().unwrap();
+    assert_eq!(hosts["a.com"], Value::from(vec!["3.3.3.3", "4.4.4.4"]));
+    assert!(!hosts.contains_key("c.com"));
+}
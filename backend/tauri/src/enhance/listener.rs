@@ -0,0 +1,100 @@
+use crate::config::{
+    check_listener_ports, reserved_ports, rule_targets, ClashCore, CoreFeature, InboundListener,
+    EXIT_PROBE,
+};
+use serde_yaml::{Mapping, Value};
+
+/// append the enabled listeners to the `listeners` of the config
+/// the listeners that are invalid or conflict with the config are skipped
+pub fn use_listeners(
+    mut config: Mapping,
+    listeners: Option<Vec<InboundListener>>,
+    core: &ClashCore,
+) -> Mapping {
+    let listeners = listeners
+        .into_iter()
+        .flatten()
+        .filter(|listener| listener.enable)
+        .collect::<Vec<_>>();
+    if listeners.is_empty() {
+        return config;
+    }
+    if !core.supports(CoreFeature::Listeners) {
+        tracing::warn!("the listeners are skipped, `{core}` does not support them");
+        return config;
+    }
+
+    let targets = rule_targets(&config);
+    let reserved = reserved_ports(&config);
+    let mut list = config
+        .get("listeners")
+        .and_then(|v| v.as_sequence().cloned())
+        .unwrap_or_default();
+    let mut accepted: Vec<InboundListener> = vec![];
+
+    for listener in listeners {
+        let checked = listener.validate(Some(&targets)).and_then(|_| {
+            let mut all = accepted.clone();
+            all.push(listener.clone());
+            check_listener_ports(&all, &reserved)
+        });
+        if let Err(err) = checked {
+            tracing::warn!("skip the listener `{}`: {err}", listener.name);
+            continue;
+        }
+        list.push(listener.to_value());
+        accepted.push(listener);
+    }
+
+    config.insert("listeners".into(), Value::from(list));
+    config
+}
+
+/// a hidden select group of all the proxies, and a local http listener using it,
+/// the app selects a node in the group and queries the exit ip through the listener
+pub fn use_exit_probe(mut config: Mapping, port: Option<u16>, core: &ClashCore) -> Mapping {
+    let Some(port) = port else {
+        return config;
+    };
+    if !core.supports(CoreFeature::Listeners) {
+        tracing::warn!("the exit probe is skipped, `{core}` does not support the listeners");
+        return config;
+    }
+    if let Some(key) = reserved_ports(&config).get(&port) {
+        tracing::warn!("the exit probe is skipped, the port {port} is used by `{key}`");
+        return config;
+    }
+
+    let mut listener = Mapping::new();
+    listener.insert("name".into(), EXIT_PROBE.into());
+    listener.insert("type".into(), "http".into());
+    listener.insert("port".into(), port.into());
+    listener.insert("listen".into(), "127.0.0.1".into());
+    listener.insert("proxy".into(), EXIT_PROBE.into());
+    let mut listeners = config
+        .get("listeners")
+        .and_then(|v| v.as_sequence().cloned())
+        .unwrap_or_default();
+    let used = listeners
+        .iter()
+        .any(|item| item.get("port").and_then(Value::as_u64) == Some(port as u64));
+    if used {
+        tracing::warn!("the exit probe is skipped, the port {port} is used by a listener");
+        return config;
+    }
+    listeners.push(Value::from(listener));
+    config.insert("listeners".into(), Value::from(listeners));
+
+    let mut group = Mapping::new();
+    group.insert("name".into(), EXIT_PROBE.into());
+    group.insert("type".into(), "select".into());
+    group.insert("include-all".into(), true.into());
+    group.insert("hidden".into(), true.into());
+    let mut groups = config
+        .get("proxy-groups")
+        .and_then(|v| v.as_sequence().cloned())
+        .unwrap_or_default();
+    groups.push(Value::from(group));
+    config.insert("proxy-groups".into(), Value::from(groups));
+    config
+}
@@ -1,16 +1,38 @@
+mod app_rules;
+mod auth;
 mod chain;
+mod dns;
 mod field;
+mod listener;
 mod merge;
+mod proxy_chain;
+mod rules;
+mod runner;
 mod script;
+mod sniffer;
+mod store;
 mod tun;
+mod tunnel;
 
+pub use self::app_rules::{injected_rules, warn_self_bypass};
 pub(self) use self::field::*;
+pub use self::runner::*;
 
+use self::app_rules::*;
+use self::auth::*;
 use self::chain::*;
+use self::dns::*;
+use self::listener::*;
 use self::merge::*;
+use self::proxy_chain::*;
+use self::rules::*;
 use self::script::*;
+use self::sniffer::*;
+use self::store::*;
 use self::tun::*;
-use crate::config::Config;
+use self::tunnel::*;
+use crate::config::{check_config_keys, check_overrides, Config, DnsOverride, EXIT_PROBE_PORT};
+use anyhow::{anyhow, Context, Result};
 use serde_yaml::Mapping;
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -23,19 +45,54 @@ pub fn enhance() -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
     // config.yaml 的配置
     let clash_config = { Config::clash().latest().0.clone() };
 
-    let (clash_core, enable_tun, enable_builtin, enable_filter) = {
+    let (clash_core, enable_tun, self_bypass, enable_builtin, enable_filter) = {
         let verge = Config::verge();
         let verge = verge.latest();
         (
             verge.clash_core.clone(),
             verge.enable_tun_mode.clone().unwrap_or(false),
+            verge.enable_self_bypass.unwrap_or(true),
             verge.enable_builtin_enhanced.clone().unwrap_or(true),
             verge.enable_clash_fields.clone().unwrap_or(true),
         )
     };
 
+    // 覆写层，在订阅和 merge 之后应用
+    let (
+        global_dns,
+        hosts,
+        user_rules,
+        app_rules,
+        proxy_chains,
+        tunnels,
+        listeners,
+        inbound_auth,
+        sniffer,
+        store,
+        exit_probe,
+    ) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.dns_override.clone(),
+            verge.hosts_entries.clone(),
+            verge.user_rules.clone(),
+            verge.app_rules.clone(),
+            verge.proxy_chains.clone(),
+            verge.tunnels.clone(),
+            verge.listeners.clone(),
+            verge.inbound_auth.clone(),
+            verge.sniffer_override.clone(),
+            verge.store_override.clone(),
+            verge
+                .enable_exit_probe
+                .unwrap_or(false)
+                .then(|| verge.exit_probe_port.unwrap_or(EXIT_PROBE_PORT)),
+        )
+    };
+
     // 从profiles里拿东西
-    let (mut config, chain, valid) = {
+    let (mut config, chain, valid, profile_dns) = {
         let profiles = Config::profiles();
         let profiles = profiles.latest();
 
@@ -51,8 +108,9 @@ pub fn enhance() -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
         };
 
         let valid = profiles.valid.clone().unwrap_or(vec![]);
+        let profile_dns = profiles.current_dns_override();
 
-        (current, chain, valid)
+        (current, chain, valid, profile_dns)
     };
 
     let mut result_map = HashMap::new(); // 保存脚本日志
@@ -98,7 +156,7 @@ pub fn enhance() -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
             .filter(|(s, _)| s.is_support(clash_core.as_ref()))
             .map(|(_, c)| c)
             .for_each(|item| {
-                log::debug!(target: "app", "run builtin script {}", item.uid);
+                tracing::debug!("run builtin script {}", item.uid);
 
                 match item.data {
                     ChainType::Script(script) => match use_script(script, config.to_owned()) {
@@ -106,7 +164,7 @@ pub fn enhance() -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
                             config = use_filter(res_config, &clash_fields, enable_filter);
                         }
                         Err(err) => {
-                            log::error!(target: "app", "builtin script error `{err}`");
+                            tracing::error!("builtin script error `{err}`");
                         }
                     },
                     _ => {}
@@ -115,7 +173,33 @@ pub fn enhance() -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
     }
 
     config = use_filter(config, &clash_fields, enable_filter);
+    let clash_core = clash_core.unwrap_or_default();
+    config = use_proxy_chains(config, proxy_chains, &clash_core);
+    config = use_tunnels(config, tunnels, &clash_core);
+    config = use_listeners(config, listeners, &clash_core);
+    config = use_exit_probe(config, exit_probe, &clash_core);
+    config = use_auth(config, inbound_auth, &clash_core);
+    config = use_rules(config, user_rules);
+    config = use_app_rules(config, app_rules, &clash_core);
+    config = use_self_bypass(config, enable_tun && self_bypass, &clash_core);
+    // dns 覆写在 tun 之前，tun 只补充缺省值
+    config = use_dns(config, DnsOverride::merge(global_dns, profile_dns));
+    config = use_hosts(config, hosts);
+    config = use_sniffer(config, sniffer, &clash_core);
+    config = use_store(config, store);
     config = use_tun(config, enable_tun);
+
+    // 切换内核后不支持的覆写会被跳过，这里只提示
+    let warnings = {
+        let verge = Config::verge();
+        let mut warnings = check_overrides(&verge.latest(), &clash_core);
+        warnings.extend(check_config_keys(&config, &clash_core));
+        warnings
+    };
+    for warning in warnings {
+        tracing::warn!("{}", warning.message);
+    }
+
     config = use_sort(config, enable_filter);
 
     let mut exists_set = HashSet::new();
@@ -124,3 +208,34 @@ pub fn enhance() -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
 
     (config, exists_keys, result_map)
 }
+
+/// run the merge and the script items against the config, for the profile hooks
+/// 返回脚本的日志，任何一个脚本报错就失败
+pub fn run_chain(mut config: Mapping, chain: &[String]) -> Result<HashMap<String, ResultLog>> {
+    let items = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        chain
+            .iter()
+            .map(|uid| {
+                let item = profiles.get_item(uid)?;
+                <Option<ChainItem>>::from(item)
+                    .ok_or_else(|| anyhow!("`{uid}` is not a merge or an approved script"))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut result_map = HashMap::new();
+    for item in items {
+        match item.data {
+            ChainType::Merge(merge) => config = use_merge(merge, config),
+            ChainType::Script(script) => {
+                let (res_config, logs) = use_script(script, config)
+                    .with_context(|| format!("the script `{}` failed", item.uid))?;
+                config = res_config;
+                result_map.insert(item.uid, logs);
+            }
+        }
+    }
+    Ok(result_map)
+}
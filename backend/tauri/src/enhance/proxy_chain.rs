@@ -0,0 +1,120 @@
+use crate::config::{ChainMode, ChainNodes, ClashCore, ProxyChain};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+
+fn find_named<'a>(list: &'a mut [Value], name: &str) -> Option<&'a mut Mapping> {
+    list.iter_mut()
+        .filter_map(Value::as_mapping_mut)
+        .find(|item| item.get("name").and_then(Value::as_str) == Some(name))
+}
+
+/// generate the relay groups or the dialer-proxy nodes of the enabled chains
+/// the invalid chains are skipped, the profile may have changed since they were saved
+pub fn use_proxy_chains(
+    mut config: Mapping,
+    chains: Option<Vec<ProxyChain>>,
+    core: &ClashCore,
+) -> Mapping {
+    for chain in chains.into_iter().flatten().filter(|chain| chain.enable) {
+        let nodes = ChainNodes::from_config(&config, &HashSet::new());
+        if let Err(err) = chain.validate(&nodes, core) {
+            tracing::warn!("skip the proxy chain `{}`: {err}", chain.name);
+            continue;
+        }
+
+        let mut proxies = config
+            .get("proxies")
+            .and_then(|v| v.as_sequence().cloned())
+            .unwrap_or_default();
+        let mut groups = config
+            .get("proxy-groups")
+            .and_then(|v| v.as_sequence().cloned())
+            .unwrap_or_default();
+
+        match chain.mode {
+            ChainMode::Relay => {
+                let mut group = Mapping::new();
+                group.insert("name".into(), chain.name.clone().into());
+                group.insert("type".into(), "relay".into());
+                group.insert("proxies".into(), chain.proxies.clone().into());
+                groups.push(group.into());
+            }
+            // 复制第一跳之后的节点，每个副本经由上一跳拨号，原节点不受影响
+            ChainMode::DialerProxy => {
+                let last = chain.proxies.len() - 1;
+                let mut dialer = chain.proxies[0].clone();
+                for (index, hop) in chain.proxies.iter().enumerate().skip(1) {
+                    let Some(mut node) = find_named(&mut proxies, hop).cloned() else {
+                        break;
+                    };
+                    let name = chain.hop_name(index == last, hop);
+                    node.insert("name".into(), name.clone().into());
+                    node.insert("dialer-proxy".into(), dialer.into());
+                    proxies.push(node.into());
+                    dialer = name;
+                }
+            }
+        }
+
+        if let Some(group) = chain
+            .attach_to
+            .as_ref()
+            .and_then(|name| find_named(&mut groups, name))
+        {
+            match group.get_mut("proxies").and_then(Value::as_sequence_mut) {
+                Some(members) => members.push(chain.name.clone().into()),
+                None => {
+                    group.insert("proxies".into(), vec![chain.name.clone()].into());
+                }
+            }
+        }
+
+        config.insert("proxies".into(), proxies.into());
+        config.insert("proxy-groups".into(), groups.into());
+    }
+    config
+}
+
+#[test]
+fn test_use_proxy_chains() {
+    let config = serde_yaml::from_str::<Mapping>(
+        r#"
+proxies:
+  - { name: a, type: ss, server: 1.1.1.1 }
+  - { name: b, type: vmess, server: 2.2.2.2 }
+proxy-groups:
+  - { name: Proxy, type: select, proxies: [a, b] }
+"#,
+    )
+    .unwrap();
+    let chain = |name: &str, mode| ProxyChain {
+        name: name.into(),
+        proxies: vec!["a".into(), "b".into()],
+        mode,
+        attach_to: Some("Proxy".into()),
+        enable: true,
+    };
+
+    let config = use_proxy_chains(
+        config,
+        Some(vec![
+            chain("Relay", ChainMode::Relay),
+            chain("Dialer", ChainMode::DialerProxy),
+        ]),
+        &ClashCore::Mihomo,
+    );
+
+    let proxies = config["proxies"].as_sequence().unwrap();
+    assert_eq!(proxies.len(), 3);
+    assert_eq!(proxies[2]["name"], Value::from("Dialer"));
+    assert_eq!(proxies[2]["server"], Value::from("2.2.2.2"));
+    assert_eq!(proxies[2]["dialer-proxy"], Value::from("a"));
+    assert!(proxies[1].get("dialer-proxy").is_none());
+
+    let groups = config["proxy-groups"].as_sequence().unwrap();
+    assert_eq!(groups[1]["type"], Value::from("relay"));
+    assert_eq!(
+        groups[0]["proxies"],
+        Value::from(vec!["a", "b", "Relay", "Dialer"])
+    );
+}
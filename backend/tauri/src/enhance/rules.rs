@@ -0,0 +1,115 @@
+use crate::config::{rule_targets, RulePosition, UserRule};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+
+fn is_final_rule(rule: &Value) -> bool {
+    rule.as_str()
+        .is_some_and(|rule| rule.starts_with("MATCH,") || rule.starts_with("FINAL,"))
+}
+
+/// put the enabled user rules into the `rules` of the config
+/// the rules whose target is missing in this profile are skipped
+pub fn use_rules(mut config: Mapping, rules: Option<Vec<UserRule>>) -> Mapping {
+    let rules = rules
+        .into_iter()
+        .flatten()
+        .filter(|rule| rule.enable)
+        .collect::<Vec<_>>();
+    if rules.is_empty() {
+        return config;
+    }
+
+    let targets = rule_targets(&config);
+    let rules_key = Value::from("rules");
+    let origin = config
+        .get(&rules_key)
+        .and_then(|v| v.as_sequence().cloned())
+        .unwrap_or_default();
+
+    let mut prepend = vec![];
+    let mut append = vec![];
+    let mut before: BTreeMap<usize, Vec<Value>> = BTreeMap::new();
+    for rule in rules {
+        if let Err(err) = rule.validate(Some(&targets)) {
+            tracing::warn!("skip the user rule `{rule}`: {err}");
+            continue;
+        }
+        let value = Value::from(rule.to_string());
+        match rule.position {
+            RulePosition::Prepend => prepend.push(value),
+            RulePosition::Before(index) if index < origin.len() => {
+                before.entry(index).or_default().push(value)
+            }
+            _ => append.push(value),
+        }
+    }
+
+    // 追加的规则要在 MATCH 之前，否则永远匹配不到
+    let final_at = origin.iter().position(is_final_rule);
+    let mut result = prepend;
+    for (index, rule) in origin.into_iter().enumerate() {
+        if Some(index) == final_at {
+            result.append(&mut append);
+        }
+        if let Some(mut list) = before.remove(&index) {
+            result.append(&mut list);
+        }
+        result.push(rule);
+    }
+    result.append(&mut append);
+
+    config.insert(rules_key, Value::from(result));
+    config
+}
+
+#[test]
+fn test_use_rules() {
+    let config = serde_yaml::from_str::<Mapping>(
+        r#"
+proxy-groups:
+  - name: Proxy
+rules:
+  - DOMAIN,a.com,DIRECT
+  - DOMAIN,b.com,Proxy
+  - MATCH,Proxy
+"#,
+    )
+    .unwrap();
+
+    let rule = |payload: &str, target: &str, position: RulePosition| UserRule {
+        id: String::new(),
+        rule_type: "DOMAIN".into(),
+        payload: payload.into(),
+        target: target.into(),
+        no_resolve: false,
+        position,
+        enable: true,
+    };
+    let config = use_rules(
+        config,
+        Some(vec![
+            rule("c.com", "Proxy", RulePosition::Append),
+            rule("d.com", "DIRECT", RulePosition::Prepend),
+            rule("e.com", "DIRECT", RulePosition::Before(1)),
+            rule("f.com", "Missing", RulePosition::Prepend),
+        ]),
+    );
+
+    let rules = config["rules"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .filter_map(|v| v.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rules,
+        vec![
+            "DOMAIN,d.com,DIRECT",
+            "DOMAIN,a.com,DIRECT",
+            "DOMAIN,e.com,DIRECT",
+            "DOMAIN,b.com,Proxy",
+            "DOMAIN,c.com,Proxy",
+            "MATCH,Proxy",
+        ]
+    );
+}
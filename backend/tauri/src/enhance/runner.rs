@@ -0,0 +1,85 @@
+use super::{use_merge, use_script, ChainItem, ChainType};
+use crate::config::Config;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_yaml::Mapping;
+use std::time::Instant;
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ChainTestResult {
+    /// 处理后的配置（yaml）
+    pub output: String,
+    /// console 输出，(level, data)
+    pub logs: Vec<(String, String)>,
+    /// 执行耗时
+    pub elapsed_ms: u64,
+    /// 脚本抛出的异常
+    pub error: Option<String>,
+}
+
+/// 用样例配置试运行某个 chain，不影响正在使用的配置
+/// sample 为空时使用当前订阅的内容
+pub fn test_chain(uid: String, sample: String) -> Result<ChainTestResult> {
+    let (item, current) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        let item = profiles.get_item(&uid)?;
+        let item = match <Option<ChainItem>>::from(item) {
+            Some(item) => item,
+            None => bail!("only merge and script items can be tested"),
+        };
+        (item, profiles.current_mapping().unwrap_or_default())
+    };
+
+    let config = match sample.trim().is_empty() {
+        true => current,
+        false => serde_yaml::from_str::<Mapping>(&sample)
+            .map_err(|err| anyhow::anyhow!("invalid sample profile: {err}"))?,
+    };
+
+    run_chain(item.data, config)
+}
+
+fn run_chain(data: ChainType, config: Mapping) -> Result<ChainTestResult> {
+    let now = Instant::now();
+    let (config, logs) = match data {
+        ChainType::Merge(merge) => (use_merge(merge, config), vec![]),
+        ChainType::Script(script) => use_script(script, config)?,
+    };
+    let elapsed_ms = now.elapsed().as_millis() as u64;
+
+    // use_script 出错时会把异常放进日志并返回原配置
+    let error = logs
+        .iter()
+        .find(|(level, _)| level == "exception")
+        .map(|(_, data)| data.clone());
+
+    Ok(ChainTestResult {
+        output: serde_yaml::to_string(&config)?,
+        logs,
+        elapsed_ms,
+        error,
+    })
+}
+
+#[test]
+fn test_run_chain() {
+    let config = serde_yaml::from_str::<Mapping>("rules:\n  - MATCH,DIRECT\n").unwrap();
+
+    let script = r#"
+    function main(config) {
+      console.log(config.rules.length);
+      config.mode = "rule";
+      return config;
+    }
+  "#;
+    let result = run_chain(ChainType::Script(script.into()), config.clone()).unwrap();
+    assert!(result.error.is_none());
+    assert!(result.output.contains("mode: rule"));
+    assert_eq!(result.logs, vec![("log".into(), "1".into())]);
+
+    let script = "function main(config) { throw new Error('boom'); }";
+    let result = run_chain(ChainType::Script(script.into()), config).unwrap();
+    assert!(result.error.unwrap().contains("boom"));
+    assert!(result.output.contains("MATCH,DIRECT"));
+}
@@ -0,0 +1,41 @@
+use crate::config::{ClashCore, CoreFeature, SnifferOverride};
+use serde_yaml::{Mapping, Value};
+
+/// apply the sniffer override on the `sniffer` of the config
+pub fn use_sniffer(
+    mut config: Mapping,
+    sniffer: Option<SnifferOverride>,
+    core: &ClashCore,
+) -> Mapping {
+    let Some(sniffer) = sniffer.filter(|sniffer| sniffer != &SnifferOverride::default()) else {
+        return config;
+    };
+    if !core.supports(CoreFeature::Sniffer) {
+        tracing::warn!("the sniffer override is skipped, `{core}` does not support it");
+        return config;
+    }
+    let patch = match serde_yaml::to_value(sniffer) {
+        Ok(Value::Mapping(patch)) => patch,
+        Ok(_) => return config,
+        Err(err) => {
+            tracing::error!("failed to convert the sniffer override: {err}");
+            return config;
+        }
+    };
+
+    let sniffer_key = Value::from("sniffer");
+    let mut sniffer_val = config
+        .get(&sniffer_key)
+        .and_then(|val| val.as_mapping().cloned())
+        .unwrap_or_default();
+    // 旧的 `sniffing` 写法和 `sniff` 冲突
+    if patch.contains_key("sniff") {
+        sniffer_val.remove("sniffing");
+    }
+    for (key, val) in patch {
+        sniffer_val.insert(key, val);
+    }
+
+    config.insert(sniffer_key, sniffer_val.into());
+    config
+}
@@ -0,0 +1,40 @@
+use crate::config::StoreOverride;
+use serde_yaml::{Mapping, Value};
+
+/// apply the store override on the `profile` of the config
+pub fn use_store(mut config: Mapping, store: Option<StoreOverride>) -> Mapping {
+    let Some(store) = store.filter(|store| store != &StoreOverride::default()) else {
+        return config;
+    };
+    let patch = match serde_yaml::to_value(store) {
+        Ok(Value::Mapping(patch)) => patch,
+        _ => return config,
+    };
+
+    let profile_key = Value::from("profile");
+    let mut profile = config
+        .get(&profile_key)
+        .and_then(|val| val.as_mapping().cloned())
+        .unwrap_or_default();
+    for (key, val) in patch {
+        profile.insert(key, val);
+    }
+
+    config.insert(profile_key, profile.into());
+    config
+}
+
+#[test]
+fn test_use_store() {
+    let config: Mapping =
+        serde_yaml::from_str("profile: { store-selected: true, tracing: true }").unwrap();
+    let store = StoreOverride {
+        store_selected: Some(false),
+        store_fake_ip: None,
+    };
+    let config = use_store(config, Some(store));
+    let profile = config.get("profile").unwrap();
+    assert_eq!(profile.get("store-selected"), Some(&Value::Bool(false)));
+    assert_eq!(profile.get("tracing"), Some(&Value::Bool(true)));
+    assert_eq!(profile.get("store-fake-ip"), None);
+}
@@ -0,0 +1,45 @@
+use crate::config::{rule_targets, ClashCore, CoreFeature, Tunnel};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+
+/// append the enabled tunnels to the `tunnels` of the config
+/// the tunnels whose proxy is missing in this profile are skipped
+pub fn use_tunnels(mut config: Mapping, tunnels: Option<Vec<Tunnel>>, core: &ClashCore) -> Mapping {
+    let tunnels = tunnels
+        .into_iter()
+        .flatten()
+        .filter(|tunnel| tunnel.enable)
+        .collect::<Vec<_>>();
+    if tunnels.is_empty() {
+        return config;
+    }
+    if !core.supports(CoreFeature::Tunnels) {
+        tracing::warn!("the tunnels are skipped, `{core}` does not support them");
+        return config;
+    }
+
+    let targets = rule_targets(&config);
+    let mut list = config
+        .get("tunnels")
+        .and_then(|v| v.as_sequence().cloned())
+        .unwrap_or_default();
+    let mut addresses = HashSet::new();
+
+    for tunnel in tunnels {
+        if let Err(err) = tunnel.validate(Some(&targets)) {
+            tracing::warn!("skip the tunnel `{}`: {err}", tunnel.address);
+            continue;
+        }
+        if !addresses.insert(tunnel.address.trim().to_string()) {
+            tracing::warn!(
+                "skip the tunnel `{}`: the address is duplicated",
+                tunnel.address
+            );
+            continue;
+        }
+        list.push(tunnel.to_value());
+    }
+
+    config.insert("tunnels".into(), Value::from(list));
+    config
+}
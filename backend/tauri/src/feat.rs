@@ -5,9 +5,12 @@
 //! - cmds 页面调用
 //!
 use crate::config::*;
+use crate::core::event_bus::{AppEvent, EventBus, ProfileChange};
+use crate::core::proxy_env::{ProxyEnv, ShellType};
 use crate::core::*;
+use crate::enhance::warn_self_bypass;
 use crate::log_err;
-use crate::utils::resolve;
+use crate::utils::{dirs, logging, resolve};
 use anyhow::{bail, Result};
 use serde_yaml::{Mapping, Value};
 use wry::application::clipboard::Clipboard;
@@ -52,7 +55,7 @@ pub fn restart_clash_core() {
             }
             Err(err) => {
                 handle::Handle::notice_message("set_config::error", format!("{err}"));
-                log::error!(target:"app", "{err}");
+                tracing::error!("{err}");
             }
         }
     });
@@ -60,23 +63,48 @@ pub fn restart_clash_core() {
 
 // 切换模式 rule/global/direct/script mode
 pub fn change_clash_mode(mode: String) {
+    tauri::async_runtime::spawn(async move {
+        log_err!(set_clash_mode(&mode).await);
+    });
+}
+
+/// change the mode and wait for the core
+pub async fn set_clash_mode(mode: &str) -> Result<()> {
+    tracing::debug!("change clash mode to {mode}");
+    check_clash_mode(mode);
+
     let mut mapping = Mapping::new();
-    mapping.insert(Value::from("mode"), mode.clone().into());
+    mapping.insert(Value::from("mode"), mode.into());
 
-    tauri::async_runtime::spawn(async move {
-        log::debug!(target: "app", "change clash mode to {mode}");
+    clash_api::patch_configs(&mapping).await?;
+    // 更新配置
+    Config::clash().data().patch_config(mapping);
+    Config::clash().data().save_config()?;
+    handle::Handle::refresh_clash();
+    log_err!(handle::Handle::update_systray_part());
+    Ok(())
+}
 
-        match clash_api::patch_configs(&mapping).await {
-            Ok(_) => {
-                // 更新配置
-                Config::clash().data().patch_config(mapping);
+/// the self bypass of the tun mode doesn't work in the global mode
+fn check_clash_mode(mode: &str) {
+    let (enable_tun, self_bypass, core) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.enable_tun_mode.unwrap_or(false),
+            verge.enable_self_bypass.unwrap_or(true),
+            verge.clash_core.clone().unwrap_or_default(),
+        )
+    };
+    warn_self_bypass(Some(mode), enable_tun, self_bypass, &core);
+}
 
-                if Config::clash().data().save_config().is_ok() {
-                    handle::Handle::refresh_clash();
-                    log_err!(handle::Handle::update_systray_part());
-                }
-            }
-            Err(err) => log::error!(target: "app", "{err}"),
+// 暂停代理，再次调用时恢复到之前的状态
+pub fn toggle_pause() {
+    tauri::async_runtime::spawn(async {
+        match pause::ProxyPause::global().toggle().await {
+            Ok(_) => handle::Handle::refresh_verge(),
+            Err(err) => tracing::error!("{err}"),
         }
     });
 }
@@ -94,7 +122,7 @@ pub fn toggle_system_proxy() {
         .await
         {
             Ok(_) => handle::Handle::refresh_verge(),
-            Err(err) => log::error!(target: "app", "{err}"),
+            Err(err) => tracing::error!("{err}"),
         }
     });
 }
@@ -109,7 +137,7 @@ pub fn enable_system_proxy() {
         .await
         {
             Ok(_) => handle::Handle::refresh_verge(),
-            Err(err) => log::error!(target: "app", "{err}"),
+            Err(err) => tracing::error!("{err}"),
         }
     });
 }
@@ -124,7 +152,7 @@ pub fn disable_system_proxy() {
         .await
         {
             Ok(_) => handle::Handle::refresh_verge(),
-            Err(err) => log::error!(target: "app", "{err}"),
+            Err(err) => tracing::error!("{err}"),
         }
     });
 }
@@ -142,7 +170,7 @@ pub fn toggle_tun_mode() {
         .await
         {
             Ok(_) => handle::Handle::refresh_verge(),
-            Err(err) => log::error!(target: "app", "{err}"),
+            Err(err) => tracing::error!("{err}"),
         }
     });
 }
@@ -157,7 +185,7 @@ pub fn enable_tun_mode() {
         .await
         {
             Ok(_) => handle::Handle::refresh_verge(),
-            Err(err) => log::error!(target: "app", "{err}"),
+            Err(err) => tracing::error!("{err}"),
         }
     });
 }
@@ -172,13 +200,16 @@ pub fn disable_tun_mode() {
         .await
         {
             Ok(_) => handle::Handle::refresh_verge(),
-            Err(err) => log::error!(target: "app", "{err}"),
+            Err(err) => tracing::error!("{err}"),
         }
     });
 }
 
 /// 修改clash的配置
 pub async fn patch_clash(patch: Mapping) -> Result<()> {
+    if let Some(mode) = patch.get("mode").and_then(|v| v.as_str()) {
+        check_clash_mode(mode);
+    }
     Config::clash().draft().patch_config(patch.clone());
 
     match {
@@ -220,6 +251,15 @@ pub async fn patch_clash(patch: Mapping) -> Result<()> {
             log_err!(handle::Handle::update_systray_part());
         }
 
+        // 开关局域网连接或者端口变化时更新防火墙规则
+        if patch.get("allow-lan").is_some()
+            || mixed_port.is_some()
+            || patch.get("port").is_some()
+            || patch.get("socks-port").is_some()
+        {
+            log_err!(lan::update_firewall());
+        }
+
         Config::runtime().latest().patch_config(patch);
 
         <Result<()>>::Ok(())
@@ -236,41 +276,292 @@ pub async fn patch_clash(patch: Mapping) -> Result<()> {
     }
 }
 
+/// the ports used by the core and the servers of the app, with the defaults filled
+fn app_ports() -> std::collections::HashMap<u16, String> {
+    let mut ports = {
+        let clash = Config::clash();
+        let clash = clash.latest();
+        reserved_ports(&clash.0)
+    };
+    let runtime = { Config::runtime().latest().config.clone() };
+    if let Some(config) = runtime {
+        ports.extend(reserved_ports(&config));
+    }
+
+    let verge = Config::verge();
+    let verge = verge.latest();
+    if let Some(port) = verge.verge_mixed_port {
+        ports.insert(port, "mixed-port".into());
+    }
+    let servers = [
+        (
+            verge.enable_control_api,
+            verge.control_api_port,
+            control_api::DEFAULT_PORT,
+            "control api",
+        ),
+        (
+            verge.enable_controller_proxy,
+            verge.controller_proxy_port,
+            controller_proxy::DEFAULT_PORT,
+            "controller proxy",
+        ),
+        (
+            verge.enable_metrics,
+            verge.metrics_port,
+            metrics::DEFAULT_PORT,
+            "metrics",
+        ),
+        (
+            verge.enable_remote_management,
+            verge.remote_management_port,
+            remote::DEFAULT_PORT,
+            "remote management",
+        ),
+        (
+            verge.enable_throttle,
+            verge.throttle_port,
+            throttle::DEFAULT_PORT,
+            "throttle",
+        ),
+        (
+            verge.enable_exit_probe,
+            verge.exit_probe_port,
+            EXIT_PROBE_PORT,
+            "exit probe",
+        ),
+    ];
+    for (enable, port, default, name) in servers {
+        if enable.unwrap_or(false) {
+            ports.insert(port.unwrap_or(default), name.into());
+        }
+    }
+    // 网页面板按需启动，端口总是保留
+    ports.insert(
+        verge.web_ui_port.unwrap_or(web_ui::DEFAULT_PORT),
+        "web ui".into(),
+    );
+    ports.insert(IVerge::get_singleton_port(), "app singleton".into());
+    ports
+}
+
+/// 检查监听的端口是否和内核、app 或者其他程序冲突
+fn check_listeners(listeners: &[InboundListener]) -> Result<()> {
+    let targets = { Config::runtime().latest().config.as_ref().map(rule_targets) };
+    let reserved = app_ports();
+    let saved = { Config::verge().latest().listeners.clone() };
+    let saved = saved.unwrap_or_default();
+
+    // 代理随订阅变化，只检查新增和修改的监听
+    for listener in listeners
+        .iter()
+        .filter(|listener| listener.enable && !saved.contains(listener))
+    {
+        listener.validate(targets.as_ref())?;
+    }
+    check_listener_ports(listeners, &reserved)?;
+
+    // 已经在监听的端口由内核占用，只检查新的端口
+    let saved_ports = saved
+        .iter()
+        .filter(|listener| listener.enable)
+        .map(|listener| listener.port)
+        .collect::<Vec<_>>();
+    for listener in listeners.iter().filter(|listener| listener.enable) {
+        if !saved_ports.contains(&listener.port)
+            && !port_scanner::local_port_available(listener.port)
+        {
+            bail!("the port {} is already in use", listener.port);
+        }
+    }
+    Ok(())
+}
+
 /// 修改verge的配置
 /// 一般都是一个个的修改
 pub async fn patch_verge(patch: IVerge) -> Result<()> {
+    for rule in patch.automation_rules.iter().flatten() {
+        rule.validate()?;
+    }
+    for window in patch.pause_windows.iter().flatten() {
+        window.validate()?;
+    }
+    if let Some(hook) = &patch.event_hook {
+        hook.validate()?;
+    }
+    if let Some(mirror) = &patch.github_mirror {
+        onboarding::validate_mirror(mirror)?;
+    }
+    if let Some(failover) = &patch.core_failover {
+        failover.validate()?;
+        for action in &failover.actions {
+            if let FailoverAction::SwitchProfile { uid } = action {
+                Config::profiles().latest().get_item(uid)?;
+            }
+        }
+    }
+    for entry in patch.hosts_entries.iter().flatten() {
+        entry.validate()?;
+    }
+    if let Some(auth) = &patch.inbound_auth {
+        auth.validate()?;
+    }
+    for entry in patch.tray_menu.iter().flatten() {
+        entry.validate()?;
+    }
+    if let Some(StartupProfile::Pinned(uid)) = &patch.startup_profile {
+        if !Config::profiles().latest().get_item(uid)?.is_profile() {
+            bail!("only a remote or a local profile can be activated on launch");
+        }
+    }
+    if let Some(uid) = patch
+        .expired_fallback_profile
+        .as_ref()
+        .filter(|uid| !uid.is_empty())
+    {
+        Config::profiles().latest().get_item(uid)?;
+    }
+    if let Some(sniffer) = &patch.sniffer_override {
+        let core = { Config::verge().latest().clash_core.clone() };
+        sniffer.validate(&core.unwrap_or_default())?;
+    }
+    if patch.enable_tun_mode.is_some() || patch.enable_self_bypass.is_some() {
+        let mode = { Config::clash().latest().get_mode() };
+        let (enable_tun, self_bypass, core) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (
+                patch
+                    .enable_tun_mode
+                    .or(verge.enable_tun_mode)
+                    .unwrap_or(false),
+                patch
+                    .enable_self_bypass
+                    .or(verge.enable_self_bypass)
+                    .unwrap_or(true),
+                verge.clash_core.clone().unwrap_or_default(),
+            )
+        };
+        warn_self_bypass(mode.as_deref(), enable_tun, self_bypass, &core);
+    }
+    if let Some(rules) = &patch.user_rules {
+        // 切换订阅后目标可能不存在，只检查新增和修改的规则，旧的规则不应挡住保存
+        let targets = { Config::runtime().latest().config.as_ref().map(rule_targets) };
+        let saved = { Config::verge().latest().user_rules.clone() };
+        let saved = saved.unwrap_or_default();
+        for rule in rules.iter().filter(|rule| !saved.contains(rule)) {
+            rule.validate(targets.as_ref())?;
+        }
+    }
+    if let Some(rules) = &patch.app_rules {
+        let targets = { Config::runtime().latest().config.as_ref().map(rule_targets) };
+        let saved = { Config::verge().latest().app_rules.clone() };
+        check_app_rules(rules, &saved.unwrap_or_default(), targets.as_ref())?;
+    }
+    if let Some(chains) = &patch.proxy_chains {
+        let nodes = ChainNodes::current();
+        let (core, saved) = {
+            let verge = Config::verge();
+            let verge = verge.latest();
+            (verge.clash_core.clone(), verge.proxy_chains.clone())
+        };
+        let saved = saved.unwrap_or_default();
+        // 节点随订阅变化，只检查新增和修改的链
+        for chain in chains
+            .iter()
+            .filter(|chain| chain.enable && !saved.contains(chain))
+        {
+            chain.validate(&nodes, &core.clone().unwrap_or_default())?;
+        }
+    }
+    if let Some(tunnels) = &patch.tunnels {
+        let targets = { Config::runtime().latest().config.as_ref().map(rule_targets) };
+        let saved = { Config::verge().latest().tunnels.clone() };
+        let saved = saved.unwrap_or_default();
+        let mut addresses = std::collections::HashSet::new();
+        for tunnel in tunnels.iter().filter(|tunnel| tunnel.enable) {
+            // 代理随订阅变化，只检查新增和修改的隧道，地址冲突仍然检查全部
+            if !saved.contains(tunnel) {
+                tunnel.validate(targets.as_ref())?;
+            }
+            if !addresses.insert(tunnel.address.trim()) {
+                bail!(
+                    "the local address `{}` is used by another tunnel",
+                    tunnel.address
+                );
+            }
+        }
+    }
+    if let Some(listeners) = &patch.listeners {
+        check_listeners(listeners)?;
+    }
+    if let Some(rules) = &patch.throttle_rules {
+        check_throttle_rules(rules)?;
+    }
+    if let Some(server) = &patch.app_dns_over_https {
+        doh::validate_server(server)?;
+    }
+    if let Some(policies) = &patch.http_policies {
+        policies.validate()?;
+    }
     Config::verge().draft().patch_config(patch.clone());
 
     let tun_mode = patch.enable_tun_mode;
+    // 这些字段会改变生成的配置
+    let regenerate = tun_mode.is_some()
+        || patch.enable_self_bypass.is_some()
+        || patch.hosts_entries.is_some()
+        || patch.user_rules.is_some()
+        || patch.app_rules.is_some()
+        || patch.proxy_chains.is_some()
+        || patch.tunnels.is_some()
+        || patch.listeners.is_some()
+        || patch.enable_exit_probe.is_some()
+        || patch.exit_probe_port.is_some()
+        || patch.inbound_auth.is_some()
+        || patch.sniffer_override.is_some()
+        || patch.store_override.is_some();
     let auto_launch = patch.enable_auto_launch;
+    let auto_launch_method = patch.auto_launch_method;
     let system_proxy = patch.enable_system_proxy;
-    let proxy_bypass = patch.system_proxy_bypass;
+    #[allow(deprecated)]
+    let proxy_bypass =
+        patch.system_proxy_bypass.is_some() || patch.system_proxy_bypass_list.is_some();
+    let proxy_pac = patch.enable_proxy_pac;
     let language = patch.language;
 
     match {
-        #[cfg(target_os = "windows")]
+        // 服务模式，macos 上是 privileged helper
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
         {
             let service_mode = patch.enable_service_mode;
 
             if service_mode.is_some() {
-                log::debug!(target: "app", "change service mode to {}", service_mode.unwrap());
+                tracing::debug!("change service mode to {}", service_mode.unwrap());
 
                 Config::generate()?;
                 CoreManager::global().run_core().await?;
-            } else if tun_mode.is_some() {
+            } else if regenerate {
                 update_core_config().await?;
             }
         }
 
-        #[cfg(not(target_os = "windows"))]
-        if tun_mode.is_some() {
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        if regenerate {
             update_core_config().await?;
         }
 
-        if auto_launch.is_some() {
+        if let Some(true) = tun_mode {
+            tun::TunManager::global().start_monitor();
+        }
+        if tun_mode.or(patch.enable_tun_dns_override).is_some() {
+            log_err!(sys_dns::update());
+        }
+
+        if auto_launch.is_some() || auto_launch_method.is_some() {
             sysopt::Sysopt::global().update_launch()?;
         }
-        if system_proxy.is_some() || proxy_bypass.is_some() {
+        if system_proxy.is_some() || proxy_bypass || proxy_pac.is_some() {
             sysopt::Sysopt::global().update_sysproxy()?;
             sysopt::Sysopt::global().guard_proxy();
         }
@@ -279,11 +570,74 @@ pub async fn patch_verge(patch: IVerge) -> Result<()> {
             sysopt::Sysopt::global().guard_proxy();
         }
 
+        if patch.enable_control_api.is_some()
+            || patch.control_api_port.is_some()
+            || patch.control_api_token.is_some()
+        {
+            control_api::ControlApi::global().restart().await?;
+        }
+
+        if patch.enable_controller_proxy.is_some()
+            || patch.controller_proxy_port.is_some()
+            || patch.controller_proxy_token.is_some()
+        {
+            controller_proxy::ControllerProxy::global()
+                .restart()
+                .await?;
+        }
+
+        if patch.web_ui_port.is_some() {
+            web_ui::WebUiServer::global().restart().await?;
+        }
+
+        if patch.enable_metrics.is_some() || patch.metrics_port.is_some() {
+            metrics::Metrics::global().restart().await?;
+        }
+
+        // 系统代理改指向限速的端口
+        if patch.enable_throttle.is_some() || patch.throttle_port.is_some() {
+            throttle::Throttle::global().restart().await?;
+            sysopt::Sysopt::global().update_sysproxy()?;
+        }
+
+        if patch.enable_remote_management.is_some()
+            || patch.remote_management_port.is_some()
+            || patch.remote_management_access.is_some()
+        {
+            remote::RemoteManagement::global().restart().await?;
+        }
+
+        if patch.enable_low_power_mode.is_some() {
+            low_power::LowPower::global().refresh();
+        }
+
         if let Some(hotkeys) = patch.hotkeys {
             hotkey::Hotkey::global().update(hotkeys)?;
         }
 
-        if language.is_some() {
+        match patch.enable_tray_traffic {
+            Some(true) => tray::TrayTraffic::global().start(),
+            Some(false) => tray::TrayTraffic::global().clear(),
+            None => {}
+        }
+
+        if let Some(true) = patch.enable_tray_proxies {
+            tray::TrayProxies::global().refresh_later();
+        }
+
+        if let Some(true) = patch.enable_clipboard_watch {
+            clipboard::ClipboardWatcher::global().start();
+        }
+
+        if patch.app_dns_over_https.is_some() {
+            doh::DohResolver::global().clear();
+        }
+
+        if patch.app_log_level.is_some() || patch.app_log_filter.is_some() {
+            logging::reload()?;
+        }
+
+        if language.is_some() || patch.enable_tray_proxies.is_some() || patch.tray_menu.is_some() {
             handle::Handle::update_systray()?;
         } else if system_proxy.or(tun_mode).is_some() {
             handle::Handle::update_systray_part()?;
@@ -294,6 +648,8 @@ pub async fn patch_verge(patch: IVerge) -> Result<()> {
         Ok(()) => {
             Config::verge().apply();
             Config::verge().data().save_file()?;
+            pause::ProxyPause::global()
+                .on_verge_patched(system_proxy.is_some(), tun_mode.is_some());
             Ok(())
         }
         Err(err) => {
@@ -303,9 +659,88 @@ pub async fn patch_verge(patch: IVerge) -> Result<()> {
     }
 }
 
+/// 导入订阅，链接已经存在时按 strategy 处理
+/// 没有 strategy 时不做修改，返回已有的配置让用户选择
+pub async fn import_profile(
+    url: String,
+    option: Option<PrfOption>,
+    strategy: Option<ImportStrategy>,
+) -> Result<ImportOutcome> {
+    let existing = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        profiles
+            .find_by_url(&url)
+            .map(|item| (item.uid.clone().unwrap_or_default(), item.name.clone()))
+    };
+
+    match (existing, strategy) {
+        (None, _) | (Some(_), Some(ImportStrategy::Copy)) => {
+            let item = PrfItem::from_url(&url, None, None, option).await?;
+            let uid = item.uid.clone().unwrap_or_default();
+            Config::profiles().data().append_item(item)?;
+            Ok(ImportOutcome::Imported { uid })
+        }
+        (Some((uid, name)), None) => Ok(ImportOutcome::Duplicate { uid, name }),
+        (Some((uid, _)), Some(ImportStrategy::Merge)) => {
+            let option = {
+                let profiles = Config::profiles();
+                let profiles = profiles.latest();
+                PrfOption::merge(profiles.get_item(&uid)?.option.clone(), option)
+            };
+            Config::profiles().data().patch_item(
+                uid.clone(),
+                PrfItem {
+                    option,
+                    ..PrfItem::default()
+                },
+            )?;
+            update_profile(uid.clone(), None).await?;
+            Ok(ImportOutcome::Merged { uid })
+        }
+        (Some((uid, _)), Some(ImportStrategy::Replace)) => {
+            let item = PrfItem::from_url(&url, None, None, option).await?;
+            // 覆盖之前留一份，可以撤销
+            trash::snapshot(trash::ProfileOperation::Import, &[uid.clone()])?;
+            let is_current = {
+                let profiles = Config::profiles();
+                let mut profiles = profiles.data();
+                profiles.patch_item(
+                    uid.clone(),
+                    PrfItem {
+                        name: item.name.clone(),
+                        url: Some(url),
+                        option: item.option.clone(),
+                        ..PrfItem::default()
+                    },
+                )?;
+                profiles.clear_selected(&uid)?;
+                profiles.update_item(uid.clone(), item)?;
+                profiles.get_current().as_ref() == Some(&uid)
+            };
+            if is_current {
+                update_core_config().await?;
+            }
+            EventBus::publish(AppEvent::ProfileChanged {
+                uid: uid.clone(),
+                change: ProfileChange::Updated,
+            });
+            Ok(ImportOutcome::Replaced { uid })
+        }
+    }
+}
+
 /// 更新某个profile
 /// 如果更新当前配置就激活配置
 pub async fn update_profile(uid: String, option: Option<PrfOption>) -> Result<()> {
+    let script = {
+        let item = Config::profiles().latest().get_item(&uid)?.clone();
+        RemoteScript::is_remote(&item).then_some(item)
+    };
+    if let Some(item) = script {
+        return update_remote_script(uid, item).await;
+    }
+
     let url_opt = {
         let profiles = Config::profiles();
         let profiles = profiles.latest();
@@ -339,11 +774,255 @@ pub async fn update_profile(uid: String, option: Option<PrfOption>) -> Result<()
         update_core_config().await?;
     }
 
+    EventBus::publish(AppEvent::ProfileChanged {
+        uid,
+        change: ProfileChange::Updated,
+    });
     Ok(())
 }
 
+/// the changed script is quarantined, the running one is kept until approved
+async fn update_remote_script(uid: String, item: PrfItem) -> Result<()> {
+    if RemoteScript::fetch(&uid, &item).await? {
+        handle::Handle::emit("verge://script-pending", &uid);
+    }
+    Config::profiles().latest().patch_item(
+        uid,
+        PrfItem {
+            updated: Some(chrono::Local::now().timestamp() as usize),
+            ..PrfItem::default()
+        },
+    )
+}
+
+/// run the reviewed remote script
+pub async fn approve_remote_script(uid: String, hash: String) -> Result<()> {
+    let (item, enabled) = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+        let enabled = profiles.chain.iter().flatten().any(|chain| chain == &uid);
+        (profiles.get_item(&uid)?.clone(), enabled)
+    };
+    RemoteScript::approve(&item, &hash)?;
+
+    if enabled {
+        update_core_config().await?;
+    }
+    Ok(())
+}
+
+/// 切换当前的 profile
+pub async fn switch_profile(uid: String) -> Result<()> {
+    patch_profiles(IProfiles {
+        current: Some(uid),
+        ..IProfiles::default()
+    })
+    .await
+}
+
+/// 修改 profiles 的配置，切换了 profile 时按它绑定的内核切换
+pub async fn patch_profiles(profiles: IProfiles) -> Result<()> {
+    let switching = profiles.current.clone();
+    // 调整或启停增强脚本前存一份快照
+    let chain_changed = match profiles.chain.as_ref() {
+        Some(chain) => Some(chain) != Config::profiles().latest().chain.as_ref(),
+        None => false,
+    };
+    if chain_changed {
+        trash::snapshot(trash::ProfileOperation::Chain, &[])?;
+    }
+    Config::profiles().draft().patch_config(profiles)?;
+
+    let result = match switching.is_some() {
+        true => match apply_profile_core().await {
+            // 切换内核时已经用新的配置启动了
+            Ok(true) => Ok(()),
+            Ok(false) => CoreManager::global().update_config().await,
+            Err(err) => Err(err),
+        },
+        false => CoreManager::global().update_config().await,
+    };
+
+    match result {
+        Ok(_) => {
+            handle::Handle::refresh_clash();
+            Config::profiles().apply();
+            Config::profiles().data().save_file()?;
+            if let Some(uid) = switching {
+                EventBus::publish(AppEvent::ProfileChanged {
+                    uid,
+                    change: ProfileChange::Switched,
+                });
+            }
+            Ok(())
+        }
+        Err(err) => {
+            Config::profiles().discard();
+            Err(err)
+        }
+    }
+}
+
+/// switch to the core bound to the current profile of the draft,
+/// or back to the previous one if it has no core, return true if the core is restarted
+async fn apply_profile_core() -> Result<bool> {
+    let preferred = { Config::profiles().latest().current_core() };
+    let (current, fallback) = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        (
+            verge.clash_core.clone().unwrap_or_default(),
+            verge.profile_core_fallback.clone(),
+        )
+    };
+
+    let target = match (preferred, fallback) {
+        (Some(core), fallback) => {
+            // 只记录第一次切换前的内核
+            if fallback.is_none() && core != current {
+                Config::verge().draft().profile_core_fallback = Some(current.clone());
+            }
+            core
+        }
+        (None, Some(core)) => {
+            Config::verge().draft().profile_core_fallback = None;
+            core
+        }
+        (None, None) => return Ok(false),
+    };
+
+    if target == current {
+        if Config::verge().apply().is_some() {
+            Config::verge().data().save_file()?;
+        }
+        return Ok(false);
+    }
+
+    let installed = dirs::core_path(&target.to_string()).map_or(false, |path| path.exists());
+    if !installed {
+        Config::verge().discard();
+        bail!("the core `{target}` bound to the profile is not installed");
+    }
+
+    tracing::info!("switch the core from `{current}` to `{target}` for the profile");
+    // the draft of the fallback core is discarded by `change_core` on failure
+    CoreManager::global().change_core(Some(target)).await?;
+    handle::Handle::refresh_verge();
+    Ok(true)
+}
+
+/// bind the core to the profile, `None` unbinds it
+pub async fn patch_profile_core(uid: String, core: Option<ClashCore>) -> Result<()> {
+    if let Some(core) = &core {
+        if !dirs::core_path(&core.to_string())?.exists() {
+            bail!("the core `{core}` is not installed");
+        }
+    }
+
+    Config::profiles().draft().set_profile_core(&uid, core)?;
+    let is_current = { Config::profiles().latest().get_current() } == Some(uid);
+    if !is_current {
+        Config::profiles().apply();
+        return Config::profiles().data().save_file();
+    }
+
+    match apply_profile_core().await {
+        Ok(_) => {
+            Config::profiles().apply();
+            Config::profiles().data().save_file()
+        }
+        Err(err) => {
+            Config::profiles().discard();
+            Err(err)
+        }
+    }
+}
+
+/// 切换到下一个配置，到最后一个时回到第一个
+pub fn switch_next_profile() {
+    let next = {
+        let profiles = Config::profiles();
+        let profiles = profiles.latest();
+
+        let uids = profiles
+            .get_items()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter(|item| item.is_profile())
+                    .filter_map(|item| item.uid.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let index = profiles
+            .get_current()
+            .and_then(|current| uids.iter().position(|uid| *uid == current))
+            .map_or(0, |index| (index + 1) % uids.len());
+        uids.get(index).cloned()
+    };
+
+    let uid = match next {
+        Some(uid) => uid,
+        None => return,
+    };
+
+    tauri::async_runtime::spawn(async move {
+        match switch_profile(uid).await {
+            Ok(_) => {
+                handle::Handle::refresh_profiles();
+                handle::Handle::notice_message("set_config::ok", "ok");
+            }
+            Err(err) => {
+                handle::Handle::notice_message("set_config::error", format!("{err}"));
+                tracing::error!("{err}");
+            }
+        }
+    });
+}
+
+/// 修改 dns 覆写，`uid` 为空时是全局的
+/// 生成配置失败时丢弃修改
+pub async fn patch_dns_override(uid: Option<String>, dns: Option<DnsOverride>) -> Result<()> {
+    if let Some(dns) = &dns {
+        dns.validate()?;
+    }
+
+    let Some(uid) = uid else {
+        Config::verge().draft().dns_override = dns;
+        return match update_core_config().await {
+            Ok(_) => {
+                Config::verge().apply();
+                Config::verge().data().save_file()
+            }
+            Err(err) => {
+                Config::verge().discard();
+                Err(err)
+            }
+        };
+    };
+
+    Config::profiles().draft().set_dns_override(&uid, dns)?;
+    let is_current = { Config::profiles().latest().get_current() } == Some(uid);
+    if !is_current {
+        Config::profiles().apply();
+        return Config::profiles().data().save_file();
+    }
+
+    match update_core_config().await {
+        Ok(_) => {
+            Config::profiles().apply();
+            Config::profiles().data().save_file()
+        }
+        Err(err) => {
+            Config::profiles().discard();
+            Err(err)
+        }
+    }
+}
+
 /// 更新配置
-async fn update_core_config() -> Result<()> {
+pub async fn update_core_config() -> Result<()> {
     match CoreManager::global().update_config().await {
         Ok(_) => {
             handle::Handle::refresh_clash();
@@ -358,22 +1037,7 @@ async fn update_core_config() -> Result<()> {
 }
 
 /// copy env variable
-pub fn copy_clash_env(option: &str) {
-    let port = { Config::verge().latest().verge_mixed_port.unwrap_or(7890) };
-    let http_proxy = format!("http://127.0.0.1:{}", port);
-    let socks5_proxy = format!("socks5://127.0.0.1:{}", port);
-
-    let sh =
-        format!("export https_proxy={http_proxy} http_proxy={http_proxy} all_proxy={socks5_proxy}");
-    let cmd: String = format!("set http_proxy={http_proxy} \n set https_proxy={http_proxy}");
-    let ps: String = format!("$env:HTTP_PROXY=\"{http_proxy}\"; $env:HTTPS_PROXY=\"{http_proxy}\"");
-
-    let mut cliboard = Clipboard::new();
-
-    match option {
-        "sh" => cliboard.write_text(sh),
-        "cmd" => cliboard.write_text(cmd),
-        "ps" => cliboard.write_text(ps),
-        _ => log::error!(target: "app", "copy_clash_env: Invalid option! {option}"),
-    }
+pub fn copy_clash_env(shell: ShellType) {
+    let env = ProxyEnv::current().render(shell);
+    Clipboard::new().write_text(env);
 }
@@ -10,16 +10,39 @@ mod enhance;
 mod feat;
 mod utils;
 
-use crate::utils::{init, resolve, server};
+use crate::utils::{cli, init, resolve, server};
 use tauri::{api, SystemTray};
 
 fn main() -> std::io::Result<()> {
+    // the privileged helper on macos, launched by launchd as root
+    #[cfg(target_os = "macos")]
+    if let Some(code) = core::mac_helper::run() {
+        std::process::exit(code);
+    }
+
+    // the elevated process to set the loopback exemptions of the uwp apps
+    #[cfg(target_os = "windows")]
+    if let Some(code) = core::win_uwp::run() {
+        std::process::exit(code);
+    }
+
+    // headless control of the running instance
+    if let Some(code) = cli::run() {
+        std::process::exit(code);
+    }
+
     // 单例检测
     if server::check_singleton().is_err() {
         println!("app exists");
         return Ok(());
     }
 
+    core::crash::install_hook();
+
+    // listen the url events on macos
+    #[cfg(target_os = "macos")]
+    tauri_plugin_deep_link::prepare("moe.elaina.clash.nyanpasu");
+
     crate::log_err!(init::init_config());
 
     #[allow(unused_mut)]
@@ -30,6 +53,94 @@ fn main() -> std::io::Result<()> {
         .invoke_handler(tauri::generate_handler![
             // common
             cmds::get_sys_proxy,
+            cmds::get_lan_access_info,
+            cmds::get_network_state,
+            cmds::get_boot_report,
+            cmds::reset_control_api_token,
+            cmds::reset_controller_proxy_token,
+            cmds::get_external_mutations,
+            cmds::clear_external_mutations,
+            cmds::check_hotkeys,
+            cmds::get_desktop_session,
+            cmds::get_proxy_env,
+            cmds::copy_proxy_env,
+            cmds::get_notifications,
+            cmds::clear_notifications,
+            cmds::run_notification_action,
+            cmds::take_notification_page,
+            cmds::set_webdav_config,
+            cmds::save_event_hook,
+            cmds::test_event_hook,
+            cmds::save_tray_menu,
+            cmds::get_remote_management_info,
+            cmds::set_remote_management_password,
+            cmds::webdav_backup,
+            cmds::webdav_list_backups,
+            cmds::webdav_preview_restore,
+            cmds::webdav_restore,
+            cmds::set_sync_s3_config,
+            cmds::sync_now,
+            cmds::get_sync_conflicts,
+            cmds::resolve_sync_conflict,
+            cmds::set_log_level,
+            cmds::generate_diagnostic_bundle,
+            cmds::export_connections,
+            cmds::export_traffic_history,
+            cmds::run_connectivity_doctor,
+            cmds::repair_installation,
+            cmds::get_crash_reports,
+            cmds::upload_crash_report,
+            cmds::clear_crash_reports,
+            cmds::check_app_update,
+            cmds::install_app_update,
+            cmds::stage_app_update,
+            cmds::skip_app_update,
+            cmds::get_stream_server,
+            cmds::list_proxies,
+            cmds::list_connections,
+            cmds::get_dns_override,
+            cmds::set_dns_override,
+            cmds::read_system_hosts,
+            cmds::get_user_rules,
+            cmds::add_user_rule,
+            cmds::update_user_rule,
+            cmds::delete_user_rule,
+            cmds::reorder_user_rules,
+            cmds::browse_rule_set,
+            cmds::get_rule_stats,
+            cmds::get_chain_candidates,
+            cmds::validate_proxy_chain,
+            cmds::save_proxy_chains,
+            cmds::get_tunnels,
+            cmds::add_tunnel,
+            cmds::update_tunnel,
+            cmds::delete_tunnel,
+            cmds::check_tunnels,
+            cmds::get_listeners,
+            cmds::add_listener,
+            cmds::update_listener,
+            cmds::toggle_listener,
+            cmds::delete_listener,
+            cmds::set_inbound_auth,
+            cmds::get_core_capabilities,
+            cmds::check_core_compatibility,
+            cmds::take_startup_profile_prompt,
+            cmds::save_pause_windows,
+            cmds::get_proxy_pause,
+            cmds::toggle_pause,
+            cmds::get_app_rules,
+            cmds::add_app_rule,
+            cmds::update_app_rule,
+            cmds::delete_app_rule,
+            cmds::get_throttle_rules,
+            cmds::add_throttle_rule,
+            cmds::update_throttle_rule,
+            cmds::delete_throttle_rule,
+            cmds::list_processes,
+            cmds::get_tun_status,
+            cmds::grant_tun_privileges,
+            cmds::parse_bypass_list,
+            cmds::get_default_bypass_list,
             cmds::open_app_dir,
             cmds::open_logs_dir,
             cmds::open_web_url,
@@ -38,8 +149,18 @@ fn main() -> std::io::Result<()> {
             cmds::restart_sidecar,
             cmds::grant_permission,
             // clash
+            cmds::get_app_snapshot,
             cmds::get_clash_info,
+            cmds::query_api_requests,
+            cmds::clear_api_requests,
+            cmds::record_api_request,
             cmds::get_clash_logs,
+            cmds::query_clash_logs,
+            cmds::subscribe_clash_logs,
+            cmds::unsubscribe_clash_logs,
+            cmds::get_clash_memory_history,
+            cmds::get_stream_states,
+            cmds::reconnect_streams,
             cmds::patch_clash_config,
             cmds::change_clash_core,
             cmds::get_runtime_config,
@@ -47,10 +168,48 @@ fn main() -> std::io::Result<()> {
             cmds::get_runtime_exists,
             cmds::get_runtime_logs,
             cmds::clash_api_get_proxy_delay,
+            cmds::test_url_matrix,
+            cmds::select_proxy,
+            cmds::select_fastest_all,
+            cmds::pin_groups_to_region,
+            cmds::reset_group_selections,
+            cmds::get_node_info,
+            cmds::enrich_nodes,
+            cmds::get_core_cache,
+            cmds::flush_fakeip_cache,
+            cmds::clear_core_cache,
+            cmds::get_maintenance_report,
+            cmds::run_maintenance,
+            cmds::get_artifact_cache,
+            cmds::purge_artifact_cache,
+            cmds::get_failover_status,
+            cmds::get_country_stats,
+            cmds::clear_country_stats,
+            cmds::get_onboarding_state,
+            cmds::onboarding_detect_env,
+            cmds::onboarding_probe_mirrors,
+            cmds::onboarding_detect_clients,
+            cmds::onboarding_set_language,
+            cmds::onboarding_set_mirror,
+            cmds::onboarding_set_core,
+            cmds::onboarding_import_client,
+            cmds::onboarding_apply,
+            cmds::onboarding_finish,
+            cmds::preview_client_import,
+            cmds::import_client_data,
+            cmds::export_flattened_config,
+            cmds::get_web_uis,
+            cmds::install_web_ui,
+            cmds::remove_web_ui,
+            cmds::open_web_ui,
             cmds::uwp::invoke_uwp_tool,
+            cmds::uwp::get_uwp_apps,
+            cmds::uwp::set_uwp_loopback,
             // updater
             cmds::fetch_latest_core_versions,
             cmds::update_core,
+            cmds::install_core_from_file,
+            cmds::select_core_file,
             cmds::get_core_version,
             // utils
             cmds::collect_logs,
@@ -61,21 +220,39 @@ fn main() -> std::io::Result<()> {
             // profile
             cmds::get_profiles,
             cmds::enhance_profiles,
+            cmds::preview_config_changes,
             cmds::patch_profiles_config,
+            cmds::set_profile_core,
             cmds::view_profile,
             cmds::patch_profile,
             cmds::create_profile,
             cmds::import_profile,
             cmds::reorder_profile,
             cmds::update_profile,
+            cmds::get_profile_history,
+            cmds::undo_last_profile_operation,
+            cmds::get_pending_script,
+            cmds::approve_remote_script,
+            cmds::reject_remote_script,
             cmds::delete_profile,
             cmds::read_profile_file,
             cmds::save_profile_file,
+            cmds::format_yaml,
+            cmds::lint_profile_file,
+            cmds::apply_profile_file,
+            cmds::test_script_chain,
             cmds::save_window_size_state,
+            cmds::reset_window_state,
             // service mode
             cmds::service::check_service,
             cmds::service::install_service,
             cmds::service::uninstall_service,
+            // systemd
+            cmds::systemd::generate_systemd_unit,
+            cmds::systemd::install_systemd_unit,
+            cmds::systemd::uninstall_systemd_unit,
+            cmds::systemd::set_systemd_unit_enabled,
+            cmds::systemd::get_systemd_unit_status,
         ]);
 
     #[cfg(target_os = "macos")]
@@ -109,6 +286,7 @@ fn main() -> std::io::Result<()> {
         tauri::RunEvent::Exit => {
             resolve::resolve_reset();
             api::process::kill_children();
+            core::app_updater::AppUpdater::global().install_on_exit();
             app_handle.exit(0);
         }
         #[cfg(target_os = "macos")]
@@ -124,6 +302,10 @@ fn main() -> std::io::Result<()> {
                         app_handle.get_window("main").map(|win| {
                             let _ = win.hide();
                         });
+                        resolve::refresh_low_power(app_handle);
+                    }
+                    tauri::WindowEvent::Focused(_) | tauri::WindowEvent::Resized(_) => {
+                        resolve::refresh_low_power(app_handle);
                     }
                     _ => {}
                 }
@@ -134,13 +316,18 @@ fn main() -> std::io::Result<()> {
             if label == "main" {
                 match event {
                     tauri::WindowEvent::CloseRequested { .. } => {
-                        // log::info!(target: "app", "window close requested");
+                        // tracing::info!("window close requested");
                         let _ = resolve::save_window_state(app_handle, true);
                     }
                     tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) => {
-                        // log::info!(target: "app", "window moved or resized");
+                        // tracing::info!("window moved or resized");
                         std::thread::sleep(std::time::Duration::from_nanos(1));
                         let _ = resolve::save_window_state(app_handle, false);
+                        resolve::refresh_low_power(app_handle);
+                    }
+                    tauri::WindowEvent::Focused(_) => resolve::refresh_low_power(app_handle),
+                    tauri::WindowEvent::Destroyed => {
+                        core::low_power::LowPower::global().set_hidden(true);
                     }
                     _ => {}
                 }
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{sync::Arc, time::Instant};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BootStage {
+    pub name: &'static str,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BootReport {
+    /// unix timestamp in millis
+    pub started_at: i64,
+    pub total_ms: u64,
+    pub finished: bool,
+    pub stages: Vec<BootStage>,
+}
+
+/// run the startup stages in order and record the timing and failures
+/// so that "app opens but nothing works" can be diagnosed with `get_boot_report`
+pub struct Boot {
+    report: Arc<Mutex<BootReport>>,
+    instant: Instant,
+}
+
+impl Boot {
+    pub fn global() -> &'static Boot {
+        static BOOT: OnceCell<Boot> = OnceCell::new();
+
+        BOOT.get_or_init(|| Boot {
+            report: Arc::new(Mutex::new(BootReport {
+                started_at: Local::now().timestamp_millis(),
+                ..BootReport::default()
+            })),
+            instant: Instant::now(),
+        })
+    }
+
+    /// a failed stage will not stop the following stages
+    pub fn stage<T>(&self, name: &'static str, f: impl FnOnce() -> Result<T>) -> Option<T> {
+        tracing::trace!("boot stage `{name}`");
+
+        let instant = Instant::now();
+        let result = f();
+        let duration_ms = instant.elapsed().as_millis() as u64;
+
+        let error = result.as_ref().err().map(|err| format!("{err:#}"));
+        if let Some(error) = error.as_ref() {
+            tracing::error!("boot stage `{name}` failed: {error}");
+        }
+
+        self.report.lock().stages.push(BootStage {
+            name,
+            duration_ms,
+            error,
+        });
+        result.ok()
+    }
+
+    pub fn finish(&self) {
+        let mut report = self.report.lock();
+        report.total_ms = self.instant.elapsed().as_millis() as u64;
+        report.finished = true;
+
+        let failed = report.stages.iter().filter(|s| s.error.is_some()).count();
+        tracing::info!(
+            "boot finished in {}ms, {failed} stage(s) failed",
+            report.total_ms
+        );
+    }
+
+    pub fn report(&self) -> BootReport {
+        self.report.lock().clone()
+    }
+}
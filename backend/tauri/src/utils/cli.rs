@@ -0,0 +1,192 @@
+use super::dirs;
+use crate::{
+    config::IVerge,
+    core::control::{self, Request, Response, Status},
+};
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+
+/// the verbs handled by the cli, other args are passed to the app
+/// e.g. `--autostart` and the deep links
+const VERBS: [&str; 9] = [
+    "profile",
+    "core",
+    "proxy",
+    "system-proxy",
+    "status",
+    "help",
+    "--help",
+    "-h",
+    "--version",
+];
+
+/// run the core, the scheduler and the local apis without the window
+/// the window is opened from the tray or by launching the app again
+pub const SERVICE_ONLY_ARG: &str = "--service";
+
+pub fn is_service_only() -> bool {
+    std::env::args().any(|arg| arg == SERVICE_ONLY_ARG)
+}
+
+#[derive(Parser)]
+#[command(
+    name = "clash-nyanpasu",
+    version,
+    about = "Control the running Clash Nyanpasu",
+    after_help = "Start with `--service` to run without the window"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// manage the profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    /// manage the clash core
+    Core {
+        #[command(subcommand)]
+        command: CoreCommands,
+    },
+    /// select the proxy of the groups
+    Proxy {
+        #[command(subcommand)]
+        command: ProxyCommands,
+    },
+    /// turn on or off the system proxy
+    SystemProxy { state: Switch },
+    /// show the status of the running instance
+    Status {
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// update the remote profile
+    Update { uid: String },
+    /// activate the profile
+    Switch { uid: String },
+}
+
+#[derive(Subcommand)]
+enum CoreCommands {
+    Restart,
+}
+
+#[derive(Subcommand)]
+enum ProxyCommands {
+    /// select the node of a selector group
+    Set { group: String, node: String },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Switch {
+    On,
+    Off,
+}
+
+/// run the cli if the args start with a verb
+/// return the exit code, `None` means launching the app as usual
+pub fn run() -> Option<i32> {
+    let verb = std::env::args().nth(1)?;
+    if !VERBS.contains(&verb.as_str()) {
+        return None;
+    }
+
+    // the release build is a gui app on windows, print to the parent console
+    #[cfg(target_os = "windows")]
+    unsafe {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+
+    let cli = Cli::parse();
+    match tauri::async_runtime::block_on(execute(cli.command)) {
+        Ok(_) => Some(0),
+        Err(err) => {
+            eprintln!("error: {err}");
+            Some(1)
+        }
+    }
+}
+
+async fn execute(command: Commands) -> Result<()> {
+    let (request, json) = match command {
+        Commands::Profile { command } => match command {
+            ProfileCommands::Update { uid } => (Request::UpdateProfile { uid }, false),
+            ProfileCommands::Switch { uid } => (Request::SwitchProfile { uid }, false),
+        },
+        Commands::Core {
+            command: CoreCommands::Restart,
+        } => (Request::RestartCore, false),
+        Commands::Proxy {
+            command: ProxyCommands::Set { group, node },
+        } => (Request::SelectProxy { group, node }, false),
+        Commands::SystemProxy { state } => (
+            Request::SetSystemProxy {
+                enable: matches!(state, Switch::On),
+            },
+            false,
+        ),
+        Commands::Status { json } => (Request::Status, json),
+    };
+
+    let is_status = matches!(request, Request::Status);
+    let response = send(&request).await?;
+    if !response.ok {
+        bail!(response.error.unwrap_or("unknown error".into()));
+    }
+
+    match (is_status, response.data) {
+        (true, Some(data)) if json => println!("{}", serde_json::to_string_pretty(&data)?),
+        (true, Some(data)) => print_status(&serde_json::from_value(data)?),
+        _ => println!("ok"),
+    }
+    Ok(())
+}
+
+/// send the request to the embed server of the running instance
+async fn send(request: &Request) -> Result<Response> {
+    // 与主程序保持一致，便携版的配置目录在程序目录下
+    let _ = unsafe { dirs::init_portable_flag() };
+
+    let token = control::read_token()?;
+    let port = IVerge::get_singleton_port();
+
+    let response = reqwest::ClientBuilder::new()
+        .no_proxy()
+        .build()?
+        .post(format!("http://127.0.0.1:{port}/ipc"))
+        .bearer_auth(token)
+        .json(request)
+        .send()
+        .await
+        .map_err(|err| anyhow!("failed to connect to the running app: {err}"))?;
+
+    Ok(response.json::<Response>().await?)
+}
+
+fn print_status(status: &Status) {
+    let on_off = |b: bool| if b { "on" } else { "off" };
+
+    println!(
+        "core:           {} ({})",
+        status.core,
+        status.core_version.as_deref().unwrap_or("not running")
+    );
+    println!("mode:           {}", status.mode.as_deref().unwrap_or("-"));
+    println!("mixed port:     {}", status.mixed_port);
+    println!("system proxy:   {}", on_off(status.system_proxy));
+    println!("tun mode:       {}", on_off(status.tun_mode));
+    println!(
+        "profile:        {} ({})",
+        status.current_profile_name.as_deref().unwrap_or("-"),
+        status.current_profile.as_deref().unwrap_or("-")
+    );
+}
@@ -0,0 +1,502 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use percent_encoding::percent_decode_str;
+use serde_yaml::{Mapping, Value};
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+/// the share link schemes could be converted to the clash proxies
+pub const LINK_SCHEMES: [&str; 6] = ["ss", "vmess", "trojan", "vless", "hysteria2", "hy2"];
+
+/// the content recognized from a text
+#[derive(Debug, Clone, PartialEq)]
+pub enum Detected {
+    /// the proxies converted from the share links
+    Proxies(Vec<Mapping>),
+    /// a subscription url
+    Subscription(String),
+}
+
+/// recognize the share links or a subscription url from a text
+/// the lines could not be converted are ignored
+pub fn detect(text: &str) -> Option<Detected> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let proxies = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| is_share_link(line))
+        .filter_map(|line| match parse_link(line) {
+            Ok(proxy) => Some(proxy),
+            Err(err) => {
+                tracing::debug!("failed to convert the share link: {err}");
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    if !proxies.is_empty() {
+        return Some(Detected::Proxies(proxies));
+    }
+
+    // 只识别单独一行的订阅链接，避免普通网址也弹出提示
+    if !text.contains(char::is_whitespace) && is_subscription(text) {
+        return Some(Detected::Subscription(text.into()));
+    }
+    None
+}
+
+pub fn is_share_link(line: &str) -> bool {
+    line.split_once("://").map_or(false, |(scheme, _)| {
+        LINK_SCHEMES.contains(&scheme.to_lowercase().as_str())
+    })
+}
+
+/// the common patterns of the subscription urls
+fn is_subscription(text: &str) -> bool {
+    const KEYWORDS: [&str; 4] = ["sub", "clash", "token=", "flag="];
+
+    match Url::parse(text) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+            let rest = format!("{}?{}", url.path(), url.query().unwrap_or_default());
+            let rest = rest.to_lowercase();
+            KEYWORDS.iter().any(|keyword| rest.contains(keyword))
+        }
+        _ => false,
+    }
+}
+
+/// convert a share link to a clash proxy
+pub fn parse_link(link: &str) -> Result<Mapping> {
+    let (scheme, _) = link
+        .split_once("://")
+        .ok_or(anyhow!("invalid share link"))?;
+
+    match scheme.to_lowercase().as_str() {
+        "ss" => parse_ss(link),
+        "vmess" => parse_vmess(link),
+        "trojan" => parse_trojan(link),
+        "vless" => parse_vless(link),
+        "hysteria2" | "hy2" => parse_hysteria2(link),
+        scheme => bail!("unsupported scheme `{scheme}`"),
+    }
+}
+
+/// a local profile with the proxies and a selector of them
+pub fn to_profile(mut proxies: Vec<Mapping>) -> Result<String> {
+    // clash 不允许重名的节点
+    let mut seen = HashSet::new();
+    let mut names = Vec::new();
+    for proxy in proxies.iter_mut() {
+        let name = proxy
+            .get("name")
+            .and_then(|name| name.as_str())
+            .unwrap_or("proxy")
+            .to_string();
+
+        let mut unique = name.clone();
+        let mut index = 1;
+        while !seen.insert(unique.clone()) {
+            index += 1;
+            unique = format!("{name} {index}");
+        }
+        insert(proxy, "name", unique.clone());
+        names.push(Value::from(unique));
+    }
+
+    let mut group = Mapping::new();
+    insert(&mut group, "name", "PROXY");
+    insert(&mut group, "type", "select");
+    insert(&mut group, "proxies", names);
+
+    let mut config = Mapping::new();
+    insert(
+        &mut config,
+        "proxies",
+        proxies.into_iter().map(Value::Mapping).collect::<Vec<_>>(),
+    );
+    insert(&mut config, "proxy-groups", vec![Value::Mapping(group)]);
+    insert(&mut config, "rules", vec!["MATCH,PROXY"]);
+
+    serde_yaml::to_string(&config).context("failed to convert the proxies to yaml")
+}
+
+/// `ss://base64(method:password)@host:port/?plugin=...#name`
+/// or the legacy `ss://base64(method:password@host:port)#name`
+fn parse_ss(link: &str) -> Result<Mapping> {
+    let body = &link["ss://".len()..];
+
+    if !body.contains('@') {
+        let (body, name) = match body.split_once('#') {
+            Some((body, name)) => (body, Some(decode(name))),
+            None => (body, None),
+        };
+        let decoded = decode_base64(body)?;
+        let (user, addr) = decoded.rsplit_once('@').ok_or(anyhow!("invalid ss link"))?;
+        let (method, password) = user.split_once(':').ok_or(anyhow!("invalid ss link"))?;
+        let (server, port) = split_host_port(addr)?;
+
+        let name = name.unwrap_or(format!("{server}:{port}"));
+        let mut proxy = proxy(name, "ss", &server, port);
+        insert(&mut proxy, "cipher", method);
+        insert(&mut proxy, "password", password);
+        return Ok(proxy);
+    }
+
+    let url = Url::parse(link)?;
+    let (server, port) = host_port(&url)?;
+    let (method, password) = match url.password() {
+        Some(password) => (decode(url.username()), decode(password)),
+        None => {
+            let user = decode_base64(&decode(url.username()))?;
+            let (method, password) = user.split_once(':').ok_or(anyhow!("invalid ss link"))?;
+            (method.to_string(), password.to_string())
+        }
+    };
+
+    let mut proxy = proxy(link_name(&url, &server, port), "ss", &server, port);
+    insert(&mut proxy, "cipher", method);
+    insert(&mut proxy, "password", password);
+
+    let query = query_map(&url);
+    if let Some(plugin) = query.get("plugin") {
+        apply_ss_plugin(&mut proxy, plugin)?;
+    }
+    Ok(proxy)
+}
+
+/// `obfs-local;obfs=http;obfs-host=example.com`
+fn apply_ss_plugin(proxy: &mut Mapping, plugin: &str) -> Result<()> {
+    let mut parts = plugin.split(';');
+    let name = parts.next().unwrap_or_default();
+    let opts = parts
+        .map(|part| part.split_once('=').unwrap_or((part, "true")))
+        .collect::<HashMap<_, _>>();
+
+    let mut plugin_opts = Mapping::new();
+    match name {
+        "obfs-local" | "simple-obfs" => {
+            insert(proxy, "plugin", "obfs");
+            insert(
+                &mut plugin_opts,
+                "mode",
+                opts.get("obfs").copied().unwrap_or("http"),
+            );
+            if let Some(host) = opts.get("obfs-host") {
+                insert(&mut plugin_opts, "host", *host);
+            }
+        }
+        "v2ray-plugin" => {
+            insert(proxy, "plugin", "v2ray-plugin");
+            insert(&mut plugin_opts, "mode", "websocket");
+            if opts.contains_key("tls") {
+                insert(&mut plugin_opts, "tls", true);
+            }
+            if let Some(host) = opts.get("host") {
+                insert(&mut plugin_opts, "host", *host);
+            }
+            if let Some(path) = opts.get("path") {
+                insert(&mut plugin_opts, "path", *path);
+            }
+        }
+        name => bail!("unsupported ss plugin `{name}`"),
+    }
+    insert(proxy, "plugin-opts", plugin_opts);
+    Ok(())
+}
+
+/// `vmess://base64(json)` in the v2rayN format
+fn parse_vmess(link: &str) -> Result<Mapping> {
+    let json = decode_base64(&link["vmess://".len()..])?;
+    let json: serde_json::Value = serde_json::from_str(&json).context("invalid vmess link")?;
+
+    // 各家客户端导出的数字字段有的是字符串
+    let field = |key: &str| match json.get(key) {
+        Some(serde_json::Value::String(s)) if !s.is_empty() => Some(s.clone()),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+
+    let server = field("add").ok_or(anyhow!("the server is missing"))?;
+    let port = field("port")
+        .and_then(|port| port.parse::<u16>().ok())
+        .ok_or(anyhow!("invalid port"))?;
+    let name = field("ps").unwrap_or(format!("{server}:{port}"));
+
+    let mut proxy = proxy(name, "vmess", &server, port);
+    insert(
+        &mut proxy,
+        "uuid",
+        field("id").ok_or(anyhow!("the uuid is missing"))?,
+    );
+    insert(
+        &mut proxy,
+        "alterId",
+        field("aid")
+            .and_then(|aid| aid.parse::<u64>().ok())
+            .unwrap_or(0),
+    );
+    insert(&mut proxy, "cipher", field("scy").unwrap_or("auto".into()));
+    if field("tls").as_deref() == Some("tls") {
+        insert(&mut proxy, "tls", true);
+        if let Some(sni) = field("sni") {
+            insert(&mut proxy, "servername", sni);
+        }
+    }
+
+    let network = field("net").unwrap_or("tcp".into());
+    apply_transport(&mut proxy, &network, field("host"), field("path"))?;
+    Ok(proxy)
+}
+
+/// `trojan://password@host:port?sni=example.com&type=ws#name`
+fn parse_trojan(link: &str) -> Result<Mapping> {
+    let url = Url::parse(link)?;
+    let (server, port) = host_port(&url)?;
+    let query = query_map(&url);
+
+    let mut proxy = proxy(link_name(&url, &server, port), "trojan", &server, port);
+    insert(&mut proxy, "password", decode(url.username()));
+    if let Some(sni) = query.get("sni").or(query.get("peer")) {
+        insert(&mut proxy, "sni", sni.as_str());
+    }
+    if is_true(query.get("allowInsecure")) {
+        insert(&mut proxy, "skip-cert-verify", true);
+    }
+
+    let network = query.get("type").map_or("tcp", String::as_str);
+    let path = transport_path(&query, network);
+    apply_transport(&mut proxy, network, query.get("host").cloned(), path)?;
+    Ok(proxy)
+}
+
+/// `vless://uuid@host:port?security=reality&sni=example.com&pbk=...&sid=...#name`
+fn parse_vless(link: &str) -> Result<Mapping> {
+    let url = Url::parse(link)?;
+    let (server, port) = host_port(&url)?;
+    let query = query_map(&url);
+
+    let mut proxy = proxy(link_name(&url, &server, port), "vless", &server, port);
+    insert(&mut proxy, "uuid", decode(url.username()));
+    insert(&mut proxy, "udp", true);
+
+    match query.get("security").map(String::as_str) {
+        Some("tls") | Some("xtls") => {
+            insert(&mut proxy, "tls", true);
+        }
+        Some("reality") => {
+            insert(&mut proxy, "tls", true);
+            let mut reality = Mapping::new();
+            if let Some(pbk) = query.get("pbk") {
+                insert(&mut reality, "public-key", pbk.as_str());
+            }
+            if let Some(sid) = query.get("sid") {
+                insert(&mut reality, "short-id", sid.as_str());
+            }
+            insert(&mut proxy, "reality-opts", reality);
+        }
+        _ => {}
+    }
+    if let Some(sni) = query.get("sni") {
+        insert(&mut proxy, "servername", sni.as_str());
+    }
+    if let Some(fp) = query.get("fp") {
+        insert(&mut proxy, "client-fingerprint", fp.as_str());
+    }
+    if let Some(flow) = query.get("flow").filter(|flow| !flow.is_empty()) {
+        insert(&mut proxy, "flow", flow.as_str());
+    }
+
+    let network = query.get("type").map_or("tcp", String::as_str);
+    let path = transport_path(&query, network);
+    apply_transport(&mut proxy, network, query.get("host").cloned(), path)?;
+    Ok(proxy)
+}
+
+/// `hysteria2://auth@host:port?sni=example.com&obfs=salamander&obfs-password=...#name`
+fn parse_hysteria2(link: &str) -> Result<Mapping> {
+    let url = Url::parse(link)?;
+    let (server, port) = host_port(&url)?;
+    let query = query_map(&url);
+
+    let mut proxy = proxy(link_name(&url, &server, port), "hysteria2", &server, port);
+    insert(&mut proxy, "password", decode(url.username()));
+    if let Some(sni) = query.get("sni") {
+        insert(&mut proxy, "sni", sni.as_str());
+    }
+    if is_true(query.get("insecure")) {
+        insert(&mut proxy, "skip-cert-verify", true);
+    }
+    if let Some(obfs) = query.get("obfs") {
+        insert(&mut proxy, "obfs", obfs.as_str());
+        if let Some(password) = query.get("obfs-password") {
+            insert(&mut proxy, "obfs-password", password.as_str());
+        }
+    }
+    Ok(proxy)
+}
+
+/// the transport options shared by vmess, vless and trojan
+fn apply_transport(
+    proxy: &mut Mapping,
+    network: &str,
+    host: Option<String>,
+    path: Option<String>,
+) -> Result<()> {
+    let mut opts = Mapping::new();
+    let key = match network {
+        "" | "tcp" => return Ok(()),
+        "ws" => {
+            if let Some(path) = path {
+                insert(&mut opts, "path", path);
+            }
+            if let Some(host) = host {
+                let mut headers = Mapping::new();
+                insert(&mut headers, "Host", host);
+                insert(&mut opts, "headers", headers);
+            }
+            "ws-opts"
+        }
+        "grpc" => {
+            if let Some(name) = path {
+                insert(&mut opts, "grpc-service-name", name);
+            }
+            "grpc-opts"
+        }
+        "h2" => {
+            if let Some(path) = path {
+                insert(&mut opts, "path", path);
+            }
+            if let Some(host) = host {
+                insert(&mut opts, "host", vec![host]);
+            }
+            "h2-opts"
+        }
+        network => bail!("unsupported network `{network}`"),
+    };
+
+    insert(proxy, "network", network);
+    insert(proxy, key, opts);
+    Ok(())
+}
+
+/// grpc uses `serviceName` instead of `path`
+fn transport_path(query: &HashMap<String, String>, network: &str) -> Option<String> {
+    match network {
+        "grpc" => query.get("serviceName").cloned(),
+        _ => query.get("path").cloned(),
+    }
+}
+
+fn proxy(name: String, r#type: &str, server: &str, port: u16) -> Mapping {
+    let mut proxy = Mapping::new();
+    insert(&mut proxy, "name", name);
+    insert(&mut proxy, "type", r#type);
+    insert(&mut proxy, "server", server);
+    insert(&mut proxy, "port", port);
+    proxy
+}
+
+fn insert<V: Into<Value>>(map: &mut Mapping, key: &str, value: V) {
+    map.insert(key.into(), value.into());
+}
+
+fn query_map(url: &Url) -> HashMap<String, String> {
+    url.query_pairs().into_owned().collect()
+}
+
+fn is_true(value: Option<&String>) -> bool {
+    matches!(value.map(String::as_str), Some("1" | "true"))
+}
+
+fn link_name(url: &Url, server: &str, port: u16) -> String {
+    url.fragment()
+        .map(decode)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(format!("{server}:{port}"))
+}
+
+fn host_port(url: &Url) -> Result<(String, u16)> {
+    let host = url.host_str().ok_or(anyhow!("the server is missing"))?;
+    let port = url.port().ok_or(anyhow!("the port is missing"))?;
+    Ok((trim_brackets(host), port))
+}
+
+fn split_host_port(addr: &str) -> Result<(String, u16)> {
+    let (host, port) = addr
+        .rsplit_once(':')
+        .ok_or(anyhow!("the port is missing"))?;
+    let port = port.parse::<u16>().context("invalid port")?;
+    Ok((trim_brackets(host), port))
+}
+
+/// the ipv6 address is wrapped by brackets in the url
+fn trim_brackets(host: &str) -> String {
+    host.trim_start_matches('[').trim_end_matches(']').into()
+}
+
+fn decode(text: &str) -> String {
+    percent_decode_str(text).decode_utf8_lossy().into_owned()
+}
+
+/// accept both the standard and the url safe alphabets, with or without padding
+fn decode_base64(text: &str) -> Result<String> {
+    let text = text
+        .trim()
+        .trim_end_matches('=')
+        .replace('-', "+")
+        .replace('_', "/");
+    let bytes = STANDARD_NO_PAD.decode(text).context("invalid base64")?;
+    String::from_utf8(bytes).context("invalid utf-8")
+}
+
+#[test]
+fn test_parse_link() {
+    let ss = parse_link("ss://YWVzLTI1Ni1nY206cGFzcw==@1.2.3.4:8388#HK%2001").unwrap();
+    assert_eq!(ss.get("name").unwrap().as_str(), Some("HK 01"));
+    assert_eq!(ss.get("cipher").unwrap().as_str(), Some("aes-256-gcm"));
+    assert_eq!(ss.get("port").unwrap().as_u64(), Some(8388));
+
+    let legacy =
+        parse_link("ss://Y2hhY2hhMjAtaWV0Zi1wb2x5MTMwNTpwQHNzQDEuMi4zLjQ6ODM4OA==").unwrap();
+    assert_eq!(legacy.get("password").unwrap().as_str(), Some("p@ss"));
+    assert_eq!(legacy.get("name").unwrap().as_str(), Some("1.2.3.4:8388"));
+
+    let vmess = parse_link("vmess://eyJ2IjoiMiIsInBzIjoiSlAiLCJhZGQiOiJqcC5leGFtcGxlLmNvbSIsInBvcnQiOiI0NDMiLCJpZCI6ImI4MzEzODFkLTYzMjQtNGQ1My1hZDRmLThjZGE0OGIzMDgxMSIsImFpZCI6IjAiLCJuZXQiOiJ3cyIsInR5cGUiOiJub25lIiwiaG9zdCI6ImpwLmV4YW1wbGUuY29tIiwicGF0aCI6Ii9yYXkiLCJ0bHMiOiJ0bHMifQ==").unwrap();
+    assert_eq!(
+        vmess.get("server").unwrap().as_str(),
+        Some("jp.example.com")
+    );
+    assert_eq!(vmess.get("network").unwrap().as_str(), Some("ws"));
+    assert_eq!(vmess.get("tls").unwrap().as_bool(), Some(true));
+
+    let trojan =
+        parse_link("trojan://secret@[2001:db8::1]:443?sni=example.com&allowInsecure=1#US").unwrap();
+    assert_eq!(trojan.get("server").unwrap().as_str(), Some("2001:db8::1"));
+    assert_eq!(
+        trojan.get("skip-cert-verify").unwrap().as_bool(),
+        Some(true)
+    );
+
+    assert!(parse_link("vless://uuid@example.com:443?type=kcp").is_err());
+}
+
+#[test]
+fn test_detect() {
+    let text = "trojan://secret@example.com:443#A\nnot a link\ntrojan://secret@example.com:443#A";
+    match detect(text) {
+        Some(Detected::Proxies(proxies)) => assert_eq!(proxies.len(), 2),
+        other => panic!("unexpected {other:?}"),
+    }
+
+    assert_eq!(
+        detect("https://example.com/api/v1/client/subscribe?token=abc"),
+        Some(Detected::Subscription(
+            "https://example.com/api/v1/client/subscribe?token=abc".into()
+        ))
+    );
+    assert_eq!(detect("https://example.com/blog/post"), None);
+    assert_eq!(detect("see https://example.com/sub"), None);
+}
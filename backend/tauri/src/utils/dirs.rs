@@ -14,6 +14,8 @@ static CLASH_CONFIG: &str = "config.yaml";
 static VERGE_CONFIG: &str = "verge.yaml";
 static PROFILE_YAML: &str = "profiles.yaml";
 static STORAGE_DB: &str = "storage.db";
+static PROFILES_DB: &str = "profiles.db";
+static SECRETS_FILE: &str = "secrets.json";
 
 static mut RESOURCE_DIR: Option<PathBuf> = None;
 
@@ -92,6 +94,36 @@ pub fn app_logs_dir() -> Result<PathBuf> {
     Ok(app_home_dir()?.join("logs"))
 }
 
+/// crash reports dir
+pub fn app_crashes_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("crashes"))
+}
+
+/// the snapshots before the destructive profile operations
+pub fn app_trash_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("trash"))
+}
+
+/// the downloaded core artifacts
+pub fn app_artifacts_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("artifacts"))
+}
+
+/// the downloaded third-party web uis
+pub fn app_web_ui_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("web-ui"))
+}
+
+/// the verified installers waiting for the app to exit
+pub fn app_updates_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("updates"))
+}
+
+/// the state of the cores not in use
+pub fn app_core_archive_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("core-archives"))
+}
+
 pub fn clash_path() -> Result<PathBuf> {
     Ok(app_home_dir()?.join(CLASH_CONFIG))
 }
@@ -108,6 +140,15 @@ pub fn storage_path() -> Result<PathBuf> {
     Ok(app_home_dir()?.join(STORAGE_DB))
 }
 
+pub fn profiles_db_path() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join(PROFILES_DB))
+}
+
+/// the fallback of the secrets when the keyring is unavailable
+pub fn secrets_path() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join(SECRETS_FILE))
+}
+
 #[allow(unused)]
 pub fn app_res_dir() -> Result<PathBuf> {
     unsafe {
@@ -151,6 +192,16 @@ pub fn service_log_file() -> Result<PathBuf> {
     Ok(log_file)
 }
 
+/// the core binary is placed next to the app executable
+pub fn core_path(core: &str) -> Result<PathBuf> {
+    let name = if cfg!(windows) {
+        format!("{core}.exe")
+    } else {
+        core.to_string()
+    };
+    Ok(tauri::utils::platform::current_exe()?.with_file_name(name))
+}
+
 pub fn path_to_str(path: &PathBuf) -> Result<&str> {
     let path_str = path
         .as_os_str()
@@ -89,7 +89,7 @@ pub fn open_file(path: PathBuf) -> Result<()> {
 
     // use vscode first
     if let Err(err) = open::with(&path, code) {
-        log::error!(target: "app", "failed to open file with VScode `{err}`");
+        tracing::error!("failed to open file with VScode `{err}`");
         // default open
         open::that(path)?;
     }
@@ -97,10 +97,15 @@ pub fn open_file(path: PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// compare the secrets in constant time, so the time doesn't leak the matched prefix
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 #[macro_export]
 macro_rules! error {
     ($result: expr) => {
-        log::error!(target: "app", "{}", $result);
+        tracing::error!("{}", $result);
     };
 }
 
@@ -108,13 +113,13 @@ macro_rules! error {
 macro_rules! log_err {
     ($result: expr) => {
         if let Err(err) = $result {
-            log::error!(target: "app", "{err}");
+            tracing::error!("{err}");
         }
     };
 
     ($result: expr, $err_str: expr) => {
         if let Err(_) = $result {
-            log::error!(target: "app", "{}", $err_str);
+            tracing::error!("{}", $err_str);
         }
     };
 }
@@ -123,9 +128,9 @@ macro_rules! log_err {
 macro_rules! trace_err {
     ($result: expr, $err_str: expr) => {
         if let Err(err) = $result {
-            log::trace!(target: "app", "{}, err {}", $err_str, err);
+            tracing::trace!("{}, err {}", $err_str, err);
         }
-    }
+    };
 }
 
 /// wrap the anyhow error
@@ -136,7 +141,7 @@ macro_rules! wrap_err {
         match $stat {
             Ok(a) => Ok(a),
             Err(err) => {
-                log::error!(target: "app", "{}", err.to_string());
+                tracing::error!("{}", err.to_string());
                 Err(format!("{}", err.to_string()))
             }
         }
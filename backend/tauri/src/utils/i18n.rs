@@ -0,0 +1,83 @@
+//! the messages shown by the backend, e.g. the os notifications, the tray menu and the dialogs
+//! the english text is the key like the frontend locales, `{name}` is replaced by the arguments
+use crate::config::Config;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+type Catalog = HashMap<String, String>;
+
+static CATALOGS: Lazy<HashMap<&'static str, Catalog>> = Lazy::new(|| {
+    [
+        ("zh", include_str!("../../locales/zh.json")),
+        ("ru", include_str!("../../locales/ru.json")),
+    ]
+    .into_iter()
+    .map(|(lang, data)| {
+        let catalog = serde_json::from_str(data).unwrap_or_else(|err| {
+            tracing::error!("failed to parse the `{lang}` messages: {err}");
+            Catalog::new()
+        });
+        (lang, catalog)
+    })
+    .collect()
+});
+
+/// the language of the ui, english by default
+pub fn language() -> String {
+    let language = { Config::verge().latest().language.clone() };
+    language.unwrap_or("en".into())
+}
+
+pub fn translate(lang: &str, key: &str, args: &[(&str, &dyn ToString)]) -> String {
+    let text = CATALOGS
+        .get(lang)
+        .and_then(|catalog| catalog.get(key))
+        .map_or(key, |text| text.as_str());
+
+    args.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), &value.to_string())
+    })
+}
+
+pub fn t(key: &str) -> String {
+    translate(&language(), key, &[])
+}
+
+/// e.g. `t_args("Version {version}", &[("version", &version)])`
+pub fn t_args(key: &str, args: &[(&str, &dyn ToString)]) -> String {
+    translate(&language(), key, args)
+}
+
+#[test]
+fn test_catalogs() {
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names = text
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    let zh = CATALOGS.get("zh").unwrap();
+    for (lang, catalog) in CATALOGS.iter() {
+        assert_eq!(catalog.len(), zh.len(), "the `{lang}` messages are missing");
+        for (key, text) in catalog {
+            assert!(zh.contains_key(key), "`{key}` is not in the `zh` messages");
+            assert_eq!(placeholders(key), placeholders(text), "{lang}: {key}");
+        }
+    }
+
+    let count = 3;
+    assert_eq!(translate("zh", "Quit", &[]), "退出");
+    assert_eq!(translate("en", "Quit", &[]), "Quit");
+    assert_eq!(
+        translate("en", "Version {version}", &[("version", &"1.4.0")]),
+        "Version 1.4.0"
+    );
+    assert_eq!(
+        translate("ja", "Unknown {count}", &[("count", &count)]),
+        "Unknown 3"
+    );
+}
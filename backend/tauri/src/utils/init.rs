@@ -1,74 +1,9 @@
 use crate::config::*;
-use crate::utils::{dirs, help};
+use crate::utils::{dirs, help, logging};
 use anyhow::Result;
-use chrono::Local;
-use log::LevelFilter;
-use log4rs::append::console::ConsoleAppender;
-use log4rs::append::file::FileAppender;
-use log4rs::config::{Appender, Logger, Root};
-use log4rs::encode::pattern::PatternEncoder;
 use std::fs;
 use tauri::PackageInfo;
 
-/// initialize this instance's log file
-fn init_log() -> Result<()> {
-    let log_dir = dirs::app_logs_dir()?;
-    if !log_dir.exists() {
-        let _ = fs::create_dir_all(&log_dir);
-    }
-
-    let log_level = Config::verge().data().get_log_level();
-    if log_level == LevelFilter::Off {
-        return Ok(());
-    }
-
-    let local_time = Local::now().format("%Y-%m-%d-%H%M").to_string();
-    let log_file = format!("{}.log", local_time);
-    let log_file = log_dir.join(log_file);
-
-    let log_pattern = match log_level {
-        LevelFilter::Trace => "{d(%Y-%m-%d %H:%M:%S)} {l} [{M}] - {m}{n}",
-        _ => "{d(%Y-%m-%d %H:%M:%S)} {l} - {m}{n}",
-    };
-
-    let encode = Box::new(PatternEncoder::new(log_pattern));
-
-    let stdout = ConsoleAppender::builder().encoder(encode.clone()).build();
-    let tofile = FileAppender::builder().encoder(encode).build(log_file)?;
-
-    let mut logger_builder = Logger::builder();
-    let mut root_builder = Root::builder();
-
-    let log_more = log_level == LevelFilter::Trace || log_level == LevelFilter::Debug;
-
-    #[cfg(feature = "verge-dev")]
-    {
-        logger_builder = logger_builder.appenders(["file", "stdout"]);
-        if log_more {
-            root_builder = root_builder.appenders(["file", "stdout"]);
-        } else {
-            root_builder = root_builder.appenders(["stdout"]);
-        }
-    }
-    #[cfg(not(feature = "verge-dev"))]
-    {
-        logger_builder = logger_builder.appenders(["file"]);
-        if log_more {
-            root_builder = root_builder.appenders(["file"]);
-        }
-    }
-
-    let (config, _) = log4rs::config::Config::builder()
-        .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .appender(Appender::builder().build("file", Box::new(tofile)))
-        .logger(logger_builder.additive(false).build("app", log_level))
-        .build_lossy(root_builder.build(log_level));
-
-    log4rs::init_config(config)?;
-
-    Ok(())
-}
-
 /// Initialize all the config files
 /// before tauri setup
 pub fn init_config() -> Result<()> {
@@ -77,7 +12,7 @@ pub fn init_config() -> Result<()> {
         let _ = dirs::init_portable_flag();
     }
 
-    let _ = init_log();
+    let _ = logging::init();
 
     crate::log_err!(dirs::app_home_dir().map(|app_dir| {
         if !app_dir.exists() {
@@ -122,6 +57,12 @@ pub fn init_config() -> Result<()> {
 /// initialize app resources
 /// after tauri setup
 pub fn init_resources(package_info: &PackageInfo) -> Result<()> {
+    copy_resources(package_info, false).map(|_| ())
+}
+
+/// copy the resource files to the app dir, return the count of the copied files
+/// `force` overwrites them even if they are up to date
+pub fn copy_resources(package_info: &PackageInfo, force: bool) -> Result<usize> {
     let app_dir = dirs::app_home_dir()?;
     let res_dir = dirs::app_resources_dir(package_info)?;
 
@@ -139,20 +80,24 @@ pub fn init_resources(package_info: &PackageInfo) -> Result<()> {
 
     // copy the resource file
     // if the source file is newer than the destination file, copy it over
+    let mut copied = 0;
     for file in file_list.iter() {
         let src_path = res_dir.join(file);
         let dest_path = app_dir.join(file);
 
-        let handle_copy = || {
+        let mut handle_copy = || {
             match fs::copy(&src_path, &dest_path) {
-                Ok(_) => log::debug!(target: "app", "resources copied '{file}'"),
+                Ok(_) => {
+                    tracing::debug!("resources copied '{file}'");
+                    copied += 1;
+                }
                 Err(err) => {
-                    log::error!(target: "app", "failed to copy resources '{file}', {err}")
+                    tracing::error!("failed to copy resources '{file}', {err}")
                 }
             };
         };
 
-        if src_path.exists() && !dest_path.exists() {
+        if src_path.exists() && (force || !dest_path.exists()) {
             handle_copy();
             continue;
         }
@@ -165,15 +110,15 @@ pub fn init_resources(package_info: &PackageInfo) -> Result<()> {
                 if src_modified > dest_modified {
                     handle_copy();
                 } else {
-                    log::debug!(target: "app", "skipping resource copy '{file}'");
+                    tracing::debug!("skipping resource copy '{file}'");
                 }
             }
             _ => {
-                log::debug!(target: "app", "failed to get modified '{file}'");
+                tracing::debug!("failed to get modified '{file}'");
                 handle_copy();
             }
         };
     }
 
-    Ok(())
+    Ok(copied)
 }
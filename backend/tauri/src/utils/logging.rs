@@ -0,0 +1,236 @@
+use crate::{
+    config::{Config, LogFormat},
+    utils::dirs,
+};
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate};
+use once_cell::sync::{Lazy, OnceCell};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tracing_subscriber::{
+    filter::LevelFilter, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter,
+    Layer, Registry,
+};
+
+static FILTER_HANDLE: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
+/// the files being written, the log cleaner skips them
+static ACTIVE_FILES: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(Default::default);
+
+pub fn is_active_file(path: &Path) -> bool {
+    let files = ACTIVE_FILES.lock().unwrap_or_else(|err| err.into_inner());
+    files.contains(path)
+}
+
+fn set_active(old: Option<&Path>, new: Option<&Path>) {
+    let mut files = ACTIVE_FILES.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(old) = old {
+        files.remove(old);
+    }
+    if let Some(new) = new {
+        files.insert(new.to_path_buf());
+    }
+}
+
+/// initialize the tracing subscriber, the logs of the `log` crate are forwarded too
+pub fn init() -> Result<()> {
+    let log_dir = dirs::app_logs_dir()?;
+    if !log_dir.exists() {
+        let _ = fs::create_dir_all(&log_dir);
+    }
+
+    let (filter, format, max_size) = {
+        let verge = Config::verge();
+        let verge = verge.data();
+        (
+            build_filter(verge.get_log_level(), verge.app_log_filter.as_deref())?,
+            verge.app_log_format.unwrap_or_default(),
+            verge.app_log_max_size.unwrap_or(10),
+        )
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let writer = Mutex::new(RotatingFile::new(log_dir, max_size * 1024 * 1024, 0)?);
+    let file_layer = match format {
+        LogFormat::Pretty => fmt::layer().with_ansi(false).with_writer(writer).boxed(),
+        LogFormat::Json => fmt::layer().json().with_writer(writer).boxed(),
+    };
+    let stdout_layer = cfg!(feature = "verge-dev").then(fmt::layer);
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(stdout_layer)
+        .try_init()?;
+
+    let _ = FILTER_HANDLE.set(handle);
+    Ok(())
+}
+
+/// apply the log level and the filters of the config without restart
+pub fn reload() -> Result<()> {
+    let filter = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        build_filter(verge.get_log_level(), verge.app_log_filter.as_deref())?
+    };
+    let handle = FILTER_HANDLE
+        .get()
+        .ok_or(anyhow!("the logger is not initialized"))?;
+    handle.reload(filter)?;
+    tracing::info!("the log filter is reloaded");
+    Ok(())
+}
+
+/// the app logs at the level, the dependencies only at debug and trace,
+/// then the custom directives, e.g. `clash_verge::core::sync=trace,reqwest=debug`
+pub fn build_filter(level: LevelFilter, directives: Option<&str>) -> Result<EnvFilter> {
+    // 越详细的等级越大
+    let mut filter = if level >= LevelFilter::DEBUG {
+        level.to_string()
+    } else if level == LevelFilter::OFF {
+        "off".to_string()
+    } else {
+        format!("warn,{}={level}", env!("CARGO_CRATE_NAME"))
+    };
+    if let Some(directives) = directives.filter(|d| !d.trim().is_empty()) {
+        filter.push(',');
+        filter.push_str(directives.trim());
+    }
+    EnvFilter::try_new(&filter).map_err(|err| anyhow!("invalid log filter `{filter}`: {err}"))
+}
+
+/// the log file rotated daily or when it is larger than the max size
+/// the name is the creation time, which is parsed by the log cleaner
+/// shared by the app logs and the core logs
+pub struct RotatingFile {
+    dir: PathBuf,
+    /// 0 means no limit
+    max_size: u64,
+    /// the files kept in the dir, the oldest are removed when rotated, 0 means no limit
+    max_count: usize,
+    path: PathBuf,
+    file: File,
+    size: u64,
+    day: NaiveDate,
+}
+
+impl RotatingFile {
+    pub fn new(dir: PathBuf, max_size: u64, max_count: usize) -> Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let (path, file, day) = Self::create(&dir)?;
+        set_active(None, Some(&path));
+        prune_oldest(&dir, max_count);
+        Ok(Self {
+            dir,
+            max_size,
+            max_count,
+            path,
+            file,
+            size: 0,
+            day,
+        })
+    }
+
+    fn create(dir: &PathBuf) -> io::Result<(PathBuf, File, NaiveDate)> {
+        let now = Local::now();
+        let name = now.format("%Y-%m-%d-%H%M%S").to_string();
+        let mut path = dir.join(format!("{name}.log"));
+        let mut index = 1;
+        while path.exists() {
+            path = dir.join(format!("{name}_{index}.log"));
+            index += 1;
+        }
+        let file = File::create(&path)?;
+        Ok((path, file, now.date_naive()))
+    }
+
+    fn rotate_if_needed(&mut self) -> io::Result<()> {
+        let oversize = self.max_size > 0 && self.size >= self.max_size;
+        if oversize || Local::now().date_naive() != self.day {
+            let (path, file, day) = Self::create(&self.dir)?;
+            set_active(Some(&self.path), Some(&path));
+            self.path = path;
+            self.file = file;
+            self.day = day;
+            self.size = 0;
+            prune_oldest(&self.dir, self.max_count);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RotatingFile {
+    fn drop(&mut self) {
+        set_active(Some(&self.path), None);
+    }
+}
+
+/// keep the newest `max_count` log files, the names sort by the creation time
+fn prune_oldest(dir: &Path, max_count: usize) {
+    if max_count == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "log"))
+        .collect::<Vec<_>>();
+    files.sort();
+    let excess = files.len().saturating_sub(max_count);
+    for path in files.into_iter().take(excess) {
+        if !is_active_file(&path) {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.rotate_if_needed()?;
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[test]
+fn test_build_filter() {
+    assert!(build_filter(LevelFilter::INFO, None).is_ok());
+    assert!(build_filter(
+        LevelFilter::INFO,
+        Some("reqwest=debug,clash_verge::core=trace")
+    )
+    .is_ok());
+    assert!(build_filter(LevelFilter::INFO, Some("reqwest=nope")).is_err());
+}
+
+#[test]
+fn test_prune_oldest() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in [
+        "2024-01-01-000000.log",
+        "2024-01-02-000000.log",
+        "2024-01-02-000000_1.log",
+        "note.txt",
+    ] {
+        fs::write(dir.path().join(name), "").unwrap();
+    }
+    prune_oldest(dir.path(), 2);
+    assert!(!dir.path().join("2024-01-01-000000.log").exists());
+    assert!(dir.path().join("2024-01-02-000000.log").exists());
+    assert!(dir.path().join("2024-01-02-000000_1.log").exists());
+    assert!(dir.path().join("note.txt").exists());
+}
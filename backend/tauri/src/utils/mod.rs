@@ -1,8 +1,15 @@
+pub mod boot;
 pub mod candy;
+pub mod cli;
+pub mod converter;
 pub mod dirs;
 pub mod help;
+pub mod i18n;
 pub mod init;
+pub mod logging;
 pub mod resolve;
+pub mod secrets;
 pub mod server;
 pub mod tmpl;
+pub mod window_state;
 // mod winhelp;
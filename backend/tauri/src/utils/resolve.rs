@@ -1,11 +1,19 @@
-use crate::config::{ClashCore, IVerge, WindowState};
+use crate::config::{ClashCore, IProfiles, IVerge, StartupProfile, WindowState};
 use crate::core::tasks::{jobs::ProfilesJobGuard, JobsManager};
-use crate::{config::Config, core::*, utils::init, utils::server};
+use crate::{
+    config::Config,
+    core::*,
+    utils::{boot::Boot, cli, dirs, help, init, secrets, server, window_state},
+};
 use crate::{log_err, trace_err};
 use anyhow::Result;
 use semver::Version;
 use serde_yaml::Mapping;
-use std::net::TcpListener;
+use std::{
+    net::TcpListener,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
 use tauri::api::process::Command;
 use tauri::{App, AppHandle, Manager};
 
@@ -20,22 +28,247 @@ pub fn find_unused_port() -> Result<u16> {
                 .latest()
                 .verge_mixed_port
                 .unwrap_or(Config::clash().data().get_mixed_port());
-            log::warn!(target: "app", "use default port: {}", port);
+            tracing::warn!("use default port: {}", port);
             Ok(port)
         }
     }
 }
 
 /// handle something when start app
+/// the stages are recorded by the boot report
 pub fn resolve_setup(app: &mut App) {
     #[cfg(target_os = "macos")]
     app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
+    let boot = Boot::global();
     handle::Handle::global().init(app.app_handle());
 
-    log_err!(init::init_resources(app.package_info()));
+    boot.stage("resources", || init::init_resources(app.package_info()));
+    boot.stage("port", resolve_port);
+    boot.stage("startup_profile", resolve_startup_profile);
+    boot.stage("config", Config::init_config);
+    boot.stage("secrets", migrate_secrets);
+
+    // 上次没有正常退出时恢复系统 dns
+    boot.stage("restore_dns", sys_dns::restore);
+
+    // 先订阅，内核启动的事件才不会漏掉
+    boot.stage("event_bus", || {
+        selection::subscribe_events();
+        event_hook::subscribe_events();
+        stream::StreamManager::global().subscribe_events();
+        notification::Notifications::global().subscribe_events();
+        tray::TrayProxies::global().subscribe_events();
+        sysopt::Sysopt::global().subscribe_events();
+        failover::Failover::global().subscribe_events();
+        Ok(())
+    });
+
+    // 启动核心，开机自启时在后台等网络就绪再启动，不阻塞托盘和窗口
+    if sysopt::is_autostart() || cli::is_service_only() {
+        tauri::async_runtime::spawn(async {
+            wait_network().await;
+            let boot = Boot::global();
+            boot.stage("core", || CoreManager::global().init());
+            boot.stage("dns", sys_dns::update);
+        });
+    } else {
+        boot.stage("core", || CoreManager::global().init());
+        boot.stage("dns", sys_dns::update);
+    }
+    boot.stage("streams", || {
+        log_stream::LogStream::global().start();
+        memory_stream::MemoryStream::global().start();
+        traffic_history::TrafficHistory::global().start();
+        country_stats::CountryStats::global().start();
+
+        let enable_tun = { Config::verge().data().enable_tun_mode.clone() };
+        if enable_tun.unwrap_or(false) {
+            tun::TunManager::global().start_monitor();
+        }
+        Ok(())
+    });
+
+    // setup a simple http server for singleton
+    boot.stage("embed_server", || {
+        server::embed_server(app.app_handle());
+        Ok(())
+    });
+
+    // 在异步运行时里监听端口
+    boot.stage("control_api", || {
+        tauri::async_runtime::block_on(control_api::ControlApi::global().restart())
+    });
+    boot.stage("controller_proxy", || {
+        tauri::async_runtime::block_on(controller_proxy::ControllerProxy::global().restart())
+    });
+    boot.stage("metrics", || {
+        tauri::async_runtime::block_on(metrics::Metrics::global().restart())
+    });
+    boot.stage("throttle", || {
+        tauri::async_runtime::block_on(throttle::Throttle::global().restart())
+    });
+    boot.stage("remote_management", || {
+        tauri::async_runtime::block_on(remote::RemoteManagement::global().restart())
+    });
+    boot.stage("auto_launch", || sysopt::Sysopt::global().init_launch());
+    boot.stage("deep_link", || {
+        deep_link::register()?;
+        if let Some(link) = deep_link::from_args() {
+            deep_link::handle_link(link);
+        }
+        Ok(())
+    });
+    boot.stage("notification", || {
+        notification::Notifications::global().start();
+        Ok(())
+    });
+    boot.stage("clipboard", || {
+        clipboard::ClipboardWatcher::global().start();
+        Ok(())
+    });
+    boot.stage("sysproxy", || {
+        sysopt::Sysopt::global().init_sysproxy()?;
+        network::NetworkMonitor::global().start();
+        power::PowerMonitor::global().start();
+        Ok(())
+    });
+
+    boot.stage("tray", || {
+        tray::TrayProxies::global().start();
+        tray::TrayTraffic::global().start();
+        tray::Tray::update_systray(&app.app_handle())?;
+        handle::Handle::update_systray_part()
+    });
+    #[cfg(target_os = "linux")]
+    boot.stage("linux_status", || {
+        linux_status::LinuxStatus::global().start();
+        Ok(())
+    });
 
-    // 处理随机端口
+    boot.stage("window", || {
+        let (silent_start, minimized) = {
+            let verge = Config::verge();
+            let verge = verge.data();
+            (
+                verge.enable_silent_start.unwrap_or(false),
+                verge.auto_launch_minimized.unwrap_or(false),
+            )
+        };
+        let service_only = cli::is_service_only();
+        if service_only {
+            tracing::info!("running in the service-only mode, the window is not created");
+        }
+        // 没有托盘时不能隐藏窗口，否则就找不回来了
+        #[cfg(target_os = "linux")]
+        let no_tray = linux_status::LinuxStatus::global().tray_protocol()
+            == linux_status::TrayProtocol::Unavailable;
+        #[cfg(not(target_os = "linux"))]
+        let no_tray = false;
+        let hidden = silent_start || (minimized && sysopt::is_autostart());
+        if !service_only && (no_tray || !hidden) {
+            create_window(&app.app_handle());
+        } else {
+            low_power::LowPower::global().refresh();
+        }
+        Ok(())
+    });
+
+    boot.stage("hotkey", || hotkey::Hotkey::global().init(app.app_handle()));
+
+    // 上传之前没有上传的崩溃报告
+    boot.stage("crash_reports", || {
+        tauri::async_runtime::spawn(crash::upload_pending());
+        Ok(())
+    });
+
+    // setup jobs
+    boot.stage("jobs", || {
+        JobsManager::global_register()?; // init task manager
+        ProfilesJobGuard::global().lock().init()?;
+        Ok(())
+    });
+
+    boot.finish();
+}
+
+/// the `ask` policy, the window picks the profile once after launch
+static ASK_PROFILE: AtomicBool = AtomicBool::new(false);
+
+pub fn take_startup_profile_prompt() -> bool {
+    ASK_PROFILE.swap(false, Ordering::SeqCst)
+}
+
+/// 按照启动策略选择 profile，需要在生成配置之前
+fn resolve_startup_profile() -> Result<()> {
+    let policy = { Config::verge().latest().startup_profile.clone() };
+    let profiles = Config::profiles();
+    let mut profiles = profiles.data();
+
+    let exists = |uid: &String| profiles.get_item(uid).is_ok_and(|item| item.is_profile());
+    let current = match policy.unwrap_or_default() {
+        StartupProfile::Pinned(uid) if exists(&uid) => Some(uid),
+        StartupProfile::Pinned(uid) => {
+            tracing::warn!(
+                "the pinned profile `{uid}` is missing or not a profile, use the last used one"
+            );
+            profiles.get_current()
+        }
+        StartupProfile::LastUsed => profiles.get_current(),
+        StartupProfile::Ask => {
+            ASK_PROFILE.store(true, Ordering::SeqCst);
+            profiles.get_current()
+        }
+    };
+
+    // 上次使用的 profile 被删除时退回第一个
+    let current = current.filter(exists).or_else(|| {
+        profiles.get_items().and_then(|items| {
+            items
+                .iter()
+                .filter(|item| item.is_profile())
+                .find_map(|item| item.uid.clone())
+        })
+    });
+    if current != profiles.get_current() {
+        tracing::info!("activate the profile on launch: {current:?}");
+        profiles.current = current;
+        profiles.save_file()?;
+    }
+    Ok(())
+}
+
+/// 旧版本明文保存的订阅链接和控制器密钥，重新保存一次移到 keyring
+fn migrate_secrets() -> Result<()> {
+    let is_plain = |value: Option<&str>| {
+        value.is_some_and(|value| {
+            !value.is_empty() && !value.starts_with(secrets::PLACEHOLDER_PREFIX)
+        })
+    };
+
+    let profiles = help::read_yaml::<IProfiles>(&dirs::profiles_path()?)?;
+    let items = profiles.items.unwrap_or_default();
+    if items.iter().any(|item| is_plain(item.url.as_deref())) {
+        tracing::info!("move the subscription urls to the keyring");
+        Config::profiles().data().save_file()?;
+    }
+
+    let clash = help::read_merge_mapping(&dirs::clash_path()?)?;
+    if is_plain(clash.get("secret").and_then(|secret| secret.as_str())) {
+        tracing::info!("move the controller secret to the keyring");
+        Config::clash().data().save_config()?;
+    }
+
+    let verge = help::read_yaml::<IVerge>(&dirs::verge_path()?)?;
+    if is_plain(verge.controller_proxy_token.as_deref()) {
+        tracing::info!("move the controller proxy token to the keyring");
+        Config::verge().data().save_file()?;
+    }
+    Ok(())
+}
+
+/// 处理随机端口
+fn resolve_port() -> Result<()> {
     let enable_random_port = Config::verge().latest().enable_random_port.unwrap_or(false);
 
     let mut port = Config::verge()
@@ -56,50 +289,49 @@ pub fn resolve_setup(app: &mut App) {
         verge_mixed_port: Some(port),
         ..IVerge::default()
     });
-    let _ = Config::verge().data().save_file();
     let mut mapping = Mapping::new();
     mapping.insert("mixed-port".into(), port.into());
     Config::clash().data().patch_config(mapping);
-    let _ = Config::clash().data().save_config();
-
-    // 启动核心
-    log::trace!("init config");
-    log_err!(Config::init_config());
 
-    log::trace!("launch core");
-    log_err!(CoreManager::global().init());
-
-    // setup a simple http server for singleton
-    log::trace!("launch embed server");
-    server::embed_server(app.app_handle());
+    // 两边都改完再保存，避免端口不一致
+    let verge = Config::verge().data().save_file();
+    let clash = Config::clash().data().save_config();
+    verge.and(clash)
+}
 
-    log::trace!("init system tray");
-    log_err!(tray::Tray::update_systray(&app.app_handle()));
+/// wait at most `auto_launch_delay` seconds until the network is up
+async fn wait_network() {
+    let delay = { Config::verge().latest().auto_launch_delay };
+    let deadline = Instant::now() + Duration::from_secs(delay.unwrap_or(0));
 
-    let silent_start = { Config::verge().data().enable_silent_start.clone() };
-    if !silent_start.unwrap_or(false) {
-        create_window(&app.app_handle());
+    while Instant::now() < deadline {
+        if network::is_network_up() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
     }
 
-    log_err!(sysopt::Sysopt::global().init_launch());
-    log_err!(sysopt::Sysopt::global().init_sysproxy());
-
-    log_err!(handle::Handle::update_systray_part());
-    log_err!(hotkey::Hotkey::global().init(app.app_handle()));
-
-    // setup jobs
-    log_err!(JobsManager::global_register()); // init task manager
-    log_err!(ProfilesJobGuard::global().lock().init());
+    if !network::is_network_up() {
+        tracing::warn!("network is still down after the boot delay");
+    }
 }
 
 /// reset system proxy
 pub fn resolve_reset() {
     log_err!(sysopt::Sysopt::global().reset_sysproxy());
+    log_err!(sys_dns::restore());
+    // 特权助手运行的内核在退出后继续运行，下次打开时接管，tun 不会断开
+    #[cfg(target_os = "macos")]
+    if crate::core::mac_helper::is_enabled() {
+        return;
+    }
     log_err!(CoreManager::global().stop_core());
 }
 
 /// create main window
 pub fn create_window(app_handle: &AppHandle) {
+    low_power::LowPower::global().set_hidden(false);
+
     if let Some(window) = app_handle.get_window("main") {
         trace_err!(window.unminimize(), "set win unminimize");
         trace_err!(window.show(), "set win visible");
@@ -118,25 +350,14 @@ pub fn create_window(app_handle: &AppHandle) {
     let win_state = &Config::verge().latest().window_size_state.clone();
     match win_state {
         Some(state) => {
-            builder = builder
-                .inner_size(state.width, state.height)
-                .position(state.x, state.y)
+            builder = builder.inner_size(state.width, state.height);
+            if restore_position() {
+                builder = builder.position(state.x, state.y);
+            }
         }
         _ => {
-            #[cfg(target_os = "windows")]
-            {
-                builder = builder.inner_size(800.0, 636.0).center();
-            }
-
-            #[cfg(target_os = "macos")]
-            {
-                builder = builder.inner_size(800.0, 642.0).center();
-            }
-
-            #[cfg(target_os = "linux")]
-            {
-                builder = builder.inner_size(800.0, 642.0).center();
-            }
+            let (width, height) = window_state::default_size();
+            builder = builder.inner_size(width, height).center();
         }
     };
 
@@ -153,36 +374,9 @@ pub fn create_window(app_handle: &AppHandle) {
             .build()
         {
             Ok(win) => {
-                if let Some(state) = win_state {
-                    if state.maximized {
-                        trace_err!(win.maximize(), "set win maximize");
-                    }
-                    if state.fullscreen {
-                        trace_err!(win.set_fullscreen(true), "set win fullscreen");
-                    }
-                }
-                log::trace!("try to calculate the monitor size");
-                let center = (|| -> Result<bool> {
-                    let mut center = false;
-                    let monitor = win.current_monitor()?.ok_or(anyhow::anyhow!(""))?;
-                    let size = monitor.size();
-                    let pos = win.outer_position()?;
-
-                    if pos.x < -400
-                        || pos.x > (size.width - 200).try_into()?
-                        || pos.y < -200
-                        || pos.y > (size.height - 200).try_into()?
-                    {
-                        center = true;
-                    }
-                    Ok(center)
-                })();
-
-                if center.unwrap_or(true) {
-                    trace_err!(win.center(), "set win center");
-                }
+                trace_err!(window_state::restore(&win), "restore win state");
 
-                log::trace!("try to create window");
+                tracing::trace!("try to create window");
                 let app_handle = app_handle.clone();
 
                 // 加点延迟避免界面闪一下
@@ -195,23 +389,47 @@ pub fn create_window(app_handle: &AppHandle) {
                         trace_err!(window.unminimize(), "set win unminimize");
                         trace_err!(window.set_focus(), "set win focus");
                     } else {
-                        log::error!(target: "app", "failed to create window, get_window is None")
+                        tracing::error!("failed to create window, get_window is None")
                     }
                 });
             }
-            Err(err) => log::error!(target: "app", "failed to create window, {err}"),
+            Err(err) => tracing::error!("failed to create window, {err}"),
         }
     }
 
     #[cfg(target_os = "macos")]
-    crate::log_err!(builder
+    let window = builder
         .decorations(true)
         .hidden_title(true)
         .title_bar_style(tauri::TitleBarStyle::Overlay)
-        .build());
+        .visible(false)
+        .build();
 
     #[cfg(target_os = "linux")]
-    crate::log_err!(builder.decorations(true).transparent(false).build());
+    let window = builder
+        .decorations(true)
+        .transparent(false)
+        .visible(false)
+        .build();
+
+    // 先还原位置再显示，免得跳一下
+    #[cfg(not(target_os = "windows"))]
+    match window {
+        Ok(win) => {
+            trace_err!(window_state::restore(&win), "restore win state");
+            trace_err!(win.show(), "set win visible");
+            trace_err!(win.set_focus(), "set win focus");
+        }
+        Err(err) => tracing::error!("failed to create window, {err}"),
+    }
+}
+
+/// the windows could not get or set the global position on wayland
+pub(crate) fn restore_position() -> bool {
+    #[cfg(target_os = "linux")]
+    return linux_status::session_type() != linux_status::SessionType::Wayland;
+    #[cfg(not(target_os = "linux"))]
+    return true;
 }
 
 /// close main window
@@ -221,6 +439,15 @@ pub fn close_window(app_handle: &AppHandle) {
     }
 }
 
+/// enter the low power mode if the window is hidden, minimized or destroyed
+pub fn refresh_low_power(app_handle: &AppHandle) {
+    let hidden = match app_handle.get_window("main") {
+        Some(win) => !win.is_visible().unwrap_or(true) || win.is_minimized().unwrap_or(false),
+        None => true,
+    };
+    low_power::LowPower::global().set_hidden(hidden);
+}
+
 /// is window open
 pub fn is_window_open(app_handle: &AppHandle) -> bool {
     app_handle.get_window("main").is_some()
@@ -274,11 +501,14 @@ pub fn save_window_state(app_handle: &AppHandle, save_to_file: bool) -> Result<(
                 state.height = size.height;
             }
             let position = win.outer_position()?.to_logical(scale_factor);
-            if !state.maximized && !is_minimized {
+            // wayland 上拿到的位置总是 0,0，保留之前的
+            if !state.maximized && !is_minimized && restore_position() {
                 state.x = position.x;
                 state.y = position.y;
             }
+            let (maximized, fullscreen) = (state.maximized, state.fullscreen);
             verge.window_size_state = Some(state);
+            window_state::save(&win, &mut verge, maximized, fullscreen, is_minimized)?;
         }
         None => {
             verge.window_size_state = None;
@@ -296,7 +526,7 @@ pub fn save_window_state(app_handle: &AppHandle, save_to_file: bool) -> Result<(
 // TODO: use enum instead
 pub fn resolve_core_version(core_type: &ClashCore) -> Result<String> {
     let core = core_type.clone().to_string();
-    log::debug!(target: "app", "check config in `{core}`");
+    tracing::debug!("check config in `{core}`");
     let cmd = match core_type {
         ClashCore::ClashPremium | ClashCore::Mihomo | ClashCore::MihomoAlpha => {
             Command::new_sidecar(core)?.args(["-v"])
@@ -304,20 +534,25 @@ pub fn resolve_core_version(core_type: &ClashCore) -> Result<String> {
         ClashCore::ClashRs => Command::new_sidecar(core)?.args(["-V"]),
     };
     let out = cmd.output()?;
-    log::debug!(target: "app", "get core version: {:?}", out);
+    tracing::debug!("get core version: {:?}", out);
     if !out.status.success() {
         return Err(anyhow::anyhow!("failed to get core version"));
     }
-    let out = out.stdout.trim().split(' ').collect::<Vec<&str>>();
+    parse_core_version(&out.stdout).ok_or(anyhow::anyhow!("failed to get core version"))
+}
+
+/// find the version in the output of `-v`
+pub fn parse_core_version(out: &str) -> Option<String> {
+    let out = out.trim().split(' ').collect::<Vec<&str>>();
     for item in out {
-        log::debug!(target: "app", "check item: {}", item);
+        tracing::debug!("check item: {}", item);
         if item.starts_with('v')
             || item.starts_with("n")
             || item.starts_with("alpha")
             || Version::parse(item).is_ok()
         {
-            return Ok(item.to_string());
+            return Some(item.to_string());
         }
     }
-    Err(anyhow::anyhow!("failed to get core version"))
+    None
 }
@@ -0,0 +1,211 @@
+//! the secrets are kept in the os keyring instead of the yaml files
+//!
+//! the yaml files keep the placeholders like `secret://profiles/<uid>/url`,
+//! the real values are resolved when loading and stored again when saving.
+//! a json file only readable by the user is used when there is no keyring,
+//! e.g. the headless linux without the secret service.
+
+use super::dirs;
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde_yaml::Value;
+use std::{collections::HashMap, fs, path::Path};
+
+const SERVICE: &str = "clash-nyanpasu";
+pub const PLACEHOLDER_PREFIX: &str = "secret://";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    Keyring,
+    File,
+}
+
+struct Secrets {
+    backend: Backend,
+    /// avoid touching the keyring every time the config is saved
+    cache: Mutex<HashMap<String, String>>,
+}
+
+fn secrets() -> &'static Secrets {
+    static SECRETS: OnceCell<Secrets> = OnceCell::new();
+
+    SECRETS.get_or_init(|| {
+        let probe = keyring::Entry::new(SERVICE, "probe").and_then(|entry| entry.get_password());
+        let backend = match probe {
+            Ok(_) | Err(keyring::Error::NoEntry) => Backend::Keyring,
+            Err(err) => {
+                tracing::warn!("the keyring is unavailable, fallback to the file: {err}");
+                Backend::File
+            }
+        };
+        Secrets {
+            backend,
+            cache: Mutex::new(HashMap::new()),
+        }
+    })
+}
+
+pub fn set(key: &str, value: &str) -> Result<()> {
+    let secrets = secrets();
+    if secrets.cache.lock().get(key).map(|v| v.as_str()) == Some(value) {
+        return Ok(());
+    }
+
+    match secrets.backend {
+        Backend::Keyring => keyring::Entry::new(SERVICE, key)?
+            .set_password(value)
+            .with_context(|| format!("failed to save the secret `{key}` to the keyring"))?,
+        Backend::File => {
+            let mut map = read_file()?;
+            map.insert(key.into(), value.into());
+            write_file(&map)?;
+        }
+    }
+    secrets.cache.lock().insert(key.into(), value.into());
+    Ok(())
+}
+
+pub fn get(key: &str) -> Result<Option<String>> {
+    let secrets = secrets();
+    if let Some(value) = secrets.cache.lock().get(key) {
+        return Ok(Some(value.clone()));
+    }
+
+    let value = match secrets.backend {
+        Backend::Keyring => match keyring::Entry::new(SERVICE, key)?.get_password() {
+            Ok(value) => Some(value),
+            Err(keyring::Error::NoEntry) => None,
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read the secret `{key}` from the keyring"))
+            }
+        },
+        Backend::File => read_file()?.remove(key),
+    };
+    if let Some(value) = &value {
+        secrets.cache.lock().insert(key.into(), value.clone());
+    }
+    Ok(value)
+}
+
+pub fn delete(key: &str) -> Result<()> {
+    let secrets = secrets();
+    secrets.cache.lock().remove(key);
+
+    match secrets.backend {
+        Backend::Keyring => match keyring::Entry::new(SERVICE, key)?.delete_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(err.into()),
+        },
+        Backend::File => {
+            let mut map = read_file()?;
+            if map.remove(key).is_some() {
+                write_file(&map)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// save the value and return the placeholder written to the yaml
+/// the plaintext is kept if failed, so nothing is lost
+pub fn protect(key: &str, value: &str) -> String {
+    if value.is_empty() || value.starts_with(PLACEHOLDER_PREFIX) {
+        return value.into();
+    }
+    match set(key, value) {
+        Ok(_) => format!("{PLACEHOLDER_PREFIX}{key}"),
+        Err(err) => {
+            tracing::error!("{err:?}");
+            value.into()
+        }
+    }
+}
+
+/// the real value of the placeholder, other values are returned as is
+pub fn resolve(value: &str) -> String {
+    let Some(key) = value.strip_prefix(PLACEHOLDER_PREFIX) else {
+        return value.into();
+    };
+    match get(key) {
+        Ok(Some(value)) => value,
+        Ok(None) => {
+            tracing::error!("the secret `{key}` is not found");
+            value.into()
+        }
+        Err(err) => {
+            tracing::error!("{err:?}");
+            value.into()
+        }
+    }
+}
+
+/// resolve all the placeholders in the yaml value
+pub fn resolve_value(value: &mut Value) {
+    match value {
+        Value::String(s) if s.starts_with(PLACEHOLDER_PREFIX) => *s = resolve(s),
+        Value::Sequence(seq) => seq.iter_mut().for_each(resolve_value),
+        Value::Mapping(map) => map.iter_mut().for_each(|(_, v)| resolve_value(v)),
+        _ => {}
+    }
+}
+
+/// the yaml file with the real values, used when it leaves this device
+/// e.g. the backups and the sync
+pub fn read_resolved(path: &Path) -> Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    let text = String::from_utf8_lossy(&data);
+    if !text.contains(PLACEHOLDER_PREFIX) {
+        return Ok(data);
+    }
+
+    // 不是 yaml 的文件原样返回
+    let Ok(mut value) = serde_yaml::from_str::<Value>(&text) else {
+        return Ok(data);
+    };
+    resolve_value(&mut value);
+    Ok(serde_yaml::to_string(&value)?.into_bytes())
+}
+
+fn read_file() -> Result<HashMap<String, String>> {
+    let path = dirs::secrets_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+fn write_file(map: &HashMap<String, String>) -> Result<()> {
+    let path = dirs::secrets_path()?;
+    let data = serde_json::to_vec_pretty(map)?;
+
+    #[cfg(unix)]
+    {
+        use std::{io::Write, os::unix::fs::OpenOptionsExt};
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&path)?;
+        file.write_all(&data)?;
+    }
+    #[cfg(not(unix))]
+    fs::write(&path, data)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_resolve_plain_value() {
+    let mut value = serde_yaml::from_str::<Value>("url: https://example.com/sub?token=1").unwrap();
+    resolve_value(&mut value);
+    assert_eq!(
+        value["url"].as_str(),
+        Some("https://example.com/sub?token=1")
+    );
+    assert_eq!(protect("any", ""), "");
+    assert_eq!(protect("any", "secret://any"), "secret://any");
+}
@@ -1,26 +1,53 @@
 extern crate warp;
 
-use super::resolve;
-use crate::config::IVerge;
-use anyhow::{bail, Result};
+use super::{cli, resolve};
+use crate::{
+    config::IVerge,
+    core::{
+        control, deep_link,
+        ipc_stream::{Channel, IpcStream},
+        pac,
+    },
+};
+use anyhow::{anyhow, bail, Result};
 use port_scanner::local_port_available;
-use tauri::AppHandle;
+use std::{collections::HashMap, future::Future, net::SocketAddr, time::Duration};
+use tauri::{
+    async_runtime::{JoinHandle, Mutex as TokioMutex},
+    AppHandle,
+};
+use tokio::sync::oneshot;
 use warp::Filter;
 
+/// the graceful shutdown waits for the open connections at most
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// check whether there is already exists
 pub fn check_singleton() -> Result<()> {
     let port = IVerge::get_singleton_port();
 
     if !local_port_available(port) {
+        // 只启动后端时不去打开已有实例的窗口
+        if cli::is_service_only() {
+            bail!("app exists");
+        }
+
         tauri::async_runtime::block_on(async {
-            let url = format!("http://127.0.0.1:{port}/commands/visible");
+            // 将 deep link 转发给已经运行的实例
+            let url = match deep_link::from_args() {
+                Some(link) => reqwest::Url::parse_with_params(
+                    &format!("http://127.0.0.1:{port}/commands/scheme"),
+                    &[("param", link)],
+                )?,
+                None => reqwest::Url::parse(&format!("http://127.0.0.1:{port}/commands/visible"))?,
+            };
             let resp = reqwest::get(url).await?.text().await?;
 
             if &resp == "ok" {
                 bail!("app exists");
             }
 
-            log::error!("failed to setup singleton listen server");
+            tracing::error!("failed to setup singleton listen server");
             Ok(())
         })
     } else {
@@ -28,10 +55,11 @@ pub fn check_singleton() -> Result<()> {
     }
 }
 
-/// The embed server only be used to implement singleton process
-/// maybe it can be used as pac server later
+/// The embed server is used to implement singleton process
+/// and serve the pac script in pac mode
 pub fn embed_server(app_handle: AppHandle) {
     let port = IVerge::get_singleton_port();
+    let token = control::ipc_token();
 
     tauri::async_runtime::spawn(async move {
         let commands = warp::path!("commands" / "visible").map(move || {
@@ -39,6 +67,109 @@ pub fn embed_server(app_handle: AppHandle) {
             format!("ok")
         });
 
-        warp::serve(commands).bind(([127, 0, 0, 1], port)).await;
+        let scheme = warp::path!("commands" / "scheme")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(|query: HashMap<String, String>| {
+                if let Some(link) = query.get("param") {
+                    deep_link::handle_link(link.clone());
+                }
+                format!("ok")
+            });
+
+        // used by the cli, the token is written to the app home dir
+        let ipc = warp::path!("ipc")
+            .and(warp::post())
+            .and(warp::header::optional::<String>("authorization"))
+            .and(warp::body::json())
+            .and_then(
+                move |auth: Option<String>, request: control::Request| async move {
+                    let authorized = auth
+                        .as_deref()
+                        .and_then(|auth| auth.strip_prefix("Bearer "))
+                        .map_or(false, |auth| auth == token);
+
+                    let response = match authorized {
+                        true => control::Response::from(control::execute(request).await),
+                        false => control::Response::from(Err(anyhow!("unauthorized"))),
+                    };
+                    Ok::<_, warp::Rejection>(warp::reply::json(&response))
+                },
+            );
+
+        let pac = warp::path!("pac").map(|| {
+            warp::reply::with_header(
+                pac::current(),
+                "Content-Type",
+                "application/x-ns-proxy-autoconfig",
+            )
+        });
+
+        // the binary channel of the high frequency streams, used by the frontend
+        let streams = warp::path!("streams" / String)
+            .and(warp::query::<HashMap<String, String>>())
+            .and(warp::ws())
+            .and_then(
+                move |name: String, query: HashMap<String, String>, ws: warp::ws::Ws| async move {
+                    let authorized = query.get("token").map_or(false, |auth| auth == token);
+                    match (authorized, Channel::from_name(&name)) {
+                        (true, Some(channel)) => {
+                            Ok(ws.on_upgrade(move |socket| {
+                                IpcStream::global().serve(channel, socket)
+                            }))
+                        }
+                        _ => Err(warp::reject::not_found()),
+                    }
+                },
+            );
+
+        warp::serve(commands.or(scheme).or(ipc).or(pac).or(streams))
+            .bind(([127, 0, 0, 1], port))
+            .await;
     });
 }
+
+/// a local server restarted when its settings change, e.g. the control api
+/// it is bound inside the async runtime, and the old listener is closed before binding the new one
+#[derive(Default)]
+pub struct LocalServer {
+    running: TokioMutex<Option<(oneshot::Sender<()>, JoinHandle<()>)>>,
+}
+
+impl LocalServer {
+    /// `bind` gets the shutdown signal,
+    /// e.g. `|rx| warp::serve(routes).try_bind_with_graceful_shutdown(addr, async { rx.await.ok(); })`
+    pub async fn start<F, S, E>(&self, bind: F) -> Result<SocketAddr>
+    where
+        F: FnOnce(oneshot::Receiver<()>) -> std::result::Result<(SocketAddr, S), E>,
+        S: Future<Output = ()> + Send + 'static,
+        E: Into<anyhow::Error>,
+    {
+        let mut running = self.running.lock().await;
+        shutdown(running.take()).await;
+
+        let (tx, rx) = oneshot::channel::<()>();
+        let (addr, server) = bind(rx).map_err(Into::into)?;
+        *running = Some((tx, tauri::async_runtime::spawn(server)));
+        Ok(addr)
+    }
+
+    /// return false if it is not running
+    pub async fn stop(&self) -> bool {
+        let running = self.running.lock().await.take();
+        let stopped = running.is_some();
+        shutdown(running).await;
+        stopped
+    }
+}
+
+async fn shutdown(running: Option<(oneshot::Sender<()>, JoinHandle<()>)>) {
+    if let Some((tx, mut task)) = running {
+        let _ = tx.send(());
+        // 长连接会拖住 graceful shutdown，超时后直接结束
+        let finished = tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut task).await;
+        if finished.is_err() {
+            task.abort();
+            let _ = task.await;
+        }
+    }
+}
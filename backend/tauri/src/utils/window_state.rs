@@ -0,0 +1,224 @@
+//! the window state of each monitor layout
+//!
+//! a position saved with two monitors could be off-screen after unplugging one,
+//! so the states are saved by the layout of all the monitors, and the window is
+//! centered if its title bar is not on any monitor. the states of the layouts are
+//! physical pixels, since the scale factors differ between the monitors.
+
+use super::resolve;
+use crate::config::{Config, IVerge, WindowState};
+use anyhow::Result;
+use tauri::{AppHandle, LogicalSize, Manager, Monitor, PhysicalPosition, PhysicalSize, Window};
+
+/// the title bar should be draggable
+const TITLE_WIDTH: f64 = 100.;
+const TITLE_HEIGHT: f64 = 30.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Rect {
+    fn of(monitor: &Monitor) -> Rect {
+        let (pos, size) = (monitor.position(), monitor.size());
+        Rect {
+            x: pos.x as f64,
+            y: pos.y as f64,
+            width: size.width as f64,
+            height: size.height as f64,
+        }
+    }
+
+    /// the width and height of the overlapping area
+    fn overlap(&self, other: &Rect) -> (f64, f64) {
+        let width = (self.x + self.width).min(other.x + other.width) - self.x.max(other.x);
+        let height = (self.y + self.height).min(other.y + other.height) - self.y.max(other.y);
+        (width.max(0.), height.max(0.))
+    }
+}
+
+/// the default logical size of the window
+pub fn default_size() -> (f64, f64) {
+    #[cfg(target_os = "windows")]
+    return (800.0, 636.0);
+    #[cfg(not(target_os = "windows"))]
+    return (800.0, 642.0);
+}
+
+/// e.g. `0,0,2560x1440@1.5|2560,0,1920x1080@1`
+fn layout_key(monitors: &[Monitor]) -> String {
+    let mut monitors = monitors
+        .iter()
+        .map(|monitor| {
+            let (pos, size) = (monitor.position(), monitor.size());
+            format!(
+                "{},{},{}x{}@{}",
+                pos.x,
+                pos.y,
+                size.width,
+                size.height,
+                monitor.scale_factor()
+            )
+        })
+        .collect::<Vec<_>>();
+    monitors.sort();
+    monitors.join("|")
+}
+
+/// whether enough of the title bar is on one of the monitors
+fn is_reachable(window: &Rect, monitors: &[Rect]) -> bool {
+    let title = Rect {
+        height: TITLE_HEIGHT.min(window.height),
+        ..*window
+    };
+    monitors.iter().any(|monitor| {
+        let (width, height) = title.overlap(monitor);
+        width >= TITLE_WIDTH.min(window.width) && height > 0.
+    })
+}
+
+/// apply the state of the current monitor layout to the created window,
+/// and center the window if it's off-screen
+pub fn restore(win: &Window) -> Result<()> {
+    let monitors = win.available_monitors()?;
+    let key = layout_key(&monitors);
+    let state = {
+        let verge = Config::verge();
+        let verge = verge.latest();
+        let states = verge.window_states.as_ref();
+        match states.and_then(|states| states.get(&key)) {
+            Some(state) => Some((state.clone(), true)),
+            None => verge.window_size_state.clone().map(|state| (state, false)),
+        }
+    };
+    let rects = monitors.iter().map(Rect::of).collect::<Vec<_>>();
+
+    if let Some((state, true)) = &state {
+        tracing::debug!("restore the window state of the monitors `{key}`");
+        // 不超过最大的显示器
+        let width = rects.iter().map(|rect| rect.width).fold(0., f64::max);
+        let height = rects.iter().map(|rect| rect.height).fold(0., f64::max);
+        win.set_size(PhysicalSize::new(
+            state.width.min(width),
+            state.height.min(height),
+        ))?;
+        if resolve::restore_position() {
+            win.set_position(PhysicalPosition::new(state.x, state.y))?;
+        }
+    }
+
+    if resolve::restore_position() && !rects.is_empty() {
+        let (pos, size) = (win.outer_position()?, win.outer_size()?);
+        let rect = Rect {
+            x: pos.x as f64,
+            y: pos.y as f64,
+            width: size.width as f64,
+            height: size.height as f64,
+        };
+        if !is_reachable(&rect, &rects) {
+            tracing::info!("the window is off-screen, move it to the center");
+            win.center()?;
+        }
+    }
+
+    if let Some((state, _)) = state {
+        if state.maximized {
+            win.maximize()?;
+        }
+        if state.fullscreen {
+            win.set_fullscreen(true)?;
+        }
+    }
+    Ok(())
+}
+
+/// save the physical state of the current monitor layout
+/// the size and position are kept while maximized or minimized
+pub fn save(
+    win: &Window,
+    verge: &mut IVerge,
+    maximized: bool,
+    fullscreen: bool,
+    minimized: bool,
+) -> Result<()> {
+    let key = layout_key(&win.available_monitors()?);
+    let states = verge.window_states.get_or_insert_with(Default::default);
+    let previous = states.get(&key).cloned().unwrap_or_default();
+    let mut state = WindowState {
+        maximized,
+        fullscreen,
+        ..previous
+    };
+
+    if !maximized && !fullscreen && !minimized {
+        let size = win.inner_size()?;
+        if size.width > 0 && size.height > 0 {
+            state.width = size.width as f64;
+            state.height = size.height as f64;
+        }
+        // wayland 上拿到的位置总是 0,0，保留之前的
+        if resolve::restore_position() {
+            let pos = win.outer_position()?;
+            state.x = pos.x as f64;
+            state.y = pos.y as f64;
+        }
+    }
+    states.insert(key, state);
+    Ok(())
+}
+
+/// forget all the states, and move the window back to the center
+pub fn reset(app_handle: &AppHandle) -> Result<()> {
+    {
+        let verge = Config::verge();
+        let mut verge = verge.latest();
+        verge.window_size_state = None;
+        verge.window_states = None;
+        verge.save_file()?;
+    }
+
+    if let Some(win) = app_handle.get_window("main") {
+        let (width, height) = default_size();
+        win.set_fullscreen(false)?;
+        win.unmaximize()?;
+        win.set_size(LogicalSize::new(width, height))?;
+        win.center()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_is_reachable() {
+    let monitors = [
+        Rect {
+            x: 0.,
+            y: 0.,
+            width: 1920.,
+            height: 1080.,
+        },
+        Rect {
+            x: 1920.,
+            y: 0.,
+            width: 2560.,
+            height: 1440.,
+        },
+    ];
+    let window = |x, y| Rect {
+        x,
+        y,
+        width: 800.,
+        height: 600.,
+    };
+
+    assert!(is_reachable(&window(100., 100.), &monitors));
+    assert!(is_reachable(&window(3000., 1200.), &monitors));
+    // 标题栏只露出一点
+    assert!(!is_reachable(&window(-750., 100.), &monitors));
+    assert!(!is_reachable(&window(100., -590.), &monitors));
+    // 拔掉了第二个显示器
+    assert!(!is_reachable(&window(3000., 100.), &monitors[..1]));
+}
@@ -0,0 +1,81 @@
+import { getOctokit } from "@actions/github";
+import fs from "fs-extra";
+import fetch from "node-fetch";
+import { createHash } from "node:crypto";
+import path from "node:path";
+import { MANIFEST_DIR } from "./utils/env";
+import { consola } from "./utils/logger";
+
+const GITHUB_TOKEN = process.env.GITHUB_TOKEN || "";
+
+const MANIFEST_WEB_UI_PATH = path.join(MANIFEST_DIR, "web-ui.json");
+
+// keep in sync with `SOURCES` in backend/tauri/src/core/web_ui.rs
+const WEB_UI_SOURCES = {
+  yacd: { owner: "MetaCubeX", repo: "Yacd-meta" },
+  metacubexd: { owner: "MetaCubeX", repo: "metacubexd" },
+  zashboard: { owner: "Zephyruso", repo: "zashboard" },
+};
+
+export interface WebUiPin {
+  commit: string;
+  sha256: string;
+}
+
+export interface ManifestWebUi {
+  manifest_version: number;
+  pins: { [K in keyof typeof WEB_UI_SOURCES]?: WebUiPin };
+  updated_at: string | null; // ISO 8601
+}
+
+const MANIFEST_VERSION = 1;
+
+// the app installs the archive of the commit and checks it against the sha256
+const resolvePin = async (owner: string, repo: string): Promise<WebUiPin> => {
+  const octokit = getOctokit(GITHUB_TOKEN);
+  const branch = await octokit.rest.repos.getBranch({
+    owner,
+    repo,
+    branch: "gh-pages",
+  });
+  const commit = branch.data.commit.sha;
+  consola.debug(`${owner}/${repo} gh-pages: ${commit}`);
+
+  const resp = await fetch(
+    `https://github.com/${owner}/${repo}/archive/${commit}.zip`,
+  );
+  if (!resp.ok) {
+    throw new Error(`failed to download ${owner}/${repo}@${commit}`);
+  }
+  const sha256 = createHash("sha256")
+    .update(Buffer.from(await resp.arrayBuffer()))
+    .digest("hex");
+  return { commit, sha256 };
+};
+
+async function main() {
+  if (!GITHUB_TOKEN) {
+    consola.fatal("GITHUB_TOKEN is not set");
+    process.exit(1);
+  }
+
+  consola.start("Resolving web ui pins");
+  const manifest: ManifestWebUi = {
+    manifest_version: MANIFEST_VERSION,
+    pins: {},
+    updated_at: new Date().toISOString(),
+  };
+  for (const [id, { owner, repo }] of Object.entries(WEB_UI_SOURCES)) {
+    manifest.pins[id as keyof typeof WEB_UI_SOURCES] = await resolvePin(
+      owner,
+      repo,
+    );
+  }
+  consola.success("Resolved web ui pins");
+
+  await fs.ensureDir(MANIFEST_DIR);
+  await fs.writeJSON(MANIFEST_WEB_UI_PATH, manifest, { spaces: 2 });
+  consola.success("Manifest written");
+}
+
+main();
@@ -0,0 +1,141 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { clearCountryStats, getCountryStats } from "@/services/cmds";
+import parseTraffic from "@/utils/parse-traffic";
+import {
+  Box,
+  Button,
+  LinearProgress,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useMemo, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const RANGES = [
+  { days: 1, label: "Today" },
+  { days: 7, label: "Last 7 Days" },
+  { days: 30, label: "Last 30 Days" },
+];
+
+export const CountryStats = forwardRef<DialogRef>((props, ref) => {
+  const { t, i18n } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [days, setDays] = useState(1);
+
+  const { data: stats, mutate } = useSWR(
+    open ? ["getCountryStats", days] : null,
+    () => getCountryStats(days),
+    { refreshInterval: 5000 },
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+    close: () => setOpen(false),
+  }));
+
+  // 用系统的地区名显示，不支持时退回国家代码
+  const regionNames = useMemo(() => {
+    try {
+      return new Intl.DisplayNames([i18n.language], { type: "region" });
+    } catch {
+      return null;
+    }
+  }, [i18n.language]);
+
+  const countryName = (code: string) => {
+    if (code === "unknown") return t("Unknown");
+    try {
+      return regionNames?.of(code) ?? code;
+    } catch {
+      return code;
+    }
+  };
+
+  const onClear = useLockFn(async () => {
+    try {
+      await clearCountryStats();
+      mutate();
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Traffic by Country")}
+      contentSx={{ width: 400 }}
+      okBtn={t("Close")}
+      disableCancel
+      onClose={() => setOpen(false)}
+      onOk={() => setOpen(false)}
+    >
+      <Box
+        sx={{
+          display: "flex",
+          alignItems: "center",
+          justifyContent: "space-between",
+        }}
+      >
+        <Select
+          size="small"
+          sx={{ width: 160, "> div": { py: "7.5px" } }}
+          value={days}
+          onChange={(e) => setDays(+e.target.value)}
+        >
+          {RANGES.map((range) => (
+            <MenuItem key={range.days} value={range.days}>
+              {t(range.label)}
+            </MenuItem>
+          ))}
+        </Select>
+        <Button size="small" disabled={!stats?.length} onClick={onClear}>
+          {t("Clear")}
+        </Button>
+      </Box>
+
+      <List dense sx={{ maxHeight: 320, overflow: "auto" }}>
+        {stats?.length ? (
+          stats.map((item) => (
+            <ListItem
+              key={item.country}
+              sx={{ padding: "4px 2px", display: "block" }}
+            >
+              <Box sx={{ display: "flex", alignItems: "center" }}>
+                <ListItemText
+                  primary={countryName(item.country)}
+                  secondary={[
+                    `↑ ${parseTraffic(item.up).join(" ")}`,
+                    `↓ ${parseTraffic(item.down).join(" ")}`,
+                  ].join(" · ")}
+                />
+                <Typography variant="body2">
+                  {(item.share * 100).toFixed(1)}%
+                </Typography>
+              </Box>
+              <LinearProgress
+                variant="determinate"
+                value={item.share * 100}
+                sx={{ borderRadius: 1 }}
+              />
+            </ListItem>
+          ))
+        ) : (
+          <Typography variant="body2" sx={{ opacity: 0.7, py: 1 }}>
+            {t("No Records")}
+          </Typography>
+        )}
+      </List>
+    </BaseDialog>
+  );
+});
+
+CountryStats.displayName = "CountryStats";
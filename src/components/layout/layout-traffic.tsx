@@ -1,3 +1,4 @@
+import { useBinaryStream } from "@/hooks/use-binary-stream";
 import { useClashInfo } from "@/hooks/use-clash";
 import { useVerge } from "@/hooks/use-verge";
 import { useVisibility } from "@/hooks/use-visibility";
@@ -29,22 +30,23 @@ export const LayoutTraffic = () => {
   // setup log ws during layout
   useLogSetup();
 
-  const { connect, disconnect } = useWebsocket((event) => {
-    const data = JSON.parse(event.data) as ITrafficItem;
-    trafficRef.current?.appendData(data);
-    setTraffic(data);
-  });
+  const { connect, disconnect } = useBinaryStream<ITrafficItem>(
+    "traffic",
+    (data) => {
+      trafficRef.current?.appendData(data);
+      setTraffic(data);
+    },
+  );
 
   useEffect(() => {
-    if (!clashInfo || !pageVisible) return;
+    if (!pageVisible) return;
 
-    const { server = "", secret = "" } = clashInfo;
-    connect(`ws://${server}/traffic?token=${encodeURIComponent(secret)}`);
+    connect();
 
     return () => {
       disconnect();
     };
-  }, [clashInfo, pageVisible]);
+  }, [pageVisible]);
 
   /* --------- meta memory information --------- */
   const isMetaCore =
@@ -0,0 +1,58 @@
+import { BaseDialog } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useProfiles } from "@/hooks/use-profiles";
+import { takeStartupProfilePrompt } from "@/services/cmds";
+import { List, ListItemButton, ListItemText } from "@mui/material";
+import { useLockFn } from "ahooks";
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+// the `ask` startup policy, shown once after launch
+export const StartupProfileDialog = () => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const { profiles = {}, patchProfiles, activateSelected } = useProfiles();
+
+  useEffect(() => {
+    takeStartupProfilePrompt().then((ask) => ask && setOpen(true));
+  }, []);
+
+  const items = (profiles.items ?? []).filter(
+    (item) => item.type === "local" || item.type === "remote",
+  );
+
+  const onSelect = useLockFn(async (uid: string) => {
+    setOpen(false);
+    if (uid === profiles.current) return;
+    try {
+      await patchProfiles({ current: uid });
+      setTimeout(() => activateSelected(), 2000);
+    } catch (err: any) {
+      useNotification(t("Error"), err?.message || err.toString());
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Choose Profile")}
+      contentSx={{ width: 360, maxHeight: 420 }}
+      disableOk
+      cancelBtn={t("Keep Last Used")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <List>
+        {items.map((item) => (
+          <ListItemButton
+            key={item.uid}
+            selected={item.uid === profiles.current}
+            onClick={() => onSelect(item.uid)}
+          >
+            <ListItemText primary={item.name} secondary={item.desc} />
+          </ListItemButton>
+        ))}
+      </List>
+    </BaseDialog>
+  );
+};
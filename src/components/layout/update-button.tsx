@@ -1,7 +1,7 @@
 import useSWR from "swr";
 import { useRef } from "react";
 import { Button } from "@mui/material";
-import { checkUpdate } from "@tauri-apps/api/updater";
+import { checkAppUpdate } from "@/services/cmds";
 import { UpdateViewer } from "../setting/mods/update-viewer";
 import { DialogRef } from "../base";
 
@@ -14,13 +14,13 @@ export const UpdateButton = (props: Props) => {
 
   const viewerRef = useRef<DialogRef>(null);
 
-  const { data: updateInfo } = useSWR("checkUpdate", checkUpdate, {
+  const { data: updateInfo } = useSWR("checkAppUpdate", checkAppUpdate, {
     errorRetryCount: 2,
     revalidateIfStale: false,
     focusThrottleInterval: 36e5, // 1 hour
   });
 
-  if (!updateInfo?.shouldUpdate) return null;
+  if (!updateInfo) return null;
 
   return (
     <>
@@ -2,38 +2,37 @@ import dayjs from "dayjs";
 import { useEffect } from "react";
 import { useRecoilValue, useSetRecoilState } from "recoil";
 import { getClashLogs } from "@/services/cmds";
-import { useClashInfo } from "@/hooks/use-clash";
 import { atomEnableLog, atomLogData } from "@/services/states";
-import { useWebsocket } from "@/hooks/use-websocket";
+import { useBinaryStream } from "@/hooks/use-binary-stream";
 
 const MAX_LOG_NUM = 1000;
 
-// setup the log websocket
+// setup the log stream
 export const useLogSetup = () => {
-  const { clashInfo } = useClashInfo();
-
   const enableLog = useRecoilValue(atomEnableLog);
   const setLogData = useSetRecoilState(atomLogData);
 
-  const { connect, disconnect } = useWebsocket((event) => {
-    const data = JSON.parse(event.data) as ILogItem;
-    const time = dayjs().format("MM-DD HH:mm:ss");
-    setLogData((l) => {
-      if (l.length >= MAX_LOG_NUM) l.shift();
-      return [...l, { ...data, time }];
-    });
-  });
+  // the logs are batched by the backend
+  const { connect, disconnect } = useBinaryStream<IClashLogItem[]>(
+    "logs",
+    (items) => {
+      const data = items.map((item) => ({
+        type: item.level,
+        payload: item.payload,
+        time: dayjs(item.time).format("MM-DD HH:mm:ss"),
+      }));
+      setLogData((l) => [...l, ...data].slice(-MAX_LOG_NUM));
+    },
+  );
 
   useEffect(() => {
-    if (!enableLog || !clashInfo) return;
+    if (!enableLog) return;
 
     getClashLogs().then(setLogData);
-
-    const { server = "", secret = "" } = clashInfo;
-    connect(`ws://${server}/logs?token=${encodeURIComponent(secret)}`);
+    connect();
 
     return () => {
       disconnect();
     };
-  }, [clashInfo, enableLog]);
+  }, [enableLog]);
 };
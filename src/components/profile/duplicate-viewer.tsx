@@ -0,0 +1,48 @@
+import { BaseDialog } from "@/components/base";
+import { List, ListItemButton, ListItemText } from "@mui/material";
+import { useTranslation } from "react-i18next";
+
+interface Props {
+  open: boolean;
+  // the name of the existing profile
+  name?: string | null;
+  onSelect: (strategy: IImportStrategy) => void;
+  onClose: () => void;
+}
+
+const STRATEGIES: IImportStrategy[] = ["merge", "replace", "copy"];
+
+// ask how to import a subscription url that already exists
+export const DuplicateViewer = (props: Props) => {
+  const { open, name, onSelect, onClose } = props;
+  const { t } = useTranslation();
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Duplicate Subscription")}
+      contentSx={{ width: 400 }}
+      disableOk
+      cancelBtn={t("Cancel")}
+      onClose={onClose}
+      onCancel={onClose}
+    >
+      {t("Duplicate Subscription Info", { name: name ?? "" })}
+
+      <List sx={{ mt: 1 }}>
+        {STRATEGIES.map((strategy) => (
+          <ListItemButton
+            key={strategy}
+            sx={{ borderRadius: 1 }}
+            onClick={() => onSelect(strategy)}
+          >
+            <ListItemText
+              primary={t(`Import Strategy ${strategy}`)}
+              secondary={t(`Import Strategy ${strategy} Info`)}
+            />
+          </ListItemButton>
+        ))}
+      </List>
+    </BaseDialog>
+  );
+};
@@ -1,21 +1,32 @@
-import { useEffect, useRef } from "react";
+import { useEffect, useRef, useState } from "react";
 import { useLockFn } from "ahooks";
 import { useRecoilValue } from "recoil";
 import { useTranslation } from "react-i18next";
 import {
+  Box,
   Button,
   Dialog,
   DialogActions,
   DialogContent,
   DialogTitle,
+  Typography,
 } from "@mui/material";
 import { atomThemeMode } from "@/services/states";
-import { readProfileFile, saveProfileFile } from "@/services/cmds";
+import {
+  applyProfileFile,
+  formatYaml,
+  lintProfileFile,
+  readProfileFile,
+  saveProfileFile,
+} from "@/services/cmds";
 
 import "monaco-editor/esm/vs/basic-languages/javascript/javascript.contribution.js";
 import "monaco-editor/esm/vs/basic-languages/yaml/yaml.contribution.js";
 import "monaco-editor/esm/vs/editor/contrib/folding/browser/folding.js";
-import { editor } from "monaco-editor/esm/vs/editor/editor.api";
+import {
+  editor,
+  MarkerSeverity,
+} from "monaco-editor/esm/vs/editor/editor.api";
 import { useNotification } from "@/hooks/use-notification";
 
 interface Props {
@@ -34,24 +45,68 @@ export const EditorViewer = (props: Props) => {
   const instanceRef = useRef<editor.IStandaloneCodeEditor | null>(null);
   const themeMode = useRecoilValue(atomThemeMode);
 
+  const [diagnostics, setDiagnostics] = useState<IDiagnostic[]>([]);
+
+  // 诊断信息同时标记在编辑器里
+  const showDiagnostics = (list: IDiagnostic[]) => {
+    setDiagnostics(list);
+    const model = instanceRef.current?.getModel();
+    if (!model) return;
+    editor.setModelMarkers(
+      model,
+      "nyanpasu",
+      list.map((item) => {
+        const line = Math.min(item.line ?? 1, model.getLineCount());
+        const column = item.column ?? 1;
+        return {
+          severity:
+            item.severity === "error"
+              ? MarkerSeverity.Error
+              : MarkerSeverity.Warning,
+          message: item.message,
+          startLineNumber: line,
+          startColumn: column,
+          endLineNumber: line,
+          endColumn: model.getLineMaxColumn(line),
+        };
+      }),
+    );
+  };
+
   useEffect(() => {
     if (!open) return;
 
+    let timer: ReturnType<typeof setTimeout> | undefined;
+    setDiagnostics([]);
+
     readProfileFile(uid).then((data) => {
       const dom = editorRef.current;
 
       if (!dom) return;
       if (instanceRef.current) instanceRef.current.dispose();
 
-      instanceRef.current = editor.create(editorRef.current, {
+      const instance = editor.create(editorRef.current, {
         value: data,
         language: mode,
         theme: themeMode === "light" ? "vs" : "vs-dark",
         minimap: { enabled: false },
       });
+      instanceRef.current = instance;
+
+      if (mode !== "yaml") return;
+      const lint = () =>
+        lintProfileFile(uid, instance.getValue())
+          .then(showDiagnostics)
+          .catch(() => {});
+      lint();
+      instance.onDidChangeModelContent(() => {
+        clearTimeout(timer);
+        timer = setTimeout(lint, 500);
+      });
     });
 
     return () => {
+      clearTimeout(timer);
       if (instanceRef.current) {
         instanceRef.current.dispose();
         instanceRef.current = null;
@@ -59,13 +114,34 @@ export const EditorViewer = (props: Props) => {
     };
   }, [open]);
 
+  const onFormat = useLockFn(async () => {
+    const instance = instanceRef.current;
+    if (!instance) return;
+
+    try {
+      const result = await formatYaml(instance.getValue());
+      if (result.comments_dropped) {
+        useNotification(t("Warning"), t("Comments Dropped"));
+      }
+      instance.setValue(result.content);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
   const onSave = useLockFn(async () => {
     const value = instanceRef.current?.getValue();
 
     if (value == null) return;
 
     try {
-      await saveProfileFile(uid, value);
+      if (mode === "yaml") {
+        const result = await applyProfileFile(uid, value);
+        showDiagnostics(result.diagnostics);
+        if (!result.saved) return;
+      } else {
+        await saveProfileFile(uid, value);
+      }
       onChange?.();
       onClose();
     } catch (err: any) {
@@ -79,9 +155,38 @@ export const EditorViewer = (props: Props) => {
 
       <DialogContent sx={{ width: 520, pb: 1, userSelect: "text" }}>
         <div style={{ width: "100%", height: "420px" }} ref={editorRef} />
+
+        {diagnostics.length > 0 && (
+          <Box sx={{ mt: 1, maxHeight: 96, overflow: "auto" }}>
+            {diagnostics.map((item, index) => (
+              <Typography
+                key={index}
+                variant="body2"
+                color={item.severity === "error" ? "error" : "warning.main"}
+                sx={{ cursor: item.line ? "pointer" : "default" }}
+                onClick={() => {
+                  if (!item.line) return;
+                  instanceRef.current?.revealLineInCenter(item.line);
+                  instanceRef.current?.setPosition({
+                    lineNumber: item.line,
+                    column: item.column ?? 1,
+                  });
+                }}
+              >
+                {item.line ? `${item.line}:${item.column ?? 1} ` : ""}
+                {item.message}
+              </Typography>
+            ))}
+          </Box>
+        )}
       </DialogContent>
 
       <DialogActions>
+        {mode === "yaml" && (
+          <Button onClick={onFormat} variant="outlined" sx={{ mr: "auto" }}>
+            {t("Format")}
+          </Button>
+        )}
         <Button onClick={onClose} variant="outlined">
           {t("Cancel")}
         </Button>
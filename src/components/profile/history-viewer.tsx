@@ -0,0 +1,82 @@
+import dayjs from "dayjs";
+import useSWR from "swr";
+import { useTranslation } from "react-i18next";
+import {
+  Button,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  Divider,
+  Typography,
+} from "@mui/material";
+import { BaseEmpty } from "@/components/base";
+import { getProfileHistory } from "@/services/cmds";
+import parseTraffic from "@/utils/parse-traffic";
+
+const size = (num: number) => parseTraffic(num).join(" ");
+
+const HOOK_LABELS: Record<IProfileHook["type"], string> = {
+  notify: "Notify",
+  run_chain: "Run Script Chain",
+  restart_core: "Restart Core",
+  connectivity_check: "Connectivity Check",
+};
+
+interface Props {
+  uid: string;
+  open: boolean;
+  onClose: () => void;
+}
+
+// 订阅的更新记录
+export const HistoryViewer = (props: Props) => {
+  const { uid, open, onClose } = props;
+
+  const { t } = useTranslation();
+  const { data: records = [] } = useSWR(
+    open ? ["getProfileHistory", uid] : null,
+    () => getProfileHistory(uid),
+  );
+
+  return (
+    <Dialog open={open} onClose={onClose}>
+      <DialogTitle>{t("Update History")}</DialogTitle>
+
+      <DialogContent sx={{ width: 400, maxHeight: 360, pb: 1 }}>
+        {records.map((item) => (
+          <div key={item.time}>
+            <Typography variant="body2">
+              {dayjs(item.time * 1000).format("YYYY-MM-DD HH:mm:ss")}
+            </Typography>
+            <Typography variant="body2" color="text.secondary">
+              {size(item.size)}
+              {item.extra &&
+                ` · ${size(item.extra.upload + item.extra.download)}` +
+                  ` / ${size(item.extra.total)}`}
+            </Typography>
+            {item.hooks?.map((res, index) => (
+              <Typography
+                key={index}
+                variant="body2"
+                color={res.error ? "error" : "text.secondary"}
+              >
+                {t(HOOK_LABELS[res.hook.type])}
+                {res.error ? ` · ${res.error}` : ` · ${t("Succeeded")}`}
+              </Typography>
+            ))}
+            <Divider sx={{ my: 0.5 }} />
+          </div>
+        ))}
+
+        {records.length === 0 && <BaseEmpty />}
+      </DialogContent>
+
+      <DialogActions>
+        <Button onClick={onClose} variant="outlined">
+          {t("Back")}
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+};
@@ -0,0 +1,112 @@
+import { useProfiles } from "@/hooks/use-profiles";
+import {
+  Box,
+  FormControl,
+  InputLabel,
+  MenuItem,
+  Select,
+  Switch,
+  Typography,
+  styled,
+} from "@mui/material";
+import { useTranslation } from "react-i18next";
+
+interface Props {
+  value?: IProfileHook[];
+  onChange: (hooks: IProfileHook[]) => void;
+}
+
+type SimpleHook = Exclude<IProfileHook["type"], "run_chain">;
+
+const SIMPLE_HOOKS: { type: SimpleHook; label: string }[] = [
+  { type: "restart_core", label: "Restart Core" },
+  { type: "connectivity_check", label: "Connectivity Check" },
+  { type: "notify", label: "Notify" },
+];
+
+// 按固定的顺序执行：脚本链、重启内核、连通性检查、通知
+const ORDER: IProfileHook["type"][] = [
+  "run_chain",
+  "restart_core",
+  "connectivity_check",
+  "notify",
+];
+
+// the hooks run after the timer updates the profile
+export const HooksInput = (props: Props) => {
+  const { value = [], onChange } = props;
+
+  const { t } = useTranslation();
+  const { profiles = {} } = useProfiles();
+
+  const chainItems = (profiles.items ?? []).filter(
+    (item) => item.type === "merge" || item.type === "script",
+  );
+
+  const find = <T extends IProfileHook["type"]>(type: T) =>
+    value.find((hook) => hook.type === type) as
+      | Extract<IProfileHook, { type: T }>
+      | undefined;
+
+  const update = (type: IProfileHook["type"], hook?: IProfileHook) => {
+    const hooks = value.filter((each) => each.type !== type);
+    if (hook) hooks.push(hook);
+    onChange(
+      hooks.sort((a, b) => ORDER.indexOf(a.type) - ORDER.indexOf(b.type)),
+    );
+  };
+
+  const chain = find("run_chain")?.chain ?? [];
+
+  return (
+    <>
+      <Typography variant="subtitle2" sx={{ mt: 1 }}>
+        {t("After Update")}
+      </Typography>
+
+      <FormControl size="small" fullWidth sx={{ mt: 1, mb: 1 }}>
+        <InputLabel>{t("Run Script Chain")}</InputLabel>
+        <Select
+          multiple
+          value={chain}
+          label={t("Run Script Chain")}
+          onChange={(e) => {
+            const uids = e.target.value as string[];
+            update(
+              "run_chain",
+              uids.length ? { type: "run_chain", chain: uids } : undefined,
+            );
+          }}
+        >
+          {chainItems.map((item) => (
+            <MenuItem key={item.uid} value={item.uid}>
+              {item.name}
+            </MenuItem>
+          ))}
+        </Select>
+      </FormControl>
+
+      {SIMPLE_HOOKS.map((hook) => (
+        <StyledBox key={hook.type}>
+          <InputLabel>{t(hook.label)}</InputLabel>
+          <Switch
+            checked={!!find(hook.type)}
+            onChange={(_, checked) =>
+              update(
+                hook.type,
+                checked ? ({ type: hook.type } as IProfileHook) : undefined,
+              )
+            }
+          />
+        </StyledBox>
+      ))}
+    </>
+  );
+};
+
+const StyledBox = styled(Box)(() => ({
+  margin: "8px 0 8px 8px",
+  display: "flex",
+  alignItems: "center",
+  justifyContent: "space-between",
+}));
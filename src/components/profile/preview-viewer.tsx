@@ -0,0 +1,113 @@
+import { BaseDialog, BaseEmpty, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { previewConfigChanges } from "@/services/cmds";
+import { Box, Chip, Divider, Typography } from "@mui/material";
+import { useLockFn } from "ahooks";
+import { Fragment, forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+interface Props {
+  onApply: () => Promise<void>;
+}
+
+const KIND = {
+  added: { label: "Added", color: "success" },
+  removed: { label: "Removed", color: "error" },
+  modified: { label: "Modified", color: "warning" },
+} as const;
+
+const show = (value: any) =>
+  typeof value === "string" ? value : JSON.stringify(value);
+
+// 应用前查看生成的配置和当前配置的差异
+export const PreviewViewer = forwardRef<DialogRef, Props>((props, ref) => {
+  const { onApply } = props;
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [preview, setPreview] = useState<IConfigPreview | null>(null);
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setPreview(null);
+      previewConfigChanges()
+        .then(setPreview)
+        .catch((err: any) =>
+          useNotification(t("Error"), err.message || err.toString()),
+        );
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onOk = useLockFn(async () => {
+    await onApply();
+    setOpen(false);
+  });
+
+  const changes = preview?.changes ?? [];
+  const exceptions = Object.values(preview?.chain_logs ?? {})
+    .flat()
+    .filter(([level]) => level === "exception");
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Preview Changes")}
+      contentSx={{ width: 520, height: 420, userSelect: "text" }}
+      okBtn={t("Apply")}
+      cancelBtn={t("Cancel")}
+      disableOk={!preview}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onOk}
+    >
+      {exceptions.map(([, log], index) => (
+        <Typography key={index} variant="body2" color="error">
+          {log}
+        </Typography>
+      ))}
+
+      {preview && changes.length === 0 && (
+        <BaseEmpty text={t("No Changes")} />
+      )}
+
+      {changes.map((item, index) => (
+        <Fragment key={index}>
+          <Box sx={{ display: "flex", alignItems: "center", gap: 1 }}>
+            <Chip
+              label={t(KIND[item.kind].label)}
+              size="small"
+              variant="outlined"
+              color={KIND[item.kind].color}
+            />
+            <Typography variant="body2" noWrap title={item.path}>
+              {item.path}
+            </Typography>
+          </Box>
+          {item.before != null && (
+            <Typography
+              variant="body2"
+              color="error"
+              sx={{ fontFamily: "monospace", wordBreak: "break-all" }}
+            >
+              - {show(item.before)}
+            </Typography>
+          )}
+          {item.after != null && (
+            <Typography
+              variant="body2"
+              color="success.main"
+              sx={{ fontFamily: "monospace", wordBreak: "break-all" }}
+            >
+              + {show(item.after)}
+            </Typography>
+          )}
+          <Divider sx={{ my: 0.5 }} />
+        </Fragment>
+      ))}
+    </BaseDialog>
+  );
+});
+
+PreviewViewer.displayName = "PreviewViewer";
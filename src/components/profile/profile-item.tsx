@@ -20,6 +20,7 @@ import { RefreshRounded, DragIndicator } from "@mui/icons-material";
 import { atomLoadingCache } from "@/services/states";
 import { updateProfile, deleteProfile, viewProfile } from "@/services/cmds";
 import { EditorViewer } from "./editor-viewer";
+import { HistoryViewer } from "./history-viewer";
 import { ProfileBox } from "./profile-box";
 import parseTraffic from "@/utils/parse-traffic";
 import { useNotification } from "@/hooks/use-notification";
@@ -92,6 +93,7 @@ export const ProfileItem = (props: Props) => {
   }, [hasUrl, updated]);
 
   const [fileOpen, setFileOpen] = useState(false);
+  const [historyOpen, setHistoryOpen] = useState(false);
 
   const onEditInfo = () => {
     setAnchorEl(null);
@@ -103,6 +105,11 @@ export const ProfileItem = (props: Props) => {
     setFileOpen(true);
   };
 
+  const onHistory = () => {
+    setAnchorEl(null);
+    setHistoryOpen(true);
+  };
+
   const onForceSelect = () => {
     setAnchorEl(null);
     onSelect(true);
@@ -173,6 +180,7 @@ export const ProfileItem = (props: Props) => {
     { label: "Open File", handler: onOpenFile },
     { label: "Update", handler: () => onUpdate(0) },
     { label: "Update(Proxy)", handler: () => onUpdate(2) },
+    { label: "Update History", handler: onHistory },
     { label: "Delete", handler: onDelete },
   ];
   const fileModeMenu = [
@@ -348,6 +356,14 @@ export const ProfileItem = (props: Props) => {
         mode="yaml"
         onClose={() => setFileOpen(false)}
       />
+
+      {hasUrl && (
+        <HistoryViewer
+          uid={uid}
+          open={historyOpen}
+          onClose={() => setHistoryOpen(false)}
+        />
+      )}
     </Box>
   );
 };
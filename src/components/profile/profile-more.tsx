@@ -1,4 +1,5 @@
 import dayjs from "dayjs";
+import useSWR from "swr";
 import { useState } from "react";
 import { useTranslation } from "react-i18next";
 import { useLockFn } from "ahooks";
@@ -12,10 +13,16 @@ import {
   IconButton,
 } from "@mui/material";
 import { FeaturedPlayListRounded } from "@mui/icons-material";
-import { viewProfile } from "@/services/cmds";
+import {
+  getPendingScript,
+  updateProfile,
+  viewProfile,
+} from "@/services/cmds";
 import { EditorViewer } from "./editor-viewer";
 import { ProfileBox } from "./profile-box";
 import { LogViewer } from "./log-viewer";
+import { TestViewer } from "./test-viewer";
+import { ScriptReviewViewer } from "./script-review-viewer";
 import { useNotification } from "@/hooks/use-notification";
 
 interface Props {
@@ -52,6 +59,15 @@ export const ProfileMore = (props: Props) => {
   const [position, setPosition] = useState({ left: 0, top: 0 });
   const [fileOpen, setFileOpen] = useState(false);
   const [logOpen, setLogOpen] = useState(false);
+  const [testOpen, setTestOpen] = useState(false);
+  const [reviewOpen, setReviewOpen] = useState(false);
+
+  // 带 url 的是远程脚本
+  const isRemote = type === "script" && !!itemData.url;
+  const { data: pending, mutate: mutatePending } = useSWR(
+    isRemote ? ["getPendingScript", uid] : null,
+    () => getPendingScript(uid),
+  );
 
   const onEditInfo = () => {
     setAnchorEl(null);
@@ -63,6 +79,11 @@ export const ProfileMore = (props: Props) => {
     setFileOpen(true);
   };
 
+  const onTestRun = () => {
+    setAnchorEl(null);
+    setTestOpen(true);
+  };
+
   const onOpenFile = useLockFn(async () => {
     setAnchorEl(null);
     try {
@@ -72,6 +93,21 @@ export const ProfileMore = (props: Props) => {
     }
   });
 
+  const onUpdate = useLockFn(async () => {
+    setAnchorEl(null);
+    try {
+      await updateProfile(uid);
+      mutatePending();
+    } catch (err: any) {
+      useNotification(t("Error"), err?.message || err.toString());
+    }
+  });
+
+  const onReview = () => {
+    setAnchorEl(null);
+    setReviewOpen(true);
+  };
+
   const fnWrapper = (fn: () => void) => () => {
     setAnchorEl(null);
     return fn();
@@ -85,6 +121,9 @@ export const ProfileMore = (props: Props) => {
     { label: "Edit Info", handler: onEditInfo },
     { label: "Edit File", handler: onEditFile },
     { label: "Open File", handler: onOpenFile },
+    { label: "Test Run", handler: onTestRun },
+    { label: "Update", show: isRemote, handler: onUpdate },
+    { label: "Review Update", show: !!pending, handler: onReview },
     { label: "To Top", show: showMove, handler: fnWrapper(onMoveTop) },
     { label: "To End", show: showMove, handler: fnWrapper(onMoveEnd) },
     { label: "Delete", handler: fnWrapper(onDelete) },
@@ -95,6 +134,9 @@ export const ProfileMore = (props: Props) => {
     { label: "Edit Info", handler: onEditInfo },
     { label: "Edit File", handler: onEditFile },
     { label: "Open File", handler: onOpenFile },
+    { label: "Test Run", handler: onTestRun },
+    { label: "Update", show: isRemote, handler: onUpdate },
+    { label: "Review Update", show: !!pending, handler: onReview },
     { label: "Delete", handler: fnWrapper(onDelete) },
   ];
 
@@ -136,11 +178,12 @@ export const ProfileMore = (props: Props) => {
           </Typography>
 
           <Chip
-            label={type}
-            color="primary"
+            label={pending ? t("Review") : type}
+            color={pending ? "warning" : "primary"}
             size="small"
             variant="outlined"
             sx={{ height: 20, textTransform: "capitalize" }}
+            onClick={pending ? onReview : undefined}
           />
         </Box>
 
@@ -226,6 +269,22 @@ export const ProfileMore = (props: Props) => {
         onClose={() => setFileOpen(false)}
       />
 
+      <TestViewer
+        uid={uid}
+        open={testOpen}
+        onClose={() => setTestOpen(false)}
+      />
+
+      {isRemote && (
+        <ScriptReviewViewer
+          uid={uid}
+          pending={pending}
+          open={reviewOpen}
+          onClose={() => setReviewOpen(false)}
+          onChange={() => mutatePending()}
+        />
+      )}
+
       {selected && (
         <LogViewer
           open={logOpen}
@@ -1,6 +1,10 @@
 import { BaseDialog } from "@/components/base";
 import { useNotification } from "@/hooks/use-notification";
-import { createProfile, patchProfile } from "@/services/cmds";
+import {
+  createProfile,
+  patchProfile,
+  setProfileCore,
+} from "@/services/cmds";
 import {
   Box,
   FormControl,
@@ -10,6 +14,7 @@ import {
   Select,
   Switch,
   TextField,
+  Typography,
   styled,
 } from "@mui/material";
 import { version } from "@root/package.json";
@@ -24,6 +29,7 @@ import {
 import { Controller, useForm } from "react-hook-form";
 import { useTranslation } from "react-i18next";
 import { FileInput } from "./file-input";
+import { HooksInput } from "./hooks-input";
 
 interface Props {
   onChange: () => void;
@@ -78,6 +84,7 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
 
     const selfProxy = watch("option.self_proxy");
     const withProxy = watch("option.with_proxy");
+    const insecure = watch("option.tls.insecure");
 
     useEffect(() => {
       if (selfProxy) formIns.setValue("option.with_proxy", false);
@@ -95,9 +102,21 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
           if (form.type === "remote" && !form.url) {
             throw new Error("The URL should not be null");
           }
-          if (form.type !== "remote" && form.type !== "local") {
+          // 远程脚本也需要拉取的选项
+          const isRemoteScript = form.type === "script" && !!form.url;
+          if (
+            form.type !== "remote" &&
+            form.type !== "local" &&
+            !isRemoteScript
+          ) {
             delete form.option;
           }
+          // 空行不算 pin
+          if (form.option?.tls?.pins) {
+            form.option.tls.pins = form.option.tls.pins
+              .map((pin) => pin.trim())
+              .filter(Boolean);
+          }
           if (form.option?.update_interval) {
             form.option.update_interval = +form.option.update_interval;
           }
@@ -111,7 +130,11 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
           // 编辑
           else {
             if (!form.uid) throw new Error("UID not found");
+            // 空字符串表示不绑定内核
+            const core = form.option?.core || null;
+            if (item.option) item.option.core = core ?? undefined;
             await patchProfile(form.uid, item);
+            if (item.option) await setProfileCore(form.uid, core);
           }
           setOpen(false);
           setLoading(false);
@@ -143,6 +166,7 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
     const formType = watch("type");
     const isRemote = formType === "remote";
     const isLocal = formType === "local";
+    const isScript = formType === "script";
 
     return (
       <BaseDialog
@@ -218,7 +242,24 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
           </>
         )}
 
-        {(isRemote || isLocal) && (
+        {isScript && (
+          <Controller
+            name="url"
+            control={control}
+            render={({ field }) => (
+              <TextField
+                {...text}
+                {...field}
+                value={field.value ?? ""}
+                disabled={openType === "edit" && !field.value}
+                label={t("Script URL")}
+                helperText={t("Script URL Info")}
+              />
+            )}
+          />
+        )}
+
+        {(isRemote || isLocal || isScript) && (
           <Controller
             name="option.update_interval"
             control={control}
@@ -243,6 +284,29 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
           />
         )}
 
+        {(isRemote || isLocal) && openType === "edit" && (
+          <Controller
+            name="option.core"
+            control={control}
+            render={({ field }) => (
+              <FormControl size="small" fullWidth sx={{ mt: 1, mb: 1 }}>
+                <InputLabel>{t("Bound Core")}</InputLabel>
+                <Select
+                  {...field}
+                  value={field.value ?? ""}
+                  label={t("Bound Core")}
+                >
+                  <MenuItem value="">{t("Not Bound")}</MenuItem>
+                  <MenuItem value="clash">Clash Premium</MenuItem>
+                  <MenuItem value="mihomo">Mihomo</MenuItem>
+                  <MenuItem value="mihomo-alpha">Mihomo Alpha</MenuItem>
+                  <MenuItem value="clash-rs">Clash Rust</MenuItem>
+                </Select>
+              </FormControl>
+            )}
+          />
+        )}
+
         {isLocal && openType === "new" && (
           <FileInput onChange={(val) => (fileDataRef.current = val)} />
         )}
@@ -270,8 +334,69 @@ export const ProfileViewer = forwardRef<ProfileViewerRef, Props>(
                 </StyledBox>
               )}
             />
+
+            <Controller
+              name="option.tls.ca"
+              control={control}
+              render={({ field }) => (
+                <TextField
+                  {...text}
+                  {...field}
+                  multiline
+                  maxRows={4}
+                  placeholder="-----BEGIN CERTIFICATE-----"
+                  label={t("Custom CA")}
+                />
+              )}
+            />
+
+            <Controller
+              name="option.tls.pins"
+              control={control}
+              render={({ field }) => (
+                <TextField
+                  {...text}
+                  multiline
+                  value={field.value?.join("\n") ?? ""}
+                  onChange={(e) => field.onChange(e.target.value.split("\n"))}
+                  placeholder="sha256/..."
+                  label={t("Public Key Pins")}
+                />
+              )}
+            />
+
+            <Controller
+              name="option.tls.insecure"
+              control={control}
+              render={({ field }) => (
+                <StyledBox>
+                  <InputLabel>{t("Skip Certificate Verification")}</InputLabel>
+                  <Switch
+                    checked={field.value ?? false}
+                    {...field}
+                    color="warning"
+                  />
+                </StyledBox>
+              )}
+            />
+
+            {insecure && (
+              <Typography variant="caption" color="warning.main">
+                {t("Skip Certificate Verification Warning")}
+              </Typography>
+            )}
           </>
         )}
+
+        {(isRemote || isLocal) && (
+          <Controller
+            name="option.hooks"
+            control={control}
+            render={({ field }) => (
+              <HooksInput value={field.value} onChange={field.onChange} />
+            )}
+          />
+        )}
       </BaseDialog>
     );
   },
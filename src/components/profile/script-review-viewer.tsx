@@ -0,0 +1,106 @@
+import dayjs from "dayjs";
+import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import {
+  Box,
+  Button,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  Typography,
+} from "@mui/material";
+import { useNotification } from "@/hooks/use-notification";
+import { approveRemoteScript, rejectRemoteScript } from "@/services/cmds";
+
+interface Props {
+  uid: string;
+  pending?: IPendingScript | null;
+  open: boolean;
+  onClose: () => void;
+  onChange: () => void;
+}
+
+const codeSx = {
+  m: 0,
+  p: 1,
+  flex: 1,
+  minWidth: 0,
+  height: 320,
+  overflow: "auto",
+  fontSize: 12,
+  fontFamily: "monospace",
+  bgcolor: "action.hover",
+  borderRadius: 1,
+} as const;
+
+// 远程脚本更新后需要确认才会运行
+export const ScriptReviewViewer = (props: Props) => {
+  const { uid, pending, open, onClose, onChange } = props;
+  const { t } = useTranslation();
+
+  const onApprove = useLockFn(async () => {
+    if (!pending) return;
+    try {
+      await approveRemoteScript(uid, pending.hash);
+      onChange();
+      onClose();
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  const onReject = useLockFn(async () => {
+    try {
+      await rejectRemoteScript(uid);
+      onChange();
+      onClose();
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <Dialog open={open} onClose={onClose} maxWidth="lg">
+      <DialogTitle>{t("Review Script Update")}</DialogTitle>
+
+      <DialogContent sx={{ width: 800 }}>
+        {pending && (
+          <>
+            <Typography variant="body2" color="text.secondary" mb={1}>
+              {pending.fetched &&
+                dayjs(pending.fetched * 1000).format("YYYY-MM-DD HH:mm:ss")}
+              {" · sha256 "}
+              {pending.hash.slice(0, 16)}
+            </Typography>
+
+            <Box display="flex" gap={1}>
+              <Box flex={1} minWidth={0}>
+                <Typography variant="subtitle2">{t("Running")}</Typography>
+                <Box component="pre" sx={codeSx}>
+                  {pending.current}
+                </Box>
+              </Box>
+              <Box flex={1} minWidth={0}>
+                <Typography variant="subtitle2">{t("Downloaded")}</Typography>
+                <Box component="pre" sx={codeSx}>
+                  {pending.data}
+                </Box>
+              </Box>
+            </Box>
+          </>
+        )}
+      </DialogContent>
+
+      <DialogActions>
+        <Button onClick={onClose}>{t("Cancel")}</Button>
+        <Button onClick={onReject} color="error" disabled={!pending}>
+          {t("Reject")}
+        </Button>
+        <Button onClick={onApprove} variant="contained" disabled={!pending}>
+          {t("Approve")}
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+};
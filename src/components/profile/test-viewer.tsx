@@ -0,0 +1,122 @@
+import { Fragment, useEffect, useState } from "react";
+import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import {
+  Box,
+  Button,
+  Chip,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  Divider,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { testScriptChain } from "@/services/cmds";
+import { useNotification } from "@/hooks/use-notification";
+
+interface Props {
+  uid: string;
+  open: boolean;
+  onClose: () => void;
+}
+
+// 用样例配置试运行 merge/script，不影响当前配置
+export const TestViewer = (props: Props) => {
+  const { uid, open, onClose } = props;
+
+  const { t } = useTranslation();
+  const [sample, setSample] = useState("");
+  const [result, setResult] = useState<IChainTestResult | null>(null);
+
+  useEffect(() => {
+    if (open) setResult(null);
+  }, [open]);
+
+  const onRun = useLockFn(async () => {
+    try {
+      setResult(await testScriptChain(uid, sample));
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <Dialog open={open} onClose={onClose}>
+      <DialogTitle>{t("Test Run")}</DialogTitle>
+
+      <DialogContent sx={{ width: 520, pb: 1, userSelect: "text" }}>
+        <TextField
+          fullWidth
+          multiline
+          minRows={6}
+          maxRows={6}
+          size="small"
+          label={t("Sample Profile")}
+          placeholder={t("Empty Uses Current Profile")}
+          value={sample}
+          onChange={(e) => setSample(e.target.value)}
+          sx={{ mt: 1, "& textarea": { fontFamily: "monospace" } }}
+        />
+
+        {result && (
+          <>
+            <Typography variant="body2" color="text.secondary" sx={{ mt: 1 }}>
+              {t("Elapsed", { ms: result.elapsed_ms })}
+            </Typography>
+
+            {result.error && (
+              <Typography variant="body2" color="error">
+                {result.error}
+              </Typography>
+            )}
+
+            <TextField
+              fullWidth
+              multiline
+              minRows={8}
+              maxRows={8}
+              size="small"
+              label={t("Output")}
+              value={result.output}
+              InputProps={{ readOnly: true }}
+              sx={{ mt: 1.5, "& textarea": { fontFamily: "monospace" } }}
+            />
+
+            <Box sx={{ mt: 1, maxHeight: 120, overflow: "auto" }}>
+              {result.logs.map(([level, log], index) => (
+                <Fragment key={index}>
+                  <Typography color="text.secondary" component="div">
+                    <Chip
+                      label={level}
+                      size="small"
+                      variant="outlined"
+                      color={
+                        level === "error" || level === "exception"
+                          ? "error"
+                          : "default"
+                      }
+                      sx={{ mr: 1 }}
+                    />
+                    {log}
+                  </Typography>
+                  <Divider sx={{ my: 0.5 }} />
+                </Fragment>
+              ))}
+            </Box>
+          </>
+        )}
+      </DialogContent>
+
+      <DialogActions>
+        <Button onClick={onClose} variant="outlined">
+          {t("Back")}
+        </Button>
+        <Button onClick={onRun} variant="contained">
+          {t("Run")}
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+};
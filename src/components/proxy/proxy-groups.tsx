@@ -1,12 +1,15 @@
 import { useRef } from "react";
 import { useLockFn } from "ahooks";
+import { useTranslation } from "react-i18next";
+import { mutate } from "swr";
 import { Virtuoso, type VirtuosoHandle } from "react-virtuoso";
 import {
   getConnections,
   providerHealthCheck,
-  updateProxy,
   deleteConnection,
 } from "@/services/api";
+import { enrichNodes, selectProxy } from "@/services/cmds";
+import { useNotification } from "@/hooks/use-notification";
 import { useProfiles } from "@/hooks/use-profiles";
 import { useVerge } from "@/hooks/use-verge";
 import { BaseEmpty } from "../base";
@@ -20,11 +23,12 @@ interface Props {
 
 export const ProxyGroups = (props: Props) => {
   const { mode } = props;
+  const { t } = useTranslation();
 
   const { renderList, onProxies, onHeadState } = useRenderList(mode);
 
   const { verge } = useVerge();
-  const { current, patchCurrent } = useProfiles();
+  const { mutateProfiles } = useProfiles();
 
   const virtuosoRef = useRef<VirtuosoHandle>(null);
 
@@ -34,8 +38,9 @@ export const ProxyGroups = (props: Props) => {
       if (group.type !== "Selector" && group.type !== "Fallback") return;
 
       const { name, now } = group;
-      await updateProxy(name, proxy.name);
+      await selectProxy(name, proxy.name);
       onProxies();
+      mutateProfiles();
 
       // 断开连接
       if (verge?.auto_close_connection) {
@@ -47,21 +52,6 @@ export const ProxyGroups = (props: Props) => {
           });
         });
       }
-
-      // 保存到selected中
-      if (!current) return;
-      if (!current.selected) current.selected = [];
-
-      const index = current.selected.findIndex(
-        (item) => item.name === group.name,
-      );
-
-      if (index < 0) {
-        current.selected.push({ name, now: proxy.name });
-      } else {
-        current.selected[index] = { name, now: proxy.name };
-      }
-      await patchCurrent({ selected: current.selected });
     },
   );
 
@@ -88,6 +78,25 @@ export const ProxyGroups = (props: Props) => {
     onProxies();
   });
 
+  // 查询节点的国家和 asn，开启了出口探测时一并查询出口
+  const handleEnrich = useLockFn(async (groupName: string) => {
+    const names = renderList
+      .filter(
+        (e) => e.group?.name === groupName && (e.type === 2 || e.type === 4),
+      )
+      .flatMap((e) => e.proxyCol || e.proxy!)
+      .filter(Boolean)
+      .map((p) => p!.name);
+
+    try {
+      await enrichNodes(names, verge?.enable_exit_probe);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    } finally {
+      mutate("getNodeInfo");
+    }
+  });
+
   // 滚到对应的节点
   const handleLocation = (group: IProxyGroupItem) => {
     if (!group) return;
@@ -126,6 +135,7 @@ export const ProxyGroups = (props: Props) => {
           indent={mode === "rule" || mode === "script"}
           onLocation={handleLocation}
           onCheckAll={handleCheckAll}
+          onEnrich={handleEnrich}
           onHeadState={onHeadState}
           onChangeProxy={handleChangeProxy}
         />
@@ -6,8 +6,10 @@ import {
   FilterAltRounded,
   MyLocationRounded,
   NetworkCheckRounded,
+  PublicRounded,
   SortByAlphaRounded,
   SortRounded,
+  TravelExploreRounded,
   VisibilityOffRounded,
   VisibilityRounded,
   WifiTetheringOffRounded,
@@ -25,6 +27,7 @@ interface Props {
   headState: HeadState;
   onLocation: () => void;
   onCheckDelay: () => void;
+  onEnrich: () => void;
   onHeadState: (val: Partial<HeadState>) => void;
 }
 
@@ -74,21 +77,34 @@ export const ProxyHead = (props: Props) => {
         <NetworkCheckRounded />
       </IconButton>
 
+      <IconButton
+        size="small"
+        color="inherit"
+        title={t("Node Info")}
+        onClick={props.onEnrich}
+      >
+        <TravelExploreRounded />
+      </IconButton>
+
       <IconButton
         size="small"
         color="inherit"
         title={
-          [t("Sort by default"), t("Sort by delay"), t("Sort by name")][
-            sortType
-          ]
+          [
+            t("Sort by default"),
+            t("Sort by delay"),
+            t("Sort by name"),
+            t("Sort by country"),
+          ][sortType]
         }
         onClick={() =>
-          onHeadState({ sortType: ((sortType + 1) % 3) as ProxySortType })
+          onHeadState({ sortType: ((sortType + 1) % 4) as ProxySortType })
         }
       >
-        {sortType !== 1 && sortType !== 2 && <SortRounded />}
+        {sortType === 0 && <SortRounded />}
         {sortType === 1 && <AccessTimeRounded />}
         {sortType === 2 && <SortByAlphaRounded />}
+        {sortType === 3 && <PublicRounded />}
       </IconButton>
 
       <IconButton
@@ -1,5 +1,6 @@
 import { BaseLoading } from "@/components/base";
 import delayManager from "@/services/delay";
+import { countryFlag, nodeCountry, nodeInfoTitle } from "@/utils/node-info";
 import { CheckCircleOutlineRounded } from "@mui/icons-material";
 import { Box, ListItemButton, Typography, alpha, styled } from "@mui/material";
 import { useLockFn } from "ahooks";
@@ -16,6 +17,7 @@ interface Props {
 // 多列布局
 export const ProxyItemMini = (props: Props) => {
   const { groupName, proxy, selected, showType = true, onClick } = props;
+  const country = nodeCountry(proxy.info);
 
   // -1/<=0 为 不显示
   // -2 为 loading
@@ -97,6 +99,11 @@ export const ProxyItemMini = (props: Props) => {
               <TypeBox component="span">{proxy.provider}</TypeBox>
             )}
             <TypeBox component="span">{proxy.type}</TypeBox>
+            {!!country && (
+              <TypeBox component="span" title={nodeInfoTitle(proxy.info)}>
+                {countryFlag(country)} {country}
+              </TypeBox>
+            )}
             {proxy.udp && <TypeBox component="span">UDP</TypeBox>}
           </Box>
         )}
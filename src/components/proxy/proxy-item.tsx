@@ -1,5 +1,6 @@
 import { BaseLoading } from "@/components/base";
 import delayManager from "@/services/delay";
+import { countryFlag, nodeCountry, nodeInfoTitle } from "@/utils/node-info";
 import { CheckCircleOutlineRounded } from "@mui/icons-material";
 import {
   Box,
@@ -44,6 +45,7 @@ const TypeBox = styled(Box)(({ theme }) => ({
 
 export const ProxyItem = (props: Props) => {
   const { groupName, proxy, selected, showType = true, sx, onClick } = props;
+  const country = nodeCountry(proxy.info);
 
   // -1/<=0 为 不显示
   // -2 为 loading
@@ -103,6 +105,11 @@ export const ProxyItem = (props: Props) => {
                 <TypeBox component="span">{proxy.provider}</TypeBox>
               )}
               {showType && <TypeBox component="span">{proxy.type}</TypeBox>}
+              {showType && !!country && (
+                <TypeBox component="span" title={nodeInfoTitle(proxy.info)}>
+                  {countryFlag(country)} {country}
+                </TypeBox>
+              )}
               {showType && proxy.udp && <TypeBox component="span">UDP</TypeBox>}
             </>
           }
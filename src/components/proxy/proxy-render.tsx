@@ -22,13 +22,21 @@ interface RenderProps {
   indent: boolean;
   onLocation: (group: IProxyGroupItem) => void;
   onCheckAll: (groupName: string) => void;
+  onEnrich: (groupName: string) => void;
   onHeadState: (groupName: string, patch: Partial<HeadState>) => void;
   onChangeProxy: (group: IProxyGroupItem, proxy: IProxyItem) => void;
 }
 
 export const ProxyRender = (props: RenderProps) => {
-  const { indent, item, onLocation, onCheckAll, onHeadState, onChangeProxy } =
-    props;
+  const {
+    indent,
+    item,
+    onLocation,
+    onCheckAll,
+    onEnrich,
+    onHeadState,
+    onChangeProxy,
+  } = props;
   const { type, group, headState, proxy, proxyCol } = item;
 
   if (type === 0) {
@@ -69,6 +77,7 @@ export const ProxyRender = (props: RenderProps) => {
         headState={headState!}
         onLocation={() => onLocation(group)}
         onCheckDelay={() => onCheckAll(group.name)}
+        onEnrich={() => onEnrich(group.name)}
         onHeadState={(p) => onHeadState(group.name, p)}
       />
     );
@@ -0,0 +1,83 @@
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  pinGroupsToRegion,
+  resetGroupSelections,
+  selectFastestAll,
+} from "@/services/cmds";
+import { Button, Divider, Menu, MenuItem } from "@mui/material";
+import { useLockFn } from "ahooks";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import { mutate } from "swr";
+
+// the codes known by the backend, other keywords are matched as they are
+const REGIONS = ["HK", "TW", "JP", "SG", "US", "KR", "GB", "DE"];
+
+export const QuickSelectButton = () => {
+  const { t } = useTranslation();
+  const { verge } = useVerge();
+
+  const [anchorEl, setAnchorEl] = useState<HTMLElement | null>(null);
+  const [loading, setLoading] = useState(false);
+
+  const onAction = useLockFn(async (action: () => Promise<IBulkSelected[]>) => {
+    setAnchorEl(null);
+    setLoading(true);
+    try {
+      const changed = await action();
+      useNotification(
+        t("Quick Select"),
+        t("Groups Changed", { count: changed.length }),
+      );
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    } finally {
+      setLoading(false);
+      mutate("getProxies");
+    }
+  });
+
+  return (
+    <>
+      <Button
+        size="small"
+        variant="outlined"
+        disabled={loading}
+        sx={{ textTransform: "capitalize" }}
+        onClick={(e) => setAnchorEl(e.currentTarget)}
+      >
+        {t("Quick Select")}
+      </Button>
+
+      <Menu
+        open={!!anchorEl}
+        anchorEl={anchorEl}
+        onClose={() => setAnchorEl(null)}
+        MenuListProps={{ sx: { py: 0.5 } }}
+      >
+        <MenuItem
+          dense
+          onClick={() =>
+            onAction(() => selectFastestAll(verge?.default_latency_test))
+          }
+        >
+          {t("Select Fastest")}
+        </MenuItem>
+        <MenuItem dense onClick={() => onAction(resetGroupSelections)}>
+          {t("Reset to Default")}
+        </MenuItem>
+        <Divider />
+        {REGIONS.map((region) => (
+          <MenuItem
+            key={region}
+            dense
+            onClick={() => onAction(() => pinGroupsToRegion(region))}
+          >
+            {t("Pin to Region", { region })}
+          </MenuItem>
+        ))}
+      </Menu>
+    </>
+  );
+};
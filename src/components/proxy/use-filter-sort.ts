@@ -1,8 +1,9 @@
 import { useEffect, useMemo, useState } from "react";
 import delayManager from "@/services/delay";
+import { nodeAsn, nodeCountry } from "@/utils/node-info";
 
-// default | delay | alphabet
-export type ProxySortType = 0 | 1 | 2;
+// default | delay | alphabet | country
+export type ProxySortType = 0 | 1 | 2 | 3;
 
 export default function useFilterSort(
   proxies: IProxyItem[],
@@ -48,10 +49,12 @@ export function filterSort(
 }
 
 /**
- * 可以通过延迟数/节点类型 过滤
+ * 可以通过延迟数/节点类型/国家/asn 过滤
  */
 const regex1 = /delay([=<>])(\d+|timeout|error)/i;
 const regex2 = /type=(.*)/i;
+const regex3 = /country=(\w+)/i;
+const regex4 = /asn=(?:as)?(\d+)/i;
 
 /**
  * filter the proxy
@@ -91,6 +94,18 @@ function filterProxies(
     return proxies.filter((p) => p.type.toLowerCase().includes(type));
   }
 
+  const res3 = regex3.exec(filterText);
+  if (res3) {
+    const country = res3[1].toUpperCase();
+    return proxies.filter((p) => nodeCountry(p.info) === country);
+  }
+
+  const res4 = regex4.exec(filterText);
+  if (res4) {
+    const asn = +res4[1];
+    return proxies.filter((p) => nodeAsn(p.info) === asn);
+  }
+
   return proxies.filter((p) => p.name.includes(filterText.trim()));
 }
 
@@ -117,6 +132,14 @@ function sortProxies(
 
       return ad - bd;
     });
+  } else if (sortType === 3) {
+    // 没有信息的排在最后
+    list.sort((a, b) => {
+      const ac = nodeCountry(a.info);
+      const bc = nodeCountry(b.info);
+      if (!ac || !bc) return ac ? -1 : bc ? 1 : 0;
+      return ac.localeCompare(bc) || a.name.localeCompare(b.name);
+    });
   } else {
     list.sort((a, b) => a.name.localeCompare(b.name));
   }
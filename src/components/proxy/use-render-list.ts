@@ -1,6 +1,7 @@
 import useSWR from "swr";
 import { useEffect, useMemo } from "react";
 import { getProxies } from "@/services/api";
+import { getNodeInfo } from "@/services/cmds";
 import { useVerge } from "@/hooks/use-verge";
 import { filterSort } from "./use-filter-sort";
 import { useWindowWidth } from "./use-window-width";
@@ -28,6 +29,12 @@ export const useRenderList = (mode: string) => {
     { refreshInterval: 45000 },
   );
 
+  const { data: nodeInfo } = useSWR("getNodeInfo", getNodeInfo);
+  const infoMap = useMemo(
+    () => Object.fromEntries((nodeInfo ?? []).map((i) => [i.name, i])),
+    [nodeInfo],
+  );
+
   const { verge } = useVerge();
   const { width } = useWindowWidth();
 
@@ -74,8 +81,11 @@ export const useRenderList = (mode: string) => {
       ];
 
       if (headState?.open || !useRule) {
+        const all = group.all.map((p) =>
+          infoMap[p.name] ? { ...p, info: infoMap[p.name] } : p,
+        );
         const proxies = filterSort(
-          group.all,
+          all,
           group.name,
           headState.filterText,
           headState.sortType,
@@ -116,7 +126,7 @@ export const useRenderList = (mode: string) => {
 
     if (!useRule) return retList.slice(1);
     return retList;
-  }, [headStates, proxiesData, mode, col]);
+  }, [headStates, proxiesData, mode, col, infoMap]);
 
   return {
     renderList,
@@ -17,6 +17,9 @@ const COLOR = [
 interface Props {
   index: number;
   value: IRuleItem;
+  // the entries of the rule set or the geosite category
+  count?: number | null;
+  onClick?: () => void;
 }
 
 const parseColor = (text: string) => {
@@ -31,10 +34,10 @@ const parseColor = (text: string) => {
 };
 
 const RuleItem = (props: Props) => {
-  const { index, value } = props;
+  const { index, value, count, onClick } = props;
 
   return (
-    <Item>
+    <Item onClick={onClick} sx={{ cursor: onClick ? "pointer" : undefined }}>
       <Typography
         color="text.secondary"
         variant="body2"
@@ -64,6 +67,17 @@ const RuleItem = (props: Props) => {
         >
           {value.proxy}
         </Typography>
+
+        {count != null && (
+          <Typography
+            component="span"
+            variant="body2"
+            color="text.secondary"
+            sx={{ ml: 3 }}
+          >
+            {count.toLocaleString()}
+          </Typography>
+        )}
       </Box>
     </Item>
   );
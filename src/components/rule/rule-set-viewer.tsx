@@ -0,0 +1,202 @@
+import { BaseDialog, BaseEmpty } from "@/components/base";
+import { getRulesProviders } from "@/services/api";
+import { browseRuleSet } from "@/services/cmds";
+import {
+  Box,
+  MenuItem,
+  Pagination,
+  Select,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const PAGE_SIZE = 50;
+
+export interface RuleSetViewerRef {
+  open: (source?: IRuleSetSource) => void;
+  close: () => void;
+}
+
+// browse the entries of a rule provider or a geosite category
+export const RuleSetViewer = forwardRef<RuleSetViewerRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [source, setSource] = useState<IRuleSetSource>({
+    type: "provider",
+    name: "",
+  });
+  const [keyword, setKeyword] = useState("");
+  const [target, setTarget] = useState("");
+  const [page, setPage] = useState(0);
+
+  useImperativeHandle(ref, () => ({
+    open: (value) => {
+      setOpen(true);
+      if (value) setSource(value);
+      setKeyword("");
+      setTarget("");
+      setPage(0);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const { data: providers = [] } = useSWR(
+    open ? "getClashProvidersRules" : null,
+    getRulesProviders,
+  );
+
+  const { data, error, isLoading } = useSWR(
+    open && source.name
+      ? ["browseRuleSet", source.type, source.name, keyword, target, page]
+      : null,
+    () =>
+      browseRuleSet(
+        source,
+        { keyword: keyword || undefined, target: target || undefined },
+        { page, page_size: PAGE_SIZE },
+      ),
+    { keepPreviousData: true },
+  );
+
+  const pageCount = data ? Math.ceil(data.total / data.page_size) : 0;
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Rule Set Browser")}
+      contentSx={{ width: 520, height: 520 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Box sx={{ display: "flex", gap: 1 }}>
+        <Select
+          size="small"
+          sx={{ width: 120, "> div": { py: "7.5px" } }}
+          value={source.type}
+          onChange={(e) => {
+            setSource({ type: e.target.value as any, name: "" });
+            setPage(0);
+          }}
+        >
+          <MenuItem value="provider">{t("Rule Provider")}</MenuItem>
+          <MenuItem value="geosite">GEOSITE</MenuItem>
+        </Select>
+
+        {source.type === "provider" ? (
+          <Select
+            size="small"
+            sx={{ flex: 1, "> div": { py: "7.5px" } }}
+            value={source.name}
+            displayEmpty
+            onChange={(e) => {
+              setSource({ type: "provider", name: e.target.value });
+              setPage(0);
+            }}
+          >
+            {providers.map((provider) => (
+              <MenuItem key={provider.name} value={provider.name}>
+                {provider.name}
+              </MenuItem>
+            ))}
+          </Select>
+        ) : (
+          <TextField
+            size="small"
+            autoComplete="off"
+            placeholder="cn / google@cn"
+            sx={{ flex: 1 }}
+            value={source.name}
+            onChange={(e) => {
+              setSource({ type: "geosite", name: e.target.value.trim() });
+              setPage(0);
+            }}
+          />
+        )}
+      </Box>
+
+      <Box sx={{ display: "flex", gap: 1, mt: 1 }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Filter conditions")}
+          sx={{ flex: 1 }}
+          value={keyword}
+          onChange={(e) => {
+            setKeyword(e.target.value);
+            setPage(0);
+          }}
+        />
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Test Domain or IP")}
+          sx={{ flex: 1 }}
+          value={target}
+          onChange={(e) => setTarget(e.target.value)}
+        />
+      </Box>
+
+      {target && data?.matched && (
+        <Typography
+          variant="body2"
+          sx={{ mt: 1 }}
+          color={data.matched.length ? "success.main" : "text.secondary"}
+        >
+          {data.matched.length
+            ? t("Matched By", {
+                entries: data.matched.map((e) => e.value).join(", "),
+              })
+            : t("Not Matched")}
+        </Typography>
+      )}
+
+      {error ? (
+        <Typography color="error" sx={{ mt: 2 }}>
+          {error.message || error.toString()}
+        </Typography>
+      ) : !data?.items.length ? (
+        <BaseEmpty text={isLoading ? t("Loading") : undefined} />
+      ) : (
+        <Box sx={{ mt: 1 }}>
+          <Typography variant="caption" color="text.secondary">
+            {data.behavior} · {data.total}
+          </Typography>
+          {data.items.map((entry, index) => (
+            <Box
+              key={`${page}-${index}`}
+              sx={{ display: "flex", py: 0.25, userSelect: "text" }}
+            >
+              <Typography
+                variant="body2"
+                color="text.secondary"
+                sx={{ width: 80, flex: "none" }}
+              >
+                {entry.kind}
+              </Typography>
+              <Typography variant="body2" sx={{ wordBreak: "break-all" }}>
+                {entry.value}
+              </Typography>
+            </Box>
+          ))}
+          {pageCount > 1 && (
+            <Pagination
+              size="small"
+              sx={{ mt: 1 }}
+              count={pageCount}
+              page={page + 1}
+              onChange={(_, value) => setPage(value - 1)}
+            />
+          )}
+        </Box>
+      )}
+    </BaseDialog>
+  );
+});
+
+RuleSetViewer.displayName = "RuleSetViewer";
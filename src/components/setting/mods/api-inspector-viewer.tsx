@@ -0,0 +1,138 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import { clearApiRequests, queryApiRequests } from "@/services/cmds";
+import {
+  Button,
+  List,
+  ListItem,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import dayjs from "dayjs";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+import { GuardState } from "./guard-state";
+import { SettingItem } from "./setting-comp";
+
+export const ApiInspectorViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge, mutateVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [path, setPath] = useState("");
+  const [slow, setSlow] = useState(0);
+
+  const { data: records = [], mutate } = useSWR(
+    open ? ["queryApiRequests", path, slow] : null,
+    () =>
+      queryApiRequests({
+        path: path || undefined,
+        min_duration_ms: slow || undefined,
+        limit: 200,
+      }),
+    { refreshInterval: 2000 },
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+    close: () => setOpen(false),
+  }));
+
+  const onSwitchFormat = (_e: any, value: boolean) => value;
+  const onError = (err: any) =>
+    useNotification(t("Error"), err.message || err.toString());
+  const onChangeData = (patch: Partial<IVergeConfig>) => {
+    mutateVerge({ ...verge, ...patch }, false);
+  };
+
+  const onClear = useLockFn(async () => {
+    try {
+      await clearApiRequests();
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("API Inspector")}
+      contentSx={{ width: 520 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <List>
+        <SettingItem label={t("Record Requests")}>
+          <GuardState
+            value={verge?.enable_api_inspector ?? false}
+            valueProps="checked"
+            onCatch={onError}
+            onFormat={onSwitchFormat}
+            onChange={(e) => onChangeData({ enable_api_inspector: e })}
+            onGuard={(e) => patchVerge({ enable_api_inspector: e })}
+          >
+            <Switch edge="end" />
+          </GuardState>
+        </SettingItem>
+
+        <ListItem sx={{ padding: "5px 2px", gap: 1 }}>
+          <TextField
+            size="small"
+            autoComplete="off"
+            sx={{ flex: 1 }}
+            placeholder={t("Filter Path")}
+            value={path}
+            onChange={(e) => setPath(e.target.value)}
+          />
+          <TextField
+            size="small"
+            type="number"
+            autoComplete="off"
+            sx={{ width: 120 }}
+            label={t("Slower Than (ms)")}
+            value={slow}
+            onChange={(e) => setSlow(parseInt(e.target.value) || 0)}
+          />
+          <Button
+            size="small"
+            disabled={records.length === 0}
+            onClick={onClear}
+          >
+            {t("Clear")}
+          </Button>
+        </ListItem>
+
+        {records.length === 0 && (
+          <Typography variant="body2" color="GrayText" sx={{ px: "2px" }}>
+            {t("No Requests")}
+          </Typography>
+        )}
+
+        {records.map((item, index) => (
+          <ListItem key={index} sx={{ padding: "2px 2px", display: "block" }}>
+            <Typography
+              variant="body2"
+              color={item.error || (item.status ?? 0) >= 400 ? "error" : ""}
+              sx={{ wordBreak: "break-all" }}
+            >
+              {item.method} {decodeURIComponent(item.path)}
+            </Typography>
+            <Typography variant="body2" color="GrayText">
+              {dayjs(item.time).format("HH:mm:ss")} · {item.source} ·{" "}
+              {item.duration_ms} ms · {item.status ?? item.error}
+            </Typography>
+          </ListItem>
+        ))}
+      </List>
+    </BaseDialog>
+  );
+});
+
+ApiInspectorViewer.displayName = "ApiInspectorViewer";
@@ -0,0 +1,197 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { getProxiesInner } from "@/services/api";
+import {
+  addAppRule,
+  deleteAppRule,
+  getAppRules,
+  listProcesses,
+  updateAppRule,
+} from "@/services/cmds";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Autocomplete,
+  Box,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const BUILTIN_TARGETS = ["DIRECT", "REJECT"];
+
+const ProcessIcon = ({ icon }: { icon?: string | null }) =>
+  icon ? (
+    <img src={icon} width={16} height={16} style={{ flexShrink: 0 }} />
+  ) : (
+    <Box sx={{ width: 16, flexShrink: 0 }} />
+  );
+
+export const AppRulesViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [process, setProcess] = useState("");
+  const [target, setTarget] = useState("DIRECT");
+
+  const { data: rules = [], mutate } = useSWR(
+    open ? "getAppRules" : null,
+    getAppRules,
+  );
+  const { data: processes = [] } = useSWR(
+    open ? "listProcesses" : null,
+    listProcesses,
+  );
+  const { data: proxies = [] } = useSWR(
+    open ? "getRuleTargets" : null,
+    async () => {
+      const names = Object.keys(await getProxiesInner());
+      return names.filter((name) => name !== "GLOBAL");
+    },
+  );
+  const targets = [
+    ...BUILTIN_TARGETS,
+    ...proxies.filter((name) => !BUILTIN_TARGETS.includes(name)),
+  ];
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setProcess("");
+      setTarget("DIRECT");
+    },
+    close: () => setOpen(false),
+  }));
+
+  const iconOf = (name: string) =>
+    processes.find((p) => p.name.toLowerCase() === name.toLowerCase())?.icon;
+
+  const onError = (err: any) => {
+    useNotification(t("Error"), err.message || err.toString());
+  };
+
+  const onAdd = useLockFn(async () => {
+    if (!process.trim()) return;
+    try {
+      await addAppRule({
+        id: "",
+        process: process.trim(),
+        target,
+        enable: true,
+      });
+      setProcess("");
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onUpdate = useLockFn(async (rule: IAppRule) => {
+    try {
+      await updateAppRule(rule.id, rule);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onDelete = useLockFn(async (id: string) => {
+    try {
+      await deleteAppRule(id);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("App Rules")}
+      contentSx={{ width: 480, maxHeight: 560 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Box sx={{ display: "flex", gap: 1 }}>
+        <Autocomplete
+          freeSolo
+          size="small"
+          sx={{ flex: 1 }}
+          options={processes.map((p) => p.name)}
+          inputValue={process}
+          onInputChange={(_, value) => setProcess(value)}
+          renderOption={(props, name) => (
+            <li {...props} key={name}>
+              <ProcessIcon icon={iconOf(name)} />
+              <Typography noWrap sx={{ ml: 1 }}>
+                {name}
+              </Typography>
+            </li>
+          )}
+          renderInput={(params) => (
+            <TextField {...params} placeholder={t("Process Name")} />
+          )}
+        />
+        <Select
+          size="small"
+          sx={{ width: 140, "> div": { py: "7.5px" } }}
+          value={target}
+          onChange={(e) => setTarget(e.target.value)}
+        >
+          {targets.map((name) => (
+            <MenuItem key={name} value={name}>
+              {name}
+            </MenuItem>
+          ))}
+        </Select>
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      {rules.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>
+          {t("No App Rules")}
+        </Typography>
+      ) : (
+        <List>
+          {rules.map((rule) => (
+            <ListItem key={rule.id} sx={{ padding: "2px", gap: 1 }}>
+              <Switch
+                size="small"
+                checked={rule.enable}
+                onChange={() => onUpdate({ ...rule, enable: !rule.enable })}
+              />
+              <ProcessIcon icon={iconOf(rule.process)} />
+              <ListItemText
+                primary={rule.process}
+                secondary={rule.target}
+                sx={{ opacity: rule.enable ? 1 : 0.5, wordBreak: "break-all" }}
+              />
+              <IconButton
+                size="small"
+                color="inherit"
+                onClick={() => onDelete(rule.id)}
+              >
+                <DeleteRounded fontSize="inherit" />
+              </IconButton>
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+AppRulesViewer.displayName = "AppRulesViewer";
@@ -0,0 +1,187 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import { getLanAccessInfo, setInboundAuth } from "@/services/cmds";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Box,
+  IconButton,
+  List,
+  ListItem,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+export const AuthViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const { verge, mutateVerge } = useVerge();
+
+  const [enable, setEnable] = useState(false);
+  const [users, setUsers] = useState<string[]>([]);
+  // 只提交修改过的密码，其他的保留在 keyring
+  const [passwords, setPasswords] = useState<Record<string, string>>({});
+  const [prefixes, setPrefixes] = useState("");
+  const [username, setUsername] = useState("");
+
+  const { data: lan, mutate: mutateLan } = useSWR(
+    open ? "getLanAccessInfo" : null,
+    getLanAccessInfo,
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      const auth = verge?.inbound_auth;
+      setOpen(true);
+      setEnable(auth?.enable ?? false);
+      setUsers(auth?.users ?? []);
+      setPasswords({});
+      setPrefixes((auth?.skip_auth_prefixes ?? []).join("\n"));
+      setUsername("");
+    },
+    close: () => setOpen(false),
+  }));
+
+  const saved = verge?.inbound_auth?.users ?? [];
+
+  const onAddUser = () => {
+    const name = username.trim();
+    if (!name || users.includes(name)) return;
+    setUsers((list) => [...list, name]);
+    setPasswords((map) => ({ ...map, [name]: "" }));
+    setUsername("");
+  };
+
+  const onDeleteUser = (name: string) => {
+    setUsers((list) => list.filter((user) => user !== name));
+    setPasswords(({ [name]: _, ...rest }) => rest);
+  };
+
+  const onSave = useLockFn(async () => {
+    try {
+      await setInboundAuth(
+        {
+          enable,
+          users,
+          skip_auth_prefixes: prefixes
+            .split("\n")
+            .map((line) => line.trim())
+            .filter(Boolean),
+        },
+        passwords,
+      );
+      mutateVerge();
+      mutateLan();
+      setOpen(false);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Inbound Auth")}
+      contentSx={{ width: 450, maxHeight: 560 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <Box sx={{ display: "flex", alignItems: "center" }}>
+        <Typography sx={{ flex: 1 }}>{t("Enable Auth")}</Typography>
+        <Switch
+          edge="end"
+          checked={enable}
+          onChange={(_, checked) => setEnable(checked)}
+        />
+      </Box>
+
+      <Box sx={{ display: "flex", gap: 1, mt: 1 }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Username")}
+          sx={{ flex: 1 }}
+          value={username}
+          onChange={(e) => setUsername(e.target.value)}
+          onKeyDown={(e) => e.key === "Enter" && onAddUser()}
+        />
+        <IconButton size="small" color="inherit" onClick={onAddUser}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      <List>
+        {users.map((user) => (
+          <ListItem key={user} sx={{ padding: "2px", gap: 1 }}>
+            <Typography sx={{ flex: 1, wordBreak: "break-all" }}>
+              {user}
+            </Typography>
+            <TextField
+              size="small"
+              type="password"
+              autoComplete="new-password"
+              sx={{ width: 180 }}
+              placeholder={
+                saved.includes(user) ? t("Password Unchanged") : t("Password")
+              }
+              value={passwords[user] ?? ""}
+              onChange={(e) =>
+                setPasswords((map) => ({ ...map, [user]: e.target.value }))
+              }
+            />
+            <IconButton
+              size="small"
+              color="inherit"
+              onClick={() => onDeleteUser(user)}
+            >
+              <DeleteRounded fontSize="inherit" />
+            </IconButton>
+          </ListItem>
+        ))}
+      </List>
+
+      <TextField
+        fullWidth
+        multiline
+        minRows={2}
+        size="small"
+        label={t("Skip Auth Prefixes")}
+        placeholder="127.0.0.1/32"
+        value={prefixes}
+        onChange={(e) => setPrefixes(e.target.value)}
+      />
+
+      {lan && (
+        <Box sx={{ mt: 2 }}>
+          <Typography variant="subtitle2">{t("LAN Sharing")}</Typography>
+          {lan.allow_lan ? (
+            <Typography
+              variant="body2"
+              color="text.secondary"
+              sx={{ userSelect: "text", wordBreak: "break-all" }}
+            >
+              {lan.ips.map((ip) => `${ip}:${lan.port}`).join(", ")}
+              {lan.auth_users.length > 0 &&
+                ` · ${t("Username")}: ${lan.auth_users.join(", ")}`}
+            </Typography>
+          ) : (
+            <Typography variant="body2" color="text.secondary">
+              {t("Allow Lan Disabled")}
+            </Typography>
+          )}
+        </Box>
+      )}
+    </BaseDialog>
+  );
+});
+
+AuthViewer.displayName = "AuthViewer";
@@ -0,0 +1,171 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  clearCoreCache,
+  flushFakeipCache,
+  getCoreCache,
+} from "@/services/cmds";
+import parseTraffic from "@/utils/parse-traffic";
+import {
+  Box,
+  Button,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import dayjs from "dayjs";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+// the empty string keeps the value of the profile
+const KEEP = "";
+
+const STORE_KEYS: (keyof IStoreOverride)[] = [
+  "store-selected",
+  "store-fake-ip",
+];
+
+export const CacheViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [loading, setLoading] = useState(false);
+
+  const { data: info, mutate } = useSWR(
+    open ? "getCoreCache" : null,
+    getCoreCache,
+  );
+
+  const core = verge?.clash_core ?? "clash";
+  const isMeta = core === "mihomo" || core === "mihomo-alpha";
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+    close: () => setOpen(false),
+  }));
+
+  const onError = (err: any) => {
+    useNotification(t("Error"), err.message || err.toString());
+  };
+
+  const onStore = useLockFn(
+    async (key: keyof IStoreOverride, value: string) => {
+      const store = { ...verge?.store_override };
+      if (value === KEEP) delete store[key];
+      else store[key] = value === "true";
+      try {
+        await patchVerge({ store_override: store });
+        mutate();
+      } catch (err: any) {
+        onError(err);
+      }
+    },
+  );
+
+  const onAction = useLockFn(async (action: () => Promise<void>) => {
+    setLoading(true);
+    try {
+      await action();
+      useNotification(t("Success"), t("Cache Cleared"));
+    } catch (err: any) {
+      onError(err);
+    } finally {
+      setLoading(false);
+      mutate();
+    }
+  });
+
+  const [size, unit] = parseTraffic(info?.size ?? 0);
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Core Cache")}
+      contentSx={{ width: 450 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Typography variant="body2" sx={{ opacity: 0.7 }}>
+        {t("Core Cache Info")}
+      </Typography>
+
+      <List>
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText
+            primary="cache.db"
+            secondary={
+              info?.exists
+                ? [
+                    `${size} ${unit}`,
+                    info.modified &&
+                      dayjs(info.modified * 1000).format("YYYY-MM-DD HH:mm"),
+                    info.fake_ip ? "fake-ip" : "",
+                  ]
+                    .filter(Boolean)
+                    .join(" · ")
+                : t("Not Exist")
+            }
+          />
+        </ListItem>
+
+        {STORE_KEYS.map((key) => {
+          const flag = verge?.store_override?.[key];
+          const current =
+            key === "store-selected"
+              ? info?.store.store_selected
+              : info?.store.store_fake_ip;
+          return (
+            <ListItem key={key} sx={{ padding: "5px 2px" }}>
+              <ListItemText
+                primary={key}
+                secondary={`${t("Current")}: ${current ? t("On") : t("Off")}`}
+              />
+              <Select
+                size="small"
+                sx={{ width: 160, "> div": { py: "7.5px" } }}
+                value={flag === undefined ? KEEP : String(flag)}
+                displayEmpty
+                onChange={(e) => onStore(key, e.target.value)}
+              >
+                <MenuItem value={KEEP}>{t("Keep Profile")}</MenuItem>
+                <MenuItem value="true">{t("On")}</MenuItem>
+                <MenuItem value="false">{t("Off")}</MenuItem>
+              </Select>
+            </ListItem>
+          );
+        })}
+      </List>
+
+      <Box sx={{ display: "flex", gap: 1, justifyContent: "flex-end" }}>
+        <Button
+          size="small"
+          variant="outlined"
+          disabled={loading || !isMeta}
+          onClick={() => onAction(flushFakeipCache)}
+        >
+          {t("Flush Fake-IP")}
+        </Button>
+        <Button
+          size="small"
+          variant="outlined"
+          color="warning"
+          disabled={loading || !info?.exists}
+          onClick={() => onAction(clearCoreCache)}
+        >
+          {t("Clear Cache")}
+        </Button>
+      </Box>
+    </BaseDialog>
+  );
+});
+
+CacheViewer.displayName = "CacheViewer";
@@ -0,0 +1,238 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  getChainCandidates,
+  saveProxyChains,
+  validateProxyChain,
+} from "@/services/cmds";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Box,
+  Chip,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const emptyChain = (): IProxyChain => ({
+  name: "",
+  proxies: [],
+  mode: "relay",
+  enable: true,
+});
+
+export const ChainViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [draft, setDraft] = useState(emptyChain());
+  const { verge, mutateVerge } = useVerge();
+
+  const chains = verge?.proxy_chains ?? [];
+
+  const { data: candidates = [] } = useSWR(
+    open ? "getChainCandidates" : null,
+    getChainCandidates,
+  );
+  const selectors = candidates.filter((node) => node.type === "select");
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setDraft(emptyChain());
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onSave = async (list: IProxyChain[]) => {
+    try {
+      await saveProxyChains(list);
+      mutateVerge();
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  };
+
+  const onAdd = useLockFn(async () => {
+    const chain = { ...draft, name: draft.name.trim() };
+    try {
+      await validateProxyChain(chain);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+      return;
+    }
+    await onSave([...chains, chain]);
+    setDraft(emptyChain());
+  });
+
+  const onToggle = useLockFn((index: number) =>
+    onSave(
+      chains.map((chain, i) =>
+        i === index ? { ...chain, enable: !chain.enable } : chain,
+      ),
+    ),
+  );
+
+  const onDelete = useLockFn((index: number) =>
+    onSave(chains.filter((_, i) => i !== index)),
+  );
+
+  // dialer-proxy 只有第一跳可以是组
+  const hopOptions = candidates.filter(
+    (node) =>
+      !draft.proxies.includes(node.name) &&
+      !["direct", "reject", "reject-drop"].includes(node.type) &&
+      (draft.mode === "relay" || !node.is_group || !draft.proxies.length),
+  );
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Proxy Chains")}
+      contentSx={{ width: 520, maxHeight: 560 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Box sx={{ display: "flex", gap: 1 }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Chain Name")}
+          sx={{ flex: 1 }}
+          value={draft.name}
+          onChange={(e) => setDraft((d) => ({ ...d, name: e.target.value }))}
+        />
+        <Select
+          size="small"
+          sx={{ width: 160, "> div": { py: "7.5px" } }}
+          value={draft.mode}
+          onChange={(e) =>
+            setDraft((d) => ({
+              ...d,
+              mode: e.target.value as IProxyChain["mode"],
+            }))
+          }
+        >
+          <MenuItem value="relay">{t("Relay")}</MenuItem>
+          <MenuItem value="dialer_proxy">{t("Dialer Proxy")}</MenuItem>
+        </Select>
+      </Box>
+
+      <Box sx={{ display: "flex", gap: 1, mt: 1 }}>
+        <Select
+          size="small"
+          sx={{ flex: 1, "> div": { py: "7.5px" } }}
+          value=""
+          displayEmpty
+          renderValue={() => t("Add Hop")}
+          onChange={(e) =>
+            setDraft((d) => ({ ...d, proxies: [...d.proxies, e.target.value] }))
+          }
+        >
+          {hopOptions.map((node) => (
+            <MenuItem key={node.name} value={node.name}>
+              {node.name}
+              <Typography
+                component="span"
+                variant="caption"
+                color="text.secondary"
+                sx={{ ml: 1 }}
+              >
+                {node.type}
+              </Typography>
+            </MenuItem>
+          ))}
+        </Select>
+        <Select
+          size="small"
+          sx={{ flex: 1, "> div": { py: "7.5px" } }}
+          value={draft.attach_to ?? ""}
+          displayEmpty
+          renderValue={(value) => value || t("Attach To Group")}
+          onChange={(e) =>
+            setDraft((d) => ({ ...d, attach_to: e.target.value || undefined }))
+          }
+        >
+          <MenuItem value="">-</MenuItem>
+          {selectors.map((node) => (
+            <MenuItem key={node.name} value={node.name}>
+              {node.name}
+            </MenuItem>
+          ))}
+        </Select>
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      {draft.proxies.length > 0 && (
+        <Box sx={{ display: "flex", gap: 0.5, mt: 1, flexWrap: "wrap" }}>
+          {draft.proxies.map((name, index) => (
+            <Chip
+              key={name}
+              size="small"
+              label={`${index + 1}. ${name}`}
+              onDelete={() =>
+                setDraft((d) => ({
+                  ...d,
+                  proxies: d.proxies.filter((hop) => hop !== name),
+                }))
+              }
+            />
+          ))}
+        </Box>
+      )}
+
+      {chains.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>
+          {t("No Proxy Chains")}
+        </Typography>
+      ) : (
+        <List>
+          {chains.map((chain, index) => (
+            <ListItem key={chain.name} sx={{ padding: "2px" }}>
+              <Switch
+                size="small"
+                checked={chain.enable}
+                onChange={() => onToggle(index)}
+              />
+              <ListItemText
+                primary={chain.name}
+                secondary={`${chain.proxies.join(" → ")} · ${t(
+                  chain.mode === "relay" ? "Relay" : "Dialer Proxy",
+                )}`}
+                sx={{
+                  ml: 1,
+                  opacity: chain.enable ? 1 : 0.5,
+                  wordBreak: "break-all",
+                }}
+              />
+              <IconButton
+                size="small"
+                color="inherit"
+                onClick={() => onDelete(index)}
+              >
+                <DeleteRounded fontSize="inherit" />
+              </IconButton>
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+ChainViewer.displayName = "ChainViewer";
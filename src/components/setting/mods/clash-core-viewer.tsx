@@ -4,14 +4,26 @@ import { useVerge } from "@/hooks/use-verge";
 import { closeAllConnections } from "@/services/api";
 import {
   changeClashCore,
+  checkCoreCompatibility,
   fetchLatestCoreVersions,
+  getArtifactCache,
   getCoreVersion,
   grantPermission,
+  installCoreFromFile,
+  purgeArtifactCache,
   restartSidecar,
+  selectCoreFile,
   updateCore,
 } from "@/services/cmds";
 import getSystem from "@/utils/get-system";
-import { FiberManualRecord, Lock, Update } from "@mui/icons-material";
+import parseTraffic from "@/utils/parse-traffic";
+import {
+  DeleteSweepRounded,
+  FiberManualRecord,
+  FileOpen,
+  Lock,
+  Update,
+} from "@mui/icons-material";
 import { LoadingButton } from "@mui/lab";
 import {
   Box,
@@ -20,13 +32,14 @@ import {
   List,
   ListItemButton,
   ListItemText,
+  Typography,
   alpha,
   useTheme,
 } from "@mui/material";
 import { useAsyncEffect, useLockFn } from "ahooks";
 import { forwardRef, useImperativeHandle, useState } from "react";
 import { useTranslation } from "react-i18next";
-import { mutate } from "swr";
+import useSWR, { mutate } from "swr";
 
 type ClashCore = Required<IVergeConfig>["clash_core"];
 
@@ -61,6 +74,25 @@ export const ClashCoreViewer = forwardRef<DialogRef>((props, ref) => {
 
   const { clash_core = "clash" } = verge ?? {};
 
+  const { data: cache, mutate: mutateCache } = useSWR(
+    open ? "getArtifactCache" : null,
+    getArtifactCache,
+  );
+
+  const onPurge = useLockFn(async () => {
+    try {
+      const size = await purgeArtifactCache();
+      useNotification(
+        t("Success"),
+        t("Space Reclaimed", { size: parseTraffic(size).join(" ") }),
+      );
+    } catch (err: any) {
+      useNotification(t("Error"), err?.message || err.toString());
+    } finally {
+      mutateCache();
+    }
+  });
+
   const [checkUpdatesLoading, setCheckUpdatesLoading] = useState(false);
   const onCheckUpdates = useLockFn(async () => {
     try {
@@ -183,6 +215,24 @@ export const ClashCoreViewer = forwardRef<DialogRef>((props, ref) => {
           />
         ))}
       </List>
+
+      <Box display="flex" alignItems="center" sx={{ px: 2, pb: 1 }}>
+        <Typography variant="body2" sx={{ flex: 1, opacity: 0.7 }}>
+          {t("Artifact Cache", {
+            count: cache?.entries.length ?? 0,
+            size: parseTraffic(cache?.size ?? 0).join(" "),
+          })}
+        </Typography>
+        <IconButton
+          color="inherit"
+          size="small"
+          title={t("Purge Cache")}
+          disabled={!cache?.entries.length}
+          onClick={onPurge}
+        >
+          <DeleteSweepRounded fontSize="inherit" />
+        </IconButton>
+      </Box>
     </BaseDialog>
   );
 });
@@ -212,6 +262,14 @@ function CoreElement({
       setLoading(true);
       onCoreChanged(core, "start");
       closeAllConnections();
+      // 不支持的覆写会被跳过，切换前提示
+      const warnings = await checkCoreCompatibility(core);
+      if (warnings.length) {
+        useNotification(
+          t("Core Compatibility"),
+          warnings.map((w) => w.message).join("\n"),
+        );
+      }
       await changeClashCore(core);
       mutateVerge();
       setTimeout(() => {
@@ -258,6 +316,29 @@ function CoreElement({
     },
   );
 
+  // 离线安装，本地的内核文件或者 release 的压缩包
+  const onInstallFile = useLockFn(async () => {
+    try {
+      const path = await selectCoreFile();
+      if (!path) return;
+      setUpdateCoreLoading(true);
+      const version = await installCoreFromFile(path, core.core);
+      mutateVerge();
+      setTimeout(() => {
+        mutate("getClashConfig");
+        mutate("getVersion");
+      }, 100);
+      useNotification(
+        t("Success"),
+        `Successfully installed ${core.core} ${version}`,
+      );
+    } catch (err: any) {
+      useNotification(t("Error"), err?.message || err.toString());
+    } finally {
+      setUpdateCoreLoading(false);
+    }
+  });
+
   return (
     <ListItemButton
       selected={selected}
@@ -329,6 +410,19 @@ function CoreElement({
           )}
         </IconButton>
       )}
+      <IconButton
+        color="inherit"
+        size="small"
+        title={t("Install From File")}
+        disabled={updateCoreLoading}
+        onClick={(e) => {
+          e.preventDefault();
+          e.stopPropagation();
+          onInstallFile();
+        }}
+      >
+        <FileOpen fontSize="inherit" />
+      </IconButton>
       {(OS === "macos" || OS === "linux") && (
         <IconButton
           color="inherit"
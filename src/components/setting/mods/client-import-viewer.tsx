@@ -0,0 +1,148 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import {
+  importClientData,
+  onboardingDetectClients,
+  previewClientImport,
+} from "@/services/cmds";
+import {
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { mutate } from "swr";
+import { SettingItem } from "./setting-comp";
+
+export const ClientImportViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [clients, setClients] = useState<IDetectedClient[]>([]);
+  const [client, setClient] = useState<IKnownClient | "">("");
+  const [report, setReport] = useState<IImportReport | null>(null);
+
+  const onError = (err: any) =>
+    useNotification(t("Error"), err.message || err.toString());
+
+  // 先预览，确认后再导入
+  const onPreview = useLockFn(async (client: IKnownClient) => {
+    setClient(client);
+    setReport(null);
+    try {
+      setReport(await previewClientImport(client));
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setReport(null);
+      onboardingDetectClients()
+        .then((list) => {
+          setClients(list);
+          if (list.length) onPreview(list[0].client);
+        })
+        .catch(onError);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onImport = useLockFn(async () => {
+    if (!client) return;
+    try {
+      const report = await importClientData(client);
+      mutate("getProfiles");
+      mutate("getVergeConfig");
+      useNotification(
+        t("Success"),
+        t("Imported {{count}} profiles", { count: report.profiles.length }),
+      );
+      setOpen(false);
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Import From Other Clients")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Import")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onImport}
+    >
+      <List>
+        <SettingItem label={t("Client")}>
+          <Select
+            size="small"
+            sx={{ width: 200, "> div": { py: "7.5px" } }}
+            value={client}
+            onChange={(e) => onPreview(e.target.value as IKnownClient)}
+          >
+            {clients.map((each) => (
+              <MenuItem key={each.client} value={each.client}>
+                {each.name}
+              </MenuItem>
+            ))}
+          </Select>
+        </SettingItem>
+
+        {clients.length === 0 && (
+          <Typography variant="body2" color="GrayText" sx={{ px: "2px" }}>
+            {t("No Clients Found")}
+          </Typography>
+        )}
+
+        {report && (
+          <>
+            <ListItem sx={{ padding: "5px 2px" }}>
+              <ListItemText primary={t("Profiles")} />
+            </ListItem>
+            {report.profiles.map((profile, index) => (
+              <Typography key={index} variant="body2" sx={{ px: "2px" }}>
+                {profile.name} · {profile.kind}
+                {profile.enabled && ` · ${t("Enabled")}`}
+              </Typography>
+            ))}
+
+            <ListItem sx={{ padding: "5px 2px" }}>
+              <ListItemText primary={t("Settings")} />
+            </ListItem>
+            <Typography variant="body2" sx={{ px: "2px" }}>
+              {report.settings.join(", ") || "-"}
+            </Typography>
+
+            {report.skipped.length > 0 && (
+              <ListItem sx={{ padding: "5px 2px" }}>
+                <ListItemText primary={t("Not Imported")} />
+              </ListItem>
+            )}
+            {report.skipped.map((skipped, index) => (
+              <Typography
+                key={index}
+                variant="body2"
+                color="GrayText"
+                sx={{ px: "2px", wordBreak: "break-all" }}
+              >
+                {skipped.item}: {skipped.reason}
+              </Typography>
+            ))}
+          </>
+        )}
+      </List>
+    </BaseDialog>
+  );
+});
+
+ClientImportViewer.displayName = "ClientImportViewer";
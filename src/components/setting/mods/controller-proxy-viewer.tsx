@@ -0,0 +1,212 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  clearExternalMutations,
+  getExternalMutations,
+  resetControllerProxyToken,
+} from "@/services/cmds";
+import { ContentCopyRounded, RefreshRounded } from "@mui/icons-material";
+import {
+  Box,
+  Button,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import dayjs from "dayjs";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+export const ControllerProxyViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge, mutateVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [values, setValues] = useState({
+    enable: false,
+    port: 33337,
+    access: "read_only" as IRemoteAccess,
+  });
+
+  const { data: mutations, mutate } = useSWR(
+    open ? "getExternalMutations" : null,
+    getExternalMutations,
+    { refreshInterval: 5000 },
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setValues({
+        enable: verge?.enable_controller_proxy ?? false,
+        port: verge?.controller_proxy_port ?? 33337,
+        access: verge?.controller_proxy_access ?? "read_only",
+      });
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onError = (err: any) =>
+    useNotification(t("Error"), err.message || err.toString());
+
+  const onSave = useLockFn(async () => {
+    try {
+      await patchVerge({
+        enable_controller_proxy: values.enable,
+        controller_proxy_port: values.port,
+        controller_proxy_access: values.access,
+      });
+      setOpen(false);
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onReset = useLockFn(async () => {
+    try {
+      await resetControllerProxyToken();
+      mutateVerge();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onClear = useLockFn(async () => {
+    try {
+      await clearExternalMutations();
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const token = verge?.controller_proxy_token ?? "";
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Controller Proxy")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <Typography variant="body2" sx={{ opacity: 0.7 }}>
+        {t("Controller Proxy Info")}
+      </Typography>
+
+      <List>
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Enable")} />
+          <Switch
+            edge="end"
+            checked={values.enable}
+            onChange={(_, enable) => setValues((v) => ({ ...v, enable }))}
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Port")} />
+          <TextField
+            size="small"
+            type="number"
+            autoComplete="off"
+            sx={{ width: 100 }}
+            value={values.port}
+            onChange={(e) =>
+              setValues((v) => ({
+                ...v,
+                port: parseInt(e.target.value) || 0,
+              }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Access")} />
+          <Select
+            size="small"
+            sx={{ width: 140, "> div": { py: "7.5px" } }}
+            value={values.access}
+            onChange={(e) =>
+              setValues((v) => ({
+                ...v,
+                access: e.target.value as IRemoteAccess,
+              }))
+            }
+          >
+            <MenuItem value="read_only">{t("Read Only")}</MenuItem>
+            <MenuItem value="read_write">{t("Read Write")}</MenuItem>
+          </Select>
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText
+            primary={t("Dashboard Secret")}
+            secondary={token ? `${token.slice(0, 6)}…` : t("Not Generated")}
+          />
+          <IconButton
+            size="small"
+            disabled={!token}
+            title={t("Copy")}
+            onClick={() => navigator.clipboard.writeText(token)}
+          >
+            <ContentCopyRounded fontSize="inherit" />
+          </IconButton>
+          <IconButton size="small" title={t("Reset")} onClick={onReset}>
+            <RefreshRounded fontSize="inherit" />
+          </IconButton>
+        </ListItem>
+      </List>
+
+      <Box
+        sx={{
+          display: "flex",
+          alignItems: "center",
+          justifyContent: "space-between",
+        }}
+      >
+        <Typography variant="subtitle2">{t("External Mutations")}</Typography>
+        <Button size="small" disabled={!mutations?.length} onClick={onClear}>
+          {t("Clear")}
+        </Button>
+      </Box>
+
+      <List dense sx={{ maxHeight: 200, overflow: "auto" }}>
+        {mutations?.length ? (
+          mutations.map((item, index) => (
+            <ListItem key={index} sx={{ padding: "2px 2px" }}>
+              <ListItemText
+                primary={`${item.method} ${item.path}`}
+                secondary={[
+                  dayjs(item.time * 1000).format("MM-DD HH:mm:ss"),
+                  item.origin,
+                  item.allowed ? item.status ?? t("Failed") : t("Denied"),
+                ]
+                  .filter(Boolean)
+                  .join(" · ")}
+              />
+            </ListItem>
+          ))
+        ) : (
+          <Typography variant="body2" sx={{ opacity: 0.7, py: 1 }}>
+            {t("No Records")}
+          </Typography>
+        )}
+      </List>
+    </BaseDialog>
+  );
+});
+
+ControllerProxyViewer.displayName = "ControllerProxyViewer";
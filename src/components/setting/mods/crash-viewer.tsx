@@ -0,0 +1,165 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  clearCrashReports,
+  getCrashReports,
+  uploadCrashReport,
+} from "@/services/cmds";
+import {
+  Button,
+  List,
+  ListItem,
+  ListItemText,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import dayjs from "dayjs";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { GuardState } from "./guard-state";
+import { SettingItem } from "./setting-comp";
+
+const KIND_LABELS: Record<ICrashReport["kind"], string> = {
+  app_panic: "App Panic",
+  core_exit: "Core Exit",
+  native_crash: "Native Crash",
+};
+
+export const CrashViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge, mutateVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [reports, setReports] = useState<ICrashReport[]>([]);
+
+  const refresh = () => getCrashReports().then(setReports);
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      refresh();
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onSwitchFormat = (_e: any, value: boolean) => value;
+  const onError = (err: any) =>
+    useNotification(t("Error"), err.message || err.toString());
+  const onChangeData = (patch: Partial<IVergeConfig>) => {
+    mutateVerge({ ...verge, ...patch }, false);
+  };
+
+  const onUpload = useLockFn(async (id: string) => {
+    try {
+      await uploadCrashReport(id);
+      await refresh();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onClear = useLockFn(async () => {
+    try {
+      await clearCrashReports();
+      await refresh();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const hasUrl = !!verge?.crash_upload_url?.trim();
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Crash Reports")}
+      contentSx={{ width: 450 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Typography variant="body2" color="GrayText" sx={{ px: "2px" }}>
+        {t("Crash Reports Description")}
+      </Typography>
+
+      <List>
+        <SettingItem label={t("Upload Crash Reports")}>
+          <GuardState
+            value={verge?.enable_crash_upload ?? false}
+            valueProps="checked"
+            onCatch={onError}
+            onFormat={onSwitchFormat}
+            onChange={(e) => onChangeData({ enable_crash_upload: e })}
+            onGuard={(e) => patchVerge({ enable_crash_upload: e })}
+          >
+            <Switch edge="end" />
+          </GuardState>
+        </SettingItem>
+
+        <SettingItem label={t("Upload Url")}>
+          <GuardState
+            value={verge?.crash_upload_url ?? ""}
+            onCatch={onError}
+            onFormat={(e: any) => e.target.value}
+            onChange={(e) => onChangeData({ crash_upload_url: e })}
+            onGuard={(e) => patchVerge({ crash_upload_url: e })}
+            waitTime={1000}
+          >
+            <TextField
+              size="small"
+              autoComplete="off"
+              sx={{ width: 250 }}
+              placeholder="https://"
+            />
+          </GuardState>
+        </SettingItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Saved Reports")} />
+          <Button
+            size="small"
+            disabled={reports.length === 0}
+            onClick={onClear}
+          >
+            {t("Clear")}
+          </Button>
+        </ListItem>
+
+        {reports.length === 0 && (
+          <Typography variant="body2" color="GrayText" sx={{ px: "2px" }}>
+            {t("No Crash Reports")}
+          </Typography>
+        )}
+
+        {reports.map((report) => (
+          <ListItem
+            key={report.id}
+            sx={{ padding: "2px 2px", display: "block" }}
+          >
+            <Typography variant="body2" sx={{ wordBreak: "break-all" }}>
+              {report.message}
+            </Typography>
+            <Typography variant="body2" color="GrayText">
+              {dayjs(report.time * 1000).format("YYYY-MM-DD HH:mm:ss")} ·{" "}
+              {t(KIND_LABELS[report.kind])} ·{" "}
+              v{report.app_version}
+            </Typography>
+            <Button
+              size="small"
+              disabled={report.uploaded || !hasUrl}
+              onClick={() => onUpload(report.id)}
+            >
+              {report.uploaded ? t("Uploaded") : t("Upload")}
+            </Button>
+          </ListItem>
+        ))}
+      </List>
+    </BaseDialog>
+  );
+});
+
+CrashViewer.displayName = "CrashViewer";
@@ -0,0 +1,255 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useProfiles } from "@/hooks/use-profiles";
+import { getDnsOverride, setDnsOverride } from "@/services/cmds";
+import {
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  TextField,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+// the empty string means the global scope
+const GLOBAL = "";
+
+// the empty string keeps the value of the profile
+const KEEP = "";
+
+type ListKey =
+  | "nameserver"
+  | "default-nameserver"
+  | "fallback"
+  | "fake-ip-filter";
+
+const LIST_KEYS: ListKey[] = [
+  "nameserver",
+  "default-nameserver",
+  "fallback",
+  "fake-ip-filter",
+];
+
+interface FormValue {
+  enable: string;
+  ipv6: string;
+  mode: string;
+  lists: Record<ListKey, string>;
+  hosts: string;
+}
+
+const emptyForm = (): FormValue => ({
+  enable: KEEP,
+  ipv6: KEEP,
+  mode: KEEP,
+  lists: {
+    nameserver: "",
+    "default-nameserver": "",
+    fallback: "",
+    "fake-ip-filter": "",
+  },
+  hosts: "",
+});
+
+export const DnsViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [scope, setScope] = useState(GLOBAL);
+  const [value, setValue] = useState(emptyForm());
+
+  const { profiles } = useProfiles();
+  const items = (profiles?.items ?? []).filter(
+    (item) => item.type === "remote" || item.type === "local",
+  );
+
+  const load = async (uid: string) => {
+    setScope(uid);
+    try {
+      const dns = await getDnsOverride(uid || undefined);
+      setValue(toForm(dns));
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  };
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      load(GLOBAL);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onSave = useLockFn(async () => {
+    try {
+      await setDnsOverride(scope || undefined, fromForm(value));
+      setOpen(false);
+      useNotification(t("Success"), t("DNS Override Saved"));
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  const boolSelect = (key: "enable" | "ipv6") => (
+    <Select
+      size="small"
+      sx={{ width: 160, "> div": { py: "7.5px" } }}
+      value={value[key]}
+      displayEmpty
+      onChange={(e) => setValue((v) => ({ ...v, [key]: e.target.value }))}
+    >
+      <MenuItem value={KEEP}>{t("Keep Profile")}</MenuItem>
+      <MenuItem value="true">{t("On")}</MenuItem>
+      <MenuItem value="false">{t("Off")}</MenuItem>
+    </Select>
+  );
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("DNS Override")}
+      contentSx={{ width: 450, maxHeight: 520 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <List>
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("DNS Override Scope")} />
+          <Select
+            size="small"
+            sx={{ width: 160, "> div": { py: "7.5px" } }}
+            value={scope}
+            displayEmpty
+            onChange={(e) => load(e.target.value)}
+          >
+            <MenuItem value={GLOBAL}>{t("Global")}</MenuItem>
+            {items.map((item) => (
+              <MenuItem key={item.uid} value={item.uid}>
+                {item.name}
+              </MenuItem>
+            ))}
+          </Select>
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Enable DNS")} />
+          {boolSelect("enable")}
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary="IPv6" />
+          {boolSelect("ipv6")}
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Enhanced Mode")} />
+          <Select
+            size="small"
+            sx={{ width: 160, "> div": { py: "7.5px" } }}
+            value={value.mode}
+            displayEmpty
+            onChange={(e) => setValue((v) => ({ ...v, mode: e.target.value }))}
+          >
+            <MenuItem value={KEEP}>{t("Keep Profile")}</MenuItem>
+            <MenuItem value="fake-ip">fake-ip</MenuItem>
+            <MenuItem value="redir-host">redir-host</MenuItem>
+          </Select>
+        </ListItem>
+
+        {LIST_KEYS.map((key) => (
+          <ListItem key={key} sx={{ padding: "5px 2px", alignItems: "start" }}>
+            <ListItemText primary={key} sx={{ padding: "3px 0" }} />
+            <TextField
+              size="small"
+              autoComplete="off"
+              multiline
+              rows={3}
+              sx={{ width: 280 }}
+              placeholder={t("Keep Profile")}
+              value={value.lists[key]}
+              onChange={(e) =>
+                setValue((v) => ({
+                  ...v,
+                  lists: { ...v.lists, [key]: e.target.value },
+                }))
+              }
+            />
+          </ListItem>
+        ))}
+
+        <ListItem sx={{ padding: "5px 2px", alignItems: "start" }}>
+          <ListItemText primary="hosts" sx={{ padding: "3px 0" }} />
+          <TextField
+            size="small"
+            autoComplete="off"
+            multiline
+            rows={3}
+            sx={{ width: 280 }}
+            placeholder="router.lan 192.168.1.1"
+            value={value.hosts}
+            onChange={(e) => setValue((v) => ({ ...v, hosts: e.target.value }))}
+          />
+        </ListItem>
+      </List>
+    </BaseDialog>
+  );
+});
+
+DnsViewer.displayName = "DnsViewer";
+
+const toForm = (dns: IDnsOverride | null): FormValue => {
+  const form = emptyForm();
+  if (!dns) return form;
+
+  const flag = (v?: boolean) => (v === undefined ? KEEP : String(v));
+  form.enable = flag(dns.enable);
+  form.ipv6 = flag(dns.ipv6);
+  form.mode = dns["enhanced-mode"] ?? KEEP;
+  LIST_KEYS.forEach((key) => {
+    form.lists[key] = (dns[key] ?? []).join("\n");
+  });
+  form.hosts = Object.entries(dns.hosts ?? {})
+    .map(([domain, ip]) => `${domain} ${ip}`)
+    .join("\n");
+  return form;
+};
+
+// one item per line, the empty fields are not overridden
+const fromForm = (form: FormValue): IDnsOverride | undefined => {
+  const dns: IDnsOverride = {};
+  const flag = (v: string) => (v === KEEP ? undefined : v === "true");
+
+  if (form.enable !== KEEP) dns.enable = flag(form.enable);
+  if (form.ipv6 !== KEEP) dns.ipv6 = flag(form.ipv6);
+  if (form.mode !== KEEP) dns["enhanced-mode"] = form.mode;
+
+  LIST_KEYS.forEach((key) => {
+    const list = splitLines(form.lists[key]);
+    if (list.length) dns[key] = list;
+  });
+
+  const hosts = splitLines(form.hosts);
+  if (hosts.length) {
+    dns.hosts = Object.fromEntries(
+      hosts.map((line) => {
+        const [domain, ip = ""] = line.split(/\s+/);
+        return [domain, ip];
+      }),
+    );
+  }
+
+  return Object.keys(dns).length ? dns : undefined;
+};
+
+const splitLines = (text: string) =>
+  text
+    .split("\n")
+    .map((line) => line.trim())
+    .filter(Boolean);
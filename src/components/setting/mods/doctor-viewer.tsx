@@ -0,0 +1,119 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { runConnectivityDoctor } from "@/services/cmds";
+import { Cancel, CheckCircle, RemoveCircle } from "@mui/icons-material";
+import {
+  Button,
+  List,
+  ListItem,
+  ListItemIcon,
+  ListItemText,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+const StatusIcon = ({ status }: { status: IDoctorCheck["status"] }) => {
+  switch (status) {
+    case "pass":
+      return <CheckCircle color="success" fontSize="small" />;
+    case "fail":
+      return <Cancel color="error" fontSize="small" />;
+    default:
+      return <RemoveCircle color="disabled" fontSize="small" />;
+  }
+};
+
+export const DoctorViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [running, setRunning] = useState(false);
+  const [checks, setChecks] = useState<IDoctorCheck[]>([]);
+
+  const onRun = useLockFn(async () => {
+    setRunning(true);
+    try {
+      setChecks(await runConnectivityDoctor());
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    } finally {
+      setRunning(false);
+    }
+  });
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      onRun();
+    },
+    close: () => setOpen(false),
+  }));
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Connectivity Doctor")}
+      contentSx={{ width: 450 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <List>
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText
+            primary={running ? t("Checking...") : t("Connectivity Checks")}
+          />
+          <Button
+            variant="contained"
+            size="small"
+            disabled={running}
+            onClick={onRun}
+          >
+            {t("Run")}
+          </Button>
+        </ListItem>
+
+        {checks.map((check) => (
+          <ListItem
+            key={check.id}
+            sx={{ padding: "2px 2px", alignItems: "flex-start" }}
+          >
+            <ListItemIcon sx={{ minWidth: 32, mt: "6px" }}>
+              <StatusIcon status={check.status} />
+            </ListItemIcon>
+            <ListItemText
+              primary={t(`doctor_${check.id}`)}
+              secondary={
+                <>
+                  <Typography
+                    component="span"
+                    variant="body2"
+                    display="block"
+                    sx={{ wordBreak: "break-all" }}
+                  >
+                    {check.detail}
+                  </Typography>
+                  {check.hint && (
+                    <Typography
+                      component="span"
+                      variant="body2"
+                      display="block"
+                      color="warning.main"
+                    >
+                      {check.hint}
+                    </Typography>
+                  )}
+                </>
+              }
+            />
+          </ListItem>
+        ))}
+      </List>
+    </BaseDialog>
+  );
+});
+
+DoctorViewer.displayName = "DoctorViewer";
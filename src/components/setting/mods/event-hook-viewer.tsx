@@ -0,0 +1,213 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import { saveEventHook, testEventHook } from "@/services/cmds";
+import {
+  Button,
+  Checkbox,
+  FormControlLabel,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+const EVENTS: { value: IHookEvent; label: string }[] = [
+  { value: "core_crashed", label: "Core Crashed" },
+  { value: "profile_updated", label: "Profile Updated" },
+  { value: "quota_exceeded", label: "Quota Exceeded" },
+  { value: "node_switched", label: "Node Switched" },
+];
+
+const initValues = (hook?: IEventHook | null) => ({
+  enable: hook?.enable ?? false,
+  type: hook?.target.type ?? "webhook",
+  url: hook?.target.type === "webhook" ? hook.target.url : "",
+  broker: hook?.target.type === "mqtt" ? hook.target.broker : "",
+  topic: hook?.target.type === "mqtt" ? hook.target.topic : "nyanpasu/events",
+  username: (hook?.target.type === "mqtt" && hook.target.username) || "",
+  password: "",
+  events: hook?.events ?? (["core_crashed"] as IHookEvent[]),
+});
+
+export const EventHookViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [values, setValues] = useState(initValues());
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setValues(initValues(verge?.event_hook));
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onError = (err: any) =>
+    useNotification(t("Error"), err.message || err.toString());
+
+  const toHook = (): IEventHook => ({
+    enable: values.enable,
+    target:
+      values.type === "webhook"
+        ? { type: "webhook", url: values.url.trim() }
+        : {
+            type: "mqtt",
+            broker: values.broker.trim(),
+            topic: values.topic.trim(),
+            username: values.username.trim() || null,
+          },
+    events: values.events,
+  });
+
+  const save = () => saveEventHook(toHook(), values.password || undefined);
+
+  const onSave = useLockFn(async () => {
+    try {
+      await save();
+      setOpen(false);
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  // 先保存再发送测试事件
+  const onTest = useLockFn(async () => {
+    try {
+      await save();
+      await testEventHook();
+      useNotification(t("Success"), t("Test Event Sent"));
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const toggleEvent = (event: IHookEvent) =>
+    setValues((v) => ({
+      ...v,
+      events: v.events.includes(event)
+        ? v.events.filter((e) => e !== event)
+        : [...v.events, event],
+    }));
+
+  const textField = (key: "url" | "broker" | "topic" | "username") => (
+    <TextField
+      size="small"
+      autoComplete="off"
+      sx={{ width: 250 }}
+      value={values[key]}
+      onChange={(e) => setValues((v) => ({ ...v, [key]: e.target.value }))}
+    />
+  );
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Event Hook")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <List>
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Enable")} />
+          <Switch
+            edge="end"
+            checked={values.enable}
+            onChange={(_, enable) => setValues((v) => ({ ...v, enable }))}
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Hook Type")} />
+          <Select
+            size="small"
+            sx={{ width: 140, "> div": { py: "7.5px" } }}
+            value={values.type}
+            onChange={(e) =>
+              setValues((v) => ({
+                ...v,
+                type: e.target.value as IHookTarget["type"],
+              }))
+            }
+          >
+            <MenuItem value="webhook">Webhook</MenuItem>
+            <MenuItem value="mqtt">MQTT</MenuItem>
+          </Select>
+        </ListItem>
+
+        {values.type === "webhook" ? (
+          <ListItem sx={{ padding: "5px 2px" }}>
+            <ListItemText primary={t("Webhook URL")} />
+            {textField("url")}
+          </ListItem>
+        ) : (
+          <>
+            <ListItem sx={{ padding: "5px 2px" }}>
+              <ListItemText primary={t("MQTT Broker")} />
+              {textField("broker")}
+            </ListItem>
+            <ListItem sx={{ padding: "5px 2px" }}>
+              <ListItemText primary={t("MQTT Topic")} />
+              {textField("topic")}
+            </ListItem>
+            <ListItem sx={{ padding: "5px 2px" }}>
+              <ListItemText primary={t("Username")} />
+              {textField("username")}
+            </ListItem>
+            <ListItem sx={{ padding: "5px 2px" }}>
+              <ListItemText primary={t("Password")} />
+              <TextField
+                size="small"
+                type="password"
+                autoComplete="off"
+                sx={{ width: 250 }}
+                value={values.password}
+                placeholder={t("Unchanged")}
+                onChange={(e) =>
+                  setValues((v) => ({ ...v, password: e.target.value }))
+                }
+              />
+            </ListItem>
+          </>
+        )}
+
+        <ListItem sx={{ padding: "5px 2px", flexWrap: "wrap" }}>
+          {EVENTS.map(({ value, label }) => (
+            <FormControlLabel
+              key={value}
+              label={t(label)}
+              control={
+                <Checkbox
+                  size="small"
+                  checked={values.events.includes(value)}
+                  onChange={() => toggleEvent(value)}
+                />
+              }
+            />
+          ))}
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Test Event")} />
+          <Button variant="contained" size="small" onClick={onTest}>
+            {t("Send")}
+          </Button>
+        </ListItem>
+      </List>
+    </BaseDialog>
+  );
+});
+
+EventHookViewer.displayName = "EventHookViewer";
@@ -0,0 +1,246 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useProfiles } from "@/hooks/use-profiles";
+import { useVerge } from "@/hooks/use-verge";
+import { getFailoverStatus } from "@/services/cmds";
+import {
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import dayjs from "dayjs";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const CORES = ["mihomo", "mihomo-alpha", "clash-rs", "clash"] as const;
+
+// the empty value skips the action
+const NONE = "";
+
+const initValues = (failover?: ICoreFailover | null) => {
+  const find = <T extends IFailoverAction["type"]>(type: T) =>
+    failover?.actions.find((action) => action.type === type) as
+      | Extract<IFailoverAction, { type: T }>
+      | undefined;
+  return {
+    enable: failover?.enable ?? false,
+    threshold: failover?.threshold ?? 3,
+    window: Math.round((failover?.window ?? 300) / 60),
+    profile: find("switch_profile")?.uid ?? NONE,
+    core: find("switch_core")?.core ?? NONE,
+    disableProxy: !!find("disable_system_proxy"),
+    notify: failover ? !!find("notify") : true,
+  };
+};
+
+export const FailoverViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge } = useVerge();
+  const { profiles = {} } = useProfiles();
+
+  const [open, setOpen] = useState(false);
+  const [values, setValues] = useState(initValues());
+
+  const { data: status } = useSWR(
+    open ? "getFailoverStatus" : null,
+    getFailoverStatus,
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setValues(initValues(verge?.core_failover));
+    },
+    close: () => setOpen(false),
+  }));
+
+  const profileItems = (profiles.items ?? []).filter(
+    (item) => item.type === "local" || item.type === "remote",
+  );
+
+  // 按固定的顺序执行：切换配置、切换内核、关闭代理、通知
+  const toActions = (): IFailoverAction[] => {
+    const actions: IFailoverAction[] = [];
+    if (values.profile) {
+      actions.push({ type: "switch_profile", uid: values.profile });
+    }
+    if (values.core) {
+      actions.push({
+        type: "switch_core",
+        core: values.core as IVergeConfig["clash_core"],
+      });
+    }
+    if (values.disableProxy) actions.push({ type: "disable_system_proxy" });
+    if (values.notify) actions.push({ type: "notify" });
+    return actions;
+  };
+
+  const onSave = useLockFn(async () => {
+    try {
+      await patchVerge({
+        core_failover: {
+          enable: values.enable,
+          threshold: values.threshold,
+          window: values.window * 60,
+          actions: toActions(),
+        },
+      });
+      setOpen(false);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  const lastRun = status?.last_run;
+  const failed = lastRun?.results.filter((r) => r.error).length ?? 0;
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Core Failover")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <Typography variant="body2" sx={{ opacity: 0.7 }}>
+        {t("Core Failover Info")}
+      </Typography>
+
+      <List>
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Enable")} />
+          <Switch
+            edge="end"
+            checked={values.enable}
+            onChange={(_, enable) => setValues((v) => ({ ...v, enable }))}
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Failure Threshold")} />
+          <TextField
+            size="small"
+            type="number"
+            sx={{ width: 100 }}
+            inputProps={{ min: 1 }}
+            value={values.threshold}
+            onChange={(e) =>
+              setValues((v) => ({
+                ...v,
+                threshold: Math.max(1, +e.target.value || 1),
+              }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Within Minutes")} />
+          <TextField
+            size="small"
+            type="number"
+            sx={{ width: 100 }}
+            inputProps={{ min: 1 }}
+            value={values.window}
+            onChange={(e) =>
+              setValues((v) => ({
+                ...v,
+                window: Math.max(1, +e.target.value || 1),
+              }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Switch Profile")} />
+          <Select
+            size="small"
+            displayEmpty
+            sx={{ width: 160, "> div": { py: "7.5px" } }}
+            value={values.profile}
+            onChange={(e) =>
+              setValues((v) => ({ ...v, profile: e.target.value }))
+            }
+          >
+            <MenuItem value={NONE}>{t("Keep Current")}</MenuItem>
+            {profileItems.map((item) => (
+              <MenuItem key={item.uid} value={item.uid}>
+                {item.name}
+              </MenuItem>
+            ))}
+          </Select>
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Switch Core")} />
+          <Select
+            size="small"
+            displayEmpty
+            sx={{ width: 160, "> div": { py: "7.5px" } }}
+            value={values.core}
+            onChange={(e) => setValues((v) => ({ ...v, core: e.target.value }))}
+          >
+            <MenuItem value={NONE}>{t("Keep Current")}</MenuItem>
+            {CORES.filter((core) => core !== verge?.clash_core).map((core) => (
+              <MenuItem key={core} value={core}>
+                {core}
+              </MenuItem>
+            ))}
+          </Select>
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText
+            primary={t("Disable System Proxy")}
+            secondary={t("Turn off the system proxy and the TUN mode")}
+          />
+          <Switch
+            edge="end"
+            checked={values.disableProxy}
+            onChange={(_, disableProxy) =>
+              setValues((v) => ({ ...v, disableProxy }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Notify")} />
+          <Switch
+            edge="end"
+            checked={values.notify}
+            onChange={(_, notify) => setValues((v) => ({ ...v, notify }))}
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText
+            primary={t("Last Failover")}
+            secondary={
+              lastRun
+                ? t("Failover Run Result", {
+                    time: dayjs(lastRun.time * 1000).format("MM-DD HH:mm"),
+                    total: lastRun.results.length,
+                    failed,
+                  })
+                : t("Never")
+            }
+          />
+          <Typography variant="body2" sx={{ opacity: 0.7 }}>
+            {t("Recent Failures", { failures: status?.failures ?? 0 })}
+          </Typography>
+        </ListItem>
+      </List>
+    </BaseDialog>
+  );
+});
+
+FailoverViewer.displayName = "FailoverViewer";
@@ -0,0 +1,104 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { exportFlattenedConfig } from "@/services/cmds";
+import { List, Switch, Typography } from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { SettingItem } from "./setting-comp";
+
+export const FlattenViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [envSecrets, setEnvSecrets] = useState(true);
+  const [allowLan, setAllowLan] = useState(true);
+  const [result, setResult] = useState<IFlattenResult | null>(null);
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setResult(null);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onExport = useLockFn(async () => {
+    try {
+      setResult(
+        await exportFlattenedConfig({
+          env_secrets: envSecrets,
+          allow_lan: allowLan,
+        }),
+      );
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Export For Router")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Export")}
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onExport}
+    >
+      <Typography variant="body2" color="GrayText" sx={{ px: "2px" }}>
+        {t("Export For Router Description")}
+      </Typography>
+
+      <List>
+        <SettingItem label={t("Template Secrets")}>
+          <Switch
+            edge="end"
+            checked={envSecrets}
+            onChange={(_, checked) => setEnvSecrets(checked)}
+          />
+        </SettingItem>
+
+        <SettingItem label={t("Allow Lan")}>
+          <Switch
+            edge="end"
+            checked={allowLan}
+            onChange={(_, checked) => setAllowLan(checked)}
+          />
+        </SettingItem>
+      </List>
+
+      {result && (
+        <>
+          <Typography variant="body2" sx={{ wordBreak: "break-all" }}>
+            {result.path}
+          </Typography>
+          {result.env_path && (
+            <Typography variant="body2" sx={{ wordBreak: "break-all" }}>
+              {result.env_path}
+            </Typography>
+          )}
+          <Typography variant="body2" color="GrayText">
+            {t("Flattened {{proxies}} proxies, {{rules}} rule providers", {
+              proxies: result.proxies,
+              rules: result.rule_providers,
+            })}
+          </Typography>
+          {result.warnings.map((warning, index) => (
+            <Typography
+              key={index}
+              variant="body2"
+              color="warning.main"
+              sx={{ wordBreak: "break-all" }}
+            >
+              {warning}
+            </Typography>
+          ))}
+        </>
+      )}
+    </BaseDialog>
+  );
+});
+
+FlattenViewer.displayName = "FlattenViewer";
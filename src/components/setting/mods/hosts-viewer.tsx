@@ -0,0 +1,155 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import { readSystemHosts } from "@/services/cmds";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Box,
+  Button,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+export const HostsViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const { verge, patchVerge } = useVerge();
+
+  const [entries, setEntries] = useState<IHostsEntry[]>([]);
+  const [domain, setDomain] = useState("");
+  const [ip, setIp] = useState("");
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setEntries(verge?.hosts_entries ?? []);
+      setDomain("");
+      setIp("");
+    },
+    close: () => setOpen(false),
+  }));
+
+  const isSame = (a: IHostsEntry, b: IHostsEntry) =>
+    a.domain === b.domain && a.ip === b.ip;
+
+  const onAdd = () => {
+    const entry = { domain: domain.trim(), ip: ip.trim(), enable: true };
+    if (!entry.domain || !entry.ip) return;
+    if (!entries.some((e) => isSame(e, entry))) {
+      setEntries((list) => [...list, entry]);
+    }
+    setDomain("");
+    setIp("");
+  };
+
+  const onImport = useLockFn(async () => {
+    try {
+      const list = await readSystemHosts();
+      const added = list.filter((e) => !entries.some((o) => isSame(o, e)));
+      setEntries((old) => [...old, ...added]);
+      useNotification(
+        t("Success"),
+        t("Hosts Imported", { count: added.length }),
+      );
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  const onSave = useLockFn(async () => {
+    try {
+      await patchVerge({ hosts_entries: entries });
+      setOpen(false);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Hosts")}
+      contentSx={{ width: 450, maxHeight: 480 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <Box sx={{ display: "flex", gap: 1, alignItems: "center", mb: 1 }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder="router.lan"
+          sx={{ flex: 1 }}
+          value={domain}
+          onChange={(e) => setDomain(e.target.value)}
+        />
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder="192.168.1.1"
+          sx={{ width: 140 }}
+          value={ip}
+          onChange={(e) => setIp(e.target.value)}
+          onKeyDown={(e) => e.key === "Enter" && onAdd()}
+        />
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      <Button size="small" variant="outlined" onClick={onImport}>
+        {t("Import System Hosts")}
+      </Button>
+
+      {entries.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>{t("No Hosts")}</Typography>
+      ) : (
+        <List>
+          {entries.map((entry, index) => (
+            <ListItem
+              key={`${entry.domain}-${entry.ip}`}
+              sx={{ padding: "2px" }}
+            >
+              <Switch
+                size="small"
+                checked={entry.enable}
+                onChange={(_, enable) =>
+                  setEntries((list) =>
+                    list.map((e, i) => (i === index ? { ...e, enable } : e)),
+                  )
+                }
+              />
+              <ListItemText
+                primary={entry.domain}
+                secondary={entry.ip}
+                sx={{ ml: 1, opacity: entry.enable ? 1 : 0.5 }}
+              />
+              <IconButton
+                size="small"
+                color="inherit"
+                onClick={() =>
+                  setEntries((list) => list.filter((_, i) => i !== index))
+                }
+              >
+                <DeleteRounded fontSize="inherit" />
+              </IconButton>
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+HostsViewer.displayName = "HostsViewer";
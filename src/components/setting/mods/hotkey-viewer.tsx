@@ -1,6 +1,7 @@
 import { BaseDialog, DialogRef } from "@/components/base";
 import { useNotification } from "@/hooks/use-notification";
 import { useVerge } from "@/hooks/use-verge";
+import { getDesktopSession } from "@/services/cmds";
 import { Typography, styled } from "@mui/material";
 import { useLatest, useLockFn } from "ahooks";
 import {
@@ -20,8 +21,9 @@ const ItemWrapper = styled("div")`
   margin-bottom: 8px;
 `;
 
-const HOTKEY_FUNC = [
+export const HOTKEY_FUNC = [
   "open_or_close_dashboard",
+  "open_dashboard",
   "clash_mode_rule",
   "clash_mode_global",
   "clash_mode_direct",
@@ -32,6 +34,10 @@ const HOTKEY_FUNC = [
   "toggle_tun_mode",
   "enable_tun_mode",
   "disable_tun_mode",
+  "toggle_pause",
+  "switch_next_profile",
+  "copy_env",
+  "restart_clash",
 ];
 
 export const HotkeyViewer = forwardRef<DialogRef>((props, ref) => {
@@ -40,6 +46,7 @@ export const HotkeyViewer = forwardRef<DialogRef>((props, ref) => {
 
   const { verge, patchVerge } = useVerge();
 
+  const [session, setSession] = useState<IDesktopSession | null>(null);
   const [hotkeyMap, setHotkeyMap] = useState<Record<string, string[]>>({});
   const hotkeyMapRef = useLatest(hotkeyMap);
 
@@ -62,6 +69,7 @@ export const HotkeyViewer = forwardRef<DialogRef>((props, ref) => {
 
       setHotkeyMap(map);
       setDuplicateItems([]);
+      getDesktopSession().then(setSession);
     },
     close: () => setOpen(false),
   }));
@@ -127,6 +135,18 @@ export const HotkeyViewer = forwardRef<DialogRef>((props, ref) => {
       onCancel={() => setOpen(false)}
       onOk={onSave}
     >
+      {session?.global_shortcuts === "portal" && (
+        <Typography
+          variant="body2"
+          color={session.shortcuts_error ? "error" : "text.secondary"}
+          sx={{ mb: 1 }}
+        >
+          {session.shortcuts_error
+            ? `${t("Portal Shortcuts Failed")}: ${session.shortcuts_error}`
+            : t("Portal Shortcuts Info")}
+        </Typography>
+      )}
+
       {HOTKEY_FUNC.map((func) => (
         <ItemWrapper key={func}>
           <Typography>{t(func)}</Typography>
@@ -0,0 +1,166 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+type Purpose = keyof IHttpPolicies;
+
+const PURPOSES: { key: Purpose; label: string }[] = [
+  { key: "updater", label: "Updates" },
+  { key: "profiles", label: "Subscriptions" },
+  { key: "geodata", label: "Rule Providers" },
+  { key: "sync", label: "Backup & Sync" },
+  { key: "webhooks", label: "Webhooks" },
+];
+
+type ProxyKind = "default" | "direct" | "system" | "core" | "custom";
+
+const proxyKind = (proxy?: IHttpProxy): ProxyKind => {
+  if (!proxy) return "default";
+  return typeof proxy === "string" ? proxy : "custom";
+};
+
+// 空的输入表示用默认值
+const toNumber = (value: string) =>
+  value.trim() === "" ? undefined : Math.max(0, parseInt(value) || 0);
+
+export const HttpPolicyViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [policies, setPolicies] = useState<IHttpPolicies>({});
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setPolicies(verge?.http_policies ?? {});
+    },
+    close: () => setOpen(false),
+  }));
+
+  const update = (key: Purpose, patch: Partial<IHttpPolicy>) =>
+    setPolicies((policies) => ({
+      ...policies,
+      [key]: { ...policies[key], ...patch },
+    }));
+
+  const onSave = useLockFn(async () => {
+    try {
+      await patchVerge({ http_policies: policies });
+      setOpen(false);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Network Requests")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <Typography variant="body2" color="GrayText" sx={{ px: "2px" }}>
+        {t("Network Requests Description")}
+      </Typography>
+
+      <List>
+        {PURPOSES.map(({ key, label }) => {
+          const policy = policies[key] ?? {};
+          const kind = proxyKind(policy.proxy);
+          return (
+            <div key={key}>
+              <ListItem sx={{ padding: "5px 2px" }}>
+                <ListItemText primary={t(label)} />
+                <Select
+                  size="small"
+                  sx={{ width: 120, "> div": { py: "7.5px" } }}
+                  value={kind}
+                  onChange={(e) => {
+                    const value = e.target.value as ProxyKind;
+                    update(key, {
+                      proxy:
+                        value === "default"
+                          ? undefined
+                          : value === "custom"
+                            ? { custom: "" }
+                            : value,
+                    });
+                  }}
+                >
+                  <MenuItem value="default">{t("Default")}</MenuItem>
+                  <MenuItem value="direct">{t("Direct")}</MenuItem>
+                  <MenuItem value="system">{t("System Proxy")}</MenuItem>
+                  <MenuItem value="core">{t("Core Proxy")}</MenuItem>
+                  <MenuItem value="custom">{t("Custom")}</MenuItem>
+                </Select>
+              </ListItem>
+
+              {kind === "custom" && (
+                <ListItem sx={{ padding: "5px 2px" }}>
+                  <TextField
+                    size="small"
+                    fullWidth
+                    autoComplete="off"
+                    spellCheck="false"
+                    placeholder="socks5://127.0.0.1:1080"
+                    value={
+                      typeof policy.proxy === "object"
+                        ? policy.proxy.custom
+                        : ""
+                    }
+                    onChange={(e) =>
+                      update(key, { proxy: { custom: e.target.value } })
+                    }
+                  />
+                </ListItem>
+              )}
+
+              <ListItem sx={{ padding: "5px 2px", gap: 1 }}>
+                <TextField
+                  size="small"
+                  type="number"
+                  label={t("Timeout (s)")}
+                  placeholder={t("Default")}
+                  value={policy.timeout ?? ""}
+                  onChange={(e) =>
+                    update(key, { timeout: toNumber(e.target.value) })
+                  }
+                />
+                <TextField
+                  size="small"
+                  type="number"
+                  label={t("Retries")}
+                  placeholder={t("Default")}
+                  inputProps={{ max: 5 }}
+                  value={policy.retries ?? ""}
+                  onChange={(e) =>
+                    update(key, { retries: toNumber(e.target.value) })
+                  }
+                />
+              </ListItem>
+            </div>
+          );
+        })}
+      </List>
+    </BaseDialog>
+  );
+});
+
+HttpPolicyViewer.displayName = "HttpPolicyViewer";
@@ -2,7 +2,8 @@ import { BaseDialog, DialogRef } from "@/components/base";
 import { pageTransitionVariants } from "@/components/layout/page-transition";
 import { useNotification } from "@/hooks/use-notification";
 import { useVerge } from "@/hooks/use-verge";
-import { List, MenuItem, Select, Switch } from "@mui/material";
+import { resetWindowState } from "@/services/cmds";
+import { Button, List, MenuItem, Select, Switch } from "@mui/material";
 import { forwardRef, useImperativeHandle, useState } from "react";
 import { useTranslation } from "react-i18next";
 import { GuardState } from "./guard-state";
@@ -106,6 +107,16 @@ export const LayoutViewer = forwardRef<DialogRef>((props, ref) => {
             </MenuItem>
           </Select>
         </SettingItem>
+
+        <SettingItem label={t("Window State")}>
+          <Button
+            variant="outlined"
+            size="small"
+            onClick={() => resetWindowState().catch(onError)}
+          >
+            {t("Reset")}
+          </Button>
+        </SettingItem>
       </List>
     </BaseDialog>
   );
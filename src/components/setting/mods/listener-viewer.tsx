@@ -0,0 +1,287 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { getProxiesInner } from "@/services/api";
+import {
+  addListener,
+  deleteListener,
+  getListeners,
+  toggleListener,
+} from "@/services/cmds";
+import getSystem from "@/utils/get-system";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Box,
+  Checkbox,
+  FormControlLabel,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const OS = getSystem();
+
+const LISTENER_TYPES: IInboundListener["type"][] = [
+  "mixed",
+  "socks",
+  "http",
+  ...(OS === "linux" || OS === "macos" ? (["redir"] as const) : []),
+  ...(OS === "linux" ? (["tproxy"] as const) : []),
+];
+
+// 和后端保持一致
+const supportAuth = (type: IInboundListener["type"]) =>
+  !["redir", "tproxy"].includes(type);
+const supportUdp = (type: IInboundListener["type"]) =>
+  !["http", "redir"].includes(type);
+
+const emptyListener = (): IInboundListener => ({
+  id: "",
+  name: "",
+  type: "mixed",
+  port: 0,
+  listen: "127.0.0.1",
+  udp: true,
+  users: [],
+  enable: true,
+});
+
+export const ListenerViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [draft, setDraft] = useState(emptyListener());
+  const [username, setUsername] = useState("");
+  const [password, setPassword] = useState("");
+
+  const { data: listeners = [], mutate } = useSWR(
+    open ? "getListeners" : null,
+    getListeners,
+  );
+  const { data: proxies = [] } = useSWR(
+    open ? "getRuleTargets" : null,
+    async () => {
+      const names = Object.keys(await getProxiesInner());
+      return names.filter((name) => name !== "GLOBAL");
+    },
+  );
+
+  const reset = () => {
+    setDraft(emptyListener());
+    setUsername("");
+    setPassword("");
+  };
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      reset();
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onError = (err: any) => {
+    useNotification(t("Error"), err.message || err.toString());
+  };
+
+  const onAdd = useLockFn(async () => {
+    const auth = supportAuth(draft.type) && username.trim();
+    try {
+      await addListener({
+        ...draft,
+        listen: draft.listen?.trim() || undefined,
+        udp: supportUdp(draft.type) && draft.udp,
+        users: auth ? [{ username: username.trim(), password }] : [],
+      });
+      reset();
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onToggle = useLockFn(async (listener: IInboundListener) => {
+    try {
+      await toggleListener(listener.id, !listener.enable);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onDelete = useLockFn(async (id: string) => {
+    try {
+      await deleteListener(id);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Inbound Listeners")}
+      contentSx={{ width: 520, maxHeight: 560 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Box sx={{ display: "flex", gap: 1 }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Listener Name")}
+          sx={{ flex: 1 }}
+          value={draft.name}
+          onChange={(e) => setDraft((d) => ({ ...d, name: e.target.value }))}
+        />
+        <Select
+          size="small"
+          sx={{ width: 100, "> div": { py: "7.5px" } }}
+          value={draft.type}
+          onChange={(e) =>
+            setDraft((d) => ({
+              ...d,
+              type: e.target.value as IInboundListener["type"],
+            }))
+          }
+        >
+          {LISTENER_TYPES.map((type) => (
+            <MenuItem key={type} value={type}>
+              {type}
+            </MenuItem>
+          ))}
+        </Select>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Bind Address")}
+          sx={{ width: 130 }}
+          value={draft.listen ?? ""}
+          onChange={(e) => setDraft((d) => ({ ...d, listen: e.target.value }))}
+        />
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Port")}
+          sx={{ width: 80 }}
+          value={draft.port || ""}
+          onChange={(e) =>
+            setDraft((d) => ({
+              ...d,
+              port: Math.min(+e.target.value.replace(/\D/g, ""), 65535),
+            }))
+          }
+        />
+      </Box>
+
+      <Box sx={{ display: "flex", gap: 1, mt: 1, alignItems: "center" }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Username")}
+          sx={{ flex: 1 }}
+          disabled={!supportAuth(draft.type)}
+          value={username}
+          onChange={(e) => setUsername(e.target.value)}
+        />
+        <TextField
+          size="small"
+          type="password"
+          autoComplete="off"
+          placeholder={t("Password")}
+          sx={{ flex: 1 }}
+          disabled={!supportAuth(draft.type)}
+          value={password}
+          onChange={(e) => setPassword(e.target.value)}
+        />
+        <Select
+          size="small"
+          sx={{ width: 120, "> div": { py: "7.5px" } }}
+          value={draft.proxy ?? ""}
+          displayEmpty
+          renderValue={(value) => value || t("Follow Rules")}
+          onChange={(e) =>
+            setDraft((d) => ({ ...d, proxy: e.target.value || undefined }))
+          }
+        >
+          <MenuItem value="">{t("Follow Rules")}</MenuItem>
+          {proxies.map((name) => (
+            <MenuItem key={name} value={name}>
+              {name}
+            </MenuItem>
+          ))}
+        </Select>
+        <FormControlLabel
+          label="UDP"
+          sx={{ mr: 0 }}
+          disabled={!supportUdp(draft.type)}
+          control={
+            <Checkbox
+              size="small"
+              checked={supportUdp(draft.type) && draft.udp}
+              onChange={(_, udp) => setDraft((d) => ({ ...d, udp }))}
+            />
+          }
+        />
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      {listeners.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>
+          {t("No Listeners")}
+        </Typography>
+      ) : (
+        <List>
+          {listeners.map((listener) => (
+            <ListItem key={listener.id} sx={{ padding: "2px" }}>
+              <Switch
+                size="small"
+                checked={listener.enable}
+                onChange={() => onToggle(listener)}
+              />
+              <ListItemText
+                primary={`${listener.name} · ${listener.type}`}
+                secondary={[
+                  `${listener.listen ?? "0.0.0.0"}:${listener.port}`,
+                  listener.udp ? "UDP" : null,
+                  listener.users.length ? t("Username") : null,
+                  listener.proxy ?? t("Follow Rules"),
+                ]
+                  .filter(Boolean)
+                  .join(" · ")}
+                sx={{
+                  ml: 1,
+                  opacity: listener.enable ? 1 : 0.5,
+                  wordBreak: "break-all",
+                }}
+              />
+              <IconButton
+                size="small"
+                color="inherit"
+                onClick={() => onDelete(listener.id)}
+              >
+                <DeleteRounded fontSize="inherit" />
+              </IconButton>
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+ListenerViewer.displayName = "ListenerViewer";
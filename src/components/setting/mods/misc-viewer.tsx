@@ -1,6 +1,7 @@
 import { BaseDialog, DialogRef } from "@/components/base";
 import { useNotification } from "@/hooks/use-notification";
 import { useVerge } from "@/hooks/use-verge";
+import { setLogLevel } from "@/services/cmds";
 import {
   List,
   ListItem,
@@ -21,11 +22,14 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
   const [open, setOpen] = useState(false);
   const [values, setValues] = useState({
     appLogLevel: "info",
+    appLogFilter: "",
+    appLogFormat: "pretty" as IVergeConfig["app_log_format"],
     autoCloseConnection: false,
     enableClashFields: true,
     enableBuiltinEnhanced: true,
     proxyLayoutColumn: 6,
     defaultLatencyTest: "",
+    appDnsOverHttps: "",
   });
 
   useImperativeHandle(ref, () => ({
@@ -33,11 +37,14 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
       setOpen(true);
       setValues({
         appLogLevel: verge?.app_log_level ?? "info",
+        appLogFilter: verge?.app_log_filter ?? "",
+        appLogFormat: verge?.app_log_format ?? "pretty",
         autoCloseConnection: verge?.auto_close_connection ?? false,
         enableClashFields: verge?.enable_clash_fields ?? true,
         enableBuiltinEnhanced: verge?.enable_builtin_enhanced ?? true,
         proxyLayoutColumn: verge?.proxy_layout_column || 6,
         defaultLatencyTest: verge?.default_latency_test || "",
+        appDnsOverHttps: verge?.app_dns_over_https ?? "",
       });
     },
     close: () => setOpen(false),
@@ -45,13 +52,15 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
 
   const onSave = useLockFn(async () => {
     try {
+      await setLogLevel(values.appLogLevel, values.appLogFilter);
       await patchVerge({
-        app_log_level: values.appLogLevel,
+        app_log_format: values.appLogFormat,
         auto_close_connection: values.autoCloseConnection,
         enable_clash_fields: values.enableClashFields,
         enable_builtin_enhanced: values.enableBuiltinEnhanced,
         proxy_layout_column: values.proxyLayoutColumn,
         default_latency_test: values.defaultLatencyTest,
+        app_dns_over_https: values.appDnsOverHttps.trim(),
       });
       setOpen(false);
     } catch (err: any) {
@@ -92,6 +101,41 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
           </Select>
         </ListItem>
 
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("App Log Filter")} />
+          <TextField
+            size="small"
+            autoComplete="off"
+            sx={{ width: 200 }}
+            value={values.appLogFilter}
+            placeholder="reqwest=debug"
+            onChange={(e) =>
+              setValues((v) => ({ ...v, appLogFilter: e.target.value }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText
+            primary={t("App Log Format")}
+            secondary={t("Takes effect after restart")}
+          />
+          <Select
+            size="small"
+            sx={{ width: 100, "> div": { py: "7.5px" } }}
+            value={values.appLogFormat}
+            onChange={(e) => {
+              setValues((v) => ({
+                ...v,
+                appLogFormat: e.target.value as IVergeConfig["app_log_format"],
+              }));
+            }}
+          >
+            <MenuItem value="pretty">Pretty</MenuItem>
+            <MenuItem value="json">JSON</MenuItem>
+          </Select>
+        </ListItem>
+
         <ListItem sx={{ padding: "5px 2px" }}>
           <ListItemText primary={t("Auto Close Connections")} />
           <Switch
@@ -165,6 +209,26 @@ export const MiscViewer = forwardRef<DialogRef>((props, ref) => {
             }
           />
         </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText
+            primary={t("App DNS over HTTPS")}
+            secondary={t("Used by subscriptions and updates")}
+          />
+          <TextField
+            size="small"
+            autoComplete="off"
+            autoCorrect="off"
+            autoCapitalize="off"
+            spellCheck="false"
+            sx={{ width: 250 }}
+            value={values.appDnsOverHttps}
+            placeholder="https://1.1.1.1/dns-query"
+            onChange={(e) =>
+              setValues((v) => ({ ...v, appDnsOverHttps: e.target.value }))
+            }
+          />
+        </ListItem>
       </List>
     </BaseDialog>
   );
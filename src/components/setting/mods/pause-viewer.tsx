@@ -0,0 +1,244 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  getProxyPause,
+  savePauseWindows,
+  togglePause,
+} from "@/services/cmds";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Box,
+  Button,
+  Checkbox,
+  FormControlLabel,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  Switch,
+  TextField,
+  ToggleButton,
+  ToggleButtonGroup,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+// 1 is monday, same as the backend
+const WEEKDAYS = [1, 2, 3, 4, 5, 6, 7];
+
+const emptyWindow = (): IPauseWindow => ({
+  id: "",
+  name: "",
+  enable: true,
+  start: "09:00",
+  end: "18:00",
+  weekdays: [1, 2, 3, 4, 5],
+  system_proxy: true,
+  tun: false,
+});
+
+export const PauseViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [draft, setDraft] = useState(emptyWindow());
+  const { verge, mutateVerge } = useVerge();
+
+  const { data: paused, mutate: mutatePaused } = useSWR(
+    open ? "getProxyPause" : null,
+    getProxyPause,
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setDraft(emptyWindow());
+    },
+    close: () => setOpen(false),
+  }));
+
+  const windows = verge?.pause_windows ?? [];
+
+  const onToggle = useLockFn(async () => {
+    try {
+      await togglePause();
+      mutateVerge();
+      mutatePaused();
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  const onSave = async (list: IPauseWindow[]) => {
+    try {
+      await savePauseWindows(list);
+      mutateVerge();
+      mutatePaused();
+      return true;
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+      return false;
+    }
+  };
+
+  const onAdd = useLockFn(async () => {
+    const name = draft.name.trim();
+    if (!name) return;
+    const item = { ...draft, name, id: `p${Date.now()}` };
+    if (await onSave([...windows, item])) setDraft(emptyWindow());
+  });
+
+  const onToggle = useLockFn(async (id: string) => {
+    await onSave(
+      windows.map((w) => (w.id === id ? { ...w, enable: !w.enable } : w)),
+    );
+  });
+
+  const onDelete = useLockFn(async (id: string) => {
+    await onSave(windows.filter((w) => w.id !== id));
+  });
+
+  const describe = (item: IPauseWindow) => {
+    const days = item.weekdays?.length
+      ? item.weekdays.map((day) => t(`Weekday ${day}`)).join(" ")
+      : t("Every Day");
+    const targets = [
+      item.system_proxy ? t("System Proxy") : null,
+      item.tun ? t("Tun Mode") : null,
+    ];
+    return [`${item.start} - ${item.end}`, days, ...targets]
+      .filter(Boolean)
+      .join(" · ");
+  };
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Pause Windows")}
+      contentSx={{ width: 500, maxHeight: 560 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Box sx={{ display: "flex", alignItems: "center", gap: 1 }}>
+        <Typography variant="body2" color="warning.main" sx={{ flex: 1 }}>
+          {paused &&
+            (paused.manual
+              ? t("Proxy Paused Manually")
+              : t("Proxy Paused By", { name: paused.window }))}
+        </Typography>
+        <Button size="small" variant="outlined" onClick={onToggle}>
+          {paused ? t("Resume") : t("Pause Now")}
+        </Button>
+      </Box>
+
+      <Box sx={{ display: "flex", gap: 1, mt: 1 }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Name")}
+          sx={{ flex: 1 }}
+          value={draft.name}
+          onChange={(e) => setDraft((d) => ({ ...d, name: e.target.value }))}
+        />
+        <TextField
+          size="small"
+          type="time"
+          sx={{ width: 110 }}
+          value={draft.start}
+          onChange={(e) => setDraft((d) => ({ ...d, start: e.target.value }))}
+        />
+        <TextField
+          size="small"
+          type="time"
+          sx={{ width: 110 }}
+          value={draft.end}
+          onChange={(e) => setDraft((d) => ({ ...d, end: e.target.value }))}
+        />
+      </Box>
+
+      <Box sx={{ display: "flex", gap: 1, mt: 1, alignItems: "center" }}>
+        <ToggleButtonGroup
+          size="small"
+          value={draft.weekdays ?? []}
+          onChange={(_, weekdays: number[]) =>
+            setDraft((d) => ({
+              ...d,
+              weekdays: weekdays.sort((a, b) => a - b),
+            }))
+          }
+        >
+          {WEEKDAYS.map((day) => (
+            <ToggleButton key={day} value={day} sx={{ px: 1, py: 0.25 }}>
+              {t(`Weekday ${day}`)}
+            </ToggleButton>
+          ))}
+        </ToggleButtonGroup>
+        <FormControlLabel
+          label={t("System Proxy")}
+          sx={{ mr: 0 }}
+          control={
+            <Checkbox
+              size="small"
+              checked={draft.system_proxy}
+              onChange={(_, system_proxy) =>
+                setDraft((d) => ({ ...d, system_proxy }))
+              }
+            />
+          }
+        />
+        <FormControlLabel
+          label="TUN"
+          sx={{ mr: 0 }}
+          control={
+            <Checkbox
+              size="small"
+              checked={draft.tun}
+              onChange={(_, tun) => setDraft((d) => ({ ...d, tun }))}
+            />
+          }
+        />
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      {windows.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>
+          {t("No Pause Windows")}
+        </Typography>
+      ) : (
+        <List>
+          {windows.map((item) => (
+            <ListItem key={item.id} sx={{ padding: "2px" }}>
+              <Switch
+                size="small"
+                checked={item.enable}
+                onChange={() => onToggle(item.id)}
+              />
+              <ListItemText
+                primary={item.name}
+                secondary={describe(item)}
+                sx={{ ml: 1, opacity: item.enable ? 1 : 0.5 }}
+              />
+              <IconButton
+                size="small"
+                color="inherit"
+                onClick={() => onDelete(item.id)}
+              >
+                <DeleteRounded fontSize="inherit" />
+              </IconButton>
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+PauseViewer.displayName = "PauseViewer";
@@ -0,0 +1,158 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  getRemoteManagementInfo,
+  setRemoteManagementPassword,
+} from "@/services/cmds";
+import {
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+export const RemoteManagementViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [values, setValues] = useState({
+    enable: false,
+    port: 33334,
+    access: "read_only" as IRemoteAccess,
+    password: "",
+  });
+
+  const { data: info, mutate } = useSWR(
+    open ? "getRemoteManagementInfo" : null,
+    getRemoteManagementInfo,
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setValues({
+        enable: verge?.enable_remote_management ?? false,
+        port: verge?.remote_management_port ?? 33334,
+        access: verge?.remote_management_access ?? "read_only",
+        password: "",
+      });
+    },
+    close: () => setOpen(false),
+  }));
+
+  // 先保存密码，可写模式启动时需要密码
+  const onSave = useLockFn(async () => {
+    try {
+      if (values.password) {
+        await setRemoteManagementPassword(values.password);
+      }
+      await patchVerge({
+        enable_remote_management: values.enable,
+        remote_management_port: values.port,
+        remote_management_access: values.access,
+      });
+      mutate();
+      setOpen(false);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Remote Management")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <List>
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Enable")} />
+          <Switch
+            edge="end"
+            checked={values.enable}
+            onChange={(_, enable) => setValues((v) => ({ ...v, enable }))}
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Port")} />
+          <TextField
+            size="small"
+            type="number"
+            autoComplete="off"
+            sx={{ width: 100 }}
+            value={values.port}
+            onChange={(e) =>
+              setValues((v) => ({
+                ...v,
+                port: parseInt(e.target.value) || 0,
+              }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Access")} />
+          <Select
+            size="small"
+            sx={{ width: 140, "> div": { py: "7.5px" } }}
+            value={values.access}
+            onChange={(e) =>
+              setValues((v) => ({
+                ...v,
+                access: e.target.value as IRemoteAccess,
+              }))
+            }
+          >
+            <MenuItem value="read_only">{t("Read Only")}</MenuItem>
+            <MenuItem value="read_write">{t("Read Write")}</MenuItem>
+          </Select>
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText
+            primary={t("Password")}
+            secondary={t(info?.has_password ? "Password Set" : "No Password")}
+          />
+          <TextField
+            size="small"
+            type="password"
+            autoComplete="off"
+            sx={{ width: 250 }}
+            value={values.password}
+            placeholder={t("Unchanged")}
+            onChange={(e) =>
+              setValues((v) => ({ ...v, password: e.target.value }))
+            }
+          />
+        </ListItem>
+
+        {info?.enable &&
+          info.urls.map((url) => (
+            <ListItem key={url} sx={{ padding: "2px 2px" }}>
+              <Typography variant="body2" sx={{ userSelect: "text" }}>
+                {url}
+              </Typography>
+            </ListItem>
+          ))}
+      </List>
+    </BaseDialog>
+  );
+});
+
+RemoteManagementViewer.displayName = "RemoteManagementViewer";
@@ -0,0 +1,317 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { getProxiesInner } from "@/services/api";
+import {
+  addUserRule,
+  deleteUserRule,
+  getUserRules,
+  reorderUserRules,
+  updateUserRule,
+} from "@/services/cmds";
+import {
+  Add,
+  ArrowDownward,
+  ArrowUpward,
+  DeleteRounded,
+} from "@mui/icons-material";
+import {
+  Box,
+  Checkbox,
+  FormControlLabel,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const RULE_TYPES = [
+  "DOMAIN",
+  "DOMAIN-SUFFIX",
+  "DOMAIN-KEYWORD",
+  "DOMAIN-REGEX",
+  "GEOSITE",
+  "GEOIP",
+  "IP-CIDR",
+  "IP-CIDR6",
+  "IP-ASN",
+  "SRC-IP-CIDR",
+  "DST-PORT",
+  "SRC-PORT",
+  "PROCESS-NAME",
+  "PROCESS-PATH",
+  "NETWORK",
+  "RULE-SET",
+  "MATCH",
+];
+
+const RESOLVE_TYPES = ["GEOIP", "IP-CIDR", "IP-CIDR6", "IP-ASN", "RULE-SET"];
+
+const emptyRule = (): IUserRule => ({
+  id: "",
+  type: "DOMAIN-SUFFIX",
+  payload: "",
+  target: "DIRECT",
+  no_resolve: false,
+  position: { type: "prepend" },
+  enable: true,
+});
+
+const formatRule = (rule: IUserRule) =>
+  [
+    rule.type,
+    rule.payload || null,
+    rule.target,
+    rule.no_resolve ? "no-resolve" : null,
+  ]
+    .filter(Boolean)
+    .join(",");
+
+export const RulesViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [draft, setDraft] = useState(emptyRule());
+
+  const { data: rules = [], mutate } = useSWR(
+    open ? "getUserRules" : null,
+    getUserRules,
+  );
+  const { data: targets = [] } = useSWR(
+    open ? "getRuleTargets" : null,
+    async () => {
+      const names = Object.keys(await getProxiesInner());
+      return names.filter((name) => name !== "GLOBAL");
+    },
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setDraft(emptyRule());
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onError = (err: any) => {
+    useNotification(t("Error"), err.message || err.toString());
+  };
+
+  const onAdd = useLockFn(async () => {
+    try {
+      await addUserRule(draft);
+      setDraft((d) => ({ ...emptyRule(), type: d.type, target: d.target }));
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onToggle = useLockFn(async (rule: IUserRule) => {
+    try {
+      await updateUserRule(rule.id, { ...rule, enable: !rule.enable });
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onDelete = useLockFn(async (id: string) => {
+    try {
+      await deleteUserRule(id);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onMove = useLockFn(async (index: number, offset: number) => {
+    const ids = rules.map((rule) => rule.id);
+    const to = index + offset;
+    if (to < 0 || to >= ids.length) return;
+    [ids[index], ids[to]] = [ids[to], ids[index]];
+    try {
+      await reorderUserRules(ids);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const positionLabel = (position: IUserRulePosition) =>
+    position.type === "before"
+      ? t("Before Rule", { index: position.index })
+      : t(position.type === "prepend" ? "Prepend" : "Append");
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("User Rules")}
+      contentSx={{ width: 520, maxHeight: 560 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Box sx={{ display: "flex", gap: 1, flexWrap: "wrap" }}>
+        <Select
+          size="small"
+          sx={{ width: 160, "> div": { py: "7.5px" } }}
+          value={draft.type}
+          onChange={(e) =>
+            setDraft((d) => ({
+              ...d,
+              type: e.target.value,
+              payload: e.target.value === "MATCH" ? "" : d.payload,
+              no_resolve: RESOLVE_TYPES.includes(e.target.value)
+                ? d.no_resolve
+                : false,
+            }))
+          }
+        >
+          {RULE_TYPES.map((type) => (
+            <MenuItem key={type} value={type}>
+              {type}
+            </MenuItem>
+          ))}
+        </Select>
+
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Payload")}
+          sx={{ flex: 1 }}
+          disabled={draft.type === "MATCH"}
+          value={draft.payload}
+          onChange={(e) => setDraft((d) => ({ ...d, payload: e.target.value }))}
+        />
+
+        <Select
+          size="small"
+          sx={{ width: 160, "> div": { py: "7.5px" } }}
+          value={targets.includes(draft.target) ? draft.target : ""}
+          displayEmpty
+          onChange={(e) => setDraft((d) => ({ ...d, target: e.target.value }))}
+        >
+          {targets.map((name) => (
+            <MenuItem key={name} value={name}>
+              {name}
+            </MenuItem>
+          ))}
+        </Select>
+
+        <Select
+          size="small"
+          sx={{ width: 120, "> div": { py: "7.5px" } }}
+          value={draft.position.type}
+          onChange={(e) => {
+            const type = e.target.value as IUserRulePosition["type"];
+            setDraft((d) => ({
+              ...d,
+              position: type === "before" ? { type, index: 0 } : { type },
+            }));
+          }}
+        >
+          <MenuItem value="prepend">{t("Prepend")}</MenuItem>
+          <MenuItem value="append">{t("Append")}</MenuItem>
+          <MenuItem value="before">{t("Before Rule N")}</MenuItem>
+        </Select>
+
+        {draft.position.type === "before" && (
+          <TextField
+            size="small"
+            sx={{ width: 80 }}
+            value={draft.position.index}
+            onChange={(e) =>
+              setDraft((d) => ({
+                ...d,
+                position: {
+                  type: "before",
+                  index: +e.target.value.replace(/\D/g, ""),
+                },
+              }))
+            }
+          />
+        )}
+
+        <FormControlLabel
+          label="no-resolve"
+          disabled={!RESOLVE_TYPES.includes(draft.type)}
+          control={
+            <Checkbox
+              size="small"
+              checked={draft.no_resolve}
+              onChange={(_, no_resolve) =>
+                setDraft((d) => ({ ...d, no_resolve }))
+              }
+            />
+          }
+        />
+
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      {rules.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>
+          {t("No User Rules")}
+        </Typography>
+      ) : (
+        <List>
+          {rules.map((rule, index) => (
+            <ListItem key={rule.id} sx={{ padding: "2px" }}>
+              <Switch
+                size="small"
+                checked={rule.enable}
+                onChange={() => onToggle(rule)}
+              />
+              <ListItemText
+                primary={formatRule(rule)}
+                secondary={positionLabel(rule.position)}
+                sx={{
+                  ml: 1,
+                  opacity: rule.enable ? 1 : 0.5,
+                  wordBreak: "break-all",
+                }}
+              />
+              <IconButton
+                size="small"
+                color="inherit"
+                disabled={index === 0}
+                onClick={() => onMove(index, -1)}
+              >
+                <ArrowUpward fontSize="inherit" />
+              </IconButton>
+              <IconButton
+                size="small"
+                color="inherit"
+                disabled={index === rules.length - 1}
+                onClick={() => onMove(index, 1)}
+              >
+                <ArrowDownward fontSize="inherit" />
+              </IconButton>
+              <IconButton
+                size="small"
+                color="inherit"
+                onClick={() => onDelete(rule.id)}
+              >
+                <DeleteRounded fontSize="inherit" />
+              </IconButton>
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+RulesViewer.displayName = "RulesViewer";
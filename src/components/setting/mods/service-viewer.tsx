@@ -6,12 +6,16 @@ import {
   patchVergeConfig,
   uninstallService,
 } from "@/services/cmds";
+import getSystem from "@/utils/get-system";
 import { Button, Stack, Typography } from "@mui/material";
 import { useLockFn } from "ahooks";
 import { forwardRef, useImperativeHandle, useState } from "react";
 import { useTranslation } from "react-i18next";
 import useSWR from "swr";
 
+// the helper is a copy of the app, install it again after updating
+const canReinstall = getSystem() === "macos";
+
 interface Props {
   enable: boolean;
 }
@@ -113,6 +117,12 @@ export const ServiceViewer = forwardRef<DialogRef, Props>((props, ref) => {
           </Button>
         )}
 
+        {canReinstall && (state === "active" || state === "installed") && (
+          <Button variant="outlined" onClick={onInstall}>
+            {t("Reinstall")}
+          </Button>
+        )}
+
         {(state === "active" || state === "installed") && (
           <Button variant="outlined" onClick={onUninstall}>
             Uninstall
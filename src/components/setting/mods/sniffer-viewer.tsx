@@ -0,0 +1,229 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+// the empty string keeps the value of the profile
+const KEEP = "";
+
+const PROTOCOLS: ISniffProtocol[] = ["HTTP", "TLS", "QUIC"];
+
+type FlagKey =
+  | "enable"
+  | "force-dns-mapping"
+  | "parse-pure-ip"
+  | "override-destination";
+
+const FLAG_KEYS: FlagKey[] = [
+  "enable",
+  "force-dns-mapping",
+  "parse-pure-ip",
+  "override-destination",
+];
+
+interface FormValue {
+  flags: Record<FlagKey, string>;
+  ports: Record<ISniffProtocol, string>;
+  forceDomain: string;
+  skipDomain: string;
+}
+
+const emptyForm = (): FormValue => ({
+  flags: {
+    enable: KEEP,
+    "force-dns-mapping": KEEP,
+    "parse-pure-ip": KEEP,
+    "override-destination": KEEP,
+  },
+  ports: { HTTP: "", TLS: "", QUIC: "" },
+  forceDomain: "",
+  skipDomain: "",
+});
+
+export const SnifferViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [value, setValue] = useState(emptyForm());
+  const { verge, patchVerge } = useVerge();
+
+  const core = verge?.clash_core ?? "clash";
+  const supported = core === "mihomo" || core === "mihomo-alpha";
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setValue(toForm(verge?.sniffer_override));
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onSave = useLockFn(async () => {
+    try {
+      await patchVerge({ sniffer_override: fromForm(value) });
+      setOpen(false);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Sniffer")}
+      contentSx={{ width: 450, maxHeight: 520 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      disableOk={!supported}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      {!supported && (
+        <Typography variant="body2" color="warning.main">
+          {t("Sniffer Unsupported", { core })}
+        </Typography>
+      )}
+
+      <List>
+        {FLAG_KEYS.map((key) => (
+          <ListItem key={key} sx={{ padding: "5px 2px" }}>
+            <ListItemText primary={key} />
+            <Select
+              size="small"
+              sx={{ width: 160, "> div": { py: "7.5px" } }}
+              value={value.flags[key]}
+              displayEmpty
+              disabled={!supported}
+              onChange={(e) =>
+                setValue((v) => ({
+                  ...v,
+                  flags: { ...v.flags, [key]: e.target.value },
+                }))
+              }
+            >
+              <MenuItem value={KEEP}>{t("Keep Profile")}</MenuItem>
+              <MenuItem value="true">{t("On")}</MenuItem>
+              <MenuItem value="false">{t("Off")}</MenuItem>
+            </Select>
+          </ListItem>
+        ))}
+
+        {PROTOCOLS.map((protocol) => (
+          <ListItem key={protocol} sx={{ padding: "5px 2px" }}>
+            <ListItemText primary={`${protocol} ${t("Port")}`} />
+            <TextField
+              size="small"
+              autoComplete="off"
+              sx={{ width: 200 }}
+              disabled={!supported}
+              placeholder={t("Keep Profile")}
+              value={value.ports[protocol]}
+              onChange={(e) =>
+                setValue((v) => ({
+                  ...v,
+                  ports: { ...v.ports, [protocol]: e.target.value },
+                }))
+              }
+            />
+          </ListItem>
+        ))}
+
+        <ListItem sx={{ padding: "5px 2px", alignItems: "start" }}>
+          <ListItemText primary="force-domain" sx={{ padding: "3px 0" }} />
+          <TextField
+            size="small"
+            autoComplete="off"
+            multiline
+            rows={3}
+            sx={{ width: 280 }}
+            disabled={!supported}
+            placeholder="+.v2ex.com"
+            value={value.forceDomain}
+            onChange={(e) =>
+              setValue((v) => ({ ...v, forceDomain: e.target.value }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px", alignItems: "start" }}>
+          <ListItemText primary="skip-domain" sx={{ padding: "3px 0" }} />
+          <TextField
+            size="small"
+            autoComplete="off"
+            multiline
+            rows={3}
+            sx={{ width: 280 }}
+            disabled={!supported}
+            placeholder="+.apple.com"
+            value={value.skipDomain}
+            onChange={(e) =>
+              setValue((v) => ({ ...v, skipDomain: e.target.value }))
+            }
+          />
+        </ListItem>
+      </List>
+    </BaseDialog>
+  );
+});
+
+SnifferViewer.displayName = "SnifferViewer";
+
+const toForm = (sniffer?: ISnifferOverride): FormValue => {
+  const form = emptyForm();
+  if (!sniffer) return form;
+
+  FLAG_KEYS.forEach((key) => {
+    const flag = sniffer[key];
+    form.flags[key] = flag === undefined ? KEEP : String(flag);
+  });
+  PROTOCOLS.forEach((protocol) => {
+    form.ports[protocol] = (sniffer.sniff?.[protocol]?.ports ?? []).join(", ");
+  });
+  form.forceDomain = (sniffer["force-domain"] ?? []).join("\n");
+  form.skipDomain = (sniffer["skip-domain"] ?? []).join("\n");
+  return form;
+};
+
+// the empty fields are not overridden, `{}` clears the override
+const fromForm = (form: FormValue): ISnifferOverride => {
+  const sniffer: ISnifferOverride = {};
+
+  FLAG_KEYS.forEach((key) => {
+    if (form.flags[key] !== KEEP) sniffer[key] = form.flags[key] === "true";
+  });
+
+  const sniff: ISnifferOverride["sniff"] = {};
+  PROTOCOLS.forEach((protocol) => {
+    const ports = form.ports[protocol]
+      .split(/[,\s]+/)
+      .map((port) => port.trim())
+      .filter(Boolean);
+    if (ports.length) sniff[protocol] = { ports };
+  });
+  if (Object.keys(sniff).length) sniffer.sniff = sniff;
+
+  const forceDomain = splitLines(form.forceDomain);
+  if (forceDomain.length) sniffer["force-domain"] = forceDomain;
+  const skipDomain = splitLines(form.skipDomain);
+  if (skipDomain.length) sniffer["skip-domain"] = skipDomain;
+
+  return sniffer;
+};
+
+const splitLines = (text: string) =>
+  text
+    .split("\n")
+    .map((line) => line.trim())
+    .filter(Boolean);
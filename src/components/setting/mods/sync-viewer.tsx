@@ -0,0 +1,251 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  getSyncConflicts,
+  resolveSyncConflict,
+  setSyncS3Config,
+  syncNow,
+} from "@/services/cmds";
+import {
+  Button,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import dayjs from "dayjs";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { GuardState } from "./guard-state";
+import { SettingItem } from "./setting-comp";
+
+export const SyncViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge, mutateVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [s3, setS3] = useState<ISyncS3Config>({
+    endpoint: "",
+    bucket: "",
+    access_key_id: "",
+  });
+  const [secret, setSecret] = useState("");
+  const [conflicts, setConflicts] = useState<ISyncConflict[]>([]);
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setSecret("");
+      setS3(
+        verge?.sync_s3 ?? { endpoint: "", bucket: "", access_key_id: "" },
+      );
+      getSyncConflicts().then(setConflicts);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const provider = verge?.sync_provider ?? "webdav";
+
+  const onSwitchFormat = (_e: any, value: boolean) => value;
+  const onError = (err: any) =>
+    useNotification(t("Error"), err.message || err.toString());
+  const onChangeData = (patch: Partial<IVergeConfig>) => {
+    mutateVerge({ ...verge, ...patch }, false);
+  };
+
+  const onReport = (report: ISyncReport) => {
+    setConflicts(report.conflicts);
+    useNotification(
+      t("Success"),
+      `${t("Pushed")}: ${report.pushed.length}, ${t("Pulled")}: ${report.pulled.length}`,
+    );
+  };
+
+  const onSave = useLockFn(async () => {
+    try {
+      if (provider === "s3") {
+        await setSyncS3Config(s3, secret || undefined);
+      }
+      setOpen(false);
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onSync = useLockFn(async () => {
+    try {
+      onReport(await syncNow());
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onResolve = useLockFn(
+    async (path: string, pick: "local" | "remote") => {
+      try {
+        onReport(await resolveSyncConflict(path, pick));
+      } catch (err: any) {
+        onError(err);
+      }
+    },
+  );
+
+  const formatTime = (time?: number) =>
+    time ? dayjs(time * 1000).format("YYYY-MM-DD HH:mm:ss") : t("Deleted");
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Profile Sync")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <List>
+        <SettingItem label={t("Enable Sync")}>
+          <GuardState
+            value={verge?.enable_sync ?? false}
+            valueProps="checked"
+            onCatch={onError}
+            onFormat={onSwitchFormat}
+            onChange={(e) => onChangeData({ enable_sync: e })}
+            onGuard={(e) => patchVerge({ enable_sync: e })}
+          >
+            <Switch edge="end" />
+          </GuardState>
+        </SettingItem>
+
+        <SettingItem label={t("Sync Provider")}>
+          <GuardState
+            value={provider}
+            onCatch={onError}
+            onFormat={(e: any) => e.target.value}
+            onChange={(e) => onChangeData({ sync_provider: e })}
+            onGuard={(e) => patchVerge({ sync_provider: e })}
+          >
+            <Select size="small" sx={{ width: 135, "> div": { py: "7.5px" } }}>
+              <MenuItem value="webdav">WebDAV</MenuItem>
+              <MenuItem value="s3">S3</MenuItem>
+            </Select>
+          </GuardState>
+        </SettingItem>
+
+        {provider === "s3" && (
+          <>
+            {(["endpoint", "bucket", "region", "access_key_id"] as const).map(
+              (key) => (
+                <ListItem key={key} sx={{ padding: "5px 2px" }}>
+                  <ListItemText primary={t(`S3 ${key}`)} />
+                  <TextField
+                    size="small"
+                    autoComplete="off"
+                    sx={{ width: 250 }}
+                    value={s3[key] ?? ""}
+                    placeholder={key === "region" ? "us-east-1" : undefined}
+                    onChange={(e) =>
+                      setS3((v) => ({ ...v, [key]: e.target.value }))
+                    }
+                  />
+                </ListItem>
+              ),
+            )}
+
+            <ListItem sx={{ padding: "5px 2px" }}>
+              <ListItemText primary={t("S3 secret")} />
+              <TextField
+                size="small"
+                type="password"
+                autoComplete="off"
+                sx={{ width: 250 }}
+                value={secret}
+                placeholder={t("Unchanged")}
+                onChange={(e) => setSecret(e.target.value)}
+              />
+            </ListItem>
+          </>
+        )}
+
+        <SettingItem label={t("Sync Interval")}>
+          <GuardState
+            value={verge?.sync_interval ?? 5}
+            onCatch={onError}
+            onFormat={(e: any) => Math.max(0, parseInt(e.target.value) || 0)}
+            onChange={(e) => onChangeData({ sync_interval: e })}
+            onGuard={(e) => patchVerge({ sync_interval: e })}
+            waitTime={1000}
+          >
+            <TextField
+              size="small"
+              type="number"
+              sx={{ width: 100 }}
+              InputProps={{ endAdornment: "m" }}
+            />
+          </GuardState>
+        </SettingItem>
+
+        <SettingItem label={t("Conflict Strategy")}>
+          <GuardState
+            value={verge?.sync_conflict_strategy ?? "last_writer_wins"}
+            onCatch={onError}
+            onFormat={(e: any) => e.target.value}
+            onChange={(e) => onChangeData({ sync_conflict_strategy: e })}
+            onGuard={(e) => patchVerge({ sync_conflict_strategy: e })}
+          >
+            <Select size="small" sx={{ width: 135, "> div": { py: "7.5px" } }}>
+              <MenuItem value="last_writer_wins">
+                {t("Last Writer Wins")}
+              </MenuItem>
+              <MenuItem value="manual">{t("Manual")}</MenuItem>
+            </Select>
+          </GuardState>
+        </SettingItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Sync Conflicts")} />
+          <Button variant="contained" size="small" onClick={onSync}>
+            {t("Sync Now")}
+          </Button>
+        </ListItem>
+
+        {conflicts.map((conflict) => (
+          <ListItem
+            key={conflict.path}
+            sx={{ padding: "2px 2px", display: "block" }}
+          >
+            <Typography variant="body2">{conflict.path}</Typography>
+            <Typography variant="body2" color="GrayText">
+              {t("Local")}: {formatTime(conflict.local_modified)} ·{" "}
+              {conflict.remote_device}:{" "}
+              {conflict.remote_deleted
+                ? t("Deleted")
+                : formatTime(conflict.remote_modified)}
+            </Typography>
+            <Button
+              size="small"
+              onClick={() => onResolve(conflict.path, "local")}
+            >
+              {t("Keep Local")}
+            </Button>
+            <Button
+              size="small"
+              onClick={() => onResolve(conflict.path, "remote")}
+            >
+              {t("Keep Remote")}
+            </Button>
+          </ListItem>
+        ))}
+      </List>
+    </BaseDialog>
+  );
+});
+
+SyncViewer.displayName = "SyncViewer";
@@ -1,7 +1,11 @@
 import { BaseDialog, DialogRef } from "@/components/base";
 import { useNotification } from "@/hooks/use-notification";
 import { useVerge } from "@/hooks/use-verge";
-import { getSystemProxy } from "@/services/cmds";
+import {
+  getDefaultBypassList,
+  getSystemProxy,
+  parseBypassList,
+} from "@/services/cmds";
 import {
   Box,
   InputAdornment,
@@ -30,22 +34,23 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
   const {
     enable_system_proxy: enabled,
     enable_proxy_guard,
-    system_proxy_bypass,
+    system_proxy_bypass_list,
     proxy_guard_duration,
   } = verge ?? {};
 
   const [value, setValue] = useState({
     guard: enable_proxy_guard,
-    bypass: system_proxy_bypass,
+    bypass: "",
     duration: proxy_guard_duration ?? 10,
   });
 
   useImperativeHandle(ref, () => ({
-    open: () => {
+    open: async () => {
       setOpen(true);
+      const list = system_proxy_bypass_list ?? (await getDefaultBypassList());
       setValue({
         guard: enable_proxy_guard,
-        bypass: system_proxy_bypass,
+        bypass: formatBypassList(list),
         duration: proxy_guard_duration ?? 10,
       });
       getSystemProxy().then((p) => setSysproxy(p));
@@ -67,11 +72,14 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
     if (value.duration !== proxy_guard_duration) {
       patch.proxy_guard_duration = value.duration;
     }
-    if (value.bypass !== system_proxy_bypass) {
-      patch.system_proxy_bypass = value.bypass;
-    }
-
     try {
+      const list = await parseBypassList(value.bypass);
+      if (
+        formatBypassList(list) !== formatBypassList(system_proxy_bypass_list)
+      ) {
+        patch.system_proxy_bypass_list = list;
+      }
+
       await patchVerge(patch);
       setOpen(false);
     } catch (err: any) {
@@ -165,6 +173,12 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
 
 SysproxyViewer.displayName = "SysproxyViewer";
 
+// one rule per line
+const formatBypassList = (list?: IBypassRule[]) =>
+  (list ?? [])
+    .map((rule) => (rule.type === "local" ? "<local>" : rule.value))
+    .join("\n");
+
 const FlexBox = styled("div")`
   display: flex;
   margin-top: 4px;
@@ -0,0 +1,166 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import {
+  generateSystemdUnit,
+  getSystemdUnitStatus,
+  installSystemdUnit,
+  setSystemdUnitEnabled,
+  uninstallSystemdUnit,
+} from "@/services/cmds";
+import {
+  Box,
+  Button,
+  MenuItem,
+  Select,
+  Stack,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+export const SystemdViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const [open, setOpen] = useState(false);
+  const [options, setOptions] = useState<ISystemdUnitOptions>({
+    scope: "system",
+    target: "core",
+  });
+
+  useImperativeHandle(ref, () => ({
+    open: () => setOpen(true),
+    close: () => setOpen(false),
+  }));
+
+  const key = `${options.scope}-${options.target}`;
+  const { data: status, mutate } = useSWR(
+    open ? ["getSystemdUnitStatus", key] : null,
+    () => getSystemdUnitStatus(options),
+    { refreshInterval: 5000 },
+  );
+  const { data: unit, error: unitError } = useSWR(
+    open ? ["generateSystemdUnit", key] : null,
+    () => generateSystemdUnit(options),
+  );
+
+  // the app needs the graphical session
+  const onScope = (scope: ISystemdUnitOptions["scope"]) =>
+    setOptions((o) => ({
+      scope,
+      target: scope === "system" ? "core" : o.target,
+    }));
+
+  // pkexec asks for the password of the system units
+  const onAction = useLockFn(async (action: () => Promise<void>) => {
+    try {
+      await action();
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    } finally {
+      mutate();
+    }
+  });
+
+  const enabled = status?.file_state === "enabled";
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Systemd Service")}
+      contentSx={{ width: 520, userSelect: "text" }}
+      disableFooter
+      onClose={() => setOpen(false)}
+    >
+      <Box display="flex" gap={1}>
+        <Select
+          size="small"
+          sx={{ width: 200, "> div": { py: "7.5px" } }}
+          value={options.target}
+          onChange={(e) =>
+            setOptions((o) => ({
+              ...o,
+              target: e.target.value as ISystemdUnitOptions["target"],
+            }))
+          }
+        >
+          <MenuItem value="core">{t("Core Only")}</MenuItem>
+          <MenuItem
+            value="service_only"
+            disabled={options.scope === "system"}
+          >
+            {t("Service Only Mode")}
+          </MenuItem>
+        </Select>
+        <Select
+          size="small"
+          sx={{ width: 200, "> div": { py: "7.5px" } }}
+          value={options.scope}
+          onChange={(e) =>
+            onScope(e.target.value as ISystemdUnitOptions["scope"])
+          }
+        >
+          <MenuItem value="system">{t("System Unit")}</MenuItem>
+          <MenuItem value="user">{t("User Unit")}</MenuItem>
+        </Select>
+      </Box>
+
+      <Typography variant="body2" sx={{ mt: 1, opacity: 0.7 }}>
+        {t("Systemd Service Info")}
+      </Typography>
+
+      {status && (
+        <Typography sx={{ mt: 1 }}>
+          {status.name}: {status.installed ? status.file_state : t("None")}
+          {" / "}
+          {status.active_state} ({status.sub_state})
+          {status.main_pid && ` PID ${status.main_pid}`}
+        </Typography>
+      )}
+
+      <Box
+        component="pre"
+        sx={{
+          mt: 1,
+          p: 1,
+          fontSize: 12,
+          overflow: "auto",
+          borderRadius: 1,
+          bgcolor: "action.hover",
+        }}
+      >
+        {unitError ? String(unitError) : unit}
+      </Box>
+
+      <Stack direction="row" spacing={1} justifyContent="flex-end">
+        <Button
+          variant="contained"
+          onClick={() => onAction(() => installSystemdUnit(options))}
+        >
+          {status?.installed ? t("Reinstall") : t("Install")}
+        </Button>
+        {status?.installed && (
+          <>
+            <Button
+              variant="outlined"
+              onClick={() =>
+                onAction(() => setSystemdUnitEnabled(options, !enabled))
+              }
+            >
+              {enabled ? t("Disable") : t("Enable")}
+            </Button>
+            <Button
+              variant="outlined"
+              color="error"
+              onClick={() => onAction(() => uninstallSystemdUnit(options))}
+            >
+              {t("Uninstall")}
+            </Button>
+          </>
+        )}
+      </Stack>
+    </BaseDialog>
+  );
+});
+
+SystemdViewer.displayName = "SystemdViewer";
@@ -1,10 +1,23 @@
 import { BaseDialog, DialogRef } from "@/components/base";
 import { useNotification } from "@/hooks/use-notification";
 import { useVerge } from "@/hooks/use-verge";
-import { List, ListItem, ListItemText, MenuItem, Select } from "@mui/material";
+import { getMaintenanceReport, runMaintenance } from "@/services/cmds";
+import parseTraffic from "@/utils/parse-traffic";
+import {
+  Button,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+} from "@mui/material";
 import { useLockFn } from "ahooks";
+import dayjs from "dayjs";
 import { forwardRef, useImperativeHandle, useState } from "react";
 import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const formatSize = (bytes: number) => parseTraffic(bytes).join(" ");
 export const TasksViewer = forwardRef<DialogRef>(
   function TasksViewer(props, ref) {
     const { t } = useTranslation();
@@ -15,6 +28,12 @@ export const TasksViewer = forwardRef<DialogRef>(
     const [values, setValues] = useState({
       auto_log_clean: 0,
     });
+    const [running, setRunning] = useState(false);
+
+    const { data: report, mutate } = useSWR(
+      open ? "getMaintenanceReport" : null,
+      getMaintenanceReport,
+    );
 
     useImperativeHandle(ref, () => ({
       open: () => {
@@ -40,6 +59,30 @@ export const TasksViewer = forwardRef<DialogRef>(
       }
     });
 
+    const onRun = useLockFn(async () => {
+      setRunning(true);
+      try {
+        const report = await runMaintenance();
+        mutate(report, false);
+        useNotification(
+          t("Maintenance"),
+          t("Space Reclaimed", {
+            size: formatSize(
+              report.app_logs +
+                report.core_logs +
+                report.snapshots +
+                report.temp_dirs,
+            ),
+          }),
+        );
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (err: any) {
+        useNotification(t("Error"), err.message || err.toString());
+      } finally {
+        setRunning(false);
+      }
+    });
+
     return (
       <BaseDialog
         title={t("Tasks")}
@@ -79,6 +122,32 @@ export const TasksViewer = forwardRef<DialogRef>(
               ))}
             </Select>
           </ListItem>
+
+          <ListItem sx={{ padding: "5px 2px" }}>
+            <ListItemText
+              primary={t("Maintenance")}
+              secondary={
+                report
+                  ? [
+                      dayjs(report.time * 1000).format("MM-DD HH:mm"),
+                      `${t("Logs")} ${formatSize(
+                        report.app_logs + report.core_logs,
+                      )}`,
+                      `${t("Snapshots")} ${formatSize(report.snapshots)}`,
+                      `${t("Temp")} ${formatSize(report.temp_dirs)}`,
+                    ].join(" · ")
+                  : t("Maintenance Info")
+              }
+            />
+            <Button
+              size="small"
+              variant="outlined"
+              disabled={running}
+              onClick={onRun}
+            >
+              {t("Run Now")}
+            </Button>
+          </ListItem>
         </List>
       </BaseDialog>
     );
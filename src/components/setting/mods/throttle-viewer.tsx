@@ -0,0 +1,237 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  addThrottleRule,
+  deleteThrottleRule,
+  getThrottleRules,
+  updateThrottleRule,
+} from "@/services/cmds";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Box,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+import { GuardState } from "./guard-state";
+import { SettingItem } from "./setting-comp";
+
+const DEFAULT_RULE: IThrottleRule = {
+  id: "",
+  host: "",
+  scope: "host",
+  download: 1024,
+  upload: 0,
+  enable: true,
+};
+
+const formatRate = (rate: number) => (rate > 0 ? `${rate} KB/s` : "-");
+
+export const ThrottleViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge, mutateVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [rule, setRule] = useState(DEFAULT_RULE);
+  const [port, setPort] = useState(0);
+
+  const { data: rules = [], mutate } = useSWR(
+    open ? "getThrottleRules" : null,
+    getThrottleRules,
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setRule(DEFAULT_RULE);
+      setPort(verge?.throttle_port ?? 33335);
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onSwitchFormat = (_e: any, value: boolean) => value;
+  const onError = (err: any) => {
+    useNotification(t("Error"), err.message || err.toString());
+  };
+  const onChangeData = (patch: Partial<IVergeConfig>) => {
+    mutateVerge({ ...verge, ...patch }, false);
+  };
+
+  const onPort = useLockFn(async () => {
+    if (port === verge?.throttle_port) return;
+    try {
+      await patchVerge({ throttle_port: port });
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onAdd = useLockFn(async () => {
+    if (!rule.host.trim()) return;
+    try {
+      await addThrottleRule({ ...rule, host: rule.host.trim() });
+      setRule(DEFAULT_RULE);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onUpdate = useLockFn(async (rule: IThrottleRule) => {
+    try {
+      await updateThrottleRule(rule.id, rule);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onDelete = useLockFn(async (id: string) => {
+    try {
+      await deleteThrottleRule(id);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Bandwidth Limit")}
+      contentSx={{ width: 520, maxHeight: 560 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Typography variant="body2" sx={{ opacity: 0.7 }}>
+        {t("Bandwidth Limit Info")}
+      </Typography>
+
+      <List>
+        <SettingItem label={t("Enable")}>
+          <GuardState
+            value={verge?.enable_throttle ?? false}
+            valueProps="checked"
+            onCatch={onError}
+            onFormat={onSwitchFormat}
+            onChange={(e) => onChangeData({ enable_throttle: e })}
+            onGuard={(e) => patchVerge({ enable_throttle: e })}
+          >
+            <Switch edge="end" />
+          </GuardState>
+        </SettingItem>
+
+        <SettingItem label={t("Port")}>
+          <TextField
+            size="small"
+            type="number"
+            autoComplete="off"
+            sx={{ width: 120 }}
+            value={port}
+            onChange={(e) => setPort(Number(e.target.value) || 0)}
+            onBlur={onPort}
+          />
+        </SettingItem>
+      </List>
+
+      <Box sx={{ display: "flex", gap: 1 }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          sx={{ flex: 1 }}
+          placeholder="+.example.com"
+          value={rule.host}
+          onChange={(e) => setRule({ ...rule, host: e.target.value })}
+        />
+        <Select
+          size="small"
+          sx={{ width: 110, "> div": { py: "7.5px" } }}
+          value={rule.scope}
+          onChange={(e) =>
+            setRule({
+              ...rule,
+              scope: e.target.value as IThrottleRule["scope"],
+            })
+          }
+        >
+          <MenuItem value="host">{t("Per Host")}</MenuItem>
+          <MenuItem value="connection">{t("Per Connection")}</MenuItem>
+        </Select>
+        <TextField
+          size="small"
+          type="number"
+          autoComplete="off"
+          sx={{ width: 90 }}
+          label={t("Download")}
+          value={rule.download}
+          onChange={(e) =>
+            setRule({ ...rule, download: Number(e.target.value) || 0 })
+          }
+        />
+        <TextField
+          size="small"
+          type="number"
+          autoComplete="off"
+          sx={{ width: 90 }}
+          label={t("Upload")}
+          value={rule.upload}
+          onChange={(e) =>
+            setRule({ ...rule, upload: Number(e.target.value) || 0 })
+          }
+        />
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      {rules.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>
+          {t("No Throttle Rules")}
+        </Typography>
+      ) : (
+        <List>
+          {rules.map((rule) => (
+            <ListItem key={rule.id} sx={{ padding: "2px", gap: 1 }}>
+              <Switch
+                size="small"
+                checked={rule.enable}
+                onChange={() => onUpdate({ ...rule, enable: !rule.enable })}
+              />
+              <ListItemText
+                primary={rule.host}
+                secondary={[
+                  rule.scope === "host" ? t("Per Host") : t("Per Connection"),
+                  `↓ ${formatRate(rule.download)}`,
+                  `↑ ${formatRate(rule.upload)}`,
+                ].join(" ")}
+                sx={{ opacity: rule.enable ? 1 : 0.5, wordBreak: "break-all" }}
+              />
+              <IconButton
+                size="small"
+                color="inherit"
+                onClick={() => onDelete(rule.id)}
+              >
+                <DeleteRounded fontSize="inherit" />
+              </IconButton>
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+ThrottleViewer.displayName = "ThrottleViewer";
@@ -0,0 +1,205 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import { saveTrayMenu } from "@/services/cmds";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Box,
+  Checkbox,
+  FormControlLabel,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { HOTKEY_FUNC } from "./hotkey-viewer";
+
+const initValues = () => ({
+  label: "",
+  type: "function" as ITrayMenuAction["type"],
+  func: HOTKEY_FUNC[0],
+  program: "",
+  args: "",
+  confirm: false,
+});
+
+export const TrayMenuViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [entries, setEntries] = useState<ITrayMenuEntry[]>([]);
+  const [values, setValues] = useState(initValues());
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setEntries(verge?.tray_menu ?? []);
+      setValues(initValues());
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onAdd = () => {
+    const label = values.label.trim();
+    const program = values.program.trim();
+    if (!label || (values.type === "command" && !program)) return;
+
+    const action: ITrayMenuAction =
+      values.type === "function"
+        ? { type: "function", func: values.func }
+        : {
+            type: "command",
+            program,
+            args: values.args.split(/\s+/).filter(Boolean),
+          };
+    setEntries((list) => [
+      ...list,
+      { label, action, confirm: values.confirm },
+    ]);
+    setValues(initValues());
+  };
+
+  // 保存后命令才允许运行
+  const onSave = useLockFn(async () => {
+    try {
+      await saveTrayMenu(entries);
+      setOpen(false);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
+  const describe = (action: ITrayMenuAction) =>
+    action.type === "function"
+      ? t(action.func)
+      : [action.program, ...action.args].join(" ");
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Tray Menu")}
+      contentSx={{ width: 480 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <Box display="flex" flexWrap="wrap" alignItems="center" gap={1}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Label")}
+          sx={{ width: 180 }}
+          value={values.label}
+          onChange={(e) => setValues((v) => ({ ...v, label: e.target.value }))}
+        />
+        <Select
+          size="small"
+          sx={{ width: 130, "> div": { py: "7.5px" } }}
+          value={values.type}
+          onChange={(e) =>
+            setValues((v) => ({
+              ...v,
+              type: e.target.value as ITrayMenuAction["type"],
+            }))
+          }
+        >
+          <MenuItem value="function">{t("Function")}</MenuItem>
+          <MenuItem value="command">{t("Command")}</MenuItem>
+        </Select>
+        <FormControlLabel
+          label={t("Confirm")}
+          control={
+            <Checkbox
+              size="small"
+              checked={values.confirm}
+              onChange={(_, confirm) => setValues((v) => ({ ...v, confirm }))}
+            />
+          }
+        />
+
+        {values.type === "function" ? (
+          <Select
+            size="small"
+            sx={{ width: 320, "> div": { py: "7.5px" } }}
+            value={values.func}
+            onChange={(e) => setValues((v) => ({ ...v, func: e.target.value }))}
+          >
+            {HOTKEY_FUNC.map((func) => (
+              <MenuItem key={func} value={func}>
+                {t(func)}
+              </MenuItem>
+            ))}
+          </Select>
+        ) : (
+          <>
+            <TextField
+              size="small"
+              autoComplete="off"
+              placeholder={t("Program")}
+              sx={{ width: 180 }}
+              value={values.program}
+              onChange={(e) =>
+                setValues((v) => ({ ...v, program: e.target.value }))
+              }
+            />
+            <TextField
+              size="small"
+              autoComplete="off"
+              placeholder={t("Arguments")}
+              sx={{ width: 132 }}
+              value={values.args}
+              onChange={(e) =>
+                setValues((v) => ({ ...v, args: e.target.value }))
+              }
+            />
+          </>
+        )}
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      <Typography variant="body2" sx={{ mt: 1, opacity: 0.7 }}>
+        {t("Tray Command Info")}
+      </Typography>
+
+      {entries.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>
+          {t("No Tray Menu Items")}
+        </Typography>
+      ) : (
+        <List>
+          {entries.map((entry, index) => (
+            <ListItem key={index} sx={{ padding: "2px" }}>
+              <ListItemText
+                primary={entry.label}
+                secondary={describe(entry.action)}
+              />
+              <IconButton
+                size="small"
+                color="inherit"
+                onClick={() =>
+                  setEntries((list) => list.filter((_, i) => i !== index))
+                }
+              >
+                <DeleteRounded fontSize="inherit" />
+              </IconButton>
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+TrayMenuViewer.displayName = "TrayMenuViewer";
@@ -0,0 +1,252 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { getProxiesInner } from "@/services/api";
+import {
+  addTunnel,
+  checkTunnels,
+  deleteTunnel,
+  getTunnels,
+  updateTunnel,
+} from "@/services/cmds";
+import { Add, DeleteRounded } from "@mui/icons-material";
+import {
+  Box,
+  Checkbox,
+  FormControlLabel,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+const emptyTunnel = (): ITunnel => ({
+  id: "",
+  network: ["tcp"],
+  address: "127.0.0.1:",
+  target: "",
+  enable: true,
+});
+
+const STATE_COLORS: Record<ITunnelStatus["state"], string> = {
+  listening: "success.main",
+  unreachable: "error.main",
+  inactive: "text.secondary",
+  unknown: "text.secondary",
+};
+
+export const TunnelViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [draft, setDraft] = useState(emptyTunnel());
+
+  const { data: tunnels = [], mutate } = useSWR(
+    open ? "getTunnels" : null,
+    getTunnels,
+  );
+  const { data: status = [], mutate: mutateStatus } = useSWR(
+    open ? "checkTunnels" : null,
+    checkTunnels,
+    { refreshInterval: 5000 },
+  );
+  const { data: proxies = [] } = useSWR(
+    open ? "getRuleTargets" : null,
+    async () => {
+      const names = Object.keys(await getProxiesInner());
+      return names.filter((name) => name !== "GLOBAL");
+    },
+  );
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setDraft(emptyTunnel());
+    },
+    close: () => setOpen(false),
+  }));
+
+  const refresh = () => {
+    mutate();
+    // 等内核重新加载配置后再检查
+    setTimeout(() => mutateStatus(), 1000);
+  };
+
+  const onError = (err: any) => {
+    useNotification(t("Error"), err.message || err.toString());
+  };
+
+  const onAdd = useLockFn(async () => {
+    try {
+      await addTunnel(draft);
+      setDraft(emptyTunnel());
+      refresh();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onToggle = useLockFn(async (tunnel: ITunnel) => {
+    try {
+      await updateTunnel(tunnel.id, { ...tunnel, enable: !tunnel.enable });
+      refresh();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onDelete = useLockFn(async (id: string) => {
+    try {
+      await deleteTunnel(id);
+      refresh();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onNetwork = (network: "tcp" | "udp", checked: boolean) =>
+    setDraft((d) => ({
+      ...d,
+      network: checked
+        ? [...d.network, network]
+        : d.network.filter((n) => n !== network),
+    }));
+
+  const stateLabel = (item?: ITunnelStatus) => {
+    if (!item) return "";
+    const label = {
+      listening: t("Listening"),
+      unreachable: t("Unreachable"),
+      inactive: t("Inactive"),
+      unknown: "UDP",
+    }[item.state];
+    return item.message ? `${label}: ${item.message}` : label;
+  };
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("Port Forwarding")}
+      contentSx={{ width: 520, maxHeight: 560 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Box sx={{ display: "flex", gap: 1 }}>
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Local Address")}
+          sx={{ flex: 1 }}
+          value={draft.address}
+          onChange={(e) => setDraft((d) => ({ ...d, address: e.target.value }))}
+        />
+        <TextField
+          size="small"
+          autoComplete="off"
+          placeholder={t("Target Address")}
+          sx={{ flex: 1 }}
+          value={draft.target}
+          onChange={(e) => setDraft((d) => ({ ...d, target: e.target.value }))}
+        />
+      </Box>
+
+      <Box sx={{ display: "flex", gap: 1, mt: 1, alignItems: "center" }}>
+        <Select
+          size="small"
+          sx={{ flex: 1, "> div": { py: "7.5px" } }}
+          value={draft.proxy ?? ""}
+          displayEmpty
+          renderValue={(value) => value || t("Follow Rules")}
+          onChange={(e) =>
+            setDraft((d) => ({ ...d, proxy: e.target.value || undefined }))
+          }
+        >
+          <MenuItem value="">{t("Follow Rules")}</MenuItem>
+          {proxies.map((name) => (
+            <MenuItem key={name} value={name}>
+              {name}
+            </MenuItem>
+          ))}
+        </Select>
+
+        {(["tcp", "udp"] as const).map((network) => (
+          <FormControlLabel
+            key={network}
+            label={network.toUpperCase()}
+            sx={{ mr: 0 }}
+            control={
+              <Checkbox
+                size="small"
+                checked={draft.network.includes(network)}
+                onChange={(_, checked) => onNetwork(network, checked)}
+              />
+            }
+          />
+        ))}
+
+        <IconButton size="small" color="inherit" onClick={onAdd}>
+          <Add />
+        </IconButton>
+      </Box>
+
+      {tunnels.length === 0 ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>{t("No Tunnels")}</Typography>
+      ) : (
+        <List>
+          {tunnels.map((tunnel) => {
+            const item = status.find((s) => s.id === tunnel.id);
+            return (
+              <ListItem key={tunnel.id} sx={{ padding: "2px" }}>
+                <Switch
+                  size="small"
+                  checked={tunnel.enable}
+                  onChange={() => onToggle(tunnel)}
+                />
+                <ListItemText
+                  primary={`${tunnel.address} → ${tunnel.target}`}
+                  secondary={
+                    <>
+                      {tunnel.network.join("/").toUpperCase()} ·{" "}
+                      {tunnel.proxy ?? t("Follow Rules")}{" "}
+                      <Typography
+                        component="span"
+                        variant="inherit"
+                        color={item ? STATE_COLORS[item.state] : undefined}
+                      >
+                        {stateLabel(item)}
+                      </Typography>
+                    </>
+                  }
+                  sx={{
+                    ml: 1,
+                    opacity: tunnel.enable ? 1 : 0.5,
+                    wordBreak: "break-all",
+                  }}
+                />
+                <IconButton
+                  size="small"
+                  color="inherit"
+                  onClick={() => onDelete(tunnel.id)}
+                >
+                  <DeleteRounded fontSize="inherit" />
+                </IconButton>
+              </ListItem>
+            );
+          })}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+TunnelViewer.displayName = "TunnelViewer";
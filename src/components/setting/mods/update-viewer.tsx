@@ -1,9 +1,13 @@
 import { BaseDialog, DialogRef } from "@/components/base";
 import { useNotification } from "@/hooks/use-notification";
+import {
+  checkAppUpdate,
+  installAppUpdate,
+  skipAppUpdate,
+  stageAppUpdate,
+} from "@/services/cmds";
 import { atomUpdateState } from "@/services/states";
-import { Box, styled } from "@mui/material";
-import { relaunch } from "@tauri-apps/api/process";
-import { checkUpdate, installUpdate } from "@tauri-apps/api/updater";
+import { Box, Button, Stack, styled } from "@mui/material";
 import { useLockFn } from "ahooks";
 import { forwardRef, useImperativeHandle, useMemo, useState } from "react";
 import { useTranslation } from "react-i18next";
@@ -21,11 +25,15 @@ export const UpdateViewer = forwardRef<DialogRef>((props, ref) => {
   const [open, setOpen] = useState(false);
   const [updateState, setUpdateState] = useRecoilState(atomUpdateState);
 
-  const { data: updateInfo } = useSWR("checkUpdate", checkUpdate, {
-    errorRetryCount: 2,
-    revalidateIfStale: false,
-    focusThrottleInterval: 36e5, // 1 hour
-  });
+  const { data: updateInfo, mutate } = useSWR(
+    "checkAppUpdate",
+    checkAppUpdate,
+    {
+      errorRetryCount: 2,
+      revalidateIfStale: false,
+      focusThrottleInterval: 36e5, // 1 hour
+    },
+  );
 
   useImperativeHandle(ref, () => ({
     open: () => setOpen(true),
@@ -34,30 +42,49 @@ export const UpdateViewer = forwardRef<DialogRef>((props, ref) => {
 
   // markdown parser
   const parseContent = useMemo(() => {
-    if (!updateInfo?.manifest?.body) {
+    if (!updateInfo?.body) {
       return "New Version is available";
     }
-    return snarkdown(updateInfo?.manifest?.body);
+    return snarkdown(updateInfo.body);
   }, [updateInfo]);
 
-  const onUpdate = useLockFn(async () => {
+  const runUpdate = async (task: () => Promise<void>) => {
     if (updateState) return;
     setUpdateState(true);
 
     try {
-      await installUpdate();
-      await relaunch();
+      await task();
     } catch (err: any) {
       useNotification(t("Error"), err?.message || err.toString());
     } finally {
       setUpdateState(false);
     }
-  });
+  };
+
+  // 安装完成后后端会重启应用
+  const onUpdate = useLockFn(() => runUpdate(installAppUpdate));
+
+  const onInstallOnExit = useLockFn(() =>
+    runUpdate(async () => {
+      const version = await stageAppUpdate();
+      await mutate();
+      useNotification(t("Success"), t("Installed On Exit", { version }));
+    }),
+  );
+
+  const onSkip = useLockFn(() =>
+    runUpdate(async () => {
+      if (!updateInfo) return;
+      await skipAppUpdate(updateInfo.version);
+      await mutate(null, false);
+      setOpen(false);
+    }),
+  );
 
   return (
     <BaseDialog
       open={open}
-      title={`New Version v${updateInfo?.manifest?.version}`}
+      title={`New Version v${updateInfo?.version}`}
       contentSx={{ minWidth: 360, maxWidth: 400, maxHeight: "50vh" }}
       okBtn={t("Update")}
       cancelBtn={t("Cancel")}
@@ -66,6 +93,19 @@ export const UpdateViewer = forwardRef<DialogRef>((props, ref) => {
       onOk={onUpdate}
     >
       <UpdateLog dangerouslySetInnerHTML={{ __html: parseContent }} />
+
+      <Stack direction="row" spacing={1} sx={{ mt: 1 }}>
+        <Button
+          size="small"
+          disabled={updateState || updateInfo?.staged}
+          onClick={onInstallOnExit}
+        >
+          {updateInfo?.staged ? t("Staged") : t("Install On Exit")}
+        </Button>
+        <Button size="small" disabled={updateState} onClick={onSkip}>
+          {t("Skip This Version")}
+        </Button>
+      </Stack>
     </BaseDialog>
   );
 });
@@ -0,0 +1,118 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { getUwpApps, invoke_uwp_tool, setUwpLoopback } from "@/services/cmds";
+import {
+  Box,
+  Button,
+  List,
+  ListItem,
+  ListItemText,
+  Switch,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+export const UwpViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+
+  const [open, setOpen] = useState(false);
+  const [filter, setFilter] = useState("");
+
+  const {
+    data: apps = [],
+    isLoading,
+    mutate,
+  } = useSWR(open ? "getUwpApps" : null, getUwpApps);
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setFilter("");
+    },
+    close: () => setOpen(false),
+  }));
+
+  const keyword = filter.trim().toLowerCase();
+  const filtered = apps.filter(
+    (app) =>
+      !keyword ||
+      app.display_name.toLowerCase().includes(keyword) ||
+      app.name.toLowerCase().includes(keyword),
+  );
+
+  // 非管理员时每次修改都会弹出 uac
+  const onSet = useLockFn(async (sids: string[], exempt: boolean) => {
+    try {
+      await setUwpLoopback(sids, exempt);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    } finally {
+      mutate();
+    }
+  });
+
+  const onExemptAll = () =>
+    onSet(
+      filtered.filter((app) => !app.exempted).map((app) => app.sid),
+      true,
+    );
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("UWP Loopback")}
+      contentSx={{ width: 480, maxHeight: 560 }}
+      disableOk
+      cancelBtn={t("Back")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+    >
+      <Typography variant="body2" sx={{ opacity: 0.7 }}>
+        {t("UWP Loopback Info")}
+      </Typography>
+
+      <Box sx={{ display: "flex", gap: 1, mt: 1 }}>
+        <TextField
+          size="small"
+          sx={{ flex: 1 }}
+          placeholder={t("Filter")}
+          value={filter}
+          onChange={(e) => setFilter(e.target.value)}
+        />
+        <Button variant="outlined" size="small" onClick={onExemptAll}>
+          {t("Exempt All")}
+        </Button>
+        <Button variant="text" size="small" onClick={invoke_uwp_tool}>
+          {t("Open UWP tool")}
+        </Button>
+      </Box>
+
+      {isLoading ? (
+        <Typography sx={{ mt: 2, opacity: 0.7 }}>{t("Loading")}</Typography>
+      ) : (
+        <List>
+          {filtered.map((app) => (
+            <ListItem key={app.sid} sx={{ padding: "2px", gap: 1 }}>
+              <Switch
+                size="small"
+                checked={app.exempted}
+                onChange={() => onSet([app.sid], !app.exempted)}
+              />
+              <ListItemText
+                primary={app.display_name}
+                secondary={app.name}
+                sx={{ wordBreak: "break-all" }}
+              />
+            </ListItem>
+          ))}
+        </List>
+      )}
+    </BaseDialog>
+  );
+});
+
+UwpViewer.displayName = "UwpViewer";
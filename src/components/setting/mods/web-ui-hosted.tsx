@@ -0,0 +1,153 @@
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  getWebUis,
+  installWebUi,
+  openWebUi,
+  removeWebUi,
+} from "@/services/cmds";
+import parseTraffic from "@/utils/parse-traffic";
+import {
+  DeleteRounded,
+  DownloadRounded,
+  OpenInNewRounded,
+  StarBorderRounded,
+  StarRounded,
+} from "@mui/icons-material";
+import {
+  CircularProgress,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import dayjs from "dayjs";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import useSWR from "swr";
+
+// the web uis downloaded and served by the backend
+export const WebUIHosted = () => {
+  const { t } = useTranslation();
+  const { verge, patchVerge } = useVerge();
+
+  const [installing, setInstalling] = useState("");
+  const { data: webUis, mutate } = useSWR("getWebUis", getWebUis);
+
+  const onError = (err: any) =>
+    useNotification(t("Error"), err.message || err.toString());
+
+  const onInstall = useLockFn(async (id: string) => {
+    setInstalling(id);
+    try {
+      await installWebUi(id);
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    } finally {
+      setInstalling("");
+    }
+  });
+
+  const onRemove = useLockFn(async (id: string) => {
+    try {
+      await removeWebUi(id);
+      if (verge?.default_web_ui === id) {
+        await patchVerge({ default_web_ui: "" });
+      }
+      mutate();
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onDefault = useLockFn(async (id: string) => {
+    try {
+      await patchVerge({ default_web_ui: id });
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  return (
+    <>
+      <Typography variant="subtitle2" sx={{ mb: 0.5 }}>
+        {t("Hosted Web UI")}
+      </Typography>
+      <Typography variant="body2" sx={{ opacity: 0.7 }}>
+        {t("Hosted Web UI Info")}
+      </Typography>
+      <Typography variant="body2" color="warning.main" sx={{ mt: 0.5 }}>
+        {t(
+          verge?.enable_controller_proxy
+            ? "Web UI Token Warning"
+            : "Web UI Secret Warning",
+        )}
+      </Typography>
+
+      <List dense>
+        {webUis?.map((item) => {
+          const [size, unit] = parseTraffic(item.size);
+          const isDefault = verge?.default_web_ui === item.id;
+          return (
+            <ListItem key={item.id} sx={{ padding: "2px 2px" }}>
+              <ListItemText
+                primary={item.name}
+                secondary={
+                  item.installed
+                    ? `${size} ${unit} · ${dayjs(
+                        (item.installed_at ?? 0) * 1000,
+                      ).format("YYYY-MM-DD")}`
+                    : t(item.pinned ? "Not Installed" : "Not Pinned")
+                }
+              />
+              {item.installed && (
+                <>
+                  <IconButton
+                    size="small"
+                    title={t("Set as Default")}
+                    onClick={() => onDefault(item.id)}
+                  >
+                    {isDefault ? (
+                      <StarRounded fontSize="inherit" color="primary" />
+                    ) : (
+                      <StarBorderRounded fontSize="inherit" />
+                    )}
+                  </IconButton>
+                  <IconButton
+                    size="small"
+                    title={t("Open")}
+                    onClick={() => openWebUi(item.id).catch(onError)}
+                  >
+                    <OpenInNewRounded fontSize="inherit" />
+                  </IconButton>
+                  <IconButton
+                    size="small"
+                    title={t("Delete")}
+                    onClick={() => onRemove(item.id)}
+                  >
+                    <DeleteRounded fontSize="inherit" />
+                  </IconButton>
+                </>
+              )}
+              <IconButton
+                size="small"
+                title={t(item.installed ? "Update" : "Download")}
+                disabled={!!installing || !item.pinned}
+                onClick={() => onInstall(item.id)}
+              >
+                {installing === item.id ? (
+                  <CircularProgress size={14} />
+                ) : (
+                  <DownloadRounded fontSize="inherit" />
+                )}
+              </IconButton>
+            </ListItem>
+          );
+        })}
+      </List>
+    </>
+  );
+};
@@ -7,6 +7,7 @@ import { Box, Button, Typography } from "@mui/material";
 import { useLockFn } from "ahooks";
 import { forwardRef, useImperativeHandle, useState } from "react";
 import { useTranslation } from "react-i18next";
+import { WebUIHosted } from "./web-ui-hosted";
 import { WebUIItem } from "./web-ui-item";
 
 export const WebUIViewer = forwardRef<DialogRef>((props, ref) => {
@@ -91,7 +92,7 @@ export const WebUIViewer = forwardRef<DialogRef>((props, ref) => {
       }
       contentSx={{
         width: 450,
-        height: 300,
+        height: 450,
         pb: 1,
         overflowY: "auto",
         userSelect: "text",
@@ -101,6 +102,8 @@ export const WebUIViewer = forwardRef<DialogRef>((props, ref) => {
       onClose={() => setOpen(false)}
       onCancel={() => setOpen(false)}
     >
+      <WebUIHosted />
+
       {editing && (
         <WebUIItem
           value=""
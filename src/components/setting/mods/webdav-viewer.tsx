@@ -0,0 +1,206 @@
+import { BaseDialog, DialogRef } from "@/components/base";
+import { useNotification } from "@/hooks/use-notification";
+import { useVerge } from "@/hooks/use-verge";
+import {
+  setWebdavConfig,
+  webdavBackup,
+  webdavListBackups,
+  webdavPreviewRestore,
+  webdavRestore,
+} from "@/services/cmds";
+import {
+  Button,
+  List,
+  ListItem,
+  ListItemText,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { useLockFn } from "ahooks";
+import { forwardRef, useImperativeHandle, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+export const WebDavViewer = forwardRef<DialogRef>((props, ref) => {
+  const { t } = useTranslation();
+  const { verge, patchVerge } = useVerge();
+
+  const [open, setOpen] = useState(false);
+  const [values, setValues] = useState({
+    url: "",
+    username: "",
+    password: "",
+    interval: 0,
+  });
+  const [backups, setBackups] = useState<IWebDavBackup[]>([]);
+  const [preview, setPreview] = useState<IRestorePreview | null>(null);
+
+  useImperativeHandle(ref, () => ({
+    open: () => {
+      setOpen(true);
+      setPreview(null);
+      setValues({
+        url: verge?.webdav_url ?? "",
+        username: verge?.webdav_username ?? "",
+        password: "",
+        interval: verge?.webdav_backup_interval ?? 0,
+      });
+      webdavListBackups()
+        .then(setBackups)
+        .catch(() => setBackups([]));
+    },
+    close: () => setOpen(false),
+  }));
+
+  const onError = (err: any) =>
+    useNotification(t("Error"), err.message || err.toString());
+
+  const onSave = useLockFn(async () => {
+    try {
+      await setWebdavConfig(
+        values.url,
+        values.username,
+        values.password || undefined,
+      );
+      await patchVerge({ webdav_backup_interval: values.interval });
+      setOpen(false);
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onBackup = useLockFn(async () => {
+    try {
+      const name = await webdavBackup();
+      useNotification(t("Success"), name);
+      setBackups(await webdavListBackups());
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const onPreview = useLockFn(async (name: string) => {
+    try {
+      setPreview(await webdavPreviewRestore(name));
+    } catch (err: any) {
+      onError(err);
+    }
+  });
+
+  const changes = preview?.changes.filter((c) => c.kind !== "unchanged") ?? [];
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t("WebDAV Backup")}
+      contentSx={{ width: 450 }}
+      okBtn={t("Save")}
+      cancelBtn={t("Cancel")}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      <List>
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("WebDAV URL")} />
+          <TextField
+            size="small"
+            autoComplete="off"
+            sx={{ width: 250 }}
+            value={values.url}
+            placeholder="https://dav.example.com/"
+            onChange={(e) => setValues((v) => ({ ...v, url: e.target.value }))}
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Username")} />
+          <TextField
+            size="small"
+            autoComplete="off"
+            sx={{ width: 250 }}
+            value={values.username}
+            onChange={(e) =>
+              setValues((v) => ({ ...v, username: e.target.value }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Password")} />
+          <TextField
+            size="small"
+            type="password"
+            autoComplete="off"
+            sx={{ width: 250 }}
+            value={values.password}
+            placeholder={t("Unchanged")}
+            onChange={(e) =>
+              setValues((v) => ({ ...v, password: e.target.value }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Backup Interval")} />
+          <TextField
+            size="small"
+            type="number"
+            sx={{ width: 100 }}
+            value={values.interval}
+            InputProps={{ endAdornment: "h" }}
+            onChange={(e) =>
+              setValues((v) => ({
+                ...v,
+                interval: Math.max(0, parseInt(e.target.value) || 0),
+              }))
+            }
+          />
+        </ListItem>
+
+        <ListItem sx={{ padding: "5px 2px" }}>
+          <ListItemText primary={t("Remote Backups")} />
+          <Button variant="contained" size="small" onClick={onBackup}>
+            {t("Backup Now")}
+          </Button>
+        </ListItem>
+
+        {backups.map((backup) => (
+          <ListItem key={backup.name} sx={{ padding: "2px 2px" }}>
+            <ListItemText
+              primary={backup.name}
+              secondary={backup.modified}
+              primaryTypographyProps={{ fontSize: 14 }}
+            />
+            <Button size="small" onClick={() => onPreview(backup.name)}>
+              {t("Restore")}
+            </Button>
+          </ListItem>
+        ))}
+
+        {preview && (
+          <ListItem sx={{ padding: "5px 2px", display: "block" }}>
+            <Typography variant="body2">
+              {preview.manifest.hostname} · {preview.manifest.app_version}
+            </Typography>
+            {changes.map((change) => (
+              <Typography key={change.path} variant="body2" color="GrayText">
+                [{change.kind}] {change.path}
+              </Typography>
+            ))}
+            <Button
+              size="small"
+              color="warning"
+              onClick={() =>
+                webdavRestore(preview.name).catch((err) => onError(err))
+              }
+            >
+              {t("Restore And Restart")}
+            </Button>
+          </ListItem>
+        )}
+      </List>
+    </BaseDialog>
+  );
+});
+
+WebDavViewer.displayName = "WebDavViewer";
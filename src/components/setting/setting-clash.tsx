@@ -2,9 +2,14 @@ import { DialogRef } from "@/components/base";
 import { useClash } from "@/hooks/use-clash";
 import { useNotification } from "@/hooks/use-notification";
 import { useVerge } from "@/hooks/use-verge";
-import { invoke_uwp_tool } from "@/services/cmds";
+import { openWebUi } from "@/services/cmds";
 import getSystem from "@/utils/get-system";
-import { ArrowForward, Settings, Shuffle } from "@mui/icons-material";
+import {
+  ArrowForward,
+  OpenInNewRounded,
+  Settings,
+  Shuffle,
+} from "@mui/icons-material";
 import {
   IconButton,
   MenuItem,
@@ -20,8 +25,23 @@ import { ClashCoreViewer } from "./mods/clash-core-viewer";
 import { ClashFieldViewer } from "./mods/clash-field-viewer";
 import { ClashPortViewer } from "./mods/clash-port-viewer";
 import { ControllerViewer } from "./mods/controller-viewer";
+import { ControllerProxyViewer } from "./mods/controller-proxy-viewer";
+import { DnsViewer } from "./mods/dns-viewer";
+import { HostsViewer } from "./mods/hosts-viewer";
+import { RulesViewer } from "./mods/rules-viewer";
+import { AppRulesViewer } from "./mods/app-rules-viewer";
+import { ChainViewer } from "./mods/chain-viewer";
+import { TunnelViewer } from "./mods/tunnel-viewer";
+import { ThrottleViewer } from "./mods/throttle-viewer";
+import { ListenerViewer } from "./mods/listener-viewer";
+import { AuthViewer } from "./mods/auth-viewer";
+import { ApiInspectorViewer } from "./mods/api-inspector-viewer";
+import { SnifferViewer } from "./mods/sniffer-viewer";
+import { CacheViewer } from "./mods/cache-viewer";
+import { FailoverViewer } from "./mods/failover-viewer";
 import { GuardState } from "./mods/guard-state";
 import { SettingItem, SettingList } from "./mods/setting-comp";
+import { UwpViewer } from "./mods/uwp-viewer";
 import { WebUIViewer } from "./mods/web-ui-viewer";
 
 const isWIN = getSystem() === "windows";
@@ -44,7 +64,22 @@ const SettingClash = ({ onError }: Props) => {
   const fieldRef = useRef<DialogRef>(null);
   const portRef = useRef<DialogRef>(null);
   const ctrlRef = useRef<DialogRef>(null);
+  const ctrlProxyRef = useRef<DialogRef>(null);
   const coreRef = useRef<DialogRef>(null);
+  const dnsRef = useRef<DialogRef>(null);
+  const hostsRef = useRef<DialogRef>(null);
+  const rulesRef = useRef<DialogRef>(null);
+  const appRulesRef = useRef<DialogRef>(null);
+  const throttleRef = useRef<DialogRef>(null);
+  const chainRef = useRef<DialogRef>(null);
+  const tunnelRef = useRef<DialogRef>(null);
+  const listenerRef = useRef<DialogRef>(null);
+  const authRef = useRef<DialogRef>(null);
+  const snifferRef = useRef<DialogRef>(null);
+  const cacheRef = useRef<DialogRef>(null);
+  const failoverRef = useRef<DialogRef>(null);
+  const inspectorRef = useRef<DialogRef>(null);
+  const uwpRef = useRef<DialogRef>(null);
 
   const onSwitchFormat = (_e: any, value: boolean) => value;
   const onChangeData = (patch: Partial<IConfigData>) => {
@@ -60,7 +95,22 @@ const SettingClash = ({ onError }: Props) => {
       <ClashFieldViewer ref={fieldRef} />
       <ClashPortViewer ref={portRef} />
       <ControllerViewer ref={ctrlRef} />
+      <ControllerProxyViewer ref={ctrlProxyRef} />
       <ClashCoreViewer ref={coreRef} />
+      <DnsViewer ref={dnsRef} />
+      <HostsViewer ref={hostsRef} />
+      <RulesViewer ref={rulesRef} />
+      <AppRulesViewer ref={appRulesRef} />
+      <ThrottleViewer ref={throttleRef} />
+      <ChainViewer ref={chainRef} />
+      <TunnelViewer ref={tunnelRef} />
+      <ListenerViewer ref={listenerRef} />
+      <AuthViewer ref={authRef} />
+      <SnifferViewer ref={snifferRef} />
+      <CacheViewer ref={cacheRef} />
+      <FailoverViewer ref={failoverRef} />
+      <ApiInspectorViewer ref={inspectorRef} />
+      {isWIN && <UwpViewer ref={uwpRef} />}
 
       <SettingItem label={t("Allow Lan")}>
         <GuardState
@@ -157,7 +207,47 @@ const SettingClash = ({ onError }: Props) => {
         </IconButton>
       </SettingItem>
 
-      <SettingItem label={t("Web UI")}>
+      <SettingItem label={t("Controller Proxy")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => ctrlProxyRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("API Inspector")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => inspectorRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem
+        label={t("Web UI")}
+        extra={
+          verge?.default_web_ui && (
+            <Tooltip title={t("Open")}>
+              <IconButton
+                color="inherit"
+                size="small"
+                onClick={() => openWebUi().catch(onError)}
+              >
+                <OpenInNewRounded
+                  fontSize="inherit"
+                  style={{ cursor: "pointer", opacity: 0.75 }}
+                />
+              </IconButton>
+            </Tooltip>
+          )
+        }
+      >
         <IconButton
           color="inherit"
           size="small"
@@ -179,6 +269,151 @@ const SettingClash = ({ onError }: Props) => {
         </IconButton>
       </SettingItem>
 
+      <SettingItem label={t("DNS Override")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => dnsRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Hosts")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => hostsRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("User Rules")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => rulesRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("App Rules")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => appRulesRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Bandwidth Limit")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => throttleRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Proxy Chains")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => chainRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Port Forwarding")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => tunnelRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Inbound Listeners")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => listenerRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Exit IP Probe")}>
+        <GuardState
+          value={verge?.enable_exit_probe ?? false}
+          valueProps="checked"
+          onCatch={onError}
+          onFormat={onSwitchFormat}
+          onChange={(e) => onChangeVerge({ enable_exit_probe: e })}
+          onGuard={(e) => patchVerge({ enable_exit_probe: e })}
+        >
+          <Switch edge="end" />
+        </GuardState>
+      </SettingItem>
+
+      <SettingItem label={t("Inbound Auth")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => authRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Sniffer")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => snifferRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Core Cache")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => cacheRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Core Failover")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => failoverRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
       <SettingItem
         label={t("Clash Core")}
         extra={
@@ -197,12 +432,12 @@ const SettingClash = ({ onError }: Props) => {
         <Typography sx={{ py: "7px", pr: 1 }}>{version}</Typography>
       </SettingItem>
       {isWIN && (
-        <SettingItem label={t("Open UWP tool")}>
+        <SettingItem label={t("UWP Loopback")}>
           <IconButton
             color="inherit"
             size="small"
             sx={{ my: "2px" }}
-            onClick={invoke_uwp_tool}
+            onClick={() => uwpRef.current?.open()}
           >
             <ArrowForward />
           </IconButton>
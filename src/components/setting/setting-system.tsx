@@ -1,15 +1,23 @@
 import useSWR from "swr";
-import { useRef } from "react";
+import { useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
-import { IconButton, Switch } from "@mui/material";
-import { ArrowForward, PrivacyTipRounded, Settings } from "@mui/icons-material";
-import { checkService } from "@/services/cmds";
+import { IconButton, MenuItem, Select, Switch } from "@mui/material";
+import {
+  ArrowForward,
+  ContentCopyRounded,
+  PrivacyTipRounded,
+  Settings,
+} from "@mui/icons-material";
+import { checkService, copyProxyEnv } from "@/services/cmds";
 import { useVerge } from "@/hooks/use-verge";
+import { useProfiles } from "@/hooks/use-profiles";
 import { DialogRef } from "@/components/base";
 import { SettingList, SettingItem } from "./mods/setting-comp";
 import { GuardState } from "./mods/guard-state";
 import { ServiceViewer } from "./mods/service-viewer";
 import { SysproxyViewer } from "./mods/sysproxy-viewer";
+import { PauseViewer } from "./mods/pause-viewer";
+import { SystemdViewer } from "./mods/systemd-viewer";
 import getSystem from "@/utils/get-system";
 
 interface Props {
@@ -17,15 +25,19 @@ interface Props {
 }
 
 const isWIN = getSystem() === "windows";
+// the privileged helper on macos
+const hasService = isWIN || getSystem() === "macos";
+const isLinux = getSystem() === "linux";
 
 const SettingSystem = ({ onError }: Props) => {
   const { t } = useTranslation();
 
   const { verge, mutateVerge, patchVerge } = useVerge();
+  const { profiles = {} } = useProfiles();
 
   // service mode
   const { data: serviceStatus } = useSWR(
-    isWIN ? "checkService" : null,
+    hasService ? "checkService" : null,
     checkService,
     {
       revalidateIfStale: false,
@@ -36,16 +48,45 @@ const SettingSystem = ({ onError }: Props) => {
 
   const serviceRef = useRef<DialogRef>(null);
   const sysproxyRef = useRef<DialogRef>(null);
+  const pauseRef = useRef<DialogRef>(null);
+  const systemdRef = useRef<DialogRef>(null);
+
+  const [shell, setShell] = useState<IShellType>(isWIN ? "powershell" : "sh");
 
   const {
     enable_tun_mode,
     enable_auto_launch,
     enable_service_mode,
     enable_silent_start,
+    enable_low_power_mode,
+    enable_lazy_core,
+    enable_self_bypass,
     enable_system_proxy,
+    enable_system_notification,
+    enable_clipboard_watch,
+    clipboard_watch_privacy,
+    startup_profile,
+    expire_reminder_days,
+    expired_fallback_profile,
   } = verge ?? {};
 
   const onSwitchFormat = (_e: any, value: boolean) => value;
+
+  // the pinned one is saved as its uid
+  const startupProfile =
+    startup_profile?.type === "pinned"
+      ? startup_profile.uid
+      : startup_profile?.type ?? "last_used";
+  const onStartupProfile = (value: string) => {
+    const policy: IStartupProfile =
+      value === "last_used" || value === "ask"
+        ? { type: value }
+        : { type: "pinned", uid: value };
+    patchVerge({ startup_profile: policy }).catch((err) => onError?.(err));
+  };
+  const profileItems = (profiles.items ?? []).filter(
+    (item) => item.type === "local" || item.type === "remote",
+  );
   const onChangeData = (patch: Partial<IVergeConfig>) => {
     mutateVerge({ ...verge, ...patch }, false);
   };
@@ -53,7 +94,9 @@ const SettingSystem = ({ onError }: Props) => {
   return (
     <SettingList title={t("System Setting")}>
       <SysproxyViewer ref={sysproxyRef} />
-      {isWIN && (
+      <PauseViewer ref={pauseRef} />
+      {isLinux && <SystemdViewer ref={systemdRef} />}
+      {hasService && (
         <ServiceViewer ref={serviceRef} enable={!!enable_service_mode} />
       )}
 
@@ -70,7 +113,22 @@ const SettingSystem = ({ onError }: Props) => {
         </GuardState>
       </SettingItem>
 
-      {isWIN && (
+      {enable_tun_mode && (
+        <SettingItem label={t("Bypass App Traffic")}>
+          <GuardState
+            value={enable_self_bypass ?? true}
+            valueProps="checked"
+            onCatch={onError}
+            onFormat={onSwitchFormat}
+            onChange={(e) => onChangeData({ enable_self_bypass: e })}
+            onGuard={(e) => patchVerge({ enable_self_bypass: e })}
+          >
+            <Switch edge="end" />
+          </GuardState>
+        </SettingItem>
+      )}
+
+      {hasService && (
         <SettingItem
           label={t("Service Mode")}
           extra={
@@ -131,6 +189,99 @@ const SettingSystem = ({ onError }: Props) => {
         </GuardState>
       </SettingItem>
 
+      {isLinux && (
+        <SettingItem label={t("Systemd Service")}>
+          <IconButton
+            color="inherit"
+            size="small"
+            sx={{ my: "2px" }}
+            onClick={() => systemdRef.current?.open()}
+          >
+            <ArrowForward />
+          </IconButton>
+        </SettingItem>
+      )}
+
+      <SettingItem label={t("Pause Windows")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => pauseRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem
+        label={t("Copy Env")}
+        extra={
+          <IconButton
+            color="inherit"
+            size="small"
+            onClick={() => copyProxyEnv(shell).catch((err) => onError?.(err))}
+          >
+            <ContentCopyRounded
+              fontSize="inherit"
+              style={{ cursor: "pointer", opacity: 0.75 }}
+            />
+          </IconButton>
+        }
+      >
+        <Select
+          size="small"
+          sx={{ width: 120, "> div": { py: "7.5px" } }}
+          value={shell}
+          onChange={(e) => setShell(e.target.value as IShellType)}
+        >
+          <MenuItem value="sh">Bash / Zsh</MenuItem>
+          <MenuItem value="fish">Fish</MenuItem>
+          <MenuItem value="powershell">PowerShell</MenuItem>
+          <MenuItem value="cmd">CMD</MenuItem>
+        </Select>
+      </SettingItem>
+
+      <SettingItem label={t("System Notification")}>
+        <GuardState
+          value={enable_system_notification ?? true}
+          valueProps="checked"
+          onCatch={onError}
+          onFormat={onSwitchFormat}
+          onChange={(e) => onChangeData({ enable_system_notification: e })}
+          onGuard={(e) => patchVerge({ enable_system_notification: e })}
+        >
+          <Switch edge="end" />
+        </GuardState>
+      </SettingItem>
+
+      <SettingItem label={t("Clipboard Watch")}>
+        <GuardState
+          value={enable_clipboard_watch ?? false}
+          valueProps="checked"
+          onCatch={onError}
+          onFormat={onSwitchFormat}
+          onChange={(e) => onChangeData({ enable_clipboard_watch: e })}
+          onGuard={(e) => patchVerge({ enable_clipboard_watch: e })}
+        >
+          <Switch edge="end" />
+        </GuardState>
+      </SettingItem>
+
+      {enable_clipboard_watch && (
+        <SettingItem label={t("Hide Clipboard Content")}>
+          <GuardState
+            value={clipboard_watch_privacy ?? true}
+            valueProps="checked"
+            onCatch={onError}
+            onFormat={onSwitchFormat}
+            onChange={(e) => onChangeData({ clipboard_watch_privacy: e })}
+            onGuard={(e) => patchVerge({ clipboard_watch_privacy: e })}
+          >
+            <Switch edge="end" />
+          </GuardState>
+        </SettingItem>
+      )}
+
       <SettingItem label={t("Auto Launch")}>
         <GuardState
           value={enable_auto_launch ?? false}
@@ -156,6 +307,91 @@ const SettingSystem = ({ onError }: Props) => {
           <Switch edge="end" />
         </GuardState>
       </SettingItem>
+
+      <SettingItem label={t("Low Power Mode")}>
+        <GuardState
+          value={enable_low_power_mode ?? true}
+          valueProps="checked"
+          onCatch={onError}
+          onFormat={onSwitchFormat}
+          onChange={(e) => onChangeData({ enable_low_power_mode: e })}
+          onGuard={(e) => patchVerge({ enable_low_power_mode: e })}
+        >
+          <Switch edge="end" />
+        </GuardState>
+      </SettingItem>
+
+      <SettingItem label={t("Start Core On Demand")}>
+        <GuardState
+          value={enable_lazy_core ?? false}
+          valueProps="checked"
+          onCatch={onError}
+          onFormat={onSwitchFormat}
+          onChange={(e) => onChangeData({ enable_lazy_core: e })}
+          onGuard={(e) => patchVerge({ enable_lazy_core: e })}
+        >
+          <Switch edge="end" />
+        </GuardState>
+      </SettingItem>
+
+      <SettingItem label={t("Startup Profile")}>
+        <Select
+          size="small"
+          sx={{ width: 160, "> div": { py: "7.5px" } }}
+          value={startupProfile}
+          onChange={(e) => onStartupProfile(e.target.value)}
+        >
+          <MenuItem value="last_used">{t("Last Used")}</MenuItem>
+          <MenuItem value="ask">{t("Ask On Launch")}</MenuItem>
+          {profileItems.map((item) => (
+            <MenuItem key={item.uid} value={item.uid}>
+              {item.name}
+            </MenuItem>
+          ))}
+        </Select>
+      </SettingItem>
+
+      <SettingItem label={t("Expiry Reminder")}>
+        <GuardState
+          value={expire_reminder_days ?? 3}
+          onCatch={onError}
+          onFormat={(e: any) => e.target.value}
+          onChange={(e) => onChangeData({ expire_reminder_days: e })}
+          onGuard={(e) => patchVerge({ expire_reminder_days: e })}
+        >
+          <Select size="small" sx={{ width: 160, "> div": { py: "7.5px" } }}>
+            <MenuItem value={0}>{t("On Expiry")}</MenuItem>
+            {[1, 3, 7, 14].map((days) => (
+              <MenuItem key={days} value={days}>
+                {t("Days Before", { days })}
+              </MenuItem>
+            ))}
+          </Select>
+        </GuardState>
+      </SettingItem>
+
+      <SettingItem label={t("Expired Fallback")}>
+        <GuardState
+          value={expired_fallback_profile ?? ""}
+          onCatch={onError}
+          onFormat={(e: any) => e.target.value}
+          onChange={(e) => onChangeData({ expired_fallback_profile: e })}
+          onGuard={(e) => patchVerge({ expired_fallback_profile: e })}
+        >
+          <Select
+            size="small"
+            displayEmpty
+            sx={{ width: 160, "> div": { py: "7.5px" } }}
+          >
+            <MenuItem value="">{t("Keep Current")}</MenuItem>
+            {profileItems.map((item) => (
+              <MenuItem key={item.uid} value={item.uid}>
+                {item.name}
+              </MenuItem>
+            ))}
+          </Select>
+        </GuardState>
+      </SettingItem>
     </SettingList>
   );
 };
@@ -2,7 +2,9 @@ import { DialogRef } from "@/components/base";
 import { useNotification } from "@/hooks/use-notification";
 import { useVerge } from "@/hooks/use-verge";
 import {
+  checkAppUpdate,
   collectLogs,
+  generateDiagnosticBundle,
   openAppDir,
   openCoreDir,
   openLogsDir,
@@ -13,24 +15,35 @@ import {
   IconButton,
   MenuItem,
   Select,
+  Switch,
   Tooltip,
   Typography,
 } from "@mui/material";
 import { version } from "@root/package.json";
-import { checkUpdate } from "@tauri-apps/api/updater";
 import { useLockFn } from "ahooks";
 import { useRef } from "react";
 import { useTranslation } from "react-i18next";
+import { mutate } from "swr";
+import { ClientImportViewer } from "./mods/client-import-viewer";
 import { ConfigViewer } from "./mods/config-viewer";
+import { CrashViewer } from "./mods/crash-viewer";
+import { DoctorViewer } from "./mods/doctor-viewer";
+import { EventHookViewer } from "./mods/event-hook-viewer";
+import { FlattenViewer } from "./mods/flatten-viewer";
 import { GuardState } from "./mods/guard-state";
 import { HotkeyViewer } from "./mods/hotkey-viewer";
+import { HttpPolicyViewer } from "./mods/http-policy-viewer";
 import { LayoutViewer } from "./mods/layout-viewer";
 import { MiscViewer } from "./mods/misc-viewer";
+import { RemoteManagementViewer } from "./mods/remote-management-viewer";
 import { SettingItem, SettingList } from "./mods/setting-comp";
 import { TasksViewer } from "./mods/tasks-viewer";
 import { ThemeModeSwitch } from "./mods/theme-mode-switch";
 import { ThemeViewer } from "./mods/theme-viewer";
+import { TrayMenuViewer } from "./mods/tray-menu-viewer";
 import { UpdateViewer } from "./mods/update-viewer";
+import { SyncViewer } from "./mods/sync-viewer";
+import { WebDavViewer } from "./mods/webdav-viewer";
 
 interface Props {
   onError?: (err: Error) => void;
@@ -51,6 +64,16 @@ const SettingVerge = ({ onError }: Props) => {
   const layoutRef = useRef<DialogRef>(null);
   const updateRef = useRef<DialogRef>(null);
   const tasksRef = useRef<DialogRef>(null);
+  const webdavRef = useRef<DialogRef>(null);
+  const syncRef = useRef<DialogRef>(null);
+  const eventHookRef = useRef<DialogRef>(null);
+  const trayMenuRef = useRef<DialogRef>(null);
+  const remoteRef = useRef<DialogRef>(null);
+  const doctorRef = useRef<DialogRef>(null);
+  const crashRef = useRef<DialogRef>(null);
+  const importRef = useRef<DialogRef>(null);
+  const flattenRef = useRef<DialogRef>(null);
+  const httpRef = useRef<DialogRef>(null);
 
   const onChangeData = (patch: Partial<IVergeConfig>) => {
     mutateVerge({ ...verge, ...patch }, false);
@@ -58,8 +81,9 @@ const SettingVerge = ({ onError }: Props) => {
 
   const onCheckUpdate = useLockFn(async () => {
     try {
-      const info = await checkUpdate();
-      if (!info?.shouldUpdate) {
+      const info = await checkAppUpdate();
+      mutate("checkAppUpdate", info, false);
+      if (!info) {
         useNotification(t("Success"), "No Updates Available");
       } else {
         updateRef.current?.open();
@@ -69,6 +93,15 @@ const SettingVerge = ({ onError }: Props) => {
     }
   });
 
+  const onDiagnosticBundle = useLockFn(async () => {
+    try {
+      const path = await generateDiagnosticBundle();
+      useNotification(t("Success"), path);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
   return (
     <SettingList title={t("Nyanpasu Setting")}>
       <ThemeViewer ref={themeRef} />
@@ -78,6 +111,16 @@ const SettingVerge = ({ onError }: Props) => {
       <LayoutViewer ref={layoutRef} />
       <UpdateViewer ref={updateRef} />
       <TasksViewer ref={tasksRef} />
+      <WebDavViewer ref={webdavRef} />
+      <SyncViewer ref={syncRef} />
+      <EventHookViewer ref={eventHookRef} />
+      <TrayMenuViewer ref={trayMenuRef} />
+      <RemoteManagementViewer ref={remoteRef} />
+      <DoctorViewer ref={doctorRef} />
+      <CrashViewer ref={crashRef} />
+      <ClientImportViewer ref={importRef} />
+      <FlattenViewer ref={flattenRef} />
+      <HttpPolicyViewer ref={httpRef} />
 
       <SettingItem label={t("Language")}>
         <GuardState
@@ -150,6 +193,83 @@ const SettingVerge = ({ onError }: Props) => {
         </IconButton>
       </SettingItem>
 
+      <SettingItem label={t("Network Requests")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => httpRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("WebDAV Backup")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => webdavRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Import From Other Clients")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => importRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Profile Sync")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => syncRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Event Hook")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => eventHookRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Tray Menu")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => trayMenuRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Remote Management")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => remoteRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
       <SettingItem label={t("Hotkey Setting")}>
         <IconButton
           color="inherit"
@@ -172,6 +292,17 @@ const SettingVerge = ({ onError }: Props) => {
         </IconButton>
       </SettingItem>
 
+      <SettingItem label={t("Export For Router")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => flattenRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
       <SettingItem label={t("Open App Dir")}>
         <IconButton
           color="inherit"
@@ -223,17 +354,84 @@ const SettingVerge = ({ onError }: Props) => {
         </IconButton>
       </SettingItem>
 
+      <SettingItem label={t("Connectivity Doctor")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => doctorRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Diagnostic Bundle")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={onDiagnosticBundle}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
+      <SettingItem label={t("Crash Reports")}>
+        <IconButton
+          color="inherit"
+          size="small"
+          sx={{ my: "2px" }}
+          onClick={() => crashRef.current?.open()}
+        >
+          <ArrowForward />
+        </IconButton>
+      </SettingItem>
+
       {!(OS === "windows" && WIN_PORTABLE) && (
-        <SettingItem label={t("Check for Updates")}>
-          <IconButton
-            color="inherit"
-            size="small"
-            sx={{ my: "2px" }}
-            onClick={onCheckUpdate}
-          >
-            <ArrowForward />
-          </IconButton>
-        </SettingItem>
+        <>
+          <SettingItem label={t("Update Channel")}>
+            <GuardState
+              value={verge?.update_channel ?? "stable"}
+              onCatch={onError}
+              onFormat={(e: any) => e.target.value}
+              onChange={(e) => onChangeData({ update_channel: e })}
+              onGuard={(e) => patchVerge({ update_channel: e })}
+            >
+              <Select
+                size="small"
+                sx={{ width: 100, "> div": { py: "7.5px" } }}
+              >
+                <MenuItem value="stable">{t("Stable")}</MenuItem>
+                <MenuItem value="beta">{t("Beta")}</MenuItem>
+                <MenuItem value="nightly">{t("Nightly")}</MenuItem>
+              </Select>
+            </GuardState>
+          </SettingItem>
+
+          <SettingItem label={t("Install Updates On Exit")}>
+            <GuardState
+              value={verge?.update_install_on_exit ?? false}
+              valueProps="checked"
+              onCatch={onError}
+              onFormat={(_e: any, value: boolean) => value}
+              onChange={(e) => onChangeData({ update_install_on_exit: e })}
+              onGuard={(e) => patchVerge({ update_install_on_exit: e })}
+            >
+              <Switch edge="end" />
+            </GuardState>
+          </SettingItem>
+
+          <SettingItem label={t("Check for Updates")}>
+            <IconButton
+              color="inherit"
+              size="small"
+              sx={{ my: "2px" }}
+              onClick={onCheckUpdate}
+            >
+              <ArrowForward />
+            </IconButton>
+          </SettingItem>
+        </>
       )}
 
       <SettingItem label={t("Nyanpasu Version")}>
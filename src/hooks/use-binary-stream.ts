@@ -0,0 +1,26 @@
+import { getStreamServer } from "@/services/cmds";
+import { decode } from "@msgpack/msgpack";
+import { useWebsocket, WsOptions } from "./use-websocket";
+
+export type StreamChannel = "connections" | "traffic" | "logs";
+
+// the msgpack frames of the high frequency streams, served by the backend
+export const useBinaryStream = <T>(
+  channel: StreamChannel,
+  onFrame: (frame: T) => void,
+  options?: WsOptions,
+) => {
+  const { connect, disconnect } = useWebsocket(
+    (event) => onFrame(decode(new Uint8Array(event.data)) as T),
+    { ...options, binary: true },
+  );
+
+  const connectStream = async () => {
+    const [port, token] = await getStreamServer();
+    connect(
+      `ws://127.0.0.1:${port}/streams/${channel}?token=${encodeURIComponent(token)}`,
+    );
+  };
+
+  return { connect: connectStream, disconnect };
+};
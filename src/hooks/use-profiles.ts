@@ -3,8 +3,9 @@ import {
   getProfiles,
   patchProfile,
   patchProfilesConfig,
+  selectProxy,
 } from "@/services/cmds";
-import { getProxies, updateProxy } from "@/services/api";
+import { getProxies } from "@/services/api";
 
 export const useProfiles = () => {
   const { data: profiles, mutate: mutateProfiles } = useSWR(
@@ -43,7 +44,7 @@ export const useProfiles = () => {
       selected.map((each) => [each.name!, each.now!]),
     );
 
-    let hasChange = false;
+    const changes: Promise<void>[] = [];
 
     const newSelected: typeof selected = [];
     const { global, groups } = proxiesData;
@@ -51,13 +52,14 @@ export const useProfiles = () => {
     [global, ...groups].forEach(({ type, name, now }) => {
       if (!now || type !== "Selector") return;
       if (selectedMap[name] != null && selectedMap[name] !== now) {
-        hasChange = true;
-        updateProxy(name, selectedMap[name]);
+        // 走后端的写入队列，和其他的选择不会互相覆盖
+        changes.push(selectProxy(name, selectedMap[name]));
       }
       newSelected.push({ name, now: selectedMap[name] });
     });
 
-    if (hasChange) {
+    if (changes.length > 0) {
+      await Promise.allSettled(changes);
       patchProfile(profileData.current!, { selected: newSelected });
       mutate("getProxies", getProxies());
     }
@@ -5,6 +5,7 @@ export type WsMsgFn = (event: MessageEvent<any>) => void;
 export interface WsOptions {
   errorCount?: number; // default is 5
   retryInterval?: number; // default is 2500
+  binary?: boolean; // receive the binary frames as ArrayBuffer
   onError?: () => void;
 }
 
@@ -31,6 +32,7 @@ export const useWebsocket = (onMessage: WsMsgFn, options?: WsOptions) => {
       disconnect();
 
       const ws = new WebSocket(url);
+      if (options?.binary) ws.binaryType = "arraybuffer";
       wsRef.current = ws;
 
       ws.addEventListener("message", onMessage);
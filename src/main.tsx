@@ -11,8 +11,10 @@ import React from "react";
 import { createRoot } from "react-dom/client";
 import { RecoilRoot } from "recoil";
 import { BrowserRouter } from "react-router-dom";
+import { mutate } from "swr";
 import { BaseErrorBoundary } from "./components/base";
 import Layout from "./pages/_layout";
+import { getAppSnapshot } from "./services/cmds";
 import "./services/i18n";
 
 const mainElementId = "root";
@@ -24,14 +26,25 @@ if (!container) {
   );
 }
 
-createRoot(container).render(
-  <React.StrictMode>
-    <RecoilRoot>
-      <BaseErrorBoundary>
-        <BrowserRouter>
-          <Layout />
-        </BrowserRouter>
-      </BaseErrorBoundary>
-    </RecoilRoot>
-  </React.StrictMode>,
-);
+const render = () =>
+  createRoot(container).render(
+    <React.StrictMode>
+      <RecoilRoot>
+        <BaseErrorBoundary>
+          <BrowserRouter>
+            <Layout />
+          </BrowserRouter>
+        </BaseErrorBoundary>
+      </RecoilRoot>
+    </React.StrictMode>,
+  );
+
+// fill the swr cache from one snapshot, the hooks revalidate later
+getAppSnapshot()
+  .then((snapshot) => {
+    mutate("getVergeConfig", snapshot.verge, false);
+    mutate("getProfiles", snapshot.profiles, false);
+    mutate("getClashInfo", snapshot.clash_info, false);
+  })
+  .catch((err) => console.error("failed to get the app snapshot", err))
+  .finally(render);
@@ -2,11 +2,13 @@ import LogoSvg from "@/assets/image/logo.svg?react";
 import { LayoutControl } from "@/components/layout/layout-control";
 import { LayoutItem } from "@/components/layout/layout-item";
 import { LayoutTraffic } from "@/components/layout/layout-traffic";
+import { StartupProfileDialog } from "@/components/layout/startup-profile-dialog";
 import { UpdateButton } from "@/components/layout/update-button";
 import { useCustomTheme } from "@/components/layout/use-custom-theme";
 import { useNotification } from "@/hooks/use-notification";
 import { useVerge } from "@/hooks/use-verge";
-import { getAxios } from "@/services/api";
+import { getAxios, setApiInspector } from "@/services/api";
+import { takeNotificationPage } from "@/services/cmds";
 import getSystem from "@/utils/get-system";
 import { List, Paper, ThemeProvider, alpha } from "@mui/material";
 import { listen } from "@tauri-apps/api/event";
@@ -19,7 +21,7 @@ import { AnimatePresence } from "framer-motion";
 import i18next from "i18next";
 import React, { useEffect } from "react";
 import { useTranslation } from "react-i18next";
-import { useLocation, useRoutes } from "react-router-dom";
+import { useLocation, useNavigate, useRoutes } from "react-router-dom";
 import { SWRConfig, mutate } from "swr";
 import { routers } from "./_routers";
 
@@ -33,9 +35,10 @@ export default function Layout() {
   const { theme } = useCustomTheme();
 
   const { verge } = useVerge();
-  const { theme_blur, language } = verge || {};
+  const { theme_blur, language, enable_api_inspector } = verge || {};
 
   const location = useLocation();
+  const navigate = useNavigate();
   const routes = useRoutes(routers);
   if (!routes) return null;
 
@@ -59,6 +62,20 @@ export default function Layout() {
     // update the verge config
     listen("verge://refresh-verge-config", () => mutate("getVergeConfig"));
 
+    // the page opened by clicking a notification
+    listen("nyanpasu://navigate", ({ payload }) => navigate(payload as string));
+    listen("nyanpasu://notification", () => mutate("getNotifications"));
+    listen("nyanpasu://event", ({ payload }) => {
+      const event = payload as IAppEvent;
+      if (event.type === "proxy_selected") mutate("getProxies");
+      if (event.type === "profile_changed") mutate("getProfiles");
+    });
+    listen("verge://script-pending", ({ payload }) => {
+      mutate(["getPendingScript", payload]);
+      useNotification(t("Review Script Update"), t("Script Pending Info"));
+    });
+    takeNotificationPage().then((page) => page && navigate(page));
+
     // 设置提示监听
     listen("verge://notice-message", ({ payload }) => {
       const [status, msg] = payload as [string, string];
@@ -75,6 +92,10 @@ export default function Layout() {
     });
   }, []);
 
+  useEffect(() => {
+    setApiInspector(!!enable_api_inspector);
+  }, [enable_api_inspector]);
+
   useEffect(() => {
     if (language) {
       dayjs.locale(language === "zh" ? "zh-cn" : language);
@@ -144,6 +165,8 @@ export default function Layout() {
             <AnimatePresence mode="wait">
               {React.cloneElement(routes, { key: location.pathname })}
             </AnimatePresence>
+
+            <StartupProfileDialog />
           </div>
         </Paper>
       </ThemeProvider>
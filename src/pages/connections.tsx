@@ -1,18 +1,22 @@
-import { BaseEmpty, BasePage } from "@/components/base";
+import { BaseEmpty, BasePage, DialogRef } from "@/components/base";
 import {
   ConnectionDetail,
   ConnectionDetailRef,
 } from "@/components/connection/connection-detail";
 import { ConnectionItem } from "@/components/connection/connection-item";
 import { ConnectionTable } from "@/components/connection/connection-table";
-import { useClashInfo } from "@/hooks/use-clash";
-import { useWebsocket } from "@/hooks/use-websocket";
+import { CountryStats } from "@/components/connection/country-stats";
+import { useBinaryStream } from "@/hooks/use-binary-stream";
+import { useNotification } from "@/hooks/use-notification";
 import { closeAllConnections } from "@/services/api";
+import { exportConnections } from "@/services/cmds";
 import { atomConnectionSetting } from "@/services/states";
 import parseTraffic from "@/utils/parse-traffic";
 import {
   ArrowDownward,
   ArrowUpward,
+  FileDownloadRounded,
+  LanguageRounded,
   Link,
   TableChartRounded,
   TableRowsRounded,
@@ -40,7 +44,6 @@ type OrderFunc = (list: IConnectionsItem[]) => IConnectionsItem[];
 
 export default function ConnectionsPage() {
   const { t, i18n } = useTranslation();
-  const { clashInfo } = useClashInfo();
 
   const [filterText, setFilterText] = useState("");
   const [curOrderOpt, setOrderOpt] = useState("Default");
@@ -74,59 +77,84 @@ export default function ConnectionsPage() {
     return connections;
   }, [connData, filterText, curOrderOpt]);
 
-  const { connect, disconnect } = useWebsocket(
-    (event) => {
-      // meta v1.15.0 出现data.connections为null的情况
-      const data = JSON.parse(event.data) as IConnections;
-      // 尽量与前一次connections的展示顺序保持一致
-      setConnData((old) => {
-        const oldConn = old.connections;
-        const maxLen = data.connections?.length;
-
-        const connections: typeof oldConn = [];
-
-        const rest = data.connections?.filter((each) => {
-          const index = oldConn.findIndex((o) => o.id === each.id);
-
-          if (index >= 0 && index < maxLen) {
-            const old = oldConn[index];
-            each.curUpload = each.upload - old.upload;
-            each.curDownload = each.download - old.download;
-
-            connections[index] = each;
-            return false;
-          }
-          return true;
+  // the snapshot kept by the delta frames, in the order of appearance
+  const snapshotRef = useRef({
+    seq: -1,
+    items: new Map<string, IConnectionsItem>(),
+  });
+
+  const { connect, disconnect } = useBinaryStream<IConnectionsFrame>(
+    "connections",
+    (frame) => {
+      const snapshot = snapshotRef.current;
+
+      if (frame.type === "full") {
+        snapshot.items = new Map(
+          frame.connections.map((each) => [
+            each.id,
+            { ...each, curUpload: 0, curDownload: 0 },
+          ]),
+        );
+      } else {
+        // 重新发送完整快照之前的旧帧
+        if (frame.seq <= snapshot.seq) return;
+
+        frame.removed.forEach((id) => snapshot.items.delete(id));
+
+        const updated = new Map(frame.updated.map((each) => [each.id, each]));
+        snapshot.items.forEach((old, id) => {
+          const each = updated.get(id);
+          snapshot.items.set(id, {
+            ...old,
+            upload: each?.upload ?? old.upload,
+            download: each?.download ?? old.download,
+            curUpload: each ? each.upload - old.upload : 0,
+            curDownload: each ? each.download - old.download : 0,
+          });
         });
 
-        for (let i = 0; i < maxLen; ++i) {
-          if (!connections[i] && rest.length > 0) {
-            connections[i] = rest.shift()!;
-            connections[i].curUpload = 0;
-            connections[i].curDownload = 0;
-          }
-        }
+        frame.added.forEach((each) =>
+          snapshot.items.set(each.id, {
+            ...each,
+            curUpload: 0,
+            curDownload: 0,
+          }),
+        );
+      }
+      snapshot.seq = frame.seq;
 
-        return { ...data, connections };
+      setConnData({
+        uploadTotal: frame.upload_total,
+        downloadTotal: frame.download_total,
+        connections: [...snapshot.items.values()],
       });
     },
     { errorCount: 3, retryInterval: 1000 },
   );
 
   useEffect(() => {
-    if (!clashInfo) return;
-
-    const { server = "", secret = "" } = clashInfo;
-    connect(`ws://${server}/connections?token=${encodeURIComponent(secret)}`);
+    snapshotRef.current.seq = -1;
+    connect();
 
     return () => {
       disconnect();
     };
-  }, [clashInfo]);
+  }, []);
 
   const onCloseAll = useLockFn(closeAllConnections);
 
+  // 按当前的搜索过滤导出
+  const onExport = useLockFn(async () => {
+    try {
+      const path = await exportConnections("csv", { host: filterText });
+      useNotification(t("Exported"), path);
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
   const detailRef = useRef<ConnectionDetailRef>(null!);
+  const countryRef = useRef<DialogRef>(null!);
 
   const connectionItems = [
     {
@@ -170,6 +198,24 @@ export default function ConnectionsPage() {
             )}
           </IconButton>
 
+          <IconButton
+            color="inherit"
+            size="small"
+            title={t("Export")}
+            onClick={onExport}
+          >
+            <FileDownloadRounded fontSize="inherit" />
+          </IconButton>
+
+          <IconButton
+            color="inherit"
+            size="small"
+            title={t("Traffic by Country")}
+            onClick={() => countryRef.current.open()}
+          >
+            <LanguageRounded fontSize="inherit" />
+          </IconButton>
+
           <Button size="small" variant="contained" onClick={onCloseAll}>
             {t("Close All")}
           </Button>
@@ -258,6 +304,7 @@ export default function ConnectionsPage() {
         </Box>
 
         <ConnectionDetail ref={detailRef} />
+        <CountryStats ref={countryRef} />
       </Paper>
     </BasePage>
   );
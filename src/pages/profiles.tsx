@@ -1,10 +1,12 @@
 import { BasePage, DialogRef } from "@/components/base";
+import { DuplicateViewer } from "@/components/profile/duplicate-viewer";
 import { ProfileItem } from "@/components/profile/profile-item";
 import { ProfileMore } from "@/components/profile/profile-more";
 import {
   ProfileViewer,
   ProfileViewerRef,
 } from "@/components/profile/profile-viewer";
+import { PreviewViewer } from "@/components/profile/preview-viewer";
 import { ConfigViewer } from "@/components/setting/mods/config-viewer";
 import { useNotification } from "@/hooks/use-notification";
 import { useProfiles } from "@/hooks/use-profiles";
@@ -16,6 +18,7 @@ import {
   getRuntimeLogs,
   importProfile,
   reorderProfile,
+  undoLastProfileOperation,
   updateProfile,
 } from "@/services/cmds";
 import { atomLoadingCache } from "@/services/states";
@@ -35,9 +38,11 @@ import {
 import {
   ClearRounded,
   ContentCopyRounded,
+  DifferenceOutlined,
   LocalFireDepartmentRounded,
   RefreshRounded,
   TextSnippetOutlined,
+  UndoRounded,
 } from "@mui/icons-material";
 import { LoadingButton } from "@mui/lab";
 import { Box, Button, Grid, IconButton, Stack, TextField } from "@mui/material";
@@ -55,6 +60,9 @@ export default function ProfilePage() {
   const [disabled, setDisabled] = useState(false);
   const [activating, setActivating] = useState("");
   const [loading, setLoading] = useState(false);
+  const [duplicate, setDuplicate] = useState<{ name: string | null } | null>(
+    null,
+  );
   const sensors = useSensors(
     useSensor(PointerSensor),
     useSensor(KeyboardSensor, {
@@ -77,6 +85,7 @@ export default function ProfilePage() {
   const chain = profiles.chain || [];
   const viewerRef = useRef<ProfileViewerRef>(null);
   const configRef = useRef<DialogRef>(null);
+  const previewRef = useRef<DialogRef>(null);
 
   // distinguish type
   const { regularItems, enhanceItems } = useMemo(() => {
@@ -97,12 +106,18 @@ export default function ProfilePage() {
     return { regularItems, enhanceItems };
   }, [profiles]);
 
-  const onImport = async () => {
+  const onImport = async (strategy?: IImportStrategy) => {
     if (!url) return;
+    setDuplicate(null);
     setLoading(true);
 
     try {
-      await importProfile(url);
+      const outcome = await importProfile(url, strategy);
+      // 链接已经存在，让用户选择合并、替换还是另存
+      if (outcome.status === "duplicate") {
+        setDuplicate({ name: outcome.name });
+        return;
+      }
       useNotification(t("Success"), "Successfully import profile.");
       setUrl("");
       setLoading(false);
@@ -165,6 +180,18 @@ export default function ProfilePage() {
     }
   });
 
+  // 撤销上一次删除、排序或导入
+  const onUndo = useLockFn(async () => {
+    try {
+      const entry = await undoLastProfileOperation();
+      mutateProfiles();
+      mutateLogs();
+      useNotification(t("Success"), t(`Undo ${entry.operation}`));
+    } catch (err: any) {
+      useNotification(t("Error"), err.message || err.toString());
+    }
+  });
+
   const onEnable = useLockFn(async (uid: string) => {
     if (chain.includes(uid)) return;
     const newChain = [...chain, uid];
@@ -252,6 +279,15 @@ export default function ProfilePage() {
             <RefreshRounded />
           </IconButton>
 
+          <IconButton
+            size="small"
+            color="inherit"
+            title={t("Undo Last Operation")}
+            onClick={onUndo}
+          >
+            <UndoRounded />
+          </IconButton>
+
           <IconButton
             size="small"
             color="inherit"
@@ -261,6 +297,15 @@ export default function ProfilePage() {
             <TextSnippetOutlined />
           </IconButton>
 
+          <IconButton
+            size="small"
+            color="inherit"
+            title={t("Preview Changes")}
+            onClick={() => previewRef.current?.open()}
+          >
+            <DifferenceOutlined />
+          </IconButton>
+
           <IconButton
             size="small"
             color="primary"
@@ -312,7 +357,7 @@ export default function ProfilePage() {
           loading={loading}
           variant="contained"
           size="small"
-          onClick={onImport}
+          onClick={() => onImport()}
         >
           {t("Import")}
         </LoadingButton>
@@ -377,6 +422,13 @@ export default function ProfilePage() {
 
       <ProfileViewer ref={viewerRef} onChange={() => mutateProfiles()} />
       <ConfigViewer ref={configRef} />
+      <DuplicateViewer
+        open={!!duplicate}
+        name={duplicate?.name}
+        onSelect={(strategy) => onImport(strategy)}
+        onClose={() => setDuplicate(null)}
+      />
+      <PreviewViewer ref={previewRef} onApply={onEnhance} />
     </BasePage>
   );
 }
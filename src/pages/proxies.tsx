@@ -1,6 +1,7 @@
 import { BasePage } from "@/components/base";
 import { ProviderButton } from "@/components/proxy/provider-button";
 import { ProxyGroups } from "@/components/proxy/proxy-groups";
+import { QuickSelectButton } from "@/components/proxy/quick-select-button";
 import { useVerge } from "@/hooks/use-verge";
 import {
   closeAllConnections,
@@ -60,6 +61,7 @@ export default function ProxyPage() {
       header={
         <Box display="flex" alignItems="center" gap={1}>
           <ProviderButton />
+          <QuickSelectButton />
 
           <ButtonGroup size="small">
             {modeList.map((mode) => (
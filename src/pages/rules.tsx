@@ -1,8 +1,13 @@
 import { BaseEmpty, BasePage } from "@/components/base";
 import RuleItem from "@/components/rule/rule-item";
+import {
+  RuleSetViewer,
+  RuleSetViewerRef,
+} from "@/components/rule/rule-set-viewer";
 import { getRules } from "@/services/api";
-import { Box, Paper, TextField } from "@mui/material";
-import { useMemo, useState } from "react";
+import { getRuleStats } from "@/services/cmds";
+import { Box, Button, Paper, TextField } from "@mui/material";
+import { useMemo, useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
 import { Virtuoso } from "react-virtuoso";
 import useSWR from "swr";
@@ -10,13 +15,35 @@ import useSWR from "swr";
 export default function RulesPage() {
   const { t } = useTranslation();
   const { data = [] } = useSWR("getRules", getRules);
+  // 后台计算完之前轮询得快一些
+  const { data: stats } = useSWR("getRuleStats", getRuleStats, {
+    refreshInterval: (stats) => (!stats || stats.pending ? 1000 : 10000),
+  });
 
   const [filterText, setFilterText] = useState("");
+  const viewerRef = useRef<RuleSetViewerRef>(null);
 
   const rules = useMemo(() => {
     return data.filter((each) => each.payload.includes(filterText));
   }, [data, filterText]);
 
+  const countOf = (item: IRuleItem) => {
+    if (item.type === "RuleSet") {
+      return stats?.providers.find((p) => p.name === item.payload)?.count;
+    } else if (item.type === "GeoSite") {
+      return stats?.geosite[item.payload.toLowerCase()];
+    }
+  };
+
+  // the rule sets and the geosite can be browsed
+  const onBrowse = (item: IRuleItem) => {
+    if (item.type === "RuleSet") {
+      viewerRef.current?.open({ type: "provider", name: item.payload });
+    } else if (item.type === "GeoSite") {
+      viewerRef.current?.open({ type: "geosite", name: item.payload });
+    }
+  };
+
   return (
     <BasePage title={t("Rules")} contentStyle={{ height: "100%" }}>
       <Paper sx={{ boxSizing: "border-box", boxShadow: 2, height: "100%" }}>
@@ -42,6 +69,14 @@ export default function RulesPage() {
             onChange={(e) => setFilterText(e.target.value)}
             sx={{ input: { py: 0.65, px: 1.25 } }}
           />
+          <Button
+            size="small"
+            variant="outlined"
+            sx={{ ml: 1, flex: "none" }}
+            onClick={() => viewerRef.current?.open()}
+          >
+            {t("Rule Sets")}
+          </Button>
         </Box>
 
         <Box height="calc(100% - 50px)">
@@ -49,7 +84,16 @@ export default function RulesPage() {
             <Virtuoso
               data={rules}
               itemContent={(index, item) => (
-                <RuleItem index={index + 1} value={item} />
+                <RuleItem
+                  index={index + 1}
+                  value={item}
+                  count={countOf(item)}
+                  onClick={
+                    ["RuleSet", "GeoSite"].includes(item.type)
+                      ? () => onBrowse(item)
+                      : undefined
+                  }
+                />
               )}
               followOutput={"smooth"}
             />
@@ -58,6 +102,8 @@ export default function RulesPage() {
           )}
         </Box>
       </Paper>
+
+      <RuleSetViewer ref={viewerRef} />
     </BasePage>
   );
 }
@@ -1,7 +1,28 @@
 import axios, { AxiosInstance } from "axios";
-import { getClashInfo } from "./cmds";
+import { getClashInfo, recordApiRequest } from "./cmds";
 
 let axiosIns: AxiosInstance = null!;
+let inspecting = false;
+
+/// report the requests to the inspector of the backend
+export const setApiInspector = (enable: boolean) => {
+  inspecting = enable;
+};
+
+const startTimes = new WeakMap<object, number>();
+
+const report = (config: any, status?: number, error?: string) => {
+  const start = config && startTimes.get(config);
+  if (!inspecting || start == null) return;
+  recordApiRequest({
+    source: "webview",
+    method: (config.method ?? "get").toUpperCase(),
+    path: (config.url ?? "").split("?")[0],
+    duration_ms: Math.round(performance.now() - start),
+    status: status ?? null,
+    error: error ?? null,
+  }).catch(() => {});
+};
 
 /// initialize some information
 /// enable force update axiosIns
@@ -29,7 +50,20 @@ export const getAxios = async (force: boolean = false) => {
     headers: secret ? { Authorization: `Bearer ${secret}` } : {},
     timeout: 15000,
   });
-  axiosIns.interceptors.response.use((r) => r.data);
+  axiosIns.interceptors.request.use((config) => {
+    if (inspecting) startTimes.set(config, performance.now());
+    return config;
+  });
+  axiosIns.interceptors.response.use(
+    (r) => {
+      report(r.config, r.status);
+      return r.data;
+    },
+    (err) => {
+      report(err.config, err.response?.status, err.message);
+      return Promise.reject(err);
+    },
+  );
   return axiosIns;
 };
 
@@ -116,7 +150,7 @@ export const getProxies = async () => {
 
   if (global?.all) {
     groups = global.all
-      .filter((name) => proxyRecord[name]?.all)
+      .filter((name) => proxyRecord[name]?.all && !proxyRecord[name].hidden)
       .map((name) => proxyRecord[name])
       .map((each) => ({
         ...each,
@@ -124,7 +158,7 @@ export const getProxies = async () => {
       }));
   } else {
     groups = Object.values(proxyRecord)
-      .filter((each) => each.name !== "GLOBAL" && each.all)
+      .filter((each) => each.name !== "GLOBAL" && each.all && !each.hidden)
       .map((each) => ({
         ...each,
         all: each.all!.map((item) => generateItem(item)),
@@ -140,7 +174,10 @@ export const getProxies = async () => {
 
   const _global: IProxyGroupItem = {
     ...global,
-    all: global?.all?.map((item) => generateItem(item)) || [],
+    all:
+      global?.all
+        ?.filter((item) => !proxyRecord[item]?.hidden)
+        .map((item) => generateItem(item)) || [],
   };
 
   return { global: _global, direct, groups, records: proxyRecord, proxies };
@@ -34,6 +34,10 @@ export async function enhanceProfiles() {
   return invoke<void>("enhance_profiles");
 }
 
+export async function previewConfigChanges() {
+  return invoke<IConfigPreview>("preview_config_changes");
+}
+
 export async function patchProfilesConfig(profiles: IProfilesConfig) {
   return invoke<void>("patch_profiles_config", { profiles });
 }
@@ -57,10 +61,31 @@ export async function saveProfileFile(index: string, fileData: string) {
   return invoke<void>("save_profile_file", { index, fileData });
 }
 
-export async function importProfile(url: string) {
-  return invoke<void>("import_profile", {
+export async function formatYaml(content: string) {
+  return invoke<IFormatResult>("format_yaml", { content });
+}
+
+export async function lintProfileFile(index: string, content: string) {
+  return invoke<IDiagnostic[]>("lint_profile_file", { index, content });
+}
+
+export async function applyProfileFile(index: string, content: string) {
+  return invoke<IApplyResult>("apply_profile_file", { index, content });
+}
+
+export async function testScriptChain(chainId: string, sampleProfile: string) {
+  return invoke<IChainTestResult>("test_script_chain", {
+    chainId,
+    sampleProfile,
+  });
+}
+
+// a duplicate url is not imported without the strategy
+export async function importProfile(url: string, strategy?: IImportStrategy) {
+  return invoke<IImportOutcome>("import_profile", {
     url,
     option: { with_proxy: true },
+    strategy,
   });
 }
 
@@ -71,6 +96,26 @@ export async function reorderProfile(activeId: string, overId: string) {
   });
 }
 
+export async function getProfileHistory(index: string) {
+  return invoke<IProfileUpdateRecord[]>("get_profile_history", { index });
+}
+
+export async function getPendingScript(uid: string) {
+  return invoke<IPendingScript | null>("get_pending_script", { uid });
+}
+
+export async function approveRemoteScript(uid: string, hash: string) {
+  return invoke<void>("approve_remote_script", { uid, hash });
+}
+
+export async function rejectRemoteScript(uid: string) {
+  return invoke<void>("reject_remote_script", { uid });
+}
+
+export async function undoLastProfileOperation() {
+  return invoke<IProfileTrashEntry>("undo_last_profile_operation");
+}
+
 export async function updateProfile(index: string, option?: IProfileOption) {
   return invoke<void>("update_profile", { index, option });
 }
@@ -79,6 +124,13 @@ export async function deleteProfile(index: string) {
   return invoke<void>("delete_profile", { index });
 }
 
+export async function setProfileCore(
+  uid: string,
+  core: IVergeConfig["clash_core"] | null,
+) {
+  return invoke<void>("set_profile_core", { uid, core });
+}
+
 export async function patchProfile(
   index: string,
   profile: Partial<IProfileItem>,
@@ -86,10 +138,26 @@ export async function patchProfile(
   return invoke<void>("patch_profile", { index, profile });
 }
 
+export async function getAppSnapshot() {
+  return invoke<IAppSnapshot>("get_app_snapshot");
+}
+
 export async function getClashInfo() {
   return invoke<IClashInfo | null>("get_clash_info");
 }
 
+export async function queryApiRequests(query: IApiRequestQuery) {
+  return invoke<IApiRequestRecord[]>("query_api_requests", { query });
+}
+
+export async function clearApiRequests() {
+  return invoke<void>("clear_api_requests");
+}
+
+export async function recordApiRequest(record: IApiRequestRecord) {
+  return invoke<void>("record_api_request", { record });
+}
+
 export async function getRuntimeConfig() {
   return invoke<any | null>("get_runtime_config");
 }
@@ -181,18 +249,55 @@ export async function uninstallService() {
   return invoke<void>("uninstall_service");
 }
 
+/// systemd units on linux
+
+export async function generateSystemdUnit(options: ISystemdUnitOptions) {
+  return invoke<string>("generate_systemd_unit", { options });
+}
+
+export async function installSystemdUnit(options: ISystemdUnitOptions) {
+  return invoke<void>("install_systemd_unit", { options });
+}
+
+export async function uninstallSystemdUnit(options: ISystemdUnitOptions) {
+  return invoke<void>("uninstall_systemd_unit", { options });
+}
+
+export async function setSystemdUnitEnabled(
+  options: ISystemdUnitOptions,
+  enable: boolean,
+) {
+  return invoke<void>("set_systemd_unit_enabled", { options, enable });
+}
+
+export async function getSystemdUnitStatus(options: ISystemdUnitOptions) {
+  return invoke<ISystemdUnitStatus>("get_systemd_unit_status", { options });
+}
+
 export async function invoke_uwp_tool() {
   return invoke<void>("invoke_uwp_tool").catch((err) =>
     useNotification("Error", err?.message || err.toString()),
   );
 }
 
+export async function getUwpApps() {
+  return invoke<IUwpApp[]>("get_uwp_apps");
+}
+
+export async function setUwpLoopback(sids: string[], exempt: boolean) {
+  return invoke<void>("set_uwp_loopback", { sids, exempt });
+}
+
 export async function save_window_size_state() {
   return invoke<void>("save_window_size_state").catch((err) =>
     useNotification("Error", err?.message || err.toString()),
   );
 }
 
+export async function resetWindowState() {
+  return invoke<void>("reset_window_state");
+}
+
 export async function getCoreVersion(
   coreType: Required<IVergeConfig>["clash_core"],
 ) {
@@ -209,11 +314,572 @@ export async function updateCore(
   return invoke<void>("update_core", { coreType });
 }
 
+export async function installCoreFromFile(
+  path: string,
+  coreType: Required<IVergeConfig>["clash_core"],
+) {
+  return invoke<string>("install_core_from_file", { path, coreType });
+}
+
+export async function selectCoreFile() {
+  return invoke<string | null>("select_core_file");
+}
+
 export async function collectLogs() {
   return invoke<void>("collect_logs");
 }
 
 export async function cmdGetProxyDelay(name: string, url?: string) {
-  name = encodeURIComponent(name);
   return invoke<{ delay: number }>("clash_api_get_proxy_delay", { name, url });
 }
+
+export async function selectProxy(group: string, name: string) {
+  return invoke<void>("select_proxy", { group, name });
+}
+
+export async function selectFastestAll(testUrl?: string) {
+  return invoke<IBulkSelected[]>("select_fastest_all", { testUrl });
+}
+
+export async function pinGroupsToRegion(region: string) {
+  return invoke<IBulkSelected[]>("pin_groups_to_region", { region });
+}
+
+export async function resetGroupSelections() {
+  return invoke<IBulkSelected[]>("reset_group_selections");
+}
+
+export async function getNodeInfo() {
+  return invoke<INodeInfo[]>("get_node_info");
+}
+
+export async function enrichNodes(names?: string[], exit?: boolean) {
+  return invoke<INodeInfo[]>("enrich_nodes", { names, exit });
+}
+
+export async function getCoreCache() {
+  return invoke<ICoreCacheInfo>("get_core_cache");
+}
+
+export async function flushFakeipCache() {
+  return invoke<void>("flush_fakeip_cache");
+}
+
+export async function clearCoreCache() {
+  return invoke<void>("clear_core_cache");
+}
+
+export async function getMaintenanceReport() {
+  return invoke<IMaintenanceReport | null>("get_maintenance_report");
+}
+
+export async function runMaintenance() {
+  return invoke<IMaintenanceReport>("run_maintenance");
+}
+
+export async function getArtifactCache() {
+  return invoke<IArtifactCacheInfo>("get_artifact_cache");
+}
+
+export async function purgeArtifactCache() {
+  return invoke<number>("purge_artifact_cache");
+}
+
+export async function getWebUis() {
+  return invoke<IWebUiInfo[]>("get_web_uis");
+}
+
+export async function installWebUi(id: string) {
+  return invoke<void>("install_web_ui", { id });
+}
+
+export async function removeWebUi(id: string) {
+  return invoke<void>("remove_web_ui", { id });
+}
+
+export async function openWebUi(id?: string) {
+  return invoke<void>("open_web_ui", { id });
+}
+
+export async function getFailoverStatus() {
+  return invoke<IFailoverStatus>("get_failover_status");
+}
+
+export async function getCountryStats(days: number) {
+  return invoke<ICountryStat[]>("get_country_stats", { days });
+}
+
+export async function clearCountryStats() {
+  return invoke<void>("clear_country_stats");
+}
+
+export async function getOnboardingState() {
+  return invoke<IOnboardingState>("get_onboarding_state");
+}
+
+export async function onboardingDetectEnv() {
+  return invoke<IOnboardingEnv>("onboarding_detect_env");
+}
+
+export async function onboardingProbeMirrors() {
+  return invoke<IMirrorProbe[]>("onboarding_probe_mirrors");
+}
+
+export async function onboardingDetectClients() {
+  return invoke<IDetectedClient[]>("onboarding_detect_clients");
+}
+
+export async function onboardingSetLanguage(language: string) {
+  return invoke<void>("onboarding_set_language", { language });
+}
+
+export async function onboardingSetMirror(mirror: string) {
+  return invoke<void>("onboarding_set_mirror", { mirror });
+}
+
+export async function onboardingSetCore(core: IVergeConfig["clash_core"]) {
+  return invoke<string>("onboarding_set_core", { core });
+}
+
+export async function onboardingImportClient(client: IKnownClient) {
+  return invoke<IImportReport>("onboarding_import_client", { client });
+}
+
+export async function onboardingApply() {
+  return invoke<void>("onboarding_apply");
+}
+
+export async function onboardingFinish() {
+  return invoke<void>("onboarding_finish");
+}
+
+export async function previewClientImport(client: IKnownClient) {
+  return invoke<IImportReport>("preview_client_import", { client });
+}
+
+export async function importClientData(client: IKnownClient) {
+  return invoke<IImportReport>("import_client_data", { client });
+}
+
+export async function exportFlattenedConfig(target: IFlattenTarget) {
+  return invoke<IFlattenResult>("export_flattened_config", { target });
+}
+
+export async function queryClashLogs(filter?: IClashLogFilter) {
+  return invoke<IClashLogItem[]>("query_clash_logs", { filter });
+}
+
+export async function subscribeClashLogs(filter: IClashLogFilter) {
+  return invoke<void>("subscribe_clash_logs", { filter });
+}
+
+export async function unsubscribeClashLogs() {
+  return invoke<void>("unsubscribe_clash_logs");
+}
+
+export async function getStreamStates() {
+  return invoke<Record<string, IStreamState>>("get_stream_states");
+}
+
+export async function reconnectStreams() {
+  return invoke<void>("reconnect_streams");
+}
+
+export async function getClashMemoryHistory() {
+  return invoke<IClashMemorySample[]>("get_clash_memory_history");
+}
+
+export async function parseBypassList(text: string) {
+  return invoke<IBypassRule[]>("parse_bypass_list", { text });
+}
+
+export async function getDefaultBypassList() {
+  return invoke<IBypassRule[]>("get_default_bypass_list");
+}
+
+export async function getTunStatus() {
+  return invoke<ITunStatus>("get_tun_status");
+}
+
+export async function grantTunPrivileges() {
+  return invoke<void>("grant_tun_privileges");
+}
+
+export async function getLanAccessInfo() {
+  return invoke<ILanAccessInfo>("get_lan_access_info");
+}
+
+export async function getNetworkState() {
+  return invoke<INetworkState>("get_network_state");
+}
+
+export async function getBootReport() {
+  return invoke<IBootReport>("get_boot_report");
+}
+
+export async function resetControlApiToken() {
+  return invoke<string>("reset_control_api_token");
+}
+
+export async function resetControllerProxyToken() {
+  return invoke<string>("reset_controller_proxy_token");
+}
+
+export async function getExternalMutations() {
+  return invoke<IExternalMutation[]>("get_external_mutations");
+}
+
+export async function clearExternalMutations() {
+  return invoke<void>("clear_external_mutations");
+}
+
+export async function checkHotkeys(hotkeys: string[]) {
+  return invoke<IHotkeyIssue[]>("check_hotkeys", { hotkeys });
+}
+
+export async function getDesktopSession() {
+  return invoke<IDesktopSession | null>("get_desktop_session");
+}
+
+export async function getProxyEnv(shell: IShellType) {
+  return invoke<string>("get_proxy_env", { shell });
+}
+
+export async function copyProxyEnv(shell: IShellType) {
+  return invoke<void>("copy_proxy_env", { shell });
+}
+
+export async function getNotifications() {
+  return invoke<INotificationItem[]>("get_notifications");
+}
+
+export async function clearNotifications() {
+  return invoke<void>("clear_notifications");
+}
+
+export async function runNotificationAction(id: number) {
+  return invoke<void>("run_notification_action", { id });
+}
+
+export async function takeNotificationPage() {
+  return invoke<string | null>("take_notification_page");
+}
+
+export async function setWebdavConfig(
+  url: string,
+  username: string,
+  password?: string,
+) {
+  return invoke<void>("set_webdav_config", { url, username, password });
+}
+
+export async function saveEventHook(hook: IEventHook, password?: string) {
+  return invoke<void>("save_event_hook", { hook, password });
+}
+
+export async function saveTrayMenu(entries: ITrayMenuEntry[]) {
+  return invoke<void>("save_tray_menu", { entries });
+}
+
+export async function testEventHook() {
+  return invoke<void>("test_event_hook");
+}
+
+export async function getRemoteManagementInfo() {
+  return invoke<IRemoteManagementInfo>("get_remote_management_info");
+}
+
+export async function setRemoteManagementPassword(password: string) {
+  return invoke<void>("set_remote_management_password", { password });
+}
+
+export async function webdavBackup() {
+  return invoke<string>("webdav_backup");
+}
+
+export async function webdavListBackups() {
+  return invoke<IWebDavBackup[]>("webdav_list_backups");
+}
+
+export async function webdavPreviewRestore(name: string) {
+  return invoke<IRestorePreview>("webdav_preview_restore", { name });
+}
+
+export async function webdavRestore(name: string) {
+  return invoke<void>("webdav_restore", { name });
+}
+
+export async function setSyncS3Config(config: ISyncS3Config, secret?: string) {
+  return invoke<void>("set_sync_s3_config", { config, secret });
+}
+
+export async function syncNow() {
+  return invoke<ISyncReport>("sync_now");
+}
+
+export async function getSyncConflicts() {
+  return invoke<ISyncConflict[]>("get_sync_conflicts");
+}
+
+export async function resolveSyncConflict(
+  path: string,
+  pick: "local" | "remote",
+) {
+  return invoke<ISyncReport>("resolve_sync_conflict", { path, pick });
+}
+
+export async function setLogLevel(level: string, filter?: string) {
+  return invoke<void>("set_log_level", { level, filter });
+}
+
+export async function generateDiagnosticBundle() {
+  return invoke<string>("generate_diagnostic_bundle");
+}
+
+export async function runConnectivityDoctor() {
+  return invoke<IDoctorCheck[]>("run_connectivity_doctor");
+}
+
+export async function repairInstallation() {
+  return invoke<IRepairStep[]>("repair_installation");
+}
+
+export async function getCrashReports() {
+  return invoke<ICrashReport[]>("get_crash_reports");
+}
+
+export async function uploadCrashReport(id: string) {
+  return invoke<void>("upload_crash_report", { id });
+}
+
+export async function clearCrashReports() {
+  return invoke<void>("clear_crash_reports");
+}
+
+export async function checkAppUpdate() {
+  return invoke<IAppUpdateInfo | null>("check_app_update");
+}
+
+export async function installAppUpdate() {
+  return invoke<void>("install_app_update");
+}
+
+export async function stageAppUpdate() {
+  return invoke<string>("stage_app_update");
+}
+
+export async function skipAppUpdate(version: string) {
+  return invoke<void>("skip_app_update", { version });
+}
+
+export async function getStreamServer() {
+  return invoke<[number, string]>("get_stream_server");
+}
+
+export async function listProxies(
+  page: IPageQuery,
+  filter?: IProxyFilter,
+  sort?: ISortBy<"name" | "type" | "delay">,
+) {
+  return invoke<IPage<IProxyRow>>("list_proxies", { page, filter, sort });
+}
+
+export async function listConnections(
+  page: IPageQuery,
+  filter?: IConnectionFilter,
+  sort?: ISortBy<"start" | "upload" | "download" | "host" | "process">,
+) {
+  return invoke<
+    IPage<IConnectionsItem> & { upload_total: number; download_total: number }
+  >("list_connections", { page, filter, sort });
+}
+
+export async function getDnsOverride(uid?: string) {
+  return invoke<IDnsOverride | null>("get_dns_override", { uid });
+}
+
+export async function setDnsOverride(uid?: string, dns?: IDnsOverride) {
+  return invoke<void>("set_dns_override", { uid, dns });
+}
+
+export async function readSystemHosts() {
+  return invoke<IHostsEntry[]>("read_system_hosts");
+}
+
+export async function getUserRules() {
+  return invoke<IUserRule[]>("get_user_rules");
+}
+
+export async function addUserRule(rule: IUserRule) {
+  return invoke<string>("add_user_rule", { rule });
+}
+
+export async function updateUserRule(id: string, rule: IUserRule) {
+  return invoke<void>("update_user_rule", { id, rule });
+}
+
+export async function deleteUserRule(id: string) {
+  return invoke<void>("delete_user_rule", { id });
+}
+
+export async function reorderUserRules(ids: string[]) {
+  return invoke<void>("reorder_user_rules", { ids });
+}
+
+export async function browseRuleSet(
+  source: IRuleSetSource,
+  filter: { keyword?: string; target?: string },
+  page: IPageQuery,
+) {
+  return invoke<
+    IPage<IRuleSetEntry> & { behavior: string; matched: IRuleSetEntry[] | null }
+  >("browse_rule_set", { source, filter, page });
+}
+
+export async function getRuleStats() {
+  return invoke<IRuleStats | null>("get_rule_stats");
+}
+
+export async function getChainCandidates() {
+  return invoke<IChainCandidate[]>("get_chain_candidates");
+}
+
+export async function validateProxyChain(chain: IProxyChain) {
+  return invoke<void>("validate_proxy_chain", { chain });
+}
+
+export async function saveProxyChains(chains: IProxyChain[]) {
+  return invoke<void>("save_proxy_chains", { chains });
+}
+
+export async function getTunnels() {
+  return invoke<ITunnel[]>("get_tunnels");
+}
+
+export async function addTunnel(tunnel: ITunnel) {
+  return invoke<string>("add_tunnel", { tunnel });
+}
+
+export async function updateTunnel(id: string, tunnel: ITunnel) {
+  return invoke<void>("update_tunnel", { id, tunnel });
+}
+
+export async function deleteTunnel(id: string) {
+  return invoke<void>("delete_tunnel", { id });
+}
+
+export async function checkTunnels() {
+  return invoke<ITunnelStatus[]>("check_tunnels");
+}
+
+export async function getListeners() {
+  return invoke<IInboundListener[]>("get_listeners");
+}
+
+export async function addListener(listener: IInboundListener) {
+  return invoke<string>("add_listener", { listener });
+}
+
+export async function updateListener(id: string, listener: IInboundListener) {
+  return invoke<void>("update_listener", { id, listener });
+}
+
+export async function toggleListener(id: string, enable: boolean) {
+  return invoke<void>("toggle_listener", { id, enable });
+}
+
+export async function deleteListener(id: string) {
+  return invoke<void>("delete_listener", { id });
+}
+
+export async function setInboundAuth(
+  auth: IInboundAuth,
+  passwords: Record<string, string>,
+) {
+  return invoke<void>("set_inbound_auth", { auth, passwords });
+}
+
+export async function getCoreCapabilities(core?: IVergeConfig["clash_core"]) {
+  return invoke<ICoreCapabilities>("get_core_capabilities", { core });
+}
+
+export async function checkCoreCompatibility(
+  core: Required<IVergeConfig>["clash_core"],
+) {
+  return invoke<ICapabilityWarning[]>("check_core_compatibility", { core });
+}
+
+export async function takeStartupProfilePrompt() {
+  return invoke<boolean>("take_startup_profile_prompt");
+}
+
+export async function savePauseWindows(windows: IPauseWindow[]) {
+  return invoke<void>("save_pause_windows", { windows });
+}
+
+export async function getProxyPause() {
+  return invoke<IPauseState | null>("get_proxy_pause");
+}
+
+export async function togglePause() {
+  return invoke<boolean>("toggle_pause");
+}
+
+export async function getAppRules() {
+  return invoke<IAppRule[]>("get_app_rules");
+}
+
+export async function addAppRule(rule: IAppRule) {
+  return invoke<string>("add_app_rule", { rule });
+}
+
+export async function updateAppRule(id: string, rule: IAppRule) {
+  return invoke<void>("update_app_rule", { id, rule });
+}
+
+export async function deleteAppRule(id: string) {
+  return invoke<void>("delete_app_rule", { id });
+}
+
+export async function getThrottleRules() {
+  return invoke<IThrottleRule[]>("get_throttle_rules");
+}
+
+export async function addThrottleRule(rule: IThrottleRule) {
+  return invoke<string>("add_throttle_rule", { rule });
+}
+
+export async function updateThrottleRule(id: string, rule: IThrottleRule) {
+  return invoke<void>("update_throttle_rule", { id, rule });
+}
+
+export async function deleteThrottleRule(id: string) {
+  return invoke<void>("delete_throttle_rule", { id });
+}
+
+export async function listProcesses() {
+  return invoke<IProcessInfo[]>("list_processes");
+}
+
+export async function testUrlMatrix(
+  urls: string[],
+  nodes: string[],
+  timeout?: number,
+) {
+  return invoke<IUrlTestMatrix>("test_url_matrix", { urls, nodes, timeout });
+}
+
+export async function exportConnections(
+  format: IExportFormat,
+  filter?: IConnectionFilter,
+) {
+  return invoke<string>("export_connections", { format, filter });
+}
+
+// unix timestamps in seconds
+export async function exportTrafficHistory(
+  range: { from: number; to: number },
+  format: IExportFormat,
+) {
+  return invoke<string>("export_traffic_history", { range, format });
+}
@@ -52,6 +52,8 @@ interface IProxyItem {
   all?: string[];
   now?: string;
   provider?: string; // 记录是否来自provider
+  hidden?: boolean;
+  info?: INodeInfo; // 节点的国家和 asn
 }
 
 type IProxyGroupItem = Omit<IProxyItem, "all"> & {
@@ -77,6 +79,817 @@ interface ILogItem {
   payload: string;
 }
 
+type IClashLogLevel = "debug" | "info" | "warning" | "error";
+
+interface IClashLogItem {
+  time: number;
+  level: IClashLogLevel;
+  payload: string;
+}
+
+interface IClashLogFilter {
+  level?: IClashLogLevel;
+  contains?: string;
+  since?: number;
+  limit?: number;
+}
+
+interface IBootReport {
+  started_at: number;
+  total_ms: number;
+  finished: boolean;
+  stages: {
+    name: string;
+    duration_ms: number;
+    error?: string;
+  }[];
+}
+
+type IShellType = "sh" | "fish" | "powershell" | "cmd";
+
+type INotificationAction =
+  | { type: "open_page"; payload: string }
+  | { type: "run_update" }
+  | { type: "restart_core" }
+  | { type: "update_profile"; payload: string };
+
+interface INotificationItem {
+  id: number;
+  kind:
+    | "core_crashed"
+    | "update_available"
+    | "subscription_expiring"
+    | "quota_alert"
+    | "command_failed"
+    | "profile_updated";
+  title: string;
+  body: string;
+  action?: INotificationAction;
+  time: number;
+}
+
+type IHotkeyIssue =
+  | { type: "conflict"; key: string; funcs: string[] }
+  | { type: "invalid_key"; key: string; func: string }
+  | { type: "unknown_function"; func: string };
+
+// only on linux, see `core/linux_status.rs`
+interface IDesktopSession {
+  session: "x11" | "wayland" | "unknown";
+  tray: "app_indicator" | "legacy" | "unavailable";
+  global_shortcuts: "native" | "portal";
+  shortcuts_error: string | null;
+  window_position: boolean;
+}
+
+// only on windows, see `core/win_uwp.rs`
+interface IUwpApp {
+  sid: string;
+  name: string;
+  display_name: string;
+  package: string;
+  exempted: boolean;
+}
+
+// only on linux, see `core/systemd.rs`
+interface ISystemdUnitOptions {
+  scope: "user" | "system";
+  target: "core" | "service_only";
+}
+
+interface ISystemdUnitStatus {
+  name: string;
+  path: string;
+  installed: boolean;
+  file_state: string | null;
+  active_state: string;
+  sub_state: string;
+  main_pid: number | null;
+}
+
+interface INetworkState {
+  network?: string;
+  ssid?: string;
+  interfaces: string[];
+  connectivity: "unknown" | "online" | "captive_portal" | "offline";
+}
+
+// the payload of `nyanpasu://event`, see `core/event_bus.rs`
+type IAppEvent =
+  | {
+      type: "core_state";
+      data: { state: "started" | "recovered" | "recover_failed" };
+    }
+  | {
+      type: "profile_changed";
+      data: { uid: string; change: "updated" | "switched" };
+    }
+  | { type: "proxy_selected"; data: { group: string; name: string } }
+  | {
+      type: "network_changed";
+      data: { previous: INetworkState; current: INetworkState };
+    };
+
+type INetworkAction =
+  | { type: "reapply_system_proxy" | "restart_core" | "disable_proxy" }
+  | { type: "switch_profile"; uid: string };
+
+interface INetworkRule {
+  ssid?: string;
+  action: INetworkAction;
+}
+
+interface IWebDavBackup {
+  name: string;
+  size?: number;
+  modified?: string;
+}
+
+interface IRestorePreview {
+  name: string;
+  manifest: {
+    version: number;
+    app_version: string;
+    hostname?: string;
+    created_at: number;
+    files: string[];
+  };
+  changes: {
+    path: string;
+    kind: "added" | "modified" | "removed" | "unchanged";
+  }[];
+}
+
+interface ISyncS3Config {
+  endpoint: string;
+  bucket: string;
+  region?: string;
+  access_key_id: string;
+}
+
+interface ISyncConflict {
+  path: string;
+  local_modified?: number;
+  remote_modified: number;
+  remote_device: string;
+  remote_deleted: boolean;
+}
+
+interface ISyncReport {
+  pushed: string[];
+  pulled: string[];
+  conflicts: ISyncConflict[];
+}
+
+interface IDoctorCheck {
+  id: string;
+  status: "pass" | "fail" | "skip";
+  detail: string;
+  hint?: string;
+}
+
+interface IRepairStep {
+  id: string;
+  ok: boolean;
+  detail: string;
+}
+
+interface ICrashCoreExit {
+  core: string;
+  pid: number;
+  code?: number;
+  signal?: number;
+  time: number;
+  uptime?: number;
+}
+
+interface ICrashReport {
+  id: string;
+  kind: "app_panic" | "core_exit" | "native_crash";
+  time: number;
+  app_version: string;
+  os: string;
+  arch: string;
+  thread?: string;
+  message: string;
+  location?: string;
+  backtrace?: string;
+  state: {
+    app_uptime: number;
+    core?: string;
+    core_pid?: number;
+    core_uptime?: number;
+    recent_core_exits: ICrashCoreExit[];
+  };
+  logs: string[];
+  minidump?: string;
+  uploaded: boolean;
+}
+
+interface IAppUpdateInfo {
+  version: string;
+  current_version: string;
+  channel: "stable" | "beta" | "nightly";
+  body?: string;
+  date?: string;
+  staged: boolean;
+}
+
+interface IPageQuery {
+  page: number; // starts from 0
+  page_size?: number; // default 100, max 500
+}
+
+interface IPage<T> {
+  items: T[];
+  total: number;
+  page: number;
+  page_size: number;
+}
+
+interface ISortBy<K extends string> {
+  key: K;
+  desc?: boolean;
+}
+
+interface IProxyRow {
+  name: string;
+  type: string;
+  udp: boolean;
+  now?: string;
+  size?: number;
+  delay?: number;
+}
+
+interface IProxyFilter {
+  keyword?: string;
+  group?: string;
+  type?: string;
+  udp?: boolean;
+  alive?: boolean;
+}
+
+interface IConnectionFilter {
+  keyword?: string;
+  network?: string;
+  process?: string;
+  rule?: string;
+}
+
+interface IDnsOverride {
+  enable?: boolean;
+  ipv6?: boolean;
+  "enhanced-mode"?: string;
+  nameserver?: string[];
+  "default-nameserver"?: string[];
+  fallback?: string[];
+  "fake-ip-filter"?: string[];
+  hosts?: Record<string, string>;
+}
+
+interface IHostsEntry {
+  domain: string;
+  ip: string;
+  enable: boolean;
+}
+
+type IUserRulePosition =
+  | { type: "prepend" }
+  | { type: "append" }
+  | { type: "before"; index: number };
+
+interface IUserRule {
+  id: string;
+  type: string;
+  payload: string;
+  target: string;
+  no_resolve: boolean;
+  position: IUserRulePosition;
+  enable: boolean;
+}
+
+interface IProxyChain {
+  name: string;
+  proxies: string[];
+  mode: "relay" | "dialer_proxy";
+  attach_to?: string;
+  enable: boolean;
+}
+
+interface IChainCandidate {
+  name: string;
+  type: string;
+  is_group: boolean;
+  udp: boolean;
+}
+
+interface ITunnel {
+  id: string;
+  network: ("tcp" | "udp")[];
+  address: string;
+  target: string;
+  proxy?: string;
+  enable: boolean;
+}
+
+interface ITunnelStatus {
+  id: string;
+  state: "listening" | "unreachable" | "inactive" | "unknown";
+  message?: string;
+}
+
+interface IInboundListener {
+  id: string;
+  name: string;
+  type: "socks" | "http" | "mixed" | "redir" | "tproxy";
+  port: number;
+  listen?: string;
+  udp: boolean;
+  users: { username: string; password: string }[];
+  proxy?: string;
+  enable: boolean;
+}
+
+interface IInboundAuth {
+  enable: boolean;
+  users: string[];
+  skip_auth_prefixes: string[];
+}
+
+type ISniffProtocol = "HTTP" | "TLS" | "QUIC";
+
+interface ISnifferOverride {
+  enable?: boolean;
+  "force-dns-mapping"?: boolean;
+  "parse-pure-ip"?: boolean;
+  "override-destination"?: boolean;
+  sniff?: Partial<
+    Record<
+      ISniffProtocol,
+      { ports: string[]; "override-destination"?: boolean }
+    >
+  >;
+  "force-domain"?: string[];
+  "skip-domain"?: string[];
+}
+
+interface ICachedArtifact {
+  url: string;
+  artifact: string;
+  sha256: string;
+  size: number;
+  last_used: number;
+}
+
+interface IArtifactCacheInfo {
+  entries: ICachedArtifact[];
+  size: number;
+  limit: number;
+}
+
+interface IMaintenanceReport {
+  time: number;
+  app_logs: number;
+  core_logs: number;
+  snapshots: number;
+  temp_dirs: number;
+  removed: number;
+}
+
+interface IStoreOverride {
+  "store-selected"?: boolean;
+  "store-fake-ip"?: boolean;
+}
+
+interface ICoreCacheInfo {
+  exists: boolean;
+  size: number;
+  modified: number | null;
+  store: { store_selected: boolean; store_fake_ip: boolean };
+  fake_ip: boolean;
+}
+
+type ICoreFeature =
+  | "sniffer"
+  | "tunnels"
+  | "listeners"
+  | "dialer_proxy"
+  | "skip_auth_prefixes"
+  | "rule_providers"
+  | "process_rules";
+
+interface ICoreCapabilities {
+  core: Required<IVergeConfig>["clash_core"];
+  features: ICoreFeature[];
+  unsupported_keys: string[];
+}
+
+interface ICapabilityWarning {
+  feature?: ICoreFeature;
+  key?: string;
+  message: string;
+}
+
+type IStartupProfile =
+  | { type: "last_used" }
+  | { type: "pinned"; uid: string }
+  | { type: "ask" };
+
+interface IPauseWindow {
+  id: string;
+  name: string;
+  enable: boolean;
+  start: string;
+  end: string;
+  weekdays?: number[];
+  system_proxy: boolean;
+  tun: boolean;
+}
+
+interface IPauseState {
+  window: string;
+  system_proxy?: boolean;
+  tun?: boolean;
+  mode?: string;
+  manual: boolean;
+  since: number;
+}
+
+interface IAppRule {
+  id: string;
+  process: string;
+  target: string;
+  enable: boolean;
+}
+
+interface IThrottleRule {
+  id: string;
+  // `*`, `example.com` or `+.example.com`
+  host: string;
+  scope: "connection" | "host";
+  // KB/s, 0 is unlimited
+  download: number;
+  upload: number;
+  enable: boolean;
+}
+
+interface IProcessInfo {
+  name: string;
+  path?: string | null;
+  icon?: string | null;
+}
+
+interface IUrlTestCell {
+  delay: number | null;
+  error: string | null;
+}
+
+interface IUrlTestMatrix {
+  urls: string[];
+  nodes: string[];
+  // results[node][url]
+  results: IUrlTestCell[][];
+  fastest: (string | null)[];
+}
+
+interface IBulkSelected {
+  group: string;
+  from: string | null;
+  to: string;
+}
+
+type IImportStrategy = "merge" | "replace" | "copy";
+
+type IImportOutcome =
+  | { status: "imported" | "merged" | "replaced"; uid: string }
+  | { status: "duplicate"; uid: string; name: string | null };
+
+interface IIpInfo {
+  ip: string;
+  country: string | null;
+  asn: number | null;
+  as_org: string | null;
+}
+
+interface INodeInfo {
+  name: string;
+  server: string;
+  entry: IIpInfo | null;
+  exit: IIpInfo | null;
+  error: string | null;
+  updated_at: number;
+}
+
+type IExportFormat = "csv" | "json";
+
+interface IConnectionFilter {
+  host?: string;
+  process?: string;
+  rule?: string;
+  chain?: string;
+}
+
+type IHookEvent =
+  | "core_crashed"
+  | "profile_updated"
+  | "quota_exceeded"
+  | "node_switched";
+
+type IHookTarget =
+  | { type: "webhook"; url: string }
+  | { type: "mqtt"; broker: string; topic: string; username?: string | null };
+
+type ITrayMenuAction =
+  | { type: "function"; func: string }
+  | { type: "command"; program: string; args: string[] };
+
+interface ITrayMenuEntry {
+  label: string;
+  action: ITrayMenuAction;
+  confirm: boolean;
+}
+
+interface IEventHook {
+  enable: boolean;
+  target: IHookTarget;
+  events: IHookEvent[];
+}
+
+type IFailoverAction =
+  | { type: "switch_profile"; uid: string }
+  | { type: "switch_core"; core: IVergeConfig["clash_core"] }
+  | { type: "disable_system_proxy" }
+  | { type: "notify" };
+
+interface ICoreFailover {
+  enable: boolean;
+  threshold: number;
+  /** seconds */
+  window: number;
+  actions: IFailoverAction[];
+}
+
+interface IFailoverStatus {
+  failures: number;
+  last_run: {
+    time: number;
+    failures: number;
+    results: { action: IFailoverAction; error: string | null }[];
+  } | null;
+}
+
+type IRemoteAccess = "read_only" | "read_write";
+
+interface ICountryStat {
+  // the iso code or "unknown"
+  country: string;
+  up: number;
+  down: number;
+  share: number;
+}
+
+interface IOnboardingEnv {
+  locale: string | null;
+  timezone: string | null;
+  region: string | null;
+  language: string;
+  in_china: boolean;
+}
+
+interface IMirrorProbe {
+  name: string;
+  // the prefix, empty for github directly
+  url: string;
+  latency: number | null;
+  error: string | null;
+  recommended: boolean;
+}
+
+type IKnownClient = "clash_verge" | "clash_verge_rev" | "clash_for_windows";
+
+interface IDetectedClient {
+  client: IKnownClient;
+  name: string;
+  dir: string;
+  profile: string | null;
+}
+
+interface IImportReport {
+  client: IKnownClient;
+  profiles: {
+    name: string;
+    kind: string;
+    enabled: boolean;
+  }[];
+  settings: string[];
+  skipped: { item: string; reason: string }[];
+  current?: string;
+}
+
+interface IFlattenTarget {
+  path?: string;
+  env_secrets?: boolean;
+  allow_lan?: boolean;
+}
+
+interface IFlattenResult {
+  path: string;
+  env_path: string | null;
+  proxies: number;
+  rule_providers: number;
+  warnings: string[];
+}
+
+interface IOnboardingState {
+  finished: boolean;
+  draft: {
+    language: string | null;
+    github_mirror: string | null;
+    clash_core: IVergeConfig["clash_core"] | null;
+    profile: string | null;
+  };
+}
+
+interface IWebUiInfo {
+  id: string;
+  name: string;
+  pinned: boolean;
+  installed: boolean;
+  size: number;
+  installed_at: number | null;
+}
+
+interface IExternalMutation {
+  time: number;
+  method: string;
+  path: string;
+  origin: string | null;
+  allowed: boolean;
+  status: number | null;
+}
+
+interface IRemoteManagementInfo {
+  enable: boolean;
+  access: IRemoteAccess;
+  has_password: boolean;
+  urls: string[];
+}
+
+interface IDiagnostic {
+  line?: number | null;
+  column?: number | null;
+  severity: "error" | "warning";
+  message: string;
+}
+
+interface IFormatResult {
+  content: string;
+  comments_dropped: boolean;
+}
+
+interface IApplyResult {
+  saved: boolean;
+  diagnostics: IDiagnostic[];
+}
+
+interface IChainTestResult {
+  output: string;
+  logs: [string, string][];
+  elapsed_ms: number;
+  error?: string | null;
+}
+
+interface IConfigChange {
+  path: string;
+  kind: "added" | "removed" | "modified";
+  before?: any;
+  after?: any;
+}
+
+interface IConfigPreview {
+  changes: IConfigChange[];
+  chain_logs: { [key: string]: [string, string][] };
+}
+
+interface IApiRequestRecord {
+  time?: number;
+  source: "app" | "webview" | "external";
+  method: string;
+  path: string;
+  duration_ms: number;
+  status: number | null;
+  error: string | null;
+}
+
+interface IApiRequestQuery {
+  path?: string;
+  min_duration_ms?: number;
+  only_failed?: boolean;
+  limit?: number;
+}
+
+interface IProfileUpdateRecord {
+  time: number;
+  size: number;
+  extra?: IProfileItem["extra"] | null;
+  hooks?: { hook: IProfileHook; error: string | null }[];
+}
+
+interface IProfileTrashEntry {
+  id: string;
+  operation: "delete" | "reorder" | "chain" | "import";
+  time: number;
+  uids: string[];
+  files: string[];
+}
+
+interface IPendingScript {
+  hash: string;
+  data: string;
+  current: string;
+  fetched?: number | null;
+}
+
+interface IRuleStats {
+  rules: Record<string, number>;
+  providers: {
+    name: string;
+    behavior: string;
+    count: number;
+    error: string | null;
+  }[];
+  geosite: Record<string, number | null>;
+  updated_at: number;
+  pending: boolean;
+}
+
+interface IRuleSetSource {
+  type: "provider" | "geosite";
+  name: string;
+}
+
+interface IRuleSetEntry {
+  kind:
+    | "full"
+    | "suffix"
+    | "keyword"
+    | "regex"
+    | "wildcard"
+    | "ip_cidr"
+    | "other";
+  value: string;
+}
+
+type IAutomationTrigger =
+  | { type: "ssid"; ssid: string }
+  | { type: "network"; network: string }
+  | { type: "time"; at: string; weekdays?: number[] };
+
+type IAutomationAction =
+  | { type: "switch_profile"; uid: string }
+  | { type: "set_system_proxy" | "set_tun_mode"; enable: boolean }
+  | { type: "set_mode"; mode: "rule" | "global" | "direct" };
+
+interface IAutomationRule {
+  name: string;
+  enable?: boolean;
+  trigger: IAutomationTrigger;
+  actions: IAutomationAction[];
+}
+
+interface ILanAccessInfo {
+  allow_lan: boolean;
+  ips: string[];
+  port: number;
+  auth_users: string[];
+  qr_payload?: string;
+}
+
+interface ITunStatus {
+  enabled: boolean;
+  privileged: boolean;
+  device?: string;
+  running: boolean;
+  conflicts: string[];
+  hint?: "service" | "helper" | "setcap";
+}
+
+type IBypassRule =
+  | { type: "domain" | "wildcard" | "ip" | "cidr"; value: string }
+  | { type: "local" };
+
+interface IClashMemorySample {
+  time: number;
+  inuse: number;
+  oslimit: number;
+}
+
+type IStreamState =
+  | { state: "connecting" }
+  | { state: "connected" }
+  | { state: "reconnecting"; attempt: number; delay: number }
+  | { state: "suspended" };
+
 interface IConnectionsItem {
   id: string;
   metadata: {
@@ -106,10 +919,53 @@ interface IConnections {
   connections: IConnectionsItem[];
 }
 
+// the frames of the binary connections stream
+type IConnectionsFrame =
+  | {
+      type: "full";
+      seq: number;
+      upload_total: number;
+      download_total: number;
+      connections: IConnectionsItem[];
+    }
+  | {
+      type: "delta";
+      seq: number;
+      upload_total: number;
+      download_total: number;
+      added: IConnectionsItem[];
+      updated: { id: string; upload: number; download: number }[];
+      removed: string[];
+    };
+
 /**
  * Some interface for command
  */
 
+// the state to render the ui at startup, by `get_app_snapshot`
+interface IAppSnapshot {
+  core: {
+    running: boolean;
+    core?: string;
+    mode?: string;
+  };
+  verge: IVergeConfig;
+  profiles: IProfilesConfig;
+  clash_info: IClashInfo;
+  // empty if the controller is unavailable
+  groups: {
+    name: string;
+    type: string;
+    now?: string;
+    all: string[];
+  }[];
+  system_proxy: {
+    enable: boolean;
+    active: boolean;
+    server?: string;
+  };
+}
+
 interface IClashInfo {
   // status: string;
   port?: number; // clash mixed port
@@ -143,6 +999,22 @@ interface IProfileOption {
   with_proxy?: boolean;
   self_proxy?: boolean;
   update_interval?: number;
+  dns_override?: IDnsOverride;
+  core?: IVergeConfig["clash_core"];
+  tls?: IProfileTls;
+  hooks?: IProfileHook[];
+}
+
+type IProfileHook =
+  | { type: "notify" }
+  | { type: "run_chain"; chain: string[] }
+  | { type: "restart_core" }
+  | { type: "connectivity_check" };
+
+interface IProfileTls {
+  ca?: string;
+  pins?: string[];
+  insecure?: boolean;
 }
 
 interface IProfilesConfig {
@@ -152,26 +1024,120 @@ interface IProfilesConfig {
   items?: IProfileItem[];
 }
 
+type IHttpProxy = "direct" | "system" | "core" | { custom: string };
+
+interface IHttpPolicy {
+  timeout?: number;
+  retries?: number;
+  proxy?: IHttpProxy;
+}
+
+interface IHttpPolicies {
+  updater?: IHttpPolicy;
+  profiles?: IHttpPolicy;
+  geodata?: IHttpPolicy;
+  sync?: IHttpPolicy;
+  webhooks?: IHttpPolicy;
+}
+
 interface IVergeConfig {
+  app_dns_over_https?: string;
+  http_policies?: IHttpPolicies;
   app_log_level?: "trace" | "debug" | "info" | "warn" | "error" | string;
+  app_log_filter?: string;
+  app_log_format?: "pretty" | "json";
+  app_log_max_size?: number;
+  enable_crash_upload?: boolean;
+  crash_upload_url?: string;
+  update_channel?: "stable" | "beta" | "nightly";
+  update_install_on_exit?: boolean;
+  update_skipped_versions?: string[];
+  github_mirror?: string;
+  dns_override?: IDnsOverride;
+  hosts_entries?: IHostsEntry[];
+  user_rules?: IUserRule[];
+  app_rules?: IAppRule[];
+  proxy_chains?: IProxyChain[];
+  tunnels?: ITunnel[];
+  listeners?: IInboundListener[];
+  inbound_auth?: IInboundAuth;
+  sniffer_override?: ISnifferOverride;
+  store_override?: IStoreOverride;
+  artifact_cache_limit?: number;
   language?: string;
   clash_core?: "mihomo" | "mihomo-alpha" | "clash-rs" | "clash";
+  profile_core_fallback?: IVergeConfig["clash_core"];
   theme_mode?: "light" | "dark" | "system";
   theme_blur?: boolean;
   traffic_graph?: boolean;
   enable_memory_usage?: boolean;
+  memory_alert_threshold?: number;
   page_transition_animation?: keyof typeof import("@/components/layout/page-transition").pageTransitionVariants;
   enable_tun_mode?: boolean;
+  enable_tun_dns_override?: boolean;
+  enable_self_bypass?: boolean;
   enable_auto_launch?: boolean;
+  enable_control_api?: boolean;
+  control_api_port?: number;
+  control_api_token?: string;
+  enable_controller_proxy?: boolean;
+  controller_proxy_port?: number;
+  controller_proxy_token?: string;
+  controller_proxy_access?: IRemoteAccess;
+  enable_metrics?: boolean;
+  metrics_port?: number;
+  enable_throttle?: boolean;
+  throttle_port?: number;
+  throttle_rules?: IThrottleRule[];
+  enable_exit_probe?: boolean;
+  exit_probe_port?: number;
+  enable_api_inspector?: boolean;
+  enable_remote_management?: boolean;
+  remote_management_port?: number;
+  remote_management_access?: IRemoteAccess;
   enable_service_mode?: boolean;
   enable_silent_start?: boolean;
+  enable_low_power_mode?: boolean;
+  enable_lazy_core?: boolean;
+  enable_tray_proxies?: boolean;
+  enable_tray_traffic?: boolean;
+  tray_menu?: ITrayMenuEntry[];
+  enable_system_notification?: boolean;
+  enable_clipboard_watch?: boolean;
+  clipboard_watch_interval?: number;
+  clipboard_watch_privacy?: boolean;
+  auto_launch_minimized?: boolean;
+  startup_profile?: IStartupProfile;
+  expire_reminder_days?: number;
+  expired_fallback_profile?: string;
+  auto_launch_delay?: number;
+  auto_launch_method?: "registry" | "startup_folder" | "scheduled_task";
   enable_system_proxy?: boolean;
   enable_random_port?: boolean;
   verge_mixed_port?: number;
   enable_proxy_guard?: boolean;
   proxy_guard_duration?: number;
+  network_rules?: INetworkRule[];
+  automation_rules?: IAutomationRule[];
+  pause_windows?: IPauseWindow[];
+  event_hook?: IEventHook | null;
+  core_failover?: ICoreFailover | null;
+  webdav_url?: string;
+  webdav_username?: string;
+  webdav_backup_interval?: number;
+  webdav_max_backups?: number;
+  enable_sync?: boolean;
+  sync_provider?: "webdav" | "s3";
+  sync_s3?: ISyncS3Config;
+  sync_interval?: number;
+  sync_conflict_strategy?: "last_writer_wins" | "manual";
+  /** @deprecated use `system_proxy_bypass_list` instead */
   system_proxy_bypass?: string;
+  system_proxy_bypass_list?: IBypassRule[];
+  enable_proxy_pac?: boolean;
   web_ui_list?: string[];
+  web_ui_port?: number;
+  default_web_ui?: string;
   hotkeys?: string[];
   theme_setting?: {
     primary_color?: string;
@@ -0,0 +1,24 @@
+// the exit is the real location of the node, the entry is the fallback
+export const nodeCountry = (info?: INodeInfo) =>
+  info?.exit?.country || info?.entry?.country || undefined;
+
+export const nodeAsn = (info?: INodeInfo) =>
+  info?.exit?.asn ?? info?.entry?.asn ?? undefined;
+
+// e.g. HK -> 🇭🇰
+export const countryFlag = (country: string) =>
+  String.fromCodePoint(
+    ...[...country.toUpperCase()].map((c) => 0x1f1a5 + c.charCodeAt(0)),
+  );
+
+const formatIp = (label: string, ip: IIpInfo | null) => {
+  if (!ip) return null;
+  const asn = ip.asn ? `AS${ip.asn} ${ip.as_org ?? ""}` : "";
+  return `${label}: ${ip.ip} ${ip.country ?? ""} ${asn}`.trim();
+};
+
+// the tooltip of the node
+export const nodeInfoTitle = (info?: INodeInfo) =>
+  [formatIp("Entry", info?.entry ?? null), formatIp("Exit", info?.exit ?? null)]
+    .filter(Boolean)
+    .join("\n");